@@ -24,7 +24,7 @@ use aptos_logger::{error, info, Schema};
 use aptos_mempool::{MempoolClientRequest, MempoolClientSender, SubmissionStatus};
 use aptos_storage_interface::{
     state_view::{DbStateView, DbStateViewAtVersion, LatestDbStateCheckpointView},
-    DbReader, Order, MAX_REQUEST_LIMIT,
+    AptosDbError, DbReader, Order, MAX_REQUEST_LIMIT,
 };
 use aptos_types::{
     access_path::{AccessPath, Path},
@@ -40,9 +40,10 @@ use aptos_types::{
         state_key::{StateKey, StateKeyInner},
         state_key_prefix::StateKeyPrefix,
         state_value::StateValue,
+        table::{TableHandle, TableInfo},
         TStateView,
     },
-    transaction::{SignedTransaction, TransactionWithProof, Version},
+    transaction::{PayloadLimits, SignedTransaction, TransactionWithProof, Version},
 };
 use aptos_utils::aptos_try;
 use aptos_vm::{data_cache::AsMoveResolver, move_vm_ext::AptosMoveResolver};
@@ -115,6 +116,7 @@ impl Context {
             gas_schedule_cache: Arc::new(RwLock::new(GasScheduleCache {
                 last_updated_epoch: None,
                 gas_schedule_params: None,
+                payload_limits: None,
             })),
             gas_estimation_cache: Arc::new(RwLock::new(GasEstimationCache {
                 last_updated_epoch: None,
@@ -631,6 +633,22 @@ impl Context {
         })
     }
 
+    /// Returns the key and value types of the table with the given handle, if the table info
+    /// is indexed, either by the table info service or by the internal indexer.
+    pub fn get_table_info(&self, handle: TableHandle) -> Result<Option<TableInfo>> {
+        if let Some(table_info_reader) = self.table_info_reader.as_ref() {
+            return Ok(table_info_reader.get_table_info(handle)?);
+        }
+        if !self.db.indexer_enabled() {
+            return Ok(None);
+        }
+        match self.db.get_table_info(handle) {
+            Ok(table_info) => Ok(Some(table_info)),
+            Err(AptosDbError::NotFound(_)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     pub fn render_transactions_sequential<E: InternalError>(
         &self,
         ledger_info: &LedgerInfo,
@@ -1199,18 +1217,21 @@ impl Context {
                 })?;
             let resolver = state_view.as_move_resolver();
 
-            let gas_schedule_params =
+            let (gas_feature_version, gas_schedule_params) =
                 match GasScheduleV2::fetch_config(&resolver).and_then(|gas_schedule| {
                     let feature_version = gas_schedule.feature_version;
                     let gas_schedule = gas_schedule.to_btree_map();
                     AptosGasParameters::from_on_chain_gas_schedule(&gas_schedule, feature_version)
                         .ok()
+                        .map(|gas_schedule| (feature_version, gas_schedule))
                 }) {
                     Some(gas_schedule) => Ok(gas_schedule),
                     None => GasSchedule::fetch_config(&resolver)
                         .and_then(|gas_schedule| {
                             let gas_schedule = gas_schedule.to_btree_map();
-                            AptosGasParameters::from_on_chain_gas_schedule(&gas_schedule, 0).ok()
+                            AptosGasParameters::from_on_chain_gas_schedule(&gas_schedule, 0)
+                                .ok()
+                                .map(|gas_schedule| (0, gas_schedule))
                         })
                         .ok_or_else(|| {
                             E::internal_with_code(
@@ -1222,6 +1243,10 @@ impl Context {
                 }?;
 
             // Update the cache
+            cache.payload_limits = gas_schedule_params
+                .vm
+                .txn
+                .payload_limits(gas_feature_version);
             cache.gas_schedule_params = Some(gas_schedule_params.clone());
             cache.last_updated_epoch = Some(ledger_info.epoch.0);
             Ok((
@@ -1231,6 +1256,15 @@ impl Context {
         }
     }
 
+    /// Returns the payload limits of the gas schedule at `ledger_info`, if the schedule has them.
+    pub fn get_payload_limits<E: InternalError>(
+        &self,
+        ledger_info: &LedgerInfo,
+    ) -> Result<Option<PayloadLimits>, E> {
+        self.get_gas_schedule::<E>(ledger_info)?;
+        Ok(self.gas_schedule_cache.read().unwrap().payload_limits)
+    }
+
     pub fn block_executor_onchain_config<E: InternalError>(
         &self,
         ledger_info: &LedgerInfo,
@@ -1319,6 +1353,7 @@ impl Context {
 pub struct GasScheduleCache {
     last_updated_epoch: Option<u64>,
     gas_schedule_params: Option<AptosGasParameters>,
+    payload_limits: Option<PayloadLimits>,
 }
 
 pub struct GasEstimationCache {
@@ -15,6 +15,7 @@ mod error_converter;
 mod events;
 mod failpoint;
 mod index;
+mod load_shedding;
 mod log;
 pub mod metrics;
 mod page;
@@ -25,6 +26,7 @@ mod state;
 #[cfg(test)]
 pub mod tests;
 mod transactions;
+mod versioning;
 mod view_function;
 
 /// API categories for the OpenAPI spec
@@ -59,3 +61,4 @@ pub enum ApiTags {
 pub use context::Context;
 pub use response::BasicError;
 pub use runtime::{attach_poem_to_runtime, bootstrap, get_api_service};
+pub use versioning::ApiVersion;
@@ -0,0 +1,295 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    context::Context,
+    metrics::{LOAD_CHECK_LATENCY, OVERLOADED, SHED_REQUESTS},
+    response::BasicError,
+};
+use aptos_api_types::{AptosError, AptosErrorCode};
+use aptos_config::config::{ApiConfig, LoadSheddingConfig};
+use aptos_logger::{info, warn};
+use poem::{
+    http::{header::RETRY_AFTER, HeaderValue, Method, StatusCode, Uri},
+    Endpoint, IntoResponse, Middleware, Request, Response, Result,
+};
+use poem_openapi::payload::Json;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::time::MissedTickBehavior;
+
+/// The number of consecutive checks within the thresholds after which an overloaded node is
+/// considered recovered, so that the shedding does not flap with every check.
+const NUM_CHECKS_TO_RECOVER: usize = 3;
+
+/// Tracks whether the node is overloaded, based on the periodic checks of its load (see
+/// [`LoadSheddingConfig`]).
+pub struct LoadMonitor {
+    config: LoadSheddingConfig,
+    overloaded: AtomicBool,
+    num_checks_within_thresholds: AtomicUsize,
+}
+
+impl LoadMonitor {
+    pub fn new(config: LoadSheddingConfig) -> Self {
+        Self {
+            config,
+            overloaded: AtomicBool::new(false),
+            num_checks_within_thresholds: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn is_overloaded(&self) -> bool {
+        self.overloaded.load(Ordering::Relaxed)
+    }
+
+    /// Records a check of the load: the latency of reading the latest ledger info, and the
+    /// lag of its timestamp (None if the latest ledger info could not be read).
+    fn record_check(&self, storage_latency: Duration, ledger_lag: Option<Duration>) {
+        let within_thresholds = storage_latency
+            <= Duration::from_millis(self.config.storage_latency_threshold_ms)
+            && ledger_lag.map_or(false, |ledger_lag| {
+                ledger_lag <= Duration::from_millis(self.config.ledger_lag_threshold_ms)
+            });
+
+        if !within_thresholds {
+            self.num_checks_within_thresholds
+                .store(0, Ordering::Relaxed);
+            if !self.overloaded.swap(true, Ordering::Relaxed) {
+                warn!(
+                    "The node is overloaded (storage latency: {:?}, ledger lag: {:?}), shedding the expensive API requests",
+                    storage_latency, ledger_lag
+                );
+            }
+        } else if self.is_overloaded()
+            && self
+                .num_checks_within_thresholds
+                .fetch_add(1, Ordering::Relaxed)
+                + 1
+                >= NUM_CHECKS_TO_RECOVER
+        {
+            self.overloaded.store(false, Ordering::Relaxed);
+            info!("The node is no longer overloaded, serving all the API requests");
+        }
+        OVERLOADED.set(self.is_overloaded() as i64);
+    }
+}
+
+/// Periodically checks the load of the node, until the runtime is shut down.
+pub async fn monitor_load(context: Arc<Context>, monitor: Arc<LoadMonitor>) {
+    let mut interval =
+        tokio::time::interval(Duration::from_millis(monitor.config.check_interval_ms));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let context = context.clone();
+        let start = Instant::now();
+        // The latency includes the wait for a blocking thread, as the API requests do.
+        let ledger_info =
+            tokio::task::spawn_blocking(move || context.get_latest_ledger_info::<BasicError>())
+                .await;
+        let storage_latency = start.elapsed();
+        LOAD_CHECK_LATENCY.observe(storage_latency.as_secs_f64());
+
+        let ledger_lag = match ledger_info {
+            Ok(Ok(ledger_info)) => Some(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .saturating_sub(Duration::from_micros(ledger_info.timestamp())),
+            ),
+            _ => None,
+        };
+        monitor.record_check(storage_latency, ledger_lag);
+    }
+}
+
+/// This middleware rejects the expensive requests while the node is overloaded (see
+/// [`LoadMonitor`]) with a 503 and a Retry-After header, so that the cheap requests are
+/// still served promptly. It must be applied after the versioning, which reroutes the
+/// requests to the v1 paths.
+#[derive(Clone)]
+pub struct LoadShedding {
+    monitor: Arc<LoadMonitor>,
+    max_page_size_under_load: u64,
+    retry_after_secs: u64,
+    // The page sizes of the account resources and modules requests without a limit
+    account_resources_page_size: u64,
+    account_modules_page_size: u64,
+}
+
+impl LoadShedding {
+    pub fn new(api_config: &ApiConfig, monitor: Arc<LoadMonitor>) -> Self {
+        Self {
+            monitor,
+            max_page_size_under_load: api_config.load_shedding.max_page_size_under_load as u64,
+            retry_after_secs: api_config.load_shedding.retry_after_secs,
+            account_resources_page_size: api_config.max_account_resources_page_size as u64,
+            account_modules_page_size: api_config.max_account_modules_page_size as u64,
+        }
+    }
+
+    /// Returns the kind of the request (for the metrics) if it is expensive.
+    fn expensive_request_kind(&self, method: &Method, uri: &Uri) -> Option<&'static str> {
+        let path = uri.path().trim_end_matches('/');
+        if method == Method::POST {
+            if path.ends_with("/transactions/simulate") {
+                return Some("simulation");
+            }
+            if path.ends_with("/view") {
+                return Some("view_function");
+            }
+            return None;
+        }
+
+        let limit = uri
+            .query()
+            .and_then(|query| {
+                query
+                    .split('&')
+                    .filter_map(|pair| pair.split_once('='))
+                    .find(|(key, _)| *key == "limit")
+            })
+            .map(|(_, limit)| limit.parse::<u64>().unwrap_or(u64::MAX));
+        let page_size = match limit {
+            Some(limit) => limit,
+            None if path.starts_with("/v1/accounts/") && path.ends_with("/resources") => {
+                self.account_resources_page_size
+            },
+            None if path.starts_with("/v1/accounts/") && path.ends_with("/modules") => {
+                self.account_modules_page_size
+            },
+            None => return None,
+        };
+        (page_size > self.max_page_size_under_load).then_some("large_page")
+    }
+
+    fn overloaded_response(&self) -> Response {
+        let mut response = Json(AptosError::new_with_error_code(
+            "The node is overloaded, please retry the request later",
+            AptosErrorCode::ServiceOverloaded,
+        ))
+        .into_response();
+        response.set_status(StatusCode::SERVICE_UNAVAILABLE);
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(self.retry_after_secs));
+        response
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for LoadShedding {
+    type Output = LoadSheddingEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        LoadSheddingEndpoint {
+            inner: ep,
+            load_shedding: self.clone(),
+        }
+    }
+}
+
+/// Endpoint for LoadShedding middleware.
+pub struct LoadSheddingEndpoint<E> {
+    inner: E,
+    load_shedding: LoadShedding,
+}
+
+#[async_trait::async_trait]
+impl<E: Endpoint> Endpoint for LoadSheddingEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        if self.load_shedding.monitor.is_overloaded() {
+            if let Some(kind) = self
+                .load_shedding
+                .expensive_request_kind(req.method(), req.uri())
+            {
+                SHED_REQUESTS.with_label_values(&[kind]).inc();
+                return Ok(self.load_shedding.overloaded_response());
+            }
+        }
+        Ok(self.inner.call(req).await?.into_response())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expensive_request_kind() {
+        let load_shedding = LoadShedding::new(
+            &ApiConfig::default(),
+            Arc::new(LoadMonitor::new(LoadSheddingConfig::default())),
+        );
+        let kind = |method: Method, uri: &str| {
+            load_shedding.expensive_request_kind(&method, &uri.parse().unwrap())
+        };
+
+        assert_eq!(
+            kind(Method::POST, "/v1/transactions/simulate"),
+            Some("simulation")
+        );
+        assert_eq!(kind(Method::POST, "/v1/view"), Some("view_function"));
+        assert_eq!(
+            kind(Method::GET, "/v1/transactions?limit=1000"),
+            Some("large_page")
+        );
+        assert_eq!(
+            kind(Method::GET, "/v1/accounts/0x1/resources"),
+            Some("large_page")
+        );
+        assert_eq!(
+            kind(Method::GET, "/v1/accounts/0x1/events/2?start=0&limit=foo"),
+            Some("large_page")
+        );
+
+        // The cheap requests are still served
+        assert_eq!(kind(Method::GET, "/v1/-/healthy"), None);
+        assert_eq!(kind(Method::GET, "/v1"), None);
+        assert_eq!(kind(Method::POST, "/v1/transactions"), None);
+        assert_eq!(kind(Method::POST, "/v1/transactions/batch"), None);
+        assert_eq!(kind(Method::GET, "/v1/transactions?limit=100"), None);
+        assert_eq!(kind(Method::GET, "/v1/transactions"), None);
+        assert_eq!(
+            kind(Method::GET, "/v1/accounts/0x1/resources?limit=10"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_load_monitor() {
+        let monitor = LoadMonitor::new(LoadSheddingConfig {
+            storage_latency_threshold_ms: 100,
+            ledger_lag_threshold_ms: 1_000,
+            ..Default::default()
+        });
+        let check_within_thresholds =
+            || monitor.record_check(Duration::from_millis(10), Some(Duration::from_millis(10)));
+
+        check_within_thresholds();
+        assert!(!monitor.is_overloaded());
+        monitor.record_check(Duration::from_millis(200), Some(Duration::from_millis(10)));
+        assert!(monitor.is_overloaded());
+
+        // The node recovers after consecutive checks within the thresholds
+        check_within_thresholds();
+        monitor.record_check(Duration::from_millis(10), Some(Duration::from_secs(2)));
+        for _ in 0..NUM_CHECKS_TO_RECOVER - 1 {
+            check_within_thresholds();
+            assert!(monitor.is_overloaded());
+        }
+        check_within_thresholds();
+        assert!(!monitor.is_overloaded());
+
+        // The node is overloaded if the latest ledger info cannot be read
+        monitor.record_check(Duration::from_millis(10), None);
+        assert!(monitor.is_overloaded());
+    }
+}
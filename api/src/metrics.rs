@@ -4,8 +4,8 @@
 
 use aptos_global_constants::DEFAULT_BUCKETS;
 use aptos_metrics_core::{
-    exponential_buckets, register_histogram_vec, register_int_counter_vec, HistogramVec,
-    IntCounterVec,
+    exponential_buckets, register_histogram, register_histogram_vec, register_int_counter_vec,
+    register_int_gauge, Histogram, HistogramVec, IntCounterVec, IntGauge,
 };
 use once_cell::sync::Lazy;
 
@@ -68,6 +68,41 @@ pub static REQUEST_SOURCE_CLIENT: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static REQUESTS_BY_API_VERSION: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_api_requests_by_version",
+        "API requests grouped by API version, operation_id, and status",
+        &["version", "operation_id", "status"]
+    )
+    .unwrap()
+});
+
+pub static OVERLOADED: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_api_overloaded",
+        "Whether the node is considered overloaded, and the expensive API requests are shed"
+    )
+    .unwrap()
+});
+
+pub static SHED_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_api_shed_requests",
+        "API requests rejected while the node is overloaded, grouped by the kind of request",
+        &["kind"]
+    )
+    .unwrap()
+});
+
+pub static LOAD_CHECK_LATENCY: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "aptos_api_load_check_latency",
+        "Latency of reading the latest ledger info when checking the load of the node",
+        SUB_MS_BUCKETS.to_vec()
+    )
+    .unwrap()
+});
+
 pub static GAS_ESTIMATE: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         "aptos_api_gas_estimate",
@@ -697,6 +697,18 @@ pub fn table_item_not_found<E: NotFoundError>(
     )
 }
 
+pub fn table_info_not_found<E: NotFoundError>(
+    table_handle: Address,
+    ledger_info: &LedgerInfo,
+) -> E {
+    build_not_found(
+        "Table Info",
+        format!("Table handle({})", table_handle),
+        AptosErrorCode::TableItemNotFound,
+        ledger_info,
+    )
+}
+
 pub fn block_not_found_by_height<E: NotFoundError>(
     block_height: u64,
     ledger_info: &LedgerInfo,
@@ -3,9 +3,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    accounts::AccountsApi, basic::BasicApi, blocks::BlocksApi, check_size::PostSizeLimit,
-    context::Context, error_converter::convert_error, events::EventsApi, index::IndexApi,
-    log::middleware_log, set_failpoints, state::StateApi, transactions::TransactionsApi,
+    accounts::AccountsApi,
+    basic::BasicApi,
+    blocks::BlocksApi,
+    check_size::PostSizeLimit,
+    context::Context,
+    error_converter::convert_error,
+    events::EventsApi,
+    index::IndexApi,
+    load_shedding::{monitor_load, LoadMonitor, LoadShedding},
+    log::middleware_log,
+    set_failpoints,
+    state::StateApi,
+    transactions::TransactionsApi,
+    versioning::ApiVersioning,
     view_function::ViewFunctionApi,
 };
 use anyhow::Context as AnyhowContext;
@@ -162,6 +173,12 @@ pub fn attach_poem_to_runtime(
     let context = Arc::new(context);
 
     let size_limit = context.content_length_limit();
+    let api_versioning = ApiVersioning::new(&config.api);
+    let load_monitor = Arc::new(LoadMonitor::new(config.api.load_shedding.clone()));
+    if config.api.load_shedding.enabled {
+        runtime_handle.spawn(monitor_load(context.clone(), load_monitor.clone()));
+    }
+    let load_shedding = LoadShedding::new(&config.api, load_monitor);
 
     let api_service = get_api_service(context.clone());
 
@@ -233,8 +250,13 @@ pub fn attach_poem_to_runtime(
             )
             .with(cors)
             .with(PostSizeLimit::new(size_limit))
-            // NOTE: Make sure to keep this after all the `with` middleware.
+            // NOTE: Make sure to keep this after all the `with` middleware that can fail.
             .catch_all_error(convert_error)
+            // The shedding sees the requests rerouted by the versioning.
+            .with(load_shedding)
+            // The versioning is applied to the converted errors too. As it reroutes
+            // the requests, it must be applied before the routes.
+            .with(api_versioning)
             .around(middleware_log);
         Server::new_with_acceptor(acceptor)
             .run(route)
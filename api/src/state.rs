@@ -6,9 +6,9 @@ use crate::{
     context::api_spawn_blocking,
     failpoint::fail_point_poem,
     response::{
-        api_forbidden, build_not_found, module_not_found, resource_not_found, table_item_not_found,
-        BadRequestError, BasicErrorWith404, BasicResponse, BasicResponseStatus, BasicResultWith404,
-        InternalError,
+        api_forbidden, build_not_found, module_not_found, resource_not_found, table_info_not_found,
+        table_item_not_found, BadRequestError, BasicErrorWith404, BasicResponse,
+        BasicResponseStatus, BasicResultWith404, InternalError,
     },
     ApiTags, Context,
 };
@@ -16,7 +16,7 @@ use anyhow::Context as AnyhowContext;
 use aptos_api_types::{
     verify_module_identifier, Address, AptosErrorCode, AsConverter, IdentifierWrapper,
     MoveModuleBytecode, MoveResource, MoveStructTag, MoveValue, RawStateValueRequest,
-    RawTableItemRequest, TableItemRequest, VerifyInput, VerifyInputWithRecursion, U64,
+    RawTableItemRequest, TableInfo, TableItemRequest, VerifyInput, VerifyInputWithRecursion, U64,
 };
 use aptos_types::{
     access_path::AccessPath,
@@ -233,6 +233,32 @@ impl StateApi {
         .await
     }
 
+    /// Get table info
+    ///
+    /// Get the key and value types of the table identified by {table_handle} in the path.
+    ///
+    /// This requires the node to index table info, either with the table info service or with
+    /// the internal indexer. If the table info is not indexed (yet), the server responds with a 404.
+    #[oai(
+        path = "/tables/:table_handle/info",
+        method = "get",
+        operation_id = "get_table_info",
+        tag = "ApiTags::Tables"
+    )]
+    async fn get_table_info(
+        &self,
+        accept_type: AcceptType,
+        /// Table handle hex encoded 32-byte string
+        table_handle: Path<Address>,
+    ) -> BasicResultWith404<TableInfo> {
+        fail_point_poem("endpoint_get_table_info")?;
+        self.context
+            .check_api_output_enabled("Get table info", &accept_type)?;
+
+        let api = self.clone();
+        api_spawn_blocking(move || api.table_info(&accept_type, table_handle.0)).await
+    }
+
     /// Get raw state value.
     ///
     /// Get a state value at a specific ledger version, identified by the key provided
@@ -394,6 +420,44 @@ impl StateApi {
     }
 
     /// Retrieve table item for a specific ledger version
+    /// Retrieve the key and value types of a table
+    ///
+    /// JSON: Convert to TableInfo
+    /// BCS: Return the BCS encoded table info
+    pub fn table_info(
+        &self,
+        accept_type: &AcceptType,
+        table_handle: Address,
+    ) -> BasicResultWith404<TableInfo> {
+        let ledger_info = self.context.get_latest_ledger_info()?;
+        let table_info = self
+            .context
+            .get_table_info(TableHandle(table_handle.into()))
+            .context(format!(
+                "Failed to retrieve table info for table handle {}",
+                table_handle
+            ))
+            .map_err(|err| {
+                BasicErrorWith404::internal_with_code(
+                    err,
+                    AptosErrorCode::InternalError,
+                    &ledger_info,
+                )
+            })?
+            .ok_or_else(|| table_info_not_found(table_handle, &ledger_info))?;
+
+        match accept_type {
+            AcceptType::Json => BasicResponse::try_from_json((
+                TableInfo::from(table_info),
+                &ledger_info,
+                BasicResponseStatus::Ok,
+            )),
+            AcceptType::Bcs => {
+                BasicResponse::try_from_bcs((table_info, &ledger_info, BasicResponseStatus::Ok))
+            },
+        }
+    }
+
     pub fn table_item(
         &self,
         accept_type: &AcceptType,
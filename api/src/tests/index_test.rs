@@ -4,6 +4,7 @@
 
 use super::new_test_context;
 use aptos_api_test_context::current_function_name;
+use aptos_api_types::X_APTOS_API_VERSION;
 use serde_json::json;
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -43,6 +44,18 @@ async fn test_health_check() {
     assert_eq!(resp.status(), 200)
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_api_version_header() {
+    let context = new_test_context(current_function_name!());
+    let resp = context
+        .reply(warp::test::request().method("GET").path("/v1/-/healthy"))
+        .await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()[X_APTOS_API_VERSION], "v1");
+    // The v1 API is not deprecated by default
+    assert!(!resp.headers().contains_key("deprecation"));
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_openapi_spec() {
     let context = new_test_context(current_function_name!());
@@ -7,7 +7,7 @@ use aptos_crypto::ed25519::Ed25519Signature;
 use aptos_types::transaction::{
     authenticator::TransactionAuthenticator, EntryFunction, TransactionPayload,
 };
-use move_core_types::{ident_str, language_storage::ModuleId};
+use move_core_types::{account_address::AccountAddress, ident_str, language_storage::ModuleId};
 use serde_json::json;
 use std::path::PathBuf;
 
@@ -80,6 +80,18 @@ async fn test_simulate_transaction_with_not_valid_signature() {
     assert!(resp[0]["success"].as_bool().is_some_and(|v| v));
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_transaction_read_stats() {
+    let mut context = new_test_context(current_function_name!());
+    let resp = simulate_aptos_transfer(&mut context, false, SMALL_TRANSFER_AMOUNT, 200).await;
+    let read_stats = &resp[0]["read_stats"];
+    let stat = |name: &str| read_stats[name].as_str().unwrap().parse::<u64>().unwrap();
+    assert!(stat("num_distinct_reads") > 0);
+    assert!(stat("bytes_read") > 0);
+    assert!(stat("num_storage_reads") >= stat("num_distinct_reads"));
+    assert_eq!(stat("num_storage_cache_hits"), 0);
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_simulate_transaction_with_insufficient_balance() {
     let mut context = new_test_context(current_function_name!());
@@ -140,3 +152,176 @@ async fn test_simulate_txn_with_aggregator() {
         unreachable!("Simulation uses Ed25519 authenticator.");
     }
 }
+
+/// Simulates a transfer with the given query, overriding the given fields of the request.
+async fn simulate_aptos_transfer_with_overrides(
+    context: &mut TestContext,
+    query: &str,
+    overrides: serde_json::Value,
+) -> serde_json::Value {
+    let alice = &mut context.gen_account();
+    let bob = &mut context.gen_account();
+    let txn = context.mint_user_account(alice).await;
+    context.commit_block(&vec![txn]).await;
+
+    let txn = context.account_transfer_to(alice, bob.address(), SMALL_TRANSFER_AMOUNT);
+    if let TransactionAuthenticator::Ed25519 {
+        public_key,
+        signature: _,
+    } = txn.authenticator_ref()
+    {
+        let mut request = json!({
+            "sender": txn.sender().to_string(),
+            "sequence_number": txn.sequence_number().to_string(),
+            "max_gas_amount": txn.max_gas_amount().to_string(),
+            "gas_unit_price": txn.gas_unit_price().to_string(),
+            "expiration_timestamp_secs": txn.expiration_timestamp_secs().to_string(),
+            "payload": {
+                "type": "entry_function_payload",
+                "function": "0x1::aptos_account::transfer",
+                "type_arguments": [],
+                "arguments": [
+                    bob.address().to_standard_string(), SMALL_TRANSFER_AMOUNT.to_string(),
+                ]
+            },
+            "signature": {
+                "type": "ed25519_signature",
+                "public_key": public_key.to_string(),
+                "signature": Ed25519Signature::dummy_signature().to_string(),
+            }
+        });
+        for (field, value) in overrides.as_object().unwrap() {
+            request[field] = value.clone();
+        }
+        context
+            .expect_status_code(200)
+            .post(&format!("/transactions/simulate{}", query), request)
+            .await
+    } else {
+        unreachable!("Simulation uses Ed25519 authenticator.");
+    }
+}
+
+fn assert_simulation_failed(resp: &serde_json::Value, vm_status: &str) {
+    assert!(!resp[0]["success"].as_bool().is_some_and(|v| v));
+    assert!(resp[0]["vm_status"]
+        .as_str()
+        .is_some_and(|s| s.contains(vm_status)));
+}
+
+fn assert_simulation_bypassed(resp: &serde_json::Value, checks: &str) {
+    assert!(resp[0]["success"].as_bool().is_some_and(|v| v));
+    assert!(resp[0]["vm_status"]
+        .as_str()
+        .is_some_and(|s| s.contains(&format!("Simulation bypassed checks: {}", checks))));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_expired_transaction() {
+    let mut context = new_test_context(current_function_name!());
+    let overrides = json!({ "expiration_timestamp_secs": "1" });
+    let resp = simulate_aptos_transfer_with_overrides(&mut context, "", overrides).await;
+    assert_simulation_failed(&resp, "TRANSACTION_EXPIRED");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_expired_transaction_ignoring_expiration() {
+    let mut context = new_test_context(current_function_name!());
+    let overrides = json!({ "expiration_timestamp_secs": "1" });
+    let resp =
+        simulate_aptos_transfer_with_overrides(&mut context, "?ignore_expiration=true", overrides)
+            .await;
+    assert_simulation_bypassed(&resp, "expiration");
+}
+
+// The maximum fee at this price exceeds the balance of the minted account.
+const UNAFFORDABLE_GAS_UNIT_PRICE: u64 = 1_000;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_unaffordable_transaction() {
+    let mut context = new_test_context(current_function_name!());
+    let overrides = json!({ "gas_unit_price": UNAFFORDABLE_GAS_UNIT_PRICE.to_string() });
+    let resp = simulate_aptos_transfer_with_overrides(&mut context, "", overrides).await;
+    assert_simulation_failed(&resp, "INSUFFICIENT_BALANCE_FOR_TRANSACTION_FEE");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_unaffordable_transaction_ignoring_balance() {
+    let mut context = new_test_context(current_function_name!());
+    let overrides = json!({ "gas_unit_price": UNAFFORDABLE_GAS_UNIT_PRICE.to_string() });
+    let resp =
+        simulate_aptos_transfer_with_overrides(&mut context, "?ignore_balance=true", overrides)
+            .await;
+    assert_simulation_bypassed(&resp, "balance");
+    // The fee is reported at the gas unit price of the transaction.
+    assert_eq!(
+        resp[0]["gas_unit_price"].as_str(),
+        Some(UNAFFORDABLE_GAS_UNIT_PRICE.to_string().as_str())
+    );
+    assert!(resp[0]["gas_used"]
+        .as_str()
+        .is_some_and(|gas_used| gas_used.parse::<u64>().unwrap() > 0));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_unaffordable_transaction_ignoring_balance_mints_nothing() {
+    let mut context = new_test_context(current_function_name!());
+    let overrides = json!({ "gas_unit_price": UNAFFORDABLE_GAS_UNIT_PRICE.to_string() });
+    let resp =
+        simulate_aptos_transfer_with_overrides(&mut context, "?ignore_balance=true", overrides)
+            .await;
+    assert_simulation_bypassed(&resp, "balance");
+
+    // The unaffordable fee is neither deducted from the balance of the sender, nor covered by
+    // coins created within the simulation: only the transferred amount leaves the account.
+    let sender = resp[0]["sender"].as_str().unwrap();
+    let balance = context
+        .get_apt_balance(AccountAddress::from_hex_literal(sender).unwrap())
+        .await;
+    let coin_store = resp[0]["changes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|change| {
+            change["address"].as_str() == Some(sender)
+                && change["data"]["type"].as_str()
+                    == Some("0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>")
+        })
+        .expect("the transfer must write the coin store of the sender");
+    assert_eq!(
+        coin_store["data"]["data"]["coin"]["value"].as_str(),
+        Some((balance - SMALL_TRANSFER_AMOUNT).to_string().as_str())
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_transaction_with_other_public_key() {
+    let mut context = new_test_context(current_function_name!());
+    let eve = context.gen_account();
+    let overrides = json!({
+        "signature": {
+            "type": "ed25519_signature",
+            "public_key": eve.public_key().to_string(),
+            "signature": Ed25519Signature::dummy_signature().to_string(),
+        }
+    });
+    let resp = simulate_aptos_transfer_with_overrides(&mut context, "", overrides).await;
+    assert_simulation_failed(&resp, "INVALID_AUTH_KEY");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_simulate_transaction_with_other_public_key_ignoring_auth_key() {
+    let mut context = new_test_context(current_function_name!());
+    let eve = context.gen_account();
+    let overrides = json!({
+        "signature": {
+            "type": "ed25519_signature",
+            "public_key": eve.public_key().to_string(),
+            "signature": Ed25519Signature::dummy_signature().to_string(),
+        }
+    });
+    let resp =
+        simulate_aptos_transfer_with_overrides(&mut context, "?ignore_auth_key=true", overrides)
+            .await;
+    assert_simulation_bypassed(&resp, "auth_key");
+}
@@ -302,6 +302,16 @@ async fn test_get_table_item() {
     assert_table_item(ctx, &nested_table, "u8", "u8", 2, 3).await;
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_table_info_not_indexed() {
+    let mut context = new_test_context(current_function_name!());
+    let resp = context
+        .expect_status_code(404)
+        .get(&format!("/tables/{}/info", AccountAddress::ONE))
+        .await;
+    assert_eq!(resp["error_code"], "table_item_not_found");
+}
+
 fn get_account_resource(address: &str, struct_tag: &str) -> String {
     format!("/accounts/{}/resource/{}", address, struct_tag)
 }
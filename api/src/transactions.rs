@@ -32,12 +32,12 @@ use aptos_types::{
     account_config::CoinStoreResource,
     mempool_status::MempoolStatusCode,
     transaction::{
-        EntryFunction, ExecutionStatus, MultisigTransactionPayload, RawTransaction,
+        EntryFunction, ExecutionStatus, MultisigTransactionPayload, PayloadLimits, RawTransaction,
         RawTransactionWithData, SignedTransaction, TransactionPayload, TransactionStatus,
     },
     vm_status::StatusCode,
 };
-use aptos_vm::{data_cache::AsMoveResolver, AptosSimulationVM};
+use aptos_vm::{data_cache::AsMoveResolver, AptosSimulationVM, SimulationOptions};
 use move_core_types::vm_status::VMStatus;
 use poem_openapi::{
     param::{Path, Query},
@@ -420,6 +420,14 @@ impl TransactionsApi {
         /// If set to true, the transaction will use a higher price than the original
         /// estimate.
         estimate_prioritized_gas_unit_price: Query<Option<bool>>,
+        /// If set to true, the transaction will be simulated even if it has expired
+        ignore_expiration: Query<Option<bool>>,
+        /// If set to true, the transaction will be simulated even if the gas payer
+        /// cannot afford the transaction fee
+        ignore_balance: Query<Option<bool>>,
+        /// If set to true, the transaction will be simulated even if the authentication
+        /// keys of the signers do not match the ones on chain
+        ignore_auth_key: Query<Option<bool>>,
         data: SubmitTransactionPost,
     ) -> SimulateTransactionResult<Vec<UserTransaction>> {
         data.verify()
@@ -525,7 +533,18 @@ impl TransactionsApi {
                 );
             }
 
-            api.simulate(&accept_type, ledger_info, signed_transaction)
+            let simulation_options = SimulationOptions {
+                ignore_expiration: ignore_expiration.0.unwrap_or_default(),
+                ignore_balance: ignore_balance.0.unwrap_or_default(),
+                ignore_auth_key: ignore_auth_key.0.unwrap_or_default(),
+            };
+
+            api.simulate(
+                &accept_type,
+                ledger_info,
+                signed_transaction,
+                simulation_options,
+            )
         })
         .await
     }
@@ -1057,7 +1076,21 @@ impl TransactionsApi {
     }
 
     /// Submits a single transaction, and converts mempool codes to errors
-    async fn create_internal(&self, txn: SignedTransaction) -> Result<(), AptosError> {
+    async fn create_internal(
+        &self,
+        txn: SignedTransaction,
+        payload_limits: Option<PayloadLimits>,
+    ) -> Result<(), AptosError> {
+        // Reject payloads over the limits before they reach mempool, with the same status code
+        // the VM would discard them with.
+        if let Some(violation) = payload_limits.and_then(|limits| limits.check(txn.payload()).err())
+        {
+            return Err(AptosError::new_with_vm_status(
+                format!("Invalid transaction: {}", violation),
+                AptosErrorCode::VmError,
+                violation.status_code(),
+            ));
+        }
         let (mempool_status, vm_status_opt) = self
             .context
             .submit_transaction(txn)
@@ -1101,6 +1134,10 @@ impl TransactionsApi {
                 mempool_status.message,
                 AptosErrorCode::InvalidTransactionUpdate,
             )),
+            MempoolStatusCode::RejectedByFilter => Err(AptosError::new_with_error_code(
+                mempool_status.message,
+                AptosErrorCode::InvalidInput,
+            )),
             MempoolStatusCode::UnknownStatus => Err(AptosError::new_with_error_code(
                 format!("Transaction was rejected with status {}", mempool_status,),
                 AptosErrorCode::InternalError,
@@ -1115,7 +1152,10 @@ impl TransactionsApi {
         ledger_info: &LedgerInfo,
         txn: SignedTransaction,
     ) -> SubmitTransactionResult<PendingTransaction> {
-        match self.create_internal(txn.clone()).await {
+        let payload_limits = self
+            .context
+            .get_payload_limits::<SubmitTransactionError>(ledger_info)?;
+        match self.create_internal(txn.clone(), payload_limits).await {
             Ok(()) => match accept_type {
                 AcceptType::Json => {
                     let state_view = self
@@ -1186,10 +1226,13 @@ impl TransactionsApi {
         ledger_info: &LedgerInfo,
         txns: Vec<SignedTransaction>,
     ) -> SubmitTransactionsBatchResult<TransactionsBatchSubmissionResult> {
+        let payload_limits = self
+            .context
+            .get_payload_limits::<SubmitTransactionError>(ledger_info)?;
         // Iterate through transactions keeping track of failures
         let mut txn_failures = Vec::new();
         for (idx, txn) in txns.iter().enumerate() {
-            if let Err(error) = self.create_internal(txn.clone()).await {
+            if let Err(error) = self.create_internal(txn.clone(), payload_limits).await {
                 txn_failures.push(TransactionsBatchSingleSubmissionFailure {
                     error,
                     transaction_index: idx,
@@ -1222,11 +1265,15 @@ impl TransactionsApi {
     ///
     /// Note: this returns a `Vec<UserTransaction>`, but for backwards compatibility, this can't
     /// be removed even though, there is only one possible transaction
+    ///
+    /// Checks bypassed via the simulation options are reported in the `vm_status` of the
+    /// returned (JSON) transaction.
     pub fn simulate(
         &self,
         accept_type: &AcceptType,
         ledger_info: LedgerInfo,
         txn: SignedTransaction,
+        simulation_options: SimulationOptions,
     ) -> SimulateTransactionResult<Vec<UserTransaction>> {
         // The caller must ensure that the signature is not valid, as otherwise
         // a malicious actor could execute the transaction without their knowledge
@@ -1240,8 +1287,12 @@ impl TransactionsApi {
 
         // Simulate transaction
         let state_view = self.context.latest_state_view_poem(&ledger_info)?;
-        let (vm_status, output) =
-            AptosSimulationVM::create_vm_and_simulate_signed_transaction(&txn, &state_view);
+        let (vm_status, output, read_stats) =
+            AptosSimulationVM::create_vm_and_simulate_signed_transaction_with_options(
+                &txn,
+                &state_view,
+                simulation_options,
+            );
         let version = ledger_info.version();
 
         // Ensure that all known statuses return their values in the output (even if they aren't supposed to)
@@ -1325,6 +1376,15 @@ impl TransactionsApi {
                                 },
                                 _ => (),
                             }
+                            let bypassed_checks = simulation_options.bypassed_checks();
+                            if !bypassed_checks.is_empty() {
+                                txn.info.vm_status += format!(
+                                    "\nSimulation bypassed checks: {}",
+                                    bypassed_checks.join(", ")
+                                )
+                                .as_str();
+                            }
+                            txn.read_stats = Some(read_stats.into());
                             user_transactions.push(txn);
                         },
                         _ => {
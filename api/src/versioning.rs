@@ -0,0 +1,227 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::metrics::REQUESTS_BY_API_VERSION;
+use aptos_api_types::X_APTOS_API_VERSION;
+use aptos_config::config::ApiConfig;
+use poem::{
+    http::{uri::PathAndQuery, HeaderName, HeaderValue, StatusCode, Uri},
+    Endpoint, IntoResponse, Middleware, Request, Response, Result,
+};
+use poem_openapi::OperationId;
+
+/// The versions of the API, which are served under their own namespaces (e.g., /v1).
+/// The version of a request is added to the request data, so that endpoints can
+/// serve the versions differently.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V2 => "v2",
+        }
+    }
+
+    fn path_prefix(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "/v1",
+            ApiVersion::V2 => "/v2",
+        }
+    }
+
+    /// Returns the version of the request with the given path (and query),
+    /// and the rest of the path after the namespace of the version.
+    fn from_path(path_and_query: &str) -> Option<(Self, &str)> {
+        [ApiVersion::V1, ApiVersion::V2]
+            .into_iter()
+            .find_map(|version| {
+                let rest = path_and_query.strip_prefix(version.path_prefix())?;
+                (rest.is_empty() || rest.starts_with('/') || rest.starts_with('?'))
+                    .then_some((version, rest))
+            })
+    }
+}
+
+/// This middleware resolves the API version of every request. Until the v2 endpoints
+/// diverge from v1, v2 requests are served by the v1 endpoints (i.e., they are rerouted
+/// to the v1 namespace). The served version is returned in the X-Aptos-Api-Version header,
+/// and v1 responses announce the deprecation of v1 (if it is configured).
+pub struct ApiVersioning {
+    v2_enabled: bool,
+    v1_deprecation_headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl ApiVersioning {
+    pub fn new(api_config: &ApiConfig) -> Self {
+        let mut v1_deprecation_headers = vec![];
+        if let Some(v1_deprecation) = &api_config.v1_deprecation {
+            v1_deprecation_headers.push((
+                HeaderName::from_static("deprecation"),
+                HeaderValue::from_static("true"),
+            ));
+            v1_deprecation_headers.push((
+                HeaderName::from_static("link"),
+                HeaderValue::from_static("</v2>; rel=\"successor-version\""),
+            ));
+            // The values are checked by the config sanitizer
+            if let Some(sunset) = v1_deprecation
+                .sunset
+                .as_ref()
+                .and_then(|sunset| HeaderValue::from_str(sunset).ok())
+            {
+                v1_deprecation_headers.push((HeaderName::from_static("sunset"), sunset));
+            }
+            if let Some(warning) = v1_deprecation.message.as_ref().and_then(|message| {
+                HeaderValue::from_str(&format!("299 - \"{}\"", message.replace('"', "'"))).ok()
+            }) {
+                v1_deprecation_headers.push((HeaderName::from_static("warning"), warning));
+            }
+        }
+
+        Self {
+            v2_enabled: api_config.v2_enabled,
+            v1_deprecation_headers,
+        }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for ApiVersioning {
+    type Output = ApiVersioningEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        ApiVersioningEndpoint {
+            inner: ep,
+            v2_enabled: self.v2_enabled,
+            v1_deprecation_headers: self.v1_deprecation_headers.clone(),
+        }
+    }
+}
+
+/// Endpoint for ApiVersioning middleware.
+pub struct ApiVersioningEndpoint<E> {
+    inner: E,
+    v2_enabled: bool,
+    v1_deprecation_headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+#[async_trait::async_trait]
+impl<E: Endpoint> Endpoint for ApiVersioningEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        let version = match req
+            .uri()
+            .path_and_query()
+            .and_then(|path_and_query| ApiVersion::from_path(path_and_query.as_str()))
+        {
+            Some((ApiVersion::V2, rest)) if self.v2_enabled => {
+                let path_and_query = format!("{}{}", ApiVersion::V1.path_prefix(), rest);
+                let uri = with_path_and_query(req.uri(), &path_and_query)
+                    .map_err(|_| poem::Error::from_status(StatusCode::BAD_REQUEST))?;
+                req.set_uri(uri);
+                Some(ApiVersion::V2)
+            },
+            Some((ApiVersion::V1, _)) => Some(ApiVersion::V1),
+            _ => None,
+        };
+        let Some(version) = version else {
+            return Ok(self.inner.call(req).await?.into_response());
+        };
+
+        req.extensions_mut().insert(version);
+        let mut response = self.inner.call(req).await?.into_response();
+
+        let headers = response.headers_mut();
+        headers.insert(
+            X_APTOS_API_VERSION,
+            HeaderValue::from_static(version.as_str()),
+        );
+        if version == ApiVersion::V1 {
+            for (name, value) in &self.v1_deprecation_headers {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+
+        let operation_id = response
+            .data::<OperationId>()
+            .map(|operation_id| operation_id.0)
+            .unwrap_or("operation_id_not_set");
+        REQUESTS_BY_API_VERSION
+            .with_label_values(&[version.as_str(), operation_id, response.status().as_str()])
+            .inc();
+
+        Ok(response)
+    }
+}
+
+/// Returns the given URI with its path (and query) replaced.
+fn with_path_and_query(
+    uri: &Uri,
+    path_and_query: &str,
+) -> std::result::Result<Uri, poem::http::Error> {
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(PathAndQuery::try_from(path_and_query)?);
+    Ok(Uri::from_parts(parts)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_config::config::ApiDeprecationConfig;
+
+    #[test]
+    fn test_version_from_path() {
+        assert_eq!(
+            ApiVersion::from_path("/v1/accounts/0x1"),
+            Some((ApiVersion::V1, "/accounts/0x1"))
+        );
+        assert_eq!(
+            ApiVersion::from_path("/v2/transactions?start=1"),
+            Some((ApiVersion::V2, "/transactions?start=1"))
+        );
+        assert_eq!(ApiVersion::from_path("/v2"), Some((ApiVersion::V2, "")));
+        assert_eq!(ApiVersion::from_path("/v20/accounts"), None);
+        assert_eq!(ApiVersion::from_path("/"), None);
+    }
+
+    #[test]
+    fn test_v1_deprecation_headers() {
+        let api_config = ApiConfig::default();
+        assert!(ApiVersioning::new(&api_config)
+            .v1_deprecation_headers
+            .is_empty());
+
+        let api_config = ApiConfig {
+            v1_deprecation: Some(ApiDeprecationConfig {
+                sunset: Some("Wed, 01 Jan 2025 00:00:00 GMT".into()),
+                message: Some("Migrate to \"v2\"".into()),
+            }),
+            ..Default::default()
+        };
+        let headers: Vec<_> = ApiVersioning::new(&api_config)
+            .v1_deprecation_headers
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_str().unwrap().to_string()))
+            .collect();
+        assert_eq!(headers, vec![
+            ("deprecation".to_string(), "true".to_string()),
+            (
+                "link".to_string(),
+                "</v2>; rel=\"successor-version\"".to_string()
+            ),
+            (
+                "sunset".to_string(),
+                "Wed, 01 Jan 2025 00:00:00 GMT".to_string()
+            ),
+            (
+                "warning".to_string(),
+                "299 - \"Migrate to 'v2'\"".to_string()
+            ),
+        ]);
+    }
+}
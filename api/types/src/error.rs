@@ -110,6 +110,8 @@ pub enum AptosErrorCode {
     BcsNotSupported = 602,
     /// API Disabled
     ApiDisabled = 603,
+    /// The node is overloaded, the request should be retried later
+    ServiceOverloaded = 604,
 }
 
 impl AptosErrorCode {
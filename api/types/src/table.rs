@@ -22,6 +22,22 @@ impl VerifyInput for TableItemRequest {
     }
 }
 
+/// Types of the keys and values of a table, for the GetTableInfo API
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct TableInfo {
+    pub key_type: MoveType,
+    pub value_type: MoveType,
+}
+
+impl From<aptos_types::state_store::table::TableInfo> for TableInfo {
+    fn from(table_info: aptos_types::state_store::table::TableInfo) -> Self {
+        Self {
+            key_type: table_info.key_type.into(),
+            value_type: table_info.value_type.into(),
+        }
+    }
+}
+
 /// Table Item request for the GetTableItemRaw API
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
 pub struct RawTableItemRequest {
@@ -21,6 +21,7 @@ use aptos_types::{
     block_metadata_ext::BlockMetadataExt,
     contract_event::{ContractEvent, EventWithVersion},
     keyless,
+    state_store::state_read_stats::StateReadStats,
     transaction::{
         authenticator::{
             AccountAuthenticator, AnyPublicKey, AnySignature, MultiKey, MultiKeyAuthenticator,
@@ -287,6 +288,7 @@ impl
             request: (txn, payload).into(),
             events,
             timestamp: timestamp.into(),
+            read_stats: None,
         }))
     }
 }
@@ -413,6 +415,33 @@ pub struct UserTransaction {
     /// Events generated by the transaction
     pub events: Vec<Event>,
     pub timestamp: U64,
+    /// The state reads of the transaction, only present in simulation responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_stats: Option<TransactionReadStats>,
+}
+
+/// The state reads of a transaction, to help optimize its storage access patterns
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Object)]
+pub struct TransactionReadStats {
+    /// The number of distinct state items (resources, resource groups and modules) read
+    pub num_distinct_reads: U64,
+    /// The total size of the values read, in bytes
+    pub bytes_read: U64,
+    /// The number of values read from storage
+    pub num_storage_reads: U64,
+    /// The number of storage reads served by a storage cache
+    pub num_storage_cache_hits: U64,
+}
+
+impl From<StateReadStats> for TransactionReadStats {
+    fn from(read_stats: StateReadStats) -> Self {
+        Self {
+            num_distinct_reads: read_stats.num_distinct_reads.into(),
+            bytes_read: read_stats.bytes_read.into(),
+            num_storage_reads: read_stats.num_storage_reads.into(),
+            num_storage_cache_hits: read_stats.num_storage_cache_hits.into(),
+        }
+    }
 }
 
 /// A state checkpoint transaction
@@ -9,8 +9,10 @@ use aptos_gas_algebra::{
     AbstractValueSize, Fee, FeePerByte, FeePerGasUnit, FeePerSlot, Gas, GasExpression,
     GasScalingFactor, GasUnit, NumModules, NumSlots,
 };
+use aptos_types::transaction::PayloadLimits;
 use move_core_types::gas_algebra::{
-    InternalGas, InternalGasPerArg, InternalGasPerByte, InternalGasUnit, NumBytes, ToUnitWithParams,
+    InternalGas, InternalGasPerArg, InternalGasPerByte, InternalGasUnit, NumArgs, NumBytes,
+    NumTypeNodes, ToUnitWithParams,
 };
 
 const GAS_SCALING_FACTOR: u64 = 1_000_000;
@@ -220,6 +222,27 @@ crate::gas_schedule::macros::define_gas_parameters!(
             { 15.. => "max_total_dependency_size" },
             1024 * 1024 * 12 / 10, // 1.2 MB
         ],
+        // Structural limits on transaction payloads, see `PayloadLimits`.
+        [
+            max_num_type_args: NumArgs,
+            { 16.. => "max_num_type_args" },
+            32,
+        ],
+        [
+            max_type_arg_depth: NumTypeNodes,
+            { 16.. => "max_type_arg_depth" },
+            8,
+        ],
+        [
+            max_num_args: NumArgs,
+            { 16.. => "max_num_args" },
+            128,
+        ],
+        [
+            max_arg_size_in_bytes: NumBytes,
+            { 16.. => "max_arg_size_in_bytes" },
+            512 * 1024,
+        ],
     ]
 );
 
@@ -246,6 +269,17 @@ impl TransactionGasParameters {
 
         MIN_TRANSACTION_GAS_UNITS + INTRINSIC_GAS_PER_BYTE * excess
     }
+
+    /// Returns the limits transaction payloads are checked against, or `None` if the gas feature
+    /// version predates them.
+    pub fn payload_limits(&self, gas_feature_version: u64) -> Option<PayloadLimits> {
+        (gas_feature_version >= 16).then(|| PayloadLimits {
+            max_num_type_args: self.max_num_type_args.into(),
+            max_type_arg_depth: self.max_type_arg_depth.into(),
+            max_num_args: self.max_num_args.into(),
+            max_arg_size_in_bytes: self.max_arg_size_in_bytes.into(),
+        })
+    }
 }
 
 impl ToUnitWithParams<TransactionGasParameters, InternalGasUnit> for GasUnit {
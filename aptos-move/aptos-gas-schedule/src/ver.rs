@@ -8,6 +8,8 @@
 ///   - Changing how gas is calculated in any way
 ///
 /// Change log:
+/// - V16
+///   - Limits on the number, depth and size of payload (type) arguments
 /// - V15
 ///   - Gas & limits for dependencies
 /// - V14
@@ -53,4 +55,4 @@
 ///       global operations.
 /// - V1
 ///   - TBA
-pub const LATEST_GAS_FEATURE_VERSION: u64 = 15;
+pub const LATEST_GAS_FEATURE_VERSION: u64 = 16;
@@ -106,6 +106,9 @@ pub enum FeatureFlag {
     MaxObjectNestingCheck,
     KeylessAccountsWithPasskeys,
     TransactionContextExtension,
+    ResourceGroupPerTagGas,
+    StagedModulePublishing,
+    DiscardedBlockBookkeeping,
 }
 
 fn generate_features_blob(writer: &CodeWriter, data: &[u64]) {
@@ -274,6 +277,9 @@ impl From<FeatureFlag> for AptosFeatureFlag {
             FeatureFlag::TransactionContextExtension => {
                 AptosFeatureFlag::TRANSACTION_CONTEXT_EXTENSION
             },
+            FeatureFlag::ResourceGroupPerTagGas => AptosFeatureFlag::RESOURCE_GROUP_PER_TAG_GAS,
+            FeatureFlag::StagedModulePublishing => AptosFeatureFlag::STAGED_MODULE_PUBLISHING,
+            FeatureFlag::DiscardedBlockBookkeeping => AptosFeatureFlag::DISCARDED_BLOCK_BOOKKEEPING,
         }
     }
 }
@@ -365,6 +371,9 @@ impl From<AptosFeatureFlag> for FeatureFlag {
             AptosFeatureFlag::TRANSACTION_CONTEXT_EXTENSION => {
                 FeatureFlag::TransactionContextExtension
             },
+            AptosFeatureFlag::RESOURCE_GROUP_PER_TAG_GAS => FeatureFlag::ResourceGroupPerTagGas,
+            AptosFeatureFlag::STAGED_MODULE_PUBLISHING => FeatureFlag::StagedModulePublishing,
+            AptosFeatureFlag::DISCARDED_BLOCK_BOOKKEEPING => FeatureFlag::DiscardedBlockBookkeeping,
         }
     }
 }
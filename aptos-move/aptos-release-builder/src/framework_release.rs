@@ -0,0 +1,290 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Library API to build a framework release bundle and diff it against the framework deployed on
+//! a network, for governance tooling and the CI of framework forks.
+
+use anyhow::{bail, Result};
+use aptos_api_types::AptosErrorCode;
+use aptos_framework::{
+    natives::code::PackageRegistry, ReleaseBundle, ReleasePackage, ReleaseTarget,
+};
+use aptos_rest_client::{error::RestError, Client};
+use aptos_types::account_address::AccountAddress;
+use move_binary_format::{access::ModuleAccess, normalized, CompiledModule};
+use move_core_types::identifier::Identifier;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use url::Url;
+
+/// A framework release bundle, together with its diff against the framework on a network.
+pub struct FrameworkRelease {
+    pub bundle: ReleaseBundle,
+    pub diff: FrameworkReleaseDiff,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct FrameworkReleaseDiff {
+    pub packages: Vec<PackageDiff>,
+}
+
+impl FrameworkReleaseDiff {
+    pub fn is_empty(&self) -> bool {
+        self.packages.iter().all(PackageDiff::is_empty)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct PackageDiff {
+    pub address: AccountAddress,
+    pub name: String,
+    /// Whether the package is not published on the network yet.
+    pub is_new: bool,
+    pub added_modules: Vec<String>,
+    pub removed_modules: Vec<String>,
+    pub changed_modules: Vec<ModuleDiff>,
+}
+
+impl PackageDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_modules.is_empty()
+            && self.removed_modules.is_empty()
+            && self.changed_modules.is_empty()
+    }
+}
+
+/// The diff of a module whose bytecode changed. The ABI diffs cover the structs and the exposed
+/// (public, friend or entry) functions; if they are all empty, only the implementation changed.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct ModuleDiff {
+    pub name: String,
+    pub added_functions: Vec<String>,
+    pub removed_functions: Vec<String>,
+    pub changed_functions: Vec<String>,
+    pub added_structs: Vec<String>,
+    pub removed_structs: Vec<String>,
+    pub changed_structs: Vec<String>,
+}
+
+impl ModuleDiff {
+    pub fn is_abi_changed(&self) -> bool {
+        !(self.added_functions.is_empty()
+            && self.removed_functions.is_empty()
+            && self.changed_functions.is_empty()
+            && self.added_structs.is_empty()
+            && self.removed_structs.is_empty()
+            && self.changed_structs.is_empty())
+    }
+}
+
+/// Builds the framework release bundle for `target` and diffs it against the framework on the
+/// network at `endpoint`.
+pub async fn build_framework_release(
+    target: ReleaseTarget,
+    endpoint: Url,
+) -> Result<FrameworkRelease> {
+    // Building the packages is CPU heavy and blocking.
+    let bundle = tokio::task::spawn_blocking(move || target.create_release_bundle(true)).await??;
+    let diff = diff_release_bundle(&bundle, &Client::new(endpoint)).await?;
+    Ok(FrameworkRelease { bundle, diff })
+}
+
+/// Diffs each package of the bundle against the package of the same name published on chain.
+pub async fn diff_release_bundle(
+    bundle: &ReleaseBundle,
+    client: &Client,
+) -> Result<FrameworkReleaseDiff> {
+    let mut packages = vec![];
+    for package in &bundle.packages {
+        if package.code().is_empty() {
+            bail!("Package {} has no modules", package.name());
+        }
+        let address = *package.compiled_module_at(0)?.self_addr();
+        let on_chain_modules = fetch_package_modules(client, address, package.name()).await?;
+        packages.push(diff_package(package, address, on_chain_modules)?);
+    }
+    Ok(FrameworkReleaseDiff { packages })
+}
+
+/// Returns the modules of the package published under `address`, or `None` if there is no such
+/// package.
+async fn fetch_package_modules(
+    client: &Client,
+    address: AccountAddress,
+    package_name: &str,
+) -> Result<Option<Vec<CompiledModule>>> {
+    let registry = match client
+        .get_account_resource_bcs::<PackageRegistry>(address, "0x1::code::PackageRegistry")
+        .await
+    {
+        Ok(registry) => registry.into_inner(),
+        Err(RestError::Api(error))
+            if matches!(
+                error.error.error_code,
+                AptosErrorCode::ResourceNotFound | AptosErrorCode::AccountNotFound
+            ) =>
+        {
+            return Ok(None)
+        },
+        Err(error) => return Err(error.into()),
+    };
+    let module_names: BTreeSet<_> = match registry
+        .packages
+        .into_iter()
+        .find(|package| package.name == package_name)
+    {
+        Some(package) => package.modules.into_iter().map(|m| m.name).collect(),
+        None => return Ok(None),
+    };
+
+    let modules = client.get_account_modules_bcs(address).await?.into_inner();
+    modules
+        .into_iter()
+        .filter(|(id, _)| module_names.contains(id.name.as_str()))
+        .map(|(_, code)| Ok(CompiledModule::deserialize(&code)?))
+        .collect::<Result<_>>()
+        .map(Some)
+}
+
+/// Diffs a release package against the modules of the package currently published on chain.
+pub fn diff_package(
+    package: &ReleasePackage,
+    address: AccountAddress,
+    on_chain_modules: Option<Vec<CompiledModule>>,
+) -> Result<PackageDiff> {
+    let is_new = on_chain_modules.is_none();
+    let old = by_name(on_chain_modules.unwrap_or_default());
+    let new = by_name(
+        (0..package.code().len())
+            .map(|idx| package.compiled_module_at(idx))
+            .collect::<Result<Vec<_>, _>>()?,
+    );
+
+    let mut diff = PackageDiff {
+        address,
+        name: package.name().to_string(),
+        is_new,
+        ..PackageDiff::default()
+    };
+    for (name, new_module) in &new {
+        match old.get(name) {
+            None => diff.added_modules.push(name.clone()),
+            Some(old_module) if old_module != new_module => diff.changed_modules.push(diff_module(
+                name,
+                &normalized::Module::new(old_module),
+                &normalized::Module::new(new_module),
+            )),
+            Some(_) => (),
+        }
+    }
+    diff.removed_modules = old
+        .into_keys()
+        .filter(|name| !new.contains_key(name))
+        .collect();
+    Ok(diff)
+}
+
+/// Diffs the ABIs of two versions of a module.
+pub fn diff_module(name: &str, old: &normalized::Module, new: &normalized::Module) -> ModuleDiff {
+    let (added_functions, removed_functions, changed_functions) =
+        diff_maps(&old.exposed_functions, &new.exposed_functions);
+    let (added_structs, removed_structs, changed_structs) = diff_maps(&old.structs, &new.structs);
+    ModuleDiff {
+        name: name.to_string(),
+        added_functions,
+        removed_functions,
+        changed_functions,
+        added_structs,
+        removed_structs,
+        changed_structs,
+    }
+}
+
+fn by_name(modules: Vec<CompiledModule>) -> BTreeMap<String, CompiledModule> {
+    modules
+        .into_iter()
+        .map(|module| (module.self_id().name().to_string(), module))
+        .collect()
+}
+
+/// Returns the added, removed and changed keys.
+fn diff_maps<T: Eq>(
+    old: &BTreeMap<Identifier, T>,
+    new: &BTreeMap<Identifier, T>,
+) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let added = new
+        .keys()
+        .filter(|name| !old.contains_key(*name))
+        .map(ToString::to_string)
+        .collect();
+    let removed = old
+        .keys()
+        .filter(|name| !new.contains_key(*name))
+        .map(ToString::to_string)
+        .collect();
+    let changed = new
+        .iter()
+        .filter(|(name, item)| old.get(*name).map_or(false, |old_item| old_item != *item))
+        .map(|(name, _)| name.to_string())
+        .collect();
+    (added, removed, changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::{AbilitySet, Visibility};
+
+    fn function(parameters: Vec<normalized::Type>) -> normalized::Function {
+        normalized::Function {
+            visibility: Visibility::Public,
+            is_entry: false,
+            type_parameters: vec![],
+            parameters,
+            return_: vec![],
+        }
+    }
+
+    fn module(functions: Vec<(&str, normalized::Function)>) -> normalized::Module {
+        normalized::Module {
+            file_format_version: 6,
+            address: AccountAddress::ONE,
+            name: Identifier::new("m").unwrap(),
+            friends: vec![],
+            structs: BTreeMap::from([(Identifier::new("S").unwrap(), normalized::Struct {
+                abilities: AbilitySet::EMPTY,
+                type_parameters: vec![],
+                fields: vec![],
+            })]),
+            exposed_functions: functions
+                .into_iter()
+                .map(|(name, function)| (Identifier::new(name).unwrap(), function))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff_module() {
+        let old = module(vec![
+            ("unchanged", function(vec![])),
+            ("changed", function(vec![normalized::Type::U64])),
+            ("removed", function(vec![])),
+        ]);
+        let new = module(vec![
+            ("unchanged", function(vec![])),
+            ("changed", function(vec![normalized::Type::U128])),
+            ("added", function(vec![])),
+        ]);
+
+        let diff = diff_module("m", &old, &new);
+        assert!(diff.is_abi_changed());
+        assert_eq!(diff, ModuleDiff {
+            name: "m".to_string(),
+            added_functions: vec!["added".to_string()],
+            removed_functions: vec!["removed".to_string()],
+            changed_functions: vec!["changed".to_string()],
+            ..ModuleDiff::default()
+        });
+        assert!(!diff_module("m", &old, &old).is_abi_changed());
+    }
+}
@@ -3,9 +3,10 @@
 
 use anyhow::Context;
 use aptos_crypto::{ed25519::Ed25519PrivateKey, ValidCryptoMaterialStringExt};
-use aptos_framework::natives::code::PackageRegistry;
+use aptos_framework::{natives::code::PackageRegistry, ReleaseTarget};
 use aptos_release_builder::{
     components::fetch_config,
+    framework_release::build_framework_release,
     initialize_aptos_core_path,
     validate::{DEFAULT_RESOLUTION_TIME, FAST_RESOLUTION_TIME},
 };
@@ -62,6 +63,15 @@ pub enum Commands {
         #[clap(short, long)]
         print_gas_schedule: bool,
     },
+    /// Build a framework release and print its diff against the framework on a network.
+    DiffFramework {
+        /// Url endpoint for the desired network. e.g: https://fullnode.mainnet.aptoslabs.com/v1.
+        #[clap(short, long)]
+        endpoint: url::Url,
+        /// The release target to build.
+        #[clap(long, default_value_t = ReleaseTarget::Head)]
+        target: ReleaseTarget,
+    },
     /// Print out package metadata.
     /// Usage: --endpoint '<URL>'
     /// --package-address <ADDRESS> --package-name <PACKAGE_NAME> [--print-json]
@@ -230,6 +240,11 @@ async fn main() -> anyhow::Result<()> {
             );
             Ok(())
         },
+        Commands::DiffFramework { endpoint, target } => {
+            let release = build_framework_release(target, endpoint).await?;
+            println!("{}", serde_json::to_string_pretty(&release.diff)?);
+            Ok(())
+        },
         Commands::PrintPackageMetadata {
             endpoint,
             package_address,
@@ -6,5 +6,6 @@
 
 mod benchmark_runner;
 pub mod measurement;
+pub mod suites;
 pub mod transaction_bench_state;
 pub mod transactions;
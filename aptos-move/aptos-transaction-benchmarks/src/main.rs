@@ -6,12 +6,16 @@ use aptos_crypto::_once_cell::sync::Lazy;
 use aptos_language_e2e_tests::account_universe::P2PTransferGen;
 use aptos_metrics_core::{register_int_gauge, IntGauge};
 use aptos_push_metrics::MetricsPusher;
-use aptos_transaction_benchmarks::transactions::TransactionBencher;
+use aptos_transaction_benchmarks::{
+    suites::{run_suite, BenchmarkSuite, SuiteConfig},
+    transactions::TransactionBencher,
+};
 use aptos_vm_logging::disable_speculative_logging;
 use clap::{Parser, Subcommand};
 use proptest::prelude::*;
 use std::{
     net::SocketAddr,
+    path::PathBuf,
     time::{SystemTime, UNIX_EPOCH},
 };
 
@@ -30,6 +34,7 @@ struct Args {
 enum BenchmarkCommand {
     ParamSweep(ParamSweepOpt),
     Execute(ExecuteOpt),
+    Suite(SuiteOpt),
 }
 
 #[derive(Debug, Parser)]
@@ -89,6 +94,40 @@ struct ExecuteOpt {
     pub generate_then_execute: bool,
 }
 
+/// Runs a named suite of a realistic workload, reporting the results as JSON.
+#[derive(Debug, Parser)]
+struct SuiteOpt {
+    #[clap(long, value_enum)]
+    pub suite: BenchmarkSuite,
+
+    #[clap(long, default_value_t = 10000)]
+    pub num_accounts: usize,
+
+    #[clap(long, default_value_t = 10000)]
+    pub block_size: usize,
+
+    #[clap(long, default_value_t = 2)]
+    pub num_warmups: usize,
+
+    #[clap(long, default_value_t = 10)]
+    pub num_blocks: usize,
+
+    /// The concurrency level of the block executor (the number of CPUs by default).
+    #[clap(long)]
+    pub concurrency_level: Option<usize>,
+
+    /// The number of pools that the swaps of the DEX swaps suite are spread over.
+    #[clap(long, default_value_t = 4)]
+    pub num_pools: usize,
+
+    #[clap(long)]
+    pub maybe_block_gas_limit: Option<u64>,
+
+    /// The file to write the JSON report to (printed if not set).
+    #[clap(long)]
+    pub output_json: Option<PathBuf>,
+}
+
 fn param_sweep(opt: ParamSweepOpt) {
     disable_speculative_logging();
 
@@ -195,6 +234,35 @@ fn execute(opt: ExecuteOpt) {
     println!("Avg Parallel TPS = {:?}", sum / par_tps.len())
 }
 
+fn suite(opt: SuiteOpt) {
+    disable_speculative_logging();
+
+    let report = run_suite(opt.suite, SuiteConfig {
+        num_accounts: opt.num_accounts,
+        block_size: opt.block_size,
+        num_warmups: opt.num_warmups,
+        num_blocks: opt.num_blocks,
+        concurrency_level: opt.concurrency_level.unwrap_or_else(num_cpus::get),
+        num_pools: opt.num_pools,
+        maybe_block_gas_limit: opt.maybe_block_gas_limit,
+    });
+    println!(
+        "Suite {:?}: TPS = {:.0}, p50 = {:.1}ms, p99 = {:.1}ms, abort rate = {:.3}",
+        report.suite,
+        report.tps,
+        report.block_execution_time.p50_ms,
+        report.block_execution_time.p99_ms,
+        report.abort_rate
+    );
+
+    let json = serde_json::to_string_pretty(&report).expect("Report must serialize");
+    match opt.output_json {
+        Some(path) => std::fs::write(&path, json)
+            .unwrap_or_else(|err| panic!("Failed to write the report to {:?}: {}", path, err)),
+        None => println!("{}", json),
+    }
+}
+
 fn main() {
     aptos_logger::Logger::new().init();
     START_TIME.set(
@@ -211,6 +279,7 @@ fn main() {
     match args.command {
         BenchmarkCommand::ParamSweep(opt) => param_sweep(opt),
         BenchmarkCommand::Execute(opt) => execute(opt),
+        BenchmarkCommand::Suite(opt) => suite(opt),
     }
 }
 
@@ -0,0 +1,377 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Named benchmark suites of realistic (contended) workloads, which report their results as JSON,
+//! so that the numbers of different runs (e.g. before and after a change) can be compared.
+
+use crate::transactions::RAYON_EXEC_POOL;
+use aptos_block_executor::txn_commit_hook::NoOpTransactionCommitHook;
+use aptos_cached_packages::aptos_stdlib;
+use aptos_language_e2e_tests::{account::Account, executor::FakeExecutor};
+use aptos_transaction_generator_lib::{
+    publishing::publish_util::{Package, PackageHandler},
+    EntryPoints,
+};
+use aptos_types::{
+    account_address::AccountAddress,
+    block_executor::config::BlockExecutorConfig,
+    transaction::{
+        signature_verified_transaction::{
+            into_signature_verified_block, SignatureVerifiedTransaction,
+        },
+        ExecutionStatus, SignedTransaction, Transaction, TransactionPayload, TransactionStatus,
+    },
+    vm_status::VMStatus,
+};
+use aptos_vm::block_executor::{AptosTransactionOutput, BlockAptosVM};
+use clap::ValueEnum;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const ACCOUNT_BALANCE: u64 = 1_000_000_000_000_000;
+const MAX_GAS_AMOUNT: u64 = 2_000_000;
+const GAS_UNIT_PRICE: u64 = 100;
+const TRANSFER_AMOUNT: u64 = 1_000;
+
+/// The workloads of the benchmark suites.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum BenchmarkSuite {
+    /// Swaps against a few pools, i.e., all senders update one of the few global resources
+    /// (num_pools of them).
+    DexSwaps,
+    /// Mints of NFTs of a single collection, which are numbered sequentially.
+    NftMints,
+    /// Transfers to new accounts, each of which creates the receiving account.
+    AccountCreation,
+}
+
+/// The parameters of a benchmark suite run.
+#[derive(Clone, Debug, Serialize)]
+pub struct SuiteConfig {
+    pub num_accounts: usize,
+    pub block_size: usize,
+    pub num_warmups: usize,
+    pub num_blocks: usize,
+    pub concurrency_level: usize,
+    /// The number of pools of the DEX swaps suite.
+    pub num_pools: usize,
+    pub maybe_block_gas_limit: Option<u64>,
+}
+
+/// The percentiles of a distribution of times, in milliseconds.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct TimePercentiles {
+    pub p50_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl TimePercentiles {
+    fn from_durations(mut durations: Vec<Duration>) -> Self {
+        durations.sort();
+        // Nearest-rank percentile of the sorted durations.
+        let percentile = |p: usize| {
+            durations
+                .get((durations.len() * p).div_ceil(100).saturating_sub(1))
+                .map_or(0.0, |duration| as_millis(*duration))
+        };
+        Self {
+            p50_ms: percentile(50),
+            p99_ms: percentile(99),
+            max_ms: percentile(100),
+        }
+    }
+}
+
+/// The total times of the stages of a suite run, in milliseconds. The worker times (summed over
+/// all the workers of the block executor) break down the execution of the measured blocks.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct StageTimings {
+    /// Creating the accounts and publishing the packages of the workload.
+    pub setup_ms: f64,
+    /// Generating and signing the transactions of all the blocks.
+    pub generation_ms: f64,
+    /// Executing the measured blocks.
+    pub execution_ms: f64,
+    /// Applying the outputs of the measured blocks to the state.
+    pub commit_ms: f64,
+    pub worker_execute_ms: f64,
+    pub worker_validate_ms: f64,
+    pub worker_materialize_ms: f64,
+}
+
+/// The report of a benchmark suite run, over the measured blocks (i.e., without the warmups).
+#[derive(Clone, Debug, Serialize)]
+pub struct SuiteReport {
+    pub suite: BenchmarkSuite,
+    pub config: SuiteConfig,
+    pub num_txns: usize,
+    /// The transactions that did not succeed (e.g. aborted or discarded).
+    pub num_failed_txns: usize,
+    pub tps: f64,
+    pub block_execution_time: TimePercentiles,
+    /// The number of (speculative) executions of the transactions of the block executor.
+    pub num_incarnations: u64,
+    /// The number of incarnations aborted after a failed validation (and re-executed).
+    pub num_aborts: u64,
+    /// The number of aborts per transaction.
+    pub abort_rate: f64,
+    /// The number of times an execution waited on a read dependency.
+    pub num_dependency_waits: u64,
+    pub stage_timings: StageTimings,
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// The accounts of a suite, which send its transactions in turns.
+struct Senders {
+    accounts: Vec<Account>,
+    sequence_numbers: Vec<u64>,
+    next: usize,
+}
+
+impl Senders {
+    fn new(executor: &mut FakeExecutor, num_accounts: usize) -> Self {
+        Self {
+            accounts: executor.create_accounts(num_accounts, ACCOUNT_BALANCE, 0),
+            sequence_numbers: vec![0; num_accounts],
+            next: 0,
+        }
+    }
+
+    fn sign(&mut self, payload: TransactionPayload) -> SignedTransaction {
+        let idx = self.next;
+        self.next = (self.next + 1) % self.accounts.len();
+        let sequence_number = self.sequence_numbers[idx];
+        self.sequence_numbers[idx] += 1;
+        sign_transaction(&self.accounts[idx], sequence_number, payload)
+    }
+}
+
+fn sign_transaction(
+    account: &Account,
+    sequence_number: u64,
+    payload: TransactionPayload,
+) -> SignedTransaction {
+    account
+        .transaction()
+        .sequence_number(sequence_number)
+        .max_gas_amount(MAX_GAS_AMOUNT)
+        .gas_unit_price(GAS_UNIT_PRICE)
+        .payload(payload)
+        .sign()
+}
+
+/// Executes the setup transaction on the executor, applying its output.
+fn execute_setup_txn(executor: &mut FakeExecutor, txn: SignedTransaction) {
+    let output = executor.execute_transaction(txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(ExecutionStatus::Success),
+        "Setup transaction of the suite failed"
+    );
+    executor.apply_write_set(output.write_set());
+}
+
+/// Publishes the package of the entry point at a new publisher account (and initializes it).
+fn publish_package(
+    executor: &mut FakeExecutor,
+    package_handler: &mut PackageHandler,
+    rng: &mut StdRng,
+    entry_point: &EntryPoints,
+) -> (Package, AccountAddress) {
+    let publisher = executor.new_account_at(AccountAddress::random());
+    let package = package_handler.pick_package(rng, *publisher.address());
+    execute_setup_txn(
+        executor,
+        sign_transaction(&publisher, 0, package.publish_transaction_payload()),
+    );
+    if let Some(init_entry_point) = entry_point.initialize_entry_point() {
+        let payload = init_entry_point.create_payload(
+            package.get_module_id(init_entry_point.module_name()),
+            Some(rng),
+            Some(publisher.address()),
+        );
+        execute_setup_txn(executor, sign_transaction(&publisher, 1, payload));
+    }
+    (package, *publisher.address())
+}
+
+/// Generates the payloads of the transactions of a suite, against the state set up for it.
+enum PayloadGenerator {
+    EntryPoint {
+        entry_point: EntryPoints,
+        // The published packages (e.g. the pools), used in turns.
+        packages: Vec<(Package, AccountAddress)>,
+        next: usize,
+        rng: StdRng,
+    },
+    AccountCreation,
+}
+
+impl PayloadGenerator {
+    fn setup(suite: BenchmarkSuite, config: &SuiteConfig, executor: &mut FakeExecutor) -> Self {
+        let (entry_point, num_packages) = match suite {
+            BenchmarkSuite::DexSwaps => (EntryPoints::IncGlobal, config.num_pools),
+            BenchmarkSuite::NftMints => (EntryPoints::TokenV1MintAndStoreNFTSequential, 1),
+            BenchmarkSuite::AccountCreation => return Self::AccountCreation,
+        };
+        assert!(num_packages > 0, "The suite requires at least one pool");
+
+        let mut rng = StdRng::seed_from_u64(14);
+        let mut package_handler = PackageHandler::new(entry_point.package_name());
+        let packages = (0..num_packages)
+            .map(|_| publish_package(executor, &mut package_handler, &mut rng, &entry_point))
+            .collect();
+        Self::EntryPoint {
+            entry_point,
+            packages,
+            next: 0,
+            rng,
+        }
+    }
+
+    fn next_payload(&mut self) -> TransactionPayload {
+        match self {
+            Self::EntryPoint {
+                entry_point,
+                packages,
+                next,
+                rng,
+            } => {
+                let (package, publisher) = &packages[*next];
+                *next = (*next + 1) % packages.len();
+                entry_point.create_payload(
+                    package.get_module_id(entry_point.module_name()),
+                    Some(rng),
+                    Some(publisher),
+                )
+            },
+            Self::AccountCreation => {
+                aptos_stdlib::aptos_account_transfer(AccountAddress::random(), TRANSFER_AMOUNT)
+            },
+        }
+    }
+}
+
+/// Runs the benchmark suite, executing the blocks one after another (each on the state resulting
+/// from the previous one), and returns the report of the measured blocks.
+pub fn run_suite(suite: BenchmarkSuite, config: SuiteConfig) -> SuiteReport {
+    let mut stage_timings = StageTimings::default();
+
+    let timer = Instant::now();
+    let mut executor = FakeExecutor::from_head_genesis().set_not_parallel();
+    let mut generator = PayloadGenerator::setup(suite, &config, &mut executor);
+    let mut senders = Senders::new(&mut executor, config.num_accounts);
+    stage_timings.setup_ms = as_millis(timer.elapsed());
+
+    let timer = Instant::now();
+    let blocks: Vec<Vec<SignatureVerifiedTransaction>> = (0..config.num_warmups
+        + config.num_blocks)
+        .map(|_| {
+            into_signature_verified_block(
+                (0..config.block_size)
+                    .map(|_| Transaction::UserTransaction(senders.sign(generator.next_payload())))
+                    .collect(),
+            )
+        })
+        .collect();
+    stage_timings.generation_ms = as_millis(timer.elapsed());
+
+    let mut num_txns = 0;
+    let mut num_failed_txns = 0;
+    let mut block_times = vec![];
+    let (mut num_incarnations, mut num_aborts, mut num_dependency_waits) = (0, 0, 0);
+    for (block_idx, block) in blocks.iter().enumerate() {
+        let is_warmup = block_idx < config.num_warmups;
+
+        let timer = Instant::now();
+        let (output, stats) = BlockAptosVM::execute_block_with_stats::<
+            _,
+            NoOpTransactionCommitHook<AptosTransactionOutput, VMStatus>,
+        >(
+            Arc::clone(&RAYON_EXEC_POOL),
+            None,
+            None,
+            block,
+            executor.get_state_view(),
+            BlockExecutorConfig::new_maybe_block_limit(
+                config.concurrency_level,
+                config.maybe_block_gas_limit,
+            ),
+            None,
+        );
+        let block_time = timer.elapsed();
+        let outputs = output
+            .expect("VM should not fail to start")
+            .into_transaction_outputs_forced();
+
+        let timer = Instant::now();
+        for output in &outputs {
+            executor.apply_write_set(output.write_set());
+        }
+        if is_warmup {
+            continue;
+        }
+        stage_timings.commit_ms += as_millis(timer.elapsed());
+        stage_timings.execution_ms += as_millis(block_time);
+        block_times.push(block_time);
+
+        num_txns += outputs.len();
+        num_failed_txns += outputs
+            .iter()
+            .filter(|output| output.status() != &TransactionStatus::Keep(ExecutionStatus::Success))
+            .count();
+        if let Some(stats) = stats {
+            num_incarnations += stats.num_incarnations;
+            num_aborts += stats.num_aborts;
+            num_dependency_waits += stats.num_dependency_waits;
+            stage_timings.worker_execute_ms += as_millis(stats.execute_time);
+            stage_timings.worker_validate_ms += as_millis(stats.validate_time);
+            stage_timings.worker_materialize_ms += as_millis(stats.materialize_time);
+        }
+    }
+
+    SuiteReport {
+        suite,
+        config,
+        num_txns,
+        num_failed_txns,
+        tps: num_txns as f64 * 1000.0 / stage_timings.execution_ms.max(f64::EPSILON),
+        block_execution_time: TimePercentiles::from_durations(block_times),
+        num_incarnations,
+        num_aborts,
+        abort_rate: num_aborts as f64 / num_txns.max(1) as f64,
+        num_dependency_waits,
+        stage_timings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_percentiles() {
+        let durations = (1..=200).rev().map(Duration::from_millis).collect();
+        assert_eq!(
+            TimePercentiles::from_durations(durations),
+            TimePercentiles {
+                p50_ms: 100.0,
+                p99_ms: 198.0,
+                max_ms: 200.0,
+            }
+        );
+        assert_eq!(
+            TimePercentiles::from_durations(vec![]),
+            TimePercentiles::default()
+        );
+    }
+}
@@ -216,6 +216,8 @@ where
             NoOpTransactionCommitHook<AptosTransactionOutput, VMStatus>,
         >(
             Arc::clone(&RAYON_EXEC_POOL),
+            None,
+            None,
             transactions,
             self.state_view.as_ref(),
             BlockExecutorConfig::new_maybe_block_limit(1, maybe_block_gas_limit),
@@ -265,6 +267,8 @@ where
             NoOpTransactionCommitHook<AptosTransactionOutput, VMStatus>,
         >(
             Arc::clone(&RAYON_EXEC_POOL),
+            None,
+            None,
             transactions,
             self.state_view.as_ref(),
             BlockExecutorConfig::new_maybe_block_limit(
@@ -1,12 +1,15 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::resolver::{ExecutorView, ResourceGroupSize};
+use crate::{
+    resolver::{ExecutorView, ResourceGroupSize},
+    resource_group_adapter::group_tagged_resource_size,
+};
 use aptos_types::{
     state_store::{state_key::StateKey, state_value::StateValueMetadata},
     write_set::{TransactionWrite, WriteOp, WriteOpSize},
 };
-use move_binary_format::errors::PartialVMResult;
+use move_binary_format::errors::{PartialVMError, PartialVMResult};
 use move_core_types::{language_storage::StructTag, value::MoveTypeLayout};
 use std::{collections::BTreeMap, sync::Arc};
 
@@ -40,6 +43,17 @@ impl AbstractResourceWriteOp {
         }
     }
 
+    /// The size used for IO gas charging. If per_tag_group_gas is set, resource group writes
+    /// are charged for the written members only, see GroupWrite::written_members_size.
+    pub fn io_gas_size(&self, per_tag_group_gas: bool) -> PartialVMResult<WriteOpSize> {
+        match self {
+            AbstractResourceWriteOp::WriteResourceGroup(group_write) if per_tag_group_gas => {
+                group_write.written_members_size()
+            },
+            _ => Ok(self.materialized_size()),
+        }
+    }
+
     pub fn materialized_size(&self) -> WriteOpSize {
         use AbstractResourceWriteOp::*;
         match self {
@@ -214,6 +228,26 @@ impl GroupWrite {
         self.prev_group_size
     }
 
+    /// The size of the written members of the group (and of their tags), computed the same
+    /// way as the size of the whole group, but ignoring the members that are not written.
+    /// Deleted members only account for their tags.
+    pub fn written_members_size(&self) -> PartialVMResult<WriteOpSize> {
+        let write_len = self
+            .inner_ops
+            .iter()
+            .try_fold(0, |len, (tag, (op, _layout))| {
+                let member_len = op.bytes().map_or(0, |bytes| bytes.len());
+                Ok::<u64, PartialVMError>(len + group_tagged_resource_size(tag, member_len)?)
+            })?;
+
+        use WriteOp::*;
+        Ok(match &self.metadata_op {
+            Creation { .. } => WriteOpSize::Creation { write_len },
+            Modification { .. } => WriteOpSize::Modification { write_len },
+            Deletion { .. } => WriteOpSize::Deletion,
+        })
+    }
+
     pub fn metadata_op(&self) -> &WriteOp {
         &self.metadata_op
     }
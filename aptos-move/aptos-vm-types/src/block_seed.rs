@@ -0,0 +1,73 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A deterministic seed for the block being executed, derived from its id.
+//!
+//! Probabilistic internal checks (e.g. [`randomly_check_layout_matches`]) sample with it rather
+//! than with a thread-local RNG, so which checks run only depends on the block and on the order
+//! its transactions are executed in by each thread (e.g. it is the same when the block is
+//! replayed sequentially). Test-only natives can read it as well.
+//!
+//! The seed is passed with the config of each block execution, and is only visible to the code
+//! run by that execution: the block executor enters it with [`with_block_seed`] on every thread
+//! executing the block, for the duration of its work.
+//!
+//! [`randomly_check_layout_matches`]: crate::change_set::randomly_check_layout_matches
+
+use aptos_crypto::HashValue;
+use rand::Rng;
+use std::cell::Cell;
+
+thread_local! {
+    static BLOCK_SEED: Cell<Option<[u8; 32]>> = Cell::new(None);
+    /// The number of samples taken by the current thread with the current seed.
+    static NUM_SAMPLES: Cell<u64> = Cell::new(0);
+}
+
+/// Restores the seed (and the number of samples) the thread had before [`with_block_seed`],
+/// also if the closure panics.
+struct RestoreBlockSeed(Option<[u8; 32]>, u64);
+
+impl Drop for RestoreBlockSeed {
+    fn drop(&mut self) {
+        BLOCK_SEED.with(|block_seed| block_seed.set(self.0));
+        NUM_SAMPLES.with(|num_samples| num_samples.set(self.1));
+    }
+}
+
+/// Runs the closure on the current thread with the given seed (or without one, for `None`), and
+/// restores the previous seed of the thread afterwards.
+pub fn with_block_seed<R>(seed: Option<[u8; 32]>, f: impl FnOnce() -> R) -> R {
+    let _restore = RestoreBlockSeed(
+        BLOCK_SEED.with(|block_seed| block_seed.replace(seed)),
+        NUM_SAMPLES.with(|num_samples| num_samples.replace(0)),
+    );
+    f()
+}
+
+/// Returns the seed of the block executed by the current thread, if any.
+pub fn block_seed() -> Option<[u8; 32]> {
+    BLOCK_SEED.with(Cell::get)
+}
+
+/// Returns a number in `[0, bound)`. Within a block it is derived from the hash of the block
+/// seed and of the number of samples the thread took before with it, so that successive calls
+/// sample independently; outside of block execution it is random.
+pub fn sample_below(bound: u32) -> u32 {
+    match block_seed() {
+        Some(seed) => {
+            let num_samples = NUM_SAMPLES.with(|num_samples| {
+                let n = num_samples.get();
+                num_samples.set(n + 1);
+                n
+            });
+            let mut bytes = seed.to_vec();
+            bytes.extend_from_slice(&num_samples.to_le_bytes());
+            let hash = HashValue::sha3_256_of(&bytes);
+            let mut sample = [0u8; 4];
+            sample.copy_from_slice(&hash.as_ref()[..4]);
+            u32::from_le_bytes(sample) % bound
+        },
+        None => rand::thread_rng().gen_range(0, bound),
+    }
+}
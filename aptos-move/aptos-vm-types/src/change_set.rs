@@ -6,6 +6,7 @@ use crate::{
         AbstractResourceWriteOp, GroupWrite, InPlaceDelayedFieldChangeOp,
         ResourceGroupInPlaceDelayedFieldChangeOp, WriteWithDelayedFieldsOp,
     },
+    block_seed::sample_below,
     check_change_set::CheckChangeSet,
     resolver::ExecutorView,
 };
@@ -35,7 +36,6 @@ use move_core_types::{
     vm_status::StatusCode,
 };
 use move_vm_types::delayed_values::delayed_field_id::DelayedFieldID;
-use rand::Rng;
 use std::{
     collections::{
         btree_map::Entry::{Occupied, Vacant},
@@ -60,9 +60,9 @@ pub fn randomly_check_layout_matches(
         // Checking if 2 layouts are equal is a recursive operation and is expensive.
         // We generally call this `randomly_check_layout_matches` function when we know
         // that the layouts are supposed to match. As an optimization, we only randomly
-        // check if the layouts are matching.
-        let mut rng = rand::thread_rng();
-        let random_number: u32 = rng.gen_range(0, 100);
+        // check if the layouts are matching. The samples are derived from the block seed,
+        // so that a replay of the block checks the same layouts.
+        let random_number = sample_below(100);
         if random_number == 1 && layout_1 != layout_2 {
             return Err(code_invariant_error(format!(
                 "Layouts don't match when they are expected to: {:?} and {:?}",
@@ -358,6 +358,23 @@ impl VMChangeSet {
             )
     }
 
+    /// Like write_set_size_iter, but returns the sizes used for IO gas charging, see
+    /// AbstractResourceWriteOp::io_gas_size.
+    pub fn io_gas_write_set_size_iter(
+        &self,
+        per_tag_group_gas: bool,
+    ) -> impl Iterator<Item = (&StateKey, PartialVMResult<WriteOpSize>)> {
+        self.resource_write_set()
+            .iter()
+            .map(move |(k, v)| (k, v.io_gas_size(per_tag_group_gas)))
+            .chain(
+                self.module_write_set()
+                    .iter()
+                    .chain(self.aggregator_v1_write_set().iter())
+                    .map(|(k, v)| (k, Ok(v.write_op_size()))),
+            )
+    }
+
     pub fn num_write_ops(&self) -> usize {
         self.resource_write_set().len()
             + self.module_write_set().len()
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod abstract_write_op;
+pub mod block_seed;
 pub mod change_set;
 pub mod check_change_set;
 pub mod output;
@@ -3,6 +3,7 @@
 
 use aptos_aggregator::resolver::{TAggregatorV1View, TDelayedFieldView};
 use aptos_types::{
+    executable::KeyPrefix,
     serde_helper::bcs_utils::size_u32_as_uleb128,
     state_store::{
         errors::StateviewError,
@@ -17,7 +18,10 @@ use bytes::Bytes;
 use move_binary_format::errors::{PartialVMError, PartialVMResult};
 use move_core_types::{language_storage::StructTag, value::MoveTypeLayout, vm_status::StatusCode};
 use move_vm_types::delayed_values::delayed_field_id::DelayedFieldID;
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Bound,
+};
 
 /// Allows to query resources from the state.
 pub trait TResourceView {
@@ -64,6 +68,22 @@ pub trait TResourceView {
     }
 }
 
+/// Allows to read the keys of a prefix (e.g. the items of a table) by range, so that they can be
+/// iterated over during the execution of a block.
+pub trait TResourceRangeView {
+    type Key: KeyPrefix;
+
+    /// Returns the keys of the prefix in the range that were written (or deleted) by the
+    /// preceding transactions of the block, in order, each with whether it exists. The other
+    /// keys of the prefix in the range are as in storage, i.e. the range in the state is the
+    /// range in storage overlaid with the returned keys.
+    fn get_written_keys_in_range(
+        &self,
+        prefix: &<Self::Key as KeyPrefix>::Prefix,
+        range: &(Bound<Self::Key>, Bound<Self::Key>),
+    ) -> PartialVMResult<Vec<(Self::Key, bool)>>;
+}
+
 /// Metadata and exists queries for the resource group, determined by a key, must be resolved
 /// via TResourceView's corresponding interfaces w. key (get_resource_state_value_metadata &
 /// resource_exists). This simplifies interfaces for now, TODO: revisit later.
@@ -258,6 +278,22 @@ where
     }
 }
 
+impl<S> TResourceRangeView for S
+where
+    S: StateView,
+{
+    type Key = StateKey;
+
+    fn get_written_keys_in_range(
+        &self,
+        _prefix: &<StateKey as KeyPrefix>::Prefix,
+        _range: &(Bound<StateKey>, Bound<StateKey>),
+    ) -> PartialVMResult<Vec<(StateKey, bool)>> {
+        // The state view is the state in storage, no keys are written on top of it.
+        Ok(vec![])
+    }
+}
+
 impl<S> TModuleView for S
 where
     S: StateView,
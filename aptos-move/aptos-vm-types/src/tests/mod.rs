@@ -1,6 +1,8 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(test)]
+mod test_block_seed;
 #[cfg(test)]
 mod test_change_set;
 #[cfg(test)]
@@ -0,0 +1,51 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::block_seed::{block_seed, sample_below, with_block_seed};
+
+#[test]
+fn test_sample_below_is_deterministic_within_block() {
+    let mut seed = [0u8; 32];
+    seed[0] = 201;
+    let samples = || {
+        with_block_seed(Some(seed), || {
+            assert_eq!(block_seed(), Some(seed));
+            (0..100).map(|_| sample_below(100)).collect::<Vec<_>>()
+        })
+    };
+
+    // The samples are the same when the block is executed again.
+    let first_samples = samples();
+    assert_eq!(samples(), first_samples);
+    assert!(first_samples.iter().all(|sample| *sample < 100));
+
+    assert_eq!(block_seed(), None);
+    assert!(sample_below(100) < 100);
+}
+
+#[test]
+fn test_sample_below_differs_across_calls() {
+    with_block_seed(Some([7u8; 32]), || {
+        let first_sample = sample_below(1 << 31);
+        assert!((0..10).any(|_| sample_below(1 << 31) != first_sample));
+    });
+}
+
+#[test]
+fn test_block_seed_is_scoped() {
+    let outer = [1u8; 32];
+    let inner = [2u8; 32];
+    with_block_seed(Some(outer), || {
+        with_block_seed(Some(inner), || assert_eq!(block_seed(), Some(inner)));
+        assert_eq!(block_seed(), Some(outer));
+
+        // A block executed without a seed does not see the seed of the enclosing one.
+        with_block_seed(None, || assert_eq!(block_seed(), None));
+
+        // The seed is not visible to other threads.
+        std::thread::spawn(|| assert_eq!(block_seed(), None))
+            .join()
+            .unwrap();
+    });
+    assert_eq!(block_seed(), None);
+}
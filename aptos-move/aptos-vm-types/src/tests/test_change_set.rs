@@ -823,6 +823,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_group_write_io_gas_size() {
+        use crate::resource_group_adapter::group_tagged_resource_size;
+        use claims::assert_none;
+
+        let write = group_write(
+            write_op_with_metadata(MODIFICATION, 100),
+            vec![
+                (
+                    mock_tag_0(),
+                    (WriteOp::legacy_modification(vec![0; 10].into()), None),
+                ),
+                (mock_tag_1(), (WriteOp::legacy_deletion(), None)),
+            ],
+            1024,
+            45279432,
+        );
+        assert_eq!(
+            write.io_gas_size(false).unwrap().write_len(),
+            Some(45279432)
+        );
+        assert_eq!(
+            write.io_gas_size(true).unwrap().write_len(),
+            Some(
+                group_tagged_resource_size(&mock_tag_0(), 10).unwrap()
+                    + group_tagged_resource_size(&mock_tag_1(), 0).unwrap()
+            )
+        );
+
+        let deletion = group_write(write_op_with_metadata(DELETION, 100), vec![], 0, 0);
+        assert_none!(deletion.io_gas_size(true).unwrap().write_len());
+    }
+
     #[test]
     fn test_squash_groups_one_empty() {
         let key_1 = StateKey::raw(vec![1]);
@@ -7,7 +7,7 @@ use crate::{
     counters::*,
     data_cache::{AsMoveResolver, StorageAdapter},
     errors::{discarded_output, expect_only_successful_execution},
-    gas::{check_gas, get_gas_parameters},
+    gas::{check_gas, check_payload_limits, get_gas_parameters},
     keyless_validation,
     move_vm_ext::{
         get_max_binary_format_version, get_max_identifier_size,
@@ -20,7 +20,9 @@ use crate::{
     transaction_validation, verifier, VMExecutor, VMValidator,
 };
 use anyhow::anyhow;
-use aptos_block_executor::txn_commit_hook::NoOpTransactionCommitHook;
+use aptos_block_executor::{
+    hot_state_cache::HotStateCache, txn_commit_hook::NoOpTransactionCommitHook,
+};
 use aptos_crypto::HashValue;
 use aptos_framework::{
     natives::{code::PublishRequest, transaction_context::NativeTransactionContext},
@@ -36,9 +38,12 @@ use aptos_metrics_core::TimerHelper;
 use aptos_types::state_store::StateViewId;
 use aptos_types::{
     account_config,
-    account_config::{new_block_event_key, AccountResource},
+    account_config::{new_block_event_key, AccountResource, STAGED_CODE_ACTIVATION_MOVE_TYPE_TAG},
     block_executor::{
-        config::{BlockExecutorConfig, BlockExecutorConfigFromOnchain, BlockExecutorLocalConfig},
+        config::{
+            AdaptiveConcurrencyConfig, BlockExecutorConfig, BlockExecutorConfigFromOnchain,
+            BlockExecutorLocalConfig, CoreAffinityConfig, DependencyWaitConfig, FallbackPolicies,
+        },
         partitioner::PartitionedTransactions,
     },
     block_metadata::BlockMetadata,
@@ -51,9 +56,14 @@ use aptos_types::{
         TimedFeatureOverride, TimedFeatures, TimedFeaturesBuilder,
     },
     randomness::Randomness,
-    state_store::{StateView, TStateView},
+    state_store::{
+        state_key::StateKey,
+        state_read_stats::{StateReadStats, StateReadStatsView},
+        StateView, TStateView,
+    },
     transaction::{
-        authenticator::AnySignature, signature_verified_transaction::SignatureVerifiedTransaction,
+        authenticator_registry::{AuthenticatorSchemeError, DEFAULT_AUTHENTICATOR_REGISTRY},
+        signature_verified_transaction::SignatureVerifiedTransaction,
         BlockOutput, EntryFunction, ExecutionError, ExecutionStatus, ModuleBundle, Multisig,
         MultisigTransactionPayload, Script, SignatureCheckedTransaction, SignedTransaction,
         Transaction, TransactionAuxiliaryData, TransactionOutput, TransactionPayload,
@@ -62,7 +72,9 @@ use aptos_types::{
     vm_status::{AbortLocation, StatusCode, VMStatus},
 };
 use aptos_utils::{aptos_try, return_on_failure};
-use aptos_vm_logging::{log_schema::AdapterLogSchema, speculative_error, speculative_log};
+use aptos_vm_logging::{
+    log_schema::AdapterLogSchema, speculative_error, speculative_log, speculative_warn,
+};
 use aptos_vm_types::{
     abstract_write_op::AbstractResourceWriteOp,
     change_set::VMChangeSet,
@@ -97,10 +109,12 @@ use move_vm_types::gas::{GasMeter, UnmeteredGasMeter};
 use num_cpus;
 use once_cell::sync::{Lazy, OnceCell};
 use std::{
+    borrow::Cow,
     cmp::{max, min},
     collections::{BTreeMap, BTreeSet},
     marker::Sync,
     sync::Arc,
+    time::Duration,
 };
 
 static EXECUTION_CONCURRENCY_LEVEL: OnceCell<usize> = OnceCell::new();
@@ -108,9 +122,31 @@ static NUM_EXECUTION_SHARD: OnceCell<usize> = OnceCell::new();
 static NUM_PROOF_READING_THREADS: OnceCell<usize> = OnceCell::new();
 static PARANOID_TYPE_CHECKS: OnceCell<bool> = OnceCell::new();
 static DISCARD_FAILED_BLOCKS: OnceCell<bool> = OnceCell::new();
+static FALLBACK_POLICIES: OnceCell<FallbackPolicies> = OnceCell::new();
+static ADAPTIVE_CONCURRENCY: OnceCell<Option<AdaptiveConcurrencyConfig>> = OnceCell::new();
+static DEPENDENCY_WAIT: OnceCell<DependencyWaitConfig> = OnceCell::new();
+static EARLY_DELAYED_FIELD_VALIDATION: OnceCell<bool> = OnceCell::new();
+static PREFETCH_BASE_VALUES: OnceCell<bool> = OnceCell::new();
+static TXN_EXECUTION_TIMEOUT: OnceCell<Option<Duration>> = OnceCell::new();
+static MAX_INCARNATIONS: OnceCell<Option<u32>> = OnceCell::new();
+static BLOCK_TIME_BUDGET: OnceCell<Option<Duration>> = OnceCell::new();
+static MVHASHMAP_MEMORY_SOFT_CAP: OnceCell<Option<u64>> = OnceCell::new();
+static PRIORITIZE_BY_GAS_PRICE: OnceCell<bool> = OnceCell::new();
+static MATERIALIZATION_CONCURRENCY: OnceCell<usize> = OnceCell::new();
+static CORE_AFFINITY: OnceCell<Option<CoreAffinityConfig>> = OnceCell::new();
+static DIFFERENTIAL_EXECUTION: OnceCell<bool> = OnceCell::new();
+static HOT_STATE_CACHES: OnceCell<Option<HotStateCaches>> = OnceCell::new();
 static PROCESSED_TRANSACTIONS_DETAILED_COUNTERS: OnceCell<bool> = OnceCell::new();
 static TIMED_FEATURE_OVERRIDE: OnceCell<TimedFeatureOverride> = OnceCell::new();
 
+/// The caches of base values of the blocks executed by consensus (the block executor) and of the
+/// chunks executed by state sync (the chunk executor), so that the blocks of one executor do not
+/// clear the cache of the other (see HotStateCache::lease).
+struct HotStateCaches {
+    block_execution: Arc<HotStateCache<StateKey>>,
+    chunk_execution: Arc<HotStateCache<StateKey>>,
+}
+
 // TODO: Don't expose this in AptosVM, and use only in BlockAptosVM!
 pub static RAYON_EXEC_POOL: Lazy<Arc<rayon::ThreadPool>> = Lazy::new(|| {
     Arc::new(
@@ -122,6 +158,18 @@ pub static RAYON_EXEC_POOL: Lazy<Arc<rayon::ThreadPool>> = Lazy::new(|| {
     )
 });
 
+/// The thread pool dedicated to materializing the committed transactions of parallel execution,
+/// if the materialization concurrency is set (otherwise, it has a single idle thread).
+pub static RAYON_MATERIALIZATION_POOL: Lazy<Arc<rayon::ThreadPool>> = Lazy::new(|| {
+    Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(max(AptosVM::get_materialization_concurrency(), 1))
+            .thread_name(|index| format!("par_mat-{}", index))
+            .build()
+            .unwrap(),
+    )
+});
+
 macro_rules! deprecated_module_bundle {
     () => {
         VMStatus::error(
@@ -188,13 +236,48 @@ pub(crate) fn get_or_vm_startup_failure<'a, T>(
     })
 }
 
+/// Checks which can be bypassed when simulating a transaction, so that e.g. developer tooling
+/// can simulate transactions for accounts it does not control.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SimulationOptions {
+    /// Skips the check that the transaction has not expired.
+    pub ignore_expiration: bool,
+    /// Skips the checks that the gas payer can afford the transaction fee. The fee is still
+    /// metered and reported at the gas unit price of the transaction, but it is only deducted
+    /// from the balance of the gas payer if the balance covers it.
+    pub ignore_balance: bool,
+    /// Skips the checks that the authentication keys of the signers match the on-chain ones.
+    pub ignore_auth_key: bool,
+}
+
+impl SimulationOptions {
+    /// Returns the names of the checks which are bypassed with these options.
+    pub fn bypassed_checks(&self) -> Vec<&'static str> {
+        let mut checks = vec![];
+        if self.ignore_expiration {
+            checks.push("expiration");
+        }
+        if self.ignore_balance {
+            checks.push("balance");
+        }
+        if self.ignore_auth_key {
+            checks.push("auth_key");
+        }
+        checks
+    }
+}
+
 pub struct AptosVM {
     is_simulation: bool,
+    simulation_options: SimulationOptions,
     move_vm: MoveVmExt,
     gas_feature_version: u64,
     gas_params: Result<AptosGasParameters, String>,
     pub(crate) storage_gas_params: Result<StorageGasParameters, String>,
     timed_features: TimedFeatures,
+    /// The epoch of the state the VM executes on. It does not change within a block, as the
+    /// rest of the block is skipped after a reconfiguration.
+    epoch: u64,
 }
 
 impl AptosVM {
@@ -216,9 +299,12 @@ impl AptosVM {
         // If no chain ID is in storage, we assume we are in a testing environment and use ChainId::TESTING
         let chain_id = ChainId::fetch_config(resolver).unwrap_or_else(ChainId::test);
 
-        let timestamp = ConfigurationResource::fetch_config(resolver)
+        let configuration = ConfigurationResource::fetch_config(resolver);
+        let timestamp = configuration
+            .as_ref()
             .map(|config| config.last_reconfiguration_time())
             .unwrap_or(0);
+        let epoch = configuration.map(|config| config.epoch()).unwrap_or(0);
 
         let mut timed_features_builder = TimedFeaturesBuilder::new(chain_id, timestamp);
         if let Some(profile) = Self::get_timed_feature_override() {
@@ -248,11 +334,13 @@ impl AptosVM {
 
         Self {
             is_simulation: false,
+            simulation_options: SimulationOptions::default(),
             move_vm,
             gas_feature_version,
             gas_params,
             storage_gas_params,
             timed_features,
+            epoch,
         }
     }
 
@@ -328,6 +416,195 @@ impl AptosVM {
         }
     }
 
+    /// Sets the fallback policies of failed parallel executions when invoked the first time.
+    pub fn set_fallback_policies_once(policies: FallbackPolicies) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        FALLBACK_POLICIES.set(policies).ok();
+    }
+
+    /// Get the fallback policies if already set, otherwise return default (sequential fallback)
+    pub fn get_fallback_policies() -> FallbackPolicies {
+        FALLBACK_POLICIES.get().copied().unwrap_or_default()
+    }
+
+    /// Sets the adaptive concurrency of parallel execution when invoked the first time.
+    pub fn set_adaptive_concurrency_once(config: Option<AdaptiveConcurrencyConfig>) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        ADAPTIVE_CONCURRENCY.set(config).ok();
+    }
+
+    /// Get the adaptive concurrency config if already set, otherwise return default (None)
+    pub fn get_adaptive_concurrency() -> Option<AdaptiveConcurrencyConfig> {
+        ADAPTIVE_CONCURRENCY.get().copied().flatten()
+    }
+
+    /// Sets how parallel execution waits on dependencies when invoked the first time.
+    pub fn set_dependency_wait_once(config: DependencyWaitConfig) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        DEPENDENCY_WAIT.set(config).ok();
+    }
+
+    /// Get the dependency wait config if already set, otherwise return default (blocking waits)
+    pub fn get_dependency_wait() -> DependencyWaitConfig {
+        DEPENDENCY_WAIT.get().copied().unwrap_or_default()
+    }
+
+    /// Sets runtime config when invoked the first time.
+    pub fn set_early_delayed_field_validation(enable: bool) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        EARLY_DELAYED_FIELD_VALIDATION.set(enable).ok();
+    }
+
+    /// Get the early delayed field validation flag if already set, otherwise return default (false)
+    pub fn get_early_delayed_field_validation() -> bool {
+        match EARLY_DELAYED_FIELD_VALIDATION.get() {
+            Some(enable) => *enable,
+            None => false,
+        }
+    }
+
+    /// Sets runtime config when invoked the first time.
+    pub fn set_prefetch_base_values(enable: bool) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        PREFETCH_BASE_VALUES.set(enable).ok();
+    }
+
+    /// Get the base value prefetch flag if already set, otherwise return default (false)
+    pub fn get_prefetch_base_values() -> bool {
+        match PREFETCH_BASE_VALUES.get() {
+            Some(enable) => *enable,
+            None => false,
+        }
+    }
+
+    /// Sets runtime config when invoked the first time.
+    pub fn set_prioritize_by_gas_price(enable: bool) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        PRIORITIZE_BY_GAS_PRICE.set(enable).ok();
+    }
+
+    /// Get the gas price prioritization flag if already set, otherwise return default (false)
+    pub fn get_prioritize_by_gas_price() -> bool {
+        match PRIORITIZE_BY_GAS_PRICE.get() {
+            Some(enable) => *enable,
+            None => false,
+        }
+    }
+
+    /// Sets the number of workers dedicated to materializing the committed transactions of
+    /// parallel execution when invoked the first time.
+    pub fn set_materialization_concurrency_once(materialization_concurrency: usize) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        MATERIALIZATION_CONCURRENCY
+            .set(materialization_concurrency)
+            .ok();
+    }
+
+    /// Get the materialization concurrency if already set, otherwise return default (0, i.e.
+    /// the execution workers materialize the committed transactions)
+    pub fn get_materialization_concurrency() -> usize {
+        MATERIALIZATION_CONCURRENCY.get().copied().unwrap_or(0)
+    }
+
+    /// Sets the CPU cores that the workers of parallel execution are pinned to when invoked the
+    /// first time.
+    pub fn set_core_affinity_once(config: Option<CoreAffinityConfig>) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        CORE_AFFINITY.set(config).ok();
+    }
+
+    /// Get the core affinity config if already set, otherwise return default (None, i.e. the
+    /// workers are not pinned)
+    pub fn get_core_affinity() -> Option<CoreAffinityConfig> {
+        CORE_AFFINITY.get().cloned().flatten()
+    }
+
+    /// Sets runtime config when invoked the first time.
+    pub fn set_differential_execution(enable: bool) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        DIFFERENTIAL_EXECUTION.set(enable).ok();
+    }
+
+    /// Get the differential execution flag if already set, otherwise return default (false)
+    pub fn get_differential_execution() -> bool {
+        DIFFERENTIAL_EXECUTION.get().copied().unwrap_or(false)
+    }
+
+    /// Creates the caches of base values shared across the executed blocks, each with the given
+    /// capacity (no caches if 0), when invoked the first time.
+    pub fn set_hot_state_cache_capacity_once(capacity: usize) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        HOT_STATE_CACHES
+            .set((capacity > 0).then(|| HotStateCaches {
+                block_execution: Arc::new(HotStateCache::new(capacity)),
+                chunk_execution: Arc::new(HotStateCache::new(capacity)),
+            }))
+            .ok();
+    }
+
+    /// Get the cache of base values shared across the blocks executed by the same executor as
+    /// the block executed against a state view with the given id (i.e. by the block executor or
+    /// by the chunk executor) if already set, otherwise return default (None, i.e. the base
+    /// values are read from the state view)
+    pub fn get_hot_state_cache(state_view_id: StateViewId) -> Option<Arc<HotStateCache<StateKey>>> {
+        let caches = HOT_STATE_CACHES.get()?.as_ref()?;
+        match state_view_id {
+            StateViewId::BlockExecution { .. } => Some(caches.block_execution.clone()),
+            StateViewId::ChunkExecution { .. } => Some(caches.chunk_execution.clone()),
+            StateViewId::TransactionValidation { .. } | StateViewId::Miscellaneous => None,
+        }
+    }
+
+    /// Sets the per-transaction execution timeout of the block executor when invoked the first
+    /// time.
+    pub fn set_txn_execution_timeout_once(timeout: Option<Duration>) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        TXN_EXECUTION_TIMEOUT.set(timeout).ok();
+    }
+
+    /// Get the per-transaction execution timeout if already set, otherwise return default (None)
+    pub fn get_txn_execution_timeout() -> Option<Duration> {
+        TXN_EXECUTION_TIMEOUT.get().copied().flatten()
+    }
+
+    /// Sets the number of incarnations after which parallel execution stops speculating on a
+    /// transaction when invoked the first time.
+    pub fn set_max_incarnations_once(max_incarnations: Option<u32>) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        MAX_INCARNATIONS.set(max_incarnations).ok();
+    }
+
+    /// Get the maximum number of incarnations per transaction if already set, otherwise return
+    /// default (None, i.e. unlimited)
+    pub fn get_max_incarnations() -> Option<u32> {
+        MAX_INCARNATIONS.get().copied().flatten()
+    }
+
+    /// Sets the wall-clock time budget of committing a block when invoked the first time.
+    pub fn set_block_time_budget_once(budget: Option<Duration>) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        BLOCK_TIME_BUDGET.set(budget).ok();
+    }
+
+    /// Get the wall-clock time budget of committing a block if already set, otherwise return
+    /// default (None, i.e. no budget)
+    pub fn get_block_time_budget() -> Option<Duration> {
+        BLOCK_TIME_BUDGET.get().copied().flatten()
+    }
+
+    /// Sets the soft cap on the memory of the multi-version data-structure of a block when
+    /// invoked the first time.
+    pub fn set_mvhashmap_memory_soft_cap_once(soft_cap: Option<u64>) {
+        // Only the first call succeeds, due to OnceCell semantics.
+        MVHASHMAP_MEMORY_SOFT_CAP.set(soft_cap).ok();
+    }
+
+    /// Get the soft cap on the memory of the multi-version data-structure of a block if already
+    /// set, otherwise return default (None, i.e. no cap)
+    pub fn get_mvhashmap_memory_soft_cap() -> Option<u64> {
+        MVHASHMAP_MEMORY_SOFT_CAP.get().copied().flatten()
+    }
+
     // Set the override profile for timed features.
     pub fn set_timed_feature_override(profile: TimedFeatureOverride) {
         TIMED_FEATURE_OVERRIDE.set(profile).ok();
@@ -593,6 +870,7 @@ impl AptosVM {
                 ZERO_STORAGE_REFUND.into(),
             )?;
             respawned_session.execute(|session| {
+                let txn_data = &self.epilogue_txn_data(session, txn_data, gas_meter.balance());
                 transaction_validation::run_failure_epilogue(
                     session,
                     gas_meter.balance(),
@@ -611,6 +889,7 @@ impl AptosVM {
 
             let fee_statement =
                 AptosVM::fee_statement_from_gas_meter(txn_data, gas_meter, ZERO_STORAGE_REFUND);
+            let txn_data = &self.epilogue_txn_data(&mut session, txn_data, gas_meter.balance());
             transaction_validation::run_failure_epilogue(
                 &mut session,
                 gas_meter.balance(),
@@ -655,6 +934,7 @@ impl AptosVM {
             u64::from(respawned_session.get_storage_fee_refund()),
         );
         respawned_session.execute(|session| {
+            let txn_data = &self.epilogue_txn_data(session, txn_data, gas_meter.balance());
             transaction_validation::run_success_epilogue(
                 session,
                 gas_meter.balance(),
@@ -816,6 +1096,7 @@ impl AptosVM {
         };
 
         self.resolve_pending_code_publish(
+            resolver,
             &mut session,
             gas_meter,
             traversal_context,
@@ -846,7 +1127,9 @@ impl AptosVM {
         txn_data: &TransactionMetadata,
         resolver: &impl AptosMoveResolver,
     ) -> Result<GasQuantity<Octa>, VMStatus> {
-        for (key, op_size) in change_set.write_set_size_iter() {
+        let per_tag_group_gas = self.features().is_resource_group_per_tag_gas_enabled();
+        for (key, op_size) in change_set.io_gas_write_set_size_iter(per_tag_group_gas) {
+            let op_size = op_size.map_err(|e| e.finish(Location::Undefined))?;
             gas_meter.charge_io_gas_for_write(key, &op_size)?;
         }
 
@@ -899,6 +1182,7 @@ impl AptosVM {
                     MultisigTransactionPayload::EntryFunction(entry_function) => {
                         aptos_try!({
                             return_on_failure!(self.execute_multisig_entry_function(
+                                resolver,
                                 &mut session,
                                 gas_meter,
                                 traversal_context,
@@ -1016,6 +1300,7 @@ impl AptosVM {
         let execution_result = match payload {
             MultisigTransactionPayload::EntryFunction(entry_function) => self
                 .execute_multisig_entry_function(
+                    resolver,
                     &mut session,
                     gas_meter,
                     traversal_context,
@@ -1111,6 +1396,7 @@ impl AptosVM {
 
     fn execute_multisig_entry_function(
         &self,
+        resolver: &impl AptosMoveResolver,
         session: &mut SessionExt,
         gas_meter: &mut impl AptosGasMeter,
         traversal_context: &mut TraversalContext,
@@ -1131,6 +1417,7 @@ impl AptosVM {
         // Resolve any pending module publishes in case the multisig transaction is deploying
         // modules.
         self.resolve_pending_code_publish(
+            resolver,
             session,
             gas_meter,
             traversal_context,
@@ -1279,6 +1566,7 @@ impl AptosVM {
     /// Resolve a pending code publish request registered via the NativeCodeContext.
     fn resolve_pending_code_publish(
         &self,
+        resolver: &impl AptosMoveResolver,
         session: &mut SessionExt,
         gas_meter: &mut impl AptosGasMeter,
         traversal_context: &mut TraversalContext,
@@ -1363,6 +1651,21 @@ impl AptosVM {
                 }
             }
 
+            // With staged module publishing, upgrades only become loadable at the next epoch.
+            // Bundles with new modules are still published right away: new modules cannot have
+            // been used yet, and they may need to be initialized by the transaction.
+            if self.features().is_staged_module_publishing_enabled()
+                && exists.len() == modules.len()
+            {
+                return self.stage_module_bundle(
+                    resolver,
+                    session,
+                    gas_meter,
+                    bundle.into_inner(),
+                    destination,
+                );
+            }
+
             // Publish the bundle and execute initializers
             // publish_module_bundle doesn't actually load the published module into
             // the loader cache. It only puts the module data in the data cache.
@@ -1370,13 +1673,7 @@ impl AptosVM {
                 bundle.into_inner(),
                 destination,
                 gas_meter,
-                Compatibility::new(
-                    true,
-                    true,
-                    !self
-                        .features()
-                        .is_enabled(FeatureFlag::TREAT_FRIEND_AS_PRIVATE),
-                ),
+                self.publish_compatibility(),
             ));
 
             self.execute_module_initialization(
@@ -1392,6 +1689,143 @@ impl AptosVM {
         }
     }
 
+    /// The compatibility checks of the modules upgraded by a publish.
+    fn publish_compatibility(&self) -> Compatibility {
+        Compatibility::new(
+            true,
+            true,
+            !self
+                .features()
+                .is_enabled(FeatureFlag::TREAT_FRIEND_AS_PRIVATE),
+        )
+    }
+
+    /// Stages an upgrade of existing modules until the next epoch, instead of publishing it. The
+    /// upgrade is verified against the code on chain in a separate session, which is discarded so
+    /// that the transaction does not write the modules. This way, the code of a module never
+    /// changes in the middle of a block, and the block executor does not need to fall back to
+    /// sequential execution when a block both upgrades and uses a module. The staged bundle is
+    /// published in the first block of the next epoch, see [`AptosVM::activate_staged_code`].
+    fn stage_module_bundle(
+        &self,
+        resolver: &impl AptosMoveResolver,
+        session: &mut SessionExt,
+        gas_meter: &mut impl AptosGasMeter,
+        code: Vec<Vec<u8>>,
+        destination: AccountAddress,
+    ) -> VMResult<()> {
+        let mut verification_session = self.new_session(resolver, SessionId::void());
+        return_on_failure!(
+            verification_session.publish_module_bundle_with_compat_config(
+                code.clone(),
+                destination,
+                gas_meter,
+                self.publish_compatibility(),
+            )
+        );
+
+        let args = serialize_values(&vec![
+            MoveValue::Signer(account_config::CORE_CODE_ADDRESS),
+            MoveValue::Address(destination),
+            MoveValue::U64(self.epoch + 1),
+            MoveValue::Vector(code.into_iter().map(MoveValue::vector_u8).collect()),
+        ]);
+        session
+            .execute_function_bypass_visibility(&CODE_MODULE, STAGE_CODE, vec![], args, gas_meter)
+            .map(|_return_vals| ())
+    }
+
+    /// Publishes the module upgrades that were staged until the current epoch (see
+    /// [`AptosVM::stage_module_bundle`]), in the block prologue. The upgrades are verified again,
+    /// as the code on chain may have changed since they were staged, and the upgrades that are
+    /// no longer compatible are dropped. An activation event is emitted for every staged bundle,
+    /// which ends the block like a reconfiguration does (see
+    /// [`AptosVM::should_restart_execution`]), so no other transaction of the block runs with
+    /// the code of before the activation.
+    fn activate_staged_code(
+        &self,
+        session: &mut SessionExt,
+        log_context: &AdapterLogSchema,
+    ) -> Result<(), VMStatus> {
+        if !self.features().is_staged_module_publishing_enabled() {
+            return Ok(());
+        }
+
+        let mut gas_meter = UnmeteredGasMeter;
+        let args = serialize_values(&vec![
+            MoveValue::Signer(account_config::CORE_CODE_ADDRESS),
+            MoveValue::U64(self.epoch),
+        ]);
+        let mut return_values = vec![];
+        session
+            .execute_function_bypass_visibility(
+                &CODE_MODULE,
+                TAKE_STAGED_CODE,
+                vec![],
+                args,
+                &mut gas_meter,
+            )
+            .map(|values| return_values = values.return_values)
+            .or_else(|e| {
+                expect_only_successful_execution(e, TAKE_STAGED_CODE.as_str(), log_context)
+            })?;
+        let staged_code = match return_values.as_slice() {
+            [(publishers, _), (codes, _)] => bcs::from_bytes::<Vec<AccountAddress>>(publishers)
+                .ok()
+                .zip(bcs::from_bytes::<Vec<Vec<Vec<u8>>>>(codes).ok()),
+            _ => None,
+        };
+        let (publishers, codes) = staged_code.ok_or_else(|| {
+            VMStatus::error(
+                StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+                Some("Unexpected return values of the staged code".to_string()),
+            )
+        })?;
+
+        for (publisher, code) in publishers.into_iter().zip(codes) {
+            let activated = match session.publish_module_bundle_with_compat_config(
+                code,
+                publisher,
+                &mut gas_meter,
+                self.publish_compatibility(),
+            ) {
+                Ok(()) => true,
+                Err(err) => {
+                    speculative_warn!(
+                        log_context,
+                        format!(
+                            "[aptos_vm] Dropping the staged code of {}: {:?}",
+                            publisher, err
+                        )
+                    );
+                    false
+                },
+            };
+            let args = serialize_values(&vec![
+                MoveValue::Signer(account_config::CORE_CODE_ADDRESS),
+                MoveValue::Address(publisher),
+                MoveValue::Bool(activated),
+            ]);
+            session
+                .execute_function_bypass_visibility(
+                    &CODE_MODULE,
+                    EMIT_STAGED_CODE_ACTIVATION,
+                    vec![],
+                    args,
+                    &mut gas_meter,
+                )
+                .map(|_return_vals| ())
+                .or_else(|e| {
+                    expect_only_successful_execution(
+                        e,
+                        EMIT_STAGED_CODE_ACTIVATION.as_str(),
+                        log_context,
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
     /// Validate a publish request.
     fn validate_publish_request(
         &self,
@@ -1887,6 +2321,7 @@ impl AptosVM {
             .or_else(|e| {
                 expect_only_successful_execution(e, BLOCK_PROLOGUE.as_str(), log_context)
             })?;
+        self.activate_staged_code(&mut session, log_context)?;
         SYSTEM_TRANSACTIONS_EXECUTED.inc();
 
         let output = get_system_transaction_output(
@@ -1962,6 +2397,7 @@ impl AptosVM {
             .or_else(|e| {
                 expect_only_successful_execution(e, BLOCK_PROLOGUE_EXT.as_str(), log_context)
             })?;
+        self.activate_staged_code(&mut session, log_context)?;
         SYSTEM_TRANSACTIONS_EXECUTED.inc();
 
         let output = get_system_transaction_output(
@@ -2067,15 +2503,18 @@ impl AptosVM {
         txn_data: &TransactionMetadata,
         log_context: &AdapterLogSchema,
     ) -> Result<(), VMStatus> {
+        let gas_params = get_or_vm_startup_failure(&self.gas_params, log_context)?;
         check_gas(
-            get_or_vm_startup_failure(&self.gas_params, log_context)?,
+            gas_params,
             self.gas_feature_version,
             resolver,
             txn_data,
             self.features(),
             log_context,
         )?;
+        check_payload_limits(gas_params, self.gas_feature_version, payload, log_context)?;
 
+        let txn_data = &self.prologue_txn_data(resolver, txn_data)?;
         match payload {
             TransactionPayload::Script(_) | TransactionPayload::EntryFunction(_) => {
                 transaction_validation::run_script_prologue(session, txn_data, log_context)
@@ -2105,12 +2544,93 @@ impl AptosVM {
         }
     }
 
+    /// Returns the transaction metadata to run the prologue with. When simulating, the checks
+    /// which should be bypassed are neutralized by overriding the corresponding inputs.
+    fn prologue_txn_data<'a>(
+        &self,
+        resolver: &impl AptosMoveResolver,
+        txn_data: &'a TransactionMetadata,
+    ) -> Result<Cow<'a, TransactionMetadata>, VMStatus> {
+        let options = &self.simulation_options;
+        if !self.is_simulation
+            || !(options.ignore_expiration || options.ignore_balance || options.ignore_auth_key)
+        {
+            return Ok(Cow::Borrowed(txn_data));
+        }
+
+        let mut txn_data = txn_data.clone();
+        if options.ignore_expiration {
+            txn_data.expiration_timestamp_secs = u64::MAX;
+        }
+        if options.ignore_balance {
+            // The prologue only checks that the maximum fee is affordable, it does not charge it.
+            txn_data.gas_unit_price = 0.into();
+        }
+        if options.ignore_auth_key {
+            if let Some(auth_key) = get_authentication_key_on_chain(resolver, &txn_data.sender)? {
+                txn_data.authentication_key = auth_key;
+            }
+            for (signer, auth_key) in txn_data
+                .secondary_signers
+                .iter()
+                .zip(txn_data.secondary_authentication_keys.iter_mut())
+            {
+                if let Some(on_chain_auth_key) = get_authentication_key_on_chain(resolver, signer)?
+                {
+                    *auth_key = on_chain_auth_key;
+                }
+            }
+            if let (Some(fee_payer), Some(auth_key)) = (
+                txn_data.fee_payer,
+                txn_data.fee_payer_authentication_key.as_mut(),
+            ) {
+                if let Some(on_chain_auth_key) =
+                    get_authentication_key_on_chain(resolver, &fee_payer)?
+                {
+                    *auth_key = on_chain_auth_key;
+                }
+            }
+        }
+        Ok(Cow::Owned(txn_data))
+    }
+
+    /// Returns the transaction metadata to run the epilogue with. When simulating with the
+    /// balance checks bypassed and the gas payer cannot afford the fee, the gas unit price is set
+    /// to zero, so that the fee is not deducted from the balance (and no funds are created to
+    /// cover it). The fee statement is still computed from the gas meter at the original price.
+    fn epilogue_txn_data<'a>(
+        &self,
+        session: &mut SessionExt,
+        txn_data: &'a TransactionMetadata,
+        gas_remaining: Gas,
+    ) -> Cow<'a, TransactionMetadata> {
+        if !self.is_simulation || !self.simulation_options.ignore_balance {
+            return Cow::Borrowed(txn_data);
+        }
+
+        let gas_used = txn_data.max_gas_amount().checked_sub(gas_remaining);
+        let fee = gas_used.map(|gas_used| {
+            u64::from(txn_data.gas_unit_price()).saturating_mul(u64::from(gas_used))
+        });
+        let balance = transaction_validation::gas_payer_balance(session, txn_data);
+        match (fee, balance) {
+            (Some(fee), Some(balance)) if fee <= balance => Cow::Borrowed(txn_data),
+            _ => {
+                let mut txn_data = txn_data.clone();
+                txn_data.gas_unit_price = 0.into();
+                Cow::Owned(txn_data)
+            },
+        }
+    }
+
+    /// Whether the rest of the block must be skipped after the transaction with the given change
+    /// set, i.e. after a reconfiguration, or after staged code was activated.
     pub fn should_restart_execution(vm_change_set: &VMChangeSet) -> bool {
         let new_epoch_event_key = new_epoch_event_key();
-        vm_change_set
-            .events()
-            .iter()
-            .any(|(event, _)| event.event_key() == Some(&new_epoch_event_key))
+        vm_change_set.events().iter().any(|(event, _)| {
+            event.event_key() == Some(&new_epoch_event_key)
+                || event.type_tag() == &*STAGED_CODE_ACTIVATION_MOVE_TYPE_TAG
+        })
     }
 
     /// Executes a single transaction (including user transactions, block
@@ -2273,6 +2793,9 @@ impl VMExecutor for AptosVM {
             NoOpTransactionCommitHook<AptosTransactionOutput, VMStatus>,
         >(
             Arc::clone(&RAYON_EXEC_POOL),
+            (Self::get_materialization_concurrency() > 0)
+                .then(|| Arc::clone(&RAYON_MATERIALIZATION_POOL)),
+            Self::get_hot_state_cache(state_view.id()),
             transactions,
             state_view,
             BlockExecutorConfig {
@@ -2280,6 +2803,30 @@ impl VMExecutor for AptosVM {
                     concurrency_level: Self::get_concurrency_level(),
                     allow_fallback: true,
                     discard_failed_blocks: Self::get_discard_failed_blocks(),
+                    fallback_policies: Self::get_fallback_policies(),
+                    adaptive_concurrency: Self::get_adaptive_concurrency(),
+                    early_delayed_field_validation: Self::get_early_delayed_field_validation(),
+                    prefetch_base_values: Self::get_prefetch_base_values(),
+                    record_conflict_graph: false,
+                    record_schedule: false,
+                    txn_execution_timeout: Self::get_txn_execution_timeout(),
+                    prioritize_by_gas_price: Self::get_prioritize_by_gas_price(),
+                    dependency_wait: Self::get_dependency_wait(),
+                    early_release: false,
+                    materialization_concurrency: Self::get_materialization_concurrency(),
+                    core_affinity: Self::get_core_affinity(),
+                    differential_execution: Self::get_differential_execution(),
+                    retain_block_state: false,
+                    max_incarnations: Self::get_max_incarnations(),
+                    block_time_budget: Self::get_block_time_budget(),
+                    parallel_single_worker: false,
+                    profile_transactions: false,
+                    validate_module_reads: false,
+                    soft_delayed_field_validation_failures: false,
+                    mvhashmap_memory_soft_cap: Self::get_mvhashmap_memory_soft_cap(),
+                    record_read_stats: false,
+                    block_seed: None,
+                    discarded_block_bookkeeping: false,
                 },
                 onchain: onchain_config,
             },
@@ -2350,24 +2897,13 @@ impl VMValidator for AptosVM {
             }
         }
 
-        if !self.features().is_enabled(FeatureFlag::WEBAUTHN_SIGNATURE) {
-            if let Ok(sk_authenticators) = transaction
-                .authenticator_ref()
-                .to_single_key_authenticators()
-            {
-                for authenticator in sk_authenticators {
-                    if let AnySignature::WebAuthn { .. } = authenticator.signature() {
-                        return VMValidatorResult::error(StatusCode::FEATURE_UNDER_GATING);
-                    }
-                }
-            } else {
-                return VMValidatorResult::error(StatusCode::INVALID_SIGNATURE);
-            }
-        }
-
-        let txn = match transaction.check_signature() {
+        // The signature schemes (and their feature gates) are checked by the registry.
+        let txn = match DEFAULT_AUTHENTICATOR_REGISTRY.verify(transaction, self.features()) {
             Ok(t) => t,
-            _ => {
+            Err(AuthenticatorSchemeError::Disabled(..)) => {
+                return VMValidatorResult::error(StatusCode::FEATURE_UNDER_GATING);
+            },
+            Err(_) => {
                 return VMValidatorResult::error(StatusCode::INVALID_SIGNATURE);
             },
         };
@@ -2407,11 +2943,19 @@ pub struct AptosSimulationVM(AptosVM);
 
 impl AptosSimulationVM {
     pub fn new(resolver: &impl AptosMoveResolver) -> Self {
+        Self::new_with_options(resolver, SimulationOptions::default())
+    }
+
+    pub fn new_with_options(
+        resolver: &impl AptosMoveResolver,
+        simulation_options: SimulationOptions,
+    ) -> Self {
         let mut vm = AptosVM::new(
             resolver,
             /*override_is_delayed_field_optimization_capable=*/ Some(false),
         );
         vm.is_simulation = true;
+        vm.simulation_options = simulation_options;
         Self(vm)
     }
 
@@ -2422,21 +2966,42 @@ impl AptosSimulationVM {
         transaction: &SignedTransaction,
         state_view: &impl StateView,
     ) -> (VMStatus, TransactionOutput) {
+        let (vm_status, txn_output, _read_stats) =
+            Self::create_vm_and_simulate_signed_transaction_with_options(
+                transaction,
+                state_view,
+                SimulationOptions::default(),
+            );
+        (vm_status, txn_output)
+    }
+
+    /// Same as `create_vm_and_simulate_signed_transaction`, but additionally bypasses the
+    /// checks specified by the simulation options, and returns the state reads of the
+    /// transaction (not counting the reads of the on-chain configs to create the VM).
+    /// *Precondition:* the transaction must **not** have a valid signature.
+    pub fn create_vm_and_simulate_signed_transaction_with_options(
+        transaction: &SignedTransaction,
+        state_view: &impl StateView,
+        simulation_options: SimulationOptions,
+    ) -> (VMStatus, TransactionOutput, StateReadStats) {
         assert_err!(
             transaction.verify_signature(),
             "Simulated transaction should not have a valid signature"
         );
 
         let resolver = state_view.as_move_resolver();
-        let vm = Self::new(&resolver);
+        let vm = Self::new_with_options(&resolver, simulation_options);
         let log_context = AdapterLogSchema::new(state_view.id(), 0);
 
+        let read_stats_view = StateReadStatsView::new(state_view);
+        let resolver = read_stats_view.as_move_resolver();
+        read_stats_view.reset_stats();
         let (vm_status, vm_output) =
             vm.0.execute_user_transaction(&resolver, transaction, &log_context);
         let txn_output = vm_output
             .try_materialize_into_transaction_output(&resolver)
             .expect("Materializing aggregator V1 deltas should never fail");
-        (vm_status, txn_output)
+        (vm_status, txn_output, read_stats_view.stats())
     }
 }
 
@@ -2482,6 +3047,22 @@ pub(crate) fn is_account_init_for_sponsored_transaction(
     )
 }
 
+fn get_authentication_key_on_chain(
+    resolver: &impl AptosMoveResolver,
+    address: &AccountAddress,
+) -> Result<Option<Vec<u8>>, VMStatus> {
+    let bytes = resolver
+        .get_resource(address, &AccountResource::struct_tag())
+        .map_err(|e| e.finish(Location::Undefined).into_vm_status())?;
+    bytes
+        .map(|bytes| {
+            bcs::from_bytes::<AccountResource>(&bytes)
+                .map(|account| account.authentication_key().to_vec())
+                .map_err(|_| VMStatus::error(StatusCode::VALUE_DESERIALIZATION_ERROR, None))
+        })
+        .transpose()
+}
+
 #[test]
 fn vm_thread_safe() {
     fn assert_send<T: Send>() {}
@@ -12,10 +12,15 @@ use aptos_aggregator::{
     delayed_change::DelayedChange, delta_change_set::DeltaOp, resolver::TAggregatorV1View,
 };
 use aptos_block_executor::{
-    errors::BlockExecutionError, executor::BlockExecutor,
+    errors::{BlockExecutionError, BlockExecutionFailure},
+    execution_stats::BlockExecutionStats,
+    executor::BlockExecutor,
+    hot_state_cache::{HotStateCache, HotStateId},
     task::TransactionOutput as BlockExecutorTransactionOutput,
-    txn_commit_hook::TransactionCommitHook, types::InputOutputKey,
+    txn_commit_hook::TransactionCommitHook,
+    types::InputOutputKey,
 };
+use aptos_crypto::HashValue;
 use aptos_infallible::Mutex;
 use aptos_types::{
     block_executor::config::BlockExecutorConfig,
@@ -23,10 +28,11 @@ use aptos_types::{
     delayed_fields::PanicError,
     executable::ExecutableTestType,
     fee_statement::FeeStatement,
+    on_chain_config::{Features, OnChainConfig},
     state_store::{state_key::StateKey, state_value::StateValueMetadata, StateView, StateViewId},
     transaction::{
         signature_verified_transaction::SignatureVerifiedTransaction, BlockOutput,
-        TransactionOutput, TransactionStatus,
+        TransactionOutput, TransactionStatus, Version,
     },
     write_set::WriteOp,
 };
@@ -389,6 +395,127 @@ impl BlockExecutorTransactionOutput for AptosTransactionOutput {
 
         writes
     }
+
+    fn committed_output_divergence(&self, other: &Self) -> Option<String> {
+        let (output, other_output) =
+            match (self.committed_output.get(), other.committed_output.get()) {
+                (Some(output), Some(other_output)) => (output, other_output),
+                (None, None) => return None,
+                (output, other_output) => {
+                    return Some(format!(
+                        "committed: {} != {}",
+                        output.is_some(),
+                        other_output.is_some()
+                    ))
+                },
+            };
+
+        if output.status() != other_output.status() {
+            return Some(format!(
+                "status: {:?} != {:?}",
+                output.status(),
+                other_output.status()
+            ));
+        }
+        if output.gas_used() != other_output.gas_used() {
+            return Some(format!(
+                "gas used: {} != {}",
+                output.gas_used(),
+                other_output.gas_used()
+            ));
+        }
+        for (state_key, write_op) in output.write_set() {
+            let other_write_op = other_output.write_set().get(state_key);
+            if other_write_op != Some(write_op) {
+                return Some(format!(
+                    "write of {:?}: {:?} != {:?}",
+                    state_key,
+                    Some(write_op),
+                    other_write_op
+                ));
+            }
+        }
+        if let Some((state_key, other_write_op)) = other_output
+            .write_set()
+            .iter()
+            .find(|(state_key, _)| output.write_set().get(state_key).is_none())
+        {
+            return Some(format!(
+                "write of {:?}: None != {:?}",
+                state_key,
+                Some(other_write_op)
+            ));
+        }
+        if output.events().len() != other_output.events().len() {
+            return Some(format!(
+                "number of events: {} != {}",
+                output.events().len(),
+                other_output.events().len()
+            ));
+        }
+        output
+            .events()
+            .iter()
+            .zip(other_output.events())
+            .enumerate()
+            .find(|(_, (event, other_event))| event != other_event)
+            .map(|(idx, (event, other_event))| {
+                format!("event {}: {:?} != {:?}", idx, event, other_event)
+            })
+    }
+
+    fn committed_write_keys(&self) -> Option<Vec<StateKey>> {
+        if let Some(output) = self.committed_output.get() {
+            return Some(
+                output
+                    .write_set()
+                    .iter()
+                    .map(|(key, _)| key.clone())
+                    .collect(),
+            );
+        }
+        // The outputs that were not committed (e.g. skipped after the block gas limit) have no
+        // writes, otherwise the written keys are considered unknown.
+        let vm_output = self.vm_output.lock();
+        let change_set = vm_output.as_ref()?.change_set();
+        (change_set.num_write_ops() == 0 && change_set.aggregator_v1_delta_set().is_empty())
+            .then(Vec::new)
+    }
+}
+
+/// Returns the deterministic seed of the block executed against a state view with the given id:
+/// the block id during block execution, and the hash of the first version when applying a chunk.
+fn block_seed(state_view_id: StateViewId) -> Option<[u8; 32]> {
+    match state_view_id {
+        StateViewId::BlockExecution { block_id, .. } => Some(*block_id),
+        StateViewId::ChunkExecution { first_version } => {
+            Some(*HashValue::sha3_256_of(&first_version.to_le_bytes()))
+        },
+        StateViewId::TransactionValidation { .. } | StateViewId::Miscellaneous => None,
+    }
+}
+
+/// Returns the state that a block executed against a state view with the given id is executed
+/// on, and the state it leaves once the given number of its transactions are committed, for the
+/// hot-state cache. Unknown outside of block and chunk execution.
+fn hot_state_ids(
+    state_view_id: StateViewId,
+    num_committed_txns: usize,
+) -> Option<(HotStateId, HotStateId)> {
+    match state_view_id {
+        StateViewId::BlockExecution {
+            block_id,
+            parent_block_id,
+        } => Some((
+            HotStateId::Block(parent_block_id),
+            HotStateId::Block(block_id),
+        )),
+        StateViewId::ChunkExecution { first_version } => Some((
+            HotStateId::Version(first_version),
+            HotStateId::Version(first_version + num_committed_txns as Version),
+        )),
+        StateViewId::TransactionValidation { .. } | StateViewId::Miscellaneous => None,
+    }
 }
 
 pub struct BlockAptosVM();
@@ -399,11 +526,42 @@ impl BlockAptosVM {
         L: TransactionCommitHook<Output = AptosTransactionOutput>,
     >(
         executor_thread_pool: Arc<ThreadPool>,
+        materialization_thread_pool: Option<Arc<ThreadPool>>,
+        hot_state_cache: Option<Arc<HotStateCache<StateKey>>>,
         signature_verified_block: &[SignatureVerifiedTransaction],
         state_view: &S,
         config: BlockExecutorConfig,
         transaction_commit_listener: Option<L>,
     ) -> Result<BlockOutput<TransactionOutput>, VMStatus> {
+        Self::execute_block_with_stats(
+            executor_thread_pool,
+            materialization_thread_pool,
+            hot_state_cache,
+            signature_verified_block,
+            state_view,
+            config,
+            transaction_commit_listener,
+        )
+        .0
+    }
+
+    /// Executes the block like [`BlockAptosVM::execute_block`], additionally returning the
+    /// statistics of the block execution (e.g. the number of aborts), for benchmarks to report.
+    pub fn execute_block_with_stats<
+        S: StateView + Sync,
+        L: TransactionCommitHook<Output = AptosTransactionOutput>,
+    >(
+        executor_thread_pool: Arc<ThreadPool>,
+        materialization_thread_pool: Option<Arc<ThreadPool>>,
+        hot_state_cache: Option<Arc<HotStateCache<StateKey>>>,
+        signature_verified_block: &[SignatureVerifiedTransaction],
+        state_view: &S,
+        mut config: BlockExecutorConfig,
+        transaction_commit_listener: Option<L>,
+    ) -> (
+        Result<BlockOutput<TransactionOutput>, VMStatus>,
+        Option<BlockExecutionStats>,
+    ) {
         let _timer = BLOCK_EXECUTOR_EXECUTE_BLOCK_SECONDS.start_timer();
         let num_txns = signature_verified_block.len();
         if state_view.id() != StateViewId::Miscellaneous {
@@ -411,6 +569,21 @@ impl BlockAptosVM {
             // can even lead to concurrent execute_block invocations, leading to errors on flush.
             init_speculative_logs(num_txns);
         }
+        config.local.block_seed = block_seed(state_view.id());
+        // The loader cache of the VM is not version-aware, see validate_module_reads.
+        config.local.validate_module_reads = false;
+        // The outputs of the discarded blocks depend on it, so all validators must agree on it.
+        config.local.discarded_block_bookkeeping = Features::fetch_config(state_view)
+            .unwrap_or_default()
+            .is_discarded_block_bookkeeping_enabled();
+
+        // The cache is only used by the blocks whose parent state is known, one at a time.
+        let hot_state_lease = match (&hot_state_cache, hot_state_ids(state_view.id(), 0)) {
+            (Some(hot_state_cache), Some((parent_state, _))) => {
+                Some(hot_state_cache.lease(parent_state))
+            },
+            _ => None,
+        };
 
         BLOCK_EXECUTOR_CONCURRENCY.set(config.local.concurrency_level as i64);
         let executor = BlockExecutor::<
@@ -420,10 +593,29 @@ impl BlockAptosVM {
             L,
             ExecutableTestType,
         >::new(config, executor_thread_pool, transaction_commit_listener);
-
-        let ret = executor.execute_block(state_view, signature_verified_block, state_view);
-        match ret {
+        let executor = match materialization_thread_pool {
+            Some(pool) => executor.with_materialization_thread_pool(pool),
+            None => executor,
+        };
+        let executor = match (&hot_state_cache, &hot_state_lease) {
+            (Some(hot_state_cache), Some(_)) => {
+                executor.with_hot_state_cache(hot_state_cache.clone())
+            },
+            _ => executor,
+        };
+
+        let ret = executor.execute_block(
+            state_view,
+            signature_verified_block,
+            state_view,
+            None,
+            None,
+            None,
+        );
+        let stats = executor.take_execution_stats();
+        let ret = match ret {
             Ok(block_output) => {
+                let block_end_info = block_output.block_end_info();
                 let transaction_outputs = block_output.into_inner();
                 let output_vec: Vec<_> = transaction_outputs
                     .into_iter()
@@ -439,16 +631,36 @@ impl BlockAptosVM {
                     flush_speculative_logs(pos);
                 }
 
-                Ok(BlockOutput::new(output_vec))
+                if let Some(hot_state_lease) = hot_state_lease {
+                    let num_committed_txns = output_vec
+                        .iter()
+                        .filter(|output| matches!(output.status(), TransactionStatus::Keep(_)))
+                        .count();
+                    if let Some((_, state)) = hot_state_ids(state_view.id(), num_committed_txns) {
+                        hot_state_lease.complete(state);
+                    }
+                }
+
+                Ok(BlockOutput::new(output_vec).with_block_end_info(block_end_info))
             },
-            Err(BlockExecutionError::FatalBlockExecutorError(PanicError::CodeInvariantError(
-                err_msg,
-            ))) => Err(VMStatus::Error {
-                status_code: StatusCode::DELAYED_MATERIALIZATION_CODE_INVARIANT_ERROR,
-                sub_status: None,
-                message: Some(err_msg),
-            }),
-            Err(BlockExecutionError::FatalVMError(err)) => Err(err),
-        }
+            Err(BlockExecutionFailure { error, txn_idx }) => match error {
+                BlockExecutionError::FatalBlockExecutorError(PanicError::CodeInvariantError(
+                    err_msg,
+                )) => Err(VMStatus::Error {
+                    status_code: StatusCode::DELAYED_MATERIALIZATION_CODE_INVARIANT_ERROR,
+                    sub_status: None,
+                    message: Some(match txn_idx {
+                        Some(txn_idx) => format!("{} (at txn {})", err_msg, txn_idx),
+                        None => err_msg,
+                    }),
+                }),
+                BlockExecutionError::FatalVMError(err) => Err(err),
+                BlockExecutionError::Cancelled => Err(VMStatus::error(
+                    StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+                    Some("Block execution was cancelled".to_string()),
+                )),
+            },
+        };
+        (ret, stats)
     }
 }
@@ -32,7 +32,7 @@ use aptos_vm_types::{
     resolver::{
         ExecutorView, ResourceGroupSize, ResourceGroupView, StateStorageView, TResourceGroupView,
     },
-    resource_group_adapter::ResourceGroupAdapter,
+    resource_group_adapter::{group_tagged_resource_size, GroupSizeKind, ResourceGroupAdapter},
 };
 use bytes::Bytes;
 use move_binary_format::{deserializer::DeserializerConfig, errors::*, CompiledModule};
@@ -72,6 +72,9 @@ pub struct StorageAdapter<'e, E> {
     deserializer_config: DeserializerConfig,
     resource_group_view: ResourceGroupAdapter<'e>,
     accessed_groups: RefCell<HashSet<StateKey>>,
+    // If set, loading a resource from a group is charged for the size of the resource (and
+    // its tag) only, instead of the size of the whole group on the first access.
+    per_tag_group_gas: bool,
 }
 
 impl<'e, E: ExecutorView> StorageAdapter<'e, E> {
@@ -95,6 +98,7 @@ impl<'e, E: ExecutorView> StorageAdapter<'e, E> {
             max_binary_version,
             max_identifier_size,
             resource_group_adapter,
+            features.is_resource_group_per_tag_gas_enabled(),
         )
     }
 
@@ -103,7 +107,11 @@ impl<'e, E: ExecutorView> StorageAdapter<'e, E> {
         max_binary_format_version: u32,
         max_identifier_size: u64,
         resource_group_view: ResourceGroupAdapter<'e>,
+        per_tag_group_gas_enabled: bool,
     ) -> Self {
+        // Per-tag sizes are only exposed by the granular (AsSum) resource group handling.
+        let per_tag_group_gas = per_tag_group_gas_enabled
+            && resource_group_view.group_size_kind() == GroupSizeKind::AsSum;
         Self {
             executor_view,
             deserializer_config: DeserializerConfig::new(
@@ -112,6 +120,7 @@ impl<'e, E: ExecutorView> StorageAdapter<'e, E> {
             ),
             resource_group_view,
             accessed_groups: RefCell::new(HashSet::new()),
+            per_tag_group_gas,
         }
     }
 
@@ -132,6 +141,14 @@ impl<'e, E: ExecutorView> StorageAdapter<'e, E> {
                 self.resource_group_view
                     .get_resource_from_group(&key, struct_tag, maybe_layout)?;
 
+            if self.per_tag_group_gas {
+                let buf_size = match &buf {
+                    Some(bytes) => group_tagged_resource_size(struct_tag, bytes.len())? as usize,
+                    None => 0,
+                };
+                return Ok((buf, buf_size));
+            }
+
             let first_access = self.accessed_groups.borrow_mut().insert(key.clone());
             let group_size = if first_access {
                 self.resource_group_view.resource_group_size(&key)?.get()
@@ -334,6 +351,7 @@ impl<S: StateView> AsMoveResolver<S> for S {
             max_binary_version,
             max_identifier_size,
             resource_group_adapter,
+            features.is_resource_group_per_tag_gas_enabled(),
         )
     }
 }
@@ -365,7 +383,6 @@ impl<'e, E> AsResourceGroupView for StorageAdapter<'e, E> {
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
-    use aptos_vm_types::resource_group_adapter::GroupSizeKind;
 
     // Expose a method to create a storage adapter with a provided group size kind.
     pub(crate) fn as_resolver_with_group_size_kind<S: StateView>(
@@ -388,6 +405,6 @@ pub(crate) mod tests {
             gas_feature_version,
             resource_groups_split_in_vm_change_set_enabled,
         );
-        StorageAdapter::new(state_view, 0, 0, group_adapter)
+        StorageAdapter::new(state_view, 0, 0, group_adapter, false)
     }
 }
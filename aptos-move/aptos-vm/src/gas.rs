@@ -7,8 +7,11 @@ use aptos_gas_schedule::{
     AptosGasParameters, FromOnChainGasSchedule, MiscGasParameters, NativeGasParameters,
 };
 use aptos_logger::{enabled, Level};
-use aptos_types::on_chain_config::{
-    ApprovedExecutionHashes, ConfigStorage, Features, GasSchedule, GasScheduleV2, OnChainConfig,
+use aptos_types::{
+    on_chain_config::{
+        ApprovedExecutionHashes, ConfigStorage, Features, GasSchedule, GasScheduleV2, OnChainConfig,
+    },
+    transaction::TransactionPayload,
 };
 use aptos_vm_logging::{log_schema::AdapterLogSchema, speculative_log, speculative_warn};
 use aptos_vm_types::storage::{
@@ -112,6 +115,23 @@ pub(crate) fn get_gas_parameters(
     )
 }
 
+/// Checks the payload against the structural limits of the gas schedule. Mempool, the API and the
+/// prologue all go through `PayloadLimits`, so they discard the same payloads.
+pub(crate) fn check_payload_limits(
+    gas_params: &AptosGasParameters,
+    gas_feature_version: u64,
+    payload: &TransactionPayload,
+    log_context: &AdapterLogSchema,
+) -> Result<(), VMStatus> {
+    if let Some(limits) = gas_params.vm.txn.payload_limits(gas_feature_version) {
+        if let Err(violation) = limits.check(payload) {
+            speculative_warn!(log_context, format!("[VM] {}", violation));
+            return Err(VMStatus::error(violation.status_code(), None));
+        }
+    }
+    Ok(())
+}
+
 pub(crate) fn check_gas(
     gas_params: &AptosGasParameters,
     gas_feature_version: u64,
@@ -120,7 +120,7 @@ mod transaction_validation;
 pub mod validator_txns;
 pub mod verifier;
 
-pub use crate::aptos_vm::{AptosSimulationVM, AptosVM};
+pub use crate::aptos_vm::{AptosSimulationVM, AptosVM, SimulationOptions};
 use crate::sharded_block_executor::{executor_client::ExecutorClient, ShardedBlockExecutor};
 use aptos_types::{
     block_executor::{
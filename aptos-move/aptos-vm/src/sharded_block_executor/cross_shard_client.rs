@@ -136,11 +136,16 @@ impl CrossShardCommitSender {
 impl TransactionCommitHook for CrossShardCommitSender {
     type Output = AptosTransactionOutput;
 
-    fn on_transaction_committed(&self, txn_idx: TxnIndex, txn_output: &Self::Output) {
+    fn on_transaction_committed(
+        &self,
+        txn_idx: TxnIndex,
+        txn_output: &Self::Output,
+    ) -> anyhow::Result<()> {
         let global_txn_idx = txn_idx + self.index_offset;
         if self.dependent_edges.contains_key(&global_txn_idx) {
             self.send_remote_update_for_success(global_txn_idx, txn_output);
         }
+        Ok(())
     }
 
     fn on_execution_aborted(&self, _txn_idx: TxnIndex) {
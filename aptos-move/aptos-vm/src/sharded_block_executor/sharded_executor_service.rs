@@ -18,7 +18,9 @@ use crate::{
 use aptos_logger::{info, trace};
 use aptos_types::{
     block_executor::{
-        config::{BlockExecutorConfig, BlockExecutorLocalConfig},
+        config::{
+            BlockExecutorConfig, BlockExecutorLocalConfig, DependencyWaitConfig, FallbackPolicies,
+        },
         partitioner::{ShardId, SubBlock, SubBlocksForShard, TransactionWithDependencies},
     },
     state_store::StateView,
@@ -136,6 +138,8 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
             s.spawn(move |_| {
                 let ret = BlockAptosVM::execute_block(
                     executor_thread_pool,
+                    None,
+                    None,
                     &signature_verified_transactions,
                     aggr_overridden_state_view.as_ref(),
                     config,
@@ -233,6 +237,30 @@ impl<S: StateView + Sync + Send + 'static> ShardedExecutorService<S> {
                                 concurrency_level: concurrency_level_per_shard,
                                 allow_fallback: true,
                                 discard_failed_blocks: false,
+                                fallback_policies: FallbackPolicies::default(),
+                                adaptive_concurrency: None,
+                                early_delayed_field_validation: false,
+                                prefetch_base_values: false,
+                                record_conflict_graph: false,
+                                record_schedule: false,
+                                txn_execution_timeout: None,
+                                prioritize_by_gas_price: false,
+                                dependency_wait: DependencyWaitConfig::default(),
+                                early_release: false,
+                                materialization_concurrency: 0,
+                                core_affinity: None,
+                                differential_execution: false,
+                                retain_block_state: false,
+                                max_incarnations: None,
+                                block_time_budget: None,
+                                parallel_single_worker: false,
+                                profile_transactions: false,
+                                validate_module_reads: false,
+                                soft_delayed_field_validation_failures: false,
+                                mvhashmap_memory_soft_cap: None,
+                                record_read_stats: false,
+                                block_seed: None,
+                                discarded_block_bookkeeping: false,
                             },
                             onchain: onchain_config,
                         },
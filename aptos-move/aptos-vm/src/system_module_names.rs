@@ -69,3 +69,23 @@ pub static TRANSACTION_FEE_MODULE: Lazy<ModuleId> = Lazy::new(|| {
 });
 
 pub const EMIT_FEE_STATEMENT: &IdentStr = ident_str!("emit_fee_statement");
+
+pub static COIN_MODULE: Lazy<ModuleId> = Lazy::new(|| {
+    ModuleId::new(
+        account_config::CORE_CODE_ADDRESS,
+        ident_str!("coin").to_owned(),
+    )
+});
+
+pub const BALANCE: &IdentStr = ident_str!("balance");
+
+pub static CODE_MODULE: Lazy<ModuleId> = Lazy::new(|| {
+    ModuleId::new(
+        account_config::CORE_CODE_ADDRESS,
+        ident_str!("code").to_owned(),
+    )
+});
+
+pub const STAGE_CODE: &IdentStr = ident_str!("stage_code");
+pub const TAKE_STAGED_CODE: &IdentStr = ident_str!("take_staged_code");
+pub const EMIT_STAGED_CODE_ACTIVATION: &IdentStr = ident_str!("emit_staged_code_activation");
@@ -10,6 +10,7 @@ use aptos_types::{
     transaction::{SignedTransaction, TransactionPayload},
 };
 
+#[derive(Clone)]
 pub struct TransactionMetadata {
     pub sender: AccountAddress,
     pub authentication_key: Vec<u8>,
@@ -5,7 +5,7 @@ use crate::{
     errors::{convert_epilogue_error, convert_prologue_error, expect_only_successful_execution},
     move_vm_ext::SessionExt,
     system_module_names::{
-        EMIT_FEE_STATEMENT, MULTISIG_ACCOUNT_MODULE, TRANSACTION_FEE_MODULE,
+        BALANCE, COIN_MODULE, EMIT_FEE_STATEMENT, MULTISIG_ACCOUNT_MODULE, TRANSACTION_FEE_MODULE,
         VALIDATE_MULTISIG_TRANSACTION,
     },
     testing::{maybe_raise_injected_error, InjectedError},
@@ -14,7 +14,7 @@ use crate::{
 use aptos_gas_algebra::Gas;
 use aptos_types::{
     account_config::constants::CORE_CODE_ADDRESS, fee_statement::FeeStatement,
-    on_chain_config::Features, transaction::Multisig,
+    on_chain_config::Features, transaction::Multisig, utility_coin::APTOS_COIN_TYPE,
 };
 use aptos_vm_logging::log_schema::AdapterLogSchema;
 use fail::fail_point;
@@ -290,3 +290,26 @@ pub(crate) fn run_failure_epilogue(
         )
     })
 }
+
+/// Returns the balance of the gas payer, or `None` if it cannot be read (e.g. the gas payer has
+/// no coin store). This is only used by simulation, to decide whether the fee can be charged
+/// when the balance checks are bypassed.
+pub(crate) fn gas_payer_balance(
+    session: &mut SessionExt,
+    txn_data: &TransactionMetadata,
+) -> Option<u64> {
+    let gas_payer = txn_data.fee_payer().unwrap_or_else(|| txn_data.sender());
+    let values = session
+        .execute_function_bypass_visibility(
+            &COIN_MODULE,
+            BALANCE,
+            vec![APTOS_COIN_TYPE.clone()],
+            serialize_values(&vec![MoveValue::Address(gas_payer)]),
+            &mut UnmeteredGasMeter,
+        )
+        .ok()?;
+    match values.return_values.as_slice() {
+        [(balance, _)] => bcs::from_bytes::<u64>(balance).ok(),
+        _ => None,
+    }
+}
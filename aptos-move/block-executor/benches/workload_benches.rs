@@ -0,0 +1,80 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// Run this bencher via `cargo bench --features fuzzing --bench workload_benches`.
+use aptos_block_executor::proptest_types::workload::{Workload, WorkloadParams};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+// Counts the allocations of the process, to report the allocations per block of the workloads
+// (e.g. to check that the write sets are not copied per incarnation).
+struct CountingAllocator;
+
+static NUM_ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        NUM_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+//
+// Synthetic workload benchmarks
+//
+
+fn bench_workload(c: &mut Criterion, name: &str, params: WorkloadParams) {
+    let workload = Workload::new(params);
+    c.bench_function(name, |b| b.iter(|| workload.run(1)));
+
+    let num_blocks = 10;
+    let num_allocations = NUM_ALLOCATIONS.load(Ordering::Relaxed);
+    let report = workload.run(num_blocks);
+    println!(
+        "{}: {}, {} allocations/block",
+        name,
+        report,
+        (NUM_ALLOCATIONS.load(Ordering::Relaxed) - num_allocations) / num_blocks as u64
+    );
+}
+
+fn workload_benches(c: &mut Criterion) {
+    bench_workload(c, "low_contention", WorkloadParams::default());
+    bench_workload(c, "hot_keys", WorkloadParams {
+        num_hot_keys: 10,
+        ..WorkloadParams::default()
+    });
+    bench_workload(c, "hot_aggregators", WorkloadParams {
+        num_hot_keys: 10,
+        aggregator_pct: 80,
+        ..WorkloadParams::default()
+    });
+    bench_workload(c, "resource_groups", WorkloadParams {
+        num_groups: 10,
+        group_size: 8,
+        ..WorkloadParams::default()
+    });
+    bench_workload(c, "write_heavy", WorkloadParams {
+        num_hot_keys: 100_000,
+        writes_per_txn: 32,
+        ..WorkloadParams::default()
+    });
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = workload_benches
+);
+
+criterion_main!(benches);
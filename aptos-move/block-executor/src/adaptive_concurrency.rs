@@ -0,0 +1,145 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::counters::ADAPTIVE_CONCURRENCY_LEVEL;
+use aptos_types::block_executor::config::AdaptiveConcurrencyConfig;
+use std::{
+    sync::atomic::{AtomicU32, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+/// How long a parked worker sleeps before checking again whether it may resume.
+const PARK_TIMEOUT: Duration = Duration::from_micros(100);
+
+/// Tracks the abort rate during the parallel execution of a block, and decides which workers
+/// are parked accordingly (see AdaptiveConcurrencyConfig). Workers are identified by the order
+/// in which they register, and the workers with the highest ids are parked first.
+pub(crate) struct AdaptiveConcurrency {
+    config: Option<AdaptiveConcurrencyConfig>,
+    max_level: usize,
+    active_level: AtomicUsize,
+    num_aborts: AtomicU32,
+    num_committed: AtomicU32,
+    next_worker_id: AtomicUsize,
+}
+
+impl AdaptiveConcurrency {
+    pub(crate) fn new(config: Option<AdaptiveConcurrencyConfig>, concurrency_level: usize) -> Self {
+        if config.is_some() {
+            ADAPTIVE_CONCURRENCY_LEVEL.set(concurrency_level as i64);
+        }
+        Self {
+            config,
+            max_level: concurrency_level,
+            active_level: AtomicUsize::new(concurrency_level),
+            num_aborts: AtomicU32::new(0),
+            num_committed: AtomicU32::new(0),
+            next_worker_id: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn register_worker(&self) -> usize {
+        self.next_worker_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub(crate) fn active_level(&self) -> usize {
+        self.active_level.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_abort(&self) {
+        if self.config.is_some() {
+            self.num_aborts.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Records a commit, and parks or resumes a worker if the abort rate crossed a threshold.
+    pub(crate) fn record_commit(&self) {
+        let config = match &self.config {
+            Some(config) => config,
+            None => return,
+        };
+        let num_committed = self.num_committed.fetch_add(1, Ordering::Relaxed) + 1;
+        if num_committed < config.min_committed_txns {
+            return;
+        }
+
+        let abort_percentage =
+            self.num_aborts.load(Ordering::Relaxed) as u64 * 100 / num_committed as u64;
+        let min_level = config.min_concurrency_level.clamp(1, self.max_level);
+        let level = self.active_level();
+        let new_level = if abort_percentage > config.park_abort_percentage {
+            level.saturating_sub(1).max(min_level)
+        } else if abort_percentage < config.unpark_abort_percentage {
+            (level + 1).min(self.max_level)
+        } else {
+            level
+        };
+        if new_level != level {
+            // Commits are processed by one worker at a time, so there are no concurrent updates.
+            self.active_level.store(new_level, Ordering::Relaxed);
+            ADAPTIVE_CONCURRENCY_LEVEL.set(new_level as i64);
+        }
+    }
+
+    /// Returns true if the worker should not take new tasks. In that case, the worker has been
+    /// put to sleep for a short while, so that it can simply check again.
+    ///
+    /// Workers suspended on a dependency count against the active level: otherwise, if all active
+    /// workers waited for a transaction that only a parked worker can execute, the block would
+    /// never make progress.
+    pub(crate) fn maybe_park(&self, worker_id: usize, num_waiting_workers: usize) -> bool {
+        if self.config.is_none() || worker_id < self.active_level() + num_waiting_workers {
+            return false;
+        }
+        std::thread::park_timeout(PARK_TIMEOUT);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adaptive_concurrency_level() {
+        let adaptive_concurrency = AdaptiveConcurrency::new(
+            Some(AdaptiveConcurrencyConfig {
+                min_concurrency_level: 2,
+                park_abort_percentage: 50,
+                unpark_abort_percentage: 20,
+                min_committed_txns: 2,
+            }),
+            4,
+        );
+        for _ in 0..4 {
+            adaptive_concurrency.record_abort();
+        }
+
+        // Not adapted until enough transactions are committed.
+        adaptive_concurrency.record_commit();
+        assert_eq!(adaptive_concurrency.active_level(), 4);
+
+        // 4 aborts per 2 and 3 commits: park down to the minimum level.
+        adaptive_concurrency.record_commit();
+        assert_eq!(adaptive_concurrency.active_level(), 3);
+        adaptive_concurrency.record_commit();
+        assert_eq!(adaptive_concurrency.active_level(), 2);
+        assert!(!adaptive_concurrency.maybe_park(1, 0));
+        assert!(adaptive_concurrency.maybe_park(2, 0));
+        // Resumed while an active worker waits for a dependency.
+        assert!(!adaptive_concurrency.maybe_park(2, 1));
+
+        // Between the thresholds, the level is kept.
+        for _ in 0..5 {
+            adaptive_concurrency.record_commit();
+        }
+        assert_eq!(adaptive_concurrency.active_level(), 2);
+
+        // Below the unpark threshold (from 21 commits), workers are resumed.
+        for _ in 0..14 {
+            adaptive_concurrency.record_commit();
+        }
+        assert_eq!(adaptive_concurrency.active_level(), 4);
+        assert!(!adaptive_concurrency.maybe_park(3, 0));
+    }
+}
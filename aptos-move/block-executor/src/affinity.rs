@@ -0,0 +1,136 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::counters::WORKER_THREAD_PIN_COUNT;
+use anyhow::bail;
+use aptos_logger::warn;
+use aptos_types::block_executor::config::CoreAffinityConfig;
+use core_affinity::CoreId;
+use std::cell::Cell;
+
+thread_local! {
+    // The core that the current thread was pinned to, if any.
+    static PINNED_CORE: Cell<Option<usize>> = Cell::new(None);
+}
+
+/// The cores that the threads of the executor thread pool are pinned to when they run the
+/// workers of parallel execution (see [`CoreAffinityConfig`]).
+pub(crate) struct CoreAffinity {
+    cores: Vec<usize>,
+}
+
+impl CoreAffinity {
+    /// Resolves the cores of the config, or returns None (which is logged) if none of them is
+    /// available to the process.
+    pub(crate) fn new(config: &CoreAffinityConfig) -> Option<Self> {
+        let available_cores: Vec<usize> = core_affinity::get_core_ids()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|core_id| core_id.id)
+            .collect();
+        let mut cores = if config.cores.is_empty() {
+            available_cores
+        } else {
+            config
+                .cores
+                .iter()
+                .copied()
+                .filter(|core| available_cores.contains(core))
+                .collect()
+        };
+        if let Some(numa_node) = config.numa_node {
+            match numa_node_cores(numa_node) {
+                Ok(numa_node_cores) => cores.retain(|core| numa_node_cores.contains(core)),
+                Err(err) => {
+                    warn!(
+                        "[BlockSTM] Cannot get the cores of NUMA node {}: {:?}",
+                        numa_node, err
+                    );
+                    cores.clear();
+                },
+            }
+        }
+
+        if cores.is_empty() {
+            warn!(
+                "[BlockSTM] No core available for {:?}, worker threads are not pinned",
+                config
+            );
+            return None;
+        }
+        Some(Self { cores })
+    }
+
+    /// Pins the current thread of the executor thread pool to a core, based on its index in the
+    /// pool (so that the threads are spread over the cores), unless it is already pinned to it.
+    pub(crate) fn pin_current_thread(&self) {
+        let Some(thread_idx) = rayon::current_thread_index() else {
+            return;
+        };
+        let core = self.cores[thread_idx % self.cores.len()];
+        if PINNED_CORE.with(|pinned_core| pinned_core.get()) == Some(core) {
+            return;
+        }
+
+        if core_affinity::set_for_current(CoreId { id: core }) {
+            PINNED_CORE.with(|pinned_core| pinned_core.set(Some(core)));
+            WORKER_THREAD_PIN_COUNT.with_label_values(&["pinned"]).inc();
+        } else {
+            PINNED_CORE.with(|pinned_core| pinned_core.set(None));
+            WORKER_THREAD_PIN_COUNT.with_label_values(&["failed"]).inc();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn numa_node_cores(numa_node: usize) -> anyhow::Result<Vec<usize>> {
+    let cpu_list = std::fs::read_to_string(format!(
+        "/sys/devices/system/node/node{}/cpulist",
+        numa_node
+    ))?;
+    parse_cpu_list(&cpu_list)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn numa_node_cores(_numa_node: usize) -> anyhow::Result<Vec<usize>> {
+    bail!("NUMA nodes are only supported on Linux")
+}
+
+/// Parses a list of cores in the format of the kernel, e.g. "0-3,8,10-11".
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_cpu_list(cpu_list: &str) -> anyhow::Result<Vec<usize>> {
+    let mut cores = Vec::new();
+    for range in cpu_list.trim().split(',').filter(|range| !range.is_empty()) {
+        let (first, last) = match range.split_once('-') {
+            Some((first, last)) => (first.parse::<usize>()?, last.parse::<usize>()?),
+            None => {
+                let core = range.parse::<usize>()?;
+                (core, core)
+            },
+        };
+        if first > last {
+            bail!("Invalid range of cores {}", range);
+        }
+        cores.extend(first..=last);
+    }
+    if cores.is_empty() {
+        bail!("No core in list {:?}", cpu_list);
+    }
+    Ok(cores)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n").unwrap(), vec![
+            0, 1, 2, 3, 8, 10, 11
+        ]);
+        assert_eq!(parse_cpu_list("5").unwrap(), vec![5]);
+        assert!(parse_cpu_list("\n").is_err());
+        assert!(parse_cpu_list("3-1").is_err());
+        assert!(parse_cpu_list("a-b").is_err());
+    }
+}
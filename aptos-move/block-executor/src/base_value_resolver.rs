@@ -0,0 +1,140 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{counters::BASE_VALUE_PREFETCH_SECONDS, hot_state_cache::HotStateCache};
+use aptos_aggregator::types::code_invariant_error;
+use aptos_mvhashmap::{types::ValueWithLayout, MVHashMap};
+use aptos_types::{
+    delayed_fields::PanicError,
+    executable::{Executable, ModulePath},
+    state_store::{errors::StateviewError, state_value::StateValue, TStateView},
+    transaction::BlockExecutableTransaction as Transaction,
+    write_set::TransactionWrite,
+};
+use aptos_vm_logging::{alert, prelude::*};
+use rayon::prelude::*;
+use std::{collections::BTreeSet, sync::Arc};
+
+/// Resolves the base values (i.e. the values prior to the block) of keys from the base view
+/// during parallel execution, and caches them in the multi-version data-structure. Code that
+/// runs outside of the VM (commit, materialization, prefetching) reads storage only through
+/// the resolver, so that failed storage reads are handled in one place. If a hot-state cache is
+/// shared across blocks, the base values are read through it.
+pub(crate) struct BaseValueResolver<'a, T: Transaction, S, X: Executable> {
+    base_view: &'a S,
+    hot_state_cache: Option<&'a HotStateCache<T::Key>>,
+    versioned_cache: &'a MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+}
+
+impl<'a, T, S, X> BaseValueResolver<'a, T, S, X>
+where
+    T: Transaction,
+    S: TStateView<Key = T::Key> + Sync,
+    X: Executable + 'static,
+{
+    pub(crate) fn new(
+        base_view: &'a S,
+        hot_state_cache: Option<&'a HotStateCache<T::Key>>,
+        versioned_cache: &'a MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+    ) -> Self {
+        Self {
+            base_view,
+            hot_state_cache,
+            versioned_cache,
+        }
+    }
+
+    /// The base view, for the views provided to the VM. The VM reads the base values through
+    /// the view, which records them in the multi-version data-structure itself.
+    pub(crate) fn base_view(&self) -> &'a S {
+        self.base_view
+    }
+
+    /// The hot-state cache, for the views provided to the VM.
+    pub(crate) fn hot_state_cache(&self) -> Option<&'a HotStateCache<T::Key>> {
+        self.hot_state_cache
+    }
+
+    fn get_state_value(&self, key: &T::Key) -> Result<Option<StateValue>, StateviewError> {
+        match self.hot_state_cache {
+            Some(hot_state_cache) => hot_state_cache.get_state_value(self.base_view, key),
+            None => self.base_view.get_state_value(key),
+        }
+    }
+
+    /// Reads the value of the key from storage. The base view should not return an error even
+    /// speculatively, hence the error is not expected outside of the VM.
+    fn fetch_base_value(&self, key: &T::Key) -> Result<Option<StateValue>, PanicError> {
+        self.get_state_value(key).map_err(|e| {
+            alert!(
+                "[BlockSTM] Error reading the base value of {:?}: {:?}",
+                key,
+                e
+            );
+            code_invariant_error(format!(
+                "Error reading the base value of {:?} from storage: {:?}",
+                key, e
+            ))
+        })
+    }
+
+    /// Returns the base value of an aggregator v1, reading it from storage and recording it in
+    /// the multi-version data-structure, where it is used for the materialization of the
+    /// subsequent deltas.
+    pub(crate) fn resolve_aggregator_v1_base_value(
+        &self,
+        key: &T::Key,
+    ) -> Result<u128, PanicError> {
+        let value: T::Value = TransactionWrite::from_state_value(self.fetch_base_value(key)?);
+        let value_u128 = value
+            .as_u128()
+            .map_err(|e| {
+                code_invariant_error(format!(
+                    "Aggregator base value deserialization error for {:?}: {:?}",
+                    key, e
+                ))
+            })?
+            .ok_or_else(|| {
+                code_invariant_error(format!("Aggregator base value must exist for {:?}", key))
+            })?;
+
+        self.versioned_cache.data().set_base_value(
+            key.clone(),
+            ValueWithLayout::RawFromStorage(Arc::new(value)),
+        );
+        Ok(value_u128)
+    }
+
+    /// Reads the base values of the keys likely to be read by the block from the base view,
+    /// concurrently (must be called from within the executor thread pool), and records them
+    /// in the multi-version data-structure before any transaction is executed. Otherwise, the
+    /// storage latency would be incurred within the first incarnation of the transactions.
+    /// Failed reads are skipped, as the keys are read again (and the errors reported) during
+    /// execution.
+    pub(crate) fn prefetch_base_values<'k>(&self, keys: impl Iterator<Item = &'k T::Key>)
+    where
+        T::Key: 'k,
+    {
+        // Modules are not stored in the multi-version data-structure as base values.
+        let keys: Vec<_> = keys
+            .filter(|key| key.module_path().is_none())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        if keys.is_empty() {
+            return;
+        }
+
+        let _timer = BASE_VALUE_PREFETCH_SECONDS.start_timer();
+        keys.into_par_iter().for_each(|key| {
+            if let Ok(state_value) = self.get_state_value(key) {
+                self.versioned_cache.data().set_base_value(
+                    key.clone(),
+                    ValueWithLayout::RawFromStorage(Arc::new(TransactionWrite::from_state_value(
+                        state_value,
+                    ))),
+                );
+            }
+        });
+    }
+}
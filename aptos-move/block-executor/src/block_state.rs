@@ -0,0 +1,60 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_mvhashmap::{state_diff::StateDiff, types::TxnIndex, MVHashMap};
+use aptos_types::{executable::Executable, transaction::BlockExecutableTransaction as Transaction};
+
+/// The multi-version data-structure of a block executed in parallel, retained with
+/// retain_block_state in the local config and taken from the block executor after the block
+/// (see [`crate::executor::BlockExecutor::take_block_state`]). It contains the writes of all the
+/// committed transactions, so that a transaction can be executed against the state of any
+/// committed prefix of the block, e.g. to simulate a reverted transaction at another position.
+pub struct ExecutedBlockState<T: Transaction, X: Executable> {
+    pub(crate) versioned_cache: MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+    // The first and the next value of the counter for delayed field identifiers, so that the
+    // identifiers of the transactions executed against the block do not collide with the
+    // identifiers of the block.
+    pub(crate) start_shared_counter: u32,
+    pub(crate) shared_counter: u32,
+    pub(crate) num_committed_txns: TxnIndex,
+}
+
+impl<T: Transaction, X: Executable> ExecutedBlockState<T, X> {
+    /// The number of transactions of the block that were committed, e.g. fewer than the
+    /// transactions of the block if the block gas limit was reached.
+    pub fn num_committed_txns(&self) -> TxnIndex {
+        self.num_committed_txns
+    }
+
+    /// The final value of every key written by the committed transactions of the block, e.g. for
+    /// the callers that need the aggregate state delta of the block (such as state sync or
+    /// storage batching) rather than the outputs of its transactions. Resource values with a
+    /// layout hold delayed field identifiers, which are exchanged for their values in the outputs.
+    pub fn state_diff(&self) -> StateDiff<T::Key, T::Tag, T::Value> {
+        self.versioned_cache
+            .committed_state_diff(self.num_committed_txns)
+    }
+
+    /// The state of the block after its first txn_idx transactions, i.e. the state that the
+    /// transaction at txn_idx was executed against, if they were all committed.
+    pub fn prefix(&self, txn_idx: TxnIndex) -> Option<BlockPrefixState<'_, T, X>> {
+        (txn_idx <= self.num_committed_txns).then_some(BlockPrefixState {
+            block_state: self,
+            txn_idx,
+        })
+    }
+}
+
+/// The state of a committed prefix of an executed block (see [`ExecutedBlockState::prefix`]).
+pub struct BlockPrefixState<'a, T: Transaction, X: Executable> {
+    pub(crate) block_state: &'a ExecutedBlockState<T, X>,
+    pub(crate) txn_idx: TxnIndex,
+}
+
+impl<'a, T: Transaction, X: Executable> BlockPrefixState<'a, T, X> {
+    /// The number of transactions of the prefix, i.e. the position of a transaction executed
+    /// against it in the block.
+    pub fn txn_idx(&self) -> TxnIndex {
+        self.txn_idx
+    }
+}
@@ -10,18 +10,23 @@ use aptos_aggregator::{
         ReadPosition,
     },
 };
+use aptos_crypto::HashValue;
 use aptos_mvhashmap::{
     types::{
-        MVDataError, MVDataOutput, MVDelayedFieldsError, MVGroupError, StorageVersion, TxnIndex,
-        ValueWithLayout, Version,
+        MVDataError, MVDataOutput, MVDelayedFieldsError, MVGroupError, MVModulesError,
+        MVModulesOutput, StorageVersion, TxnIndex, ValueWithLayout, Version,
     },
     versioned_data::VersionedData,
     versioned_delayed_fields::TVersionedDelayedFieldView,
     versioned_group_data::VersionedGroupData,
+    versioned_modules::VersionedModules,
 };
 use aptos_types::{
-    delayed_fields::PanicError, state_store::state_value::StateValueMetadata,
-    transaction::BlockExecutableTransaction as Transaction, write_set::TransactionWrite,
+    delayed_fields::PanicError,
+    executable::{Executable, ExecutableDescriptor, KeyPrefix},
+    state_store::state_value::StateValueMetadata,
+    transaction::BlockExecutableTransaction as Transaction,
+    write_set::TransactionWrite,
 };
 use aptos_vm_types::resolver::ResourceGroupSize;
 use derivative::Derivative;
@@ -34,7 +39,11 @@ use std::{
         },
         BTreeMap, HashMap, HashSet,
     },
-    sync::Arc,
+    ops::Bound,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
 
 /// The enum variants should not be re-ordered, as it defines a relation
@@ -70,6 +79,16 @@ pub(crate) enum DataRead<V> {
     Resolved(u128),
 }
 
+/// The version of a module read by a transaction: the module was either read from storage
+/// (i.e. it was not published by an earlier transaction of the block), or it is the module
+/// with the given hash, published by an earlier transaction. The hash is compared instead of
+/// the incarnation, so that re-publishing the same module does not invalidate the reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ModuleRead {
+    Storage,
+    Published(HashValue),
+}
+
 // Represents the result of comparing DataReads ('self' and 'other').
 #[derive(Debug)]
 enum DataReadComparison {
@@ -124,6 +143,16 @@ impl<V: TransactionWrite> DataRead<V> {
         }
     }
 
+    // Compares the values of two versioned reads, ignoring the versions.
+    fn value_eq(&self, other: &DataRead<V>) -> bool {
+        match (self, other) {
+            (DataRead::Versioned(_, v1, _), DataRead::Versioned(_, v2, _)) => {
+                v1.as_state_value() == v2.as_state_value()
+            },
+            _ => false,
+        }
+    }
+
     /// If the reads contains sufficient information, extract this information and generate
     /// a new DataRead of the desired kind (e.g. Metadata kind from Value).
     pub(crate) fn downcast(&self, kind: ReadKind) -> Option<DataRead<V>> {
@@ -175,6 +204,15 @@ pub(crate) struct GroupRead<T: Transaction> {
     pub(crate) inner_reads: HashMap<T::Tag, DataRead<T::Value>>,
 }
 
+/// The keys of a prefix in a range that were written by the preceding transactions, as read by
+/// the transaction (see [`VersionedData::fetch_range`]). The read is valid if the same keys are
+/// read again, with the same existence.
+pub(crate) struct RangeRead<T: Transaction> {
+    prefix: <T::Key as KeyPrefix>::Prefix,
+    range: (Bound<T::Key>, Bound<T::Key>),
+    keys: Vec<(T::Key, bool)>,
+}
+
 /// Defines different ways `DelayedFieldResolver` can be used to read its values
 /// from the state.
 /// The enum variants should not be re-ordered, as it defines a relation
@@ -298,11 +336,21 @@ impl DelayedFieldRead {
 pub(crate) struct CapturedReads<T: Transaction> {
     data_reads: HashMap<T::Key, DataRead<T::Value>>,
     group_reads: HashMap<T::Key, GroupRead<T>>,
-    // Currently, we record paths for triggering module R/W fallback.
-    // TODO: implement a general functionality once the fallback is removed.
-    pub(crate) module_reads: Vec<T::Key>,
+    // The module reads are validated if validate_module_reads is set in the local config, and
+    // otherwise only their paths are used, for triggering the module R/W fallback.
+    module_reads: HashMap<T::Key, ModuleRead>,
+    range_reads: Vec<RangeRead<T>>,
 
     delayed_field_reads: HashMap<T::Identifier, DelayedFieldRead>,
+    /// Keys whose read went past an estimate (see [`Scheduler::reads_past_estimates`]). If
+    /// the version changed, these reads are validated by comparing the values.
+    ///
+    /// [`Scheduler::reads_past_estimates`]: crate::scheduler::Scheduler::reads_past_estimates
+    past_estimate_reads: HashSet<T::Key>,
+    /// The change clock of the versioned data (see [`VersionedData::changed_since`]) up to
+    /// which the data reads are known to be valid: the reads of the keys that did not change
+    /// since are not re-validated.
+    validated_at: AtomicU64,
 
     /// If there is a speculative failure (e.g. delta application failure, or an
     /// observed inconsistency), the transaction output is irrelevant (must be
@@ -421,6 +469,32 @@ impl<T: Transaction> CapturedReads<T> {
             .and_then(|group| group.collected_size)
     }
 
+    pub(crate) fn capture_range_read(
+        &mut self,
+        prefix: <T::Key as KeyPrefix>::Prefix,
+        range: (Bound<T::Key>, Bound<T::Key>),
+        keys: Vec<(T::Key, bool)>,
+    ) {
+        self.range_reads.push(RangeRead {
+            prefix,
+            range,
+            keys,
+        });
+    }
+
+    /// Returns the keys of the range if it was already read, so that the transaction observes
+    /// the same keys if it reads the range again.
+    pub(crate) fn get_range_read(
+        &self,
+        prefix: &<T::Key as KeyPrefix>::Prefix,
+        range: &(Bound<T::Key>, Bound<T::Key>),
+    ) -> Option<Vec<(T::Key, bool)>> {
+        self.range_reads
+            .iter()
+            .find(|read| read.prefix == *prefix && read.range == *range)
+            .map(|read| read.keys.clone())
+    }
+
     // Error means there was a inconsistency in information read (must be due to the
     // speculative nature of reads).
     pub(crate) fn capture_read(
@@ -451,6 +525,25 @@ impl<T: Transaction> CapturedReads<T> {
         }
     }
 
+    /// Records the version of the module read at the key. Reading a different version of the
+    /// same module during the execution is a speculative failure.
+    pub(crate) fn capture_module_read(&mut self, key: T::Key, read: ModuleRead) {
+        match self.module_reads.entry(key) {
+            Vacant(entry) => {
+                entry.insert(read);
+            },
+            Occupied(entry) => {
+                if *entry.get() != read {
+                    self.speculative_failure = true;
+                }
+            },
+        }
+    }
+
+    pub(crate) fn module_read_keys(&self) -> impl Iterator<Item = &T::Key> {
+        self.module_reads.keys()
+    }
+
     // If maybe_tag is provided, then we check the group, otherwise, normal reads.
     pub(crate) fn get_by_kind(
         &self,
@@ -549,6 +642,20 @@ impl<T: Transaction> CapturedReads<T> {
         self.incorrect_use
     }
 
+    /// Creates captured reads for an execution that started when the change clock of the
+    /// versioned data had the given value.
+    pub(crate) fn with_change_clock(change_clock: u64) -> Self {
+        Self {
+            validated_at: AtomicU64::new(change_clock),
+            ..Self::new()
+        }
+    }
+
+    /// Records that the read of the key went past an estimate.
+    pub(crate) fn mark_past_estimate_read(&mut self, key: T::Key) {
+        self.past_estimate_reads.insert(key);
+    }
+
     pub(crate) fn validate_data_reads(
         &self,
         data_map: &VersionedData<T::Key, T::Value>,
@@ -560,13 +667,33 @@ impl<T: Transaction> CapturedReads<T> {
 
         use MVDataError::*;
         use MVDataOutput::*;
-        self.data_reads.iter().all(|(k, r)| {
+        // Loaded before the reads are validated, as all the changes with lower stamps are then
+        // visible when the keys are accessed.
+        let change_clock = data_map.change_clock();
+        let validated_at = self.validated_at.load(Ordering::Relaxed);
+        // When there were more changes than reads, checking which keys changed is not cheaper
+        // than validating all the reads.
+        let incremental = change_clock.saturating_sub(validated_at) <= self.data_reads.len() as u64;
+
+        let valid = self.data_reads.iter().all(|(k, r)| {
+            if incremental && !data_map.changed_since(k, validated_at) {
+                return true;
+            }
+
             match data_map.fetch_data(k, idx_to_validate) {
                 Ok(Versioned(version, v)) => {
-                    matches!(
-                        DataRead::from_value_with_layout(version, v).contains(r),
-                        DataReadComparison::Contains
-                    )
+                    let current = DataRead::from_value_with_layout(version, v);
+                    match current.contains(r) {
+                        DataReadComparison::Contains => true,
+                        // A read of an aborted incarnation's value stays valid if the value
+                        // was written again.
+                        DataReadComparison::Inconsistent
+                            if self.past_estimate_reads.contains(k) =>
+                        {
+                            current.value_eq(r)
+                        },
+                        _ => false,
+                    }
                 },
                 Ok(Resolved(value)) => matches!(
                     DataRead::Resolved(value).contains(r),
@@ -580,6 +707,44 @@ impl<T: Transaction> CapturedReads<T> {
                 | Err(DeltaApplicationFailure)
                 | Err(Uninitialized) => false,
             }
+        }) && self.range_reads.iter().all(|read| {
+            // The range reads are not validated incrementally, as the change clock of the keys
+            // does not cover the keys that were not in the range when it was read.
+            data_map
+                .fetch_range(&read.prefix, &read.range, idx_to_validate)
+                .map_or(false, |keys| keys == read.keys)
+        });
+        if valid {
+            self.validated_at.fetch_max(change_clock, Ordering::Relaxed);
+        }
+        valid
+    }
+
+    pub(crate) fn validate_module_reads<X: Executable>(
+        &self,
+        module_map: &VersionedModules<T::Key, T::Value, X>,
+        idx_to_validate: TxnIndex,
+    ) -> bool {
+        use MVModulesError::*;
+        use MVModulesOutput::*;
+
+        if self.speculative_failure {
+            return false;
+        }
+
+        self.module_reads.iter().all(|(k, r)| {
+            let current = match module_map.fetch_module(k, idx_to_validate) {
+                Ok(Module((_, hash)))
+                | Ok(Executable((_, ExecutableDescriptor::Published(hash)))) => {
+                    ModuleRead::Published(hash)
+                },
+                Ok(Executable((_, ExecutableDescriptor::Storage))) | Err(NotFound) => {
+                    ModuleRead::Storage
+                },
+                // The module is being re-published by an earlier transaction.
+                Err(Dependency(_)) => return false,
+            };
+            current == *r
         })
     }
 
@@ -630,6 +795,58 @@ impl<T: Transaction> CapturedReads<T> {
         })
     }
 
+    // A cheap, best-effort check of the delayed field reads against the current speculative
+    // values, to abort incarnations that read obviously stale values before they are committed.
+    // Unlike validate_delayed_field_reads, a successful check does not imply the reads are
+    // valid, so the check at commit time is still required.
+    pub(crate) fn validate_delayed_field_reads_speculatively(
+        &self,
+        delayed_fields: &dyn TVersionedDelayedFieldView<T::Identifier>,
+        idx_to_validate: TxnIndex,
+    ) -> Result<bool, PanicError> {
+        if self.speculative_failure {
+            return Ok(false);
+        }
+
+        use MVDelayedFieldsError::*;
+        for (id, read_value) in &self.delayed_field_reads {
+            match delayed_fields.read(id, idx_to_validate) {
+                Ok(current_value) => match read_value {
+                    DelayedFieldRead::Value { value, .. } => {
+                        if value != &current_value {
+                            return Ok(false);
+                        }
+                    },
+                    DelayedFieldRead::HistoryBounded {
+                        restriction,
+                        max_value,
+                        ..
+                    } => {
+                        if restriction
+                            .validate_against_base_value(
+                                current_value.into_aggregator_value()?,
+                                *max_value,
+                            )
+                            .is_err()
+                        {
+                            return Ok(false);
+                        }
+                    },
+                },
+                Err(PanicOr::Or(Dependency(_))) => {
+                    return Ok(false);
+                },
+                // Inconclusive before commit (e.g. the field may be created by the transaction
+                // itself, or earlier speculative deltas may not apply), left to the commit time.
+                Err(PanicOr::Or(NotFound)) | Err(PanicOr::Or(DeltaApplicationFailure)) => {},
+                Err(PanicOr::CodeInvariantError(msg)) => {
+                    return Err(PanicError::CodeInvariantError(msg));
+                },
+            }
+        }
+        Ok(true)
+    }
+
     // This validation needs to be called at commit time
     // (as it internally uses read_latest_committed_value to get the current value).
     pub(crate) fn validate_delayed_field_reads(
@@ -694,10 +911,16 @@ impl<T: Transaction> CapturedReads<T> {
             }
         }
 
-        for key in &self.module_reads {
+        for key in self.module_reads.keys() {
             ret.insert(InputOutputKey::Resource(key.clone()));
         }
 
+        for read in &self.range_reads {
+            for (key, _) in &read.keys {
+                ret.insert(InputOutputKey::Resource(key.clone()));
+            }
+        }
+
         for (key, read) in &self.delayed_field_reads {
             if let DelayedFieldRead::Value { .. } = read {
                 ret.insert(InputOutputKey::DelayedField(*key));
@@ -756,7 +979,12 @@ impl<T: Transaction> UnsyncReadSet<T> {
 mod test {
     use super::*;
     use crate::proptest_types::types::{raw_metadata, KeyType, MockEvent, ValueType};
-    use aptos_mvhashmap::types::StorageVersion;
+    use aptos_mvhashmap::{types::StorageVersion, MVHashMap};
+    use aptos_types::{
+        account_address::AccountAddress,
+        executable::ExecutableTestType,
+        state_store::{state_key::StateKey, table::TableHandle},
+    };
     use claims::{assert_err, assert_gt, assert_matches, assert_none, assert_ok, assert_some_eq};
     use move_vm_types::delayed_values::delayed_field_id::DelayedFieldID;
     use test_case::test_case;
@@ -946,6 +1174,72 @@ mod test {
         }
     }
 
+    // Transactions with table items, which can be read by range.
+    #[derive(Clone, Debug)]
+    struct TableTransactionType {}
+
+    impl Transaction for TableTransactionType {
+        type Event = MockEvent;
+        type Identifier = DelayedFieldID;
+        type Key = StateKey;
+        type Tag = u32;
+        type Value = ValueType;
+
+        fn user_txn_bytes_len(&self) -> usize {
+            0
+        }
+    }
+
+    // Delayed field values before the validated transaction: None stands for an estimate.
+    struct MockDelayedFieldView(HashMap<DelayedFieldID, Option<u128>>);
+
+    impl TVersionedDelayedFieldView<DelayedFieldID> for MockDelayedFieldView {
+        fn read(
+            &self,
+            id: &DelayedFieldID,
+            _txn_idx: TxnIndex,
+        ) -> Result<DelayedFieldValue, PanicOr<MVDelayedFieldsError>> {
+            match self.0.get(id) {
+                Some(Some(value)) => Ok(DelayedFieldValue::Aggregator(*value)),
+                Some(None) => Err(PanicOr::Or(MVDelayedFieldsError::Dependency(0))),
+                None => Err(PanicOr::Or(MVDelayedFieldsError::NotFound)),
+            }
+        }
+
+        fn read_latest_committed_value(
+            &self,
+            _id: &DelayedFieldID,
+            _current_txn_idx: TxnIndex,
+            _read_position: ReadPosition,
+        ) -> Result<DelayedFieldValue, MVDelayedFieldsError> {
+            unreachable!("Not used by the speculative validation");
+        }
+    }
+
+    #[test]
+    fn validate_delayed_field_reads_speculatively() {
+        let id = DelayedFieldID::new_for_test_for_u64(600);
+        let mut captured_reads = CapturedReads::<TestTransactionType>::new();
+        assert_ok!(
+            captured_reads.capture_delayed_field_read(id, false, DelayedFieldRead::Value {
+                value: DelayedFieldValue::Aggregator(5),
+            })
+        );
+
+        let validate = |current: Option<Option<u128>>| {
+            let view = MockDelayedFieldView(current.into_iter().map(|v| (id, v)).collect());
+            captured_reads
+                .validate_delayed_field_reads_speculatively(&view, 1)
+                .unwrap()
+        };
+        // Same value, or inconclusive (the field is not found).
+        assert!(validate(Some(Some(5))));
+        assert!(validate(None));
+        // Stale value, or an estimate of an aborted transaction.
+        assert!(!validate(Some(Some(6))));
+        assert!(!validate(Some(None)));
+    }
+
     macro_rules! assert_update_incorrect_use {
         ($m:expr, $x:expr, $y:expr) => {{
             let original = $m.get(&$x).cloned().unwrap();
@@ -1294,4 +1588,201 @@ mod test {
         captured_reads.mark_failure();
         assert!(captured_reads.speculative_failure);
     }
+
+    #[test]
+    fn past_estimate_read_validation() {
+        let map =
+            MVHashMap::<KeyType<u32>, u32, ValueType, ExecutableTestType, DelayedFieldID>::new();
+        let key = KeyType::<u32>(1, false);
+        let value = ValueType::from_value(vec![1], true);
+
+        // Transaction 2 reads the value written by the aborted incarnation of transaction 1.
+        map.data()
+            .write(key.clone(), 1, 0, Arc::new(value.clone()), None);
+        map.data().mark_estimate(&key, 1);
+        assert_matches!(
+            map.data().fetch_data(&key, 2),
+            Err(MVDataError::Dependency(1))
+        );
+        let read = match map.data().fetch_data_past_estimates(&key, 2) {
+            Ok(MVDataOutput::Versioned(version, v)) => DataRead::from_value_with_layout(version, v),
+            _ => unreachable!("Must read the estimated write"),
+        };
+        let mut version_reads = CapturedReads::<TestTransactionType>::new();
+        assert_ok!(version_reads.capture_read(key.clone(), None, read.clone()));
+        let mut value_reads = CapturedReads::<TestTransactionType>::new();
+        assert_ok!(value_reads.capture_read(key.clone(), None, read));
+        value_reads.mark_past_estimate_read(key.clone());
+
+        // Not valid while the estimate is in place.
+        assert!(!value_reads.validate_data_reads(map.data(), 2));
+
+        // The re-execution writes the same value.
+        map.data().write(key.clone(), 1, 1, Arc::new(value), None);
+        assert!(!version_reads.validate_data_reads(map.data(), 2));
+        assert!(value_reads.validate_data_reads(map.data(), 2));
+
+        // The next re-execution writes a different value.
+        map.data().mark_estimate(&key, 1);
+        map.data().write(
+            key,
+            1,
+            2,
+            Arc::new(ValueType::from_value(vec![2], true)),
+            None,
+        );
+        assert!(!value_reads.validate_data_reads(map.data(), 2));
+    }
+
+    #[test]
+    fn module_read_validation() {
+        let map =
+            MVHashMap::<KeyType<u32>, u32, ValueType, ExecutableTestType, DelayedFieldID>::new();
+        let key = KeyType::<u32>(1, true);
+        let value = ValueType::from_value(vec![1], true);
+
+        let mut storage_reads = CapturedReads::<TestTransactionType>::new();
+        storage_reads.capture_module_read(key.clone(), ModuleRead::Storage);
+        assert!(storage_reads.validate_module_reads(map.modules(), 2));
+
+        // Transaction 1 publishes the module read by transaction 2.
+        map.modules().write(key.clone(), 1, value.clone());
+        assert!(!storage_reads.validate_module_reads(map.modules(), 2));
+        let hash = match map.modules().fetch_module(&key, 2) {
+            Ok(MVModulesOutput::Module((_, hash))) => hash,
+            _ => unreachable!("Must read the published module"),
+        };
+        let mut published_reads = CapturedReads::<TestTransactionType>::new();
+        published_reads.capture_module_read(key.clone(), ModuleRead::Published(hash));
+        assert!(published_reads.validate_module_reads(map.modules(), 2));
+        // Transactions up to 1 are not affected by the publish.
+        assert!(storage_reads.validate_module_reads(map.modules(), 1));
+
+        // Not valid while the estimate is in place, valid again if the same module is published.
+        map.modules().mark_estimate(&key, 1);
+        assert!(!published_reads.validate_module_reads(map.modules(), 2));
+        map.modules().write(key.clone(), 1, value);
+        assert!(published_reads.validate_module_reads(map.modules(), 2));
+
+        map.modules()
+            .write(key.clone(), 1, ValueType::from_value(vec![2], true));
+        assert!(!published_reads.validate_module_reads(map.modules(), 2));
+
+        // Reading different versions of the module is a speculative failure.
+        storage_reads.capture_module_read(key, ModuleRead::Published(hash));
+        assert!(storage_reads.speculative_failure);
+        assert!(!storage_reads.validate_module_reads(map.modules(), 1));
+    }
+
+    #[test]
+    fn incremental_data_read_validation() {
+        let map =
+            MVHashMap::<KeyType<u32>, u32, ValueType, ExecutableTestType, DelayedFieldID>::new();
+        let key = KeyType::<u32>(1, false);
+        let other_key = KeyType::<u32>(2, false);
+        let value = ValueType::from_value(vec![1], true);
+
+        map.data()
+            .write(key.clone(), 1, 0, Arc::new(value.clone()), None);
+        map.data()
+            .write(other_key.clone(), 1, 0, Arc::new(value.clone()), None);
+        let read = match map.data().fetch_data(&key, 2) {
+            Ok(MVDataOutput::Versioned(version, v)) => DataRead::from_value_with_layout(version, v),
+            _ => unreachable!("Must read the write"),
+        };
+        // A read of an incarnation that did not write, which full validation rejects.
+        let stale_read = DataRead::Versioned(Ok((1, 5)), Arc::new(value.clone()), None);
+
+        let clock = map.data().change_clock();
+        let mut reads = CapturedReads::<TestTransactionType>::with_change_clock(clock);
+        assert_ok!(reads.capture_read(key.clone(), None, read));
+        let mut stale_reads = CapturedReads::<TestTransactionType>::with_change_clock(clock);
+        assert_ok!(stale_reads.capture_read(key.clone(), None, stale_read.clone()));
+        assert!(reads.validate_data_reads(map.data(), 2));
+        // Reads of the keys that did not change since the execution are not re-validated.
+        assert!(stale_reads.validate_data_reads(map.data(), 2));
+
+        // A change of another key does not require re-validation.
+        map.data()
+            .write(other_key.clone(), 1, 1, Arc::new(value.clone()), None);
+        assert!(stale_reads.validate_data_reads(map.data(), 2));
+
+        // A change of the read key does.
+        map.data().mark_estimate(&key, 1);
+        assert!(!reads.validate_data_reads(map.data(), 2));
+        assert!(!stale_reads.validate_data_reads(map.data(), 2));
+        map.data().write(key, 1, 1, Arc::new(value.clone()), None);
+        assert!(!reads.validate_data_reads(map.data(), 2));
+
+        // All the reads are validated when more keys changed than were read.
+        let clock = map.data().change_clock();
+        let mut stale_reads = CapturedReads::<TestTransactionType>::with_change_clock(clock);
+        assert_ok!(stale_reads.capture_read(other_key, None, stale_read));
+        for i in 3..5 {
+            map.data().write(
+                KeyType::<u32>(i, false),
+                1,
+                0,
+                Arc::new(value.clone()),
+                None,
+            );
+        }
+        assert!(!stale_reads.validate_data_reads(map.data(), 2));
+    }
+
+    #[test]
+    fn range_read_validation() {
+        let map = MVHashMap::<StateKey, u32, ValueType, ExecutableTestType, DelayedFieldID>::new();
+        let handle = TableHandle(AccountAddress::ONE);
+        let item = |i: u8| StateKey::table_item(handle, vec![i]);
+        let range = (Bound::Included(item(1)), Bound::Excluded(item(5)));
+        let value = Arc::new(ValueType::from_value(vec![1], true));
+
+        map.data().write(item(2), 1, 0, value.clone(), None);
+        // Outside of the range, and in another table.
+        map.data().write(item(5), 1, 0, value.clone(), None);
+        map.data().write(
+            StateKey::table_item(TableHandle(AccountAddress::TWO), vec![3]),
+            1,
+            0,
+            value.clone(),
+            None,
+        );
+
+        let keys = map.data().fetch_range(&handle, &range, 3).unwrap();
+        assert_eq!(keys, vec![(item(2), true)]);
+        let mut reads = CapturedReads::<TableTransactionType>::new();
+        reads.capture_range_read(handle, range.clone(), keys);
+        assert_some_eq!(reads.get_range_read(&handle, &range), vec![(item(2), true)]);
+        assert_none!(reads.get_range_read(&handle, &(Bound::Unbounded, Bound::Unbounded)));
+        assert!(reads.validate_data_reads(map.data(), 3));
+        assert!(reads
+            .get_read_summary()
+            .contains(&InputOutputKey::Resource(item(2))));
+
+        // Writes outside of the range, or by the later transactions, do not invalidate the read.
+        map.data().write(item(6), 2, 0, value.clone(), None);
+        map.data().write(item(3), 3, 0, value.clone(), None);
+        assert!(reads.validate_data_reads(map.data(), 3));
+
+        // Neither does a re-execution that writes the same keys.
+        map.data().mark_estimate(&item(2), 1);
+        assert!(!reads.validate_data_reads(map.data(), 3));
+        map.data().write(item(2), 1, 1, value.clone(), None);
+        assert!(reads.validate_data_reads(map.data(), 3));
+
+        // A key inserted in the range does, as does a deletion of a key in the range.
+        map.data().write(item(4), 2, 0, value.clone(), None);
+        assert!(!reads.validate_data_reads(map.data(), 3));
+        map.data().remove(&item(4), 2);
+        assert!(reads.validate_data_reads(map.data(), 3));
+        map.data().write(
+            item(2),
+            2,
+            0,
+            Arc::new(ValueType::from_value(vec![1], false)),
+            None,
+        );
+        assert!(!reads.validate_data_reads(map.data(), 3));
+    }
 }
@@ -0,0 +1,218 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Optional recording of Block-STM scheduler and worker events, dumped as one Chrome
+//! `trace_event` JSON file per block (viewable in chrome://tracing or Perfetto).
+//!
+//! Tracing is enabled process-wide by [`enable`]. While enabled, every parallel block execution
+//! records the execution and validation tasks, commits, aborts, wake-ups and dependency waits
+//! of its workers, with timestamps and thread ids. Only one block is recorded at a time, so
+//! blocks executed concurrently (e.g. by different shards) are not traced. When disabled, the
+//! overhead is a single relaxed atomic load per event.
+
+use anyhow::Result;
+use aptos_infallible::Mutex;
+use aptos_logger::{error, info};
+use aptos_mvhashmap::types::{Incarnation, TxnIndex};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    time::Instant,
+};
+
+static OUTPUT_DIR: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static RECORDER: Lazy<Mutex<Option<Recorder>>> = Lazy::new(|| Mutex::new(None));
+static NEXT_BLOCK_SEQ: AtomicU64 = AtomicU64::new(0);
+static NEXT_THREAD_ID: AtomicU32 = AtomicU32::new(0);
+
+thread_local! {
+    static THREAD_ID: u32 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Enables tracing of the subsequent parallel block executions. The trace of each block is
+/// written to `output_dir` as `block_<seq>.json`.
+pub fn enable(output_dir: PathBuf) {
+    *OUTPUT_DIR.lock() = Some(output_dir);
+}
+
+pub fn disable() {
+    *OUTPUT_DIR.lock() = None;
+}
+
+struct Recorder {
+    start: Instant,
+    events: Vec<TraceEvent>,
+}
+
+/// An event in the Chrome `trace_event` format. Timestamps and durations are in microseconds.
+#[derive(Debug, Serialize)]
+struct TraceEvent {
+    name: &'static str,
+    cat: &'static str,
+    /// "X" for complete events (with a duration) and "i" for instant events.
+    ph: &'static str,
+    ts: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<f64>,
+    pid: u32,
+    tid: u32,
+    args: TraceArgs,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub(crate) struct TraceArgs {
+    txn_idx: TxnIndex,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    incarnation: Option<Incarnation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dep_idx: Option<TxnIndex>,
+}
+
+impl TraceArgs {
+    pub(crate) fn txn(txn_idx: TxnIndex) -> Self {
+        Self {
+            txn_idx,
+            incarnation: None,
+            dep_idx: None,
+        }
+    }
+
+    pub(crate) fn incarnation(mut self, incarnation: Incarnation) -> Self {
+        self.incarnation = Some(incarnation);
+        self
+    }
+
+    pub(crate) fn dependency(mut self, dep_idx: TxnIndex) -> Self {
+        self.dep_idx = Some(dep_idx);
+        self
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TraceFile<'a> {
+    trace_events: &'a [TraceEvent],
+    display_time_unit: &'static str,
+}
+
+/// Recording of a single block, started by [`begin_block`]. The trace is written out by
+/// [`BlockTrace::finish`].
+pub(crate) struct BlockTrace {
+    path: PathBuf,
+}
+
+/// Starts recording the block if tracing is enabled and no other block is being recorded.
+pub(crate) fn begin_block() -> Option<BlockTrace> {
+    let output_dir = OUTPUT_DIR.lock().clone()?;
+    if RECORDING
+        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
+        .is_err()
+    {
+        return None;
+    }
+
+    *RECORDER.lock() = Some(Recorder {
+        start: Instant::now(),
+        events: Vec::new(),
+    });
+    let seq = NEXT_BLOCK_SEQ.fetch_add(1, Ordering::Relaxed);
+    Some(BlockTrace {
+        path: output_dir.join(format!("block_{}.json", seq)),
+    })
+}
+
+impl BlockTrace {
+    /// Stops recording and writes the trace of the block.
+    pub(crate) fn finish(self) {
+        let recorder = RECORDER.lock().take();
+        RECORDING.store(false, Ordering::Release);
+
+        if let Some(recorder) = recorder {
+            match write_trace(&self.path, &recorder.events) {
+                Ok(()) => info!(
+                    "Wrote {} block executor trace events to {:?}",
+                    recorder.events.len(),
+                    self.path
+                ),
+                Err(e) => error!(
+                    "Failed to write the block executor trace to {:?}: {:?}",
+                    self.path, e
+                ),
+            }
+        }
+    }
+}
+
+fn write_trace(path: &Path, events: &[TraceEvent]) -> Result<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(writer, &TraceFile {
+        trace_events: events,
+        display_time_unit: "ns",
+    })?;
+    Ok(())
+}
+
+fn record(name: &'static str, start: Option<Instant>, args: TraceArgs) {
+    let tid = THREAD_ID.with(|id| *id);
+    if let Some(recorder) = RECORDER.lock().as_mut() {
+        let micros_since = |instant: Instant| {
+            instant
+                .saturating_duration_since(recorder.start)
+                .as_secs_f64()
+                * 1_000_000.0
+        };
+        let now = micros_since(Instant::now());
+        let (ph, ts, dur) = match start {
+            Some(start) => {
+                let ts = micros_since(start);
+                ("X", ts, Some(now - ts))
+            },
+            None => ("i", now, None),
+        };
+        recorder.events.push(TraceEvent {
+            name,
+            cat: "block_stm",
+            ph,
+            ts,
+            dur,
+            pid: 0,
+            tid,
+            args,
+        });
+    }
+}
+
+/// Records an instant event, e.g. an abort or a wake-up.
+#[inline]
+pub(crate) fn instant(name: &'static str, args: TraceArgs) {
+    if RECORDING.load(Ordering::Relaxed) {
+        record(name, None, args);
+    }
+}
+
+/// Records a complete event spanning until the returned guard is dropped.
+#[inline]
+pub(crate) fn span(name: &'static str, args: TraceArgs) -> Option<Span> {
+    RECORDING.load(Ordering::Relaxed).then(|| Span {
+        name,
+        start: Instant::now(),
+        args,
+    })
+}
+
+pub(crate) struct Span {
+    name: &'static str,
+    start: Instant,
+    args: TraceArgs,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        record(self.name, Some(self.start), self.args);
+    }
+}
@@ -3,7 +3,8 @@
 
 use aptos_metrics_core::{
     exponential_buckets, register_histogram, register_histogram_vec, register_int_counter,
-    register_int_counter_vec, Histogram, HistogramVec, IntCounter, IntCounterVec,
+    register_int_counter_vec, register_int_gauge, Histogram, HistogramVec, IntCounter,
+    IntCounterVec, IntGauge,
 };
 use aptos_types::fee_statement::FeeStatement;
 use once_cell::sync::Lazy;
@@ -47,6 +48,26 @@ fn output_buckets() -> std::vec::Vec<f64> {
     .unwrap()
 }
 
+/// Count of transactions in sequential execution that read or wrote a module that is both read
+/// and written in the block.
+pub static MODULE_RW_CONFLICT_TXN_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_module_rw_conflict_txn_count",
+        "Count of txns touching a module read and written in the block (sequential execution)"
+    )
+    .unwrap()
+});
+
+/// Count of the modules with a read/write conflict touched by the transactions counted in
+/// MODULE_RW_CONFLICT_TXN_COUNT.
+pub static MODULE_RW_CONFLICT_MODULE_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_module_rw_conflict_module_count",
+        "Count of conflicting modules touched by txns in sequential execution"
+    )
+    .unwrap()
+});
+
 /// Count of times the module publishing fallback was triggered in parallel execution.
 pub static MODULE_PUBLISHING_FALLBACK_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
@@ -65,6 +86,175 @@ pub static SPECULATIVE_ABORT_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of validations skipped for transactions of pre-partitioned blocks.
+pub static PARTITIONED_VALIDATION_SKIP_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_partitioned_validation_skip_count",
+        "Number of redundant validations of partitioned transactions skipped in parallel execution"
+    )
+    .unwrap()
+});
+
+/// Count of validations failed by the early (speculative) validation of delayed field reads.
+pub static EARLY_DELAYED_FIELD_VALIDATION_FAILURE_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_early_delayed_field_validation_failure_count",
+        "Number of validations failed early due to stale delayed field reads in parallel execution"
+    )
+    .unwrap()
+});
+
+/// Count of transaction executions that exceeded the per-transaction execution timeout.
+pub static TXN_EXECUTION_TIMEOUT_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_execution_txn_timeout_count",
+        "Number of transaction executions that exceeded the execution timeout, by outcome",
+        &["outcome"]
+    )
+    .unwrap()
+});
+
+/// Count of resource group members whose serialization was reused from a previous commit.
+pub static GROUP_SERIALIZATION_CACHE_HIT_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_group_serialization_cache_hit_count",
+        "Number of resource group members not re-serialized when committing their group"
+    )
+    .unwrap()
+});
+
+/// Count of blocks halted by the transaction commit hook.
+pub static COMMIT_HOOK_HALT_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_commit_hook_halt_count",
+        "Number of blocks halted by the transaction commit hook before the last transaction"
+    )
+    .unwrap()
+});
+
+/// Count of incarnations soft-failed by the early validation of their delayed field reads,
+/// whose re-execution is deferred until commit (or until their other reads are invalidated).
+pub static SOFT_FAILED_TXN_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_soft_failed_txn_count",
+        "Number of incarnations soft-failed by the early validation of delayed field reads"
+    )
+    .unwrap()
+});
+
+/// Count of transactions deferred to be executed at commit, after reaching the maximum number of
+/// incarnations.
+pub static DEFERRED_TXN_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_deferred_txn_count",
+        "Number of transactions whose speculative execution stopped at the maximum incarnations"
+    )
+    .unwrap()
+});
+
+/// Count of executions without writes, validated once at commit instead of in validation waves.
+pub static READ_ONLY_EXECUTION_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_read_only_execution_count",
+        "Number of incarnations whose output contains no writes"
+    )
+    .unwrap()
+});
+
+/// Count of transactions in the conflict-free prefixes of the blocks (per their access hints),
+/// validated once at commit instead of in validation waves.
+pub static CONFLICT_FREE_PREFIX_TXN_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_conflict_free_prefix_txn_count",
+        "Number of transactions in the hinted conflict-free prefixes of the blocks"
+    )
+    .unwrap()
+});
+
+/// Count of expired transactions discarded without being executed.
+pub static EXPIRED_TXN_DISCARD_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_execution_expired_txn_discard_count",
+        "Number of transactions discarded before execution as expired at the block timestamp",
+        &["mode"]
+    )
+    .unwrap()
+});
+
+/// Count of transactions discarded as their output exceeded the per-transaction output limit.
+pub static TXN_OUTPUT_LIMIT_DISCARD_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_execution_txn_output_limit_discard_count",
+        "Number of transaction outputs discarded as exceeding the per-transaction output limit",
+        &["mode"]
+    )
+    .unwrap()
+});
+
+/// Count of transaction outputs released to the commit hook ahead of their commit.
+pub static EARLY_RELEASE_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_early_release_count",
+        "Number of transaction outputs released to the commit hook ahead of their commit"
+    )
+    .unwrap()
+});
+
+/// Count of early released transaction outputs that were not committed.
+pub static EARLY_RELEASE_REVOKED_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_early_release_revoked_count",
+        "Number of early released transaction outputs that were not committed"
+    )
+    .unwrap()
+});
+
+/// Number of committed transactions waiting to be materialized, observed when popping them.
+pub static COMMIT_QUEUE_DEPTH: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "aptos_execution_commit_queue_depth",
+        "Number of committed transactions in the commit queue when popping from it",
+        output_buckets(),
+    )
+    .unwrap()
+});
+
+/// Count of pops from the commit queue retried because another worker popped concurrently.
+pub static COMMIT_QUEUE_POP_CONTENTION_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_commit_queue_pop_contention_count",
+        "Number of pops from the commit queue retried due to a concurrent pop"
+    )
+    .unwrap()
+});
+
+/// Count of commit-time re-executions handed off by the commit coordinator to another worker.
+pub static COMMIT_REEXECUTION_HANDOFF_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_commit_reexecution_handoff_count",
+        "Number of commit-time re-executions handed off to another worker"
+    )
+    .unwrap()
+});
+
+/// Count of executions stopped on a dependency, to be executed again once it is resolved.
+pub static DEPENDENCY_REQUEUE_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_dependency_requeue_count",
+        "Number of executions requeued on a dependency instead of waiting"
+    )
+    .unwrap()
+});
+
+/// Count of reads that went past an estimate instead of waiting on the dependency.
+pub static PAST_ESTIMATE_READ_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_past_estimate_read_count",
+        "Number of reads that went past an estimate instead of waiting on the dependency"
+    )
+    .unwrap()
+});
+
 /// Count of times the BlockSTM is early halted due to exceeding the per-block gas limit.
 pub static EXCEED_PER_BLOCK_GAS_LIMIT_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -85,6 +275,38 @@ pub static EXCEED_PER_BLOCK_OUTPUT_LIMIT_COUNT: Lazy<IntCounterVec> = Lazy::new(
     .unwrap()
 });
 
+/// Count of times the BlockSTM is early halted due to exceeding the per-block limit on the
+/// execution gas, IO gas or storage fee.
+pub static EXCEED_PER_BLOCK_CATEGORY_LIMIT_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_execution_category_limit_count",
+        "Count of times the BlockSTM is early halted due to exceeding a per-block category limit",
+        &["mode", "category"]
+    )
+    .unwrap()
+});
+
+/// Count of times the BlockSTM is early halted due to exceeding the wall-clock time budget of
+/// committing the block.
+pub static EXCEED_PER_BLOCK_TIME_BUDGET_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_execution_time_budget_count",
+        "Count of times the BlockSTM is early halted due to exceeding the block time budget",
+        &["mode"]
+    )
+    .unwrap()
+});
+
+/// Count of times the BlockSTM is early halted due to the multi-version data-structure exceeding
+/// its memory soft cap.
+pub static EXCEED_MVHASHMAP_MEMORY_SOFT_CAP_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_mvhashmap_memory_soft_cap_count",
+        "Count of times the BlockSTM is early halted due to exceeding the MVHashMap memory soft cap"
+    )
+    .unwrap()
+});
+
 pub static PARALLEL_EXECUTION_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         // metric name
@@ -96,6 +318,17 @@ pub static PARALLEL_EXECUTION_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static BASE_VALUE_PREFETCH_SECONDS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        // metric name
+        "aptos_execution_base_value_prefetch_seconds",
+        // metric description
+        "The time spent in seconds prefetching base values before parallel execution",
+        time_buckets(),
+    )
+    .unwrap()
+});
+
 pub static RAYON_EXECUTION_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         // metric name
@@ -107,6 +340,57 @@ pub static RAYON_EXECUTION_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of the blocks executed both in parallel and sequentially by differential execution, by
+/// whether their outputs diverged.
+pub static DIFFERENTIAL_EXECUTION_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_execution_differential_execution_count",
+        "Number of blocks compared by differential execution, by outcome",
+        &["outcome"]
+    )
+    .unwrap()
+});
+
+/// Count of the threads of the executor thread pool pinned to a core for parallel execution
+/// (see CoreAffinityConfig), by outcome.
+pub static WORKER_THREAD_PIN_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_execution_worker_thread_pin_count",
+        "Number of times a worker thread of parallel execution was pinned to a core, by outcome",
+        &["outcome"]
+    )
+    .unwrap()
+});
+
+/// Count of the reads of base values served by the hot-state cache shared across blocks (see
+/// HotStateCache), by result (hit or miss).
+pub static HOT_STATE_CACHE_READ_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_execution_hot_state_cache_read_count",
+        "Number of base value reads through the hot-state cache, by result",
+        &["result"]
+    )
+    .unwrap()
+});
+
+/// Count of the entries of the hot-state cache invalidated by the writes of committed blocks.
+pub static HOT_STATE_CACHE_INVALIDATION_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_hot_state_cache_invalidation_count",
+        "Number of hot-state cache entries invalidated by committed writes"
+    )
+    .unwrap()
+});
+
+/// Count of executors reused from a previous block instead of being initialized.
+pub static EXECUTOR_CACHE_HIT_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_executor_cache_hit_count",
+        "Number of executors reused across blocks by the block executor"
+    )
+    .unwrap()
+});
+
 pub static VM_INIT_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         // metric name
@@ -160,6 +444,42 @@ pub static DEPENDENCY_WAIT_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of active workers in parallel execution with adaptive concurrency.
+pub static ADAPTIVE_CONCURRENCY_LEVEL: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_execution_adaptive_concurrency_level",
+        "Number of active (not parked) workers in parallel execution with adaptive concurrency",
+    )
+    .unwrap()
+});
+
+/// Number of commit events buffered for the buffered transaction commit hook.
+pub static COMMIT_HOOK_BUFFERED_EVENTS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "aptos_execution_commit_hook_buffered_events",
+        "Number of commit events buffered for the buffered transaction commit hook",
+    )
+    .unwrap()
+});
+
+/// Count of times the commit pipeline blocked on a full commit hook buffer.
+pub static COMMIT_HOOK_BUFFER_FULL_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_execution_commit_hook_buffer_full_count",
+        "Count of times the commit pipeline blocked on a full commit hook buffer",
+    )
+    .unwrap()
+});
+
+pub static COMMIT_HOOK_BACKPRESSURE_SECONDS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "aptos_execution_commit_hook_backpressure_seconds",
+        "The time the commit pipeline spent blocked on a full commit hook buffer",
+        time_buckets(),
+    )
+    .unwrap()
+});
+
 pub static BLOCK_GAS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         "aptos_execution_block_gas",
@@ -0,0 +1,86 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::task::TransactionOutput;
+use aptos_mvhashmap::types::TxnIndex;
+
+/// The first transaction of a block whose committed outputs differ between the parallel and the
+/// sequential execution of the block (see differential_execution in the local config).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExecutionDivergence {
+    pub txn_idx: TxnIndex,
+    /// The difference between the parallel and the sequential output of the transaction (see
+    /// [`TransactionOutput::committed_output_divergence`]).
+    pub details: String,
+}
+
+/// Compares the outputs of the parallel and the sequential execution of a block, transaction by
+/// transaction, and returns the first divergence.
+pub(crate) fn first_divergence<O: TransactionOutput>(
+    parallel_outputs: &[O],
+    sequential_outputs: &[O],
+) -> Option<ExecutionDivergence> {
+    let divergence = parallel_outputs
+        .iter()
+        .zip(sequential_outputs)
+        .enumerate()
+        .find_map(|(txn_idx, (parallel_output, sequential_output))| {
+            parallel_output
+                .committed_output_divergence(sequential_output)
+                .map(|details| ExecutionDivergence {
+                    txn_idx: txn_idx as TxnIndex,
+                    details,
+                })
+        });
+    if divergence.is_none() && parallel_outputs.len() != sequential_outputs.len() {
+        return Some(ExecutionDivergence {
+            txn_idx: parallel_outputs.len().min(sequential_outputs.len()) as TxnIndex,
+            details: format!(
+                "{} outputs in parallel execution != {} outputs in sequential execution",
+                parallel_outputs.len(),
+                sequential_outputs.len()
+            ),
+        });
+    }
+    divergence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest_types::types::{KeyType, MockEvent, MockOutput};
+
+    type Output = MockOutput<KeyType<u32>, MockEvent>;
+
+    fn output_with_gas(total_gas: u64) -> Output {
+        Output {
+            total_gas,
+            skipped: false,
+            ..Output::skip_output()
+        }
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        let parallel_outputs = vec![output_with_gas(1), output_with_gas(2), output_with_gas(3)];
+        let sequential_outputs = vec![output_with_gas(1), output_with_gas(2), output_with_gas(3)];
+        assert_eq!(
+            first_divergence(&parallel_outputs, &sequential_outputs),
+            None
+        );
+
+        let sequential_outputs = vec![output_with_gas(1), output_with_gas(4), output_with_gas(5)];
+        assert_eq!(
+            first_divergence(&parallel_outputs, &sequential_outputs),
+            Some(ExecutionDivergence {
+                txn_idx: 1,
+                details: "gas: 2 != 4".to_string(),
+            })
+        );
+
+        assert_eq!(
+            first_divergence(&parallel_outputs, &parallel_outputs[..2]).map(|d| d.txn_idx),
+            Some(2)
+        );
+    }
+}
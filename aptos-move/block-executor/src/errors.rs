@@ -2,6 +2,8 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use aptos_aggregator::types::PanicOr;
+use aptos_mvhashmap::types::TxnIndex;
 use aptos_types::delayed_fields::PanicError;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -10,23 +12,109 @@ pub(crate) enum ParallelBlockExecutionError {
     // This may trigger a race due to the Move-VM loader cache implementation, and mitigation requires
     // aborting the parallel execution pipeline and falling back to the sequential execution.
     // TODO: provide proper multi-versioning for code (like data) for the cache.
-    ModulePathReadWriteError,
+    ModulePathReadWriteError {
+        txn_idx: TxnIndex,
+    },
     /// unrecoverable VM error
-    FatalVMError,
+    FatalVMError {
+        txn_idx: TxnIndex,
+        message: String,
+    },
+}
+
+/// The first fatal error of a failed parallel execution of a block, with its context, which
+/// determines the fallback policy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParallelExecutionFailure {
+    /// A code invariant was violated, with the message of the error.
+    CodeInvariantError(String),
+    /// A module was both read and written, first observed when the incarnation of the
+    /// transaction at txn_idx was recorded.
+    ModulePathReadWriteError { txn_idx: TxnIndex },
+    /// The transaction at txn_idx failed with an unrecoverable VM error (with its debug
+    /// representation, as the error type is generic).
+    FatalVMError { txn_idx: TxnIndex, message: String },
+    /// The block was cancelled through its cancellation token (there is no fallback).
+    Cancelled,
+}
+
+impl From<&PanicOr<ParallelBlockExecutionError>> for ParallelExecutionFailure {
+    fn from(err: &PanicOr<ParallelBlockExecutionError>) -> Self {
+        match err {
+            PanicOr::CodeInvariantError(message) => {
+                ParallelExecutionFailure::CodeInvariantError(message.clone())
+            },
+            PanicOr::Or(ParallelBlockExecutionError::ModulePathReadWriteError { txn_idx }) => {
+                ParallelExecutionFailure::ModulePathReadWriteError { txn_idx: *txn_idx }
+            },
+            PanicOr::Or(ParallelBlockExecutionError::FatalVMError { txn_idx, message }) => {
+                ParallelExecutionFailure::FatalVMError {
+                    txn_idx: *txn_idx,
+                    message: message.clone(),
+                }
+            },
+        }
+    }
+}
+
+/// Why the replay of a recorded schedule (see BlockExecutor::replay_schedule) failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScheduleReplayError {
+    /// The schedule was recorded for a block with a different number of transactions, or with
+    /// a different concurrency level.
+    TraceMismatch {
+        trace_num_txns: TxnIndex,
+        trace_concurrency_level: usize,
+        num_txns: usize,
+        concurrency_level: usize,
+    },
+    /// The parallel execution of the block failed. A divergence from the recorded schedule is
+    /// reported as a code invariant error describing it.
+    ExecutionFailure(ParallelExecutionFailure),
 }
 
 // This is separate error because we need to match the error variant to provide a specialized
 // fallback logic if a resource group serialization error occurs.
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub(crate) struct ResourceGroupSerializationError;
+pub(crate) struct ResourceGroupSerializationError {
+    /// Index of the transaction whose output contained the group.
+    pub txn_idx: TxnIndex,
+    /// Debug representation of the group key (the key type is generic).
+    pub group_key: String,
+    /// Number of resources (tags) in the finalized group.
+    pub num_resources: usize,
+    /// Sum of the sizes of the resources in the group, approximating the serialized size.
+    pub approx_size: usize,
+}
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 /// Logging is bottlenecked in constructors.
 pub(crate) enum SequentialBlockExecutionError<E> {
     // This is separate error because we need to match the error variant to provide a specialized
     // fallback logic if a resource group serialization error occurs.
-    ResourceGroupSerializationError,
-    ErrorToReturn(BlockExecutionError<E>),
+    ResourceGroupSerializationError(ResourceGroupSerializationError),
+    ErrorToReturn {
+        error: BlockExecutionError<E>,
+        // Index of the transaction at which the error occurred, if known.
+        txn_idx: Option<TxnIndex>,
+    },
+}
+
+impl<E> SequentialBlockExecutionError<E> {
+    /// Records the index of the transaction at which the error occurred, unless it was
+    /// already recorded.
+    pub(crate) fn at_txn(self, idx: TxnIndex) -> Self {
+        match self {
+            SequentialBlockExecutionError::ErrorToReturn {
+                error,
+                txn_idx: None,
+            } => SequentialBlockExecutionError::ErrorToReturn {
+                error,
+                txn_idx: Some(idx),
+            },
+            err => err,
+        }
+    }
 }
 
 /// If the unrecoverable error occurs during sequential execution (e.g. fallback),
@@ -37,9 +125,19 @@ pub enum BlockExecutionError<E> {
     FatalBlockExecutorError(PanicError),
     /// unrecoverable VM error
     FatalVMError(E),
+    /// block execution was cancelled through its cancellation token
+    Cancelled,
 }
 
-pub type BlockExecutionResult<T, E> = Result<T, BlockExecutionError<E>>;
+/// The unrecoverable error returned by block execution, with the index of the transaction at
+/// which it occurred (if known).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockExecutionFailure<E> {
+    pub error: BlockExecutionError<E>,
+    pub txn_idx: Option<TxnIndex>,
+}
+
+pub type BlockExecutionResult<T, E> = Result<T, BlockExecutionFailure<E>>;
 
 impl<E> From<PanicError> for BlockExecutionError<E> {
     fn from(err: PanicError) -> Self {
@@ -47,10 +145,20 @@ impl<E> From<PanicError> for BlockExecutionError<E> {
     }
 }
 
+impl<E> From<BlockExecutionError<E>> for BlockExecutionFailure<E> {
+    fn from(error: BlockExecutionError<E>) -> Self {
+        BlockExecutionFailure {
+            error,
+            txn_idx: None,
+        }
+    }
+}
+
 impl<E> From<PanicError> for SequentialBlockExecutionError<E> {
     fn from(err: PanicError) -> Self {
-        SequentialBlockExecutionError::ErrorToReturn(BlockExecutionError::FatalBlockExecutorError(
-            err,
-        ))
+        SequentialBlockExecutionError::ErrorToReturn {
+            error: BlockExecutionError::FatalBlockExecutorError(err),
+            txn_idx: None,
+        }
     }
 }
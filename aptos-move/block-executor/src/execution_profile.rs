@@ -0,0 +1,177 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_infallible::Mutex;
+use aptos_mvhashmap::types::{Incarnation, TxnIndex};
+use std::time::Duration;
+
+/// The timings of an incarnation of a transaction.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IncarnationProfile {
+    /// The time spent executing the incarnation (including the dependency waits). If the
+    /// execution was requeued on a dependency, the time of all its executions is added.
+    pub execute_time: Duration,
+    /// The time the execution of the incarnation spent waiting on read dependencies.
+    pub dependency_wait_time: Duration,
+    /// The number of times the incarnation was validated, and the total time spent on it.
+    pub num_validations: u32,
+    pub validate_time: Duration,
+}
+
+/// The timings of a transaction, by incarnation.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TransactionProfile {
+    pub incarnations: Vec<IncarnationProfile>,
+    /// The time spent materializing the output of the committed transaction.
+    pub materialize_time: Duration,
+    // The time waited on dependencies by the incarnation being executed, which is only
+    // known once its execution finishes.
+    pending_dependency_wait_time: Duration,
+}
+
+impl TransactionProfile {
+    /// The total time spent on the transaction by the workers, over all its incarnations.
+    pub fn total_time(&self) -> Duration {
+        self.incarnations
+            .iter()
+            .map(|incarnation| incarnation.execute_time + incarnation.validate_time)
+            .sum::<Duration>()
+            + self.materialize_time
+    }
+
+    fn incarnation_mut(&mut self, incarnation: Incarnation) -> &mut IncarnationProfile {
+        let incarnation = incarnation as usize;
+        if self.incarnations.len() <= incarnation {
+            self.incarnations
+                .resize_with(incarnation + 1, IncarnationProfile::default);
+        }
+        &mut self.incarnations[incarnation]
+    }
+}
+
+/// The per-transaction timings of a parallel block execution, recorded with
+/// profile_transactions set in the local config (see
+/// [`crate::executor::BlockExecutor::take_execution_profile`]). Unlike the block execution
+/// statistics and the counters, which are aggregated over the block, the profile shows e.g.
+/// which transactions were re-executed or waited on dependencies the longest.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlockExecutionProfile {
+    /// The profiles of the transactions of the block, by index.
+    pub transactions: Vec<TransactionProfile>,
+}
+
+impl BlockExecutionProfile {
+    /// Returns the (at most) num_txns transactions that took the longest total time, slowest
+    /// first.
+    pub fn slowest_transactions(&self, num_txns: usize) -> Vec<(TxnIndex, Duration)> {
+        let mut total_times: Vec<_> = self
+            .transactions
+            .iter()
+            .enumerate()
+            .map(|(txn_idx, profile)| (txn_idx as TxnIndex, profile.total_time()))
+            .collect();
+        total_times.sort_by(|(_, a), (_, b)| b.cmp(a));
+        total_times.truncate(num_txns);
+        total_times
+    }
+}
+
+/// Records the profile of a parallel block execution, concurrently from all workers.
+pub(crate) struct ExecutionProfiler {
+    transactions: Vec<Mutex<TransactionProfile>>,
+}
+
+impl ExecutionProfiler {
+    pub(crate) fn new(num_txns: TxnIndex) -> Self {
+        Self {
+            transactions: (0..num_txns)
+                .map(|_| Mutex::new(TransactionProfile::default()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn record_dependency_wait(&self, txn_idx: TxnIndex, wait_time: Duration) {
+        self.transactions[txn_idx as usize]
+            .lock()
+            .pending_dependency_wait_time += wait_time;
+    }
+
+    pub(crate) fn record_execution(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        execute_time: Duration,
+    ) {
+        let mut profile = self.transactions[txn_idx as usize].lock();
+        let dependency_wait_time = std::mem::take(&mut profile.pending_dependency_wait_time);
+        let incarnation = profile.incarnation_mut(incarnation);
+        incarnation.execute_time += execute_time;
+        incarnation.dependency_wait_time += dependency_wait_time;
+    }
+
+    pub(crate) fn record_validation(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        validate_time: Duration,
+    ) {
+        let mut profile = self.transactions[txn_idx as usize].lock();
+        let incarnation = profile.incarnation_mut(incarnation);
+        incarnation.num_validations += 1;
+        incarnation.validate_time += validate_time;
+    }
+
+    pub(crate) fn record_materialization(&self, txn_idx: TxnIndex, materialize_time: Duration) {
+        self.transactions[txn_idx as usize].lock().materialize_time += materialize_time;
+    }
+
+    pub(crate) fn finish(self) -> BlockExecutionProfile {
+        BlockExecutionProfile {
+            transactions: self
+                .transactions
+                .into_iter()
+                .map(Mutex::into_inner)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execution_profiler() {
+        let profiler = ExecutionProfiler::new(3);
+        profiler.record_execution(0, 0, Duration::from_millis(5));
+        profiler.record_validation(0, 0, Duration::from_millis(1));
+        profiler.record_materialization(0, Duration::from_millis(2));
+
+        // The dependency waits are attributed to the incarnation once its execution finishes.
+        profiler.record_execution(1, 0, Duration::from_millis(1));
+        profiler.record_dependency_wait(1, Duration::from_millis(7));
+        profiler.record_execution(1, 1, Duration::from_millis(10));
+        profiler.record_validation(1, 1, Duration::from_millis(1));
+        profiler.record_validation(1, 1, Duration::from_millis(1));
+
+        let profile = profiler.finish();
+        assert_eq!(profile.transactions.len(), 3);
+        assert_eq!(profile.transactions[1].incarnations, vec![
+            IncarnationProfile {
+                execute_time: Duration::from_millis(1),
+                ..IncarnationProfile::default()
+            },
+            IncarnationProfile {
+                execute_time: Duration::from_millis(10),
+                dependency_wait_time: Duration::from_millis(7),
+                num_validations: 2,
+                validate_time: Duration::from_millis(2),
+            },
+        ]);
+        assert_eq!(profile.slowest_transactions(2), vec![
+            (1, Duration::from_millis(13)),
+            (0, Duration::from_millis(8)),
+        ]);
+        assert_eq!(profile.transactions[2].total_time(), Duration::ZERO);
+    }
+}
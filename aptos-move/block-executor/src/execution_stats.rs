@@ -0,0 +1,122 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::errors::ParallelExecutionFailure;
+use aptos_infallible::Mutex;
+use aptos_types::block_executor::config::FallbackPolicy;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Statistics of the execution of a single block, e.g. for embedding services and tests to
+/// assert on the efficiency of the scheduling. Unlike the (global) counters, the statistics
+/// cover all the executions of the block: if a parallel execution fails, the statistics of the
+/// retries and of the fallback (e.g. sequential) execution are added.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlockExecutionStats {
+    /// The number of (speculative) executions of transactions, i.e. of incarnations in
+    /// parallel execution, and of transactions in sequential execution.
+    pub num_incarnations: u64,
+    /// The number of incarnations aborted after a failed validation (and re-executed).
+    pub num_aborts: u64,
+    /// The number of validation waves of parallel execution.
+    pub num_waves: u64,
+    /// The number of times a transaction was suspended on a read dependency.
+    pub num_dependency_waits: u64,
+    /// The total time that workers spent executing transactions.
+    pub execute_time: Duration,
+    /// The total time that workers spent validating transactions.
+    pub validate_time: Duration,
+    /// The total time that workers spent materializing committed transactions.
+    pub materialize_time: Duration,
+    /// The fallbacks taken after failed executions of the block, in order (empty if the first
+    /// execution succeeded).
+    pub fallbacks: Vec<FallbackPolicy>,
+    /// The failures of the parallel executions of the block, in order, each with the first
+    /// fatal error of the execution (e.g. the transaction with the fatal VM error).
+    pub parallel_failures: Vec<ParallelExecutionFailure>,
+}
+
+/// Collects the statistics of the block being executed, concurrently from all workers.
+#[derive(Default)]
+pub(crate) struct BlockExecutionStatsCollector {
+    num_incarnations: AtomicU64,
+    num_aborts: AtomicU64,
+    num_waves: AtomicU64,
+    num_dependency_waits: AtomicU64,
+    execute_nanos: AtomicU64,
+    validate_nanos: AtomicU64,
+    materialize_nanos: AtomicU64,
+    fallbacks: Mutex<Vec<FallbackPolicy>>,
+    parallel_failures: Mutex<Vec<ParallelExecutionFailure>>,
+}
+
+fn add_duration(nanos: &AtomicU64, duration: Duration) {
+    nanos.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+}
+
+impl BlockExecutionStatsCollector {
+    /// Starts collecting the statistics of a new block.
+    pub(crate) fn reset(&self) {
+        for counter in [
+            &self.num_incarnations,
+            &self.num_aborts,
+            &self.num_waves,
+            &self.num_dependency_waits,
+            &self.execute_nanos,
+            &self.validate_nanos,
+            &self.materialize_nanos,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        self.fallbacks.lock().clear();
+        self.parallel_failures.lock().clear();
+    }
+
+    pub(crate) fn record_execution(&self, duration: Duration) {
+        self.num_incarnations.fetch_add(1, Ordering::Relaxed);
+        add_duration(&self.execute_nanos, duration);
+    }
+
+    pub(crate) fn record_validation(&self, duration: Duration) {
+        add_duration(&self.validate_nanos, duration);
+    }
+
+    pub(crate) fn record_abort(&self) {
+        self.num_aborts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_materialization(&self, duration: Duration) {
+        add_duration(&self.materialize_nanos, duration);
+    }
+
+    /// Records the statistics kept by the scheduler of a finished parallel execution.
+    pub(crate) fn record_scheduler(&self, num_waves: u64, num_dependency_waits: u64) {
+        self.num_waves.fetch_add(num_waves, Ordering::Relaxed);
+        self.num_dependency_waits
+            .fetch_add(num_dependency_waits, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_fallback(&self, policy: FallbackPolicy) {
+        self.fallbacks.lock().push(policy);
+    }
+
+    pub(crate) fn record_parallel_failure(&self, failure: ParallelExecutionFailure) {
+        self.parallel_failures.lock().push(failure);
+    }
+
+    pub(crate) fn snapshot(&self) -> BlockExecutionStats {
+        BlockExecutionStats {
+            num_incarnations: self.num_incarnations.load(Ordering::Relaxed),
+            num_aborts: self.num_aborts.load(Ordering::Relaxed),
+            num_waves: self.num_waves.load(Ordering::Relaxed),
+            num_dependency_waits: self.num_dependency_waits.load(Ordering::Relaxed),
+            execute_time: Duration::from_nanos(self.execute_nanos.load(Ordering::Relaxed)),
+            validate_time: Duration::from_nanos(self.validate_nanos.load(Ordering::Relaxed)),
+            materialize_time: Duration::from_nanos(self.materialize_nanos.load(Ordering::Relaxed)),
+            fallbacks: self.fallbacks.lock().clone(),
+            parallel_failures: self.parallel_failures.lock().clone(),
+        }
+    }
+}
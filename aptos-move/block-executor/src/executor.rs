@@ -3,20 +3,40 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    adaptive_concurrency::AdaptiveConcurrency,
+    affinity::CoreAffinity,
+    base_value_resolver::BaseValueResolver,
+    block_state::{BlockPrefixState, ExecutedBlockState},
+    cancellation::CancellationToken,
+    chrome_trace::{self, TraceArgs},
     counters,
     counters::{
         PARALLEL_EXECUTION_SECONDS, RAYON_EXECUTION_SECONDS, TASK_EXECUTE_SECONDS,
-        TASK_VALIDATE_SECONDS, VM_INIT_SECONDS, WORK_WITH_TASK_SECONDS,
+        TASK_VALIDATE_SECONDS, WORK_WITH_TASK_SECONDS,
     },
+    differential_execution::{first_divergence, ExecutionDivergence},
     errors::*,
+    execution_profile::{BlockExecutionProfile, ExecutionProfiler},
+    execution_stats::{BlockExecutionStats, BlockExecutionStatsCollector},
+    executor_cache::ExecutorCache,
     executor_utilities::*,
     explicit_sync_wrapper::ExplicitSyncWrapper,
-    limit_processor::BlockGasLimitProcessor,
+    group_serialization_cache::GroupSerializationCache,
+    hot_state_cache::HotStateCache,
+    limit_processor::{BlockGasLimitProcessor, BlockLimitPolicy, BlockLimitPolicyFactory},
+    schedule_trace::{ScheduleTrace, ScheduleTracer},
     scheduler::{DependencyStatus, ExecutionTaskType, Scheduler, SchedulerTask, Wave},
     task::{ExecutionStatus, ExecutorTask, TransactionOutput},
-    txn_commit_hook::TransactionCommitHook,
-    txn_last_input_output::{KeyKind, TxnLastInputOutput},
-    types::ReadWriteSummary,
+    txn_commit_hook::{
+        handle_commit_hook_result, CommitDecision, CommittedOutputStream,
+        NoOpTransactionCommitHook, StreamedOutput, TransactionCommitHook,
+    },
+    txn_last_input_output::{KeyKind, RecordError, TxnLastInputOutput},
+    txn_provider::TxnProvider,
+    types::{
+        conflict_free_prefix, gas_price_priority, hinted_dependencies, BlockHints, ConflictGraph,
+        ConflictGraphBuilder, ReadWriteSummary,
+    },
     view::{LatestView, ParallelState, SequentialState, ViewState},
 };
 use aptos_aggregator::{
@@ -25,7 +45,8 @@ use aptos_aggregator::{
     types::{code_invariant_error, expect_ok, PanicOr},
 };
 use aptos_drop_helper::DEFAULT_DROPPER;
-use aptos_logger::{debug, error, info};
+use aptos_infallible::Mutex;
+use aptos_logger::{debug, error, info, warn};
 use aptos_mvhashmap::{
     types::{Incarnation, MVDelayedFieldsError, TxnIndex, ValueWithLayout},
     unsync_map::UnsyncMap,
@@ -33,39 +54,129 @@ use aptos_mvhashmap::{
     MVHashMap,
 };
 use aptos_types::{
-    block_executor::config::BlockExecutorConfig,
+    block_executor::config::{
+        BlockExecutorConfig, DependencyWaitConfig, ExecutionOverrides, FallbackPolicy,
+    },
     delayed_fields::PanicError,
     executable::Executable,
     on_chain_config::BlockGasLimitType,
-    state_store::{state_value::StateValue, TStateView},
-    transaction::{BlockExecutableTransaction as Transaction, BlockOutput},
+    state_store::{state_read_stats::StateReadStats, state_value::StateValue, TStateView},
+    transaction::{
+        BlockEndInfo, BlockExecutableTransaction as Transaction, BlockLimitUsage, BlockOutput,
+        SkipReason,
+    },
     write_set::{TransactionWrite, WriteOp},
 };
 use aptos_vm_logging::{alert, clear_speculative_txn_logs, init_speculative_logs, prelude::*};
-use aptos_vm_types::change_set::randomly_check_layout_matches;
+use aptos_vm_types::{block_seed::with_block_seed, change_set::randomly_check_layout_matches};
 use bytes::Bytes;
 use claims::assert_none;
 use core::panic;
+use crossbeam::channel::Receiver;
 use fail::fail_point;
 use move_core_types::{value::MoveTypeLayout, vm_status::StatusCode};
 use num_cpus;
 use rayon::ThreadPool;
 use std::{
     cell::RefCell,
+    cmp::{max, min},
     collections::{BTreeMap, HashMap, HashSet},
     marker::{PhantomData, Sync},
     sync::{
-        atomic::{AtomicBool, AtomicU32, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
         Arc,
     },
+    time::{Duration, Instant},
 };
 
-pub struct BlockExecutor<T, E, S, L, X> {
+/// The maximum number of committed transactions that a worker drains from the commit queue at
+/// once, so that e.g. the deltas of a hot aggregator v1 are materialized for all of them in one
+/// pass.
+const MAX_COMMIT_BATCH_SIZE: usize = 16;
+
+/// The number of transactions after the next transaction to commit that are considered for an
+/// early release (see [`TransactionCommitHook::on_transaction_released`]).
+const EARLY_RELEASE_WINDOW: TxnIndex = 32;
+
+/// The timestamp (in seconds) of a block, set by its block metadata transaction at txn_idx and
+/// observed by the transactions that follow it.
+#[derive(Clone, Copy)]
+struct BlockTimestamp {
+    txn_idx: TxnIndex,
+    secs: u64,
+}
+
+impl BlockTimestamp {
+    fn from_block<T: Transaction>(block: &dyn TxnProvider<T>) -> Option<Self> {
+        block.iter().enumerate().find_map(|(txn_idx, txn)| {
+            txn.block_timestamp_usecs().map(|usecs| Self {
+                txn_idx: txn_idx as TxnIndex,
+                secs: usecs / 1_000_000,
+            })
+        })
+    }
+
+    /// Whether the transaction at txn_idx expired before the block timestamp. Expired
+    /// transactions are discarded without being executed, as the VM would discard them in the
+    /// prologue anyway.
+    fn is_expired<T: Transaction>(&self, txn: &T, txn_idx: TxnIndex) -> bool {
+        txn_idx > self.txn_idx
+            && txn
+                .expiration_timestamp_secs()
+                .map_or(false, |expiration_secs| expiration_secs <= self.secs)
+    }
+}
+
+/// The updates of an execution to the multi-versioned data-structure (see
+/// [`BlockExecutor::execute`]).
+#[derive(Clone, Copy)]
+struct ExecutionUpdates {
+    /// Whether the execution wrote outside the write set of the previous incarnation.
+    updates_outside: bool,
+    /// Whether the output contains no writes, deltas or delayed field changes at all, in which
+    /// case the incarnation is only validated at commit (see
+    /// [`Scheduler::finish_read_only_execution`]).
+    read_only: bool,
+}
+
+pub struct BlockExecutor<T: Transaction, E, S, L, X> {
     // Number of active concurrent tasks, corresponding to the maximum number of rayon
     // threads that may be concurrently participating in parallel execution.
     config: BlockExecutorConfig,
     executor_thread_pool: Arc<ThreadPool>,
+    // The thread pool of the workers dedicated to materialization (see materialization_concurrency
+    // in the local config). If None, these workers run on the executor thread pool.
+    materialization_thread_pool: Option<Arc<ThreadPool>>,
     transaction_commit_hook: Option<L>,
+    // The conflict graph of the committed transactions of the last executed block (if recorded).
+    conflict_graph: Mutex<Option<ConflictGraphBuilder<T>>>,
+    // The schedule of the last parallel block execution (if recorded).
+    schedule_trace: Mutex<Option<ScheduleTrace>>,
+    // The per-transaction timings of the last parallel block execution (if profiled).
+    execution_profile: Mutex<Option<BlockExecutionProfile>>,
+    // The serialized members of the resource groups committed in the block being executed.
+    group_serialization_cache: GroupSerializationCache<T>,
+    // The statistics of the block being executed, and of the last executed block.
+    execution_stats: BlockExecutionStatsCollector,
+    last_execution_stats: Mutex<Option<BlockExecutionStats>>,
+    // The incarnations of the transactions whose outputs were released early to the commit hook
+    // in the block being executed, until they are committed.
+    released_txns: Mutex<BTreeMap<TxnIndex, Incarnation>>,
+    // The executors of the workers, reused across blocks (if the executor task allows it).
+    executor_cache: ExecutorCache<E>,
+    // The cores that the threads running the execution workers are pinned to (if configured).
+    core_affinity: Option<CoreAffinity>,
+    // The first divergence between the parallel and sequential outputs of the last executed
+    // block (with differential execution).
+    execution_divergence: Mutex<Option<ExecutionDivergence>>,
+    // The cache of base values shared across blocks (if set), see HotStateCache.
+    hot_state_cache: Option<Arc<HotStateCache<T::Key>>>,
+    // Creates the block limit policy of every block (if set), instead of BlockGasLimitProcessor.
+    block_limit_policy_factory: Option<BlockLimitPolicyFactory<T>>,
+    // The number of transactions committed by the last parallel execution, and the state of
+    // the last block executed in parallel (if retained).
+    num_committed_txns: AtomicU32,
+    block_state: Mutex<Option<ExecutedBlockState<T, X>>>,
     phantom: PhantomData<(T, E, S, L, X)>,
 }
 
@@ -77,8 +188,9 @@ where
     L: TransactionCommitHook<Output = E::Output>,
     X: Executable + 'static,
 {
-    /// The caller needs to ensure that concurrency_level > 1 (0 is illegal and 1 should
-    /// be handled by sequential execution) and that concurrency_level <= num_cpus.
+    /// The caller needs to ensure that concurrency_level > 0 and that concurrency_level <=
+    /// num_cpus. A concurrency level of 1 is handled by sequential execution, unless
+    /// parallel_single_worker is set in the local config.
     pub fn new(
         config: BlockExecutorConfig,
         executor_thread_pool: Arc<ThreadPool>,
@@ -89,30 +201,236 @@ where
             "Parallel execution concurrency level {} should be between 1 and number of CPUs",
             config.local.concurrency_level
         );
+        let core_affinity = config
+            .local
+            .core_affinity
+            .as_ref()
+            .and_then(CoreAffinity::new);
         Self {
             config,
             executor_thread_pool,
+            materialization_thread_pool: None,
             transaction_commit_hook,
+            conflict_graph: Mutex::new(None),
+            schedule_trace: Mutex::new(None),
+            execution_profile: Mutex::new(None),
+            group_serialization_cache: GroupSerializationCache::new(),
+            execution_stats: BlockExecutionStatsCollector::default(),
+            last_execution_stats: Mutex::new(None),
+            released_txns: Mutex::new(BTreeMap::new()),
+            executor_cache: ExecutorCache::new(),
+            core_affinity,
+            execution_divergence: Mutex::new(None),
+            hot_state_cache: None,
+            block_limit_policy_factory: None,
+            num_committed_txns: AtomicU32::new(0),
+            block_state: Mutex::new(None),
             phantom: PhantomData,
         }
     }
 
+    /// Sets the thread pool on which the workers dedicated to materializing the committed
+    /// transactions run, if materialization_concurrency is set in the local config, so that
+    /// e.g. serializing the outputs does not take threads from the execution workers.
+    pub fn with_materialization_thread_pool(mut self, pool: Arc<ThreadPool>) -> Self {
+        self.materialization_thread_pool = Some(pool);
+        self
+    }
+
+    /// Sets the cache of base values shared with the blocks executed before and after (by this
+    /// or other block executors), which is consulted before the base view. Every block must be
+    /// executed under a lease of the cache on its parent state (see [`HotStateCache::lease`]).
+    pub fn with_hot_state_cache(mut self, hot_state_cache: Arc<HotStateCache<T::Key>>) -> Self {
+        self.hot_state_cache = Some(hot_state_cache);
+        self
+    }
+
+    /// Sets the policy that decides when to end the blocks early, created for every block from
+    /// its block gas limit type and number of transactions, instead of the default
+    /// [`BlockGasLimitProcessor`] (see [`BlockLimitPolicy`]). The policy applies to both parallel
+    /// and sequential execution, and must be deterministic for the outputs to be deterministic.
+    /// The block time budget of the local config only applies to the default policy.
+    pub fn with_block_limit_policy(mut self, factory: BlockLimitPolicyFactory<T>) -> Self {
+        self.block_limit_policy_factory = Some(factory);
+        self
+    }
+
+    fn new_block_limit_policy(
+        &self,
+        block_gas_limit_type: &BlockGasLimitType,
+        num_txns: usize,
+    ) -> Box<dyn BlockLimitPolicy<T>> {
+        match &self.block_limit_policy_factory {
+            Some(factory) => factory(block_gas_limit_type.clone(), num_txns),
+            None => {
+                let processor = BlockGasLimitProcessor::new(block_gas_limit_type.clone(), num_txns);
+                Box::new(match self.config.local.block_time_budget {
+                    Some(time_budget) => processor.with_time_budget(time_budget),
+                    None => processor,
+                })
+            },
+        }
+    }
+
+    /// Drops the executors cached for the next blocks (see [`ExecutorTask::argument_fingerprint`]),
+    /// e.g. when the on-chain configs or features change. Must be called between blocks.
+    pub fn invalidate_cached_executors(&self) {
+        self.executor_cache.invalidate();
+    }
+
+    /// Returns the read/write conflict graph of the last executed block, if it was executed
+    /// successfully with record_conflict_graph set in the local config.
+    pub fn take_conflict_graph(&self) -> Option<ConflictGraph> {
+        self.conflict_graph
+            .lock()
+            .take()
+            .map(ConflictGraphBuilder::build)
+    }
+
+    /// Returns the state of the last executed block, if it was executed in parallel successfully
+    /// with retain_block_state set in the local config.
+    pub fn take_block_state(&self) -> Option<ExecutedBlockState<T, X>> {
+        self.block_state.lock().take()
+    }
+
+    /// Executes the transaction against the state of a committed prefix of a block (taken with
+    /// [`BlockExecutor::take_block_state`]), as if it were at the position following the prefix
+    /// in the block, e.g. to find out why a transaction of the block was reverted. The block is
+    /// not modified. The output is not materialized: e.g. it contains the aggregator v1 deltas
+    /// and delayed field changes as such. The base view must be the one the block was executed
+    /// against.
+    pub fn execute_single_at(
+        &self,
+        executor_arguments: E::Argument,
+        base_view: &S,
+        prefix_state: &BlockPrefixState<'_, T, X>,
+        txn: &T,
+    ) -> ExecutionStatus<E::Output, E::Error> {
+        let block_state = prefix_state.block_state;
+        let txn_idx = prefix_state.txn_idx;
+        // The writes of the committed transactions are final, so the transaction does not depend
+        // on another one. The scheduler is halted, so that a read of an unexpected estimate
+        // fails the execution rather than waiting for the dependency.
+        let scheduler = Scheduler::new(txn_idx + 1);
+        scheduler.halt();
+        let shared_counter = AtomicU32::new(block_state.shared_counter);
+
+        let view = LatestView::new(
+            base_view,
+            ViewState::Sync(ParallelState::new(
+                &block_state.versioned_cache,
+                &scheduler,
+                block_state.start_shared_counter,
+                &shared_counter,
+            )),
+            txn_idx,
+        )
+        .with_hot_state_cache(self.hot_state_cache.as_deref());
+        let executor = self.executor_cache.take(executor_arguments);
+        with_block_seed(self.config.local.block_seed, || {
+            executor.execute_transaction(&view, txn, txn_idx)
+        })
+    }
+
+    /// Returns the schedule of the last parallel block execution, if it was recorded with
+    /// record_schedule set in the local config. The schedule is recorded also when the parallel
+    /// execution fails (e.g. with a code invariant error), to reproduce the failure with
+    /// [`BlockExecutor::replay_schedule`].
+    pub fn take_schedule_trace(&self) -> Option<ScheduleTrace> {
+        self.schedule_trace.lock().take()
+    }
+
+    /// Returns the statistics of the last executed block (whether it was executed successfully
+    /// or not).
+    pub fn take_execution_stats(&self) -> Option<BlockExecutionStats> {
+        self.last_execution_stats.lock().take()
+    }
+
+    /// Returns the first transaction whose outputs differ between the parallel and sequential
+    /// execution of the last executed block, if it was executed with differential_execution set
+    /// in the local config (see [`TransactionOutput::committed_output_divergence`]).
+    pub fn take_execution_divergence(&self) -> Option<ExecutionDivergence> {
+        self.execution_divergence.lock().take()
+    }
+
+    /// Starts recording the conflict graph (if enabled) from the first transaction of the block.
+    fn reset_conflict_graph(&self) {
+        *self.conflict_graph.lock() = self
+            .config
+            .local
+            .record_conflict_graph
+            .then(ConflictGraphBuilder::new);
+    }
+
+    /// Returns the per-transaction timings of the last parallel block execution, if it was
+    /// profiled with profile_transactions set in the local config (whether it succeeded or not).
+    pub fn take_execution_profile(&self) -> Option<BlockExecutionProfile> {
+        self.execution_profile.lock().take()
+    }
+
+    /// Returns the updates of the execution, or None if it was requeued on a dependency (and its
+    /// output dropped).
     fn execute(
         idx_to_execute: TxnIndex,
         incarnation: Incarnation,
-        signature_verified_block: &[T],
+        signature_verified_block: &dyn TxnProvider<T>,
+        block_timestamp: Option<BlockTimestamp>,
         last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
         versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
         executor: &E,
-        base_view: &S,
+        base_resolver: &BaseValueResolver<T, S, X>,
         latest_view: ParallelState<T, X>,
-    ) -> Result<bool, PanicOr<ParallelBlockExecutionError>> {
+        txn_execution_timeout: Option<Duration>,
+        record_read_stats: bool,
+    ) -> Result<Option<ExecutionUpdates>, PanicOr<ParallelBlockExecutionError>> {
         let _timer = TASK_EXECUTE_SECONDS.start_timer();
-        let txn = &signature_verified_block[idx_to_execute as usize];
+        let txn = signature_verified_block.get_txn(idx_to_execute);
 
         // VM execution.
-        let sync_view = LatestView::new(base_view, ViewState::Sync(latest_view), idx_to_execute);
-        let execute_result = executor.execute_transaction(&sync_view, txn, idx_to_execute);
+        let sync_view = LatestView::new(
+            base_resolver.base_view(),
+            ViewState::Sync(latest_view),
+            idx_to_execute,
+        )
+        .with_hot_state_cache(base_resolver.hot_state_cache())
+        .with_read_stats(record_read_stats);
+        let start_time = Instant::now();
+        let mut execute_result = if block_timestamp.map_or(false, |block_timestamp| {
+            block_timestamp.is_expired(txn, idx_to_execute)
+        }) {
+            counters::EXPIRED_TXN_DISCARD_COUNT
+                .with_label_values(&[counters::Mode::PARALLEL])
+                .inc();
+            ExecutionStatus::Success(E::Output::discard_output(StatusCode::TRANSACTION_EXPIRED))
+        } else {
+            executor.execute_transaction(&sync_view, txn, idx_to_execute)
+        };
+        if sync_view.is_requeued() {
+            // The execution was stopped at the dependency, and the same incarnation is executed
+            // again once the dependency is resolved, so nothing is recorded.
+            counters::DEPENDENCY_REQUEUE_COUNT.inc();
+            return Ok(None);
+        }
+
+        // The execution cannot be preempted, so the timeout is checked once it completes. The
+        // output of an execution that timed out is dropped, as if the incarnation was aborted
+        // by a speculative error, see TxnLastInputOutput::discard_timed_out_output.
+        let timed_out = txn_execution_timeout.map_or(false, |timeout| {
+            start_time.elapsed() >= timeout
+                && matches!(
+                    execute_result,
+                    ExecutionStatus::Success(_) | ExecutionStatus::SkipRest(_)
+                )
+        });
+        if timed_out {
+            counters::TXN_EXECUTION_TIMEOUT_COUNT
+                .with_label_values(&["speculative"])
+                .inc();
+            execute_result = ExecutionStatus::SpeculativeExecutionAbortError(format!(
+                "Execution of txn {} (incarnation {}) exceeded the timeout",
+                idx_to_execute, incarnation
+            ));
+        }
 
         let mut prev_modified_keys = last_input_output
             .modified_keys(idx_to_execute)
@@ -123,9 +441,14 @@ where
             .map_or(HashSet::new(), |keys| keys.collect());
 
         let mut read_set = sync_view.take_parallel_reads();
+        if let Some(read_stats) = sync_view.read_stats() {
+            last_input_output.record_read_stats(idx_to_execute, read_stats);
+        }
 
         // For tracking whether the recent execution wrote outside of the previous write/delta set.
         let mut updates_outside = false;
+        // For tracking whether the recent execution wrote anything at all.
+        let mut has_writes = false;
         let mut apply_updates = |output: &E::Output| -> Result<
             Vec<(T::Key, Arc<T::Value>, Option<Arc<MoveTypeLayout>>)>, // Cached resource writes
             PanicError,
@@ -133,6 +456,7 @@ where
             for (group_key, group_metadata_op, group_ops) in
                 output.resource_group_write_set().into_iter()
             {
+                has_writes = true;
                 if prev_modified_keys.remove(&group_key).is_none() {
                     // Previously no write to the group at all.
                     updates_outside = true;
@@ -157,15 +481,18 @@ where
                 }
             }
 
+            // The write set is recorded (for materialization) after the writes are applied, so the
+            // entries are cloned one at a time rather than cloning the whole write set.
             let resource_write_set = output.resource_write_set();
 
             // Then, process resource & aggregator_v1 & module writes.
-            for (k, v, maybe_layout) in resource_write_set.clone().into_iter().chain(
+            for (k, v, maybe_layout) in resource_write_set.iter().cloned().chain(
                 output
                     .aggregator_v1_write_set()
                     .into_iter()
                     .map(|(state_key, write_op)| (state_key, Arc::new(write_op), None)),
             ) {
+                has_writes = true;
                 if prev_modified_keys.remove(&k).is_none() {
                     updates_outside = true;
                 }
@@ -175,6 +502,7 @@ where
             }
 
             for (k, v) in output.module_write_set().into_iter() {
+                has_writes = true;
                 if prev_modified_keys.remove(&k).is_none() {
                     updates_outside = true;
                 }
@@ -183,6 +511,7 @@ where
 
             // Then, apply deltas.
             for (k, d) in output.aggregator_v1_delta_set().into_iter() {
+                has_writes = true;
                 if prev_modified_keys.remove(&k).is_none() {
                     updates_outside = true;
                 }
@@ -204,6 +533,7 @@ where
             // }
 
             for (id, change) in delayed_field_change_set.into_iter() {
+                has_writes = true;
                 prev_modified_delayed_fields.remove(&id);
 
                 let entry = change.into_entry_no_additional_history();
@@ -232,7 +562,7 @@ where
             Ok(resource_write_set)
         };
 
-        let (result, resource_write_set) = match execute_result {
+        let (result, resource_write_set, read_only) = match execute_result {
             // These statuses are the results of speculative execution, so even for
             // SkipRest (skip the rest of transactions) and Abort (abort execution with
             // user defined error), no immediate action is taken. Instead the statuses
@@ -240,20 +570,33 @@ where
             ExecutionStatus::Success(output) => {
                 // Apply the writes/deltas to the versioned_data_cache.
                 let resource_write_set = apply_updates(&output)?;
-                (ExecutionStatus::Success(output), resource_write_set)
+                (
+                    ExecutionStatus::Success(output),
+                    resource_write_set,
+                    !has_writes,
+                )
             },
             ExecutionStatus::SkipRest(output) => {
                 // Apply the writes/deltas and record status indicating skip.
                 let resource_write_set = apply_updates(&output)?;
-                (ExecutionStatus::SkipRest(output), resource_write_set)
+                (
+                    ExecutionStatus::SkipRest(output),
+                    resource_write_set,
+                    !has_writes,
+                )
             },
             ExecutionStatus::SpeculativeExecutionAbortError(msg) => {
-                read_set.capture_delayed_field_read_error(&PanicOr::Or(
-                    MVDelayedFieldsError::DeltaApplicationFailure,
-                ));
+                // The incarnation must fail validation, unless it timed out (in which case
+                // its read-set is validated as usual).
+                if !timed_out {
+                    read_set.capture_delayed_field_read_error(&PanicOr::Or(
+                        MVDelayedFieldsError::DeltaApplicationFailure,
+                    ));
+                }
                 (
                     ExecutionStatus::SpeculativeExecutionAbortError(msg),
                     Vec::new(),
+                    false,
                 )
             },
             ExecutionStatus::Abort(err) => {
@@ -261,7 +604,7 @@ where
                 // can occur due to speculative execution (in particular for BlockMetadata txn).
                 // Therefore, we do not short circuit here. TODO: investigate if we can
                 // eliminate the scenarios when Abort status can happen speculatively.
-                (ExecutionStatus::Abort(err), Vec::new())
+                (ExecutionStatus::Abort(err), Vec::new(), false)
             },
             ExecutionStatus::DelayedFieldsCodeInvariantError(msg) => {
                 return Err(code_invariant_error(format!(
@@ -289,21 +632,47 @@ where
             versioned_cache.delayed_fields().remove(&id, idx_to_execute);
         }
 
-        if !last_input_output.record(idx_to_execute, read_set, result, resource_write_set) {
-            // Module R/W is an expected fallback behavior, no alert is required.
-            debug!("[Execution] At txn {}, Module read & write", idx_to_execute);
+        match last_input_output.record(
+            idx_to_execute,
+            read_set,
+            result,
+            resource_write_set,
+            timed_out,
+        ) {
+            Ok(()) => (),
+            Err(RecordError::ModuleReadWrite(conflicting_modules)) => {
+                // Module R/W is an expected fallback behavior, no alert is required.
+                debug!(
+                    "[Execution] At txn {}, Module read & write on {:?}",
+                    idx_to_execute, conflicting_modules
+                );
 
-            return Err(PanicOr::Or(
-                ParallelBlockExecutionError::ModulePathReadWriteError,
-            ));
+                return Err(PanicOr::Or(
+                    ParallelBlockExecutionError::ModulePathReadWriteError {
+                        txn_idx: idx_to_execute,
+                    },
+                ));
+            },
+            // Alerted by the worker loop.
+            Err(err) => {
+                return Err(code_invariant_error(format!(
+                    "[Execution] At txn {}, failed to record the incarnation: {:?}",
+                    idx_to_execute, err
+                ))
+                .into());
+            },
         }
-        Ok(updates_outside)
+        Ok(Some(ExecutionUpdates {
+            updates_outside,
+            read_only,
+        }))
     }
 
     fn validate(
         idx_to_validate: TxnIndex,
         last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
         versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+        early_delayed_field_validation: bool,
     ) -> Result<bool, PanicError> {
         let _timer = TASK_VALIDATE_SECONDS.start_timer();
         let read_set = last_input_output
@@ -316,18 +685,52 @@ where
             ));
         }
 
-        // Note: we validate delayed field reads only at try_commit.
-        // TODO[agg_v2](optimize): potentially add some basic validation.
-        // TODO[agg_v2](optimize): potentially add more sophisticated validation, but if it fails,
-        // we mark it as a soft failure, requires some new statuses in the scheduler
-        // (i.e. not re-execute unless some other part of the validation fails or
-        // until commit, but mark as estimates).
-
-        // TODO: validate modules when there is no r/w fallback.
-        Ok(
-            read_set.validate_data_reads(versioned_cache.data(), idx_to_validate)
-                && read_set.validate_group_reads(versioned_cache.group_data(), idx_to_validate),
-        )
+        // Note: delayed field reads are fully validated only at try_commit. If enabled, a basic
+        // check against the speculative values aborts obviously stale incarnations earlier
+        // (or soft-fails them, see validate_delayed_field_reads_early).
+
+        // With the module R/W fallback (i.e. unless validate_module_reads is set), the module
+        // reads are always valid: if a module is both read and written in the block, the block
+        // falls back to sequential execution instead.
+        if !(read_set.validate_data_reads(versioned_cache.data(), idx_to_validate)
+            && read_set.validate_group_reads(versioned_cache.group_data(), idx_to_validate)
+            && (!last_input_output.validates_module_reads()
+                || read_set.validate_module_reads(versioned_cache.modules(), idx_to_validate)))
+        {
+            return Ok(false);
+        }
+        if early_delayed_field_validation
+            && !Self::validate_delayed_field_reads_early(
+                idx_to_validate,
+                last_input_output,
+                versioned_cache,
+            )?
+        {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Checks the delayed field reads against the speculative values. With
+    /// soft_delayed_field_validation_failures set in the local config, an incarnation whose
+    /// other reads are valid, but fails this check, is soft-failed instead of aborted (see
+    /// Scheduler::try_soft_fail).
+    fn validate_delayed_field_reads_early(
+        idx_to_validate: TxnIndex,
+        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+        versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+    ) -> Result<bool, PanicError> {
+        let valid = last_input_output
+            .read_set(idx_to_validate)
+            .expect("[BlockSTM]: Prior read-set must be recorded")
+            .validate_delayed_field_reads_speculatively(
+                versioned_cache.delayed_fields(),
+                idx_to_validate,
+            )?;
+        if !valid {
+            counters::EARLY_DELAYED_FIELD_VALIDATION_FAILURE_COUNT.inc();
+        }
+        Ok(valid)
     }
 
     fn update_transaction_on_abort(
@@ -349,6 +752,7 @@ where
                     Module => versioned_cache.modules().mark_estimate(&k, txn_idx),
                     Group => {
                         versioned_cache.data().mark_estimate(&k, txn_idx);
+                        // Only marks the tags written by the transaction.
                         versioned_cache.group_data().mark_estimate(&k, txn_idx);
                     },
                 };
@@ -363,17 +767,35 @@ where
     }
 
     fn update_on_validation(
+        &self,
         txn_idx: TxnIndex,
         incarnation: Incarnation,
         valid: bool,
+        soft_failure: bool,
         validation_wave: Wave,
         last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
         versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
         scheduler: &Scheduler,
+        adaptive_concurrency: &AdaptiveConcurrency,
     ) -> Result<SchedulerTask, PanicError> {
-        let aborted = !valid && scheduler.try_abort(txn_idx, incarnation);
+        // Only the delayed field reads are invalid: the incarnation is not re-executed until
+        // commit, unless it is already soft-failed (or no longer executed).
+        if soft_failure && scheduler.try_soft_fail(txn_idx, incarnation) {
+            adaptive_concurrency.record_abort();
+            self.execution_stats.record_abort();
+            chrome_trace::instant(
+                "soft_failure",
+                TraceArgs::txn(txn_idx).incarnation(incarnation),
+            );
+            return Ok(SchedulerTask::NoTask);
+        }
+
+        let aborted = !valid && !soft_failure && scheduler.try_abort(txn_idx, incarnation);
 
         if aborted {
+            adaptive_concurrency.record_abort();
+            self.execution_stats.record_abort();
+            chrome_trace::instant("abort", TraceArgs::txn(txn_idx).incarnation(incarnation));
             Self::update_transaction_on_abort(txn_idx, last_input_output, versioned_cache);
             scheduler.finish_abort(txn_idx, incarnation)
         } else {
@@ -419,6 +841,150 @@ where
         Ok(execution_still_valid)
     }
 
+    /// The final re-execution of the next transaction to commit, which failed the commit-time
+    /// validation (and was aborted). Performed while holding the coordination of the commits,
+    /// so the new incarnation must be valid.
+    fn execute_during_commit(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        scheduler: &Scheduler,
+        versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+        base_resolver: &BaseValueResolver<T, S, X>,
+        start_shared_counter: u32,
+        shared_counter: &AtomicU32,
+        executor: &E,
+        block: &dyn TxnProvider<T>,
+        block_timestamp: Option<BlockTimestamp>,
+    ) -> Result<(), PanicOr<ParallelBlockExecutionError>> {
+        // We are going to skip reducing validation index here, as we
+        // are executing immediately, and will reduce it unconditionally
+        // after execution, inside finish_execution_during_commit.
+        // Because of that, the updates are only checked to not be requeued.
+        let execute_start = Instant::now();
+        let updates = Self::execute(
+            txn_idx,
+            incarnation,
+            block,
+            block_timestamp,
+            last_input_output,
+            versioned_cache,
+            executor,
+            base_resolver,
+            ParallelState::new(
+                versioned_cache,
+                scheduler,
+                start_shared_counter,
+                shared_counter,
+            ),
+            self.config.local.txn_execution_timeout,
+            self.config.local.record_read_stats,
+        )?;
+        self.execution_stats
+            .record_execution(execute_start.elapsed());
+        if let Some(profiler) = scheduler.execution_profiler() {
+            profiler.record_execution(txn_idx, incarnation, execute_start.elapsed());
+        }
+        if updates.is_none() {
+            // All the preceding transactions are committed, so there may be no dependency.
+            return Err(code_invariant_error(format!(
+                "Re-execution during commit of txn {} was requeued on a dependency",
+                txn_idx
+            ))
+            .into());
+        }
+
+        scheduler.finish_execution_during_commit(txn_idx)?;
+
+        // Delayed fields are validated by validate_commit_ready below.
+        let validation_result = Self::validate(txn_idx, last_input_output, versioned_cache, false)?;
+        if !validation_result
+            || !Self::validate_commit_ready(txn_idx, versioned_cache, last_input_output)
+                .unwrap_or(false)
+        {
+            return Err(code_invariant_error(format!(
+                "Validation after re-execution failed for {} txn, validate() = {}",
+                txn_idx, validation_result
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Whether the materialized output of the next transaction to commit exceeds the
+    /// per-transaction output limit (see BlockGasLimitType::txn_output_limit). The size is
+    /// measured at commit (see materialized_output_size), as the groups written by the
+    /// transaction are final once all the preceding transactions are committed.
+    fn exceeds_txn_output_limit(
+        &self,
+        txn_idx: TxnIndex,
+        txn_output_limit: Option<u64>,
+        versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+    ) -> Result<bool, PanicError> {
+        let Some(limit) = txn_output_limit else {
+            return Ok(false);
+        };
+        let txn_output = last_input_output.txn_output(txn_idx);
+        let output = match txn_output.as_deref() {
+            Some(ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output)) => output,
+            _ => return Ok(false),
+        };
+        let size = materialized_output_size(
+            output,
+            |group_key| {
+                versioned_cache
+                    .group_data()
+                    .get_last_committed_group(group_key)
+            },
+            |key| {
+                versioned_cache
+                    .data()
+                    .fetch_exchanged_data(key, txn_idx)
+                    .map(|(value, _)| value)
+            },
+            &self.group_serialization_cache,
+        )?;
+        Ok(size > limit)
+    }
+
+    /// Discards the output of the next transaction to commit, while holding the coordination of
+    /// the commits. Its writes are removed from the multi-versioned data-structure, and the later
+    /// transactions are validated again before being committed, so that those that read the
+    /// writes are re-executed.
+    fn discard_during_commit(
+        txn_idx: TxnIndex,
+        discard_code: StatusCode,
+        scheduler: &Scheduler,
+        versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+    ) -> Result<(), PanicError> {
+        if let Some(keys) = last_input_output.modified_keys(txn_idx) {
+            for (k, kind) in keys {
+                use KeyKind::*;
+                match kind {
+                    Resource => versioned_cache.data().remove(&k, txn_idx),
+                    Module => versioned_cache.modules().remove(&k, txn_idx),
+                    Group => {
+                        versioned_cache.data().remove(&k, txn_idx);
+                        versioned_cache.group_data().remove(&k, txn_idx);
+                    },
+                };
+            }
+        }
+        if let Some(ids) = last_input_output.delayed_field_keys(txn_idx) {
+            for id in ids {
+                // The delayed fields of the transaction are already committed, and only an
+                // estimate may be removed.
+                versioned_cache.delayed_fields().mark_estimate(&id, txn_idx);
+                versioned_cache.delayed_fields().remove(&id, txn_idx);
+            }
+        }
+        last_input_output.discard_output_during_commit(txn_idx, discard_code);
+        scheduler.finish_execution_during_commit(txn_idx)
+    }
+
     /// This method may be executed by different threads / workers, but is guaranteed to be executed
     /// non-concurrently by the scheduling in parallel executor. This allows to perform light logic
     /// related to committing a transaction in a simple way and without excessive synchronization
@@ -427,69 +993,177 @@ where
     /// in outputs, which is heavier (due to serialization / deserialization, copies, etc). Moreover,
     /// since prepare_and_queue_commit_ready_txns takes care of synchronization in the flag-combining
     /// way, the materialization can be almost embarrassingly parallelizable.
+    ///
+    /// If a transaction must be re-executed at commit time while the worker has a task of its
+    /// own, the re-execution is handed off to another worker along with the coordination of the
+    /// commits, and false is returned (the caller must not mark the coordination done). The
+    /// worker that performs the re-execution resumes the commits from the re-executed transaction.
     fn prepare_and_queue_commit_ready_txns(
         &self,
-        block_gas_limit_type: &BlockGasLimitType,
         scheduler: &Scheduler,
         versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
         scheduler_task: &mut SchedulerTask,
         last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
-        shared_commit_state: &ExplicitSyncWrapper<BlockGasLimitProcessor<T>>,
-        base_view: &S,
+        shared_commit_state: &ExplicitSyncWrapper<Box<dyn BlockLimitPolicy<T>>>,
+        base_resolver: &BaseValueResolver<T, S, X>,
         start_shared_counter: u32,
         shared_counter: &AtomicU32,
         executor: &E,
-        block: &[T],
-    ) -> Result<(), PanicOr<ParallelBlockExecutionError>> {
+        block: &dyn TxnProvider<T>,
+        block_timestamp: Option<BlockTimestamp>,
+        adaptive_concurrency: &AdaptiveConcurrency,
+        mut reexecuted_txn_idx: Option<TxnIndex>,
+    ) -> Result<bool, PanicOr<ParallelBlockExecutionError>> {
         let mut block_limit_processor = shared_commit_state.acquire();
+        let block_gas_limit_type = block_limit_processor.block_gas_limit_type().clone();
 
-        while let Some((txn_idx, incarnation)) = scheduler.try_commit() {
-            if !Self::validate_commit_ready(txn_idx, versioned_cache, last_input_output)? {
-                // Transaction needs to be re-executed, one final time.
-
-                Self::update_transaction_on_abort(txn_idx, last_input_output, versioned_cache);
-                // We are going to skip reducing validation index here, as we
-                // are executing immediately, and will reduce it unconditionally
-                // after execution, inside finish_execution_during_commit.
-                // Because of that, we can also ignore _updates_outside result.
-                let _updates_outside = Self::execute(
+        loop {
+            // The incarnation is None if the transaction was re-executed during commit.
+            let (txn_idx, committed_incarnation) = match reexecuted_txn_idx.take() {
+                Some(txn_idx) => (txn_idx, None),
+                None => {
+                    let (txn_idx, incarnation) = match scheduler.try_commit() {
+                        Some(version) => version,
+                        None => {
+                            if let Some((txn_idx, incarnation, soft_failed)) =
+                                scheduler.try_commit_deferred()
+                            {
+                                // The deferred incarnation is executed once, now that all the
+                                // preceding transactions are committed.
+                                adaptive_concurrency.record_commit();
+                                if soft_failed {
+                                    // The writes of the soft-failed incarnation were kept.
+                                    Self::update_transaction_on_abort(
+                                        txn_idx,
+                                        last_input_output,
+                                        versioned_cache,
+                                    );
+                                }
+                                if matches!(
+                                    scheduler_task,
+                                    SchedulerTask::NoTask | SchedulerTask::Done
+                                ) {
+                                    self.execute_during_commit(
+                                        txn_idx,
+                                        incarnation,
+                                        scheduler,
+                                        versioned_cache,
+                                        last_input_output,
+                                        base_resolver,
+                                        start_shared_counter,
+                                        shared_counter,
+                                        executor,
+                                        block,
+                                        block_timestamp,
+                                    )?;
+                                } else {
+                                    counters::COMMIT_REEXECUTION_HANDOFF_COUNT.inc();
+                                    scheduler.hand_off_commit_reexecution(txn_idx, incarnation);
+                                    return Ok(false);
+                                }
+                                reexecuted_txn_idx = Some(txn_idx);
+                                continue;
+                            }
+                            if self.config.local.early_release {
+                                self.release_independent_txns(
+                                    scheduler,
+                                    versioned_cache,
+                                    last_input_output,
+                                )?;
+                            }
+                            return Ok(true);
+                        },
+                    };
+                    adaptive_concurrency.record_commit();
+                    let valid = (!scheduler.is_validated_at_commit(txn_idx)
+                        || Self::validate(txn_idx, last_input_output, versioned_cache, false)?)
+                        && Self::validate_commit_ready(
+                            txn_idx,
+                            versioned_cache,
+                            last_input_output,
+                        )?;
+                    if !valid {
+                        // Transaction needs to be re-executed, one final time.
+
+                        adaptive_concurrency.record_abort();
+                        self.execution_stats.record_abort();
+                        Self::update_transaction_on_abort(
+                            txn_idx,
+                            last_input_output,
+                            versioned_cache,
+                        );
+                        if matches!(scheduler_task, SchedulerTask::NoTask | SchedulerTask::Done) {
+                            self.execute_during_commit(
+                                txn_idx,
+                                incarnation + 1,
+                                scheduler,
+                                versioned_cache,
+                                last_input_output,
+                                base_resolver,
+                                start_shared_counter,
+                                shared_counter,
+                                executor,
+                                block,
+                                block_timestamp,
+                            )?;
+                        } else {
+                            // Rather than delaying its own task (while the other workers may
+                            // be idle), the worker hands off the re-execution.
+                            counters::COMMIT_REEXECUTION_HANDOFF_COUNT.inc();
+                            scheduler.hand_off_commit_reexecution(txn_idx, incarnation + 1);
+                            return Ok(false);
+                        }
+                        (txn_idx, None)
+                    } else {
+                        (txn_idx, Some(incarnation))
+                    }
+                },
+            };
+            // The output limit applies to the materialized output, so it is checked at commit,
+            // before any later transaction (that may have read the writes) is committed.
+            let committed_incarnation = if self.exceeds_txn_output_limit(
+                txn_idx,
+                block_gas_limit_type.txn_output_limit(),
+                versioned_cache,
+                last_input_output,
+            )? {
+                counters::TXN_OUTPUT_LIMIT_DISCARD_COUNT
+                    .with_label_values(&[counters::Mode::PARALLEL])
+                    .inc();
+                Self::discard_during_commit(
                     txn_idx,
-                    incarnation + 1,
-                    block,
-                    last_input_output,
+                    StatusCode::STORAGE_WRITE_LIMIT_REACHED,
+                    scheduler,
                     versioned_cache,
-                    executor,
-                    base_view,
-                    ParallelState::new(
-                        versioned_cache,
-                        scheduler,
-                        start_shared_counter,
-                        shared_counter,
-                    ),
+                    last_input_output,
                 )?;
-
-                scheduler.finish_execution_during_commit(txn_idx)?;
-
-                let validation_result =
-                    Self::validate(txn_idx, last_input_output, versioned_cache)?;
-                if !validation_result
-                    || !Self::validate_commit_ready(txn_idx, versioned_cache, last_input_output)
-                        .unwrap_or(false)
-                {
-                    return Err(code_invariant_error(format!(
-                        "Validation after re-execution failed for {} txn, validate() = {}",
-                        txn_idx, validation_result
-                    ))
-                    .into());
-                }
+                // The committed output is not the output of any incarnation.
+                None
+            } else {
+                committed_incarnation
+            };
+            if self.config.local.early_release {
+                self.check_released_at_commit(txn_idx, committed_incarnation);
             }
 
             last_input_output
                 .check_fatal_vm_error(txn_idx)
                 .map_err(PanicOr::Or)?;
+            if last_input_output.discard_timed_out_output(txn_idx)? {
+                counters::TXN_EXECUTION_TIMEOUT_COUNT
+                    .with_label_values(&["discarded"])
+                    .inc();
+            }
             // Handle a potential vm error, then check invariants on the recorded outputs.
             last_input_output.check_execution_status_during_commit(txn_idx)?;
 
+            if let Some(conflict_graph) = self.conflict_graph.lock().as_mut() {
+                conflict_graph.record_committed(
+                    txn_idx,
+                    last_input_output.get_txn_read_write_summary(txn_idx),
+                );
+            }
+
             if let Some(fee_statement) = last_input_output.fee_statement(txn_idx) {
                 let approx_output_size = block_gas_limit_type.block_output_limit().and_then(|_| {
                     last_input_output
@@ -497,7 +1171,7 @@ where
                         .map(|approx_output| {
                             approx_output
                                 + if block_gas_limit_type.include_user_txn_size_in_block_output() {
-                                    block[txn_idx as usize].user_txn_bytes_len()
+                                    block.get_txn(txn_idx).user_txn_bytes_len()
                                 } else {
                                     0
                                 } as u64
@@ -508,18 +1182,31 @@ where
                     .map(|_| last_input_output.get_txn_read_write_summary(txn_idx));
 
                 // For committed txns with Success status, calculate the accumulated gas costs.
-                block_limit_processor.accumulate_fee_statement(
+                block_limit_processor.accumulate(
                     fee_statement,
                     txn_read_write_summary,
                     approx_output_size,
                 );
 
-                if txn_idx < scheduler.num_txns() - 1
-                    && block_limit_processor.should_end_block_parallel()
-                {
-                    // Set the execution output status to be SkipRest, to skip the rest of the txns.
-                    last_input_output.update_to_skip_rest(txn_idx);
+                if txn_idx < scheduler.num_txns() - 1 {
+                    if let Some(skip_reason) = block_limit_processor.should_end(true) {
+                        // Set the execution output status to be SkipRest, to skip the rest of the txns.
+                        last_input_output.update_to_skip_rest(txn_idx, skip_reason);
+                    } else if self.exceeds_mvhashmap_memory_soft_cap(txn_idx, versioned_cache) {
+                        last_input_output
+                            .update_to_skip_rest(txn_idx, SkipReason::MVHashMapMemorySoftCap);
+                    }
                 }
+
+                if let Some(commit_hook) = &self.transaction_commit_hook {
+                    commit_hook.on_block_limit_snapshot(txn_idx, &block_limit_processor.snapshot());
+                }
+            }
+
+            if txn_idx < scheduler.num_txns() - 1
+                && self.is_halted_by_commit_hook(txn_idx, last_input_output)
+            {
+                last_input_output.update_to_skip_rest(txn_idx, SkipReason::CommitHook);
             }
 
             let finalized_groups = groups_to_finalize!(last_input_output, txn_idx)
@@ -565,10 +1252,7 @@ where
                 }
 
                 if scheduler.halt() {
-                    block_limit_processor.finish_parallel_update_counters_and_log_info(
-                        txn_idx + 1,
-                        scheduler.num_txns(),
-                    );
+                    block_limit_processor.finish(true, txn_idx + 1, scheduler.num_txns());
 
                     // failpoint triggering error at the last committed transaction,
                     // to test that next transaction is handled correctly
@@ -577,90 +1261,260 @@ where
                     )
                     .into()));
                 }
-                return Ok(());
+                return Ok(true);
             }
         }
-        Ok(())
     }
 
-    fn materialize_aggregator_v1_delta_writes(
-        txn_idx: TxnIndex,
-        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+    /// Releases the outputs of the executed transactions following the next transaction to
+    /// commit (up to EARLY_RELEASE_WINDOW) to the commit hook, if their read/write summaries are
+    /// disjoint from those of all the earlier transactions not committed yet, see
+    /// [`TransactionCommitHook::on_transaction_released`]. Must be called by the commit
+    /// coordinator.
+    fn release_independent_txns(
+        &self,
+        scheduler: &Scheduler,
         versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
-        base_view: &S,
-    ) -> Vec<(T::Key, WriteOp)> {
-        // Materialize all the aggregator v1 deltas.
-        let aggregator_v1_delta_keys = last_input_output.aggregator_v1_delta_keys(txn_idx);
-        let mut aggregator_v1_delta_writes = Vec::with_capacity(aggregator_v1_delta_keys.len());
-        for k in aggregator_v1_delta_keys.into_iter() {
-            // Note that delta materialization happens concurrently, but under concurrent
-            // commit_hooks (which may be dispatched by the coordinator), threads may end up
-            // contending on delta materialization of the same aggregator. However, the
-            // materialization is based on previously materialized values and should not
-            // introduce long critical sections. Moreover, with more aggregators, and given
-            // that the commit_hook will be performed at dispersed times based on the
-            // completion of the respective previous tasks of threads, this should not be
-            // an immediate bottleneck - confirmed by an experiment with 32 core and a
-            // single materialized aggregator. If needed, the contention may be further
-            // mitigated by batching consecutive commit_hooks.
-            let committed_delta = versioned_cache
-                .data()
-                .materialize_delta(&k, txn_idx)
-                .unwrap_or_else(|op| {
-                    // TODO[agg_v1](cleanup): this logic should improve with the new AGGR data structure
-                    // TODO[agg_v1](cleanup): and the ugly base_view parameter will also disappear.
-                    let storage_value = base_view
-                        .get_state_value(&k)
-                        .expect("Error reading the base value for committed delta in storage");
-
-                    let w: T::Value = TransactionWrite::from_state_value(storage_value);
-                    let value_u128 = w
-                        .as_u128()
-                        .expect("Aggregator base value deserialization error")
-                        .expect("Aggregator base value must exist");
+        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+    ) -> Result<(), PanicError> {
+        let commit_hook = match &self.transaction_commit_hook {
+            Some(commit_hook) => commit_hook,
+            None => return Ok(()),
+        };
+        let next_to_commit = scheduler.next_txn_to_commit();
+        let end = scheduler
+            .num_txns()
+            .min(next_to_commit.saturating_add(EARLY_RELEASE_WINDOW));
+
+        let mut released_txns = self.released_txns.lock();
+        // The accumulated reads and writes of the earlier transactions not committed yet.
+        let mut previous = ReadWriteSummary::new(HashSet::new(), HashSet::new());
+        for txn_idx in next_to_commit..end {
+            // The summaries of all the earlier transactions must be known.
+            let incarnation = match scheduler.executed_incarnation(txn_idx) {
+                Some(incarnation) => incarnation,
+                None => break,
+            };
+            let summary = last_input_output.get_txn_read_write_summary(txn_idx);
+            if txn_idx > next_to_commit
+                && !released_txns.contains_key(&txn_idx)
+                && summary.is_independent_of_previous(&previous)
+                && !summary.has_group_or_delayed_field_access()
+                && last_input_output
+                    .aggregator_v1_delta_keys(txn_idx)
+                    .is_empty()
+                && last_input_output
+                    .reads_needing_delayed_field_exchange(txn_idx)
+                    .is_empty()
+                && Self::validate(txn_idx, last_input_output, versioned_cache, false)?
+            {
+                if let Some(ExecutionStatus::Success(output)) =
+                    last_input_output.txn_output(txn_idx).as_deref()
+                {
+                    handle_commit_hook_result(
+                        commit_hook,
+                        txn_idx,
+                        commit_hook.on_transaction_released(txn_idx, output),
+                    )?;
+                    released_txns.insert(txn_idx, incarnation);
+                    counters::EARLY_RELEASE_COUNT.inc();
+                }
+            }
+            previous.extend(summary);
+        }
+        Ok(())
+    }
 
-                    versioned_cache
-                        .data()
-                        .set_base_value(k.clone(), ValueWithLayout::RawFromStorage(Arc::new(w)));
-                    op.apply_to(value_u128)
-                        .expect("Materializing delta w. base value set must succeed")
-                });
+    /// Revokes the early release of the transaction if the committed incarnation (None if it
+    /// was re-executed or discarded during commit) is not the released one.
+    fn check_released_at_commit(
+        &self,
+        txn_idx: TxnIndex,
+        committed_incarnation: Option<Incarnation>,
+    ) {
+        let released_incarnation = self.released_txns.lock().remove(&txn_idx);
+        if released_incarnation.is_some() && released_incarnation != committed_incarnation {
+            self.revoke_release(txn_idx);
+        }
+    }
 
-            // Must contain committed value as we set the base value above.
-            aggregator_v1_delta_writes.push((
-                k,
-                WriteOp::legacy_modification(serialize(&committed_delta).into()),
-            ));
+    fn revoke_release(&self, txn_idx: TxnIndex) {
+        counters::EARLY_RELEASE_REVOKED_COUNT.inc();
+        if let Some(commit_hook) = &self.transaction_commit_hook {
+            commit_hook.on_release_revoked(txn_idx);
         }
-        aggregator_v1_delta_writes
     }
 
-    fn materialize_txn_commit(
+    /// Whether the entries of the multi-version data-structure take more memory than the soft
+    /// cap of the local config, once the transaction at txn_idx is committed.
+    fn exceeds_mvhashmap_memory_soft_cap(
         &self,
         txn_idx: TxnIndex,
         versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
-        scheduler: &Scheduler,
-        start_shared_counter: u32,
-        shared_counter: &AtomicU32,
+    ) -> bool {
+        let soft_cap = match self.config.local.mvhashmap_memory_soft_cap {
+            Some(soft_cap) => soft_cap,
+            None => return false,
+        };
+        let memory_usage = versioned_cache.memory_usage();
+        let exceeded = memory_usage.total() >= soft_cap;
+        if exceeded {
+            info!(
+                "[BlockSTM]: parallel execution early halted at txn {} due to \
+                MVHashMap memory usage {:?} >= soft cap {}",
+                txn_idx, memory_usage, soft_cap,
+            );
+            counters::EXCEED_MVHASHMAP_MEMORY_SOFT_CAP_COUNT.inc();
+        }
+        exceeded
+    }
+
+    /// Whether the commit hook halts the block at the transaction, which must have a Success
+    /// output (i.e. the block is not already halted by a SkipRest output).
+    fn is_halted_by_commit_hook(
+        &self,
+        txn_idx: TxnIndex,
         last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
-        base_view: &S,
-        final_results: &ExplicitSyncWrapper<Vec<E::Output>>,
-    ) -> Result<(), PanicError> {
-        let parallel_state = ParallelState::<T, X>::new(
-            versioned_cache,
-            scheduler,
-            start_shared_counter,
-            shared_counter,
+    ) -> bool {
+        let commit_hook = match &self.transaction_commit_hook {
+            Some(commit_hook) => commit_hook,
+            None => return false,
+        };
+        let halted = matches!(
+            last_input_output.txn_output(txn_idx).as_deref(),
+            Some(ExecutionStatus::Success(output))
+                if commit_hook.should_commit(txn_idx, output) == CommitDecision::HaltBlock
         );
-        let latest_view = LatestView::new(base_view, ViewState::Sync(parallel_state), txn_idx);
-        let finalized_groups = last_input_output.take_finalized_group(txn_idx);
-        let materialized_finalized_groups =
-            map_id_to_values_in_group_writes(finalized_groups, &latest_view)?;
-
-        let serialized_groups =
-            serialize_groups::<T>(materialized_finalized_groups).map_err(|e| {
-                code_invariant_error(format!("Panic error in serializing groups {e:?}"))
-            })?;
+        if halted {
+            info!(
+                "Transaction commit hook halted the block at txn {}",
+                txn_idx
+            );
+            counters::COMMIT_HOOK_HALT_COUNT.inc();
+        }
+        halted
+    }
+
+    /// Materializes the aggregator v1 deltas of the committed transactions (with increasing
+    /// indices, e.g. a batch drained from the commit queue). Returns the delta writes of each
+    /// transaction, in the order of its delta keys.
+    fn materialize_aggregator_v1_delta_writes(
+        txn_indices: &[TxnIndex],
+        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+        versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+        base_resolver: &BaseValueResolver<T, S, X>,
+    ) -> Result<Vec<Vec<(T::Key, WriteOp)>>, PanicError> {
+        let delta_keys: Vec<_> = txn_indices
+            .iter()
+            .map(|txn_idx| last_input_output.aggregator_v1_delta_keys(*txn_idx))
+            .collect();
+        // For each key, the positions of the transactions (and of the key among their delta
+        // keys) with a delta at the key, in the order of the transactions.
+        let mut deltas_by_key: HashMap<&T::Key, Vec<(usize, usize)>> = HashMap::new();
+        for (txn_pos, keys) in delta_keys.iter().enumerate() {
+            for (key_pos, key) in keys.iter().enumerate() {
+                deltas_by_key
+                    .entry(key)
+                    .or_default()
+                    .push((txn_pos, key_pos));
+            }
+        }
+
+        let mut committed_values: Vec<Vec<u128>> =
+            delta_keys.iter().map(|keys| vec![0; keys.len()]).collect();
+        for (key, positions) in deltas_by_key {
+            // Note that delta materialization happens concurrently, and threads may end up
+            // contending on delta materialization of the same (hot) aggregator. Hence, all the
+            // deltas of the batch at the key are materialized under a single lock of the key,
+            // each one based on the previously materialized value.
+            let batch_indices: Vec<_> = positions
+                .iter()
+                .map(|(txn_pos, _)| txn_indices[*txn_pos])
+                .collect();
+            let values = match versioned_cache
+                .data()
+                .materialize_deltas(key, &batch_indices)
+            {
+                Ok(values) => values,
+                Err(op) => {
+                    // TODO[agg_v1](cleanup): this logic should improve with the new AGGR data structure
+                    let value_u128 = base_resolver.resolve_aggregator_v1_base_value(key)?;
+                    let first_value = op.apply_to(value_u128).map_err(|e| {
+                        code_invariant_error(format!(
+                            "Materializing delta w. base value set must succeed: {:?}",
+                            e
+                        ))
+                    })?;
+                    // Must resolve, as we set the base value above.
+                    let mut values = vec![first_value];
+                    if batch_indices.len() > 1 {
+                        values.extend(
+                            versioned_cache
+                                .data()
+                                .materialize_deltas(key, &batch_indices[1..])
+                                .map_err(|_| {
+                                    code_invariant_error(format!(
+                                        "Materializing deltas at {:?} w. base value set must succeed",
+                                        key
+                                    ))
+                                })?,
+                        );
+                    }
+                    values
+                },
+            };
+            for ((txn_pos, key_pos), value) in positions.into_iter().zip(values) {
+                committed_values[txn_pos][key_pos] = value;
+            }
+        }
+
+        Ok(delta_keys
+            .into_iter()
+            .zip(committed_values)
+            .map(|(keys, values)| {
+                keys.into_iter()
+                    .zip(values)
+                    .map(|(key, value)| {
+                        (key, WriteOp::legacy_modification(serialize(&value).into()))
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn materialize_txn_commit(
+        &self,
+        txn_idx: TxnIndex,
+        versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+        scheduler: &Scheduler,
+        start_shared_counter: u32,
+        shared_counter: &AtomicU32,
+        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+        base_resolver: &BaseValueResolver<T, S, X>,
+        aggregator_v1_delta_writes: Vec<(T::Key, WriteOp)>,
+    ) -> Result<(), PanicError> {
+        let parallel_state = ParallelState::<T, X>::new(
+            versioned_cache,
+            scheduler,
+            start_shared_counter,
+            shared_counter,
+        );
+        let latest_view = LatestView::new(
+            base_resolver.base_view(),
+            ViewState::Sync(parallel_state),
+            txn_idx,
+        )
+        .with_hot_state_cache(base_resolver.hot_state_cache());
+        let finalized_groups = last_input_output.take_finalized_group(txn_idx);
+        let materialized_finalized_groups =
+            map_id_to_values_in_group_writes(finalized_groups, &latest_view)?;
+
+        let serialized_groups = serialize_groups::<T>(
+            materialized_finalized_groups,
+            txn_idx,
+            &self.group_serialization_cache,
+        )
+        .map_err(|e| code_invariant_error(format!("Panic error in serializing groups {e:?}")))?;
 
         let resource_write_set = last_input_output.take_resource_write_set(txn_idx);
         let resource_writes_to_materialize = resource_writes_to_materialize!(
@@ -674,12 +1528,6 @@ where
 
         let events = last_input_output.events(txn_idx);
         let materialized_events = map_id_to_values_events(events, &latest_view)?;
-        let aggregator_v1_delta_writes = Self::materialize_aggregator_v1_delta_writes(
-            txn_idx,
-            last_input_output,
-            versioned_cache,
-            base_view,
-        );
 
         last_input_output.record_materialized_txn_output(
             txn_idx,
@@ -693,7 +1541,11 @@ where
         if let Some(txn_commit_listener) = &self.transaction_commit_hook {
             match last_input_output.txn_output(txn_idx).unwrap().as_ref() {
                 ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
-                    txn_commit_listener.on_transaction_committed(txn_idx, output);
+                    handle_commit_hook_result(
+                        txn_commit_listener,
+                        txn_idx,
+                        txn_commit_listener.on_transaction_committed(txn_idx, output),
+                    )?;
                 },
                 ExecutionStatus::Abort(_) => {
                     txn_commit_listener.on_execution_aborted(txn_idx);
@@ -705,89 +1557,225 @@ where
             }
         }
 
-        let mut final_results = final_results.acquire();
-        match last_input_output.take_output(txn_idx) {
-            ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => {
-                final_results[txn_idx as usize] = t;
-            },
-            ExecutionStatus::Abort(_) => (),
-            ExecutionStatus::SpeculativeExecutionAbortError(msg)
-            | ExecutionStatus::DelayedFieldsCodeInvariantError(msg) => {
-                panic!("Cannot be materializing with {}", msg);
-            },
-        };
+        last_input_output.release_read_set(txn_idx);
         Ok(())
     }
 
+    /// Materializes the committed transactions in the commit queue, until it is empty.
+    fn drain_commit_queue(
+        &self,
+        versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+        scheduler: &Scheduler,
+        start_shared_counter: u32,
+        shared_counter: &AtomicU32,
+        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+        base_resolver: &BaseValueResolver<T, S, X>,
+    ) -> Result<(), PanicError> {
+        loop {
+            let txn_indices: Vec<TxnIndex> = scheduler
+                .pop_from_commit_queue(MAX_COMMIT_BATCH_SIZE)
+                .collect();
+            if txn_indices.is_empty() {
+                return Ok(());
+            }
+
+            let materialize_start = Instant::now();
+            let aggregator_v1_delta_writes = Self::materialize_aggregator_v1_delta_writes(
+                &txn_indices,
+                last_input_output,
+                versioned_cache,
+                base_resolver,
+            )?;
+            for (txn_idx, aggregator_v1_delta_writes) in
+                txn_indices.iter().zip(aggregator_v1_delta_writes)
+            {
+                let _span = chrome_trace::span("commit", TraceArgs::txn(*txn_idx));
+                let txn_materialize_start = Instant::now();
+                self.materialize_txn_commit(
+                    *txn_idx,
+                    versioned_cache,
+                    scheduler,
+                    start_shared_counter,
+                    shared_counter,
+                    last_input_output,
+                    base_resolver,
+                    aggregator_v1_delta_writes,
+                )?;
+                if let Some(profiler) = scheduler.execution_profiler() {
+                    profiler.record_materialization(*txn_idx, txn_materialize_start.elapsed());
+                }
+            }
+            self.execution_stats
+                .record_materialization(materialize_start.elapsed());
+        }
+    }
+
+    /// The loop of a worker dedicated to materialization: drains the commit queue until all the
+    /// execution workers are done (and hence no more transactions are committed).
+    fn materialization_loop(
+        &self,
+        versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+        scheduler: &Scheduler,
+        start_shared_counter: u32,
+        shared_counter: &AtomicU32,
+        last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
+        base_resolver: &BaseValueResolver<T, S, X>,
+        num_running_execution_workers: &AtomicUsize,
+    ) -> Result<(), PanicError> {
+        loop {
+            let execution_done = num_running_execution_workers.load(Ordering::Acquire) == 0;
+            self.drain_commit_queue(
+                versioned_cache,
+                scheduler,
+                start_shared_counter,
+                shared_counter,
+                last_input_output,
+                base_resolver,
+            )?;
+            if execution_done {
+                return Ok(());
+            }
+            // Waiting for commits: run a pending task of the thread pool if there is one (e.g.
+            // an execution worker not started yet, when sharing the executor thread pool), or
+            // otherwise let the other threads run.
+            if !matches!(rayon::yield_now(), Some(rayon::Yield::Executed)) {
+                std::thread::yield_now();
+            }
+        }
+    }
+
     fn worker_loop(
         &self,
         executor_arguments: &E::Argument,
-        block: &[T],
+        block: &dyn TxnProvider<T>,
+        block_timestamp: Option<BlockTimestamp>,
         last_input_output: &TxnLastInputOutput<T, E::Output, E::Error>,
         versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
         scheduler: &Scheduler,
-        // TODO: should not need to pass base view.
-        base_view: &S,
+        base_resolver: &BaseValueResolver<T, S, X>,
         start_shared_counter: u32,
         shared_counter: &AtomicU32,
-        shared_commit_state: &ExplicitSyncWrapper<BlockGasLimitProcessor<T>>,
-        final_results: &ExplicitSyncWrapper<Vec<E::Output>>,
+        shared_commit_state: &ExplicitSyncWrapper<Box<dyn BlockLimitPolicy<T>>>,
+        cancellation: Option<&CancellationToken>,
+        shared_cancelled: &AtomicBool,
+        adaptive_concurrency: &AdaptiveConcurrency,
+        dedicated_materialization: bool,
     ) -> Result<(), PanicOr<ParallelBlockExecutionError>> {
-        // Make executor for each task. TODO: fast concurrent executor.
-        let init_timer = VM_INIT_SECONDS.start_timer();
-        let executor = E::init(*executor_arguments);
-        drop(init_timer);
+        let worker_id = adaptive_concurrency.register_worker();
+        let executor = self.executor_cache.take(*executor_arguments);
 
         let _timer = WORK_WITH_TASK_SECONDS.start_timer();
         let mut scheduler_task = SchedulerTask::NoTask;
 
         let drain_commit_queue = || -> Result<(), PanicError> {
-            while let Ok(txn_idx) = scheduler.pop_from_commit_queue() {
-                self.materialize_txn_commit(
-                    txn_idx,
-                    versioned_cache,
-                    scheduler,
-                    start_shared_counter,
-                    shared_counter,
-                    last_input_output,
-                    base_view,
-                    final_results,
-                )?;
+            if dedicated_materialization {
+                // Drained by the dedicated materialization workers.
+                return Ok(());
             }
-            Ok(())
+            self.drain_commit_queue(
+                versioned_cache,
+                scheduler,
+                start_shared_counter,
+                shared_counter,
+                last_input_output,
+                base_resolver,
+            )
         };
 
         loop {
+            if cancellation.map_or(false, CancellationToken::is_cancelled) {
+                // Halting resolves all pending dependencies, so that the other workers also
+                // finish promptly (the speculative state is dropped with the block).
+                shared_cancelled.store(true, Ordering::SeqCst);
+                scheduler.halt();
+                break Ok(());
+            }
+
             while scheduler.should_coordinate_commits() {
-                self.prepare_and_queue_commit_ready_txns(
-                    &self.config.onchain.block_gas_limit_type,
+                if !self.prepare_and_queue_commit_ready_txns(
                     scheduler,
                     versioned_cache,
                     &mut scheduler_task,
                     last_input_output,
                     shared_commit_state,
-                    base_view,
+                    base_resolver,
                     start_shared_counter,
                     shared_counter,
                     &executor,
                     block,
-                )?;
+                    block_timestamp,
+                    adaptive_concurrency,
+                    None,
+                )? {
+                    // The coordination of the commits was handed off.
+                    break;
+                }
                 scheduler.queueing_commits_mark_done();
             }
 
             drain_commit_queue()?;
 
+            if !scheduler.start_task(&scheduler_task) {
+                // The task is not due yet in the replayed schedule, or the replay diverged
+                // (and the scheduler was halted).
+                if scheduler.done() {
+                    scheduler_task = SchedulerTask::Done;
+                }
+                continue;
+            }
+
             scheduler_task = match scheduler_task {
                 SchedulerTask::ValidationTask(txn_idx, incarnation, wave) => {
-                    let valid = Self::validate(txn_idx, last_input_output, versioned_cache)?;
-                    Self::update_on_validation(
+                    let _span = chrome_trace::span(
+                        "validate",
+                        TraceArgs::txn(txn_idx).incarnation(incarnation),
+                    );
+                    let soft_failures = self.config.local.early_delayed_field_validation
+                        && self.config.local.soft_delayed_field_validation_failures;
+                    let mut soft_failure = false;
+                    let valid = if scheduler.is_validation_redundant(txn_idx, incarnation) {
+                        counters::PARTITIONED_VALIDATION_SKIP_COUNT.inc();
+                        true
+                    } else {
+                        let validate_start = Instant::now();
+                        let mut valid = Self::validate(
+                            txn_idx,
+                            last_input_output,
+                            versioned_cache,
+                            self.config.local.early_delayed_field_validation && !soft_failures,
+                        )?;
+                        if valid
+                            && soft_failures
+                            && !Self::validate_delayed_field_reads_early(
+                                txn_idx,
+                                last_input_output,
+                                versioned_cache,
+                            )?
+                        {
+                            valid = false;
+                            soft_failure = true;
+                        }
+                        self.execution_stats
+                            .record_validation(validate_start.elapsed());
+                        if let Some(profiler) = scheduler.execution_profiler() {
+                            profiler.record_validation(
+                                txn_idx,
+                                incarnation,
+                                validate_start.elapsed(),
+                            );
+                        }
+                        valid
+                    };
+                    self.update_on_validation(
                         txn_idx,
                         incarnation,
                         valid,
+                        soft_failure,
                         wave,
                         last_input_output,
                         versioned_cache,
                         scheduler,
+                        adaptive_concurrency,
                     )?
                 },
                 SchedulerTask::ExecutionTask(
@@ -795,24 +1783,55 @@ where
                     incarnation,
                     ExecutionTaskType::Execution,
                 ) => {
-                    let updates_outside = Self::execute(
+                    let _span = chrome_trace::span(
+                        "execute",
+                        TraceArgs::txn(txn_idx).incarnation(incarnation),
+                    );
+                    let execute_start = Instant::now();
+                    let maybe_updates = Self::execute(
                         txn_idx,
                         incarnation,
                         block,
+                        block_timestamp,
                         last_input_output,
                         versioned_cache,
                         &executor,
-                        base_view,
+                        base_resolver,
                         ParallelState::new(
                             versioned_cache,
                             scheduler,
                             start_shared_counter,
                             shared_counter,
                         ),
+                        self.config.local.txn_execution_timeout,
+                        self.config.local.record_read_stats,
                     )?;
-                    scheduler.finish_execution(txn_idx, incarnation, updates_outside)?
+                    self.execution_stats
+                        .record_execution(execute_start.elapsed());
+                    if let Some(profiler) = scheduler.execution_profiler() {
+                        profiler.record_execution(txn_idx, incarnation, execute_start.elapsed());
+                    }
+                    match maybe_updates {
+                        Some(ExecutionUpdates {
+                            read_only: true, ..
+                        }) => scheduler.finish_read_only_execution(txn_idx, incarnation)?,
+                        Some(ExecutionUpdates {
+                            updates_outside, ..
+                        }) => scheduler.finish_execution(txn_idx, incarnation, updates_outside)?,
+                        // Requeued, the scheduler creates a new execution task once resumed.
+                        None => SchedulerTask::NoTask,
+                    }
                 },
-                SchedulerTask::ExecutionTask(_, _, ExecutionTaskType::Wakeup(condvar)) => {
+                SchedulerTask::ExecutionTask(
+                    txn_idx,
+                    incarnation,
+                    ExecutionTaskType::Wakeup(condvar),
+                ) => {
+                    chrome_trace::instant(
+                        "wakeup",
+                        TraceArgs::txn(txn_idx).incarnation(incarnation),
+                    );
+                    scheduler.finish_dependency_wait();
                     let (lock, cvar) = &*condvar;
                     // Mark dependency resolved.
                     let mut lock = lock.lock();
@@ -822,6 +1841,53 @@ where
 
                     scheduler.next_task()
                 },
+                SchedulerTask::CommitReExecutionTask(txn_idx, incarnation) => {
+                    let _span = chrome_trace::span(
+                        "execute",
+                        TraceArgs::txn(txn_idx).incarnation(incarnation),
+                    );
+                    self.execute_during_commit(
+                        txn_idx,
+                        incarnation,
+                        scheduler,
+                        versioned_cache,
+                        last_input_output,
+                        base_resolver,
+                        start_shared_counter,
+                        shared_counter,
+                        &executor,
+                        block,
+                        block_timestamp,
+                    )?;
+                    // Take over the coordination of the commits from the re-executed
+                    // transaction (committed by the scheduler before it was handed off).
+                    if self.prepare_and_queue_commit_ready_txns(
+                        scheduler,
+                        versioned_cache,
+                        &mut SchedulerTask::NoTask,
+                        last_input_output,
+                        shared_commit_state,
+                        base_resolver,
+                        start_shared_counter,
+                        shared_counter,
+                        &executor,
+                        block,
+                        block_timestamp,
+                        adaptive_concurrency,
+                        Some(txn_idx),
+                    )? {
+                        scheduler.queueing_commits_mark_done();
+                    }
+                    SchedulerTask::NoTask
+                },
+                // A parked worker keeps coordinating commits, but does not take new tasks.
+                SchedulerTask::NoTask
+                    if !scheduler.done()
+                        && adaptive_concurrency
+                            .maybe_park(worker_id, scheduler.num_waiting_workers()) =>
+                {
+                    SchedulerTask::NoTask
+                },
                 SchedulerTask::NoTask => scheduler.next_task(),
                 SchedulerTask::Done => {
                     drain_commit_queue()?;
@@ -834,96 +1900,417 @@ where
     pub(crate) fn execute_transactions_parallel(
         &self,
         executor_initial_arguments: E::Argument,
-        signature_verified_block: &[T],
+        signature_verified_block: &dyn TxnProvider<T>,
         base_view: &S,
-    ) -> Result<BlockOutput<E::Output>, ()> {
-        let _timer = PARALLEL_EXECUTION_SECONDS.start_timer();
-        // Using parallel execution with 1 thread currently will not work as it
-        // will only have a coordinator role but no workers for rolling commit.
-        // Need to special case no roles (commit hook by thread itself) to run
-        // w. concurrency_level = 1 for some reason.
-        assert!(
-            self.config.local.concurrency_level > 1,
-            "Must use sequential execution"
+        hints: Option<BlockHints<'_, T::Key>>,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<BlockOutput<E::Output>, ParallelExecutionFailure> {
+        self.execute_transactions_parallel_with_concurrency(
+            self.config.local.concurrency_level,
+            &self.config.onchain.block_gas_limit_type,
+            executor_initial_arguments,
+            signature_verified_block,
+            base_view,
+            hints,
+            cancellation,
+        )
+    }
+
+    /// Executes the block in parallel with the given concurrency level (e.g. reduced when
+    /// retrying a failed parallel execution) and block gas limit type.
+    fn execute_transactions_parallel_with_concurrency(
+        &self,
+        concurrency_level: usize,
+        block_gas_limit_type: &BlockGasLimitType,
+        executor_initial_arguments: E::Argument,
+        signature_verified_block: &dyn TxnProvider<T>,
+        base_view: &S,
+        hints: Option<BlockHints<'_, T::Key>>,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<BlockOutput<E::Output>, ParallelExecutionFailure> {
+        let versioned_cache = MVHashMap::new();
+        let shared_counter = AtomicU32::new(gen_id_start_value(false));
+
+        let ret = self.execute_transactions_parallel_with_caches(
+            concurrency_level,
+            block_gas_limit_type,
+            executor_initial_arguments,
+            signature_verified_block,
+            base_view,
+            hints,
+            cancellation,
+            &versioned_cache,
+            &shared_counter,
+            &[],
+            None,
         );
 
+        if ret.is_ok() && self.config.local.retain_block_state {
+            *self.block_state.lock() = Some(ExecutedBlockState {
+                versioned_cache,
+                start_shared_counter: gen_id_start_value(false),
+                shared_counter: shared_counter.into_inner(),
+                num_committed_txns: self.num_committed_txns.load(Ordering::Relaxed),
+            });
+        } else {
+            // Explicit async drop.
+            DEFAULT_DROPPER.schedule_drop(versioned_cache);
+        }
+        ret
+    }
+
+    /// Re-executes the block in parallel, replaying the schedule recorded in the trace (see
+    /// [`crate::schedule_trace`]), to reproduce a failure that depends on the interleaving of
+    /// the workers. The block, base view and hints must be the ones of the recorded execution,
+    /// and so must the concurrency level. Unlike [`BlockExecutor::execute_block`], there is no
+    /// fallback to sequential execution: an error is returned if the execution fails or
+    /// diverges from the recorded schedule.
+    pub fn replay_schedule(
+        &self,
+        executor_arguments: E::Argument,
+        signature_verified_block: &(impl TxnProvider<T> + ?Sized),
+        base_view: &S,
+        hints: Option<BlockHints<'_, T::Key>>,
+        trace: ScheduleTrace,
+    ) -> Result<BlockOutput<E::Output>, ScheduleReplayError> {
+        if trace.concurrency_level() != self.config.local.concurrency_level
+            || trace.num_txns() as usize != signature_verified_block.num_txns()
+        {
+            return Err(ScheduleReplayError::TraceMismatch {
+                trace_num_txns: trace.num_txns(),
+                trace_concurrency_level: trace.concurrency_level(),
+                num_txns: signature_verified_block.num_txns(),
+                concurrency_level: self.config.local.concurrency_level,
+            });
+        }
+
         let versioned_cache = MVHashMap::new();
-        let start_shared_counter = gen_id_start_value(false);
-        let shared_counter = AtomicU32::new(start_shared_counter);
+        let shared_counter = AtomicU32::new(gen_id_start_value(false));
+
+        let ret = self
+            .execute_transactions_parallel_with_caches(
+                self.config.local.concurrency_level,
+                &self.config.onchain.block_gas_limit_type,
+                executor_arguments,
+                &signature_verified_block,
+                base_view,
+                hints,
+                None,
+                &versioned_cache,
+                &shared_counter,
+                &[],
+                Some(trace),
+            )
+            .map_err(ScheduleReplayError::ExecutionFailure);
+
+        // Explicit async drop.
+        DEFAULT_DROPPER.schedule_drop(versioned_cache);
+        ret
+    }
+
+    /// Executes the block in parallel using the provided multi-version data-structure, which
+    /// may contain base values from storage, and the counter for delayed field identifiers,
+    /// which must not be reset while any of the base values refer to its identifiers. If
+    /// enabled, the base values of the hinted keys and of the provided prefetch keys are
+    /// read before the execution starts. If a schedule trace is provided, it is replayed.
+    fn execute_transactions_parallel_with_caches(
+        &self,
+        concurrency_level: usize,
+        block_gas_limit_type: &BlockGasLimitType,
+        executor_initial_arguments: E::Argument,
+        signature_verified_block: &dyn TxnProvider<T>,
+        base_view: &S,
+        hints: Option<BlockHints<'_, T::Key>>,
+        cancellation: Option<&CancellationToken>,
+        versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+        shared_counter: &AtomicU32,
+        prefetch_keys: &[T::Key],
+        schedule_replay: Option<ScheduleTrace>,
+    ) -> Result<BlockOutput<E::Output>, ParallelExecutionFailure> {
+        let _timer = PARALLEL_EXECUTION_SECONDS.start_timer();
+        // With a single worker, the worker coordinates and materializes its own commits.
+        assert!(concurrency_level > 0, "Must have at least one worker");
+
+        let start_shared_counter = shared_counter.load(Ordering::SeqCst);
+        self.reset_conflict_graph();
+        self.group_serialization_cache.clear();
+        *self.schedule_trace.lock() = None;
 
         if signature_verified_block.is_empty() {
-            return Ok(BlockOutput::new(vec![]));
+            return Ok(
+                BlockOutput::new(vec![]).with_block_end_info(Some(BlockEndInfo {
+                    num_committed_txns: 0,
+                    skip_reason: None,
+                    limit_usage: BlockLimitUsage::default(),
+                })),
+            );
         }
 
-        let num_txns = signature_verified_block.len();
+        let num_txns = signature_verified_block.num_txns();
 
-        let shared_commit_state = ExplicitSyncWrapper::new(BlockGasLimitProcessor::new(
-            self.config.onchain.block_gas_limit_type.clone(),
-            num_txns,
-        ));
-        let shared_maybe_error = AtomicBool::new(false);
+        let shared_commit_state =
+            ExplicitSyncWrapper::new(self.new_block_limit_policy(block_gas_limit_type, num_txns));
+        let shared_failure = Mutex::new(None);
+        let shared_cancelled = AtomicBool::new(false);
 
-        let final_results = ExplicitSyncWrapper::new(Vec::with_capacity(num_txns));
+        let num_txns = num_txns as u32;
 
-        {
-            final_results
-                .acquire()
-                .resize_with(num_txns, E::Output::skip_output);
+        let base_resolver =
+            BaseValueResolver::new(base_view, self.hot_state_cache.as_deref(), versioned_cache);
+        let block_timestamp = BlockTimestamp::from_block(signature_verified_block);
+        if self.config.local.prefetch_base_values {
+            let hinted_keys = match hints {
+                Some(BlockHints::Access(access_hints)) => access_hints,
+                _ => &[],
+            }
+            .iter()
+            .flat_map(|hint| hint.reads.iter().chain(hint.writes.iter()));
+            self.executor_thread_pool.install(|| {
+                base_resolver.prefetch_base_values(hinted_keys.chain(prefetch_keys.iter()))
+            });
         }
 
-        let num_txns = num_txns as u32;
+        let last_input_output = TxnLastInputOutput::new(num_txns)
+            .with_module_read_validation(self.config.local.validate_module_reads);
+        let scheduler = match hints {
+            Some(BlockHints::Access(access_hints)) if access_hints.len() == num_txns as usize => {
+                let prefix_len = conflict_free_prefix(access_hints);
+                counters::CONFLICT_FREE_PREFIX_TXN_COUNT.inc_by(prefix_len as u64);
+                Scheduler::new_with_hinted_dependencies(num_txns, hinted_dependencies(access_hints))
+                    .with_conflict_free_prefix(prefix_len)
+            },
+            Some(BlockHints::Access(access_hints)) => {
+                error!(
+                    "Ignoring access hints for {} transactions in a block of {} transactions",
+                    access_hints.len(),
+                    num_txns
+                );
+                Scheduler::new(num_txns)
+            },
+            Some(BlockHints::Partitioning(partitioning))
+                if partitioning.num_txns() == num_txns as usize =>
+            {
+                Scheduler::new_with_partitioning(num_txns, partitioning)
+            },
+            Some(BlockHints::Partitioning(partitioning)) => {
+                error!(
+                    "Ignoring partitioning of {} transactions in a block of {} transactions",
+                    partitioning.num_txns(),
+                    num_txns
+                );
+                Scheduler::new(num_txns)
+            },
+            None => Scheduler::new(num_txns),
+        };
+        let schedule_tracer = match schedule_replay {
+            Some(trace) => Some(ScheduleTracer::replay(trace)),
+            None => self
+                .config
+                .local
+                .record_schedule
+                .then(|| ScheduleTracer::record(concurrency_level, num_txns)),
+        };
+        let execution_priority = if self.config.local.prioritize_by_gas_price {
+            let gas_unit_prices: Vec<u64> = signature_verified_block
+                .iter()
+                .map(T::gas_unit_price)
+                .collect();
+            gas_price_priority(&gas_unit_prices)
+        } else {
+            Vec::new()
+        };
+        // The workers dedicated to materialization take the place of execution workers, unless
+        // they have their own thread pool.
+        let materialization_concurrency = match self.materialization_thread_pool {
+            Some(_) => self.config.local.materialization_concurrency,
+            None => min(
+                self.config.local.materialization_concurrency,
+                concurrency_level - 1,
+            ),
+        };
+        let execution_concurrency = match self.materialization_thread_pool {
+            Some(_) => concurrency_level,
+            None => concurrency_level - materialization_concurrency,
+        };
+        // A single execution worker would wait forever on a dependency, which only it can
+        // resolve, so the transactions are requeued instead.
+        let dependency_wait = DependencyWaitConfig {
+            requeue: self.config.local.dependency_wait.requeue || execution_concurrency == 1,
+            ..self.config.local.dependency_wait
+        };
+        let mut scheduler = scheduler
+            .with_execution_priority(execution_priority)
+            .with_dependency_wait(dependency_wait)
+            .with_max_incarnations(self.config.local.max_incarnations)
+            .with_schedule_tracer(schedule_tracer)
+            .with_execution_profiler(
+                self.config
+                    .local
+                    .profile_transactions
+                    .then(|| ExecutionProfiler::new(num_txns)),
+            );
+        let num_running_execution_workers = AtomicUsize::new(execution_concurrency);
 
-        let last_input_output = TxnLastInputOutput::new(num_txns);
-        let scheduler = Scheduler::new(num_txns);
+        let adaptive_concurrency = AdaptiveConcurrency::new(
+            self.config.local.adaptive_concurrency,
+            execution_concurrency,
+        );
+        let handle_worker_error = |err: PanicOr<ParallelBlockExecutionError>| {
+            // If there are multiple errors, they all get logged:
+            // ModulePathReadWriteError and FatalVMErrorvariant is logged at construction,
+            // and below we log CodeInvariantErrors.
+            if let PanicOr::CodeInvariantError(err_msg) = &err {
+                alert!("[BlockSTM] worker loop: CodeInvariantError({:?})", err_msg);
+            }
+            shared_failure
+                .lock()
+                .get_or_insert(ParallelExecutionFailure::from(&err));
+
+            // Make sure to halt the scheduler if it hasn't already been halted.
+            scheduler.halt();
+            if matches!(err, PanicOr::CodeInvariantError(_)) {
+                dump_versioned_cache(versioned_cache, &last_input_output, num_txns);
+            }
+        };
+        let run_execution_worker = || {
+            if let Some(core_affinity) = &self.core_affinity {
+                core_affinity.pin_current_thread();
+            }
+            if let Err(err) = with_block_seed(self.config.local.block_seed, || {
+                self.worker_loop(
+                    &executor_initial_arguments,
+                    signature_verified_block,
+                    block_timestamp,
+                    &last_input_output,
+                    versioned_cache,
+                    &scheduler,
+                    &base_resolver,
+                    start_shared_counter,
+                    shared_counter,
+                    &shared_commit_state,
+                    cancellation,
+                    &shared_cancelled,
+                    &adaptive_concurrency,
+                    materialization_concurrency > 0,
+                )
+            }) {
+                handle_worker_error(err);
+            }
+            num_running_execution_workers.fetch_sub(1, Ordering::Release);
+        };
+        let run_materialization_worker = || {
+            if let Err(err) = with_block_seed(self.config.local.block_seed, || {
+                self.materialization_loop(
+                    versioned_cache,
+                    &scheduler,
+                    start_shared_counter,
+                    shared_counter,
+                    &last_input_output,
+                    &base_resolver,
+                    &num_running_execution_workers,
+                )
+            }) {
+                handle_worker_error(err.into());
+            }
+        };
 
+        let block_trace = chrome_trace::begin_block();
         let timer = RAYON_EXECUTION_SECONDS.start_timer();
-        self.executor_thread_pool.scope(|s| {
-            for _ in 0..self.config.local.concurrency_level {
-                s.spawn(|_| {
-                    if let Err(err) = self.worker_loop(
-                        &executor_initial_arguments,
-                        signature_verified_block,
-                        &last_input_output,
-                        &versioned_cache,
-                        &scheduler,
-                        base_view,
-                        start_shared_counter,
-                        &shared_counter,
-                        &shared_commit_state,
-                        &final_results,
-                    ) {
-                        // If there are multiple errors, they all get logged:
-                        // ModulePathReadWriteError and FatalVMErrorvariant is logged at construction,
-                        // and below we log CodeInvariantErrors.
-                        if let PanicOr::CodeInvariantError(err_msg) = err {
-                            alert!("[BlockSTM] worker loop: CodeInvariantError({:?})", err_msg);
-                        }
-                        shared_maybe_error.store(true, Ordering::SeqCst);
-
-                        // Make sure to halt the scheduler if it hasn't already been halted.
-                        scheduler.halt();
+        match &self.materialization_thread_pool {
+            Some(materialization_thread_pool) => materialization_thread_pool.in_place_scope(|ms| {
+                for _ in 0..materialization_concurrency {
+                    ms.spawn(|_| run_materialization_worker());
+                }
+                self.executor_thread_pool.scope(|s| {
+                    for _ in 0..execution_concurrency {
+                        s.spawn(|_| run_execution_worker());
                     }
                 });
-            }
-        });
+            }),
+            None => self.executor_thread_pool.scope(|s| {
+                for _ in 0..execution_concurrency {
+                    s.spawn(|_| run_execution_worker());
+                }
+                for _ in 0..materialization_concurrency {
+                    s.spawn(|_| run_materialization_worker());
+                }
+            }),
+        }
         drop(timer);
+        if let Some(block_trace) = block_trace {
+            block_trace.finish();
+        }
+        // The transactions released early but not committed, e.g. after the end of the block.
+        let released_txns = std::mem::take(&mut *self.released_txns.lock());
+        for txn_idx in released_txns.into_keys() {
+            self.revoke_release(txn_idx);
+        }
+        self.execution_stats.record_scheduler(
+            scheduler.num_validation_waves() as u64,
+            scheduler.num_dependency_waits() as u64,
+        );
+        let num_committed_txns = scheduler.next_txn_to_commit();
+        self.num_committed_txns
+            .store(num_committed_txns, Ordering::Relaxed);
+        let block_end_info = BlockEndInfo {
+            num_committed_txns,
+            // The rest of the block is skipped after the last committed transaction, if any are
+            // left.
+            skip_reason: (num_committed_txns > 0 && num_committed_txns < num_txns as TxnIndex)
+                .then(|| last_input_output.skip_reason(num_committed_txns - 1))
+                .flatten(),
+            limit_usage: shared_commit_state.acquire().limit_usage(),
+        };
+        if let Some(profile) = scheduler.take_execution_profile() {
+            *self.execution_profile.lock() = Some(profile);
+        }
+        match scheduler.finish_schedule_trace() {
+            Some(Ok(trace)) => *self.schedule_trace.lock() = Some(trace),
+            Some(Err(divergence)) => {
+                error!("[BlockSTM] {}", divergence);
+                shared_failure
+                    .lock()
+                    .get_or_insert(ParallelExecutionFailure::CodeInvariantError(
+                        divergence.to_string(),
+                    ));
+            },
+            None => (),
+        }
+        let result = if shared_cancelled.load(Ordering::SeqCst) {
+            Err(ParallelExecutionFailure::Cancelled)
+        } else if let Some(failure) = shared_failure.into_inner() {
+            Err(failure)
+        } else {
+            // The outputs of the transactions after the block end are skipped.
+            let mut final_results: Vec<_> = last_input_output
+                .take_committed_outputs(num_committed_txns)
+                .collect();
+            final_results.resize_with(num_txns as usize, E::Output::skip_output);
+            let read_stats = self.config.local.record_read_stats.then(|| {
+                (0..num_txns)
+                    .map(|txn_idx| {
+                        (txn_idx < num_committed_txns)
+                            .then(|| last_input_output.read_stats(txn_idx))
+                            .flatten()
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            });
+            Ok(BlockOutput::new(final_results)
+                .with_block_end_info(Some(block_end_info))
+                .with_read_stats(read_stats))
+        };
         // Explicit async drops.
-        DEFAULT_DROPPER.schedule_drop((last_input_output, scheduler, versioned_cache));
-
-        // TODO add block end info to output.
-        // block_limit_processor.is_block_limit_reached();
-
-        (!shared_maybe_error.load(Ordering::SeqCst))
-            .then(|| BlockOutput::new(final_results.into_inner()))
-            .ok_or(())
+        DEFAULT_DROPPER.schedule_drop((last_input_output, scheduler));
+        result
     }
 
     fn apply_output_sequential(
         unsync_map: &UnsyncMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
         output: &E::Output,
-        resource_write_set: Vec<(T::Key, Arc<T::Value>, Option<Arc<MoveTypeLayout>>)>,
+        resource_write_set: &[(T::Key, Arc<T::Value>, Option<Arc<MoveTypeLayout>>)],
     ) -> Result<(), SequentialBlockExecutionError<E::Error>> {
-        for (key, write_op, layout) in resource_write_set.into_iter() {
+        for (key, write_op, layout) in resource_write_set.iter().cloned() {
             unsync_map.write(key, write_op, layout);
         }
 
@@ -995,35 +2382,106 @@ where
     pub(crate) fn execute_transactions_sequential(
         &self,
         executor_arguments: E::Argument,
-        signature_verified_block: &[T],
+        signature_verified_block: &dyn TxnProvider<T>,
         base_view: &S,
+        block_gas_limit_type: &BlockGasLimitType,
         resource_group_bcs_fallback: bool,
+        cancellation: Option<&CancellationToken>,
     ) -> Result<BlockOutput<E::Output>, SequentialBlockExecutionError<E::Error>> {
-        let num_txns = signature_verified_block.len();
-        let init_timer = VM_INIT_SECONDS.start_timer();
-        let executor = E::init(executor_arguments);
-        drop(init_timer);
+        let num_txns = signature_verified_block.num_txns();
+        self.reset_conflict_graph();
+        self.group_serialization_cache.clear();
+        let executor = self.executor_cache.take(executor_arguments);
 
         let start_counter = gen_id_start_value(true);
         let counter = RefCell::new(start_counter);
         let unsync_map = UnsyncMap::new();
         let mut ret = Vec::with_capacity(num_txns);
-        let mut block_limit_processor = BlockGasLimitProcessor::<T>::new(
-            self.config.onchain.block_gas_limit_type.clone(),
-            num_txns,
-        );
+        let mut block_limit_processor = self.new_block_limit_policy(block_gas_limit_type, num_txns);
+        let block_gas_limit_type = block_limit_processor.block_gas_limit_type().clone();
+        let mut skip_reason = None;
+        let block_timestamp = BlockTimestamp::from_block(signature_verified_block);
+        let mut read_stats = Vec::new();
 
         let last_input_output: TxnLastInputOutput<T, E::Output, E::Error> =
             TxnLastInputOutput::new(num_txns as TxnIndex);
 
         for (idx, txn) in signature_verified_block.iter().enumerate() {
+            if cancellation.map_or(false, CancellationToken::is_cancelled) {
+                return Err(SequentialBlockExecutionError::ErrorToReturn {
+                    error: BlockExecutionError::Cancelled,
+                    txn_idx: Some(idx as TxnIndex),
+                });
+            }
+
             let latest_view = LatestView::<T, S, X>::new(
                 base_view,
                 ViewState::Unsync(SequentialState::new(&unsync_map, start_counter, &counter)),
                 idx as TxnIndex,
-            );
-            let res = executor.execute_transaction(&latest_view, txn, idx as TxnIndex);
-            let must_skip = matches!(res, ExecutionStatus::SkipRest(_));
+            )
+            .with_hot_state_cache(self.hot_state_cache.as_deref())
+            .with_read_stats(self.config.local.record_read_stats);
+            let start_time = Instant::now();
+            let mut res = if block_timestamp.map_or(false, |block_timestamp| {
+                block_timestamp.is_expired(txn, idx as TxnIndex)
+            }) {
+                counters::EXPIRED_TXN_DISCARD_COUNT
+                    .with_label_values(&[counters::Mode::SEQUENTIAL])
+                    .inc();
+                ExecutionStatus::Success(E::Output::discard_output(StatusCode::TRANSACTION_EXPIRED))
+            } else {
+                executor.execute_transaction(&latest_view, txn, idx as TxnIndex)
+            };
+            self.execution_stats.record_execution(start_time.elapsed());
+            read_stats.extend(latest_view.read_stats());
+            // As in parallel execution, the output limit applies to the materialized output,
+            // measured before the writes are applied.
+            let exceeds_txn_output_limit = match (&res, block_gas_limit_type.txn_output_limit()) {
+                (
+                    ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output),
+                    Some(limit),
+                ) => {
+                    materialized_output_size(
+                        output,
+                        |group_key| Ok(unsync_map.finalize_group(group_key).collect()),
+                        |key| unsync_map.fetch_exchanged_data(key).map(|(value, _)| value),
+                        &self.group_serialization_cache,
+                    )
+                    .map_err(|e| SequentialBlockExecutionError::from(e).at_txn(idx as TxnIndex))?
+                        > limit
+                },
+                _ => false,
+            };
+            if exceeds_txn_output_limit {
+                counters::TXN_OUTPUT_LIMIT_DISCARD_COUNT
+                    .with_label_values(&[counters::Mode::SEQUENTIAL])
+                    .inc();
+                res = ExecutionStatus::Success(E::Output::discard_output(
+                    StatusCode::STORAGE_WRITE_LIMIT_REACHED,
+                ));
+            }
+            if self
+                .config
+                .local
+                .txn_execution_timeout
+                .map_or(false, |timeout| start_time.elapsed() >= timeout)
+                && matches!(
+                    res,
+                    ExecutionStatus::Success(_) | ExecutionStatus::SkipRest(_)
+                )
+            {
+                // Sequential execution is final, so the transaction is discarded right away.
+                counters::TXN_EXECUTION_TIMEOUT_COUNT
+                    .with_label_values(&["discarded"])
+                    .inc();
+                res = ExecutionStatus::Success(E::Output::discard_output(
+                    StatusCode::EXECUTION_LIMIT_REACHED,
+                ));
+            }
+            let mut must_skip = matches!(res, ExecutionStatus::SkipRest(_));
+            if must_skip {
+                skip_reason = Some(SkipReason::TransactionSkipRest);
+            }
             match res {
                 ExecutionStatus::Abort(err) => {
                     if let Some(commit_hook) = &self.transaction_commit_hook {
@@ -1034,76 +2492,94 @@ where
                         idx as TxnIndex
                     );
                     // Record the status indicating the unrecoverable VM failure.
-                    return Err(SequentialBlockExecutionError::ErrorToReturn(
-                        BlockExecutionError::FatalVMError(err),
-                    ));
+                    return Err(SequentialBlockExecutionError::ErrorToReturn {
+                        error: BlockExecutionError::FatalVMError(err),
+                        txn_idx: Some(idx as TxnIndex),
+                    });
                 },
                 ExecutionStatus::DelayedFieldsCodeInvariantError(msg) => {
                     if let Some(commit_hook) = &self.transaction_commit_hook {
                         commit_hook.on_execution_aborted(idx as TxnIndex);
                     }
                     alert!("Sequential execution DelayedFieldsCodeInvariantError error by transaction {}: {}", idx as TxnIndex, msg);
-                    return Err(SequentialBlockExecutionError::ErrorToReturn(
-                        BlockExecutionError::FatalBlockExecutorError(code_invariant_error(msg)),
-                    ));
+                    return Err(SequentialBlockExecutionError::ErrorToReturn {
+                        error: BlockExecutionError::FatalBlockExecutorError(code_invariant_error(
+                            msg,
+                        )),
+                        txn_idx: Some(idx as TxnIndex),
+                    });
                 },
                 ExecutionStatus::SpeculativeExecutionAbortError(msg) => {
                     if let Some(commit_hook) = &self.transaction_commit_hook {
                         commit_hook.on_execution_aborted(idx as TxnIndex);
                     }
                     alert!("Sequential execution SpeculativeExecutionAbortError error by transaction {}: {}", idx as TxnIndex, msg);
-                    return Err(SequentialBlockExecutionError::ErrorToReturn(
-                        BlockExecutionError::FatalBlockExecutorError(code_invariant_error(msg)),
-                    ));
+                    return Err(SequentialBlockExecutionError::ErrorToReturn {
+                        error: BlockExecutionError::FatalBlockExecutorError(code_invariant_error(
+                            msg,
+                        )),
+                        txn_idx: Some(idx as TxnIndex),
+                    });
                 },
                 ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
                     // Calculating the accumulated gas costs of the committed txns.
                     let fee_statement = output.fee_statement();
 
-                    let approx_output_size = self
-                        .config
-                        .onchain
-                        .block_gas_limit_type
-                        .block_output_limit()
-                        .map(|_| {
-                            output.output_approx_size()
-                                + if self
-                                    .config
-                                    .onchain
-                                    .block_gas_limit_type
-                                    .include_user_txn_size_in_block_output()
-                                {
-                                    txn.user_txn_bytes_len()
-                                } else {
-                                    0
-                                } as u64
-                        });
+                    let approx_output_size = block_gas_limit_type.block_output_limit().map(|_| {
+                        output.output_approx_size()
+                            + if block_gas_limit_type.include_user_txn_size_in_block_output() {
+                                txn.user_txn_bytes_len()
+                            } else {
+                                0
+                            } as u64
+                    });
 
                     let sequential_reads = latest_view.take_sequential_reads();
-                    let read_write_summary = self
-                        .config
-                        .onchain
-                        .block_gas_limit_type
-                        .conflict_penalty_window()
-                        .map(|_| {
+                    let read_write_summary =
+                        block_gas_limit_type.conflict_penalty_window().map(|_| {
                             ReadWriteSummary::new(
                                 sequential_reads.get_read_summary(),
                                 output.get_write_summary(),
                             )
                         });
 
-                    if last_input_output.check_and_append_module_rw_conflict(
-                        sequential_reads.module_reads.iter(),
-                        output.module_write_set().keys(),
-                    ) {
+                    if let Some(conflict_graph) = self.conflict_graph.lock().as_mut() {
+                        conflict_graph.record_committed(
+                            idx as TxnIndex,
+                            ReadWriteSummary::new(
+                                sequential_reads.get_read_summary(),
+                                output.get_write_summary(),
+                            ),
+                        );
+                    }
+
+                    let conflicting_modules = last_input_output
+                        .check_and_append_module_rw_conflict(
+                            sequential_reads.module_reads.iter(),
+                            output.module_write_set().keys(),
+                        );
+                    if !conflicting_modules.is_empty() {
+                        debug!(
+                            "[Execution] At txn {}, module read & write conflict on {:?}",
+                            idx, conflicting_modules
+                        );
+                        counters::MODULE_RW_CONFLICT_TXN_COUNT.inc();
+                        counters::MODULE_RW_CONFLICT_MODULE_COUNT
+                            .inc_by(conflicting_modules.len() as u64);
                         block_limit_processor.process_module_rw_conflict();
                     }
 
-                    block_limit_processor.accumulate_fee_statement(
+                    block_limit_processor.accumulate(
                         fee_statement,
                         read_write_summary,
                         approx_output_size,
                     );
+                    if let Some(commit_hook) = &self.transaction_commit_hook {
+                        commit_hook.on_block_limit_snapshot(
+                            idx as TxnIndex,
+                            &block_limit_processor.snapshot(),
+                        );
+                    }
 
                     output.materialize_agg_v1(&latest_view);
                     assert_eq!(
@@ -1183,73 +2659,100 @@ where
 
                     // Apply the writes.
                     let resource_write_set = output.resource_write_set();
-                    Self::apply_output_sequential(
-                        &unsync_map,
-                        &output,
-                        resource_write_set.clone(),
-                    )?;
+                    Self::apply_output_sequential(&unsync_map, &output, &resource_write_set)
+                        .map_err(|e| e.at_txn(idx as TxnIndex))?;
 
                     // If dynamic change set materialization part (indented for clarity/variable scope):
-                    {
-                        let finalized_groups = groups_to_finalize!(output,)
-                            .map(|((group_key, metadata_op), is_read_needing_exchange)| {
-                                let finalized_group =
-                                    Ok(unsync_map.finalize_group(&group_key).collect());
-                                map_finalized_group::<T>(
-                                    group_key,
-                                    finalized_group,
-                                    metadata_op,
-                                    is_read_needing_exchange,
-                                )
-                            })
-                            .collect::<Result<Vec<_>, _>>()?;
-                        let materialized_finalized_groups =
-                            map_id_to_values_in_group_writes(finalized_groups, &latest_view)?;
-                        let serialized_groups =
-                            serialize_groups::<T>(materialized_finalized_groups).map_err(|_| {
-                                SequentialBlockExecutionError::ResourceGroupSerializationError
-                            })?;
-
-                        let resource_writes_to_materialize = resource_writes_to_materialize!(
-                            resource_write_set,
-                            output,
-                            unsync_map,
-                        )?;
-                        // Replace delayed field id with values in resource write set and read set.
-                        let materialized_resource_write_set = map_id_to_values_in_write_set(
-                            resource_writes_to_materialize,
-                            &latest_view,
-                        )?;
-
-                        // Replace delayed field id with values in events
-                        let materialized_events = map_id_to_values_events(
-                            Box::new(output.get_events().into_iter()),
-                            &latest_view,
-                        )?;
-
-                        output.incorporate_materialized_txn_output(
-                            // No aggregator v1 delta writes are needed for sequential execution.
-                            // They are already handled because we passed materialize_deltas=true
-                            // to execute_transaction.
-                            vec![],
-                            materialized_resource_write_set
-                                .into_iter()
-                                .chain(serialized_groups.into_iter())
-                                .collect(),
-                            materialized_events,
-                        )?;
-                    }
+                    let materialize_output =
+                        || -> Result<(), SequentialBlockExecutionError<E::Error>> {
+                            let finalized_groups = groups_to_finalize!(output,)
+                                .map(|((group_key, metadata_op), is_read_needing_exchange)| {
+                                    let finalized_group =
+                                        Ok(unsync_map.finalize_group(&group_key).collect());
+                                    map_finalized_group::<T>(
+                                        group_key,
+                                        finalized_group,
+                                        metadata_op,
+                                        is_read_needing_exchange,
+                                    )
+                                })
+                                .collect::<Result<Vec<_>, _>>()?;
+                            let materialized_finalized_groups =
+                                map_id_to_values_in_group_writes(finalized_groups, &latest_view)?;
+                            let serialized_groups = serialize_groups::<T>(
+                                materialized_finalized_groups,
+                                idx as TxnIndex,
+                                &self.group_serialization_cache,
+                            )
+                            .map_err(
+                                SequentialBlockExecutionError::ResourceGroupSerializationError,
+                            )?;
+
+                            let resource_writes_to_materialize = resource_writes_to_materialize!(
+                                resource_write_set,
+                                output,
+                                unsync_map,
+                            )?;
+                            // Replace delayed field id with values in resource write set and read set.
+                            let materialized_resource_write_set = map_id_to_values_in_write_set(
+                                resource_writes_to_materialize,
+                                &latest_view,
+                            )?;
+
+                            // Replace delayed field id with values in events
+                            let materialized_events = map_id_to_values_events(
+                                Box::new(output.get_events().into_iter()),
+                                &latest_view,
+                            )?;
+
+                            output.incorporate_materialized_txn_output(
+                                // No aggregator v1 delta writes are needed for sequential execution.
+                                // They are already handled because we passed materialize_deltas=true
+                                // to execute_transaction.
+                                vec![],
+                                materialized_resource_write_set
+                                    .into_iter()
+                                    .chain(serialized_groups.into_iter())
+                                    .collect(),
+                                materialized_events,
+                            )?;
+                            Ok(())
+                        };
+                    materialize_output().map_err(|e| e.at_txn(idx as TxnIndex))?;
                     // If dynamic change set is disabled, this can be used to assert nothing needs patching instead:
                     //   output.set_txn_output_for_non_dynamic_change_set();
 
                     if latest_view.is_incorrect_use() {
-                        return Err(
-                            code_invariant_error("Incorrect use in sequential execution").into(),
-                        );
+                        return Err(SequentialBlockExecutionError::ErrorToReturn {
+                            error: BlockExecutionError::FatalBlockExecutorError(
+                                code_invariant_error("Incorrect use in sequential execution"),
+                            ),
+                            txn_idx: Some(idx as TxnIndex),
+                        });
                     }
 
                     if let Some(commit_hook) = &self.transaction_commit_hook {
-                        commit_hook.on_transaction_committed(idx as TxnIndex, &output);
+                        if !must_skip
+                            && idx < num_txns - 1
+                            && commit_hook.should_commit(idx as TxnIndex, &output)
+                                == CommitDecision::HaltBlock
+                        {
+                            info!("Transaction commit hook halted the block at txn {}", idx);
+                            counters::COMMIT_HOOK_HALT_COUNT.inc();
+                            must_skip = true;
+                            skip_reason = Some(SkipReason::CommitHook);
+                        }
+                        handle_commit_hook_result(
+                            commit_hook,
+                            idx as TxnIndex,
+                            commit_hook.on_transaction_committed(idx as TxnIndex, &output),
+                        )
+                        .map_err(|err| {
+                            SequentialBlockExecutionError::ErrorToReturn {
+                                error: BlockExecutionError::FatalBlockExecutorError(err),
+                                txn_idx: Some(idx as TxnIndex),
+                            }
+                        })?;
                     }
                     ret.push(output);
                 },
@@ -1259,57 +2762,384 @@ where
                 break;
             }
 
-            if idx < num_txns - 1 && block_limit_processor.should_end_block_sequential() {
-                break;
+            if idx < num_txns - 1 {
+                skip_reason = block_limit_processor.should_end(false);
+                if skip_reason.is_some() {
+                    break;
+                }
             }
         }
 
-        block_limit_processor
-            .finish_sequential_update_counters_and_log_info(ret.len() as u32, num_txns as u32);
+        block_limit_processor.finish(false, ret.len() as u32, num_txns as u32);
 
+        let block_end_info = BlockEndInfo {
+            num_committed_txns: ret.len() as TxnIndex,
+            // The rest of the block is skipped after the last committed transaction, if any are
+            // left.
+            skip_reason: (!ret.is_empty() && ret.len() < num_txns)
+                .then_some(skip_reason)
+                .flatten(),
+            limit_usage: block_limit_processor.limit_usage(),
+        };
         ret.resize_with(num_txns, E::Output::skip_output);
+        let read_stats = self.config.local.record_read_stats.then(|| {
+            read_stats.resize(num_txns, StateReadStats::default());
+            read_stats
+        });
 
-        // TODO add block end info to output.
-        // block_limit_processor.is_block_limit_reached();
+        Ok(BlockOutput::new(ret)
+            .with_block_end_info(Some(block_end_info))
+            .with_read_stats(read_stats))
+    }
 
-        Ok(BlockOutput::new(ret))
+    fn log_sequential_error(error: &BlockExecutionError<E::Error>, txn_idx: Option<TxnIndex>) {
+        match error {
+            BlockExecutionError::FatalBlockExecutorError(err) => alert!(
+                "Sequential execution FatalBlockExecutorError at txn {:?}: {:?}",
+                txn_idx,
+                err
+            ),
+            BlockExecutionError::FatalVMError(err) => error!(
+                "Sequential execution FatalVMError at txn {:?}: {:?}",
+                txn_idx, err
+            ),
+            BlockExecutionError::Cancelled => {
+                info!("Sequential execution cancelled at txn {:?}", txn_idx)
+            },
+        }
     }
 
+    /// Executes the block. The optional hints (access hints or the partitioning of the block) are
+    /// used to seed the order of parallel execution, and are ignored by sequential execution.
+    /// If the optional
+    /// cancellation token is cancelled before the execution finishes, the execution is aborted
+    /// and [`BlockExecutionError::Cancelled`] is returned. Errors are returned as a
+    /// [`BlockExecutionFailure`], with the index of the transaction at which they occurred (if
+    /// known). The optional overrides take precedence over the config of the block executor for
+    /// this block only.
     pub fn execute_block(
         &self,
         executor_arguments: E::Argument,
-        signature_verified_block: &[T],
+        signature_verified_block: &(impl TxnProvider<T> + ?Sized),
         base_view: &S,
+        hints: Option<BlockHints<'_, T::Key>>,
+        cancellation: Option<&CancellationToken>,
+        overrides: Option<ExecutionOverrides>,
     ) -> BlockExecutionResult<BlockOutput<E::Output>, E::Error> {
-        if self.config.local.concurrency_level > 1 {
-            let parallel_result = self.execute_transactions_parallel(
+        self.execute_block_with_caches(
+            executor_arguments,
+            &signature_verified_block,
+            base_view,
+            hints,
+            cancellation,
+            overrides.unwrap_or_default(),
+            None,
+        )
+    }
+
+    /// Executes consecutive blocks, re-using the caches of parallel execution across the
+    /// blocks (see [`BlockPipeline`]). The base view of each block must reflect the outputs
+    /// of all the previous blocks. Execution stops at the first block that returns an error,
+    /// so the returned results correspond to a prefix of the blocks.
+    pub fn execute_block_pipeline(
+        &self,
+        executor_arguments: E::Argument,
+        blocks: Vec<(&[T], &S)>,
+        cancellation: Option<&CancellationToken>,
+    ) -> Vec<BlockExecutionResult<BlockOutput<E::Output>, E::Error>> {
+        let mut pipeline = BlockPipeline::new(self);
+        let mut results = Vec::with_capacity(blocks.len());
+        for (signature_verified_block, base_view) in blocks {
+            let result = pipeline.execute_block(
                 executor_arguments,
                 signature_verified_block,
                 base_view,
+                None,
+                cancellation,
             );
-
-            // If parallel gave us result, return it
-            if let Ok(output) = parallel_result {
-                return Ok(output);
+            let is_err = result.is_err();
+            results.push(result);
+            if is_err {
+                break;
             }
+        }
+        results
+    }
 
-            if !self.config.local.allow_fallback {
-                panic!("Parallel execution failed and fallback is not allowed");
-            }
+    fn execute_block_with_caches(
+        &self,
+        executor_arguments: E::Argument,
+        signature_verified_block: &dyn TxnProvider<T>,
+        base_view: &S,
+        hints: Option<BlockHints<'_, T::Key>>,
+        cancellation: Option<&CancellationToken>,
+        overrides: ExecutionOverrides,
+        pipeline_caches: Option<&mut PipelineCaches<T, X>>,
+    ) -> BlockExecutionResult<BlockOutput<E::Output>, E::Error> {
+        self.execution_stats.reset();
+        // The workers of parallel execution enter the seed of the block on their own threads.
+        let result = with_block_seed(self.config.local.block_seed, || {
+            self.execute_block_with_fallbacks(
+                executor_arguments,
+                signature_verified_block,
+                base_view,
+                hints,
+                cancellation,
+                overrides,
+                pipeline_caches,
+            )
+        });
+        *self.last_execution_stats.lock() = Some(self.execution_stats.snapshot());
+        // The writes of a block that failed are not committed, so the cache remains valid.
+        if let (Some(hot_state_cache), Ok(output)) = (&self.hot_state_cache, &result) {
+            hot_state_cache.invalidate_committed(output.get_transaction_outputs_forced());
+        }
+        result
+    }
+
+    /// Executes the block sequentially for differential execution, with a separate block
+    /// executor (so that the commit hook and the state of this executor are not affected).
+    /// Returns None if the sequential execution fails, in which case the outputs of the block
+    /// are not compared.
+    fn execute_block_sequential_reference(
+        &self,
+        executor_arguments: E::Argument,
+        signature_verified_block: &dyn TxnProvider<T>,
+        base_view: &S,
+        block_gas_limit_type: &BlockGasLimitType,
+        cancellation: Option<&CancellationToken>,
+    ) -> Option<Vec<E::Output>> {
+        let reference_executor =
+            BlockExecutor::<T, E, S, NoOpTransactionCommitHook<E::Output, usize>, X>::new(
+                self.config.clone(),
+                Arc::clone(&self.executor_thread_pool),
+                None,
+            );
+        let result = reference_executor.execute_transactions_sequential(
+            executor_arguments,
+            signature_verified_block,
+            base_view,
+            block_gas_limit_type,
+            false,
+            cancellation,
+        );
+        // Only the logs of the execution whose outputs are returned are reported.
+        init_speculative_logs(signature_verified_block.num_txns());
+
+        match result {
+            Ok(output) => Some(output.into_inner()),
+            Err(_) => {
+                warn!("[BlockSTM] Differential execution: sequential execution failed");
+                None
+            },
+        }
+    }
+
+    fn record_execution_divergence(
+        &self,
+        parallel_output: &BlockOutput<E::Output>,
+        sequential_outputs: &[E::Output],
+    ) {
+        let divergence = first_divergence(
+            parallel_output.get_transaction_outputs_forced(),
+            sequential_outputs,
+        );
+        match &divergence {
+            Some(divergence) => {
+                counters::DIFFERENTIAL_EXECUTION_COUNT
+                    .with_label_values(&["diverged"])
+                    .inc();
+                alert!(
+                    "[BlockSTM] Parallel and sequential execution diverged at txn {}: {}",
+                    divergence.txn_idx,
+                    divergence.details
+                );
+            },
+            None => counters::DIFFERENTIAL_EXECUTION_COUNT
+                .with_label_values(&["matched"])
+                .inc(),
+        }
+        *self.execution_divergence.lock() = divergence;
+    }
+
+    fn execute_block_with_fallbacks(
+        &self,
+        executor_arguments: E::Argument,
+        signature_verified_block: &dyn TxnProvider<T>,
+        base_view: &S,
+        hints: Option<BlockHints<'_, T::Key>>,
+        cancellation: Option<&CancellationToken>,
+        overrides: ExecutionOverrides,
+        mut pipeline_caches: Option<&mut PipelineCaches<T, X>>,
+    ) -> BlockExecutionResult<BlockOutput<E::Output>, E::Error> {
+        let block_gas_limit_type = overrides
+            .gas_limit_override
+            .unwrap_or_else(|| self.config.onchain.block_gas_limit_type.clone());
+        let concurrency_level = overrides
+            .concurrency_level
+            .map_or(self.config.local.concurrency_level, |concurrency_level| {
+                concurrency_level.clamp(1, num_cpus::get())
+            });
+
+        *self.execution_divergence.lock() = None;
+        if let Some(block_state) = self.block_state.lock().take() {
+            DEFAULT_DROPPER.schedule_drop(block_state);
+        }
+        if concurrency_level > 1 || self.config.local.parallel_single_worker {
+            // Executed before parallel execution, so that its speculative logs can be cleared.
+            let sequential_outputs = if self.config.local.differential_execution {
+                self.execute_block_sequential_reference(
+                    executor_arguments,
+                    signature_verified_block,
+                    base_view,
+                    &block_gas_limit_type,
+                    cancellation,
+                )
+            } else {
+                None
+            };
+
+            let mut concurrency_level = concurrency_level;
+            let mut retried = false;
+            loop {
+                self.notify_block_execution_started();
+                let parallel_result = match pipeline_caches.as_deref_mut() {
+                    Some(caches) => {
+                        let parallel_result = self.execute_transactions_parallel_with_caches(
+                            concurrency_level,
+                            &block_gas_limit_type,
+                            executor_arguments,
+                            signature_verified_block,
+                            base_view,
+                            hints,
+                            cancellation,
+                            &caches.versioned_cache,
+                            &caches.shared_counter,
+                            &caches.prefetch_keys,
+                            None,
+                        );
+                        if parallel_result.is_ok() {
+                            caches.prefetch_keys =
+                                caches.versioned_cache.retain_unmodified_base_values();
+                        } else {
+                            // The writes of the block are not reflected in the data-structure
+                            // unless parallel execution succeeds, so the base values are stale.
+                            caches.reset();
+                        }
+                        parallel_result
+                    },
+                    None => self.execute_transactions_parallel_with_concurrency(
+                        concurrency_level,
+                        &block_gas_limit_type,
+                        executor_arguments,
+                        signature_verified_block,
+                        base_view,
+                        hints,
+                        cancellation,
+                    ),
+                };
+
+                // If parallel gave us result, return it
+                let failure = match parallel_result {
+                    Ok(output) => {
+                        if let Some(sequential_outputs) = &sequential_outputs {
+                            self.record_execution_divergence(&output, sequential_outputs);
+                        }
+                        return Ok(output);
+                    },
+                    Err(failure) => failure,
+                };
+
+                self.execution_stats
+                    .record_parallel_failure(failure.clone());
+                let fallback_policies = &self.config.local.fallback_policies;
+                let policy = match failure {
+                    // Don't fall back to sequential execution if the block was cancelled.
+                    ParallelExecutionFailure::Cancelled => {
+                        info!("parallel execution cancelled");
+                        return Err(BlockExecutionError::Cancelled.into());
+                    },
+                    ParallelExecutionFailure::CodeInvariantError(_) => {
+                        fallback_policies.code_invariant_error
+                    },
+                    ParallelExecutionFailure::ModulePathReadWriteError { .. } => {
+                        fallback_policies.module_read_write_conflict
+                    },
+                    ParallelExecutionFailure::FatalVMError { .. } => {
+                        fallback_policies.fatal_vm_error
+                    },
+                };
+
+                if !self.config.local.allow_fallback {
+                    panic!("Parallel execution failed and fallback is not allowed");
+                }
+                self.execution_stats.record_fallback(match policy {
+                    FallbackPolicy::RetryParallelOnce
+                    | FallbackPolicy::ParallelWithReducedConcurrency
+                        if retried =>
+                    {
+                        FallbackPolicy::Sequential
+                    },
+                    policy => policy,
+                });
 
-            // All logs from the parallel execution should be cleared and not reported.
-            // Clear by re-initializing the speculative logs.
-            init_speculative_logs(signature_verified_block.len());
+                // All logs from the parallel execution should be cleared and not reported.
+                // Clear by re-initializing the speculative logs.
+                init_speculative_logs(signature_verified_block.num_txns());
+
+                match policy {
+                    FallbackPolicy::RetryParallelOnce if !retried => {
+                        info!("parallel execution failed ({:?}), retrying", failure);
+                    },
+                    FallbackPolicy::ParallelWithReducedConcurrency if !retried => {
+                        concurrency_level = max(concurrency_level / 2, min(concurrency_level, 2));
+                        info!(
+                            "parallel execution failed ({:?}), retrying with concurrency level {}",
+                            failure, concurrency_level
+                        );
+                    },
+                    FallbackPolicy::Discard => {
+                        alert!(
+                            "Parallel execution failed ({:?}), discarding the block",
+                            failure
+                        );
+                        // The conflict graph of a failed block is incomplete.
+                        *self.conflict_graph.lock() = None;
+                        let error_code = match failure {
+                            ParallelExecutionFailure::CodeInvariantError(_) => {
+                                StatusCode::DELAYED_MATERIALIZATION_CODE_INVARIANT_ERROR
+                            },
+                            _ => StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+                        };
+                        return Ok(self.discard_block(
+                            executor_arguments,
+                            signature_verified_block,
+                            base_view,
+                            &block_gas_limit_type,
+                            cancellation,
+                            error_code,
+                        ));
+                    },
+                    // A failed retry falls back to sequential execution.
+                    FallbackPolicy::RetryParallelOnce
+                    | FallbackPolicy::ParallelWithReducedConcurrency
+                    | FallbackPolicy::Sequential => break,
+                }
+                retried = true;
+            }
 
             info!("parallel execution requiring fallback");
         }
 
         // If we didn't run parallel or it didn't finish successfully - run sequential
+        self.notify_block_execution_started();
         let sequential_result = self.execute_transactions_sequential(
             executor_arguments,
             signature_verified_block,
             base_view,
+            &block_gas_limit_type,
             false,
+            cancellation,
         );
 
         // If sequential gave us result, return it
@@ -1317,22 +3147,34 @@ where
             Ok(output) => {
                 return Ok(output);
             },
-            Err(SequentialBlockExecutionError::ResourceGroupSerializationError) => {
+            Err(SequentialBlockExecutionError::ResourceGroupSerializationError(err)) => {
                 if !self.config.local.allow_fallback {
                     panic!("Parallel execution failed and fallback is not allowed");
                 }
 
+                alert!(
+                    "Sequential execution resource group serialization error at txn {} \
+                     (group key {}, {} resources, approx size {}), falling back to bcs",
+                    err.txn_idx,
+                    err.group_key,
+                    err.num_resources,
+                    err.approx_size
+                );
+
                 // TODO[agg_v2](cleanup): check if sequential execution logs anything in the speculative logs,
                 // and whether clearing them below is needed at all.
                 // All logs from the first pass of sequential execution should be cleared and not reported.
                 // Clear by re-initializing the speculative logs.
-                init_speculative_logs(signature_verified_block.len());
+                init_speculative_logs(signature_verified_block.num_txns());
 
+                self.notify_block_execution_started();
                 let sequential_result = self.execute_transactions_sequential(
                     executor_arguments,
                     signature_verified_block,
                     base_view,
+                    &block_gas_limit_type,
                     true,
+                    cancellation,
                 );
 
                 // If sequential gave us result, return it
@@ -1340,36 +3182,248 @@ where
                     Ok(output) => {
                         return Ok(output);
                     },
-                    Err(SequentialBlockExecutionError::ResourceGroupSerializationError) => {
-                        BlockExecutionError::FatalBlockExecutorError(code_invariant_error(
-                            "resource group serialization during bcs fallback should not happen",
-                        ))
+                    Err(SequentialBlockExecutionError::ResourceGroupSerializationError(err)) => {
+                        BlockExecutionFailure {
+                            error: BlockExecutionError::FatalBlockExecutorError(
+                                code_invariant_error(format!(
+                                    "resource group serialization during bcs fallback should not \
+                                     happen: {:?}",
+                                    err
+                                )),
+                            ),
+                            txn_idx: Some(err.txn_idx),
+                        }
+                    },
+                    Err(SequentialBlockExecutionError::ErrorToReturn { error, txn_idx }) => {
+                        Self::log_sequential_error(&error, txn_idx);
+                        BlockExecutionFailure { error, txn_idx }
                     },
-                    Err(SequentialBlockExecutionError::ErrorToReturn(err)) => err,
                 }
             },
-            Err(SequentialBlockExecutionError::ErrorToReturn(err)) => err,
+            Err(SequentialBlockExecutionError::ErrorToReturn { error, txn_idx }) => {
+                Self::log_sequential_error(&error, txn_idx);
+                BlockExecutionFailure { error, txn_idx }
+            },
         };
+        // The conflict graph of a failed block is incomplete.
+        *self.conflict_graph.lock() = None;
 
-        if self.config.local.discard_failed_blocks {
-            // We cannot execute block, discard everything (including block metadata and validator transactions)
-            // (TODO: maybe we should add fallback here to first try BlockMetadataTransaction alone)
+        // A cancelled block must not be committed, not even with discarded transactions.
+        if self.config.local.discard_failed_blocks
+            && !matches!(sequential_error.error, BlockExecutionError::Cancelled)
+        {
+            // We cannot execute block, discard the user transactions (and if the block metadata
+            // and validator transactions cannot be executed alone either, discard everything).
             // StateCheckpoint will be added afterwards.
-            let error_code = match sequential_error {
+            self.execution_stats
+                .record_fallback(FallbackPolicy::Discard);
+            let error_code = match sequential_error.error {
                 BlockExecutionError::FatalBlockExecutorError(_) => {
                     StatusCode::DELAYED_MATERIALIZATION_CODE_INVARIANT_ERROR
                 },
-                BlockExecutionError::FatalVMError(_) => {
+                BlockExecutionError::FatalVMError(_) | BlockExecutionError::Cancelled => {
                     StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR
                 },
             };
-            let ret = signature_verified_block
+            return Ok(self.discard_block(
+                executor_arguments,
+                signature_verified_block,
+                base_view,
+                &block_gas_limit_type,
+                cancellation,
+                error_code,
+            ));
+        }
+
+        Err(sequential_error)
+    }
+
+    /// Notifies the commit hook (if any) that the executor starts executing the block (again).
+    fn notify_block_execution_started(&self) {
+        if let Some(commit_hook) = &self.transaction_commit_hook {
+            commit_hook.on_block_execution_started();
+        }
+    }
+
+    /// Discards the transactions of a block that cannot be executed, with the given error code.
+    /// If discarded_block_bookkeeping is set, the block metadata and validator transactions are
+    /// executed on their own (sequentially) instead, so that e.g. the block timestamp and epoch
+    /// changes are not lost. If they cannot be executed either, or if there is a commit hook
+    /// (which observes the indices of the executed transactions), all transactions are
+    /// discarded.
+    fn discard_block(
+        &self,
+        executor_arguments: E::Argument,
+        signature_verified_block: &dyn TxnProvider<T>,
+        base_view: &S,
+        block_gas_limit_type: &BlockGasLimitType,
+        cancellation: Option<&CancellationToken>,
+        error_code: StatusCode,
+    ) -> BlockOutput<E::Output> {
+        // The commit hook is not called for the discarded transactions.
+        self.notify_block_execution_started();
+        let bookkeeping_txns: Vec<T> = signature_verified_block
+            .iter()
+            .filter(|txn| txn.is_block_metadata_or_validator_txn())
+            .cloned()
+            .collect();
+
+        let mut bookkeeping_outputs = None;
+        let mut limit_usage = BlockLimitUsage::default();
+        if self.config.local.discarded_block_bookkeeping
+            && self.transaction_commit_hook.is_none()
+            && !bookkeeping_txns.is_empty()
+            && bookkeeping_txns.len() < signature_verified_block.num_txns()
+        {
+            init_speculative_logs(bookkeeping_txns.len());
+            match self.execute_transactions_sequential(
+                executor_arguments,
+                &bookkeeping_txns,
+                base_view,
+                block_gas_limit_type,
+                false,
+                cancellation,
+            ) {
+                Ok(output) => {
+                    if let Some(block_end_info) = output.block_end_info() {
+                        limit_usage = block_end_info.limit_usage;
+                    }
+                    bookkeeping_outputs = Some(output.into_transaction_outputs_forced());
+                },
+                Err(_) => {
+                    alert!("Block metadata and validator transactions failed to execute alone");
+                },
+            }
+            // The conflict graph would only cover the executed transactions.
+            *self.conflict_graph.lock() = None;
+        }
+
+        let outputs: Vec<_> = match bookkeeping_outputs {
+            Some(bookkeeping_outputs) => {
+                let mut bookkeeping_outputs = bookkeeping_outputs.into_iter();
+                signature_verified_block
+                    .iter()
+                    .map(|txn| {
+                        if txn.is_block_metadata_or_validator_txn() {
+                            bookkeeping_outputs
+                                .next()
+                                .expect("Output must exist for each executed transaction")
+                        } else {
+                            E::Output::discard_output(error_code)
+                        }
+                    })
+                    .collect()
+            },
+            None => signature_verified_block
                 .iter()
                 .map(|_| E::Output::discard_output(error_code))
-                .collect();
-            return Ok(BlockOutput::new(ret));
+                .collect(),
+        };
+        // The discarded transactions are committed (with their discard outputs), none skipped.
+        let block_end_info = BlockEndInfo {
+            num_committed_txns: outputs.len() as TxnIndex,
+            skip_reason: None,
+            limit_usage,
+        };
+        BlockOutput::new(outputs).with_block_end_info(Some(block_end_info))
+    }
+}
+
+impl<T, E, S, X> BlockExecutor<T, E, S, CommittedOutputStream<E::Output>, X>
+where
+    T: Transaction,
+    E: ExecutorTask<Txn = T>,
+    E::Output: Clone,
+    S: TStateView<Key = T::Key> + Sync,
+    X: Executable + 'static,
+{
+    /// Creates a block executor that streams the materialized output of every committed
+    /// transaction of the executed blocks as soon as it is materialized, in order (see
+    /// [`CommittedOutputStream`]), instead of only returning the outputs of a block once it is
+    /// executed. The stream ends once the block executor is dropped.
+    pub fn new_with_output_stream(
+        config: BlockExecutorConfig,
+        executor_thread_pool: Arc<ThreadPool>,
+    ) -> (Self, Receiver<StreamedOutput<E::Output>>) {
+        let (output_stream, receiver) = CommittedOutputStream::new();
+        (
+            Self::new(config, executor_thread_pool, Some(output_stream)),
+            receiver,
+        )
+    }
+}
+
+/// The multi-version data-structure and the counter for delayed field identifiers, carried
+/// over between the blocks executed by a [`BlockPipeline`].
+struct PipelineCaches<T: Transaction, X: Executable> {
+    versioned_cache: MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+    shared_counter: AtomicU32,
+    /// The keys read from storage by the previous block that are no longer cached, as they
+    /// were modified by the block. Prefetched for the next block, if enabled.
+    prefetch_keys: Vec<T::Key>,
+}
+
+impl<T: Transaction, X: Executable + 'static> PipelineCaches<T, X> {
+    fn new() -> Self {
+        Self {
+            versioned_cache: MVHashMap::new(),
+            shared_counter: AtomicU32::new(gen_id_start_value(false)),
+            prefetch_keys: Vec::new(),
         }
+    }
 
-        Err(sequential_error)
+    fn reset(&mut self) {
+        DEFAULT_DROPPER.schedule_drop(std::mem::replace(self, Self::new()));
+    }
+}
+
+/// Executes consecutive blocks, e.g. when replaying a chunk of blocks, without rebuilding
+/// the caches of parallel execution for every block. After a block is executed in parallel,
+/// the base values of the keys and resource groups that it did not modify are retained in
+/// the multi-version data-structure and served to the next block without reading the base
+/// view, and the counter for delayed field identifiers keeps increasing across blocks.
+///
+/// The base view of each block must reflect the outputs of all the previously executed
+/// blocks. If a block is not executed in parallel (e.g. due to a fallback to sequential
+/// execution), the caches are reset.
+pub struct BlockPipeline<'a, T: Transaction, E, S, L, X: Executable> {
+    executor: &'a BlockExecutor<T, E, S, L, X>,
+    caches: PipelineCaches<T, X>,
+}
+
+impl<'a, T, E, S, L, X> BlockPipeline<'a, T, E, S, L, X>
+where
+    T: Transaction,
+    E: ExecutorTask<Txn = T>,
+    S: TStateView<Key = T::Key> + Sync,
+    L: TransactionCommitHook<Output = E::Output>,
+    X: Executable + 'static,
+{
+    pub fn new(executor: &'a BlockExecutor<T, E, S, L, X>) -> Self {
+        Self {
+            executor,
+            caches: PipelineCaches::new(),
+        }
+    }
+
+    /// Executes the next block of the pipeline, with the same semantics as
+    /// [`BlockExecutor::execute_block`].
+    pub fn execute_block(
+        &mut self,
+        executor_arguments: E::Argument,
+        signature_verified_block: &(impl TxnProvider<T> + ?Sized),
+        base_view: &S,
+        hints: Option<BlockHints<'_, T::Key>>,
+        cancellation: Option<&CancellationToken>,
+    ) -> BlockExecutionResult<BlockOutput<E::Output>, E::Error> {
+        self.executor.execute_block_with_caches(
+            executor_arguments,
+            &signature_verified_block,
+            base_view,
+            hints,
+            cancellation,
+            ExecutionOverrides::default(),
+            Some(&mut self.caches),
+        )
     }
 }
@@ -0,0 +1,148 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters::{EXECUTOR_CACHE_HIT_COUNT, VM_INIT_SECONDS},
+    task::ExecutorTask,
+};
+use aptos_infallible::Mutex;
+use std::ops::Deref;
+
+struct CachedExecutors<E> {
+    // The argument fingerprint of the cached executors (see ExecutorTask::argument_fingerprint).
+    fingerprint: Option<u64>,
+    executors: Vec<E>,
+}
+
+/// Caches the initialized executor instances across the blocks executed by a block executor, so
+/// that the workers do not initialize an executor for every block (e.g. a VM, which loads the
+/// on-chain configs). Only the executors whose instances are interchangeable for the arguments
+/// with the same fingerprint are cached (see [`ExecutorTask::argument_fingerprint`]).
+pub(crate) struct ExecutorCache<E> {
+    cached: Mutex<CachedExecutors<E>>,
+}
+
+impl<E: ExecutorTask> ExecutorCache<E> {
+    pub(crate) fn new() -> Self {
+        Self {
+            cached: Mutex::new(CachedExecutors {
+                fingerprint: None,
+                executors: Vec::new(),
+            }),
+        }
+    }
+
+    /// Takes a cached executor initialized with arguments of the same fingerprint, or else
+    /// initializes one. The executor is returned to the cache when the guard is dropped.
+    pub(crate) fn take(&self, args: E::Argument) -> CachedExecutor<'_, E> {
+        let fingerprint = E::argument_fingerprint(&args);
+        let cached = fingerprint.and_then(|fingerprint| {
+            let mut cached = self.cached.lock();
+            (cached.fingerprint == Some(fingerprint))
+                .then(|| cached.executors.pop())
+                .flatten()
+        });
+        let executor = match cached {
+            Some(executor) => {
+                EXECUTOR_CACHE_HIT_COUNT.inc();
+                executor
+            },
+            None => {
+                let _timer = VM_INIT_SECONDS.start_timer();
+                E::init(args)
+            },
+        };
+
+        CachedExecutor {
+            cache: self,
+            fingerprint,
+            executor: Some(executor),
+        }
+    }
+
+    /// Drops the cached executors, e.g. when the on-chain configs or features that they were
+    /// initialized with change without the argument fingerprint reflecting it. Must not be
+    /// called while a block is being executed, as the executors in use are cached afterwards.
+    pub(crate) fn invalidate(&self) {
+        let mut cached = self.cached.lock();
+        cached.fingerprint = None;
+        cached.executors.clear();
+    }
+
+    fn put(&self, fingerprint: u64, executor: E) {
+        let mut cached = self.cached.lock();
+        if cached.fingerprint != Some(fingerprint) {
+            // The executors initialized with different arguments are not needed anymore.
+            cached.fingerprint = Some(fingerprint);
+            cached.executors.clear();
+        }
+        cached.executors.push(executor);
+    }
+
+    #[cfg(test)]
+    fn num_cached(&self) -> usize {
+        self.cached.lock().executors.len()
+    }
+}
+
+/// An executor taken from the [`ExecutorCache`], to which it is returned when dropped (if it
+/// can be reused).
+pub(crate) struct CachedExecutor<'a, E: ExecutorTask> {
+    cache: &'a ExecutorCache<E>,
+    fingerprint: Option<u64>,
+    executor: Option<E>,
+}
+
+impl<'a, E: ExecutorTask> Deref for CachedExecutor<'a, E> {
+    type Target = E;
+
+    fn deref(&self) -> &E {
+        self.executor
+            .as_ref()
+            .expect("Executor is only taken when dropped")
+    }
+}
+
+impl<'a, E: ExecutorTask> Drop for CachedExecutor<'a, E> {
+    fn drop(&mut self) {
+        if let (Some(fingerprint), Some(executor)) = (self.fingerprint, self.executor.take()) {
+            self.cache.put(fingerprint, executor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest_types::types::{KeyType, MockEvent, MockTask};
+
+    type Task = MockTask<KeyType<u32>, MockEvent>;
+
+    #[test]
+    fn test_executor_reuse() {
+        let cache = ExecutorCache::<Task>::new();
+        let first = cache.take(());
+        let second = cache.take(());
+        assert_eq!(cache.num_cached(), 0);
+        drop(first);
+        drop(second);
+        assert_eq!(cache.num_cached(), 2);
+
+        // The cached executors are reused.
+        let executor = cache.take(());
+        assert_eq!(cache.num_cached(), 1);
+        drop(executor);
+        assert_eq!(cache.num_cached(), 2);
+
+        // Caching an executor initialized with different arguments drops the others.
+        cache.put(1, Task::new());
+        assert_eq!(cache.num_cached(), 1);
+        let executor = cache.take(());
+        assert_eq!(cache.num_cached(), 1);
+        drop(executor);
+        assert_eq!(cache.num_cached(), 1);
+
+        cache.invalidate();
+        assert_eq!(cache.num_cached(), 0);
+    }
+}
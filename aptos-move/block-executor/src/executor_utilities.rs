@@ -1,10 +1,16 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{errors::*, view::LatestView};
+use crate::{
+    errors::*, group_serialization_cache::GroupSerializationCache, task::TransactionOutput,
+    txn_last_input_output::TxnLastInputOutput, view::LatestView,
+};
 use aptos_aggregator::types::code_invariant_error;
 use aptos_logger::error;
-use aptos_mvhashmap::types::ValueWithLayout;
+use aptos_mvhashmap::{
+    types::{TxnIndex, ValueWithLayout},
+    MVHashMap,
+};
 use aptos_types::{
     contract_event::TransactionEvent, delayed_fields::PanicError, executable::Executable,
     state_store::TStateView, transaction::BlockExecutableTransaction as Transaction,
@@ -15,7 +21,7 @@ use bytes::Bytes;
 use fail::fail_point;
 use move_core_types::value::MoveTypeLayout;
 use rand::{thread_rng, Rng};
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, fmt::Debug, sync::Arc};
 
 // TODO(clean-up): refactor & replace these macros with functions for code clarity. Currently
 // not possible due to type & API mismatch.
@@ -118,30 +124,41 @@ pub(crate) fn map_finalized_group<T: Transaction>(
 
 pub(crate) fn serialize_groups<T: Transaction>(
     finalized_groups: Vec<(T::Key, T::Value, Vec<(T::Tag, Arc<T::Value>)>)>,
+    txn_idx: TxnIndex,
+    serialization_cache: &GroupSerializationCache<T>,
 ) -> Result<Vec<(T::Key, T::Value)>, ResourceGroupSerializationError> {
     fail_point!(
         "fail-point-resource-group-serialization",
         !finalized_groups.is_empty(),
-        |_| Err(ResourceGroupSerializationError)
+        |_| Err(ResourceGroupSerializationError {
+            txn_idx,
+            group_key: format!("{:?}", finalized_groups[0].0),
+            num_resources: finalized_groups[0].2.len(),
+            approx_size: finalized_groups[0]
+                .2
+                .iter()
+                .map(|(_, v)| v.bytes().map_or(0, |bytes| bytes.len()))
+                .sum(),
+        })
     );
 
     finalized_groups
         .into_iter()
         .map(|(group_key, mut metadata_op, finalized_group)| {
-            let btree: BTreeMap<T::Tag, Bytes> = finalized_group
-                .into_iter()
-                .map(|(resource_tag, arc_v)| {
-                    let bytes = arc_v
-                        .extract_raw_bytes()
-                        .expect("Deletions should already be applied");
-                    (resource_tag, bytes)
-                })
-                .collect();
-
-            bcs::to_bytes(&btree)
+            serialization_cache
+                .serialize_group(&group_key, &finalized_group)
                 .map_err(|e| {
-                    alert!("Unexpected resource group error {:?}", e);
-                    ResourceGroupSerializationError
+                    let err = ResourceGroupSerializationError {
+                        txn_idx,
+                        group_key: format!("{:?}", group_key),
+                        num_resources: finalized_group.len(),
+                        approx_size: finalized_group
+                            .iter()
+                            .map(|(_, value)| value.bytes().map_or(0, |bytes| bytes.len()))
+                            .sum(),
+                    };
+                    alert!("Unexpected resource group error {:?}: {:?}", e, err);
+                    err
                 })
                 .map(|group_bytes| {
                     metadata_op.set_bytes(group_bytes.into());
@@ -151,6 +168,114 @@ pub(crate) fn serialize_groups<T: Transaction>(
         .collect()
 }
 
+/// The size of the materialized output of a transaction, i.e. the sum of the sizes of the values
+/// of its materialized write set and of the data of its events. Unlike output_approx_size, a
+/// resource group written by the transaction (or whose delayed fields it reads) counts as the
+/// whole serialized group, a resource whose delayed fields it reads counts as written, and an
+/// aggregator v1 delta counts as the written value. Replacing the delayed field identifiers with
+/// their values preserves the sizes, so the size is known before the output is materialized,
+/// given the group before the transaction (committed_group) and the read resource values
+/// (exchanged_value).
+pub(crate) fn materialized_output_size<T: Transaction>(
+    output: &impl TransactionOutput<Txn = T>,
+    committed_group: impl Fn(&T::Key) -> anyhow::Result<Vec<(T::Tag, ValueWithLayout<T::Value>)>>,
+    exchanged_value: impl Fn(&T::Key) -> Option<Arc<T::Value>>,
+    serialization_cache: &GroupSerializationCache<T>,
+) -> Result<u64, PanicError> {
+    let value_size = |value: &T::Value| value.bytes().map_or(0, |bytes| bytes.len());
+
+    let mut size: usize = output
+        .resource_write_set()
+        .iter()
+        .map(|(_, value, _)| value_size(value))
+        .sum();
+    size += output
+        .reads_needing_delayed_field_exchange()
+        .iter()
+        .filter_map(|(key, _, _)| exchanged_value(key))
+        .map(|value| value_size(&value))
+        .sum::<usize>();
+    size += output
+        .aggregator_v1_write_set()
+        .values()
+        .chain(output.module_write_set().values())
+        .map(value_size)
+        .sum::<usize>();
+    // The value of an aggregator v1 is a u128.
+    size += output.aggregator_v1_delta_set().len() * std::mem::size_of::<u128>();
+
+    let group_writes = output
+        .resource_group_write_set()
+        .into_iter()
+        .map(|(group_key, _, group_ops)| (group_key, group_ops))
+        .chain(
+            output
+                .group_reads_needing_delayed_field_exchange()
+                .into_iter()
+                .map(|(group_key, _)| (group_key, BTreeMap::new())),
+        );
+    for (group_key, group_ops) in group_writes {
+        let mut group: BTreeMap<_, _> = committed_group(&group_key)
+            .map_err(|e| code_invariant_error(format!("Error reading resource group {:?}", e)))?
+            .into_iter()
+            .map(|(tag, value_with_layout)| match value_with_layout {
+                ValueWithLayout::RawFromStorage(value) | ValueWithLayout::Exchanged(value, _) => {
+                    (tag, value)
+                },
+            })
+            .collect();
+        for (tag, (group_op, _)) in group_ops {
+            if group_op.is_deletion() {
+                group.remove(&tag);
+            } else {
+                group.insert(tag, Arc::new(group_op));
+            }
+        }
+        if !group.is_empty() {
+            // A group that fails to serialize is handled when the output is materialized.
+            size += serialization_cache
+                .serialized_group_size(&group_key, &group.into_iter().collect::<Vec<_>>())
+                .unwrap_or(0);
+        }
+    }
+
+    size += output
+        .get_events()
+        .iter()
+        .map(|(event, _)| event.get_event_data().len())
+        .sum::<usize>();
+    Ok(size as u64)
+}
+
+/// Logs the version chains of the keys written by the last recorded outputs of the transactions
+/// of the block (see [`MVHashMap::dump_key`]) if the failpoint is enabled, so that the
+/// postmortems of code invariant errors include the state of the multi-version data-structure.
+#[allow(unused_variables)]
+pub(crate) fn dump_versioned_cache<T, O, E, X>(
+    versioned_cache: &MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
+    last_input_output: &TxnLastInputOutput<T, O, E>,
+    num_txns: TxnIndex,
+) where
+    T: Transaction,
+    O: TransactionOutput<Txn = T>,
+    E: Debug + Send + Clone,
+    X: Executable,
+{
+    fail_point!("dump-versioned-cache-on-code-invariant-error", |_| {
+        let modified_keys: std::collections::HashSet<T::Key> = (0..num_txns)
+            .filter_map(|txn_idx| last_input_output.modified_keys(txn_idx))
+            .flatten()
+            .map(|(key, _)| key)
+            .collect();
+        for key in modified_keys {
+            error!(
+                "[BlockSTM] version chain of {}",
+                versioned_cache.dump_key(&key)
+            );
+        }
+    });
+}
+
 pub(crate) fn gen_id_start_value(sequential: bool) -> u32 {
     // IDs are ephemeral. Pick a random prefix, and different each time,
     // in case exchange is mistakenly not performed - to more easily catch it.
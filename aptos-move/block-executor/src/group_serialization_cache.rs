@@ -0,0 +1,250 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::counters::GROUP_SERIALIZATION_CACHE_HIT_COUNT;
+use aptos_types::{
+    transaction::BlockExecutableTransaction as Transaction, write_set::TransactionWrite,
+};
+use bytes::Bytes;
+use dashmap::DashMap;
+use rayon::prelude::*;
+use std::sync::Arc;
+
+// Groups with at least this many members have their members serialized in parallel (e.g. the
+// 0x1 object groups), on the thread pool of the materialization.
+const PARALLEL_SERIALIZATION_MIN_MEMBERS: usize = 32;
+
+struct SerializedMember<V> {
+    // The serialized value, which determines the version of the member: the value of a member
+    // that is not modified keeps being shared (as the same Arc) by the committed groups.
+    value: Arc<V>,
+    tag_len: usize,
+    // The BCS serialized map entry of the member, i.e. the tag followed by the value bytes.
+    entry: Bytes,
+}
+
+/// Caches the serialized members of the resource groups committed in a block, so that a group
+/// is serialized at every commit without re-serializing its members that were not modified
+/// (e.g. for large groups such as the 0x1 account resources). A member is re-serialized when
+/// its value is no longer the cached one (by identity, which the cache pins).
+pub(crate) struct GroupSerializationCache<T: Transaction> {
+    members: DashMap<(T::Key, T::Tag), SerializedMember<T::Value>>,
+}
+
+impl<T: Transaction> GroupSerializationCache<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            members: DashMap::new(),
+        }
+    }
+
+    /// Drops the cached members, e.g. at the start of a block.
+    pub(crate) fn clear(&self) {
+        self.members.clear();
+    }
+
+    /// Returns the same bytes as the BCS serialization of the group as a BTreeMap from tags to
+    /// value bytes. Deletions must already be applied.
+    pub(crate) fn serialize_group(
+        &self,
+        group_key: &T::Key,
+        group: &[(T::Tag, Arc<T::Value>)],
+    ) -> Result<Vec<u8>, bcs::Error> {
+        if group.len() > bcs::MAX_SEQUENCE_LENGTH {
+            return Err(bcs::Error::ExceededMaxLen(group.len()));
+        }
+
+        let mut entries = if group.len() >= PARALLEL_SERIALIZATION_MIN_MEMBERS {
+            group
+                .par_iter()
+                .map(|(tag, value)| self.serialized_member(group_key, tag, value))
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            group
+                .iter()
+                .map(|(tag, value)| self.serialized_member(group_key, tag, value))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        // BCS orders the entries of a map by their serialized keys.
+        entries.sort_by(|(len_1, entry_1), (len_2, entry_2)| {
+            entry_1[..*len_1].cmp(&entry_2[..*len_2])
+        });
+        let mut bytes =
+            Vec::with_capacity(entries.iter().map(|(_, entry)| entry.len()).sum::<usize>() + 5);
+        write_uleb128(&mut bytes, entries.len());
+        for (_, entry) in entries {
+            bytes.extend_from_slice(&entry);
+        }
+        Ok(bytes)
+    }
+
+    /// Returns the length of the serialization of the group (see serialize_group), reusing the
+    /// cached members but without caching the others, e.g. to measure a group that may not be
+    /// committed.
+    pub(crate) fn serialized_group_size(
+        &self,
+        group_key: &T::Key,
+        group: &[(T::Tag, Arc<T::Value>)],
+    ) -> Result<usize, bcs::Error> {
+        if group.len() > bcs::MAX_SEQUENCE_LENGTH {
+            return Err(bcs::Error::ExceededMaxLen(group.len()));
+        }
+
+        let mut len_bytes = Vec::new();
+        write_uleb128(&mut len_bytes, group.len());
+        group
+            .iter()
+            .try_fold(len_bytes.len(), |size, (tag, value)| {
+                let entry_size = match self
+                    .members
+                    .get(&(group_key.clone(), tag.clone()))
+                    .filter(|member| Arc::ptr_eq(&member.value, value))
+                {
+                    Some(member) => member.entry.len(),
+                    None => {
+                        let bytes = value
+                            .extract_raw_bytes()
+                            .expect("Deletions should already be applied");
+                        bcs::serialized_size(tag)? + bcs::serialized_size(&bytes)?
+                    },
+                };
+                Ok(size + entry_size)
+            })
+    }
+
+    /// Returns the BCS serialized map entry of a group member (the tag followed by the value
+    /// bytes) and the length of the serialized tag. The bytes cached for the member are returned
+    /// if its value is unchanged, otherwise the member is serialized and cached.
+    pub(crate) fn serialized_member(
+        &self,
+        group_key: &T::Key,
+        tag: &T::Tag,
+        value: &Arc<T::Value>,
+    ) -> Result<(usize, Bytes), bcs::Error> {
+        let key = (group_key.clone(), tag.clone());
+        if let Some(member) = self
+            .members
+            .get(&key)
+            .filter(|member| Arc::ptr_eq(&member.value, value))
+        {
+            GROUP_SERIALIZATION_CACHE_HIT_COUNT.inc();
+            return Ok((member.tag_len, member.entry.clone()));
+        }
+
+        let mut entry = bcs::to_bytes(tag)?;
+        let tag_len = entry.len();
+        let bytes = value
+            .extract_raw_bytes()
+            .expect("Deletions should already be applied");
+        entry.extend(bcs::to_bytes(&bytes)?);
+        let entry = Bytes::from(entry);
+        self.members.insert(key, SerializedMember {
+            value: value.clone(),
+            tag_len,
+            entry: entry.clone(),
+        });
+        Ok((tag_len, entry))
+    }
+}
+
+fn write_uleb128(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest_types::types::{KeyType, MockEvent, MockTransaction, ValueType};
+    use std::collections::BTreeMap;
+
+    type Txn = MockTransaction<KeyType<u32>, MockEvent>;
+
+    fn bcs_group(group: &[(u32, Arc<ValueType>)]) -> Vec<u8> {
+        let btree: BTreeMap<u32, Bytes> = group
+            .iter()
+            .map(|(tag, value)| (*tag, value.extract_raw_bytes().unwrap()))
+            .collect();
+        bcs::to_bytes(&btree).unwrap()
+    }
+
+    #[test]
+    fn test_serialize_group_matches_bcs() {
+        let cache = GroupSerializationCache::<Txn>::new();
+        let group_key = KeyType(1, false);
+        // More than 128 members (multi-byte length), with tags whose serialized (little
+        // endian) bytes are not ordered like the tags.
+        let mut group: Vec<_> = (0..300u32)
+            .map(|i| {
+                let tag = i * 255;
+                (tag, Arc::new(ValueType::from_value(vec![i as u8], true)))
+            })
+            .collect();
+        assert_eq!(
+            cache.serialize_group(&group_key, &group).unwrap(),
+            bcs_group(&group)
+        );
+
+        // Unchanged members are reused, while modified members are re-serialized.
+        group[7].1 = Arc::new(ValueType::from_value(vec![200], true));
+        group.truncate(250);
+        assert_eq!(
+            cache.serialize_group(&group_key, &group).unwrap(),
+            bcs_group(&group)
+        );
+
+        assert_eq!(
+            cache.serialize_group(&group_key, &[]).unwrap(),
+            bcs_group(&[])
+        );
+    }
+
+    #[test]
+    fn test_serialized_group_size_matches_bcs() {
+        let cache = GroupSerializationCache::<Txn>::new();
+        let group_key = KeyType(1, false);
+        let mut group: Vec<_> = (0..200u32)
+            .map(|i| {
+                let tag = i * 255;
+                (tag, Arc::new(ValueType::from_value(vec![i as u8; 3], true)))
+            })
+            .collect();
+        assert_eq!(
+            cache.serialized_group_size(&group_key, &group).unwrap(),
+            bcs_group(&group).len()
+        );
+        // Measuring does not cache the members.
+        assert!(cache.members.is_empty());
+
+        // Cached and not cached members are measured alike.
+        cache.serialize_group(&group_key, &group[..100]).unwrap();
+        group[150].1 = Arc::new(ValueType::from_value(vec![1; 1000], true));
+        assert_eq!(
+            cache.serialized_group_size(&group_key, &group).unwrap(),
+            bcs_group(&group).len()
+        );
+        assert_eq!(cache.members.len(), 100);
+    }
+
+    #[test]
+    fn test_serialized_member_reuses_unchanged_bytes() {
+        let cache = GroupSerializationCache::<Txn>::new();
+        let group_key = KeyType(1, false);
+        let value = Arc::new(ValueType::from_value(vec![1, 2, 3], true));
+
+        let (tag_len, entry) = cache.serialized_member(&group_key, &7, &value).unwrap();
+        assert_eq!(&entry[..tag_len], bcs::to_bytes(&7u32).unwrap().as_slice());
+        let (_, cached_entry) = cache.serialized_member(&group_key, &7, &value).unwrap();
+        assert_eq!(cached_entry.as_ptr(), entry.as_ptr());
+
+        // An equal but different value is serialized again.
+        let new_value = Arc::new(ValueType::from_value(vec![1, 2, 3], true));
+        let (_, new_entry) = cache.serialized_member(&group_key, &7, &new_value).unwrap();
+        assert_eq!(new_entry, entry);
+        assert_ne!(new_entry.as_ptr(), entry.as_ptr());
+    }
+}
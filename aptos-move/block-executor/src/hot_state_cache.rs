@@ -0,0 +1,276 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters::{HOT_STATE_CACHE_INVALIDATION_COUNT, HOT_STATE_CACHE_READ_COUNT},
+    task::TransactionOutput,
+};
+use aptos_crypto::HashValue;
+use aptos_infallible::{Mutex, MutexGuard};
+use aptos_types::{
+    state_store::{errors::StateviewError, state_value::StateValue, TStateView},
+    transaction::{BlockExecutableTransaction as Transaction, Version},
+};
+use lru::LruCache;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+// The entries are spread over shards, so that the workers reading base values concurrently do
+// not contend on a single lock.
+const NUM_SHARDS: usize = 16;
+
+/// Identifies the state that the base values of a block are read from, or the state that a
+/// block leaves.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HotStateId {
+    /// The state after the block with the given id.
+    Block(HashValue),
+    /// The state before the transaction at the given version.
+    Version(Version),
+}
+
+/// A bounded, least-recently-used cache of base values shared across the blocks executed with it
+/// (see [`BlockExecutor::with_hot_state_cache`]), so that the values read by most blocks (e.g.
+/// the resources of 0x1, the gas schedule, hot account balances) are not read from the base view
+/// for every block.
+///
+/// The cached values are the values after the last block executed with the cache: the keys
+/// written by the committed transactions of a block are invalidated after the block. Hence, a
+/// block must only be executed with the cache under a [`HotStateCacheLease`], which clears the
+/// cache unless the parent state of the block is the state left by the previous block (e.g. if
+/// the block is executed on another branch, or if the state was synced without execution).
+///
+/// [`BlockExecutor::with_hot_state_cache`]: crate::executor::BlockExecutor::with_hot_state_cache
+pub struct HotStateCache<K> {
+    shards: Vec<Mutex<LruCache<K, Option<StateValue>>>>,
+    // The state that the cached values are from, i.e. the state left by the last block executed
+    // with the cache, if known. Locked while a block is executed with the cache.
+    state: Mutex<Option<HotStateId>>,
+}
+
+/// The exclusive use of a [`HotStateCache`] for the execution of one block, see
+/// [`HotStateCache::lease`].
+pub struct HotStateCacheLease<'a> {
+    state: MutexGuard<'a, Option<HotStateId>>,
+}
+
+impl HotStateCacheLease<'_> {
+    /// Records the state left by the block executed under the lease, once the writes of its
+    /// committed transactions are invalidated (i.e. once the block executor returned its
+    /// output). If the lease is dropped instead (e.g. if the execution failed), the cache is
+    /// cleared before the next block.
+    pub fn complete(mut self, state: HotStateId) {
+        *self.state = Some(state);
+    }
+}
+
+impl<K: Hash + Eq + Clone> HotStateCache<K> {
+    /// Creates a cache holding up to (about) capacity values, which must be positive.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Hot-state cache capacity must be positive");
+        let shard_capacity = (capacity + NUM_SHARDS - 1) / NUM_SHARDS;
+        Self {
+            shards: (0..NUM_SHARDS)
+                .map(|_| Mutex::new(LruCache::new(shard_capacity)))
+                .collect(),
+            state: Mutex::new(None),
+        }
+    }
+
+    /// Prepares the cache for the execution of a block on the given parent state, clearing it
+    /// unless the cached values are from that state. Waits for the lease of the block executed
+    /// with the cache before (if any) to be completed or dropped.
+    pub fn lease(&self, parent_state: HotStateId) -> HotStateCacheLease<'_> {
+        let mut state = self.state.lock();
+        if *state != Some(parent_state) {
+            self.clear();
+        }
+        // Unknown until the block is executed.
+        *state = None;
+        HotStateCacheLease { state }
+    }
+
+    fn shard(&self, key: &K) -> &Mutex<LruCache<K, Option<StateValue>>> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % NUM_SHARDS]
+    }
+
+    /// Returns the value of the key from the cache, or else reads it from the base view and
+    /// caches it (unless the read fails).
+    pub(crate) fn get_state_value<S: TStateView<Key = K>>(
+        &self,
+        base_view: &S,
+        key: &K,
+    ) -> Result<Option<StateValue>, StateviewError> {
+        self.get_state_value_with_hit(base_view, key)
+            .map(|(value, _)| value)
+    }
+
+    /// Same as [`HotStateCache::get_state_value`], also returning whether the value was served
+    /// by the cache.
+    pub(crate) fn get_state_value_with_hit<S: TStateView<Key = K>>(
+        &self,
+        base_view: &S,
+        key: &K,
+    ) -> Result<(Option<StateValue>, bool), StateviewError> {
+        if let Some(value) = self.shard(key).lock().get(key) {
+            HOT_STATE_CACHE_READ_COUNT.with_label_values(&["hit"]).inc();
+            return Ok((value.clone(), true));
+        }
+
+        HOT_STATE_CACHE_READ_COUNT
+            .with_label_values(&["miss"])
+            .inc();
+        let value = base_view.get_state_value(key)?;
+        self.shard(key).lock().put(key.clone(), value.clone());
+        Ok((value, false))
+    }
+
+    /// Invalidates the keys written by the committed outputs of a block. If the keys written by
+    /// an output are not known, the whole cache is cleared.
+    pub(crate) fn invalidate_committed<O>(&self, outputs: &[O])
+    where
+        O: TransactionOutput,
+        O::Txn: Transaction<Key = K>,
+    {
+        let mut num_invalidated = 0;
+        for output in outputs {
+            let Some(keys) = output.committed_write_keys() else {
+                self.clear();
+                return;
+            };
+            for key in keys {
+                if self.shard(&key).lock().pop(&key).is_some() {
+                    num_invalidated += 1;
+                }
+            }
+        }
+        HOT_STATE_CACHE_INVALIDATION_COUNT.inc_by(num_invalidated);
+    }
+
+    /// Drops all the cached values.
+    fn clear(&self) {
+        for shard in &self.shards {
+            shard.lock().clear();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest_types::types::{DeltaDataView, KeyType, MockEvent, MockOutput, ValueType};
+    use aptos_types::state_store::{state_storage_usage::StateStorageUsage, StateViewId};
+    use std::{
+        marker::PhantomData,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    struct CountingView {
+        num_reads: AtomicUsize,
+        inner: DeltaDataView<KeyType<u32>>,
+    }
+
+    impl TStateView for CountingView {
+        type Key = KeyType<u32>;
+
+        fn get_state_value(
+            &self,
+            key: &KeyType<u32>,
+        ) -> Result<Option<StateValue>, StateviewError> {
+            self.num_reads.fetch_add(1, Ordering::Relaxed);
+            self.inner.get_state_value(key)
+        }
+
+        fn id(&self) -> StateViewId {
+            StateViewId::Miscellaneous
+        }
+
+        fn get_usage(&self) -> Result<StateStorageUsage, StateviewError> {
+            unreachable!("Not used in tests");
+        }
+    }
+
+    #[test]
+    fn test_invalidate_committed_writes() {
+        let cache = HotStateCache::new(100);
+        let view = CountingView {
+            num_reads: AtomicUsize::new(0),
+            inner: DeltaDataView {
+                phantom: PhantomData,
+            },
+        };
+        let (written_key, read_key) = (KeyType(1, false), KeyType(2, false));
+        for key in [&written_key, &read_key, &written_key, &read_key] {
+            assert!(cache.get_state_value(&view, key).unwrap().is_some());
+        }
+        assert_eq!(view.num_reads.load(Ordering::Relaxed), 2);
+        assert_eq!(cache.len(), 2);
+
+        // Only the written key is read again after the block.
+        let output = MockOutput::<KeyType<u32>, MockEvent> {
+            writes: vec![(written_key.clone(), ValueType::from_value(vec![1], true))],
+            skipped: false,
+            ..MockOutput::skip_output()
+        };
+        cache.invalidate_committed(&[output, MockOutput::skip_output()]);
+        assert_eq!(cache.len(), 1);
+        for key in [&written_key, &read_key] {
+            assert!(cache.get_state_value(&view, key).unwrap().is_some());
+        }
+        assert_eq!(view.num_reads.load(Ordering::Relaxed), 3);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_lease_clears_unless_parent_state_is_cached() {
+        let cache = HotStateCache::new(100);
+        let view = CountingView {
+            num_reads: AtomicUsize::new(0),
+            inner: DeltaDataView {
+                phantom: PhantomData,
+            },
+        };
+        let key = KeyType(1, false);
+        let (block_1, block_2, sibling_2) = (
+            HotStateId::Block(HashValue::random()),
+            HotStateId::Block(HashValue::random()),
+            HotStateId::Block(HashValue::random()),
+        );
+
+        // Nothing is known to be cached before the first block.
+        let lease = cache.lease(block_1);
+        assert!(cache.get_state_value(&view, &key).unwrap().is_some());
+        lease.complete(block_2);
+
+        // The child of the last executed block reads the cached value.
+        let lease = cache.lease(block_2);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get_state_value(&view, &key).unwrap().is_some());
+        assert_eq!(view.num_reads.load(Ordering::Relaxed), 1);
+        drop(lease);
+
+        // The execution under the dropped lease did not complete, so the cache is cleared.
+        let lease = cache.lease(block_2);
+        assert!(cache.is_empty());
+        assert!(cache.get_state_value(&view, &key).unwrap().is_some());
+        lease.complete(sibling_2);
+
+        // A block executed on another branch does not read the values of the cache.
+        let _lease = cache.lease(block_2);
+        assert!(cache.is_empty());
+    }
+}
@@ -139,19 +139,33 @@ subsequent incarnation to finish.
 #[macro_use(defer)]
 extern crate scopeguard;
 
+mod adaptive_concurrency;
+mod affinity;
+mod base_value_resolver;
+pub mod block_state;
+pub mod cancellation;
 mod captured_reads;
+pub mod chrome_trace;
 pub mod counters;
+pub mod differential_execution;
 pub mod errors;
+pub mod execution_profile;
+pub mod execution_stats;
 pub mod executor;
+mod executor_cache;
 mod executor_utilities;
 pub mod explicit_sync_wrapper;
-mod limit_processor;
+mod group_serialization_cache;
+pub mod hot_state_cache;
+pub mod limit_processor;
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod proptest_types;
+pub mod schedule_trace;
 mod scheduler;
 pub mod task;
 pub mod txn_commit_hook;
 pub mod txn_last_input_output;
+pub mod txn_provider;
 pub mod types;
 #[cfg(test)]
 mod unit_tests;
@@ -1,14 +1,89 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{counters, types::ReadWriteSummary};
+use crate::{
+    counters,
+    types::{InputOutputKey, ReadWriteSummary},
+};
 use aptos_logger::info;
 use aptos_types::{
-    fee_statement::FeeStatement, on_chain_config::BlockGasLimitType,
-    transaction::BlockExecutableTransaction as Transaction,
+    fee_statement::FeeStatement,
+    on_chain_config::BlockGasLimitType,
+    transaction::{BlockExecutableTransaction as Transaction, BlockLimitUsage, SkipReason},
 };
 use claims::{assert_le, assert_none};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+/// Decides when to end a block early, based on the transactions committed so far (in order).
+/// [`BlockGasLimitProcessor`] is the default policy, which enforces the block gas limit type of
+/// the block. Other policies can be set on the block executor (see
+/// [`BlockExecutor::with_block_limit_policy`]), e.g. to experiment with different limits.
+///
+/// [`BlockExecutor::with_block_limit_policy`]: crate::executor::BlockExecutor::with_block_limit_policy
+pub trait BlockLimitPolicy<T: Transaction>: Send {
+    /// The block gas limit type of the block, which determines whether the read/write summaries
+    /// and the approximate output sizes of the transactions are computed for accumulate.
+    fn block_gas_limit_type(&self) -> &BlockGasLimitType;
+
+    /// Accumulates the fee statement (and read/write summary and approximate output size, if
+    /// required by the block gas limit type) of the next committed transaction.
+    fn accumulate(
+        &mut self,
+        fee_statement: FeeStatement,
+        txn_read_write_summary: Option<ReadWriteSummary<T>>,
+        approx_output_size: Option<u64>,
+    );
+
+    /// Called in sequential execution before accumulating a transaction that read or wrote a
+    /// module that is both read and written in the block, i.e. the penalty (if any) applies to
+    /// the next accumulated transaction only.
+    fn process_module_rw_conflict(&mut self) {}
+
+    /// Returns the reason to skip the rest of the block after the accumulated transactions, if
+    /// the block should end.
+    fn should_end(&mut self, is_parallel: bool) -> Option<SkipReason>;
+
+    /// Called once after the last transaction of the block is committed, e.g. to update the
+    /// counters and log the block.
+    fn finish(&mut self, is_parallel: bool, num_committed: u32, num_total: u32);
+
+    /// The amounts accumulated so far for each of the block limits, reported in the block end
+    /// info.
+    fn limit_usage(&self) -> BlockLimitUsage;
+
+    /// The running totals after the last accumulated transaction, passed to the commit hook
+    /// (see [`TransactionCommitHook::on_block_limit_snapshot`]).
+    ///
+    /// [`TransactionCommitHook::on_block_limit_snapshot`]: crate::txn_commit_hook::TransactionCommitHook::on_block_limit_snapshot
+    fn snapshot(&self) -> BlockLimitSnapshot;
+}
+
+/// The running totals of the block limits after a committed transaction, e.g. for a streaming
+/// consumer of the committed transactions to apply backpressure, or to shrink the next block.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockLimitSnapshot {
+    /// The number of committed transactions accumulated so far.
+    pub num_accumulated_txns: u32,
+    pub limit_usage: BlockLimitUsage,
+    /// The conflict multiplier of the gas of the last accumulated transaction (1 if it did not
+    /// conflict, or if the conflicts are not penalized).
+    pub last_conflict_multiplier: u64,
+}
+
+/// Creates the block limit policy of a block, from the block gas limit type of the block and the
+/// number of transactions in the block.
+pub type BlockLimitPolicyFactory<T> =
+    Arc<dyn Fn(BlockGasLimitType, usize) -> Box<dyn BlockLimitPolicy<T>> + Send + Sync>;
+
+// The fixed-point scale of the decaying conflict scores of the keys: a write of a key adds
+// CONFLICT_SCORE_SCALE to its score (see conflict_penalty_decay_percentage).
+const CONFLICT_SCORE_SCALE: u64 = 1_000_000;
+
+/// The default block limit policy, which enforces the block gas limit type of the block.
 pub struct BlockGasLimitProcessor<T: Transaction> {
     block_gas_limit_type: BlockGasLimitType,
     accumulated_effective_block_gas: u64,
@@ -16,8 +91,17 @@ pub struct BlockGasLimitProcessor<T: Transaction> {
     accumulated_fee_statement: FeeStatement,
     txn_fee_statements: Vec<FeeStatement>,
     txn_read_write_summaries: Vec<ReadWriteSummary<T>>,
-    block_limit_reached: bool,
+    // With a decaying conflict penalty, the (scaled) conflict score of each written key, and
+    // the index of the txn at which it was last updated.
+    key_conflict_scores: HashMap<InputOutputKey<T::Key, T::Tag, T::Identifier>, (u64, usize)>,
+    // Which block limit was reached, if any.
+    block_limit_reached: Option<SkipReason>,
+    // Whether the next accumulated txn touched a module with a read/write conflict.
     module_rw_conflict: bool,
+    last_conflict_multiplier: u64,
+    // The wall-clock time budget of the block (if set), from the creation of the processor.
+    time_budget: Option<Duration>,
+    start_time: Instant,
 }
 
 impl<T: Transaction> BlockGasLimitProcessor<T> {
@@ -29,11 +113,22 @@ impl<T: Transaction> BlockGasLimitProcessor<T> {
             accumulated_fee_statement: FeeStatement::zero(),
             txn_fee_statements: Vec::with_capacity(init_size),
             txn_read_write_summaries: Vec::with_capacity(init_size),
-            block_limit_reached: false,
+            key_conflict_scores: HashMap::new(),
+            block_limit_reached: None,
             module_rw_conflict: false,
+            last_conflict_multiplier: 1,
+            time_budget: None,
+            start_time: Instant::now(),
         }
     }
 
+    /// Ends the block once the time elapsed since the processor was created reaches the budget,
+    /// in addition to the limits of the block gas limit type.
+    pub fn with_time_budget(mut self, time_budget: Duration) -> Self {
+        self.time_budget = Some(time_budget);
+        self
+    }
+
     pub(crate) fn accumulate_fee_statement(
         &mut self,
         fee_statement: FeeStatement,
@@ -60,7 +155,22 @@ impl<T: Transaction> BlockGasLimitProcessor<T> {
                     txn_read_write_summary.collapse_resource_group_conflicts()
                 },
             );
-            if self.module_rw_conflict {
+            if let Some(decay_percentage) = self
+                .block_gas_limit_type
+                .conflict_penalty_decay_percentage()
+            {
+                // The scores are updated even for a txn with a module read/write conflict, as it
+                // only affects the multiplier.
+                let conflict_multiplier = self.compute_decayed_conflict_multiplier(
+                    conflict_overlap_length as usize,
+                    decay_percentage as u64,
+                );
+                if self.module_rw_conflict {
+                    conflict_overlap_length as u64
+                } else {
+                    conflict_multiplier
+                }
+            } else if self.module_rw_conflict {
                 conflict_overlap_length as u64
             } else {
                 self.compute_conflict_multiplier(conflict_overlap_length as usize)
@@ -69,10 +179,12 @@ impl<T: Transaction> BlockGasLimitProcessor<T> {
             assert_none!(txn_read_write_summary);
             1
         };
+        self.module_rw_conflict = false;
 
         // When the accumulated execution and io gas of the committed txns exceeds
         // PER_BLOCK_GAS_LIMIT, early halt BlockSTM. Storage fee does not count towards
         // the per block gas limit, as we measure execution related cost here.
+        self.last_conflict_multiplier = conflict_multiplier;
         self.accumulated_effective_block_gas += conflict_multiplier
             * (fee_statement.execution_gas_used()
                 * self
@@ -89,33 +201,6 @@ impl<T: Transaction> BlockGasLimitProcessor<T> {
         }
     }
 
-    pub(crate) fn process_module_rw_conflict(&mut self) {
-        if self.module_rw_conflict
-            || !self
-                .block_gas_limit_type
-                .use_module_publishing_block_conflict()
-        {
-            return;
-        }
-
-        let conflict_multiplier = if let Some(conflict_overlap_length) =
-            self.block_gas_limit_type.conflict_penalty_window()
-        {
-            conflict_overlap_length
-        } else {
-            return;
-        };
-
-        self.accumulated_effective_block_gas = conflict_multiplier as u64
-            * (self.accumulated_fee_statement.execution_gas_used()
-                * self
-                    .block_gas_limit_type
-                    .execution_gas_effective_multiplier()
-                + self.accumulated_fee_statement.io_gas_used()
-                    * self.block_gas_limit_type.io_gas_effective_multiplier());
-        self.module_rw_conflict = true;
-    }
-
     fn should_end_block(&mut self, mode: &str) -> bool {
         if let Some(per_block_gas_limit) = self.block_gas_limit_type.block_gas_limit() {
             // When the accumulated block gas of the committed txns exceeds
@@ -130,7 +215,7 @@ impl<T: Transaction> BlockGasLimitProcessor<T> {
                     accumulated_block_gas {} >= PER_BLOCK_GAS_LIMIT {}",
                     mode, accumulated_block_gas, per_block_gas_limit,
                 );
-                self.block_limit_reached = true;
+                self.block_limit_reached = Some(SkipReason::BlockGasLimit);
 
                 return true;
             }
@@ -147,7 +232,62 @@ impl<T: Transaction> BlockGasLimitProcessor<T> {
                     accumulated_output {} >= PER_BLOCK_OUTPUT_LIMIT {}",
                     mode, accumulated_output, per_block_output_limit,
                 );
-                self.block_limit_reached = true;
+                self.block_limit_reached = Some(SkipReason::BlockOutputLimit);
+
+                return true;
+            }
+        }
+
+        // The limits on the (unscaled) amounts of each category of fees.
+        for (category, limit, accumulated, skip_reason) in [
+            (
+                "execution_gas",
+                self.block_gas_limit_type.execution_gas_limit(),
+                self.accumulated_fee_statement.execution_gas_used(),
+                SkipReason::BlockExecutionGasLimit,
+            ),
+            (
+                "io_gas",
+                self.block_gas_limit_type.io_gas_limit(),
+                self.accumulated_fee_statement.io_gas_used(),
+                SkipReason::BlockIoGasLimit,
+            ),
+            (
+                "storage_fee",
+                self.block_gas_limit_type.storage_fee_limit(),
+                self.accumulated_fee_statement.storage_fee_used(),
+                SkipReason::BlockStorageFeeLimit,
+            ),
+        ] {
+            if let Some(limit) = limit {
+                if accumulated >= limit {
+                    counters::EXCEED_PER_BLOCK_CATEGORY_LIMIT_COUNT
+                        .with_label_values(&[mode, category])
+                        .inc();
+                    info!(
+                        "[BlockSTM]: execution ({}) early halted due to \
+                        accumulated {} {} >= limit {}",
+                        mode, category, accumulated, limit,
+                    );
+                    self.block_limit_reached = Some(skip_reason);
+
+                    return true;
+                }
+            }
+        }
+
+        if let Some(time_budget) = self.time_budget {
+            let elapsed = self.start_time.elapsed();
+            if elapsed >= time_budget {
+                counters::EXCEED_PER_BLOCK_TIME_BUDGET_COUNT
+                    .with_label_values(&[mode])
+                    .inc();
+                info!(
+                    "[BlockSTM]: execution ({}) early halted due to \
+                    elapsed time {:?} >= block time budget {:?}",
+                    mode, elapsed, time_budget,
+                );
+                self.block_limit_reached = Some(SkipReason::BlockTimeBudget);
 
                 return true;
             }
@@ -190,6 +330,48 @@ impl<T: Transaction> BlockGasLimitProcessor<T> {
         (conflict_count + 1) as u64
     }
 
+    fn decay_conflict_score(score: u64, decay_percentage: u64, num_txns: usize) -> u64 {
+        // As the decay percentage is below 100, the score reaches 0 after a bounded number of
+        // txns (regardless of the size of the block).
+        (0..num_txns)
+            .try_fold(score, |score, _| {
+                (score > 0).then(|| score * decay_percentage / 100)
+            })
+            .unwrap_or(0)
+    }
+
+    // Computes the conflict multiplier of the last txn from the decayed conflict scores of the
+    // keys it read, then adds its writes to the conflict scores.
+    fn compute_decayed_conflict_multiplier(
+        &mut self,
+        conflict_overlap_length: usize,
+        decay_percentage: u64,
+    ) -> u64 {
+        let txn_idx = self.txn_read_write_summaries.len() - 1;
+        let current = &self.txn_read_write_summaries[txn_idx];
+
+        let max_conflict_score = current
+            .reads()
+            .iter()
+            .filter_map(|key| self.key_conflict_scores.get(key))
+            .map(|(score, last_txn_idx)| {
+                Self::decay_conflict_score(*score, decay_percentage, txn_idx - last_txn_idx)
+            })
+            .max()
+            .unwrap_or(0);
+        for key in current.writes() {
+            let (score, last_txn_idx) = self
+                .key_conflict_scores
+                .entry(key.clone())
+                .or_insert((0, txn_idx));
+            *score = Self::decay_conflict_score(*score, decay_percentage, txn_idx - *last_txn_idx)
+                + CONFLICT_SCORE_SCALE;
+            *last_txn_idx = txn_idx;
+        }
+
+        (1 + max_conflict_score / CONFLICT_SCORE_SCALE).min(conflict_overlap_length as u64)
+    }
+
     fn finish_update_counters_and_log_info(
         &self,
         is_parallel: bool,
@@ -210,6 +392,12 @@ impl<T: Transaction> BlockGasLimitProcessor<T> {
 
         info!(
             effective_block_gas = accumulated_effective_block_gas,
+            execution_gas = self.accumulated_fee_statement.execution_gas_used(),
+            execution_gas_limit = self.block_gas_limit_type.execution_gas_limit().unwrap_or(0),
+            io_gas = self.accumulated_fee_statement.io_gas_used(),
+            io_gas_limit = self.block_gas_limit_type.io_gas_limit().unwrap_or(0),
+            storage_fee = self.accumulated_fee_statement.storage_fee_used(),
+            storage_fee_limit = self.block_gas_limit_type.storage_fee_limit().unwrap_or(0),
             block_gas_limit = self.block_gas_limit_type.block_gas_limit().unwrap_or(0),
             block_gas_limit_exceeded = self
                 .block_gas_limit_type
@@ -232,25 +420,77 @@ impl<T: Transaction> BlockGasLimitProcessor<T> {
         );
     }
 
-    pub(crate) fn finish_parallel_update_counters_and_log_info(
-        &self,
-        num_committed: u32,
-        num_total: u32,
-    ) {
-        self.finish_update_counters_and_log_info(true, num_committed, num_total)
+    #[allow(unused)]
+    pub(crate) fn is_block_limit_reached(&self) -> bool {
+        self.block_limit_reached.is_some()
     }
 
-    pub(crate) fn finish_sequential_update_counters_and_log_info(
-        &self,
-        num_committed: u32,
-        num_total: u32,
+    /// The reason to skip the rest of the block, if should_end_block_parallel or
+    /// should_end_block_sequential returned true.
+    pub(crate) fn block_limit_skip_reason(&self) -> Option<SkipReason> {
+        self.block_limit_reached
+    }
+}
+
+impl<T: Transaction> BlockLimitPolicy<T> for BlockGasLimitProcessor<T> {
+    fn block_gas_limit_type(&self) -> &BlockGasLimitType {
+        &self.block_gas_limit_type
+    }
+
+    fn accumulate(
+        &mut self,
+        fee_statement: FeeStatement,
+        txn_read_write_summary: Option<ReadWriteSummary<T>>,
+        approx_output_size: Option<u64>,
     ) {
-        self.finish_update_counters_and_log_info(false, num_committed, num_total)
+        self.accumulate_fee_statement(fee_statement, txn_read_write_summary, approx_output_size);
     }
 
-    #[allow(unused)]
-    pub(crate) fn is_block_limit_reached(&self) -> bool {
-        self.block_limit_reached
+    fn process_module_rw_conflict(&mut self) {
+        // Only the txns touching the conflicting modules are penalized (with the maximum
+        // multiplier), the rest of the block is accounted as usual.
+        self.module_rw_conflict = self
+            .block_gas_limit_type
+            .use_module_publishing_block_conflict()
+            && self
+                .block_gas_limit_type
+                .conflict_penalty_window()
+                .is_some();
+    }
+
+    fn should_end(&mut self, is_parallel: bool) -> Option<SkipReason> {
+        let should_end = if is_parallel {
+            self.should_end_block_parallel()
+        } else {
+            self.should_end_block_sequential()
+        };
+        if should_end {
+            self.block_limit_skip_reason()
+        } else {
+            None
+        }
+    }
+
+    fn finish(&mut self, is_parallel: bool, num_committed: u32, num_total: u32) {
+        self.finish_update_counters_and_log_info(is_parallel, num_committed, num_total);
+    }
+
+    fn limit_usage(&self) -> BlockLimitUsage {
+        BlockLimitUsage {
+            effective_block_gas: self.get_effective_accumulated_block_gas(),
+            execution_gas: self.accumulated_fee_statement.execution_gas_used(),
+            io_gas: self.accumulated_fee_statement.io_gas_used(),
+            storage_fee: self.accumulated_fee_statement.storage_fee_used(),
+            approx_output_size: self.get_accumulated_approx_output_size(),
+        }
+    }
+
+    fn snapshot(&self) -> BlockLimitSnapshot {
+        BlockLimitSnapshot {
+            num_accumulated_txns: self.txn_fee_statements.len() as u32,
+            limit_usage: self.limit_usage(),
+            last_conflict_multiplier: self.last_conflict_multiplier,
+        }
     }
 }
 
@@ -316,6 +556,23 @@ mod test {
         assert!(processor.should_end_block_parallel());
     }
 
+    #[test]
+    fn test_time_budget() {
+        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(DEFAULT_COMPLEX_LIMIT, 10)
+            .with_time_budget(Duration::from_secs(3600));
+        processor.accumulate_fee_statement(execution_fee(10), None, None);
+        assert!(!processor.should_end_block_parallel());
+
+        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(DEFAULT_COMPLEX_LIMIT, 10)
+            .with_time_budget(Duration::ZERO);
+        processor.accumulate_fee_statement(execution_fee(10), None, None);
+        assert!(processor.should_end_block_sequential());
+        assert_eq!(
+            processor.block_limit_skip_reason(),
+            Some(SkipReason::BlockTimeBudget)
+        );
+    }
+
     #[test]
     fn test_output_limit_used() {
         let block_gas_limit = BlockGasLimitType::ComplexLimitV1 {
@@ -459,6 +716,58 @@ mod test {
         assert!(!processor.should_end_block_parallel());
     }
 
+    #[test]
+    fn test_decayed_conflict_limit() {
+        let block_gas_limit = BlockGasLimitType::ComplexLimitV2 {
+            effective_block_gas_limit: 1000,
+            execution_gas_effective_multiplier: 1,
+            io_gas_effective_multiplier: 1,
+            conflict_penalty_window: 8,
+            use_granular_resource_group_conflicts: false,
+            use_module_publishing_block_conflict: false,
+            block_output_limit: None,
+            include_user_txn_size_in_block_output: true,
+            add_block_limit_outcome_onchain: false,
+            execution_gas_limit: None,
+            io_gas_limit: None,
+            storage_fee_limit: None,
+            conflict_penalty_decay_percentage: Some(90),
+            txn_output_limit: None,
+        };
+
+        let mut processor = BlockGasLimitProcessor::<TestTxn>::new(block_gas_limit, 10);
+
+        // A single conflict with the previous txn is not penalized, but the penalty grows as
+        // the txns keep writing the same key, and decays once they stop.
+        for (reads, writes, expected_effective_block_gas) in [
+            (vec![1], vec![1], 10),
+            (vec![1], vec![1], 20),
+            (vec![1], vec![1], 40),
+            (vec![1], vec![1], 70),
+            (vec![2], vec![2], 80),
+            (vec![1], vec![], 110),
+        ] {
+            let to_keys = |keys: Vec<u64>| {
+                to_map(
+                    &keys
+                        .into_iter()
+                        .map(InputOutputKey::Resource)
+                        .collect::<Vec<_>>(),
+                )
+            };
+            processor.accumulate_fee_statement(
+                execution_fee(10),
+                Some(ReadWriteSummary::new(to_keys(reads), to_keys(writes))),
+                None,
+            );
+            assert_eq!(
+                processor.accumulated_effective_block_gas,
+                expected_effective_block_gas
+            );
+        }
+        assert!(!processor.should_end_block_parallel());
+    }
+
     #[test]
     fn test_module_publishing_txn_conflict() {
         let conflict_penalty_window = 4;
@@ -494,11 +803,9 @@ mod test {
         assert_eq!(1, processor.compute_conflict_multiplier(8));
         assert_eq!(processor.accumulated_effective_block_gas, 30);
 
+        // The previous txns are not penalized for the conflict.
         processor.process_module_rw_conflict();
-        assert_eq!(
-            processor.accumulated_effective_block_gas,
-            30 * conflict_penalty_window as u64
-        );
+        assert_eq!(processor.accumulated_effective_block_gas, 30);
 
         processor.accumulate_fee_statement(
             execution_fee(25),
@@ -510,7 +817,21 @@ mod test {
         );
         assert_eq!(
             processor.accumulated_effective_block_gas,
-            55 * conflict_penalty_window as u64
+            30 + 25 * conflict_penalty_window as u64
+        );
+
+        // Neither are the next txns that do not touch the conflicting modules.
+        processor.accumulate_fee_statement(
+            execution_fee(5),
+            Some(ReadWriteSummary::new(
+                to_map(&[InputOutputKey::Group(3, 3)]),
+                to_map(&[InputOutputKey::Group(3, 3)]),
+            )),
+            None,
+        );
+        assert_eq!(
+            processor.accumulated_effective_block_gas,
+            35 + 25 * conflict_penalty_window as u64
         );
     }
 }
@@ -12,12 +12,15 @@
 /// number, and hence it is crucial for the baseline to know the final incarnation number
 /// of each transaction of the tested block executor execution.
 use crate::{
-    errors::{BlockExecutionError, BlockExecutionResult},
+    errors::{
+        BlockExecutionError, BlockExecutionFailure, BlockExecutionResult, ParallelExecutionFailure,
+    },
     proptest_types::types::{
         MockOutput, MockTransaction, ValueType, RESERVED_TAG, STORAGE_AGGREGATOR_VALUE,
     },
 };
 use aptos_aggregator::delta_change_set::serialize;
+use aptos_mvhashmap::types::TxnIndex;
 use aptos_types::{
     contract_event::TransactionEvent, transaction::BlockOutput, write_set::TransactionWrite,
 };
@@ -361,26 +364,32 @@ impl<K: Debug + Hash + Clone + Eq> BaselineOutput<K> {
             Ok(block_output) => {
                 self.assert_success(block_output);
             },
-            Err(BlockExecutionError::FatalVMError(idx)) => {
-                assert_matches!(&self.status, BaselineStatus::Aborted);
-                assert_eq!(*idx, self.read_values.len());
-                assert_eq!(*idx, self.resolved_deltas.len());
-            },
-            Err(BlockExecutionError::FatalBlockExecutorError(e)) => {
-                unimplemented!("not tested here FallbackToSequential({:?})", e);
+            Err(BlockExecutionFailure { error, txn_idx }) => match error {
+                BlockExecutionError::FatalVMError(idx) => {
+                    assert_matches!(&self.status, BaselineStatus::Aborted);
+                    assert_eq!(*idx, self.read_values.len());
+                    assert_eq!(*idx, self.resolved_deltas.len());
+                    assert_eq!(*txn_idx, Some(*idx as TxnIndex));
+                },
+                BlockExecutionError::FatalBlockExecutorError(e) => {
+                    unimplemented!("not tested here FallbackToSequential({:?})", e);
+                },
+                BlockExecutionError::Cancelled => {
+                    unimplemented!("not tested here Cancelled");
+                },
             },
         }
     }
 
     pub(crate) fn assert_parallel_output<E: Debug>(
         &self,
-        results: &Result<BlockOutput<MockOutput<K, E>>, ()>,
+        results: &Result<BlockOutput<MockOutput<K, E>>, ParallelExecutionFailure>,
     ) {
         match results {
             Ok(block_output) => {
                 self.assert_success(block_output);
             },
-            Err(()) => {
+            Err(_) => {
                 // Parallel execution currently returns an arbitrary error to fallback.
                 // TODO: adjust the logic to be able to test better.
             },
@@ -0,0 +1,240 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A reusable harness generating blocks that mix the operations supported by the mock
+//! transactions (conflicting reads and writes, aggregator deltas, resource group operations and
+//! module accesses), with a tunable conflict rate, and asserting that their parallel execution
+//! matches the sequential baseline (also under a block gas limit).
+
+use crate::{
+    executor::BlockExecutor,
+    proptest_types::{
+        baseline::BaselineOutput,
+        types::{
+            DeltaGroupDataView, KeyType, MockEvent, MockIncarnation, MockOutput, MockTask,
+            MockTransaction, TransactionGen, TransactionGenParams, MAX_GAS_PER_TXN,
+        },
+    },
+    txn_commit_hook::NoOpTransactionCommitHook,
+};
+use aptos_aggregator::delta_change_set::{delta_add, delta_sub};
+use aptos_types::{
+    block_executor::config::BlockExecutorConfig, executable::ExecutableTestType,
+    write_set::TransactionWrite,
+};
+use proptest::{collection::vec, option, prelude::*};
+use std::sync::Arc;
+
+type Key = [u8; 32];
+type Txn = MockTransaction<KeyType<Key>, MockEvent>;
+
+/// The number of resource groups accessed by the blocks with resource group operations (the
+/// mock transactions use the last 3 keys of the universe as group keys).
+const NUM_GROUPS: usize = 3;
+
+/// Tunes the mix of operations of the generated blocks.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BlockGenParams {
+    pub(crate) num_txns: usize,
+    /// The number of keys accessed by the transactions (besides the resource groups): the
+    /// fewer, the more conflicts between the transactions.
+    pub(crate) num_keys: usize,
+    /// If true, the incarnations of a transaction may access different keys.
+    pub(crate) dynamic: bool,
+    /// The percentage of the keys that are updated with aggregator deltas (and not only writes).
+    pub(crate) delta_pct: u8,
+    /// If true, some of the reads and writes go to (members of) resource groups.
+    pub(crate) resource_groups: bool,
+    /// The percentage of the keys accessed as modules. A module read and written by the block
+    /// makes parallel execution fall back to sequential execution.
+    pub(crate) module_pct: u8,
+    pub(crate) block_gas_limit: Option<u64>,
+}
+
+impl Arbitrary for BlockGenParams {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        (
+            10usize..500,
+            2usize..100,
+            any::<bool>(),
+            prop_oneof![Just(0u8), 1u8..50],
+            any::<bool>(),
+            prop_oneof![3 => Just(0u8), 1 => 1u8..10],
+            option::of(0u64..(500 * MAX_GAS_PER_TXN / 2)),
+        )
+            .prop_map(
+                |(
+                    num_txns,
+                    num_keys,
+                    dynamic,
+                    delta_pct,
+                    resource_groups,
+                    module_pct,
+                    block_gas_limit,
+                )| BlockGenParams {
+                    num_txns,
+                    num_keys,
+                    dynamic,
+                    delta_pct,
+                    resource_groups,
+                    module_pct,
+                    block_gas_limit,
+                },
+            )
+            .boxed()
+    }
+}
+
+/// The randomness of a block generated for the given [`BlockGenParams`].
+#[derive(Clone, Debug)]
+pub(crate) struct BlockGen {
+    universe: Vec<Key>,
+    transaction_gens: Vec<TransactionGen<Key>>,
+}
+
+impl BlockGen {
+    pub(crate) fn strategy(params: BlockGenParams) -> impl Strategy<Value = Self> {
+        let num_groups = if params.resource_groups {
+            NUM_GROUPS
+        } else {
+            0
+        };
+        let transaction_gen_params = if params.dynamic {
+            TransactionGenParams::new_dynamic()
+        } else {
+            TransactionGenParams::default()
+        };
+        (
+            vec(any::<Key>(), params.num_keys + num_groups),
+            vec(
+                any_with::<TransactionGen<Key>>(transaction_gen_params),
+                params.num_txns,
+            )
+            .no_shrink(),
+        )
+            .prop_map(|(universe, transaction_gens)| BlockGen {
+                universe,
+                transaction_gens,
+            })
+    }
+
+    /// Returns the transactions of the block, and the storage they are executed against.
+    pub(crate) fn materialize(
+        self,
+        params: &BlockGenParams,
+    ) -> (Vec<Txn>, DeltaGroupDataView<KeyType<Key>>) {
+        let universe = &self.universe;
+        // Aggregators and modules can't be deleted.
+        let allow_deletes = params.delta_pct == 0 && params.module_pct == 0;
+        let transactions = self
+            .transaction_gens
+            .into_iter()
+            .map(|txn_gen| {
+                let txn: Txn = if params.resource_groups {
+                    txn_gen.materialize_groups(universe, [Some(30), Some(50), None])
+                } else {
+                    // The deltas are derived from the writes below (on a subset of the keys).
+                    txn_gen.materialize_with_deltas(universe, universe.len(), allow_deletes)
+                };
+                let mut behaviors = txn.into_behaviors();
+                for behavior in behaviors.iter_mut() {
+                    convert_operations(behavior, params);
+                }
+                MockTransaction::from_behaviors(behaviors)
+            })
+            .collect();
+
+        let num_groups = if params.resource_groups {
+            NUM_GROUPS
+        } else {
+            0
+        };
+        let data_view = DeltaGroupDataView {
+            group_keys: universe[(universe.len() - num_groups)..]
+                .iter()
+                .map(|key| KeyType(*key, false))
+                .collect(),
+        };
+        (transactions, data_view)
+    }
+}
+
+// The keys are random, so their bytes determine the operations on them.
+fn is_module_key(key: &Key, params: &BlockGenParams) -> bool {
+    key[0] % 100 < params.module_pct
+}
+
+fn is_delta_key(key: &Key, params: &BlockGenParams) -> bool {
+    !is_module_key(key, params) && key[1] % 100 < params.delta_pct
+}
+
+/// Turns the accesses of the module keys into module accesses, and most writes of the delta keys
+/// into deltas (derived from the written value, similar to materialize_with_deltas).
+fn convert_operations(
+    behavior: &mut MockIncarnation<KeyType<Key>, MockEvent>,
+    params: &BlockGenParams,
+) {
+    for read in behavior.reads.iter_mut() {
+        read.1 = is_module_key(&read.0, params);
+    }
+
+    let mut writes = Vec::with_capacity(behavior.writes.len());
+    for (key, value) in std::mem::take(&mut behavior.writes) {
+        if is_module_key(&key.0, params) {
+            writes.push((KeyType(key.0, true), value));
+            continue;
+        }
+        if is_delta_key(&key.0, params) {
+            let val = value.as_u128().unwrap().unwrap();
+            if val % 10 < 5 {
+                behavior.deltas.push((key, delta_sub(val % 100, u128::MAX)));
+                continue;
+            } else if val % 10 > 5 {
+                behavior.deltas.push((key, delta_add(val % 100, u128::MAX)));
+                continue;
+            }
+        }
+        writes.push((key, value));
+    }
+    behavior.writes = writes;
+}
+
+/// Executes the block num_repeat times (in parallel, falling back to sequential execution if
+/// needed), and asserts that the outputs match the sequential baseline. The outputs of blocks
+/// whose transactions behave the same in all incarnations are also compared to the outputs of
+/// the sequential execution by the block executor (with differential execution).
+pub(crate) fn assert_parallel_matches_sequential(
+    params: &BlockGenParams,
+    block_gen: BlockGen,
+    num_repeat: usize,
+) {
+    let (transactions, data_view) = block_gen.materialize(params);
+
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_cpus::get())
+            .build()
+            .unwrap(),
+    );
+    let mut config =
+        BlockExecutorConfig::new_maybe_block_limit(num_cpus::get(), params.block_gas_limit);
+    // Executing a dynamic transaction again changes its behavior.
+    config.local.differential_execution = !params.dynamic;
+
+    for _ in 0..num_repeat {
+        let block_executor = BlockExecutor::<
+            Txn,
+            MockTask<KeyType<Key>, MockEvent>,
+            DeltaGroupDataView<KeyType<Key>>,
+            NoOpTransactionCommitHook<MockOutput<KeyType<Key>, MockEvent>, usize>,
+            ExecutableTestType,
+        >::new(config.clone(), executor_thread_pool.clone(), None);
+        let output = block_executor.execute_block((), &transactions, &data_view, None, None, None);
+
+        BaselineOutput::generate(&transactions, params.block_gas_limit).assert_output(&output);
+        assert_eq!(block_executor.take_execution_divergence(), None);
+    }
+}
@@ -5,5 +5,8 @@
 pub(crate) mod baseline;
 pub mod bencher;
 #[cfg(test)]
+mod harness;
+#[cfg(test)]
 mod tests;
 pub(crate) mod types;
+pub mod workload;
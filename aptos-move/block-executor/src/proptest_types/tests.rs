@@ -3,21 +3,23 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    errors::SequentialBlockExecutionError,
+    errors::{BlockExecutionFailure, ParallelExecutionFailure, SequentialBlockExecutionError},
     executor::BlockExecutor,
     proptest_types::{
         baseline::BaselineOutput,
+        harness::{assert_parallel_matches_sequential, BlockGen, BlockGenParams},
         types::{
             DeltaDataView, EmptyDataView, KeyType, MockEvent, MockOutput, MockTask,
             MockTransaction, NonEmptyGroupDataView, TransactionGen, TransactionGenParams,
             MAX_GAS_PER_TXN,
         },
+        workload::{Workload, WorkloadParams},
     },
     txn_commit_hook::NoOpTransactionCommitHook,
 };
 use aptos_types::{
     block_executor::config::BlockExecutorConfig, contract_event::TransactionEvent,
-    executable::ExecutableTestType,
+    executable::ExecutableTestType, on_chain_config::BlockGasLimitType,
 };
 use claims::{assert_matches, assert_ok};
 use num_cpus;
@@ -45,6 +47,36 @@ fn run_transactions<K, V, E>(
     V: Clone + Eq + Send + Sync + Arbitrary + 'static,
     E: Send + Sync + Debug + Clone + TransactionEvent + 'static,
     Vec<u8>: From<V>,
+{
+    run_transactions_impl::<K, V, E>(
+        key_universe,
+        transaction_gens,
+        abort_transactions,
+        skip_rest_transactions,
+        num_repeat,
+        module_access,
+        maybe_block_gas_limit,
+        false,
+    );
+}
+
+// With validate_module_reads, transactions that read and publish the same modules are
+// executed in parallel (instead of falling back), and compared against the baseline.
+#[allow(clippy::too_many_arguments)]
+fn run_transactions_impl<K, V, E>(
+    key_universe: &[K],
+    transaction_gens: Vec<TransactionGen<V>>,
+    abort_transactions: Vec<Index>,
+    skip_rest_transactions: Vec<Index>,
+    num_repeat: usize,
+    module_access: (bool, bool),
+    maybe_block_gas_limit: Option<u64>,
+    validate_module_reads: bool,
+) where
+    K: Hash + Clone + Debug + Eq + Send + Sync + PartialOrd + Ord + 'static,
+    V: Clone + Eq + Send + Sync + Arbitrary + 'static,
+    E: Send + Sync + Debug + Clone + TransactionEvent + 'static,
+    Vec<u8>: From<V>,
 {
     let mut transactions: Vec<_> = transaction_gens
         .into_iter()
@@ -70,6 +102,10 @@ fn run_transactions<K, V, E>(
             .unwrap(),
     );
 
+    let mut config =
+        BlockExecutorConfig::new_maybe_block_limit(num_cpus::get(), maybe_block_gas_limit);
+    config.local.validate_module_reads = validate_module_reads;
+
     for _ in 0..num_repeat {
         let output = BlockExecutor::<
             MockTransaction<KeyType<K>, E>,
@@ -77,15 +113,14 @@ fn run_transactions<K, V, E>(
             EmptyDataView<KeyType<K>>,
             NoOpTransactionCommitHook<MockOutput<KeyType<K>, E>, usize>,
             ExecutableTestType,
-        >::new(
-            BlockExecutorConfig::new_maybe_block_limit(num_cpus::get(), maybe_block_gas_limit),
-            executor_thread_pool.clone(),
-            None,
-        )
-        .execute_transactions_parallel((), &transactions, &data_view);
-
-        if module_access.0 && module_access.1 {
-            assert_matches!(output, Err(()));
+        >::new(config.clone(), executor_thread_pool.clone(), None)
+        .execute_transactions_parallel((), &transactions, &data_view, None, None);
+
+        if module_access.0 && module_access.1 && !validate_module_reads {
+            assert_matches!(
+                output,
+                Err(ParallelExecutionFailure::ModulePathReadWriteError { .. })
+            );
             continue;
         }
 
@@ -217,7 +252,7 @@ fn deltas_writes_mixed_with_block_gas_limit(num_txns: usize, maybe_block_gas_lim
             executor_thread_pool.clone(),
             None,
         )
-        .execute_transactions_parallel((), &transactions, &data_view);
+        .execute_transactions_parallel((), &transactions, &data_view, None, None);
 
         BaselineOutput::generate(&transactions, maybe_block_gas_limit)
             .assert_parallel_output(&output);
@@ -268,7 +303,7 @@ fn deltas_resolver_with_block_gas_limit(num_txns: usize, maybe_block_gas_limit:
             executor_thread_pool.clone(),
             None,
         )
-        .execute_transactions_parallel((), &transactions, &data_view);
+        .execute_transactions_parallel((), &transactions, &data_view, None, None);
 
         BaselineOutput::generate(&transactions, maybe_block_gas_limit)
             .assert_parallel_output(&output);
@@ -352,6 +387,36 @@ fn module_publishing_fallback_with_block_gas_limit(
     );
 }
 
+fn module_publishing_validation_with_block_gas_limit(
+    num_txns: usize,
+    maybe_block_gas_limit: Option<u64>,
+) {
+    let mut runner = TestRunner::default();
+
+    let universe = vec(any::<[u8; 32]>(), 100)
+        .new_tree(&mut runner)
+        .expect("creating a new value should succeed")
+        .current();
+    let transaction_gen = vec(
+        any_with::<TransactionGen<[u8; 32]>>(TransactionGenParams::new_dynamic()),
+        num_txns,
+    )
+    .new_tree(&mut runner)
+    .expect("creating a new value should succeed")
+    .current();
+
+    run_transactions_impl::<[u8; 32], [u8; 32], MockEvent>(
+        &universe,
+        transaction_gen,
+        vec![],
+        vec![],
+        2,
+        (true, true),
+        maybe_block_gas_limit,
+        true,
+    );
+}
+
 fn publishing_fixed_params_with_block_gas_limit(
     num_txns: usize,
     maybe_block_gas_limit: Option<u64>,
@@ -424,7 +489,7 @@ fn publishing_fixed_params_with_block_gas_limit(
         executor_thread_pool,
         None,
     )
-    .execute_transactions_parallel((), &transactions, &data_view);
+    .execute_transactions_parallel((), &transactions, &data_view, None, None);
     assert_ok!(output);
 
     // Adjust the reads of txn indices[2] to contain module read to key 42.
@@ -469,9 +534,12 @@ fn publishing_fixed_params_with_block_gas_limit(
             executor_thread_pool.clone(),
             None,
         ) // Ensure enough gas limit to commit the module txns (4 is maximum gas per txn)
-        .execute_transactions_parallel((), &transactions, &data_view);
+        .execute_transactions_parallel((), &transactions, &data_view, None, None);
 
-        assert_matches!(output, Err(()));
+        assert_matches!(
+            output,
+            Err(ParallelExecutionFailure::ModulePathReadWriteError { .. })
+        );
     }
 }
 
@@ -548,7 +616,7 @@ fn non_empty_group(
             executor_thread_pool.clone(),
             None,
         )
-        .execute_transactions_parallel((), &transactions, &data_view);
+        .execute_transactions_parallel((), &transactions, &data_view, None, None);
 
         BaselineOutput::generate(&transactions, None).assert_parallel_output(&output);
     }
@@ -565,14 +633,23 @@ fn non_empty_group(
             executor_thread_pool.clone(),
             None,
         )
-        .execute_transactions_sequential((), &transactions, &data_view, false);
+        .execute_transactions_sequential(
+            (),
+            &transactions,
+            &data_view,
+            &BlockGasLimitType::NoLimit,
+            false,
+            None,
+        );
         // TODO: test dynamic disabled as well.
 
         BaselineOutput::generate(&transactions, None).assert_output(&output.map_err(|e| match e {
-            SequentialBlockExecutionError::ResourceGroupSerializationError => {
+            SequentialBlockExecutionError::ResourceGroupSerializationError(_) => {
                 panic!("Unexpected error")
             },
-            SequentialBlockExecutionError::ErrorToReturn(err) => err,
+            SequentialBlockExecutionError::ErrorToReturn { error, txn_idx } => {
+                BlockExecutionFailure { error, txn_idx }
+            },
         }));
     }
 }
@@ -602,6 +679,11 @@ fn module_publishing_fallback() {
     module_publishing_fallback_with_block_gas_limit(3000, None);
 }
 
+#[test]
+fn module_publishing_validation() {
+    module_publishing_validation_with_block_gas_limit(3000, None);
+}
+
 #[test]
 // Test a single transaction intersection interleaves with a lot of dependencies and
 // not overlapping module r/w keys.
@@ -711,6 +793,14 @@ fn module_publishing_fallback_with_block_gas_limit_test() {
     );
 }
 
+#[test]
+fn module_publishing_validation_with_block_gas_limit_test() {
+    module_publishing_validation_with_block_gas_limit(
+        3000,
+        Some(rand::thread_rng().gen_range(0, 3000 * MAX_GAS_PER_TXN / 2)),
+    );
+}
+
 #[test]
 // Test a single transaction intersection interleaves with a lot of dependencies and
 // not overlapping module r/w keys.
@@ -722,3 +812,56 @@ fn module_publishing_races_with_block_gas_limit_test() {
         );
     }
 }
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+    #[test]
+    fn mixed_operations_against_baseline(
+        (params, block_gen) in any::<BlockGenParams>()
+            .prop_flat_map(|params| (Just(params), BlockGen::strategy(params))),
+    ) {
+        assert_parallel_matches_sequential(&params, block_gen, 2);
+    }
+}
+
+// Resource groups and deltas in a contended block, with and without a block gas limit.
+#[test_case(false, None)]
+#[test_case(false, Some(200))]
+#[test_case(true, None)]
+#[test_case(true, Some(200))]
+fn groups_and_deltas_contended(dynamic: bool, block_gas_limit: Option<u64>) {
+    let params = BlockGenParams {
+        num_txns: 1000,
+        num_keys: 10,
+        dynamic,
+        delta_pct: 40,
+        resource_groups: true,
+        module_pct: 0,
+        block_gas_limit,
+    };
+    let block_gen = BlockGen::strategy(params)
+        .new_tree(&mut TestRunner::default())
+        .expect("creating a new value should succeed")
+        .current();
+    assert_parallel_matches_sequential(&params, block_gen, 5);
+}
+
+#[test]
+fn synthetic_workload_report() {
+    let workload = Workload::new(WorkloadParams {
+        num_txns: 200,
+        num_hot_keys: 10,
+        aggregator_pct: 30,
+        num_groups: 2,
+        ..WorkloadParams::default()
+    });
+    let report = workload.run(3);
+
+    assert_eq!(report.block_times.len(), 3);
+    assert_eq!(report.block_stats.len(), 3);
+    for stats in &report.block_stats {
+        assert!(stats.num_incarnations >= 200);
+    }
+    assert!(report.throughput() > 0.0);
+    assert!(report.abort_rate() >= 0.0);
+}
@@ -5,6 +5,7 @@
 use crate::{
     explicit_sync_wrapper::ExplicitSyncWrapper,
     task::{ExecutionStatus, ExecutorTask, TransactionOutput},
+    types::InputOutputKey,
 };
 use aptos_aggregator::{
     delayed_change::DelayedChange,
@@ -17,7 +18,7 @@ use aptos_types::{
     account_address::AccountAddress,
     contract_event::TransactionEvent,
     delayed_fields::PanicError,
-    executable::ModulePath,
+    executable::{KeyPrefix, ModulePath},
     fee_statement::FeeStatement,
     on_chain_config::CurrentTimeMicroseconds,
     state_store::{
@@ -114,6 +115,41 @@ where
     }
 }
 
+/// Contains a non-empty group (w. value at RESERVED_TAG) for the group keys, like
+/// NonEmptyGroupDataView, and an aggregator with STORAGE_AGGREGATOR_VALUE for the other keys,
+/// like DeltaDataView, so that blocks can mix resource group operations and deltas.
+pub(crate) struct DeltaGroupDataView<K> {
+    pub(crate) group_keys: HashSet<K>,
+}
+
+impl<K> TStateView for DeltaGroupDataView<K>
+where
+    K: PartialOrd + Ord + Send + Sync + Clone + Hash + Eq + ModulePath + 'static,
+{
+    type Key = K;
+
+    fn get_state_value(&self, key: &K) -> Result<Option<StateValue>, StateviewError> {
+        if self.group_keys.contains(key) {
+            let group: BTreeMap<u32, Bytes> = BTreeMap::from([(RESERVED_TAG, vec![0].into())]);
+
+            let bytes = bcs::to_bytes(&group).unwrap();
+            Ok(Some(StateValue::new_legacy(bytes.into())))
+        } else {
+            Ok(Some(StateValue::new_legacy(
+                serialize(&STORAGE_AGGREGATOR_VALUE).into(),
+            )))
+        }
+    }
+
+    fn id(&self) -> StateViewId {
+        StateViewId::Miscellaneous
+    }
+
+    fn get_usage(&self) -> Result<StateStorageUsage, StateviewError> {
+        unreachable!("Not used in tests");
+    }
+}
+
 pub(crate) struct EmptyDataView<K> {
     pub(crate) phantom: PhantomData<K>,
 }
@@ -172,6 +208,15 @@ impl<K: Hash + Clone + Debug + Eq + PartialOrd + Ord> ModulePath for KeyType<K>
     }
 }
 
+// The mock transactions do not read ranges, so the keys are not indexed.
+impl<K: Hash + Clone + Debug + Eq + PartialOrd + Ord> KeyPrefix for KeyType<K> {
+    type Prefix = ();
+
+    fn key_prefix(&self) -> Option<()> {
+        None
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct ValueType {
     /// Wrapping the types used for testing to add TransactionWrite trait implementation (below).
@@ -832,6 +877,11 @@ where
         Self::new()
     }
 
+    fn argument_fingerprint(_argument: &Self::Argument) -> Option<u64> {
+        // Mock tasks are stateless.
+        Some(0)
+    }
+
     fn execute_transaction(
         &self,
         view: &(impl TExecutorView<K, u32, MoveTypeLayout, DelayedFieldID, ValueType>
@@ -980,7 +1030,7 @@ pub(crate) fn raw_metadata(v: u64) -> StateValueMetadata {
     StateValueMetadata::legacy(v, &CurrentTimeMicroseconds { microseconds: v })
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) struct MockOutput<K, E> {
     pub(crate) writes: Vec<(K, ValueType)>,
     // Key, metadata_op, inner_ops
@@ -1156,8 +1206,25 @@ where
     }
 
     fn output_approx_size(&self) -> u64 {
-        // TODO add block output limit testing
-        0
+        // The keys have no size, so only the sizes of the written values and of the event data
+        // are counted.
+        let write_size: usize = self
+            .writes
+            .iter()
+            .map(|(_, value)| value)
+            .chain(
+                self.group_writes
+                    .iter()
+                    .flat_map(|(_, _, inner_ops)| inner_ops.values()),
+            )
+            .map(|value| value.bytes().map_or(0, |bytes| bytes.len()))
+            .sum();
+        let event_size: usize = self
+            .events
+            .iter()
+            .map(|event| event.get_event_data().len())
+            .sum();
+        (write_size + event_size) as u64
     }
 
     fn get_write_summary(
@@ -1169,7 +1236,76 @@ where
             <Self::Txn as Transaction>::Identifier,
         >,
     > {
-        HashSet::new()
+        self.writes
+            .iter()
+            .map(|(k, _)| InputOutputKey::Resource(k.clone()))
+            .chain(self.group_writes.iter().flat_map(|(k, _, inner_ops)| {
+                inner_ops
+                    .keys()
+                    .map(|tag| InputOutputKey::Group(k.clone(), *tag))
+            }))
+            .chain(
+                self.deltas
+                    .iter()
+                    .map(|(k, _)| InputOutputKey::Resource(k.clone())),
+            )
+            .collect()
+    }
+
+    fn committed_write_keys(&self) -> Option<Vec<<Self::Txn as Transaction>::Key>> {
+        if self.skipped {
+            return Some(vec![]);
+        }
+        Some(
+            self.writes
+                .iter()
+                .map(|(k, _)| k.clone())
+                .chain(self.group_writes.iter().map(|(k, _, _)| k.clone()))
+                .chain(self.deltas.iter().map(|(k, _)| k.clone()))
+                .collect(),
+        )
+    }
+
+    // The mock outputs also contain the values read by the transaction, which are compared
+    // as well, as they determine the outputs of a real transaction.
+    fn committed_output_divergence(&self, other: &Self) -> Option<String> {
+        let writes = |output: &Self| {
+            output
+                .writes
+                .iter()
+                .map(|(k, v)| (k.clone(), v.bytes().cloned()))
+                .collect::<Vec<_>>()
+        };
+        let events = |output: &Self| {
+            output
+                .events
+                .iter()
+                .map(|event| event.get_event_data().to_vec())
+                .collect::<Vec<_>>()
+        };
+
+        if self.skipped != other.skipped {
+            Some(format!("skipped: {} != {}", self.skipped, other.skipped))
+        } else if self.total_gas != other.total_gas {
+            Some(format!("gas: {} != {}", self.total_gas, other.total_gas))
+        } else if self.read_results != other.read_results {
+            Some(format!(
+                "reads: {:?} != {:?}",
+                self.read_results, other.read_results
+            ))
+        } else if writes(self) != writes(other) {
+            Some(format!("writes: {:?} != {:?}", writes(self), writes(other)))
+        } else if self.materialized_delta_writes.get() != other.materialized_delta_writes.get() {
+            Some(format!(
+                "delta writes: {:?} != {:?}",
+                self.materialized_delta_writes.get(),
+                other.materialized_delta_writes.get()
+            ))
+        } else if events(self) != events(other) {
+            Some(format!("events: {:?} != {:?}", events(self), events(other)))
+        } else {
+            None
+        }
     }
 }
 
@@ -1178,6 +1314,12 @@ pub(crate) struct MockEvent {
     event_data: Vec<u8>,
 }
 
+impl MockEvent {
+    pub(crate) fn new(event_data: Vec<u8>) -> Self {
+        Self { event_data }
+    }
+}
+
 impl TransactionEvent for MockEvent {
     fn get_event_data(&self) -> &[u8] {
         &self.event_data
@@ -0,0 +1,271 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Synthetic workloads to benchmark the block executor without a node or a VM: blocks of mock
+//! transactions with a parameterized number of hot keys, share of aggregator operations and
+//! resource group sizes, whose execution reports the throughput, the abort rate and the time
+//! spent in each phase (see [`WorkloadReport`]).
+
+use crate::{
+    execution_stats::BlockExecutionStats,
+    executor::BlockExecutor,
+    proptest_types::types::{
+        DeltaGroupDataView, KeyType, MockEvent, MockIncarnation, MockOutput, MockTask,
+        MockTransaction, ValueType, MAX_GAS_PER_TXN,
+    },
+    txn_commit_hook::NoOpTransactionCommitHook,
+};
+use aptos_aggregator::delta_change_set::{delta_add, delta_sub, DeltaOp};
+use aptos_types::{block_executor::config::BlockExecutorConfig, executable::ExecutableTestType};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::ThreadPool;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+type Txn = MockTransaction<KeyType<u64>, MockEvent>;
+
+fn hot_key(rng: &mut StdRng, params: &WorkloadParams) -> KeyType<u64> {
+    KeyType(rng.gen_range(0, params.num_hot_keys as u64), false)
+}
+
+/// The parameters of the blocks of a synthetic workload.
+#[derive(Clone, Copy, Debug)]
+pub struct WorkloadParams {
+    pub num_txns: usize,
+    /// The number of keys that the transactions read and write: the fewer, the more conflicts.
+    pub num_hot_keys: usize,
+    pub reads_per_txn: usize,
+    pub writes_per_txn: usize,
+    /// The percentage of the writes that are aggregator (v1) deltas.
+    pub aggregator_pct: u8,
+    /// The number of resource groups (in addition to the hot keys) that every transaction reads
+    /// a member of and writes to. No group is accessed if 0.
+    pub num_groups: usize,
+    /// The number of members of each resource group that the transactions access.
+    pub group_size: usize,
+    pub concurrency_level: usize,
+    pub block_gas_limit: Option<u64>,
+    /// The seed of the generation of the transactions, for reproducible workloads.
+    pub seed: u64,
+}
+
+impl Default for WorkloadParams {
+    fn default() -> Self {
+        Self {
+            num_txns: 10_000,
+            num_hot_keys: 1_000,
+            reads_per_txn: 4,
+            writes_per_txn: 2,
+            aggregator_pct: 0,
+            num_groups: 0,
+            group_size: 4,
+            concurrency_level: num_cpus::get(),
+            block_gas_limit: None,
+            seed: 0,
+        }
+    }
+}
+
+/// The measurements of the blocks executed by a [`Workload`].
+#[derive(Clone, Debug, Default)]
+pub struct WorkloadReport {
+    pub num_txns_per_block: usize,
+    /// The wall-clock time of the execution of each block.
+    pub block_times: Vec<Duration>,
+    /// The statistics of the execution of each block, including the time spent by the workers
+    /// in each phase (execution, validation and materialization).
+    pub block_stats: Vec<BlockExecutionStats>,
+}
+
+impl WorkloadReport {
+    pub fn total_time(&self) -> Duration {
+        self.block_times.iter().sum()
+    }
+
+    /// The number of transactions executed per second, over all blocks.
+    pub fn throughput(&self) -> f64 {
+        (self.num_txns_per_block * self.block_times.len()) as f64 / self.total_time().as_secs_f64()
+    }
+
+    /// The number of aborted incarnations per transaction, over all blocks.
+    pub fn abort_rate(&self) -> f64 {
+        let num_aborts: u64 = self.block_stats.iter().map(|stats| stats.num_aborts).sum();
+        num_aborts as f64 / (self.num_txns_per_block * self.block_stats.len()) as f64
+    }
+
+    /// The time spent by the workers in each phase (execution, validation, materialization),
+    /// over all blocks.
+    pub fn phase_times(&self) -> (Duration, Duration, Duration) {
+        self.block_stats.iter().fold(
+            (Duration::ZERO, Duration::ZERO, Duration::ZERO),
+            |(execute_time, validate_time, materialize_time), stats| {
+                (
+                    execute_time + stats.execute_time,
+                    validate_time + stats.validate_time,
+                    materialize_time + stats.materialize_time,
+                )
+            },
+        )
+    }
+}
+
+impl fmt::Display for WorkloadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (execute_time, validate_time, materialize_time) = self.phase_times();
+        write!(
+            f,
+            "{} blocks of {} txns in {:?}: {:.0} txns/s, {:.3} aborts/txn, \
+             execute {:?}, validate {:?}, materialize {:?}",
+            self.block_times.len(),
+            self.num_txns_per_block,
+            self.total_time(),
+            self.throughput(),
+            self.abort_rate(),
+            execute_time,
+            validate_time,
+            materialize_time
+        )
+    }
+}
+
+/// A block of synthetic transactions, generated once and executed any number of times.
+pub struct Workload {
+    params: WorkloadParams,
+    transactions: Vec<Txn>,
+    data_view: DeltaGroupDataView<KeyType<u64>>,
+    executor_thread_pool: Arc<ThreadPool>,
+}
+
+impl Workload {
+    pub fn new(params: WorkloadParams) -> Self {
+        assert!(params.num_hot_keys > 0, "The transactions must access keys");
+        assert!(
+            params.num_groups == 0 || params.group_size > 0,
+            "The transactions must access group members"
+        );
+        let mut rng = StdRng::seed_from_u64(params.seed);
+        // The group keys follow the hot keys.
+        let group_keys: Vec<_> = (0..params.num_groups)
+            .map(|idx| KeyType((params.num_hot_keys + idx) as u64, false))
+            .collect();
+
+        let transactions = (0..params.num_txns)
+            .map(|_| {
+                let reads = (0..params.reads_per_txn)
+                    .map(|_| hot_key(&mut rng, &params))
+                    .collect();
+                let mut writes: Vec<(KeyType<u64>, ValueType)> = Vec::new();
+                let mut deltas: Vec<(KeyType<u64>, DeltaOp)> = Vec::new();
+                for _ in 0..params.writes_per_txn {
+                    let key = hot_key(&mut rng, &params);
+                    if writes.iter().any(|(k, _)| *k == key)
+                        || deltas.iter().any(|(k, _)| *k == key)
+                    {
+                        continue;
+                    }
+                    let value: u128 = rng.gen_range(0, 100);
+                    if rng.gen_range(0, 100) < params.aggregator_pct {
+                        // Like the deltas of the proptests, these may fail (if the value
+                        // underflows), which is part of the workload.
+                        if rng.gen::<bool>() {
+                            deltas.push((key, delta_add(value, u128::MAX)));
+                        } else {
+                            deltas.push((key, delta_sub(value, u128::MAX)));
+                        }
+                    } else {
+                        // Written values are valid aggregator values, for the later deltas.
+                        writes.push((
+                            key,
+                            ValueType::from_value(bcs::to_bytes(&(value + 100_000)).unwrap(), true),
+                        ));
+                    }
+                }
+
+                let mut behavior = MockIncarnation::new(
+                    reads,
+                    writes,
+                    deltas,
+                    vec![],
+                    rng.gen_range(1, MAX_GAS_PER_TXN + 1),
+                );
+                if let Some(group_key) = (!group_keys.is_empty())
+                    .then(|| group_keys[rng.gen_range(0, group_keys.len())].clone())
+                {
+                    // Tag 0 is reserved (present in all groups in storage).
+                    let tag = |rng: &mut StdRng| rng.gen_range(1, params.group_size as u32 + 1);
+                    behavior.group_reads = vec![(group_key.clone(), tag(&mut rng))];
+                    behavior.group_writes = vec![(
+                        group_key,
+                        HashMap::from([(
+                            tag(&mut rng),
+                            ValueType::from_value(vec![rng.gen::<u8>(); 16], true),
+                        )]),
+                    )];
+                }
+                MockTransaction::from_behavior(behavior)
+            })
+            .collect();
+
+        let executor_thread_pool = Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(num_cpus::get())
+                .build()
+                .unwrap(),
+        );
+
+        Self {
+            params,
+            transactions,
+            data_view: DeltaGroupDataView {
+                group_keys: group_keys.into_iter().collect(),
+            },
+            executor_thread_pool,
+        }
+    }
+
+    /// Executes the block num_blocks times (with a new block executor every time).
+    pub fn run(&self, num_blocks: usize) -> WorkloadReport {
+        let mut report = WorkloadReport {
+            num_txns_per_block: self.params.num_txns,
+            ..WorkloadReport::default()
+        };
+        for _ in 0..num_blocks {
+            let block_executor = BlockExecutor::<
+                Txn,
+                MockTask<KeyType<u64>, MockEvent>,
+                DeltaGroupDataView<KeyType<u64>>,
+                NoOpTransactionCommitHook<MockOutput<KeyType<u64>, MockEvent>, usize>,
+                ExecutableTestType,
+            >::new(
+                BlockExecutorConfig::new_maybe_block_limit(
+                    self.params.concurrency_level,
+                    self.params.block_gas_limit,
+                ),
+                self.executor_thread_pool.clone(),
+                None,
+            );
+
+            let start_time = Instant::now();
+            let output = block_executor.execute_block(
+                (),
+                &self.transactions,
+                &self.data_view,
+                None,
+                None,
+                None,
+            );
+            report.block_times.push(start_time.elapsed());
+            assert!(output.is_ok(), "Synthetic workload failed to execute");
+            report.block_stats.push(
+                block_executor
+                    .take_execution_stats()
+                    .expect("Statistics are recorded for every block"),
+            );
+        }
+        report
+    }
+}
@@ -0,0 +1,252 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Optional recording and replay of the schedule of a parallel block execution, to reproduce
+//! Block-STM bugs that only manifest under a specific interleaving of the workers.
+//!
+//! While recording, the scheduler records the start of every execution and validation task,
+//! and every abort and commit, in the order in which the workers reach them. While replaying,
+//! a worker that reaches one of these points waits until it is due in the recorded order, so
+//! that the tasks start, and the transactions abort and commit, in the same global order as in
+//! the recorded execution. Which worker performs a task is not preserved, as it does not affect
+//! the outcome.
+//!
+//! The replay does not order the accesses to the multi-version data-structure within the tasks,
+//! e.g. a read racing with the write of a concurrently executing transaction, nor the wake-ups
+//! of the transactions suspended on a dependency. If the execution departs from the recorded
+//! schedule, i.e. the next recorded point is not reached within [`REPLAY_PROGRESS_TIMEOUT`],
+//! the replay is halted and reported as diverged.
+
+use anyhow::Result;
+use aptos_infallible::Mutex;
+use aptos_mvhashmap::types::{Incarnation, TxnIndex};
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::Condvar,
+    time::{Duration, Instant},
+};
+
+/// The time after which a replay that does not reach the next recorded point is diverged.
+pub const REPLAY_PROGRESS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a worker waits for the start of its task to be due, before it returns to the worker
+/// loop (e.g. to coordinate the commits that are due before its task).
+pub(crate) const REPLAY_TURN_WAIT: Duration = Duration::from_millis(1);
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ScheduleEvent {
+    /// A worker started executing the incarnation of the transaction.
+    Execute(TxnIndex, Incarnation),
+    /// A worker started validating the incarnation of the transaction.
+    Validate(TxnIndex, Incarnation),
+    /// The incarnation of the transaction was aborted after a failed validation.
+    Abort(TxnIndex, Incarnation),
+    /// The incarnation of the transaction was soft-failed after only the early validation of
+    /// its delayed field reads failed.
+    SoftFail(TxnIndex, Incarnation),
+    /// The transaction was committed.
+    Commit(TxnIndex),
+}
+
+/// The recorded schedule of a parallel block execution, see the module documentation.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScheduleTrace {
+    concurrency_level: usize,
+    num_txns: TxnIndex,
+    events: Vec<ScheduleEvent>,
+}
+
+impl ScheduleTrace {
+    pub fn new(concurrency_level: usize, num_txns: TxnIndex, events: Vec<ScheduleEvent>) -> Self {
+        Self {
+            concurrency_level,
+            num_txns,
+            events,
+        }
+    }
+
+    /// The concurrency level of the recorded execution, which the replay must use.
+    pub fn concurrency_level(&self) -> usize {
+        self.concurrency_level
+    }
+
+    pub fn num_txns(&self) -> TxnIndex {
+        self.num_txns
+    }
+
+    pub fn events(&self) -> &[ScheduleEvent] {
+        &self.events
+    }
+
+    /// Serializes the trace (in BCS), e.g. to be attached to a bug report.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bcs::to_bytes(self).expect("Schedule trace must serialize")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(bcs::from_bytes(bytes)?)
+    }
+}
+
+/// Whether the point reached by a worker is due in the replayed schedule.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum ReplayTurn {
+    /// The point is due (and is now marked as replayed), the worker proceeds.
+    Due,
+    /// The point was already replayed, e.g. a concurrent abort of the same incarnation by
+    /// another worker. The worker skips it.
+    Replayed,
+    /// The point is not due yet, the worker should retry.
+    Pending,
+    /// The replay diverged from the recorded schedule and must be halted.
+    Diverged,
+}
+
+struct ReplayState {
+    // Index of the next recorded event to be replayed.
+    cursor: usize,
+    last_progress: Instant,
+    divergence: Option<String>,
+}
+
+/// Records or replays the schedule of a single parallel block execution, on behalf of the
+/// scheduler.
+pub(crate) enum ScheduleTracer {
+    Record {
+        concurrency_level: usize,
+        num_txns: TxnIndex,
+        events: Mutex<Vec<ScheduleEvent>>,
+    },
+    Replay {
+        trace: ScheduleTrace,
+        state: Mutex<ReplayState>,
+        progress: Condvar,
+    },
+}
+
+impl ScheduleTracer {
+    pub(crate) fn record(concurrency_level: usize, num_txns: TxnIndex) -> Self {
+        Self::Record {
+            concurrency_level,
+            num_txns,
+            events: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn replay(trace: ScheduleTrace) -> Self {
+        Self::Replay {
+            trace,
+            state: Mutex::new(ReplayState {
+                cursor: 0,
+                last_progress: Instant::now(),
+                divergence: None,
+            }),
+            progress: Condvar::new(),
+        }
+    }
+
+    /// Records the event, if recording.
+    pub(crate) fn record_event(&self, event: ScheduleEvent) {
+        if let Self::Record { events, .. } = self {
+            events.lock().push(event);
+        }
+    }
+
+    /// If replaying, waits up to the given duration for the event to be due. When recording,
+    /// the event is always due (but not recorded, see [`ScheduleTracer::record_event`]).
+    pub(crate) fn wait_for_turn(&self, event: ScheduleEvent, wait: Duration) -> ReplayTurn {
+        let (trace, state, progress) = match self {
+            Self::Record { .. } => return ReplayTurn::Due,
+            Self::Replay {
+                trace,
+                state,
+                progress,
+            } => (trace, state, progress),
+        };
+
+        let deadline = Instant::now() + wait;
+        let mut state = state.lock();
+        loop {
+            if state.divergence.is_some() {
+                return ReplayTurn::Diverged;
+            }
+            if trace.events.get(state.cursor) == Some(&event) {
+                state.cursor += 1;
+                state.last_progress = Instant::now();
+                progress.notify_all();
+                return ReplayTurn::Due;
+            }
+            // An incarnation is aborted (or soft-failed) at most once, while the same
+            // incarnation may e.g. be validated multiple times.
+            if matches!(
+                event,
+                ScheduleEvent::Abort(..) | ScheduleEvent::SoftFail(..)
+            ) && trace.events[..state.cursor].contains(&event)
+            {
+                return ReplayTurn::Replayed;
+            }
+
+            let now = Instant::now();
+            if now.duration_since(state.last_progress) >= REPLAY_PROGRESS_TIMEOUT {
+                state.divergence = Some(format!(
+                    "Replay of the schedule made no progress at event {} of {} ({:?}), \
+                     while {:?} was reached",
+                    state.cursor,
+                    trace.events.len(),
+                    trace.events.get(state.cursor),
+                    event
+                ));
+                progress.notify_all();
+                return ReplayTurn::Diverged;
+            }
+            if now >= deadline {
+                return ReplayTurn::Pending;
+            }
+            state = progress
+                .wait_timeout(state, deadline - now)
+                .expect("Cannot currently handle a poisoned lock")
+                .0;
+        }
+    }
+
+    /// Whether the next event to be replayed is a commit.
+    pub(crate) fn is_commit_due(&self) -> bool {
+        match self {
+            Self::Record { .. } => false,
+            Self::Replay { trace, state, .. } => matches!(
+                trace.events.get(state.lock().cursor),
+                Some(ScheduleEvent::Commit(_))
+            ),
+        }
+    }
+
+    /// Returns the recorded trace, or for a replay, the given trace if it was replayed in full,
+    /// and the reason of the divergence otherwise.
+    pub(crate) fn finish(self) -> Result<ScheduleTrace, String> {
+        match self {
+            Self::Record {
+                concurrency_level,
+                num_txns,
+                events,
+            } => Ok(ScheduleTrace::new(
+                concurrency_level,
+                num_txns,
+                events.into_inner(),
+            )),
+            Self::Replay { trace, state, .. } => {
+                let state = state.into_inner();
+                if let Some(divergence) = state.divergence {
+                    return Err(divergence);
+                }
+                if state.cursor < trace.events.len() {
+                    return Err(format!(
+                        "Replay of the schedule finished after {} of {} events",
+                        state.cursor,
+                        trace.events.len()
+                    ));
+                }
+                Ok(trace)
+            },
+        }
+    }
+}
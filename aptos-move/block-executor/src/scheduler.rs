@@ -2,20 +2,27 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::explicit_sync_wrapper::ExplicitSyncWrapper;
+use crate::{
+    counters,
+    execution_profile::{BlockExecutionProfile, ExecutionProfiler},
+    explicit_sync_wrapper::ExplicitSyncWrapper,
+    schedule_trace::{ReplayTurn, ScheduleEvent, ScheduleTrace, ScheduleTracer, REPLAY_TURN_WAIT},
+    types::BlockPartitioning,
+};
 use aptos_aggregator::types::code_invariant_error;
 use aptos_infallible::Mutex;
 use aptos_mvhashmap::types::{Incarnation, TxnIndex};
-use aptos_types::delayed_fields::PanicError;
-use concurrent_queue::{ConcurrentQueue, PopError};
+use aptos_types::{block_executor::config::DependencyWaitConfig, delayed_fields::PanicError};
 use crossbeam::utils::CachePadded;
 use parking_lot::{RwLock, RwLockUpgradableReadGuard};
 use std::{
     cmp::{max, min},
+    ops::Range,
     sync::{
-        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
         Arc, Condvar,
     },
+    time::Duration,
 };
 
 const TXN_IDX_MASK: u64 = (1 << 32) - 1;
@@ -51,6 +58,58 @@ impl ArmedLock {
     }
 }
 
+/// The queue of the committed transactions, to be materialized. The transactions are committed,
+/// and hence pushed, in order and one at a time (by the worker coordinating the commits), so the
+/// queue is a range of transaction indices: pushing publishes the next index, and the workers pop
+/// (batches of) transactions by advancing the start of the range, without locking.
+struct CommitQueue {
+    num_pushed: CachePadded<AtomicU32>,
+    num_popped: CachePadded<AtomicU32>,
+}
+
+impl CommitQueue {
+    fn new() -> Self {
+        Self {
+            num_pushed: CachePadded::new(AtomicU32::new(0)),
+            num_popped: CachePadded::new(AtomicU32::new(0)),
+        }
+    }
+
+    fn push(&self, txn_idx: TxnIndex) {
+        assert_eq!(
+            self.num_pushed.load(Ordering::Relaxed),
+            txn_idx,
+            "Transactions must be pushed to the commit queue in order"
+        );
+        self.num_pushed.store(txn_idx + 1, Ordering::Release);
+    }
+
+    fn pop_batch(&self, max_batch_size: usize) -> Range<TxnIndex> {
+        let mut num_popped = self.num_popped.load(Ordering::Acquire);
+        loop {
+            let num_pushed = self.num_pushed.load(Ordering::Acquire);
+            if num_popped == num_pushed {
+                return num_popped..num_popped;
+            }
+            counters::COMMIT_QUEUE_DEPTH.observe((num_pushed - num_popped) as f64);
+
+            let batch_end = min(num_pushed, num_popped.saturating_add(max_batch_size as u32));
+            match self.num_popped.compare_exchange(
+                num_popped,
+                batch_end,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return num_popped..batch_end,
+                Err(current) => {
+                    counters::COMMIT_QUEUE_POP_CONTENTION_COUNT.inc();
+                    num_popped = current;
+                },
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum DependencyStatus {
     // The dependency is not resolved yet.
@@ -68,6 +127,9 @@ pub enum DependencyResult {
     Dependency(DependencyCondvar),
     Resolved,
     ExecutionHalted,
+    /// The transaction was suspended on the dependency, and its incarnation is executed again
+    /// from the start once the dependency is resolved (see [`DependencyWaitConfig::requeue`]).
+    Requeued,
 }
 
 /// Two types of execution tasks: Execution and Wakeup.
@@ -87,6 +149,9 @@ pub enum ExecutionTaskType {
 pub enum SchedulerTask {
     ExecutionTask(TxnIndex, Incarnation, ExecutionTaskType),
     ValidationTask(TxnIndex, Incarnation, Wave),
+    /// The final re-execution of the next transaction to commit, which failed the commit-time
+    /// validation. The worker performing it takes over the coordination of the commits.
+    CommitReExecutionTask(TxnIndex, Incarnation),
     NoTask,
     Done,
 }
@@ -114,7 +179,19 @@ pub enum SchedulerTask {
 /// that there is exactly one abort, changing the status to 'Aborting' in the process. Once the
 /// thread that successfully aborted performs everything that's required, it sets the status
 /// to 'Ready(incarnation + 1)', allowing the scheduler to create an execution
-/// task for the next incarnation of the transaction.
+/// task for the next incarnation of the transaction. If the transaction reached the maximum
+/// number of incarnations (see [`Scheduler::with_max_incarnations`]), the status is set to
+/// 'Deferred(incarnation + 1)' instead: no execution task is created, and the incarnation is
+/// executed by the commit coordinator once the transaction is the next to commit (see
+/// [`Scheduler::try_commit_deferred`]).
+///
+/// An incarnation that only fails the early validation of its delayed field reads may instead
+/// be soft-failed (see [`Scheduler::try_soft_fail`]), changing its status to
+/// 'SoftFailed(incarnation)'. A soft-failed incarnation is not re-executed speculatively and its
+/// writes are kept (so that no transaction waits on it), but it is still validated, and if the
+/// validation of its other reads fails, it is aborted as an executed incarnation would be.
+/// Otherwise, it is executed again by the commit coordinator once the transaction is the next
+/// to commit, like a deferred transaction.
 ///
 /// 'ExecutionHalted' is a transaction status marking that parallel execution is halted, due to
 /// reasons such as module r/w intersection or exceeding per-block gas limit. It is safe to ignore
@@ -134,13 +211,30 @@ pub enum SchedulerTask {
 ///    |  try_abort (abort successfully)                                                     |
 ///    ↓                finish_abort                                                         |
 /// Aborting(i) ---------------------------------------------------------> Ready(i+1)      ---
+///    |                                                                                     |
+///    |  finish_abort (max incarnations reached)      try_commit_deferred                   |
+///    ↓                                              ----------------------> Committed(i+1) |
+/// Deferred(i+1) -----------------------------------|                                       |
+///                                                  |                                       |
+/// Executed(i)                                      |                                       |
+///    |                                             |                                       |
+///    |  try_soft_fail                              |                                       |
+///    ↓                       try_commit_deferred   |                                       |
+/// SoftFailed(i) -----------------------------------|                                       |
+///    |                                                                                     |
+///    |  try_abort (abort successfully)                                                     |
+///    ↓                finish_abort                                                         |
+/// Aborting(i) ---------------------------------------------------------> Ready(i+1)      ---
 ///
 #[derive(Debug)]
 enum ExecutionStatus {
     Ready(Incarnation, ExecutionTaskType),
     Executing(Incarnation, ExecutionTaskType),
-    Suspended(Incarnation, DependencyCondvar),
+    // The condition variable is None if no worker waits, i.e. the transaction was requeued.
+    Suspended(Incarnation, Option<DependencyCondvar>),
     Executed(Incarnation),
+    Deferred(Incarnation),
+    SoftFailed(Incarnation),
     // TODO[agg_v2](cleanup): rename to Finalized or ReadyToCommit / CommitReady?
     // it gets committed later, without scheduler tracking.
     Committed(Incarnation),
@@ -170,6 +264,8 @@ impl PartialEq for ExecutionStatus {
             )
             | (&Suspended(ref a, _), &Suspended(ref b, _))
             | (&Executed(ref a), &Executed(ref b))
+            | (&Deferred(ref a), &Deferred(ref b))
+            | (&SoftFailed(ref a), &SoftFailed(ref b))
             | (&Committed(ref a), &Committed(ref b))
             | (&Aborting(ref a), &Aborting(ref b)) => a == b,
             _ => false,
@@ -227,11 +323,17 @@ impl PartialEq for ExecutionStatus {
 /// decrease_validation_idx is always called for txn_idx + 1 (e.g. when aborting, there is no need to validate
 /// the transaction before re-execution, and in finish_execution, even if there is a need to validate txn_idx,
 /// it is returned to the caller directly, which is done so as an optimization and also for uniformity).
+///
+/// 'read_only' is set when the last executed incarnation has no writes (see
+/// finish_read_only_execution). Such an incarnation can not invalidate other transactions, and
+/// it is not validated in waves: it is committed regardless of the waves, and validated once by
+/// the commit coordinator, when all the preceding transactions are committed.
 #[derive(Debug)]
 struct ValidationStatus {
     max_triggered_wave: Wave,
     required_wave: Wave,
     maybe_max_validated_wave: Option<Wave>,
+    read_only: bool,
 }
 
 impl ValidationStatus {
@@ -240,8 +342,149 @@ impl ValidationStatus {
             max_triggered_wave: 0,
             required_wave: 0,
             maybe_max_validated_wave: None,
+            read_only: false,
+        }
+    }
+}
+
+/// Fenwick tree of atomic values, supporting concurrent monotonic updates and prefix maxima.
+struct MaxFenwickTree {
+    tree: Vec<AtomicU64>,
+}
+
+impl MaxFenwickTree {
+    fn new(len: usize) -> Self {
+        Self {
+            tree: (0..=len).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    /// Raises the value at position pos to at least value.
+    fn update(&self, pos: usize, value: u64) {
+        let mut i = pos + 1;
+        while i < self.tree.len() {
+            self.tree[i].fetch_max(value, Ordering::SeqCst);
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Returns the maximum value at positions below end.
+    fn prefix_max(&self, end: usize) -> u64 {
+        let mut i = end;
+        let mut ret = 0;
+        while i > 0 {
+            ret = max(ret, self.tree[i].load(Ordering::SeqCst));
+            i -= i & i.wrapping_neg();
+        }
+        ret
+    }
+}
+
+/// Tracks the changes to the transactions of a pre-partitioned block (see [`BlockPartitioning`]),
+/// to tell when the validation of a partitioned transaction is redundant.
+///
+/// A partitioned transaction can only read the writes of earlier transactions of its partition
+/// and of earlier cross-partition transactions. The first incarnation of a partitioned
+/// transaction is deferred until the previous transaction of the partition has been executed,
+/// so the transactions of a partition only affect the later ones when they are aborted or
+/// re-executed. Every such change, as well as every execution or abort of a cross-partition
+/// transaction, is stamped by a logical clock after it is visible in the multi-versioned data
+/// structure, and before validation index is decreased. An incarnation of a partitioned
+/// transaction started at clock c is then valid if no change that can affect it is stamped
+/// after c, and otherwise the change triggers a new validation wave.
+struct PartitionTracker {
+    /// An index i maps to the partition of transaction i and its position within the partition,
+    /// or None for cross-partition transactions.
+    positions: Vec<Option<(u32, u32)>>,
+    /// An index i maps to the next transaction in the partition of transaction i, if any.
+    next_in_partition: Vec<Option<TxnIndex>>,
+    /// Latest change stamp of the transactions of each partition, by position.
+    partition_changes: Vec<MaxFenwickTree>,
+    /// Latest change stamp of the cross-partition transactions, by transaction index.
+    cross_partition_changes: MaxFenwickTree,
+    clock: CachePadded<AtomicU64>,
+    /// An index i maps to the last started incarnation of transaction i and its start clock.
+    execution_start: Vec<CachePadded<Mutex<(Incarnation, u64)>>>,
+}
+
+impl PartitionTracker {
+    fn new(num_txns: TxnIndex, partitioning: &BlockPartitioning) -> Self {
+        let mut partition_lens = vec![0u32; partitioning.num_partitions()];
+        let mut last_in_partition: Vec<Option<TxnIndex>> =
+            vec![None; partitioning.num_partitions()];
+        let mut next_in_partition = vec![None; num_txns as usize];
+        let positions = (0..num_txns)
+            .map(|txn_idx| {
+                partitioning.partition(txn_idx).map(|partition| {
+                    if let Some(prev_idx) = last_in_partition[partition as usize] {
+                        next_in_partition[prev_idx as usize] = Some(txn_idx);
+                    }
+                    last_in_partition[partition as usize] = Some(txn_idx);
+                    let pos = partition_lens[partition as usize];
+                    partition_lens[partition as usize] += 1;
+                    (partition, pos)
+                })
+            })
+            .collect();
+
+        Self {
+            positions,
+            next_in_partition,
+            partition_changes: partition_lens
+                .into_iter()
+                .map(|len| MaxFenwickTree::new(len as usize))
+                .collect(),
+            cross_partition_changes: MaxFenwickTree::new(num_txns as usize),
+            clock: CachePadded::new(AtomicU64::new(0)),
+            execution_start: (0..num_txns)
+                .map(|_| CachePadded::new(Mutex::new((0, 0))))
+                .collect(),
+        }
+    }
+
+    /// For each transaction, returns the previous transaction in its partition, if any.
+    fn previous_in_partition(&self) -> Vec<Option<TxnIndex>> {
+        let mut previous = vec![None; self.positions.len()];
+        for (txn_idx, next) in self.next_in_partition.iter().enumerate() {
+            if let Some(next) = next {
+                previous[*next as usize] = Some(txn_idx as TxnIndex);
+            }
+        }
+        previous
+    }
+
+    fn record_execution_start(&self, txn_idx: TxnIndex, incarnation: Incarnation) {
+        if self.positions[txn_idx as usize].is_some() {
+            *self.execution_start[txn_idx as usize].lock() =
+                (incarnation, self.clock.load(Ordering::SeqCst));
+        }
+    }
+
+    /// Records a finished execution (with first_execution set for incarnation 0) or an abort.
+    fn record_change(&self, txn_idx: TxnIndex, first_execution: bool) {
+        match self.positions[txn_idx as usize] {
+            Some(_) if first_execution => (),
+            Some((partition, pos)) => {
+                let stamp = self.clock.fetch_add(1, Ordering::SeqCst) + 1;
+                self.partition_changes[partition as usize].update(pos as usize, stamp);
+            },
+            None => {
+                let stamp = self.clock.fetch_add(1, Ordering::SeqCst) + 1;
+                self.cross_partition_changes.update(txn_idx as usize, stamp);
+            },
         }
     }
+
+    fn is_validation_redundant(&self, txn_idx: TxnIndex, incarnation: Incarnation) -> bool {
+        let (partition, pos) = match self.positions[txn_idx as usize] {
+            Some(position) => position,
+            None => return false,
+        };
+        let (started_incarnation, start) = *self.execution_start[txn_idx as usize].lock();
+        started_incarnation == incarnation
+            && self.partition_changes[partition as usize].prefix_max(pos as usize) <= start
+            && self.cross_partition_changes.prefix_max(txn_idx as usize) <= start
+    }
 }
 
 pub trait TWaitForDependency {
@@ -250,6 +493,9 @@ pub trait TWaitForDependency {
         txn_idx: TxnIndex,
         dep_txn_idx: TxnIndex,
     ) -> Result<DependencyResult, PanicError>;
+
+    /// Called once the transaction is done waiting on a dependency, with the time it waited.
+    fn record_dependency_wait(&self, _txn_idx: TxnIndex, _wait_time: Duration) {}
 }
 
 pub struct Scheduler {
@@ -259,6 +505,18 @@ pub struct Scheduler {
     /// An index i maps to indices of other transactions that depend on transaction i, i.e. they
     /// should be re-executed once transaction i's next incarnation finishes.
     txn_dependency: Vec<CachePadded<Mutex<Vec<TxnIndex>>>>,
+    /// An index i maps to the transaction i is hinted to depend on (from the access hints
+    /// provided with the block), if any. The first incarnation of i is not scheduled before
+    /// the hinted dependency has been executed, avoiding an execution that would likely abort.
+    hinted_dependency: Vec<Option<TxnIndex>>,
+    /// An index i maps to indices of transactions whose first incarnation was deferred until
+    /// transaction i is executed, due to a hinted dependency.
+    hinted_dependents: Vec<CachePadded<Mutex<Vec<TxnIndex>>>>,
+    /// Transactions whose first incarnation is started ahead of the transactions at lower
+    /// indices, in order (see [`Scheduler::with_execution_priority`]).
+    execution_priority: Vec<TxnIndex>,
+    /// The position in execution_priority of the next transaction to start.
+    execution_priority_idx: CachePadded<AtomicUsize>,
     /// An index i maps to the most up-to-date status of transaction i.
     txn_status: Vec<CachePadded<(RwLock<ExecutionStatus>, RwLock<ValidationStatus>)>>,
 
@@ -296,21 +554,93 @@ pub struct Scheduler {
     has_halted: CachePadded<AtomicBool>,
 
     queueing_commits_lock: CachePadded<ArmedLock>,
-
-    commit_queue: ConcurrentQueue<u32>,
+    /// The re-execution of the next transaction to commit, if handed off by the commit
+    /// coordinator (see [`Scheduler::hand_off_commit_reexecution`]).
+    commit_reexecution: CachePadded<Mutex<Option<(TxnIndex, Incarnation)>>>,
+    has_commit_reexecution: AtomicBool,
+
+    commit_queue: CommitQueue,
+
+    /// Number of workers suspended on a dependency, i.e. from when wait_for_dependency returns
+    /// a dependency until the corresponding wake-up task is handled.
+    num_waiting_workers: CachePadded<AtomicUsize>,
+    /// Total number of times a worker was suspended on a dependency.
+    num_dependency_waits: CachePadded<AtomicUsize>,
+    /// How transactions wait on dependencies, see [`Scheduler::with_dependency_wait`].
+    dependency_wait: DependencyWaitConfig,
+    /// An index i maps to the number of dependencies transaction i waited on, tracked when
+    /// dependency_wait has a wait budget.
+    txn_dependency_waits: Vec<AtomicU32>,
+
+    /// Set when the block is pre-partitioned.
+    partition_tracker: Option<PartitionTracker>,
+
+    /// Set when the schedule is recorded or replayed, see [`crate::schedule_trace`].
+    schedule_tracer: Option<ScheduleTracer>,
+
+    /// Set when the transactions are profiled, see [`crate::execution_profile`].
+    execution_profiler: Option<ExecutionProfiler>,
+    /// The number of incarnations of a transaction after which it is deferred, see
+    /// [`Scheduler::with_max_incarnations`].
+    max_incarnations: Option<Incarnation>,
+
+    /// The number of transactions at the start of the block that are validated once at commit
+    /// rather than in waves, see [`Scheduler::with_conflict_free_prefix`].
+    conflict_free_prefix: TxnIndex,
 }
 
 /// Public Interfaces for the Scheduler
 impl Scheduler {
     pub fn new(num_txns: TxnIndex) -> Self {
+        Self::new_with_hinted_dependencies(num_txns, vec![None; num_txns as usize])
+    }
+
+    /// Creates a scheduler that defers the first incarnation of each transaction until its
+    /// hinted dependency (see [`crate::types::AccessHint`]) has been executed.
+    pub fn new_with_hinted_dependencies(
+        num_txns: TxnIndex,
+        hinted_dependency: Vec<Option<TxnIndex>>,
+    ) -> Self {
+        Self::new_impl(num_txns, hinted_dependency, None)
+    }
+
+    /// Creates a scheduler for a pre-partitioned block, see [`BlockPartitioning`]. The first
+    /// incarnation of each partitioned transaction is deferred until the previous transaction
+    /// of its partition has been executed.
+    pub fn new_with_partitioning(num_txns: TxnIndex, partitioning: &BlockPartitioning) -> Self {
+        assert_eq!(partitioning.num_txns(), num_txns as usize);
+        let partition_tracker = PartitionTracker::new(num_txns, partitioning);
+        Self::new_impl(
+            num_txns,
+            partition_tracker.previous_in_partition(),
+            Some(partition_tracker),
+        )
+    }
+
+    fn new_impl(
+        num_txns: TxnIndex,
+        hinted_dependency: Vec<Option<TxnIndex>>,
+        partition_tracker: Option<PartitionTracker>,
+    ) -> Self {
         // Empty block should early return and not create a scheduler.
         assert!(num_txns > 0, "No scheduler needed for 0 transactions");
+        assert_eq!(hinted_dependency.len(), num_txns as usize);
+        assert!(hinted_dependency
+            .iter()
+            .enumerate()
+            .all(|(txn_idx, dep)| dep.map_or(true, |dep| (dep as usize) < txn_idx)));
 
         Self {
             num_txns,
             txn_dependency: (0..num_txns)
                 .map(|_| CachePadded::new(Mutex::new(Vec::new())))
                 .collect(),
+            hinted_dependency,
+            hinted_dependents: (0..num_txns)
+                .map(|_| CachePadded::new(Mutex::new(Vec::new())))
+                .collect(),
+            execution_priority: Vec::new(),
+            execution_priority_idx: CachePadded::new(AtomicUsize::new(0)),
             txn_status: (0..num_txns)
                 .map(|_| {
                     CachePadded::new((
@@ -325,22 +655,134 @@ impl Scheduler {
             done_marker: CachePadded::new(AtomicBool::new(false)),
             has_halted: CachePadded::new(AtomicBool::new(false)),
             queueing_commits_lock: CachePadded::new(ArmedLock::new()),
-            commit_queue: ConcurrentQueue::<u32>::bounded(num_txns as usize),
+            commit_reexecution: CachePadded::new(Mutex::new(None)),
+            has_commit_reexecution: AtomicBool::new(false),
+            commit_queue: CommitQueue::new(),
+            num_waiting_workers: CachePadded::new(AtomicUsize::new(0)),
+            num_dependency_waits: CachePadded::new(AtomicUsize::new(0)),
+            dependency_wait: DependencyWaitConfig::default(),
+            txn_dependency_waits: Vec::new(),
+            partition_tracker,
+            schedule_tracer: None,
+            execution_profiler: None,
+            max_incarnations: None,
+            conflict_free_prefix: 0,
         }
     }
 
+    /// Starts the first incarnations of the given transactions (in the given order) before the
+    /// transactions at lower indices, e.g. so that the outputs of high-fee transactions are
+    /// ready when they are committed. The commit order is unchanged. Transactions with a hinted
+    /// dependency keep their regular order, and the priority is ignored for a pre-partitioned
+    /// block.
+    pub(crate) fn with_execution_priority(mut self, execution_priority: Vec<TxnIndex>) -> Self {
+        assert!(execution_priority
+            .iter()
+            .all(|txn_idx| *txn_idx < self.num_txns));
+        if self.partition_tracker.is_none() {
+            self.execution_priority = execution_priority;
+        }
+        self
+    }
+
+    /// Sets how transactions wait on dependencies. With requeue, wait_for_dependency returns
+    /// [`DependencyResult::Requeued`] instead of a condition variable to wait on, and resuming
+    /// the transaction creates an execution task (rather than a wake-up task) for the same
+    /// incarnation. With a wait budget, the waits of each transaction are counted, see
+    /// [`Scheduler::dependency_wait_budget_exhausted`].
+    pub(crate) fn with_dependency_wait(mut self, dependency_wait: DependencyWaitConfig) -> Self {
+        if dependency_wait.wait_budget.is_some() {
+            self.txn_dependency_waits = (0..self.num_txns).map(|_| AtomicU32::new(0)).collect();
+        }
+        self.dependency_wait = dependency_wait;
+        self
+    }
+
+    /// Returns true if the transaction waited on as many dependencies as its wait budget allows,
+    /// after which its reads should go past the estimates instead of waiting.
+    pub(crate) fn dependency_wait_budget_exhausted(&self, txn_idx: TxnIndex) -> bool {
+        self.dependency_wait.wait_budget.map_or(false, |budget| {
+            self.txn_dependency_waits[txn_idx as usize].load(Ordering::Relaxed) >= budget
+        })
+    }
+
+    /// Returns true if the reads of the transaction should go past the estimates (reading the
+    /// values written by the aborted incarnations) instead of waiting on the dependency.
+    pub(crate) fn reads_past_estimates(&self, txn_idx: TxnIndex) -> bool {
+        self.dependency_wait.speculate_past_estimates
+            || self.dependency_wait_budget_exhausted(txn_idx)
+    }
+
+    /// Stops speculating on the transactions that were executed max_incarnations times: once
+    /// such a transaction is aborted, its next incarnation is deferred until it is the next
+    /// transaction to commit, when it is executed exactly once by the commit coordinator (see
+    /// [`Scheduler::try_commit_deferred`]). The transactions that depend on a deferred
+    /// transaction are requeued rather than waiting for it (see [`DependencyWaitConfig`]).
+    pub(crate) fn with_max_incarnations(mut self, max_incarnations: Option<Incarnation>) -> Self {
+        assert!(
+            max_incarnations.map_or(true, |max_incarnations| max_incarnations > 0),
+            "The first incarnation can not be deferred"
+        );
+        self.max_incarnations = max_incarnations;
+        self
+    }
+
+    /// Sets the number of transactions at the start of the block that are expected not to
+    /// conflict with each other (see [`crate::types::conflict_free_prefix`]). These transactions
+    /// are not validated in waves: each one is committed once all the preceding transactions
+    /// are, and validated a single time then by the caller of try_commit. As the expectation may
+    /// be wrong, a transaction of the prefix that fails this validation is re-executed during
+    /// the commit, as usual.
+    pub(crate) fn with_conflict_free_prefix(mut self, conflict_free_prefix: TxnIndex) -> Self {
+        assert!(conflict_free_prefix <= self.num_txns);
+        self.conflict_free_prefix = conflict_free_prefix;
+        self.validation_idx =
+            AtomicU64::new(Self::pack_into_validation_index(conflict_free_prefix, 0));
+        self
+    }
+
+    /// Records the schedule of the execution into the tracer, or replays the schedule of a
+    /// recorded execution from it.
+    pub(crate) fn with_schedule_tracer(mut self, schedule_tracer: Option<ScheduleTracer>) -> Self {
+        self.schedule_tracer = schedule_tracer;
+        self
+    }
+
+    /// Returns the recorded (or replayed) schedule, or the reason the replay diverged.
+    pub(crate) fn finish_schedule_trace(&mut self) -> Option<Result<ScheduleTrace, String>> {
+        self.schedule_tracer.take().map(ScheduleTracer::finish)
+    }
+
+    /// Records the timings of the transactions into the profiler, by the workers.
+    pub(crate) fn with_execution_profiler(
+        mut self,
+        execution_profiler: Option<ExecutionProfiler>,
+    ) -> Self {
+        self.execution_profiler = execution_profiler;
+        self
+    }
+
+    pub(crate) fn execution_profiler(&self) -> Option<&ExecutionProfiler> {
+        self.execution_profiler.as_ref()
+    }
+
+    pub(crate) fn take_execution_profile(&mut self) -> Option<BlockExecutionProfile> {
+        self.execution_profiler
+            .take()
+            .map(ExecutionProfiler::finish)
+    }
+
     pub fn num_txns(&self) -> TxnIndex {
         self.num_txns
     }
 
     pub fn add_to_commit_queue(&self, txn_idx: u32) {
-        self.commit_queue
-            .push(txn_idx)
-            .expect("Pushing to the commit_queue should never fail");
+        self.commit_queue.push(txn_idx);
     }
 
-    pub fn pop_from_commit_queue(&self) -> Result<u32, PopError> {
-        self.commit_queue.pop()
+    /// Pops up to max_batch_size consecutive committed transactions (an empty range if none).
+    pub fn pop_from_commit_queue(&self, max_batch_size: usize) -> Range<TxnIndex> {
+        self.commit_queue.pop_batch(max_batch_size)
     }
 
     pub fn queueing_commits_mark_done(&self) {
@@ -355,7 +797,42 @@ impl Scheduler {
         self.queueing_commits_lock.try_lock()
     }
 
-    /// If successful, returns Some(TxnIndex), the index of committed transaction.
+    /// Hands off the final re-execution of the transaction, which failed the commit-time
+    /// validation, to the next worker asking for a task. The caller keeps the coordination of
+    /// the commits locked (i.e. does not mark it done), as it passes to that worker.
+    pub(crate) fn hand_off_commit_reexecution(&self, txn_idx: TxnIndex, incarnation: Incarnation) {
+        let mut commit_reexecution = self.commit_reexecution.lock();
+        assert!(
+            commit_reexecution.is_none(),
+            "Only the commit coordinator can hand off a re-execution"
+        );
+        *commit_reexecution = Some((txn_idx, incarnation));
+        self.has_commit_reexecution.store(true, Ordering::Release);
+    }
+
+    fn try_take_commit_reexecution(&self) -> Option<(TxnIndex, Incarnation)> {
+        if !self.has_commit_reexecution.load(Ordering::Acquire) {
+            return None;
+        }
+        let mut commit_reexecution = self.commit_reexecution.lock();
+        self.has_commit_reexecution.store(false, Ordering::Release);
+        commit_reexecution.take()
+    }
+
+    /// Returns the index of the next transaction to commit. Must be called while holding the
+    /// commit lock (see [`Scheduler::should_coordinate_commits`]).
+    pub(crate) fn next_txn_to_commit(&self) -> TxnIndex {
+        self.commit_state.acquire().dereference().0
+    }
+
+    /// Returns the incarnation of the transaction if it is executed (but not committed).
+    pub(crate) fn executed_incarnation(&self, txn_idx: TxnIndex) -> Option<Incarnation> {
+        self.is_executed(txn_idx, false)
+    }
+
+    /// If successful, returns Some(TxnIndex), the index of committed transaction. A transaction
+    /// validated at commit (see [`Scheduler::is_validated_at_commit`]) is committed without being
+    /// validated, and must then be validated by the caller.
     pub fn try_commit(&self) -> Option<(TxnIndex, Incarnation)> {
         let mut commit_state = self.commit_state.acquire();
         let (commit_idx, commit_wave) = commit_state.dereference_mut();
@@ -380,20 +857,40 @@ impl Scheduler {
                 // decreased thus affecting all later txns as well,
                 // while required_wave only records the new wave for one single txn.
                 *commit_wave = max(*commit_wave, validation_status.max_triggered_wave);
-                if let Some(validated_wave) = validation_status.maybe_max_validated_wave {
-                    if validated_wave >= max(*commit_wave, validation_status.required_wave) {
-                        let mut status_write = RwLockUpgradableReadGuard::upgrade(status);
-                        // Upgrade the execution status read lock to write lock.
-                        // Can commit.
-                        *status_write = ExecutionStatus::Committed(incarnation);
-
-                        *commit_idx += 1;
-                        if *commit_idx == self.num_txns {
-                            // All txns have been committed, the parallel execution can finish.
-                            self.done_marker.store(true, Ordering::SeqCst);
+                // A transaction validated at commit is validated by the caller instead.
+                let validated = Self::validated_at_commit(
+                    *commit_idx,
+                    &validation_status,
+                    self.conflict_free_prefix,
+                ) || validation_status.maybe_max_validated_wave.map_or(
+                    false,
+                    |validated_wave| {
+                        validated_wave >= max(*commit_wave, validation_status.required_wave)
+                    },
+                );
+                if validated {
+                    if let Some(tracer) = &self.schedule_tracer {
+                        // When replaying, the commit is retried once it is due (see
+                        // next_task). The scheduler is not halted here on a divergence,
+                        // as halting acquires the execution status locks.
+                        let event = ScheduleEvent::Commit(*commit_idx);
+                        if tracer.wait_for_turn(event, Duration::ZERO) != ReplayTurn::Due {
+                            return None;
                         }
-                        return Some((*commit_idx - 1, incarnation));
+                        tracer.record_event(event);
+                    }
+
+                    let mut status_write = RwLockUpgradableReadGuard::upgrade(status);
+                    // Upgrade the execution status read lock to write lock.
+                    // Can commit.
+                    *status_write = ExecutionStatus::Committed(incarnation);
+
+                    *commit_idx += 1;
+                    if *commit_idx == self.num_txns {
+                        // All txns have been committed, the parallel execution can finish.
+                        self.done_marker.store(true, Ordering::SeqCst);
                     }
+                    return Some((*commit_idx - 1, incarnation));
                 }
             }
 
@@ -409,6 +906,51 @@ impl Scheduler {
         None
     }
 
+    /// If the next transaction to commit is deferred (see [`Scheduler::with_max_incarnations`])
+    /// or soft-failed (see [`Scheduler::try_soft_fail`]), commits it and returns its index, the
+    /// next incarnation, which the caller must then execute (as the final re-execution during
+    /// commit), and whether the transaction was soft-failed. Must be called while holding the
+    /// commit lock.
+    pub(crate) fn try_commit_deferred(&self) -> Option<(TxnIndex, Incarnation, bool)> {
+        let mut commit_state = self.commit_state.acquire();
+        let (commit_idx, commit_wave) = commit_state.dereference_mut();
+
+        if *commit_idx == self.num_txns {
+            return None;
+        }
+
+        let validation_status = self.txn_status[*commit_idx as usize].1.read();
+        let mut status = self.txn_status[*commit_idx as usize].0.write();
+        let (incarnation, soft_failed) = match *status {
+            ExecutionStatus::Deferred(incarnation) => (incarnation, false),
+            ExecutionStatus::SoftFailed(incarnation) => (incarnation + 1, true),
+            _ => return None,
+        };
+        if let Some(tracer) = &self.schedule_tracer {
+            let event = ScheduleEvent::Commit(*commit_idx);
+            if tracer.wait_for_turn(event, Duration::ZERO) != ReplayTurn::Due {
+                return None;
+            }
+            tracer.record_event(event);
+        }
+
+        // The incarnation is executed after all the preceding transactions are committed, so
+        // it is not validated (other than at commit).
+        *commit_wave = max(*commit_wave, validation_status.max_triggered_wave);
+        *status = ExecutionStatus::Committed(incarnation);
+        *commit_idx += 1;
+        if *commit_idx == self.num_txns {
+            self.done_marker.store(true, Ordering::SeqCst);
+        }
+        Some((*commit_idx - 1, incarnation, soft_failed))
+    }
+
+    /// Returns the index of the next transaction to commit. Must be called while holding the
+    /// commit lock (see [`Scheduler::should_coordinate_commits`]).
+    pub(crate) fn next_txn_to_commit(&self) -> TxnIndex {
+        self.commit_state.acquire().dereference().0
+    }
+
     #[cfg(test)]
     /// Return the TxnIndex and Wave of current commit index
     pub fn commit_state(&self) -> (TxnIndex, u32) {
@@ -418,32 +960,95 @@ impl Scheduler {
 
     /// Try to abort version = (txn_idx, incarnation), called upon validation failure.
     /// When the invocation manages to update the status of the transaction, it changes
-    /// Executed(incarnation) => Aborting(incarnation), it returns true. Otherwise,
-    /// returns false. Since incarnation numbers never decrease, this also ensures
-    /// that the same version may not successfully abort more than once.
+    /// Executed(incarnation) or SoftFailed(incarnation) => Aborting(incarnation), it returns
+    /// true. Otherwise, returns false. Since incarnation numbers never decrease, this also
+    /// ensures that the same version may not successfully abort more than once.
     pub fn try_abort(&self, txn_idx: TxnIndex, incarnation: Incarnation) -> bool {
+        let event = ScheduleEvent::Abort(txn_idx, incarnation);
+        if let Some(tracer) = &self.schedule_tracer {
+            loop {
+                match self.schedule_turn(tracer, event) {
+                    ReplayTurn::Due => break,
+                    ReplayTurn::Pending => continue,
+                    ReplayTurn::Replayed | ReplayTurn::Diverged => return false,
+                }
+            }
+        }
+
         // lock the execution status.
         // Note: we could upgradable read, then upgrade and write. Similar for other places.
         // However, it is likely an overkill (and overhead to actually upgrade),
         // while unlikely there would be much contention on a specific index lock.
         let mut status = self.txn_status[txn_idx as usize].0.write();
 
-        if *status == ExecutionStatus::Executed(incarnation) {
+        if *status == ExecutionStatus::Executed(incarnation)
+            || *status == ExecutionStatus::SoftFailed(incarnation)
+        {
             *status = ExecutionStatus::Aborting(incarnation);
+            if let Some(tracer) = &self.schedule_tracer {
+                tracer.record_event(event);
+            }
             true
         } else {
             false
         }
     }
 
+    /// Try to soft-fail version = (txn_idx, incarnation), called when only the early validation
+    /// of its delayed field reads fails. Changes Executed(incarnation) => SoftFailed(incarnation)
+    /// and returns true, otherwise (e.g. if the incarnation is already soft-failed) returns
+    /// false. The transaction is not re-executed speculatively, until the validation of its
+    /// other reads fails, or it is the next to commit.
+    pub(crate) fn try_soft_fail(&self, txn_idx: TxnIndex, incarnation: Incarnation) -> bool {
+        let event = ScheduleEvent::SoftFail(txn_idx, incarnation);
+        if let Some(tracer) = &self.schedule_tracer {
+            loop {
+                match self.schedule_turn(tracer, event) {
+                    ReplayTurn::Due => break,
+                    ReplayTurn::Pending => continue,
+                    ReplayTurn::Replayed | ReplayTurn::Diverged => return false,
+                }
+            }
+        }
+
+        {
+            let mut status = self.txn_status[txn_idx as usize].0.write();
+            if *status != ExecutionStatus::Executed(incarnation) {
+                return false;
+            }
+            *status = ExecutionStatus::SoftFailed(incarnation);
+            if let Some(tracer) = &self.schedule_tracer {
+                tracer.record_event(event);
+            }
+        }
+
+        counters::SOFT_FAILED_TXN_COUNT.inc();
+        // The soft-failed transaction may be the next to commit.
+        self.queueing_commits_arm();
+        true
+    }
+
     /// Return the next task for the thread.
     pub fn next_task(&self) -> SchedulerTask {
+        if let Some(tracer) = &self.schedule_tracer {
+            // When replaying, a commit that was not due when attempted is only retried once
+            // the commits are armed again.
+            if tracer.is_commit_due() {
+                self.queueing_commits_arm();
+            }
+        }
+
         loop {
             if self.done() {
                 // No more tasks.
                 return SchedulerTask::Done;
             }
 
+            // The commits are blocked until the handed off re-execution is performed.
+            if let Some((txn_idx, incarnation)) = self.try_take_commit_reexecution() {
+                return SchedulerTask::CommitReExecutionTask(txn_idx, incarnation);
+            }
+
             let (idx_to_validate, wave) =
                 Self::unpack_validation_idx(self.validation_idx.load(Ordering::Acquire));
 
@@ -465,8 +1070,9 @@ impl Scheduler {
             }
 
             if idx_to_execute < self.num_txns {
-                if let Some((txn_idx, incarnation, execution_task_type)) =
-                    self.try_execute_next_version()
+                if let Some((txn_idx, incarnation, execution_task_type)) = self
+                    .try_execute_prioritized_version()
+                    .or_else(|| self.try_execute_next_version())
                 {
                     return SchedulerTask::ExecutionTask(txn_idx, incarnation, execution_task_type);
                 }
@@ -489,6 +1095,10 @@ impl Scheduler {
             // Holding the lock, take dependency vector.
             std::mem::take(&mut stored_deps)
         };
+        let hinted_deps: Vec<TxnIndex> = {
+            let mut stored_deps = self.hinted_dependents[txn_idx as usize].lock();
+            std::mem::take(&mut stored_deps)
+        };
 
         // Mark dependencies as resolved and find the minimum index among them.
         let mut min_dep = None;
@@ -499,6 +1109,10 @@ impl Scheduler {
                 min_dep = Some(dep);
             }
         }
+        // Deferred transactions are still ready, they only need to be scheduled again.
+        if let Some(min_hinted_dep) = hinted_deps.into_iter().min() {
+            min_dep = Some(min_dep.map_or(min_hinted_dep, |min_dep| min(min_dep, min_hinted_dep)));
+        }
         if let Some(execution_target_idx) = min_dep {
             // Decrease the execution index as necessary to ensure resolved dependencies
             // get a chance to be re-executed.
@@ -511,12 +1125,57 @@ impl Scheduler {
     /// After txn is executed, schedule its dependencies for re-execution.
     /// If revalidate_suffix is true, decrease validation_idx to schedule all higher transactions
     /// for (re-)validation. Otherwise, in some cases (if validation_idx not already lower),
-    /// return a validation task of the transaction to the caller (otherwise NoTask).
+    /// return a validation task of the transaction to the caller (otherwise NoTask). For a
+    /// pre-partitioned block, if validating the transaction is redundant, it is validated right
+    /// away and the execution task of the next transaction of its partition may be returned.
     pub fn finish_execution(
         &self,
         txn_idx: TxnIndex,
         incarnation: Incarnation,
         revalidate_suffix: bool,
+    ) -> Result<SchedulerTask, PanicError> {
+        self.finish_execution_impl(txn_idx, incarnation, revalidate_suffix, false)
+    }
+
+    /// Finishes the execution of an incarnation whose output contains no writes. It is not
+    /// validated in waves (and can not be aborted before it is the next to commit): it is
+    /// committed once all the preceding transactions are, and validated a single time then by
+    /// the caller of try_commit, see [`Scheduler::is_validated_at_commit`].
+    pub(crate) fn finish_read_only_execution(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+    ) -> Result<SchedulerTask, PanicError> {
+        counters::READ_ONLY_EXECUTION_COUNT.inc();
+        self.finish_execution_impl(txn_idx, incarnation, false, true)
+    }
+
+    /// Returns true if the transaction is not validated in waves, but once by the caller of
+    /// try_commit: if it is in the conflict-free prefix of the block (see
+    /// [`Scheduler::with_conflict_free_prefix`]), or if its last executed incarnation has no
+    /// writes (see [`Scheduler::finish_read_only_execution`]).
+    pub(crate) fn is_validated_at_commit(&self, txn_idx: TxnIndex) -> bool {
+        Self::validated_at_commit(
+            txn_idx,
+            &self.txn_status[txn_idx as usize].1.read(),
+            self.conflict_free_prefix,
+        )
+    }
+
+    fn validated_at_commit(
+        txn_idx: TxnIndex,
+        validation_status: &ValidationStatus,
+        conflict_free_prefix: TxnIndex,
+    ) -> bool {
+        txn_idx < conflict_free_prefix || validation_status.read_only
+    }
+
+    fn finish_execution_impl(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        revalidate_suffix: bool,
+        read_only: bool,
     ) -> Result<SchedulerTask, PanicError> {
         // Note: It is preferable to hold the validation lock throughout the finish_execution,
         // in particular before updating execution status. The point was that we don't want
@@ -526,6 +1185,10 @@ impl Scheduler {
         // the reason why we grab write lock directly, and never release it during the whole function.
         // So even validation status readers have to wait if they somehow end up at the same index.
         let mut validation_status = self.txn_status[txn_idx as usize].1.write();
+        if let Some(partition_tracker) = &self.partition_tracker {
+            partition_tracker.record_change(txn_idx, incarnation == 0);
+        }
+        validation_status.read_only = read_only;
         self.set_executed_status(txn_idx, incarnation)?;
 
         self.wake_dependencies_after_execution(txn_idx)?;
@@ -533,8 +1196,15 @@ impl Scheduler {
         let (cur_val_idx, mut cur_wave) =
             Self::unpack_validation_idx(self.validation_idx.load(Ordering::Acquire));
 
-        // Needs to be re-validated in a new wave
-        if cur_val_idx > txn_idx {
+        if Self::validated_at_commit(txn_idx, &validation_status, self.conflict_free_prefix) {
+            if revalidate_suffix {
+                // Only the transactions after the conflict-free prefix are validated in waves.
+                self.decrease_validation_idx(max(txn_idx + 1, self.conflict_free_prefix));
+            }
+            // Can be committed right away.
+            self.queueing_commits_arm();
+        } else if cur_val_idx > txn_idx {
+            // Needs to be re-validated in a new wave
             if revalidate_suffix {
                 // The transaction execution required revalidating all higher txns (not
                 // only itself), currently happens when incarnation writes to a new path
@@ -545,17 +1215,54 @@ impl Scheduler {
             }
             // Update the minimum wave this txn needs to pass.
             validation_status.required_wave = cur_wave;
-            return Ok(SchedulerTask::ValidationTask(
-                txn_idx,
-                incarnation,
-                cur_wave,
-            ));
+            if !self.is_validation_redundant(txn_idx, incarnation) {
+                return Ok(SchedulerTask::ValidationTask(
+                    txn_idx,
+                    incarnation,
+                    cur_wave,
+                ));
+            }
+            // Validated right away, as the worker can then continue with the partition.
+            validation_status.maybe_max_validated_wave = Some(
+                validation_status
+                    .maybe_max_validated_wave
+                    .map_or(cur_wave, |prev_wave| max(prev_wave, cur_wave)),
+            );
+            self.queueing_commits_arm();
         }
 
-        Ok(SchedulerTask::NoTask)
+        // Continue with the next transaction of the partition, if no other worker took it.
+        Ok(self
+            .partition_tracker
+            .as_ref()
+            .and_then(|partition_tracker| partition_tracker.next_in_partition[txn_idx as usize])
+            .and_then(|next_idx| {
+                self.try_incarnate(next_idx)
+                    .map(|(incarnation, execution_task_type)| {
+                        SchedulerTask::ExecutionTask(next_idx, incarnation, execution_task_type)
+                    })
+            })
+            .unwrap_or(SchedulerTask::NoTask))
+    }
+
+    /// Returns true if the transaction is partitioned and its incarnation can not have been
+    /// invalidated, so that validating its reads is not needed.
+    pub(crate) fn is_validation_redundant(
+        &self,
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+    ) -> bool {
+        self.partition_tracker
+            .as_ref()
+            .map_or(false, |partition_tracker| {
+                partition_tracker.is_validation_redundant(txn_idx, incarnation)
+            })
     }
 
     pub fn finish_execution_during_commit(&self, txn_idx: TxnIndex) -> Result<(), PanicError> {
+        if let Some(partition_tracker) = &self.partition_tracker {
+            partition_tracker.record_change(txn_idx, false);
+        }
         // We have exclusivity on this transaction.
         self.wake_dependencies_after_execution(txn_idx)?;
 
@@ -585,12 +1292,26 @@ impl Scheduler {
             // provide correctness between finish_execution & try_commit.
             let _validation_status = self.txn_status[txn_idx as usize].1.write();
 
-            self.set_aborted_status(txn_idx, incarnation)?;
+            if let Some(partition_tracker) = &self.partition_tracker {
+                partition_tracker.record_change(txn_idx, false);
+            }
+            let deferred = self.set_aborted_status(txn_idx, incarnation)?;
 
             // Schedule higher txns for validation, skipping txn_idx itself (needs to be
             // re-executed first).
             self.decrease_validation_idx(txn_idx + 1);
 
+            if deferred {
+                counters::DEFERRED_TXN_COUNT.inc();
+                // The transactions that already wait on txn_idx are woken up, to be requeued
+                // when they read its estimates again (see wait_for_dependency), as no worker
+                // may wait until the deferred transaction is committed.
+                self.wake_dependencies_after_execution(txn_idx)?;
+                // The deferred transaction may be the next to commit.
+                self.queueing_commits_arm();
+                return Ok(SchedulerTask::NoTask);
+            }
+
             // Can release the lock early.
         }
 
@@ -641,6 +1362,33 @@ impl Scheduler {
 
         !self.has_halted.swap(true, Ordering::SeqCst)
     }
+
+    /// Called by a worker before it performs an execution or validation task. Returns false
+    /// if the schedule of a recorded execution is replayed and the start of the task is not
+    /// due yet, in which case the worker keeps the task and retries (after coordinating the
+    /// commits that may be due first). If the replay diverged, the scheduler is halted.
+    pub(crate) fn start_task(&self, task: &SchedulerTask) -> bool {
+        let tracer = match &self.schedule_tracer {
+            Some(tracer) => tracer,
+            None => return true,
+        };
+        let event = match task {
+            SchedulerTask::ExecutionTask(txn_idx, incarnation, ExecutionTaskType::Execution) => {
+                ScheduleEvent::Execute(*txn_idx, *incarnation)
+            },
+            SchedulerTask::ValidationTask(txn_idx, incarnation, _) => {
+                ScheduleEvent::Validate(*txn_idx, *incarnation)
+            },
+            _ => return true,
+        };
+
+        if self.schedule_turn(tracer, event) == ReplayTurn::Due {
+            tracer.record_event(event);
+            true
+        } else {
+            false
+        }
+    }
 }
 
 impl TWaitForDependency for Scheduler {
@@ -664,9 +1412,6 @@ impl TWaitForDependency for Scheduler {
         // Note: Could pre-check that txn dep_txn_idx isn't in an executed state, but the caller
         // usually has just observed the read dependency.
 
-        // Create a condition variable associated with the dependency.
-        let dep_condvar = Arc::new((Mutex::new(DependencyStatus::Unresolved), Condvar::new()));
-
         let mut stored_deps = self.txn_dependency[dep_txn_idx as usize].lock();
 
         // Note: is_executed & suspend calls acquire (a different, status) mutex, while holding
@@ -684,6 +1429,17 @@ impl TWaitForDependency for Scheduler {
             return Ok(DependencyResult::Resolved);
         }
 
+        // A deferred transaction is only executed once all the preceding transactions are
+        // committed, which may take all the workers, so none waits on it.
+        let requeue = self.dependency_wait.requeue
+            || matches!(
+                *self.txn_status[dep_txn_idx as usize].0.read(),
+                ExecutionStatus::Deferred(_)
+            );
+        // Create a condition variable associated with the dependency, unless no worker waits.
+        let dep_condvar = (!requeue)
+            .then(|| Arc::new((Mutex::new(DependencyStatus::Unresolved), Condvar::new())));
+
         // If the execution is already halted, suspend will return false.
         // The synchronization is guaranteed by the Mutex around txn_status.
         // If the execution is halted, the first finishing thread will first set the status of each txn
@@ -697,23 +1453,54 @@ impl TWaitForDependency for Scheduler {
         // Safe to add dependency here (still holding the lock) - finish_execution of txn
         // dep_txn_idx is guaranteed to acquire the same lock later and clear the dependency.
         stored_deps.push(txn_idx);
+        self.num_dependency_waits.fetch_add(1, Ordering::Relaxed);
+        if self.dependency_wait.wait_budget.is_some() {
+            self.txn_dependency_waits[txn_idx as usize].fetch_add(1, Ordering::Relaxed);
+        }
+        let dep_condvar = match dep_condvar {
+            Some(dep_condvar) => dep_condvar,
+            // No worker waits, the transaction is executed again once resumed.
+            None => return Ok(DependencyResult::Requeued),
+        };
+        // Counted before the lock is released, so before the wake-up task can be handled.
+        self.num_waiting_workers.fetch_add(1, Ordering::SeqCst);
 
         // Stored deps gets unlocked here.
 
         Ok(DependencyResult::Dependency(dep_condvar))
     }
+
+    fn record_dependency_wait(&self, txn_idx: TxnIndex, wait_time: Duration) {
+        if let Some(profiler) = &self.execution_profiler {
+            profiler.record_dependency_wait(txn_idx, wait_time);
+        }
+    }
 }
 
 /// Private functions of the Scheduler
 impl Scheduler {
     /// Helper function to be called from Scheduler::halt(); Sets the transaction status to Halted.
     /// If the transaction is suspended, it will wake it up.
+    /// Waits briefly for the event to be due in the replayed schedule. While waiting on a
+    /// commit to be replayed, arms the commits so that the workers attempt it.
+    fn schedule_turn(&self, tracer: &ScheduleTracer, event: ScheduleEvent) -> ReplayTurn {
+        let turn = tracer.wait_for_turn(event, REPLAY_TURN_WAIT);
+        match turn {
+            ReplayTurn::Pending if tracer.is_commit_due() => self.queueing_commits_arm(),
+            ReplayTurn::Diverged => {
+                self.halt();
+            },
+            _ => (),
+        }
+        turn
+    }
+
     fn halt_transaction_execution(&self, txn_idx: TxnIndex) {
         let mut status = self.txn_status[txn_idx as usize].0.write();
 
         // Replace status to sure that the txn never gets suspended.
         match std::mem::replace(&mut *status, ExecutionStatus::ExecutionHalted) {
-            ExecutionStatus::Suspended(_, condvar)
+            ExecutionStatus::Suspended(_, Some(condvar))
             | ExecutionStatus::Ready(_, ExecutionTaskType::Wakeup(condvar))
             | ExecutionStatus::Executing(_, ExecutionTaskType::Wakeup(condvar)) => {
                 let (lock, cvar) = &*(condvar.clone());
@@ -792,6 +1579,12 @@ impl Scheduler {
         // while unlikely there would be much contention on a specific index lock.
         let mut status = self.txn_status[txn_idx as usize].0.write();
         if let ExecutionStatus::Ready(incarnation, execution_task_type) = &*status {
+            // A woken up execution keeps the start of its incarnation.
+            if let (Some(partition_tracker), ExecutionTaskType::Execution) =
+                (&self.partition_tracker, execution_task_type)
+            {
+                partition_tracker.record_execution_start(txn_idx, *incarnation);
+            }
             let ret: (u32, ExecutionTaskType) = (*incarnation, (*execution_task_type).clone());
             *status = ExecutionStatus::Executing(*incarnation, (*execution_task_type).clone());
             Some(ret)
@@ -800,7 +1593,8 @@ impl Scheduler {
         }
     }
 
-    /// If the status of transaction is Executed(incarnation), returns Some(incarnation),
+    /// If the status of transaction is Executed(incarnation) (or SoftFailed(incarnation), as
+    /// the writes of a soft-failed incarnation are kept), returns Some(incarnation),
     /// Useful to determine when a transaction can be validated, and to avoid a race in
     /// dependency resolution.
     /// If include_committed is true (which is when calling from wait_for_dependency),
@@ -812,7 +1606,9 @@ impl Scheduler {
     fn is_executed(&self, txn_idx: TxnIndex, include_committed: bool) -> Option<Incarnation> {
         let status = self.txn_status[txn_idx as usize].0.read();
         match *status {
-            ExecutionStatus::Executed(incarnation) => Some(incarnation),
+            ExecutionStatus::Executed(incarnation) | ExecutionStatus::SoftFailed(incarnation) => {
+                Some(incarnation)
+            },
             ExecutionStatus::Committed(incarnation) => {
                 if include_committed {
                     // Committed txns are also considered executed for dependency resolution purposes.
@@ -870,6 +1666,9 @@ impl Scheduler {
             // Successfully claimed idx_to_validate to attempt validation.
             // If incarnation was last executed, and thus ready for validation,
             // return version and wave for validation task, otherwise None.
+            if self.is_validated_at_commit(idx_to_validate) {
+                return None;
+            }
             return self
                 .is_executed(idx_to_validate, false)
                 .map(|incarnation| (idx_to_validate, incarnation, wave));
@@ -888,7 +1687,7 @@ impl Scheduler {
     fn try_execute_next_version(&self) -> Option<(TxnIndex, Incarnation, ExecutionTaskType)> {
         let idx_to_execute = self.execution_idx.fetch_add(1, Ordering::SeqCst);
 
-        if idx_to_execute >= self.num_txns {
+        if idx_to_execute >= self.num_txns || self.defer_for_hinted_dependency(idx_to_execute) {
             return None;
         }
 
@@ -900,6 +1699,53 @@ impl Scheduler {
             })
     }
 
+    /// Grab the next prioritized transaction (by fetch-and-incrementing execution_priority_idx)
+    /// to start its first incarnation ahead of execution_idx. Transactions that execution_idx
+    /// already reached or that have a hinted dependency are left to the regular order.
+    fn try_execute_prioritized_version(
+        &self,
+    ) -> Option<(TxnIndex, Incarnation, ExecutionTaskType)> {
+        while self.execution_priority_idx.load(Ordering::Relaxed) < self.execution_priority.len() {
+            let priority_idx = self.execution_priority_idx.fetch_add(1, Ordering::Relaxed);
+            let txn_idx = *self.execution_priority.get(priority_idx)?;
+            if txn_idx < self.execution_idx.load(Ordering::Acquire)
+                || self.hinted_dependency[txn_idx as usize].is_some()
+            {
+                continue;
+            }
+            if let Some((incarnation, execution_task_type)) = self.try_incarnate(txn_idx) {
+                return Some((txn_idx, incarnation, execution_task_type));
+            }
+        }
+        None
+    }
+
+    /// Returns true if the first incarnation of the transaction must wait for its hinted
+    /// dependency to be executed, in which case the transaction is registered to be scheduled
+    /// again once that happens. Re-executions are never deferred.
+    fn defer_for_hinted_dependency(&self, txn_idx: TxnIndex) -> bool {
+        let dep_txn_idx = match self.hinted_dependency[txn_idx as usize] {
+            Some(dep_txn_idx) => dep_txn_idx,
+            None => return false,
+        };
+        if !matches!(
+            *self.txn_status[txn_idx as usize].0.read(),
+            ExecutionStatus::Ready(0, ExecutionTaskType::Execution)
+        ) {
+            return false;
+        }
+
+        // Same protocol as in wait_for_dependency: the executed status is set before the
+        // dependents are taken in wake_dependencies_after_execution, so holding the lock while
+        // checking the status ensures the transaction is not left deferred.
+        let mut stored_deps = self.hinted_dependents[dep_txn_idx as usize].lock();
+        if self.is_executed(dep_txn_idx, true).is_some() || self.done() {
+            return false;
+        }
+        stored_deps.push(txn_idx);
+        true
+    }
+
     /// Put a transaction in a suspended state, with a condition variable that can be
     /// used to wake it up after the dependency is resolved.
     /// Return true when the txn is successfully suspended.
@@ -907,7 +1753,7 @@ impl Scheduler {
     fn suspend(
         &self,
         txn_idx: TxnIndex,
-        dep_condvar: DependencyCondvar,
+        dep_condvar: Option<DependencyCondvar>,
     ) -> Result<bool, PanicError> {
         let mut status = self.txn_status[txn_idx as usize].0.write();
         match *status {
@@ -928,7 +1774,12 @@ impl Scheduler {
     fn resume(&self, txn_idx: TxnIndex) -> Result<(), PanicError> {
         let mut status = self.txn_status[txn_idx as usize].0.write();
         match &*status {
-            ExecutionStatus::Suspended(incarnation, dep_condvar) => {
+            ExecutionStatus::Suspended(incarnation, None) => {
+                // The requeued execution was stopped, so the incarnation starts over.
+                *status = ExecutionStatus::Ready(*incarnation, ExecutionTaskType::Execution);
+                Ok(())
+            },
+            ExecutionStatus::Suspended(incarnation, Some(dep_condvar)) => {
                 *status = ExecutionStatus::Ready(
                     *incarnation,
                     ExecutionTaskType::Wakeup(dep_condvar.clone()),
@@ -969,21 +1820,28 @@ impl Scheduler {
     }
 
     /// After a successful abort, mark the transaction as ready for re-execution with
-    /// an incremented incarnation number.
+    /// an incremented incarnation number, or as deferred if it reached the maximum number of
+    /// incarnations (in which case true is returned).
     fn set_aborted_status(
         &self,
         txn_idx: TxnIndex,
         incarnation: Incarnation,
-    ) -> Result<(), PanicError> {
+    ) -> Result<bool, PanicError> {
         let mut status = self.txn_status[txn_idx as usize].0.write();
         match *status {
             ExecutionStatus::Aborting(stored_incarnation) if stored_incarnation == incarnation => {
+                if self.max_incarnations.map_or(false, |max_incarnations| {
+                    incarnation + 1 >= max_incarnations
+                }) {
+                    *status = ExecutionStatus::Deferred(incarnation + 1);
+                    return Ok(true);
+                }
                 *status = ExecutionStatus::Ready(incarnation + 1, ExecutionTaskType::Execution);
-                Ok(())
+                Ok(false)
             },
             ExecutionStatus::ExecutionHalted => {
                 // The execution is already halted.
-                Ok(())
+                Ok(false)
             },
             _ => Err(code_invariant_error(format!(
                 "Expected Aborting incarnation {incarnation}, got {:?}",
@@ -993,9 +1851,28 @@ impl Scheduler {
     }
 
     /// Checks whether the done marker is set. The marker can only be set by 'try_commit'.
-    fn done(&self) -> bool {
+    pub(crate) fn done(&self) -> bool {
         self.done_marker.load(Ordering::Acquire)
     }
+
+    pub(crate) fn num_waiting_workers(&self) -> usize {
+        self.num_waiting_workers.load(Ordering::SeqCst)
+    }
+
+    /// Must be called when handling a wake-up task, before the suspended worker is notified.
+    pub(crate) fn finish_dependency_wait(&self) {
+        self.num_waiting_workers.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    pub(crate) fn num_dependency_waits(&self) -> usize {
+        self.num_dependency_waits.load(Ordering::Relaxed)
+    }
+
+    /// The number of validation waves so far, i.e. the initial wave, and one per decrease of
+    /// the validation index.
+    pub(crate) fn num_validation_waves(&self) -> Wave {
+        Self::unpack_validation_idx(self.validation_idx.load(Ordering::Acquire)).1 + 1
+    }
 }
 
 #[cfg(test)]
@@ -47,7 +47,7 @@ pub struct Accesses<K> {
 
 /// Trait for single threaded transaction executor.
 // TODO: Sync should not be required. Sync is only introduced because this trait occurs as a phantom type of executor struct.
-pub trait ExecutorTask: Sync {
+pub trait ExecutorTask: Send + Sync {
     /// Type of transaction and its associated key and value.
     type Txn: Transaction;
 
@@ -64,6 +64,13 @@ pub trait ExecutorTask: Sync {
     /// Create an instance of the transaction executor.
     fn init(args: Self::Argument) -> Self;
 
+    /// Identifies the arguments for reusing the executor instances across blocks: instances
+    /// initialized with arguments of the same fingerprint are interchangeable. None if the
+    /// instances must not be reused, e.g. if they refer to the state view of the block.
+    fn argument_fingerprint(_args: &Self::Argument) -> Option<u64> {
+        None
+    }
+
     /// Execute a single transaction given the view of the current state.
     fn execute_transaction(
         &self,
@@ -204,4 +211,20 @@ pub trait TransactionOutput: Send + Sync + Debug {
             <Self::Txn as Transaction>::Identifier,
         >,
     >;
+
+    /// Describes the first difference (in the write set, events or gas) between the committed
+    /// outputs, i.e. after materialization, of the same transaction in two executions of the
+    /// block, if they differ. Used by differential execution to compare the outputs of parallel
+    /// (self) and sequential (other) execution. By default, the outputs are not compared.
+    fn committed_output_divergence(&self, _other: &Self) -> Option<String> {
+        None
+    }
+
+    /// The keys written by the committed output of the transaction, i.e. after materialization
+    /// (including the aggregator v1 and resource group writes), or no keys if the output was not
+    /// committed. Used to invalidate the values of the hot-state cache shared across blocks,
+    /// which is cleared if the keys are not known (None, the default).
+    fn committed_write_keys(&self) -> Option<Vec<<Self::Txn as Transaction>::Key>> {
+        None
+    }
 }
@@ -1,17 +1,117 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::task::TransactionOutput;
+use crate::{
+    counters::{
+        COMMIT_HOOK_BACKPRESSURE_SECONDS, COMMIT_HOOK_BUFFERED_EVENTS,
+        COMMIT_HOOK_BUFFER_FULL_COUNT,
+    },
+    limit_processor::BlockLimitSnapshot,
+    task::TransactionOutput,
+};
+use aptos_aggregator::types::code_invariant_error;
+use aptos_infallible::Mutex;
+use aptos_logger::error;
 use aptos_mvhashmap::types::TxnIndex;
+use aptos_types::delayed_fields::PanicError;
+use crossbeam::channel::{bounded, unbounded, Receiver, Sender, TrySendError};
+use std::{collections::BTreeMap, sync::Arc, thread::JoinHandle};
+
+/// What the executor does when a commit hook fails to process a committed transaction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommitHookErrorPolicy {
+    /// Fail the block execution with a fatal block executor error.
+    Fail,
+    /// Log the error and continue executing the block.
+    LogAndContinue,
+}
+
+/// Whether the block executor commits a transaction whose output was checked by the commit hook.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommitDecision {
+    Commit,
+    /// Commit the transaction, but skip the rest of the block, the same as for a SkipRest output
+    /// (e.g. once the block gas limit is reached).
+    HaltBlock,
+}
 
 /// An interface for listening to transaction commit events. The listener is called only once
 /// for each transaction commit.
 pub trait TransactionCommitHook: Send + Sync {
     type Output;
 
-    fn on_transaction_committed(&self, txn_idx: TxnIndex, output: &Self::Output);
+    fn on_transaction_committed(
+        &self,
+        txn_idx: TxnIndex,
+        output: &Self::Output,
+    ) -> anyhow::Result<()>;
 
     fn on_execution_aborted(&self, txn_idx: TxnIndex);
+
+    fn error_policy(&self) -> CommitHookErrorPolicy {
+        CommitHookErrorPolicy::Fail
+    }
+
+    /// Called by the commit pipeline before the transaction is committed, e.g. to halt the block
+    /// when the output trips an emergency circuit breaker. Unlike on_transaction_committed, the
+    /// output may not be materialized yet (e.g. delayed fields may not be exchanged with their
+    /// values during parallel execution). Not called for the last transaction of the block.
+    fn should_commit(&self, _txn_idx: TxnIndex, _output: &Self::Output) -> CommitDecision {
+        CommitDecision::Commit
+    }
+
+    /// Called during parallel execution with early release enabled, ahead of the commit of the
+    /// transaction (possibly out of order, and at most once per transaction), if its read/write
+    /// summary is disjoint from those of all the earlier transactions not committed yet, and
+    /// its output needs no materialization (e.g. it does not access delayed fields). The output
+    /// is still passed to on_transaction_committed once the transaction is committed, in order.
+    fn on_transaction_released(
+        &self,
+        _txn_idx: TxnIndex,
+        _output: &Self::Output,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called if the released output of the transaction is not committed after all, as an
+    /// earlier transaction was re-executed with a conflicting write (the transaction is then
+    /// committed with another output) or ended the block (the transaction is not committed).
+    fn on_release_revoked(&self, _txn_idx: TxnIndex) {}
+
+    /// Called by the commit pipeline when a transaction is committed (ahead of
+    /// on_transaction_committed, as the output is materialized after the commit), with the
+    /// running totals of the block limits including the transaction, e.g. to apply backpressure
+    /// or to shrink the next proposal in real time. Only called for the transactions whose
+    /// fee statement is accumulated towards the block limits.
+    fn on_block_limit_snapshot(&self, _txn_idx: TxnIndex, _snapshot: &BlockLimitSnapshot) {}
+
+    /// Called when the executor starts executing a block, before any of its transactions is
+    /// committed. Called again if the block is executed again, e.g. when parallel execution
+    /// fails and the block falls back to sequential execution (or is discarded), in which case
+    /// the transactions committed by the failed execution are not part of the block output.
+    fn on_block_execution_started(&self) {}
+}
+
+/// Applies the error policy of the hook to the result of processing a committed transaction.
+pub(crate) fn handle_commit_hook_result<H: TransactionCommitHook>(
+    hook: &H,
+    txn_idx: TxnIndex,
+    result: anyhow::Result<()>,
+) -> Result<(), PanicError> {
+    match (result, hook.error_policy()) {
+        (Ok(()), _) => Ok(()),
+        (Err(err), CommitHookErrorPolicy::Fail) => Err(code_invariant_error(format!(
+            "Transaction commit hook failed at txn {}: {:?}",
+            txn_idx, err
+        ))),
+        (Err(err), CommitHookErrorPolicy::LogAndContinue) => {
+            error!(
+                "Transaction commit hook failed at txn {}, continuing: {:?}",
+                txn_idx, err
+            );
+            Ok(())
+        },
+    }
 }
 
 pub struct NoOpTransactionCommitHook<T, E> {
@@ -37,11 +137,365 @@ impl<T: TransactionOutput, E: Sync + Send> TransactionCommitHook
 {
     type Output = T;
 
-    fn on_transaction_committed(&self, _txn_idx: TxnIndex, _output: &Self::Output) {
+    fn on_transaction_committed(
+        &self,
+        _txn_idx: TxnIndex,
+        _output: &Self::Output,
+    ) -> anyhow::Result<()> {
         // no-op
+        Ok(())
     }
 
     fn on_execution_aborted(&self, _txn_idx: TxnIndex) {
         // no-op
     }
 }
+
+enum CommitEvent<O> {
+    Committed(TxnIndex, O),
+    Aborted(TxnIndex),
+    Released(TxnIndex, O),
+    Revoked(TxnIndex),
+    LimitSnapshot(TxnIndex, BlockLimitSnapshot),
+    BlockExecutionStarted,
+}
+
+/// Wraps a (synchronous) commit hook, so that it runs on a dedicated thread instead of inside
+/// the commit pipeline: committed outputs are cloned onto a bounded buffer, and a slow listener
+/// only stalls the commit pipeline once the buffer is full. Events are delivered to the listener
+/// in order, and all of them have been delivered once the wrapper is finished or dropped.
+///
+/// As the listener runs asynchronously, an error it returns is reported by the wrapper on the
+/// next committed transaction (with the error policy of the listener), and the listener cannot
+/// halt the block (its should_commit is not called).
+pub struct BufferedTransactionCommitHook<H: TransactionCommitHook> {
+    sender: Option<Sender<CommitEvent<H::Output>>>,
+    handle: Option<JoinHandle<H>>,
+    error_policy: CommitHookErrorPolicy,
+    first_error: Arc<Mutex<Option<String>>>,
+}
+
+impl<H> BufferedTransactionCommitHook<H>
+where
+    H: TransactionCommitHook + 'static,
+    H::Output: Clone + Send,
+{
+    pub fn new(listener: H, buffer_size: usize) -> Self {
+        let (sender, receiver) = bounded::<CommitEvent<H::Output>>(buffer_size);
+        let error_policy = listener.error_policy();
+        let first_error = Arc::new(Mutex::new(None));
+        let thread_first_error = first_error.clone();
+        let handle = std::thread::Builder::new()
+            .name("txn-commit-hook".to_string())
+            .spawn(move || {
+                for event in receiver {
+                    COMMIT_HOOK_BUFFERED_EVENTS.dec();
+                    match event {
+                        CommitEvent::Committed(txn_idx, output) => {
+                            let result = listener.on_transaction_committed(txn_idx, &output);
+                            if let Err(err) = handle_commit_hook_result(&listener, txn_idx, result)
+                            {
+                                thread_first_error
+                                    .lock()
+                                    .get_or_insert_with(|| format!("{:?}", err));
+                            }
+                        },
+                        CommitEvent::Aborted(txn_idx) => listener.on_execution_aborted(txn_idx),
+                        CommitEvent::Released(txn_idx, output) => {
+                            let result = listener.on_transaction_released(txn_idx, &output);
+                            if let Err(err) = handle_commit_hook_result(&listener, txn_idx, result)
+                            {
+                                thread_first_error
+                                    .lock()
+                                    .get_or_insert_with(|| format!("{:?}", err));
+                            }
+                        },
+                        CommitEvent::Revoked(txn_idx) => listener.on_release_revoked(txn_idx),
+                        CommitEvent::LimitSnapshot(txn_idx, snapshot) => {
+                            listener.on_block_limit_snapshot(txn_idx, &snapshot)
+                        },
+                        CommitEvent::BlockExecutionStarted => listener.on_block_execution_started(),
+                    }
+                }
+                listener
+            })
+            .expect("Failed to spawn the commit hook thread");
+        Self {
+            sender: Some(sender),
+            handle: Some(handle),
+            error_policy,
+            first_error,
+        }
+    }
+
+    /// Waits until all the buffered events are delivered, and returns the listener.
+    pub fn finish(mut self) -> H {
+        self.join().expect("The commit hook thread must exist")
+    }
+
+    fn join(&mut self) -> Option<H> {
+        // Dropping the sender ends the listener loop once the buffer is drained.
+        self.sender.take();
+        self.handle
+            .take()
+            .map(|handle| handle.join().expect("The commit hook thread panicked"))
+    }
+
+    fn send(&self, event: CommitEvent<H::Output>) {
+        let sender = self
+            .sender
+            .as_ref()
+            .expect("Commit hook must not be used after being finished");
+        COMMIT_HOOK_BUFFERED_EVENTS.inc();
+        if let Err(TrySendError::Full(event)) = sender.try_send(event) {
+            COMMIT_HOOK_BUFFER_FULL_COUNT.inc();
+            let _timer = COMMIT_HOOK_BACKPRESSURE_SECONDS.start_timer();
+            sender
+                .send(event)
+                .expect("The commit hook thread must be alive");
+        }
+    }
+}
+
+impl<H> TransactionCommitHook for BufferedTransactionCommitHook<H>
+where
+    H: TransactionCommitHook + 'static,
+    H::Output: Clone + Send,
+{
+    type Output = H::Output;
+
+    fn on_transaction_committed(
+        &self,
+        txn_idx: TxnIndex,
+        output: &Self::Output,
+    ) -> anyhow::Result<()> {
+        // Errors with the log and continue policy were already logged by the listener thread.
+        if let Some(err) = self.first_error.lock().as_ref() {
+            anyhow::bail!("Buffered commit hook failed earlier: {}", err);
+        }
+        self.send(CommitEvent::Committed(txn_idx, output.clone()));
+        Ok(())
+    }
+
+    fn on_execution_aborted(&self, txn_idx: TxnIndex) {
+        self.send(CommitEvent::Aborted(txn_idx));
+    }
+
+    fn on_transaction_released(
+        &self,
+        txn_idx: TxnIndex,
+        output: &Self::Output,
+    ) -> anyhow::Result<()> {
+        if let Some(err) = self.first_error.lock().as_ref() {
+            anyhow::bail!("Buffered commit hook failed earlier: {}", err);
+        }
+        self.send(CommitEvent::Released(txn_idx, output.clone()));
+        Ok(())
+    }
+
+    fn on_release_revoked(&self, txn_idx: TxnIndex) {
+        self.send(CommitEvent::Revoked(txn_idx));
+    }
+
+    fn on_block_limit_snapshot(&self, txn_idx: TxnIndex, snapshot: &BlockLimitSnapshot) {
+        self.send(CommitEvent::LimitSnapshot(txn_idx, *snapshot));
+    }
+
+    fn on_block_execution_started(&self) {
+        self.send(CommitEvent::BlockExecutionStarted);
+    }
+
+    fn error_policy(&self) -> CommitHookErrorPolicy {
+        self.error_policy
+    }
+}
+
+impl<H: TransactionCommitHook> Drop for BufferedTransactionCommitHook<H> {
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            // Don't panic while dropping, the listener thread reports its own panic.
+            let _ = handle.join();
+        }
+    }
+}
+
+/// An item of the stream of committed outputs (see [`CommittedOutputStream`]).
+#[derive(Debug, Eq, PartialEq)]
+pub enum StreamedOutput<O> {
+    /// The executor started executing a block. If the block was already being executed (i.e.
+    /// the block executor did not return yet), the items streamed since the previous start are
+    /// not part of the block output and must be discarded.
+    Started,
+    /// The materialized output of a committed transaction.
+    Committed(TxnIndex, O),
+    /// A committed transaction whose execution was aborted.
+    Aborted(TxnIndex),
+}
+
+struct OrderedOutputs<O> {
+    next_idx: TxnIndex,
+    // The transactions materialized ahead of the next transaction to stream.
+    pending: BTreeMap<TxnIndex, Option<O>>,
+}
+
+/// A commit hook that streams the materialized outputs of the committed transactions as soon as
+/// they are materialized, in order of their indices (during parallel execution, the committed
+/// transactions can be materialized out of order by different threads), e.g. so that downstream
+/// stages can start processing the outputs while the tail of the block is still executing.
+pub struct CommittedOutputStream<O> {
+    outputs: Mutex<OrderedOutputs<O>>,
+    sender: Sender<StreamedOutput<O>>,
+}
+
+impl<O: Clone + Send> CommittedOutputStream<O> {
+    /// Returns the hook and the receiving end of the stream, which ends once the hook is dropped.
+    pub fn new() -> (Self, Receiver<StreamedOutput<O>>) {
+        let (sender, receiver) = unbounded();
+        let stream = Self {
+            outputs: Mutex::new(OrderedOutputs {
+                next_idx: 0,
+                pending: BTreeMap::new(),
+            }),
+            sender,
+        };
+        (stream, receiver)
+    }
+
+    fn push(&self, txn_idx: TxnIndex, output: Option<O>) {
+        let mut outputs = self.outputs.lock();
+        outputs.pending.insert(txn_idx, output);
+        loop {
+            let next_idx = outputs.next_idx;
+            let Some(output) = outputs.pending.remove(&next_idx) else {
+                break;
+            };
+            // The receiver may be dropped if the caller is no longer interested in the stream.
+            let _ = self.sender.send(match output {
+                Some(output) => StreamedOutput::Committed(next_idx, output),
+                None => StreamedOutput::Aborted(next_idx),
+            });
+            outputs.next_idx += 1;
+        }
+    }
+}
+
+impl<O: Clone + Send> TransactionCommitHook for CommittedOutputStream<O> {
+    type Output = O;
+
+    fn on_transaction_committed(
+        &self,
+        txn_idx: TxnIndex,
+        output: &Self::Output,
+    ) -> anyhow::Result<()> {
+        self.push(txn_idx, Some(output.clone()));
+        Ok(())
+    }
+
+    fn on_execution_aborted(&self, txn_idx: TxnIndex) {
+        self.push(txn_idx, None);
+    }
+
+    fn on_block_execution_started(&self) {
+        let mut outputs = self.outputs.lock();
+        outputs.next_idx = 0;
+        outputs.pending.clear();
+        let _ = self.sender.send(StreamedOutput::Started);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_infallible::Mutex;
+    use std::{thread, time::Duration};
+
+    #[derive(Default)]
+    struct SlowRecordingHook {
+        events: Mutex<Vec<(TxnIndex, Option<u64>)>>,
+    }
+
+    impl TransactionCommitHook for SlowRecordingHook {
+        type Output = u64;
+
+        fn on_transaction_committed(&self, txn_idx: TxnIndex, output: &u64) -> anyhow::Result<()> {
+            thread::sleep(Duration::from_millis(1));
+            self.events.lock().push((txn_idx, Some(*output)));
+            Ok(())
+        }
+
+        fn on_execution_aborted(&self, txn_idx: TxnIndex) {
+            self.events.lock().push((txn_idx, None));
+        }
+    }
+
+    #[test]
+    fn test_buffered_commit_hook_delivers_in_order() {
+        let hook = BufferedTransactionCommitHook::new(SlowRecordingHook::default(), 2);
+        for txn_idx in 0..10 {
+            hook.on_transaction_committed(txn_idx, &(txn_idx as u64 * 10))
+                .unwrap();
+        }
+        hook.on_execution_aborted(10);
+
+        let events = hook.finish().events.lock().clone();
+        let mut expected: Vec<_> = (0..10).map(|i| (i, Some(i as u64 * 10))).collect();
+        expected.push((10, None));
+        assert_eq!(events, expected);
+    }
+
+    struct FailingHook(CommitHookErrorPolicy);
+
+    impl TransactionCommitHook for FailingHook {
+        type Output = u64;
+
+        fn on_transaction_committed(
+            &self,
+            _txn_idx: TxnIndex,
+            _output: &u64,
+        ) -> anyhow::Result<()> {
+            anyhow::bail!("listener failure")
+        }
+
+        fn on_execution_aborted(&self, _txn_idx: TxnIndex) {}
+
+        fn error_policy(&self) -> CommitHookErrorPolicy {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_committed_output_stream_is_ordered() {
+        let (stream, receiver) = CommittedOutputStream::<u64>::new();
+        stream.on_block_execution_started();
+        stream.on_transaction_committed(1, &10).unwrap();
+        stream.on_transaction_committed(0, &0).unwrap();
+        stream.on_transaction_committed(3, &30).unwrap();
+        // The failed execution of the block is restarted, the pending outputs are dropped.
+        stream.on_block_execution_started();
+        stream.on_transaction_committed(0, &0).unwrap();
+        stream.on_transaction_committed(2, &20).unwrap();
+        stream.on_execution_aborted(1);
+        drop(stream);
+
+        let items: Vec<_> = receiver.into_iter().collect();
+        assert_eq!(items, vec![
+            StreamedOutput::Started,
+            StreamedOutput::Committed(0, 0),
+            StreamedOutput::Committed(1, 10),
+            StreamedOutput::Started,
+            StreamedOutput::Committed(0, 0),
+            StreamedOutput::Aborted(1),
+            StreamedOutput::Committed(2, 20),
+        ]);
+    }
+
+    #[test]
+    fn test_handle_commit_hook_result() {
+        let hook = FailingHook(CommitHookErrorPolicy::Fail);
+        assert!(handle_commit_hook_result(&hook, 3, hook.on_transaction_committed(3, &0)).is_err());
+
+        let hook = FailingHook(CommitHookErrorPolicy::LogAndContinue);
+        assert!(handle_commit_hook_result(&hook, 3, hook.on_transaction_committed(3, &0)).is_ok());
+    }
+}
@@ -12,19 +12,24 @@ use aptos_aggregator::types::code_invariant_error;
 use aptos_logger::error;
 use aptos_mvhashmap::types::{TxnIndex, ValueWithLayout};
 use aptos_types::{
-    delayed_fields::PanicError, fee_statement::FeeStatement,
-    state_store::state_value::StateValueMetadata,
-    transaction::BlockExecutableTransaction as Transaction, write_set::WriteOp,
+    delayed_fields::PanicError,
+    fee_statement::FeeStatement,
+    state_store::{state_read_stats::StateReadStats, state_value::StateValueMetadata},
+    transaction::{BlockExecutableTransaction as Transaction, SkipReason},
+    write_set::WriteOp,
 };
 use arc_swap::ArcSwapOption;
 use crossbeam::utils::CachePadded;
 use dashmap::DashSet;
-use move_core_types::value::MoveTypeLayout;
+use move_core_types::{value::MoveTypeLayout, vm_status::StatusCode};
 use std::{
     collections::{BTreeMap, HashSet},
     fmt::Debug,
     iter::{empty, Iterator},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 type TxnInput<T> = CapturedReads<T>;
@@ -49,6 +54,20 @@ pub(crate) enum KeyKind {
     Group,
 }
 
+/// The reason why [`TxnLastInputOutput::record`] rejected the recording of an incarnation.
+#[derive(Debug)]
+pub(crate) enum RecordError<K> {
+    /// The modules (read or written by the incarnation) that are both read and written in the
+    /// block. This is expected, and handled by falling back to sequential execution.
+    ModuleReadWrite(Vec<K>),
+    /// The transaction was already committed (and its output materialized), so it must not be
+    /// executed again. This is a bug.
+    DoubleRecord(TxnIndex),
+    /// The transaction index is not in the block, whose number of transactions is given. This
+    /// is a bug.
+    ArityMismatch(TxnIndex, usize),
+}
+
 pub struct TxnLastInputOutput<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug> {
     inputs: Vec<CachePadded<ArcSwapOption<TxnInput<T>>>>, // txn_idx -> input.
     // Set once when the group outputs are committed sequentially, to be processed later by
@@ -73,6 +92,21 @@ pub struct TxnLastInputOutput<T: Transaction, O: TransactionOutput<Txn = T>, E:
     // Move-VM loader cache - see 'record' function comment for more information.
     module_writes: DashSet<T::Key>,
     module_reads: DashSet<T::Key>,
+    // If set, the module reads are validated (see CapturedReads::validate_module_reads), and
+    // a module read/write intersection does not require the fallback.
+    validate_module_reads: bool,
+
+    // Whether the last execution of the transaction exceeded the execution timeout (and is
+    // recorded with SpeculativeExecutionAbortError status).
+    timed_out: Vec<CachePadded<AtomicBool>>,
+    // Whether the output of the (committed) transaction was materialized.
+    materialized: Vec<CachePadded<AtomicBool>>,
+    // The state reads of the last execution of the transaction, if recorded.
+    read_stats: Vec<CachePadded<ExplicitSyncWrapper<Option<StateReadStats>>>>,
+
+    // The reason recorded when the output of a committed transaction was updated to SkipRest.
+    // Only the last committed transaction of the block can skip the rest.
+    skip_reason: ExplicitSyncWrapper<Option<(TxnIndex, SkipReason)>>,
 }
 
 impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
@@ -94,26 +128,48 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
                 .collect(),
             module_writes: DashSet::new(),
             module_reads: DashSet::new(),
+            validate_module_reads: false,
+            timed_out: (0..num_txns)
+                .map(|_| CachePadded::new(AtomicBool::new(false)))
+                .collect(),
+            materialized: (0..num_txns)
+                .map(|_| CachePadded::new(AtomicBool::new(false)))
+                .collect(),
+            read_stats: (0..num_txns)
+                .map(|_| CachePadded::new(ExplicitSyncWrapper::new(None)))
+                .collect(),
+            skip_reason: ExplicitSyncWrapper::new(None),
         }
     }
 
+    /// Records the module reads and writes without checking their intersection, as the module
+    /// reads are validated by the block executor.
+    pub fn with_module_read_validation(mut self, validate_module_reads: bool) -> Self {
+        self.validate_module_reads = validate_module_reads;
+        self
+    }
+
+    pub(crate) fn validates_module_reads(&self) -> bool {
+        self.validate_module_reads
+    }
+
     fn append_and_check<'a>(
         paths: impl Iterator<Item = &'a T::Key>,
         set_to_append: &DashSet<T::Key>,
         set_to_check: &DashSet<T::Key>,
-    ) -> bool {
+        conflicts: &mut Vec<T::Key>,
+    ) {
         for path in paths {
             // Standard flags, first show, then look.
             set_to_append.insert(path.clone());
 
-            if set_to_check.contains(path) {
-                return true;
+            if set_to_check.contains(path) && !conflicts.contains(path) {
+                conflicts.push(path.clone());
             }
         }
-        false
     }
 
-    /// Returns false on an error - if a module path that was read was previously written to, and vice versa.
+    /// Returns a ModuleReadWrite error if a module path that was read was previously written to, and vice versa.
     /// Since parallel executor is instantiated per block, any module that is in the Move-VM loader
     /// cache must previously be read and would be recorded in the 'module_reads' set. Any module
     /// that is written (published or re-published) goes through transaction output write-set and
@@ -125,13 +181,28 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
     /// error that ensures a fallback to a correct sequential execution.
     /// When the sets do not have an intersection, it is impossible for the race to occur as any
     /// module in the loader cache may not be published by a transaction in the ongoing block.
+    /// If the module reads are validated, the paths are not checked (the executor tasks must
+    /// not cache modules across their versions).
+    /// The status of an execution that exceeded the execution timeout must be recorded as
+    /// SpeculativeExecutionAbortError, see [`TxnLastInputOutput::discard_timed_out_output`].
+    /// The input and output of the previous incarnation (if any) are freed when replaced, unless
+    /// they are still referenced (e.g. by an ongoing validation).
+    /// The other errors are invariant violations, see [`RecordError`].
     pub(crate) fn record(
         &self,
         txn_idx: TxnIndex,
         input: CapturedReads<T>,
         output: ExecutionStatus<O, E>,
         arced_resource_writes: Vec<(T::Key, Arc<T::Value>, Option<Arc<MoveTypeLayout>>)>,
-    ) -> bool {
+        timed_out: bool,
+    ) -> Result<(), RecordError<T::Key>> {
+        if txn_idx as usize >= self.outputs.len() {
+            return Err(RecordError::ArityMismatch(txn_idx, self.outputs.len()));
+        }
+        if self.materialized[txn_idx as usize].load(Ordering::Relaxed) {
+            return Err(RecordError::DoubleRecord(txn_idx));
+        }
+
         let written_modules = match &output {
             ExecutionStatus::Success(output) | ExecutionStatus::SkipRest(output) => {
                 output.module_write_set()
@@ -141,27 +212,48 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
             | ExecutionStatus::DelayedFieldsCodeInvariantError(_) => BTreeMap::new(),
         };
 
-        if self
-            .check_and_append_module_rw_conflict(input.module_reads.iter(), written_modules.keys())
-        {
-            return false;
+        if !self.validate_module_reads {
+            let conflicting_modules = self.check_and_append_module_rw_conflict(
+                input.module_read_keys(),
+                written_modules.keys(),
+            );
+            if !conflicting_modules.is_empty() {
+                return Err(RecordError::ModuleReadWrite(conflicting_modules));
+            }
         }
 
         *self.arced_resource_writes[txn_idx as usize].acquire() = arced_resource_writes;
+        self.timed_out[txn_idx as usize].store(timed_out, Ordering::Relaxed);
         self.inputs[txn_idx as usize].store(Some(Arc::new(input)));
         self.outputs[txn_idx as usize].store(Some(Arc::new(output)));
 
-        true
+        Ok(())
     }
 
+    /// Records the modules read and written by a transaction, and returns the modules among them
+    /// that are both read and written in the block (including by the transaction itself), i.e.
+    /// the modules of the transaction with a read/write conflict. A module that conflicted for a
+    /// previous transaction is returned again for every later transaction that touches it.
     pub(crate) fn check_and_append_module_rw_conflict<'a>(
         &self,
         module_reads_keys: impl Iterator<Item = &'a T::Key>,
         module_writes_keys: impl Iterator<Item = &'a T::Key>,
-    ) -> bool {
+    ) -> Vec<T::Key> {
         // Check if adding new read & write modules leads to intersections.
-        Self::append_and_check(module_reads_keys, &self.module_reads, &self.module_writes)
-            || Self::append_and_check(module_writes_keys, &self.module_writes, &self.module_reads)
+        let mut conflicts = Vec::new();
+        Self::append_and_check(
+            module_reads_keys,
+            &self.module_reads,
+            &self.module_writes,
+            &mut conflicts,
+        );
+        Self::append_and_check(
+            module_writes_keys,
+            &self.module_writes,
+            &self.module_reads,
+            &mut conflicts,
+        );
+        conflicts
     }
 
     pub(crate) fn read_set(&self, txn_idx: TxnIndex) -> Option<Arc<CapturedReads<T>>> {
@@ -216,7 +308,10 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
                     "FatalVMError from parallel execution {:?} at txn {}",
                     err, txn_idx
                 );
-                return Err(ParallelBlockExecutionError::FatalVMError);
+                return Err(ParallelBlockExecutionError::FatalVMError {
+                    txn_idx,
+                    message: format!("{:?}", err),
+                });
             }
         }
         Ok(())
@@ -250,9 +345,49 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
         }
     }
 
-    pub(crate) fn update_to_skip_rest(&self, txn_idx: TxnIndex) {
+    /// Unlike other speculative errors, an execution that timed out does not fail validation
+    /// by itself. Hence, if its read-set is still valid at commit time, the transaction is
+    /// committed as discarded (instead of being re-executed, which would likely time out again).
+    /// Must be called during commit, before check_execution_status_during_commit. Returns true
+    /// if the output was discarded.
+    pub(crate) fn discard_timed_out_output(&self, txn_idx: TxnIndex) -> Result<bool, PanicError> {
+        if !self.timed_out[txn_idx as usize].load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+
+        // No execution is concurrent with the commit of the transaction.
+        if !matches!(
+            self.txn_output(txn_idx).as_deref(),
+            Some(ExecutionStatus::SpeculativeExecutionAbortError(_))
+        ) {
+            return Err(code_invariant_error(format!(
+                "Timed out execution of txn {} must be recorded as speculative error",
+                txn_idx
+            )));
+        }
+        self.outputs[txn_idx as usize].store(Some(Arc::new(ExecutionStatus::Success(
+            O::discard_output(StatusCode::EXECUTION_LIMIT_REACHED),
+        ))));
+        Ok(true)
+    }
+
+    /// Replaces the output of the transaction with a discard output, which has no writes (e.g. if
+    /// its materialized output exceeds the per-transaction output limit). Must be called during
+    /// the commit of the transaction, once its writes are removed from the multi-versioned
+    /// data-structure.
+    pub(crate) fn discard_output_during_commit(&self, txn_idx: TxnIndex, discard_code: StatusCode) {
+        self.arced_resource_writes[txn_idx as usize]
+            .acquire()
+            .clear();
+        self.outputs[txn_idx as usize].store(Some(Arc::new(ExecutionStatus::Success(
+            O::discard_output(discard_code),
+        ))));
+    }
+
+    /// Must be called during the commit of the transaction at txn_idx.
+    pub(crate) fn update_to_skip_rest(&self, txn_idx: TxnIndex, reason: SkipReason) {
         if self.block_skips_rest_at_idx(txn_idx) {
-            // Already skipping.
+            // Already skipping (e.g. the transaction asked to, or a block limit was reached).
             return;
         }
 
@@ -260,11 +395,33 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
         // Hence, since the status is not SkipRest, it must be Success.
         if let ExecutionStatus::Success(output) = self.take_output(txn_idx) {
             self.outputs[txn_idx as usize].store(Some(Arc::new(ExecutionStatus::SkipRest(output))));
+            *self.skip_reason.acquire().dereference_mut() = Some((txn_idx, reason));
         } else {
             unreachable!("Unexpected status, must be Success");
         }
     }
 
+    /// Records the state reads of an execution of the transaction, before its output is recorded.
+    pub(crate) fn record_read_stats(&self, txn_idx: TxnIndex, read_stats: StateReadStats) {
+        *self.read_stats[txn_idx as usize].acquire() = Some(read_stats);
+    }
+
+    /// Returns the state reads of the last execution of the transaction, if recorded.
+    pub(crate) fn read_stats(&self, txn_idx: TxnIndex) -> Option<StateReadStats> {
+        *self.read_stats[txn_idx as usize].acquire().dereference()
+    }
+
+    /// Why the rest of the block is skipped after the committed transaction at txn_idx, if it
+    /// is. Transactions with SkipRest status that were not updated to it skip the rest themselves.
+    pub(crate) fn skip_reason(&self, txn_idx: TxnIndex) -> Option<SkipReason> {
+        self.block_skips_rest_at_idx(txn_idx).then(|| {
+            match *self.skip_reason.acquire().dereference() {
+                Some((idx, reason)) if idx == txn_idx => reason,
+                _ => SkipReason::TransactionSkipRest,
+            }
+        })
+    }
+
     pub(crate) fn txn_output(&self, txn_idx: TxnIndex) -> Option<Arc<ExecutionStatus<O, E>>> {
         self.outputs[txn_idx as usize].load_full()
     }
@@ -444,14 +601,144 @@ impl<T: Transaction, O: TransactionOutput<Txn = T>, E: Debug + Send + Clone>
         }
     }
 
-    // Must be executed after parallel execution is done, grabs outputs. Will panic if
-    // other outstanding references to the recorded outputs exist.
-    pub(crate) fn take_output(&self, txn_idx: TxnIndex) -> ExecutionStatus<O, E> {
-        let owning_ptr = self.outputs[txn_idx as usize]
-            .swap(None)
-            .expect("[BlockSTM]: Output must be recorded after execution");
+    // Called once the output of a committed transaction is materialized: the read set is not
+    // validated anymore, so it is freed right away rather than with the rest of the block.
+    pub(crate) fn release_read_set(&self, txn_idx: TxnIndex) {
+        self.materialized[txn_idx as usize].store(true, Ordering::Relaxed);
+        self.inputs[txn_idx as usize].store(None);
+    }
+
+    // Must be executed after parallel execution is done, grabs the outputs of the committed
+    // transactions in order, taking each output only when the stream reaches it (the output of
+    // an aborted transaction is a skip output). Will panic if other outstanding references to
+    // the recorded outputs exist.
+    pub(crate) fn take_committed_outputs(
+        &self,
+        num_committed_txns: TxnIndex,
+    ) -> impl Iterator<Item = O> + '_ {
+        self.outputs[..num_committed_txns as usize]
+            .iter()
+            .map(|output| {
+                let owning_ptr = output
+                    .swap(None)
+                    .expect("[BlockSTM]: Output must be recorded after execution");
+                match Arc::try_unwrap(owning_ptr)
+                    .expect("[BlockSTM]: Output should be uniquely owned after execution")
+                {
+                    ExecutionStatus::Success(t) | ExecutionStatus::SkipRest(t) => t,
+                    ExecutionStatus::Abort(_) => O::skip_output(),
+                    ExecutionStatus::SpeculativeExecutionAbortError(msg)
+                    | ExecutionStatus::DelayedFieldsCodeInvariantError(msg) => {
+                        panic!("Cannot be materializing with {}", msg);
+                    },
+                }
+            })
+    }
+}
 
-        Arc::try_unwrap(owning_ptr)
-            .expect("[BlockSTM]: Output should be uniquely owned after execution")
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        captured_reads::ModuleRead,
+        proptest_types::types::{KeyType, MockEvent, MockOutput, MockTransaction, ValueType},
+    };
+
+    type TestOutput = MockOutput<KeyType<u32>, MockEvent>;
+
+    #[test]
+    fn test_record_errors() {
+        let last_input_output =
+            TxnLastInputOutput::<MockTransaction<KeyType<u32>, MockEvent>, TestOutput, usize>::new(
+                2,
+            );
+        let module_key = KeyType(1, true);
+        let publish_output = TestOutput {
+            writes: vec![(module_key.clone(), ValueType::from_value(vec![1], true))],
+            skipped: false,
+            ..TestOutput::skip_output()
+        };
+        assert!(last_input_output
+            .record(
+                0,
+                CapturedReads::new(),
+                ExecutionStatus::Success(publish_output),
+                vec![],
+                false
+            )
+            .is_ok());
+
+        let mut module_read = CapturedReads::new();
+        module_read.capture_module_read(module_key.clone(), ModuleRead::Storage);
+        assert!(matches!(
+            last_input_output.record(
+                1,
+                module_read,
+                ExecutionStatus::Success(TestOutput::skip_output()),
+                vec![],
+                false
+            ),
+            Err(RecordError::ModuleReadWrite(modules)) if modules == [module_key.clone()]
+        ));
+
+        assert!(matches!(
+            last_input_output.record(
+                2,
+                CapturedReads::new(),
+                ExecutionStatus::Success(TestOutput::skip_output()),
+                vec![],
+                false
+            ),
+            Err(RecordError::ArityMismatch(2, 2))
+        ));
+
+        last_input_output.release_read_set(0);
+        assert!(matches!(
+            last_input_output.record(
+                0,
+                CapturedReads::new(),
+                ExecutionStatus::Success(TestOutput::skip_output()),
+                vec![],
+                false
+            ),
+            Err(RecordError::DoubleRecord(0))
+        ));
+    }
+
+    #[test]
+    fn test_record_module_read_write_with_validation() {
+        let last_input_output =
+            TxnLastInputOutput::<MockTransaction<KeyType<u32>, MockEvent>, TestOutput, usize>::new(
+                2,
+            )
+            .with_module_read_validation(true);
+        let module_key = KeyType(1, true);
+        let publish_output = TestOutput {
+            writes: vec![(module_key.clone(), ValueType::from_value(vec![1], true))],
+            skipped: false,
+            ..TestOutput::skip_output()
+        };
+        assert!(last_input_output
+            .record(
+                0,
+                CapturedReads::new(),
+                ExecutionStatus::Success(publish_output),
+                vec![],
+                false
+            )
+            .is_ok());
+
+        // The read is validated by the executor instead.
+        let mut module_read = CapturedReads::new();
+        module_read.capture_module_read(module_key, ModuleRead::Storage);
+        assert!(last_input_output
+            .record(
+                1,
+                module_read,
+                ExecutionStatus::Success(TestOutput::skip_output()),
+                vec![],
+                false
+            )
+            .is_ok());
     }
 }
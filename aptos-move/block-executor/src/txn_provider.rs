@@ -0,0 +1,130 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_mvhashmap::types::TxnIndex;
+use std::sync::Arc;
+
+/// Provides the transactions of a block by their index, so that a block does not need to be
+/// copied into a contiguous slice to be executed, e.g. if its transactions are held in shared
+/// batches (see [`BatchedTxnProvider`]).
+pub trait TxnProvider<T>: Sync {
+    /// The number of transactions in the block.
+    fn num_txns(&self) -> usize;
+
+    /// The transaction at the given index, which must be lower than the number of transactions.
+    fn get_txn(&self, idx: TxnIndex) -> &T;
+}
+
+impl<'a, T> dyn TxnProvider<T> + 'a {
+    pub fn is_empty(&self) -> bool {
+        self.num_txns() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        (0..self.num_txns()).map(move |idx| self.get_txn(idx as TxnIndex))
+    }
+}
+
+impl<T: Sync> TxnProvider<T> for [T] {
+    fn num_txns(&self) -> usize {
+        self.len()
+    }
+
+    fn get_txn(&self, idx: TxnIndex) -> &T {
+        &self[idx as usize]
+    }
+}
+
+impl<T: Sync> TxnProvider<T> for Vec<T> {
+    fn num_txns(&self) -> usize {
+        self.len()
+    }
+
+    fn get_txn(&self, idx: TxnIndex) -> &T {
+        &self[idx as usize]
+    }
+}
+
+impl<T, P: TxnProvider<T> + ?Sized> TxnProvider<T> for &P {
+    fn num_txns(&self) -> usize {
+        (**self).num_txns()
+    }
+
+    fn get_txn(&self, idx: TxnIndex) -> &T {
+        (**self).get_txn(idx)
+    }
+}
+
+impl<T, P: TxnProvider<T> + Send + ?Sized> TxnProvider<T> for Arc<P> {
+    fn num_txns(&self) -> usize {
+        (**self).num_txns()
+    }
+
+    fn get_txn(&self, idx: TxnIndex) -> &T {
+        (**self).get_txn(idx)
+    }
+}
+
+/// A block made of consecutive batches of transactions, which are shared and not copied.
+pub struct BatchedTxnProvider<T> {
+    batches: Vec<Arc<Vec<T>>>,
+    // The index of the first transaction of each batch in the block.
+    batch_starts: Vec<usize>,
+    num_txns: usize,
+}
+
+impl<T> BatchedTxnProvider<T> {
+    pub fn new(batches: Vec<Arc<Vec<T>>>) -> Self {
+        let mut batch_starts = Vec::with_capacity(batches.len());
+        let mut num_txns = 0;
+        for batch in &batches {
+            batch_starts.push(num_txns);
+            num_txns += batch.len();
+        }
+        Self {
+            batches,
+            batch_starts,
+            num_txns,
+        }
+    }
+}
+
+impl<T: Send + Sync> TxnProvider<T> for BatchedTxnProvider<T> {
+    fn num_txns(&self) -> usize {
+        self.num_txns
+    }
+
+    fn get_txn(&self, idx: TxnIndex) -> &T {
+        let idx = idx as usize;
+        assert!(
+            idx < self.num_txns,
+            "Transaction index {} out of bounds for a block of {} transactions",
+            idx,
+            self.num_txns
+        );
+        // The last batch starting at or before the index (skipping the empty batches).
+        let batch = self.batch_starts.partition_point(|start| *start <= idx) - 1;
+        &self.batches[batch][idx - self.batch_starts[batch]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batched_txn_provider() {
+        let provider = BatchedTxnProvider::new(vec![
+            Arc::new(vec![0, 1]),
+            Arc::new(vec![]),
+            Arc::new(vec![2, 3, 4]),
+        ]);
+        let block: &dyn TxnProvider<u32> = &provider;
+        assert_eq!(block.num_txns(), 5);
+        assert_eq!(block.iter().copied().collect::<Vec<_>>(), vec![
+            0, 1, 2, 3, 4
+        ]);
+        assert_eq!(*block.get_txn(2), 2);
+        assert!(!block.is_empty());
+    }
+}
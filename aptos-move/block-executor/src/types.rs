@@ -1,10 +1,155 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use aptos_mvhashmap::types::TxnIndex;
 use aptos_types::transaction::BlockExecutableTransaction as Transaction;
-use std::{collections::HashSet, fmt};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::Hash,
+};
 
-#[derive(Eq, Hash, PartialEq, Debug)]
+/// Keys a transaction is expected to read and write, declared ahead of execution (e.g. by the
+/// sender of a block of p2p transfers between few accounts, who knows its conflict structure).
+/// Hints don't need to be exact: they only seed the parallel execution order, while correctness
+/// is still ensured by validation.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AccessHint<K> {
+    pub reads: Vec<K>,
+    pub writes: Vec<K>,
+}
+
+impl<K> AccessHint<K> {
+    pub fn new(reads: Vec<K>, writes: Vec<K>) -> Self {
+        Self { reads, writes }
+    }
+}
+
+/// The partitioning of a block that was pre-partitioned into conflict-disjoint chunks (e.g. by the
+/// sharded block partitioner). Transactions in different partitions must not conflict, while
+/// cross-partition transactions may conflict with any transaction. Each partition is executed in
+/// order by one worker at a time, and only the reads of cross-partition transactions (and of the
+/// transactions affected by their re-executions) are validated.
+///
+/// Unlike access hints, the partitioning is trusted: a partitioning with conflicts between
+/// partitions can lead to an incorrect output.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlockPartitioning {
+    partitions: Vec<Option<u32>>,
+    num_partitions: usize,
+}
+
+impl BlockPartitioning {
+    /// Takes the partition of each transaction of the block, or None for cross-partition
+    /// transactions. Partition ids don't need to be contiguous.
+    pub fn new<P: Hash + Eq>(partitions: Vec<Option<P>>) -> Self {
+        let mut dense_ids: HashMap<P, u32> = HashMap::new();
+        let partitions = partitions
+            .into_iter()
+            .map(|partition| {
+                partition.map(|partition| {
+                    let num_partitions = dense_ids.len() as u32;
+                    *dense_ids.entry(partition).or_insert(num_partitions)
+                })
+            })
+            .collect();
+        Self {
+            partitions,
+            num_partitions: dense_ids.len(),
+        }
+    }
+
+    pub fn num_txns(&self) -> usize {
+        self.partitions.len()
+    }
+
+    pub fn num_partitions(&self) -> usize {
+        self.num_partitions
+    }
+
+    /// Returns the partition of the transaction, with partitions numbered from 0 in the order of
+    /// their first transaction.
+    pub fn partition(&self, txn_idx: TxnIndex) -> Option<u32> {
+        self.partitions[txn_idx as usize]
+    }
+}
+
+/// Information about the conflicts of a block, provided ahead of its execution.
+#[derive(Debug)]
+pub enum BlockHints<'a, K> {
+    /// One access hint per transaction.
+    Access(&'a [AccessHint<K>]),
+    Partitioning(&'a BlockPartitioning),
+}
+
+// Not derived, as the derive would require K: Copy.
+impl<K> Clone for BlockHints<'_, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for BlockHints<'_, K> {}
+
+/// For each transaction, returns the highest-indexed earlier transaction hinted to write a key
+/// the transaction is hinted to read, if any.
+pub(crate) fn hinted_dependencies<K: Hash + Eq>(hints: &[AccessHint<K>]) -> Vec<Option<TxnIndex>> {
+    let mut last_writers: HashMap<&K, TxnIndex> = HashMap::new();
+    hints
+        .iter()
+        .enumerate()
+        .map(|(txn_idx, hint)| {
+            let dependency = hint
+                .reads
+                .iter()
+                .filter_map(|key| last_writers.get(key).copied())
+                .max();
+            for key in &hint.writes {
+                last_writers.insert(key, txn_idx as TxnIndex);
+            }
+            dependency
+        })
+        .collect()
+}
+
+/// Returns the number of transactions at the start of the block whose hinted accesses do not
+/// conflict, i.e. no key hinted to be written by one of these transactions is hinted to be read
+/// or written by another one.
+pub(crate) fn conflict_free_prefix<K: Hash + Eq>(hints: &[AccessHint<K>]) -> TxnIndex {
+    let mut reads: HashSet<&K> = HashSet::new();
+    let mut writes: HashSet<&K> = HashSet::new();
+    for (txn_idx, hint) in hints.iter().enumerate() {
+        if hint.reads.iter().any(|key| writes.contains(key))
+            || hint
+                .writes
+                .iter()
+                .any(|key| writes.contains(key) || reads.contains(key))
+        {
+            return txn_idx as TxnIndex;
+        }
+        reads.extend(hint.reads.iter());
+        writes.extend(hint.writes.iter());
+    }
+    hints.len() as TxnIndex
+}
+
+/// Returns the transactions with a higher gas unit price than the cheapest user transactions of
+/// the block, by decreasing gas unit price (and by index for the same price). A gas unit price of
+/// 0 stands for a transaction that is not a user transaction.
+pub(crate) fn gas_price_priority(gas_unit_prices: &[u64]) -> Vec<TxnIndex> {
+    let min_gas_unit_price = match gas_unit_prices.iter().filter(|price| **price > 0).min() {
+        Some(min_gas_unit_price) => *min_gas_unit_price,
+        None => return Vec::new(),
+    };
+    let mut priority: Vec<TxnIndex> = (0..gas_unit_prices.len() as TxnIndex)
+        .filter(|txn_idx| gas_unit_prices[*txn_idx as usize] > min_gas_unit_price)
+        .collect();
+    priority.sort_by_key(|txn_idx| Reverse(gas_unit_prices[*txn_idx as usize]));
+    priority
+}
+
+#[derive(Clone, Eq, Hash, PartialEq, Debug)]
 pub enum InputOutputKey<K, T, I> {
     Resource(K),
     Group(K, T),
@@ -24,10 +169,44 @@ impl<T: Transaction> ReadWriteSummary<T> {
         Self { reads, writes }
     }
 
+    pub(crate) fn reads(&self) -> &HashSet<InputOutputKey<T::Key, T::Tag, T::Identifier>> {
+        &self.reads
+    }
+
+    pub(crate) fn writes(&self) -> &HashSet<InputOutputKey<T::Key, T::Tag, T::Identifier>> {
+        &self.writes
+    }
+
     pub fn conflicts_with_previous(&self, previous: &Self) -> bool {
         !self.reads.is_disjoint(&previous.writes)
     }
 
+    /// Returns true if the transaction neither reads nor writes the keys written by the previous
+    /// transaction(s), nor writes the keys they read.
+    pub fn is_independent_of_previous(&self, previous: &Self) -> bool {
+        self.reads.is_disjoint(&previous.writes)
+            && self.writes.is_disjoint(&previous.writes)
+            && self.writes.is_disjoint(&previous.reads)
+    }
+
+    /// Returns true if the transaction accesses resource groups or delayed fields, whose values
+    /// are only known once the transaction is committed.
+    pub(crate) fn has_group_or_delayed_field_access(&self) -> bool {
+        self.reads.iter().chain(self.writes.iter()).any(|key| {
+            matches!(
+                key,
+                InputOutputKey::Group(_, _) | InputOutputKey::DelayedField(_)
+            )
+        })
+    }
+
+    /// Adds the reads and writes of the other summary, e.g. to accumulate those of a range of
+    /// transactions.
+    pub(crate) fn extend(&mut self, other: Self) {
+        self.reads.extend(other.reads);
+        self.writes.extend(other.writes);
+    }
+
     pub fn collapse_resource_group_conflicts(self) -> Self {
         let collapse = |k: InputOutputKey<T::Key, T::Tag, T::Identifier>| match k {
             InputOutputKey::Resource(k) => InputOutputKey::Resource(k),
@@ -41,6 +220,87 @@ impl<T: Transaction> ReadWriteSummary<T> {
     }
 }
 
+/// The realized read/write conflict graph of an executed block: for each committed transaction,
+/// the earlier transactions whose writes it read (i.e. for each key it read, the last earlier
+/// transaction that wrote the key in the committed outputs). Transactions that read only values
+/// from storage have no dependencies. This allows evaluating ordering policies and partitioning
+/// strategies against real workloads.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConflictGraph {
+    dependencies: Vec<Vec<TxnIndex>>,
+}
+
+impl ConflictGraph {
+    /// The number of committed transactions.
+    pub fn num_txns(&self) -> usize {
+        self.dependencies.len()
+    }
+
+    /// The earlier transactions whose writes the transaction read, in increasing order.
+    pub fn dependencies(&self, txn_idx: TxnIndex) -> &[TxnIndex] {
+        &self.dependencies[txn_idx as usize]
+    }
+
+    /// Returns all (reader, writer) edges of the graph.
+    pub fn edges(&self) -> impl Iterator<Item = (TxnIndex, TxnIndex)> + '_ {
+        self.dependencies
+            .iter()
+            .enumerate()
+            .flat_map(|(txn_idx, dependencies)| {
+                dependencies
+                    .iter()
+                    .map(move |dependency| (txn_idx as TxnIndex, *dependency))
+            })
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.dependencies.iter().map(Vec::len).sum()
+    }
+}
+
+/// Builds the conflict graph of a block from the read/write summaries of the transactions, which
+/// must be recorded in the order they are committed.
+pub(crate) struct ConflictGraphBuilder<T: Transaction> {
+    last_writers: HashMap<InputOutputKey<T::Key, T::Tag, T::Identifier>, TxnIndex>,
+    dependencies: Vec<Vec<TxnIndex>>,
+}
+
+impl<T: Transaction> ConflictGraphBuilder<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            last_writers: HashMap::new(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    pub(crate) fn record_committed(&mut self, txn_idx: TxnIndex, summary: ReadWriteSummary<T>) {
+        assert_eq!(
+            txn_idx as usize,
+            self.dependencies.len(),
+            "Transactions must be recorded in the commit order"
+        );
+
+        let mut dependencies: Vec<_> = summary
+            .reads
+            .iter()
+            .filter_map(|key| self.last_writers.get(key).copied())
+            .collect();
+        dependencies.sort_unstable();
+        dependencies.dedup();
+        self.dependencies.push(dependencies);
+
+        for key in summary.writes {
+            self.last_writers.insert(key, txn_idx);
+        }
+    }
+
+    pub(crate) fn build(self) -> ConflictGraph {
+        ConflictGraph {
+            dependencies: self.dependencies,
+        }
+    }
+}
+
 impl<T: Transaction> fmt::Debug for ReadWriteSummary<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "ReadWriteSummary")?;
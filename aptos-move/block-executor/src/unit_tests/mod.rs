@@ -2,33 +2,60 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+mod scripted_scheduler;
+
 use crate::{
-    errors::SequentialBlockExecutionError,
+    cancellation::CancellationToken,
+    chrome_trace,
+    errors::{
+        BlockExecutionError, BlockExecutionFailure, BlockExecutionResult, ParallelExecutionFailure,
+        ScheduleReplayError, SequentialBlockExecutionError,
+    },
     executor::BlockExecutor,
+    limit_processor::{BlockLimitPolicy, BlockLimitSnapshot},
     proptest_types::{
         baseline::BaselineOutput,
         types::{
-            DeltaDataView, KeyType, MockEvent, MockIncarnation, MockOutput, MockTask,
-            MockTransaction, NonEmptyGroupDataView, ValueType,
+            DeltaDataView, EmptyDataView, KeyType, MockEvent, MockIncarnation, MockOutput,
+            MockTask, MockTransaction, NonEmptyGroupDataView, ValueType, STORAGE_AGGREGATOR_VALUE,
         },
     },
+    schedule_trace::{ScheduleEvent, ScheduleTrace},
     scheduler::{
         DependencyResult, ExecutionTaskType, Scheduler, SchedulerTask, TWaitForDependency,
     },
-    txn_commit_hook::NoOpTransactionCommitHook,
+    task::ExecutionStatus,
+    txn_commit_hook::{
+        CommitDecision, CommittedOutputStream, NoOpTransactionCommitHook, StreamedOutput,
+        TransactionCommitHook,
+    },
+    types::{
+        conflict_free_prefix, gas_price_priority, hinted_dependencies, AccessHint, BlockHints,
+        BlockPartitioning, InputOutputKey, ReadWriteSummary,
+    },
+    unit_tests::scripted_scheduler::{
+        ExpectedDependency, ExpectedTask, ScriptedSchedulerDriver, Step,
+    },
 };
 use aptos_aggregator::{
     bounded_math::SignedU128,
-    delta_change_set::{delta_add, delta_sub, DeltaOp},
+    delta_change_set::{delta_add, delta_sub, serialize, DeltaOp},
     delta_math::DeltaHistory,
 };
-use aptos_mvhashmap::types::TxnIndex;
+use aptos_infallible::Mutex;
+use aptos_mvhashmap::{state_diff::FinalValue, types::TxnIndex};
 use aptos_types::{
-    block_executor::config::BlockExecutorConfig,
+    block_executor::config::{
+        BlockExecutorConfig, DependencyWaitConfig, ExecutionOverrides, FallbackPolicy,
+    },
     contract_event::TransactionEvent,
     executable::{ExecutableTestType, ModulePath},
+    fee_statement::FeeStatement,
+    on_chain_config::BlockGasLimitType,
+    state_store::{state_value::StateValueMetadata, TStateView},
+    transaction::{BlockEndInfo, BlockLimitUsage, BlockOutput, SkipReason},
 };
-use claims::assert_matches;
+use claims::{assert_le, assert_matches, assert_none};
 use fail::FailScenario;
 use rand::{prelude::*, random};
 use std::{
@@ -38,8 +65,54 @@ use std::{
     hash::Hash,
     marker::PhantomData,
     sync::Arc,
+    time::Duration,
 };
 
+/// A block executor of mock transactions on u32 keys, against a DeltaDataView.
+type MockBlockExecutor<L> = BlockExecutor<
+    MockTransaction<KeyType<u32>, MockEvent>,
+    MockTask<KeyType<u32>, MockEvent>,
+    DeltaDataView<KeyType<u32>>,
+    L,
+    ExecutableTestType,
+>;
+
+type MockCommitHook = NoOpTransactionCommitHook<MockOutput<KeyType<u32>, MockEvent>, usize>;
+
+fn new_executor_thread_pool() -> Arc<rayon::ThreadPool> {
+    Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_cpus::get())
+            .build()
+            .unwrap(),
+    )
+}
+
+fn new_block_executor(config: BlockExecutorConfig) -> MockBlockExecutor<MockCommitHook> {
+    MockBlockExecutor::new(config, new_executor_thread_pool(), None)
+}
+
+fn new_block_executor_with_hook<L>(
+    config: BlockExecutorConfig,
+    commit_hook: L,
+) -> MockBlockExecutor<L>
+where
+    L: TransactionCommitHook<Output = MockOutput<KeyType<u32>, MockEvent>>,
+{
+    MockBlockExecutor::new(config, new_executor_thread_pool(), Some(commit_hook))
+}
+
+/// Executes the block (with the fallbacks of the config) against a DeltaDataView.
+fn run_block_executor(
+    transactions: &[MockTransaction<KeyType<u32>, MockEvent>],
+    config: BlockExecutorConfig,
+) -> BlockExecutionResult<BlockOutput<MockOutput<KeyType<u32>, MockEvent>>, usize> {
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    new_block_executor(config).execute_block((), transactions, &data_view, None, None, None)
+}
+
 #[test]
 fn resource_group_bcs_fallback() {
     let no_group_incarnation_1: MockIncarnation<KeyType<u32>, MockEvent> = MockIncarnation::new(
@@ -96,7 +169,8 @@ fn resource_group_bcs_fallback() {
     );
 
     // Execute the block normally.
-    let output = block_executor.execute_transactions_parallel((), &transactions, &data_view);
+    let output =
+        block_executor.execute_transactions_parallel((), &transactions, &data_view, None, None);
     match output {
         Ok(block_output) => {
             let txn_outputs = block_output.into_transaction_outputs_forced();
@@ -114,26 +188,47 @@ fn resource_group_bcs_fallback() {
     fail::cfg("fail-point-resource-group-serialization", "return()").unwrap();
     assert!(!fail::list().is_empty());
 
-    let par_output = block_executor.execute_transactions_parallel((), &transactions, &data_view);
-    assert_matches!(par_output, Err(()));
+    let par_output =
+        block_executor.execute_transactions_parallel((), &transactions, &data_view, None, None);
+    assert_matches!(
+        par_output,
+        Err(ParallelExecutionFailure::CodeInvariantError(_))
+    );
 
-    let seq_output =
-        block_executor.execute_transactions_sequential((), &transactions, &data_view, false);
+    let seq_output = block_executor.execute_transactions_sequential(
+        (),
+        &transactions,
+        &data_view,
+        &BlockGasLimitType::NoLimit,
+        false,
+        None,
+    );
     assert_matches!(
         seq_output,
-        Err(SequentialBlockExecutionError::ResourceGroupSerializationError)
+        Err(SequentialBlockExecutionError::ResourceGroupSerializationError(err))
+            if err.txn_idx == 1
     );
 
     // Now execute with fallback handling for resource group serialization error:
     let fallback_output = block_executor
-        .execute_transactions_sequential((), &transactions, &data_view, true)
+        .execute_transactions_sequential(
+            (),
+            &transactions,
+            &data_view,
+            &BlockGasLimitType::NoLimit,
+            true,
+            None,
+        )
         .map_err(|e| match e {
-            SequentialBlockExecutionError::ResourceGroupSerializationError => {
+            SequentialBlockExecutionError::ResourceGroupSerializationError(_) => {
                 panic!("Unexpected error")
             },
-            SequentialBlockExecutionError::ErrorToReturn(err) => err,
+            SequentialBlockExecutionError::ErrorToReturn { error, txn_idx } => {
+                BlockExecutionFailure { error, txn_idx }
+            },
         });
-    let fallback_output_block = block_executor.execute_block((), &transactions, &data_view);
+    let fallback_output_block =
+        block_executor.execute_block((), &transactions, &data_view, None, None, None);
     for output in [fallback_output, fallback_output_block] {
         match output {
             Ok(block_output) => {
@@ -191,498 +286,2861 @@ fn block_output_err_precedence() {
     let scenario = FailScenario::setup();
     assert!(fail::has_failpoints());
     fail::cfg("commit-all-halt-err", "return()").unwrap();
+    // The version chains of the written keys are logged with the code invariant error.
+    fail::cfg("dump-versioned-cache-on-code-invariant-error", "return()").unwrap();
     assert!(!fail::list().is_empty());
     // Pause the thread that processes the aborting txn1, so txn2 can halt the scheduler first.
     // Confirm that the fatal VM error is still detected and sequential fallback triggered.
-    let output = block_executor.execute_transactions_parallel((), &transactions, &data_view);
-    assert_matches!(output, Err(()));
+    let output =
+        block_executor.execute_transactions_parallel((), &transactions, &data_view, None, None);
+    assert_matches!(
+        output,
+        Err(ParallelExecutionFailure::CodeInvariantError(message))
+            if message.contains("Last committed transaction halted")
+    );
     scenario.teardown();
 }
 
 #[test]
-fn skip_rest_gas_limit() {
-    // The contents of the second txn does not matter, as the first should hit the gas limit and
-    // also skip. But it ensures block is not finished at the first txn (different processing).
-    let transactions = Vec::from([MockTransaction::SkipRest(10), MockTransaction::SkipRest(10)]);
-
+fn fallback_policies() {
+    let transactions: Vec<_> = (0..10)
+        .map(|i| {
+            let key = KeyType::<u32>(i, false);
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![key.clone()],
+                vec![(key, random_value(false))],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
+        .collect();
     let data_view = DeltaDataView::<KeyType<u32>> {
         phantom: PhantomData,
     };
-    let executor_thread_pool = Arc::new(
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(num_cpus::get())
-            .build()
-            .unwrap(),
-    );
-    let block_executor = BlockExecutor::<
-        MockTransaction<KeyType<u32>, MockEvent>,
-        MockTask<KeyType<u32>, MockEvent>,
-        DeltaDataView<KeyType<u32>>,
-        NoOpTransactionCommitHook<MockOutput<KeyType<u32>, MockEvent>, usize>,
-        ExecutableTestType,
-    >::new(
-        BlockExecutorConfig::new_maybe_block_limit(num_cpus::get(), Some(5)),
-        executor_thread_pool,
-        None,
-    );
 
-    // Should hit block limit on the skip transaction.
-    let _ = block_executor.execute_transactions_parallel((), &transactions, &data_view);
+    // Parallel execution always fails with a code invariant error.
+    let scenario = FailScenario::setup();
+    fail::cfg("commit-all-halt-err", "return()").unwrap();
+    for (policy, discarded, fallbacks) in [
+        (FallbackPolicy::RetryParallelOnce, false, vec![
+            FallbackPolicy::RetryParallelOnce,
+            FallbackPolicy::Sequential,
+        ]),
+        (FallbackPolicy::ParallelWithReducedConcurrency, false, vec![
+            FallbackPolicy::ParallelWithReducedConcurrency,
+            FallbackPolicy::Sequential,
+        ]),
+        (FallbackPolicy::Sequential, false, vec![
+            FallbackPolicy::Sequential,
+        ]),
+        (FallbackPolicy::Discard, true, vec![FallbackPolicy::Discard]),
+    ] {
+        let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get().max(2));
+        config.local.fallback_policies.code_invariant_error = policy;
+        let block_executor = new_block_executor(config);
+
+        let output = block_executor.execute_block((), &transactions, &data_view, None, None, None);
+        if discarded {
+            let output = output.unwrap();
+            assert!(output
+                .get_transaction_outputs_forced()
+                .iter()
+                .all(|output| output.skipped));
+        } else {
+            // The failed retries fall back to sequential execution.
+            BaselineOutput::generate(&transactions, None).assert_output(&output);
+        }
+        let stats = block_executor.take_execution_stats().unwrap();
+        // Every parallel execution failed, the first one and the retry (if any).
+        assert_eq!(stats.parallel_failures.len(), stats.fallbacks.len());
+        assert!(stats.parallel_failures.iter().all(|failure| matches!(
+            failure,
+            ParallelExecutionFailure::CodeInvariantError(message)
+                if message.contains("Last committed transaction halted")
+        )));
+        assert_eq!(stats.fallbacks, fallbacks);
+    }
+    scenario.teardown();
 }
 
-// TODO: add unit test for block gas limit!
-fn run_and_assert<K, E>(transactions: Vec<MockTransaction<K, E>>)
-where
-    K: PartialOrd + Ord + Send + Sync + Clone + Hash + Eq + ModulePath + Debug + 'static,
-    E: Send + Sync + Debug + Clone + TransactionEvent + 'static,
-{
-    let data_view = DeltaDataView::<K> {
+#[test]
+fn block_execution_stats() {
+    let transactions: Vec<_> = (0..20)
+        .map(|i| {
+            let key = KeyType::<u32>(i % 3, false);
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![key.clone()],
+                vec![(key, random_value(false))],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<u32>> {
         phantom: PhantomData,
     };
 
-    let executor_thread_pool = Arc::new(
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(num_cpus::get())
-            .build()
-            .unwrap(),
-    );
+    for concurrency_level in [1, num_cpus::get().max(2)] {
+        let block_executor =
+            new_block_executor(BlockExecutorConfig::new_no_block_limit(concurrency_level));
+        assert_eq!(block_executor.take_execution_stats(), None);
+
+        let output = block_executor.execute_block((), &transactions, &data_view, None, None, None);
+        BaselineOutput::generate(&transactions, None).assert_output(&output);
+
+        // Every transaction is executed at least once, and re-executed after every abort.
+        let stats = block_executor.take_execution_stats().unwrap();
+        assert!(stats.num_incarnations >= transactions.len() as u64 + stats.num_aborts);
+        if concurrency_level == 1 {
+            assert_eq!(stats.num_incarnations, transactions.len() as u64);
+            assert_eq!(stats.num_aborts, 0);
+            assert_eq!(stats.num_waves, 0);
+        }
+        assert_eq!(block_executor.take_execution_stats(), None);
+    }
+}
 
-    let output = BlockExecutor::<
-        MockTransaction<K, E>,
-        MockTask<K, E>,
-        DeltaDataView<K>,
-        NoOpTransactionCommitHook<MockOutput<K, E>, usize>,
-        ExecutableTestType,
-    >::new(
-        BlockExecutorConfig::new_no_block_limit(num_cpus::get()),
-        executor_thread_pool,
-        None,
-    )
-    .execute_transactions_parallel((), &transactions, &data_view);
+#[test]
+fn txn_execution_timeout() {
+    let transactions: Vec<_> = (0..10)
+        .map(|i| {
+            let key = KeyType::<u32>(i % 3, false);
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![key.clone()],
+                vec![(key, random_value(false))],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
+        .collect();
 
-    let baseline = BaselineOutput::generate(&transactions, None);
-    baseline.assert_parallel_output(&output);
+    // Every execution times out, so all transactions are discarded, both by the sequential and
+    // by the parallel execution (once the timed out incarnations are validated).
+    for concurrency_level in [1, num_cpus::get().max(2)] {
+        let mut config = BlockExecutorConfig::new_no_block_limit(concurrency_level);
+        config.local.txn_execution_timeout = Some(Duration::ZERO);
+        let output = run_block_executor(&transactions, config).unwrap();
+        let outputs = output.get_transaction_outputs_forced();
+        assert_eq!(outputs.len(), transactions.len());
+        assert!(outputs.iter().all(|output| output.skipped));
+    }
 }
 
-fn random_value(delete_value: bool) -> ValueType {
-    ValueType::from_value(
-        (0..32).map(|_| (random::<u8>())).collect::<Vec<u8>>(),
-        !delete_value,
-    )
+#[test]
+fn block_time_budget() {
+    let transactions: Vec<_> = (0..10)
+        .map(|_| {
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
+        .collect();
+
+    // The budget is exhausted when the first transaction is committed, which ends the block,
+    // both in sequential and in parallel execution.
+    for concurrency_level in [1, num_cpus::get().max(2)] {
+        let mut config = BlockExecutorConfig::new_no_block_limit(concurrency_level);
+        config.local.block_time_budget = Some(Duration::ZERO);
+        let output = run_block_executor(&transactions, config).unwrap();
+        let block_end_info = output.block_end_info().unwrap();
+        assert_eq!(block_end_info.num_committed_txns, 1);
+        assert_eq!(
+            block_end_info.skip_reason,
+            Some(SkipReason::BlockTimeBudget)
+        );
+        let outputs = output.get_transaction_outputs_forced();
+        assert!(!outputs[0].skipped);
+        assert!(outputs[1..].iter().all(|output| output.skipped));
+    }
 }
 
-#[test]
-fn empty_block() {
-    // This test checks that we do not trigger asserts due to an empty block, e.g. in the
-    // scheduler. Instead, parallel execution should gracefully early return empty output.
-    run_and_assert::<KeyType<[u8; 32]>, MockEvent>(vec![]);
+struct HaltingCommitHook {
+    halt_at: TxnIndex,
 }
 
-#[test]
-fn delta_counters() {
-    let key = KeyType(random::<[u8; 32]>(), false);
-    let mut transactions = vec![MockTransaction::from_behavior(MockIncarnation::<
-        KeyType<[u8; 32]>,
-        MockEvent,
-    >::new(
-        vec![],
-        vec![(key, random_value(false))], // writes
-        vec![],
-        vec![],
-        1, // gas
-    ))];
+impl TransactionCommitHook for HaltingCommitHook {
+    type Output = MockOutput<KeyType<u32>, MockEvent>;
 
-    for _ in 0..50 {
-        transactions.push(MockTransaction::from_behavior(MockIncarnation::<
-            KeyType<[u8; 32]>,
-            MockEvent,
-        >::new(
-            vec![key], // reads
-            vec![],
-            vec![(key, delta_add(5, u128::MAX))], // deltas
-            vec![],
-            1, // gas
-        )));
+    fn on_transaction_committed(
+        &self,
+        txn_idx: TxnIndex,
+        _output: &Self::Output,
+    ) -> anyhow::Result<()> {
+        assert!(txn_idx <= self.halt_at);
+        Ok(())
     }
 
-    transactions.push(MockTransaction::from_behavior(MockIncarnation::<
-        KeyType<[u8; 32]>,
-        MockEvent,
-    >::new(
-        vec![],
-        vec![(key, random_value(false))], // writes
-        vec![],
-        vec![],
-        1, // gas
-    )));
+    fn on_execution_aborted(&self, _txn_idx: TxnIndex) {}
 
-    for _ in 0..50 {
-        transactions.push(MockTransaction::from_behavior(MockIncarnation::<
-            KeyType<[u8; 32]>,
-            MockEvent,
-        >::new(
-            vec![key], // reads
-            vec![],
-            vec![(key, delta_sub(2, u128::MAX))], // deltas
-            vec![],
-            1, // gas
-        )));
+    fn should_commit(&self, txn_idx: TxnIndex, _output: &Self::Output) -> CommitDecision {
+        if txn_idx == self.halt_at {
+            CommitDecision::HaltBlock
+        } else {
+            CommitDecision::Commit
+        }
     }
-
-    run_and_assert(transactions)
 }
 
 #[test]
-fn delta_chains() {
-    let mut transactions = vec![];
-    // Generate a series of transactions add and subtract from an aggregator.
-
-    let keys: Vec<KeyType<[u8; 32]>> = (0..10)
-        .map(|_| KeyType(random::<[u8; 32]>(), false))
-        .collect();
-
-    for i in 0..500 {
-        transactions.push(
-            MockTransaction::<KeyType<[u8; 32]>, MockEvent>::from_behavior(MockIncarnation::new(
-                keys.clone(), // reads
+fn commit_hook_halts_block() {
+    let transactions: Vec<_> = (0..10)
+        .map(|i| {
+            let key = KeyType::<u32>(i % 3, false);
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![key.clone()],
+                vec![(key, random_value(false))],
                 vec![],
-                keys.iter()
-                    .enumerate()
-                    .filter_map(|(j, k)| match (i + j) % 2 == 0 {
-                        true => Some((
-                            *k,
-                            // Deterministic pattern for adds/subtracts.
-                            DeltaOp::new(
-                                if (i % 2 == 0) == (j < 5) {
-                                    SignedU128::Positive(10)
-                                } else {
-                                    SignedU128::Negative(1)
-                                },
-                                // below params irrelevant for this test.
-                                u128::MAX,
-                                DeltaHistory::new(),
-                            ),
-                        )),
-                        false => None,
-                    })
-                    .collect(), // deltas
                 vec![],
-                1, // gas
-            )),
+                10,
+            ))
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+
+    for concurrency_level in [1, num_cpus::get().max(2)] {
+        let block_executor = new_block_executor_with_hook(
+            BlockExecutorConfig::new_no_block_limit(concurrency_level),
+            HaltingCommitHook { halt_at: 3 },
         );
+
+        let output = block_executor
+            .execute_block((), &transactions, &data_view, None, None, None)
+            .unwrap();
+        let skipped: Vec<_> = output
+            .get_transaction_outputs_forced()
+            .iter()
+            .map(|output| output.skipped)
+            .collect();
+        assert_eq!(skipped, (0..10).map(|i| i > 3).collect::<Vec<_>>());
     }
+}
 
-    run_and_assert(transactions)
+struct SnapshotRecordingHook {
+    snapshots: Arc<Mutex<Vec<(TxnIndex, BlockLimitSnapshot)>>>,
 }
 
-const TOTAL_KEY_NUM: u64 = 50;
-const WRITES_PER_KEY: u64 = 100;
+impl TransactionCommitHook for SnapshotRecordingHook {
+    type Output = MockOutput<KeyType<u32>, MockEvent>;
+
+    fn on_transaction_committed(
+        &self,
+        _txn_idx: TxnIndex,
+        _output: &Self::Output,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn on_execution_aborted(&self, _txn_idx: TxnIndex) {}
+
+    fn on_block_limit_snapshot(&self, txn_idx: TxnIndex, snapshot: &BlockLimitSnapshot) {
+        self.snapshots.lock().push((txn_idx, *snapshot));
+    }
+}
 
 #[test]
-fn cycle_transactions() {
-    let mut transactions = vec![];
-    // For every key in `TOTAL_KEY_NUM`, generate a series of transactions that will assign a
-    // value to this key.
-    for _ in 0..TOTAL_KEY_NUM {
-        let key = random::<[u8; 32]>();
-        for _ in 0..WRITES_PER_KEY {
-            transactions.push(MockTransaction::from_behavior(MockIncarnation::<
-                KeyType<[u8; 32]>,
-                MockEvent,
-            >::new(
-                vec![KeyType(key, false)],                        // reads
-                vec![(KeyType(key, false), random_value(false))], // writes
+fn commit_hook_block_limit_snapshots() {
+    let transactions: Vec<_> = (0..10)
+        .map(|i| {
+            let key = KeyType::<u32>(i % 3, false);
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![key.clone()],
+                vec![(key, random_value(false))],
                 vec![],
                 vec![],
-                1, // gas
-            )));
-        }
+                10,
+            ))
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+
+    // The block ends once the accumulated gas reaches the limit, after the 5th transaction.
+    let expected_snapshots: Vec<_> = (0..5)
+        .map(|i| {
+            (i, BlockLimitSnapshot {
+                num_accumulated_txns: i + 1,
+                limit_usage: BlockLimitUsage {
+                    effective_block_gas: 10 * (i as u64 + 1),
+                    execution_gas: 5 * (i as u64 + 1),
+                    io_gas: 5 * (i as u64 + 1),
+                    storage_fee: 0,
+                    approx_output_size: 0,
+                },
+                last_conflict_multiplier: 1,
+            })
+        })
+        .collect();
+    for concurrency_level in [1, num_cpus::get().max(2)] {
+        let snapshots = Arc::new(Mutex::new(vec![]));
+        let block_executor = new_block_executor_with_hook(
+            BlockExecutorConfig::new_maybe_block_limit(concurrency_level, Some(50)),
+            SnapshotRecordingHook {
+                snapshots: snapshots.clone(),
+            },
+        );
+
+        block_executor
+            .execute_block((), &transactions, &data_view, None, None, None)
+            .unwrap();
+        assert_eq!(*snapshots.lock(), expected_snapshots);
     }
-    run_and_assert(transactions)
 }
 
-const NUM_BLOCKS: u64 = 10;
-const TXN_PER_BLOCK: u64 = 100;
+#[derive(Default)]
+struct ReleaseRecord {
+    released: Mutex<Vec<(TxnIndex, Vec<Option<Vec<u8>>>)>>,
+    revoked: Mutex<HashSet<TxnIndex>>,
+    committed: Mutex<BTreeMap<TxnIndex, Vec<Option<Vec<u8>>>>>,
+}
 
-#[test]
-fn one_reads_all_barrier() {
-    let mut transactions = vec![];
-    let keys: Vec<KeyType<_>> = (0..TXN_PER_BLOCK)
-        .map(|_| KeyType(random::<[u8; 32]>(), false))
-        .collect();
-    for _ in 0..NUM_BLOCKS {
-        for key in &keys {
-            transactions.push(MockTransaction::from_behavior(MockIncarnation::<
-                KeyType<[u8; 32]>,
-                MockEvent,
-            >::new(
-                vec![*key],                        // reads
-                vec![(*key, random_value(false))], // writes
+struct ReleaseRecordingHook(Arc<ReleaseRecord>);
+
+impl TransactionCommitHook for ReleaseRecordingHook {
+    type Output = MockOutput<KeyType<u32>, MockEvent>;
+
+    fn on_transaction_committed(
+        &self,
+        txn_idx: TxnIndex,
+        output: &Self::Output,
+    ) -> anyhow::Result<()> {
+        self.0
+            .committed
+            .lock()
+            .insert(txn_idx, output.read_results.clone());
+        Ok(())
+    }
+
+    fn on_execution_aborted(&self, _txn_idx: TxnIndex) {}
+
+    fn on_transaction_released(
+        &self,
+        txn_idx: TxnIndex,
+        output: &Self::Output,
+    ) -> anyhow::Result<()> {
+        // Released ahead of the commit.
+        assert!(!self.0.committed.lock().contains_key(&txn_idx));
+        self.0
+            .released
+            .lock()
+            .push((txn_idx, output.read_results.clone()));
+        Ok(())
+    }
+
+    fn on_release_revoked(&self, txn_idx: TxnIndex) {
+        assert!(self.0.revoked.lock().insert(txn_idx));
+    }
+}
+
+#[test]
+fn read_write_summary_independence() {
+    type Txn = MockTransaction<KeyType<u32>, MockEvent>;
+    let resource = |key| InputOutputKey::Resource(KeyType::<u32>(key, false));
+    let summary = |reads: Vec<u32>, writes: Vec<u32>| {
+        ReadWriteSummary::<Txn>::new(
+            reads.into_iter().map(resource).collect(),
+            writes.into_iter().map(resource).collect(),
+        )
+    };
+
+    let mut previous = summary(vec![1], vec![2]);
+    previous.extend(summary(vec![3], vec![4]));
+    assert!(summary(vec![5], vec![6]).is_independent_of_previous(&previous));
+    // Reads a key written by a previous transaction.
+    assert!(!summary(vec![4], vec![6]).is_independent_of_previous(&previous));
+    // Writes a key written by a previous transaction.
+    assert!(!summary(vec![5], vec![2]).is_independent_of_previous(&previous));
+    // Writes a key read by a previous transaction.
+    assert!(!summary(vec![5], vec![3]).is_independent_of_previous(&previous));
+    // Reading the same keys is not a conflict.
+    assert!(summary(vec![1, 3], vec![6]).is_independent_of_previous(&previous));
+}
+
+#[test]
+fn early_release_parallel_execution() {
+    // Every other transaction reads and writes a hot key, the others their own keys.
+    let num_txns = 200;
+    let transactions: Vec<_> = (0..num_txns)
+        .map(|i| {
+            let (read_key, write_key) = if i % 2 == 0 {
+                (KeyType::<u32>(0, false), KeyType::<u32>(0, false))
+            } else {
+                (
+                    KeyType::<u32>(1000 + i, false),
+                    KeyType::<u32>(2000 + i, false),
+                )
+            };
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![read_key],
+                vec![(write_key, random_value(false))],
                 vec![],
                 vec![],
-                1, // gas
-            )));
+                10,
+            ))
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+
+    let record = Arc::new(ReleaseRecord::default());
+    let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get().max(2));
+    config.local.early_release = true;
+    let block_executor = new_block_executor_with_hook(config, ReleaseRecordingHook(record.clone()));
+    let output =
+        block_executor.execute_transactions_parallel((), &transactions, &data_view, None, None);
+    BaselineOutput::generate(&transactions, None).assert_parallel_output(&output);
+
+    // Unless revoked, the released outputs are the committed ones.
+    let committed = record.committed.lock();
+    assert_eq!(committed.len(), num_txns as usize);
+    let revoked = record.revoked.lock();
+    for (txn_idx, read_results) in record.released.lock().iter() {
+        assert_ne!(*txn_idx, 0);
+        if !revoked.contains(txn_idx) {
+            assert_eq!(committed.get(txn_idx), Some(read_results));
         }
-        // One transaction reading the write results of every prior transactions in the block.
-        transactions.push(MockTransaction::from_behavior(MockIncarnation::<
-            KeyType<[u8; 32]>,
-            MockEvent,
-        >::new(
-            keys.clone(), //reads
-            vec![],
-            vec![],
-            vec![],
-            1, //gas
-        )));
     }
-    run_and_assert(transactions)
 }
 
 #[test]
-fn one_writes_all_barrier() {
-    let mut transactions = vec![];
-    let keys: Vec<KeyType<_>> = (0..TXN_PER_BLOCK)
-        .map(|_| KeyType(random::<[u8; 32]>(), false))
+fn dedicated_materialization_parallel_execution() {
+    let num_txns = 200;
+    let transactions: Vec<_> = (0..num_txns)
+        .map(|i| {
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![KeyType::<u32>(i % 10, false)],
+                vec![(KeyType::<u32>((i + 1) % 10, false), random_value(false))],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
         .collect();
-    for _ in 0..NUM_BLOCKS {
-        for key in &keys {
-            transactions.push(MockTransaction::from_behavior(MockIncarnation::new(
-                vec![*key],                        //reads
-                vec![(*key, random_value(false))], //writes
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let materialization_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap(),
+    );
+
+    // On the executor thread pool (taking the place of execution workers), and on a dedicated
+    // thread pool.
+    for dedicated_pool in [false, true] {
+        let record = Arc::new(ReleaseRecord::default());
+        let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get().max(2));
+        config.local.materialization_concurrency = 2;
+        let block_executor =
+            new_block_executor_with_hook(config, ReleaseRecordingHook(record.clone()));
+        let block_executor = if dedicated_pool {
+            block_executor.with_materialization_thread_pool(materialization_thread_pool.clone())
+        } else {
+            block_executor
+        };
+        let output =
+            block_executor.execute_transactions_parallel((), &transactions, &data_view, None, None);
+        BaselineOutput::generate(&transactions, None).assert_parallel_output(&output);
+        assert_eq!(record.committed.lock().len(), num_txns as usize);
+    }
+}
+
+#[test]
+fn execution_overrides() {
+    let transactions: Vec<_> = (0..50)
+        .map(|i| {
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![KeyType::<u32>(i % 5, false)],
+                vec![(KeyType::<u32>((i + 1) % 5, false), random_value(false))],
                 vec![],
                 vec![],
-                1, //gas
-            )));
-        }
-        // One transaction writing to the write results of every prior transactions in the block.
-        transactions.push(MockTransaction::from_behavior(MockIncarnation::<
-            KeyType<[u8; 32]>,
-            MockEvent,
-        >::new(
-            keys.clone(), // reads
-            keys.iter()
-                .map(|key| (*key, random_value(false)))
-                .collect::<Vec<_>>(), //writes
-            vec![],
-            vec![],
-            1, // gas
-        )));
+                10,
+            ))
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let block_executor = new_block_executor(BlockExecutorConfig::new_no_block_limit(
+        num_cpus::get().max(2),
+    ));
+
+    // Sequentially, and in parallel at a different concurrency level, with a block gas limit.
+    for concurrency_level in [1, 2] {
+        let output = block_executor.execute_block(
+            (),
+            &transactions,
+            &data_view,
+            None,
+            None,
+            Some(ExecutionOverrides {
+                concurrency_level: Some(concurrency_level),
+                gas_limit_override: Some(BlockGasLimitType::Limit(100)),
+            }),
+        );
+        BaselineOutput::generate(&transactions, Some(100)).assert_output(&output);
     }
-    run_and_assert(transactions)
+
+    // The overrides only apply to the block they were passed with.
+    let output = block_executor.execute_block((), &transactions, &data_view, None, None, None);
+    BaselineOutput::generate(&transactions, None).assert_output(&output);
 }
 
 #[test]
-fn early_aborts() {
-    let mut transactions = vec![];
-    let keys: Vec<_> = (0..TXN_PER_BLOCK)
-        .map(|_| KeyType(random::<[u8; 32]>(), false))
+fn differential_execution() {
+    let transactions: Vec<_> = (0..50)
+        .map(|i| {
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![KeyType::<u32>(i % 5, false)],
+                vec![(KeyType::<u32>((i + 1) % 5, false), random_value(false))],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
         .collect();
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get().max(2));
+    config.local.differential_execution = true;
+    let block_executor = new_block_executor(config);
 
-    for _ in 0..NUM_BLOCKS {
-        for key in &keys {
-            transactions.push(MockTransaction::from_behavior(MockIncarnation::<
-                KeyType<[u8; 32]>,
-                MockEvent,
-            >::new(
-                vec![*key],                        // reads
-                vec![(*key, random_value(false))], // writes
+    let output = block_executor.execute_block((), &transactions, &data_view, None, None, None);
+    BaselineOutput::generate(&transactions, None).assert_output(&output);
+    assert_eq!(block_executor.take_execution_divergence(), None);
+}
+
+#[test]
+fn execute_single_at_block_prefix() {
+    let key = KeyType::<u32>(1, false);
+    let transactions: Vec<_> = (1..=10)
+        .map(|i| {
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![key.clone()],
+                vec![(key.clone(), ValueType::from_value(vec![i as u8; 16], true))],
                 vec![],
                 vec![],
-                1, // gas
-            )));
+                10,
+            ))
+        })
+        .collect();
+    let data_view = EmptyDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get().max(2));
+    config.local.retain_block_state = true;
+    let block_executor = BlockExecutor::<
+        MockTransaction<KeyType<u32>, MockEvent>,
+        MockTask<KeyType<u32>, MockEvent>,
+        EmptyDataView<KeyType<u32>>,
+        NoOpTransactionCommitHook<MockOutput<KeyType<u32>, MockEvent>, usize>,
+        ExecutableTestType,
+    >::new(config, new_executor_thread_pool(), None);
+
+    let output = block_executor.execute_block((), &transactions, &data_view, None, None, None);
+    BaselineOutput::generate(&transactions, None).assert_output(&output);
+    let block_state = block_executor.take_block_state().unwrap();
+    assert_eq!(block_state.num_committed_txns(), 10);
+    assert!(block_state.prefix(11).is_none());
+    // The state diff of the block holds the write of the last transaction.
+    let state_diff = block_state.state_diff();
+    assert_matches!(
+        &state_diff.resources[..],
+        [(k, 9, FinalValue::Write(_))] if *k == key
+    );
+
+    // A transaction reading the key observes the write of the last transaction of the prefix.
+    let reader = MockTransaction::from_behavior(MockIncarnation::new(
+        vec![key.clone()],
+        vec![],
+        vec![],
+        vec![],
+        10,
+    ));
+    for txn_idx in [0, 5, 10] {
+        let prefix_state = block_state.prefix(txn_idx).unwrap();
+        let read_result =
+            match block_executor.execute_single_at((), &data_view, &prefix_state, &reader) {
+                ExecutionStatus::Success(output) => output.read_results[0].clone(),
+                _ => unreachable!("Transaction must succeed"),
+            };
+        assert_eq!(read_result, (txn_idx > 0).then(|| vec![txn_idx as u8; 16]));
+    }
+    // Executing a transaction does not modify the block.
+    let prefix_state = block_state.prefix(5).unwrap();
+    assert_matches!(
+        block_executor.execute_single_at((), &data_view, &prefix_state, &transactions[9]),
+        ExecutionStatus::Success(_)
+    );
+    let read_result = match block_executor.execute_single_at((), &data_view, &prefix_state, &reader)
+    {
+        ExecutionStatus::Success(output) => output.read_results[0].clone(),
+        _ => unreachable!("Transaction must succeed"),
+    };
+    assert_eq!(read_result, Some(vec![5; 16]));
+}
+
+#[test]
+fn cancelled_block_execution() {
+    let transactions = Vec::from([MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(
+        MockIncarnation::new(vec![KeyType::<u32>(1, false)], vec![], vec![], vec![], 10),
+    )]);
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+    for concurrency_level in [1, 2] {
+        let block_executor =
+            new_block_executor(BlockExecutorConfig::new_no_block_limit(concurrency_level));
+        assert_matches!(
+            block_executor.execute_block(
+                (),
+                &transactions,
+                &data_view,
+                None,
+                Some(&cancellation),
+                None
+            ),
+            Err(BlockExecutionFailure {
+                error: BlockExecutionError::Cancelled,
+                ..
+            })
+        );
+    }
+}
+
+#[test]
+fn chrome_trace_block_execution() {
+    let transactions: Vec<_> = (0..20)
+        .map(|i| {
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![KeyType::<u32>(i, false)],
+                vec![(
+                    KeyType::<u32>(i + 1, false),
+                    ValueType::from_value(vec![i as u8], true),
+                )],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let block_executor = new_block_executor(BlockExecutorConfig::new_no_block_limit(4));
+
+    let trace_dir = aptos_temppath::TempPath::new();
+    trace_dir.create_as_dir().unwrap();
+    chrome_trace::enable(trace_dir.path().to_path_buf());
+    // Blocks of other tests may be recorded concurrently instead of ours, but all traced
+    // blocks are written to the same directory.
+    let mut traced_events = vec![];
+    for _ in 0..10 {
+        block_executor
+            .execute_block((), &transactions, &data_view, None, None, None)
+            .unwrap();
+        for entry in std::fs::read_dir(trace_dir.path()).unwrap() {
+            let trace: serde_json::Value =
+                serde_json::from_slice(&std::fs::read(entry.unwrap().path()).unwrap()).unwrap();
+            traced_events.extend(trace["traceEvents"].as_array().unwrap().clone());
+        }
+        if !traced_events.is_empty() {
+            break;
         }
-        // One transaction that triggers an abort
-        transactions.push(MockTransaction::Abort)
     }
-    run_and_assert(transactions)
+    chrome_trace::disable();
+
+    let execution = traced_events
+        .iter()
+        .find(|event| event["name"] == "execute")
+        .expect("Executions must be traced");
+    assert_eq!(execution["ph"], "X");
+    assert!(execution["dur"].as_f64().is_some());
+    assert!(execution["args"]["txn_idx"].as_u64().is_some());
+    assert!(traced_events.iter().any(|event| event["name"] == "commit"));
 }
 
 #[test]
-fn early_skips() {
-    let mut transactions = vec![];
-    let keys: Vec<_> = (0..TXN_PER_BLOCK)
-        .map(|_| KeyType(random::<[u8; 32]>(), false))
+fn skip_rest_gas_limit() {
+    // The contents of the second txn does not matter, as the first should hit the gas limit and
+    // also skip. But it ensures block is not finished at the first txn (different processing).
+    let transactions = Vec::from([MockTransaction::SkipRest(10), MockTransaction::SkipRest(10)]);
+
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_cpus::get())
+            .build()
+            .unwrap(),
+    );
+    let block_executor = BlockExecutor::<
+        MockTransaction<KeyType<u32>, MockEvent>,
+        MockTask<KeyType<u32>, MockEvent>,
+        DeltaDataView<KeyType<u32>>,
+        NoOpTransactionCommitHook<MockOutput<KeyType<u32>, MockEvent>, usize>,
+        ExecutableTestType,
+    >::new(
+        BlockExecutorConfig::new_maybe_block_limit(num_cpus::get(), Some(5)),
+        executor_thread_pool,
+        None,
+    );
+
+    // Should hit block limit on the skip transaction.
+    let _ = block_executor.execute_transactions_parallel((), &transactions, &data_view, None, None);
+}
+
+#[test]
+fn block_gas_limit_end_info() {
+    let transactions: Vec<_> = (0..4)
+        .map(|i| {
+            MockTransaction::from_behavior(MockIncarnation::<KeyType<u32>, MockEvent>::new(
+                vec![],
+                vec![(
+                    KeyType::<u32>(i, false),
+                    ValueType::from_value(vec![5], true),
+                )],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
         .collect();
 
-    for _ in 0..NUM_BLOCKS {
-        for key in &keys {
-            transactions.push(MockTransaction::from_behavior(MockIncarnation::<
-                KeyType<[u8; 32]>,
-                MockEvent,
-            >::new(
-                vec![*key],                        // reads
-                vec![(*key, random_value(false))], //writes
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let block_executor = new_block_executor(BlockExecutorConfig::new_maybe_block_limit(
+        num_cpus::get(),
+        Some(15),
+    ));
+
+    // The second transaction reaches the limit, the rest are skipped.
+    let expected_end_info = BlockEndInfo {
+        num_committed_txns: 2,
+        skip_reason: Some(SkipReason::BlockGasLimit),
+        limit_usage: BlockLimitUsage {
+            effective_block_gas: 20,
+            execution_gas: 10,
+            io_gas: 10,
+            storage_fee: 0,
+            approx_output_size: 0,
+        },
+    };
+    for output in [
+        block_executor
+            .execute_transactions_parallel((), &transactions, &data_view, None, None)
+            .unwrap(),
+        block_executor
+            .execute_transactions_sequential(
+                (),
+                &transactions,
+                &data_view,
+                &BlockGasLimitType::Limit(15),
+                false,
+                None,
+            )
+            .unwrap(),
+    ] {
+        assert_eq!(output.block_end_info(), Some(expected_end_info));
+        assert_eq!(output.num_committed_txns(), 2);
+        let txn_outputs = output.into_transaction_outputs_forced();
+        assert!(!txn_outputs[1].skipped);
+        assert!(txn_outputs[2].skipped && txn_outputs[3].skipped);
+    }
+}
+
+#[test]
+fn single_worker_parallel_execution() {
+    let num_txns = 100;
+    let transactions: Vec<_> = (0..num_txns)
+        .map(|i| {
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![KeyType::<u32>(i % 10, false)],
+                vec![(KeyType::<u32>((i + 1) % 10, false), random_value(false))],
                 vec![],
                 vec![],
-                1, // gas
-            )));
+                10,
+            ))
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap(),
+    );
+
+    // The materialization workers cannot take the place of the only execution worker, which
+    // materializes its own commits.
+    let record = Arc::new(ReleaseRecord::default());
+    let mut config = BlockExecutorConfig::new_no_block_limit(1);
+    config.local.parallel_single_worker = true;
+    config.local.materialization_concurrency = 2;
+    config.local.record_schedule = true;
+    let block_executor = MockBlockExecutor::new(
+        config,
+        executor_thread_pool,
+        Some(ReleaseRecordingHook(record.clone())),
+    );
+    let output = block_executor.execute_block((), &transactions, &data_view, None, None, None);
+    BaselineOutput::generate(&transactions, None).assert_output(&output);
+    assert_eq!(record.committed.lock().len(), num_txns as usize);
+
+    // The schedule is only recorded by parallel execution.
+    let trace = block_executor.take_schedule_trace().unwrap();
+    assert_eq!(trace.concurrency_level(), 1);
+}
+
+#[test]
+fn streamed_committed_outputs() {
+    let num_txns = 200;
+    let transactions: Vec<_> = (0..num_txns)
+        .map(|i| {
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![KeyType::<u32>(i % 10, false)],
+                vec![(KeyType::<u32>((i + 1) % 10, false), random_value(false))],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+
+    // The dedicated workers may materialize the committed transactions out of order.
+    let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get());
+    config.local.materialization_concurrency = 2;
+    let (block_executor, stream) = MockBlockExecutor::<
+        CommittedOutputStream<MockOutput<KeyType<u32>, MockEvent>>,
+    >::new_with_output_stream(config, new_executor_thread_pool());
+    let output = block_executor
+        .execute_block((), &transactions, &data_view, None, None, None)
+        .unwrap();
+    drop(block_executor);
+
+    let mut items = stream.into_iter();
+    assert_matches!(items.next(), Some(StreamedOutput::Started));
+    let items: Vec<_> = items.collect();
+    assert_eq!(items.len(), num_txns as usize);
+    let outputs = output.get_transaction_outputs_forced();
+    for (expected_idx, item) in items.into_iter().enumerate() {
+        match item {
+            StreamedOutput::Committed(txn_idx, streamed_output) => {
+                assert_eq!(txn_idx as usize, expected_idx);
+                assert_eq!(
+                    streamed_output.writes.len(),
+                    outputs[expected_idx].writes.len()
+                );
+            },
+            item => panic!("Unexpected item {:?} at {}", item, expected_idx),
         }
-        // One transaction that triggers an abort
-        transactions.push(MockTransaction::SkipRest(0))
     }
-    run_and_assert(transactions)
 }
 
 #[test]
-fn scheduler_tasks() {
+fn transaction_profiling() {
+    let num_txns = 100;
+    let transactions: Vec<_> = (0..num_txns)
+        .map(|i| {
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![KeyType::<u32>(i % 5, false)],
+                vec![(KeyType::<u32>((i + 1) % 5, false), random_value(false))],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
+        .collect();
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+
+    let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get());
+    config.local.parallel_single_worker = true;
+    config.local.profile_transactions = true;
+    let block_executor = new_block_executor(config);
+    let output = block_executor.execute_block((), &transactions, &data_view, None, None, None);
+    BaselineOutput::generate(&transactions, None).assert_output(&output);
+
+    // Every transaction was executed and validated (or committed without validation) at least
+    // once, and the profile accounts for all the incarnations of the block.
+    let profile = block_executor.take_execution_profile().unwrap();
+    assert_eq!(profile.transactions.len(), num_txns as usize);
+    let num_incarnations: usize = profile
+        .transactions
+        .iter()
+        .map(|txn_profile| {
+            assert!(!txn_profile.incarnations.is_empty());
+            txn_profile.incarnations.len()
+        })
+        .sum();
+    let stats = block_executor.take_execution_stats().unwrap();
+    assert!(num_incarnations as u64 <= stats.num_incarnations);
+    assert_eq!(profile.slowest_transactions(10).len(), 10);
+    assert!(block_executor.take_execution_profile().is_none());
+}
+
+#[test]
+fn mvhashmap_memory_soft_cap() {
+    let transactions: Vec<_> = (0..10)
+        .map(|_| {
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
+        .collect();
+
+    // The soft cap is exceeded when the first transaction is committed, which ends the block.
+    // Only parallel execution uses the multi-version data-structure.
+    let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get().max(2));
+    config.local.mvhashmap_memory_soft_cap = Some(0);
+    let output = run_block_executor(&transactions, config).unwrap();
+    let block_end_info = output.block_end_info().unwrap();
+    assert_eq!(block_end_info.num_committed_txns, 1);
+    assert_eq!(
+        block_end_info.skip_reason,
+        Some(SkipReason::MVHashMapMemorySoftCap)
+    );
+    let outputs = output.get_transaction_outputs_forced();
+    assert!(!outputs[0].skipped);
+    assert!(outputs[1..].iter().all(|output| output.skipped));
+}
+
+#[test]
+fn record_read_stats() {
+    let transactions: Vec<_> = (0..10)
+        .map(|_| {
+            MockTransaction::<KeyType<u32>, MockEvent>::from_behavior(MockIncarnation::new(
+                vec![
+                    KeyType::<u32>(1, false),
+                    KeyType::<u32>(2, false),
+                    KeyType::<u32>(1, false),
+                ],
+                vec![],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
+        .collect();
+    let value_size = serialize(&STORAGE_AGGREGATOR_VALUE).len() as u64;
+
+    let parallel_concurrency_level = num_cpus::get().max(2);
+    for (concurrency_level, record_read_stats) in [
+        (1, true),
+        (parallel_concurrency_level, true),
+        (parallel_concurrency_level, false),
+    ] {
+        let mut config = BlockExecutorConfig::new_no_block_limit(concurrency_level);
+        config.local.record_read_stats = record_read_stats;
+        let output = run_block_executor(&transactions, config).unwrap();
+        if !record_read_stats {
+            assert_none!(output.read_stats());
+            continue;
+        }
+
+        let read_stats = output.read_stats().unwrap();
+        assert_eq!(read_stats.len(), transactions.len());
+        for stats in read_stats {
+            assert_eq!(stats.num_distinct_reads, 2);
+            assert_eq!(stats.bytes_read, 3 * value_size);
+            assert_le!(stats.num_storage_reads, 2);
+            assert_eq!(stats.num_storage_cache_hits, 0);
+        }
+    }
+}
+
+#[test]
+fn block_end_info_skip_reason() {
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let gas_txn = |gas| {
+        MockTransaction::from_behavior(MockIncarnation::new(vec![], vec![], vec![], vec![], gas))
+    };
+
+    for (block_gas_limit, transactions, expected_block_end_info) in [
+        (None, vec![gas_txn(1), gas_txn(1), gas_txn(1)], (3, None)),
+        (
+            None,
+            vec![gas_txn(1), MockTransaction::SkipRest(1), gas_txn(1)],
+            (2, Some(SkipReason::TransactionSkipRest)),
+        ),
+        // A transaction skipping the rest at the end of the block does not skip any.
+        (
+            None,
+            vec![gas_txn(1), MockTransaction::SkipRest(1)],
+            (2, None),
+        ),
+        (
+            Some(15),
+            vec![gas_txn(10), gas_txn(10), gas_txn(10)],
+            (2, Some(SkipReason::BlockGasLimit)),
+        ),
+    ] {
+        let block_executor = new_block_executor(BlockExecutorConfig::new_maybe_block_limit(
+            num_cpus::get(),
+            block_gas_limit,
+        ));
+
+        let par_output = block_executor
+            .execute_transactions_parallel((), &transactions, &data_view, None, None)
+            .unwrap();
+        assert_eq!(
+            par_output
+                .block_end_info()
+                .map(|info| (info.num_committed_txns, info.skip_reason)),
+            Some(expected_block_end_info)
+        );
+
+        let seq_output = block_executor
+            .execute_transactions_sequential(
+                (),
+                &transactions,
+                &data_view,
+                &block_gas_limit.map_or(BlockGasLimitType::NoLimit, BlockGasLimitType::Limit),
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            seq_output
+                .block_end_info()
+                .map(|info| (info.num_committed_txns, info.skip_reason)),
+            Some(expected_block_end_info)
+        );
+    }
+}
+
+#[test]
+fn block_end_info_of_fully_committed_block() {
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let block_executor = new_block_executor(BlockExecutorConfig::new_maybe_block_limit(
+        num_cpus::get(),
+        Some(100),
+    ));
+    let transactions: Vec<_> = (0..3)
+        .map(|_| {
+            MockTransaction::from_behavior(MockIncarnation::new(vec![], vec![], vec![], vec![], 10))
+        })
+        .collect();
+
+    // Blocks that commit fully (including empty ones) have an end info without a skip reason.
+    for (transactions, expected_block_end_info) in [
+        (transactions, BlockEndInfo {
+            num_committed_txns: 3,
+            skip_reason: None,
+            limit_usage: BlockLimitUsage {
+                effective_block_gas: 30,
+                execution_gas: 15,
+                io_gas: 15,
+                storage_fee: 0,
+                approx_output_size: 0,
+            },
+        }),
+        (vec![], BlockEndInfo {
+            num_committed_txns: 0,
+            skip_reason: None,
+            limit_usage: BlockLimitUsage::default(),
+        }),
+    ] {
+        let par_output = block_executor
+            .execute_transactions_parallel((), &transactions, &data_view, None, None)
+            .unwrap();
+        assert_eq!(par_output.block_end_info(), Some(expected_block_end_info));
+        assert_eq!(par_output.num_committed_txns(), transactions.len());
+
+        let seq_output = block_executor
+            .execute_transactions_sequential(
+                (),
+                &transactions,
+                &data_view,
+                &BlockGasLimitType::Limit(100),
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(seq_output.block_end_info(), Some(expected_block_end_info));
+        assert_eq!(seq_output.num_committed_txns(), transactions.len());
+    }
+}
+
+#[test]
+fn block_category_limits() {
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    // The gas of each transaction is split evenly into execution and IO gas, without storage fee.
+    let transactions: Vec<_> = (0..3)
+        .map(|_| {
+            MockTransaction::from_behavior(MockIncarnation::new(vec![], vec![], vec![], vec![], 10))
+        })
+        .collect();
+    let block_gas_limit_type =
+        |execution_gas_limit, io_gas_limit, storage_fee_limit| BlockGasLimitType::ComplexLimitV2 {
+            effective_block_gas_limit: 1_000_000,
+            execution_gas_effective_multiplier: 1,
+            io_gas_effective_multiplier: 1,
+            conflict_penalty_window: 1,
+            use_granular_resource_group_conflicts: false,
+            use_module_publishing_block_conflict: false,
+            block_output_limit: None,
+            include_user_txn_size_in_block_output: true,
+            add_block_limit_outcome_onchain: false,
+            execution_gas_limit,
+            io_gas_limit,
+            storage_fee_limit,
+            conflict_penalty_decay_percentage: None,
+            txn_output_limit: None,
+        };
+    let limit_usage = |num_txns: u64| BlockLimitUsage {
+        effective_block_gas: 10 * num_txns,
+        execution_gas: 5 * num_txns,
+        io_gas: 5 * num_txns,
+        storage_fee: 0,
+        approx_output_size: 0,
+    };
+
+    for (block_gas_limit_type, expected_block_end_info) in [
+        (
+            block_gas_limit_type(Some(100), Some(100), None),
+            BlockEndInfo {
+                num_committed_txns: 3,
+                skip_reason: None,
+                limit_usage: limit_usage(3),
+            },
+        ),
+        (
+            block_gas_limit_type(Some(8), None, None),
+            BlockEndInfo {
+                num_committed_txns: 2,
+                skip_reason: Some(SkipReason::BlockExecutionGasLimit),
+                limit_usage: limit_usage(2),
+            },
+        ),
+        (
+            block_gas_limit_type(Some(100), Some(5), None),
+            BlockEndInfo {
+                num_committed_txns: 1,
+                skip_reason: Some(SkipReason::BlockIoGasLimit),
+                limit_usage: limit_usage(1),
+            },
+        ),
+        (
+            block_gas_limit_type(None, None, Some(0)),
+            BlockEndInfo {
+                num_committed_txns: 1,
+                skip_reason: Some(SkipReason::BlockStorageFeeLimit),
+                limit_usage: limit_usage(1),
+            },
+        ),
+    ] {
+        let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get().max(2));
+        config.onchain.block_gas_limit_type = block_gas_limit_type.clone();
+        let block_executor = new_block_executor(config);
+
+        let par_output = block_executor
+            .execute_transactions_parallel((), &transactions, &data_view, None, None)
+            .unwrap();
+        assert_eq!(par_output.block_end_info(), Some(expected_block_end_info));
+        let expected_num_committed_txns = expected_block_end_info.num_committed_txns as usize;
+        assert_eq!(par_output.num_committed_txns(), expected_num_committed_txns);
+
+        let seq_output = block_executor
+            .execute_transactions_sequential(
+                (),
+                &transactions,
+                &data_view,
+                &block_gas_limit_type,
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(seq_output.block_end_info(), Some(expected_block_end_info));
+        assert_eq!(seq_output.num_committed_txns(), expected_num_committed_txns);
+    }
+}
+
+#[test]
+fn module_rw_conflict_penalizes_touching_txns() {
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    // Txn 0 publishes a module that txns 1 and 3 read, the other txns touch no module.
+    let module_key = KeyType(100, true);
+    let transactions: Vec<_> = (0..15)
+        .map(|idx| {
+            let (reads, writes) = match idx {
+                0 => (vec![], vec![(
+                    module_key.clone(),
+                    ValueType::from_value(vec![1], true),
+                )]),
+                1 | 3 => (vec![module_key.clone()], vec![]),
+                _ => (vec![], vec![]),
+            };
+            MockTransaction::from_behavior(MockIncarnation::new(reads, writes, vec![], vec![], 10))
+        })
+        .collect();
+    let conflict_penalty_window = 4;
+    let block_gas_limit_type = BlockGasLimitType::ComplexLimitV1 {
+        effective_block_gas_limit: 200,
+        execution_gas_effective_multiplier: 1,
+        io_gas_effective_multiplier: 1,
+        conflict_penalty_window,
+        use_granular_resource_group_conflicts: false,
+        use_module_publishing_block_conflict: true,
+        block_output_limit: None,
+        include_user_txn_size_in_block_output: true,
+        add_block_limit_outcome_onchain: false,
+    };
+
+    let mut config = BlockExecutorConfig::new_no_block_limit(1);
+    config.onchain.block_gas_limit_type = block_gas_limit_type.clone();
+    let block_executor = new_block_executor(config);
+
+    let output = block_executor
+        .execute_transactions_sequential(
+            (),
+            &transactions,
+            &data_view,
+            &block_gas_limit_type,
+            false,
+            None,
+        )
+        .unwrap();
+    // Only txns 1 and 3 are penalized, so the limit is reached at txn 13 (rather than at txn 2,
+    // if the whole block was penalized after the conflict).
+    assert_eq!(
+        output.block_end_info(),
+        Some(BlockEndInfo {
+            num_committed_txns: 14,
+            skip_reason: Some(SkipReason::BlockGasLimit),
+            limit_usage: BlockLimitUsage {
+                effective_block_gas: 12 * 10 + 2 * 10 * conflict_penalty_window as u64,
+                execution_gas: 5 * 14,
+                io_gas: 5 * 14,
+                storage_fee: 0,
+                approx_output_size: 0,
+            },
+        })
+    );
+}
+
+#[test]
+fn block_output_limit_with_events() {
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    // The transactions write nothing, but the data of their events counts towards the output.
+    let transactions: Vec<_> = (0..10)
+        .map(|_| {
+            MockTransaction::from_behavior(MockIncarnation::new(
+                vec![],
+                vec![],
+                vec![],
+                vec![MockEvent::new(vec![0; 60]), MockEvent::new(vec![0; 40])],
+                10,
+            ))
+        })
+        .collect();
+    let block_gas_limit_type = BlockGasLimitType::ComplexLimitV1 {
+        effective_block_gas_limit: 1_000_000,
+        execution_gas_effective_multiplier: 1,
+        io_gas_effective_multiplier: 1,
+        conflict_penalty_window: 1,
+        use_granular_resource_group_conflicts: false,
+        use_module_publishing_block_conflict: false,
+        block_output_limit: Some(250),
+        include_user_txn_size_in_block_output: false,
+        add_block_limit_outcome_onchain: false,
+    };
+    let expected_block_end_info = Some(BlockEndInfo {
+        num_committed_txns: 3,
+        skip_reason: Some(SkipReason::BlockOutputLimit),
+        limit_usage: BlockLimitUsage {
+            effective_block_gas: 30,
+            execution_gas: 15,
+            io_gas: 15,
+            storage_fee: 0,
+            approx_output_size: 300,
+        },
+    });
+
+    let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get().max(2));
+    config.onchain.block_gas_limit_type = block_gas_limit_type.clone();
+    let block_executor = new_block_executor(config);
+
+    let par_output = block_executor
+        .execute_transactions_parallel((), &transactions, &data_view, None, None)
+        .unwrap();
+    assert_eq!(par_output.block_end_info(), expected_block_end_info);
+
+    let seq_output = block_executor
+        .execute_transactions_sequential(
+            (),
+            &transactions,
+            &data_view,
+            &block_gas_limit_type,
+            false,
+            None,
+        )
+        .unwrap();
+    assert_eq!(seq_output.block_end_info(), expected_block_end_info);
+}
+
+/// Executes the block both in parallel and sequentially with the given per-transaction output
+/// limit, and returns the transaction outputs of both executions.
+fn execute_with_txn_output_limit<S>(
+    transactions: &[MockTransaction<KeyType<u32>, MockEvent>],
+    data_view: &S,
+    txn_output_limit: u64,
+) -> Vec<Vec<MockOutput<KeyType<u32>, MockEvent>>>
+where
+    S: TStateView<Key = KeyType<u32>> + Sync,
+{
+    let block_gas_limit_type = BlockGasLimitType::ComplexLimitV2 {
+        effective_block_gas_limit: 1_000_000,
+        execution_gas_effective_multiplier: 1,
+        io_gas_effective_multiplier: 1,
+        conflict_penalty_window: 1,
+        use_granular_resource_group_conflicts: false,
+        use_module_publishing_block_conflict: false,
+        block_output_limit: None,
+        include_user_txn_size_in_block_output: false,
+        add_block_limit_outcome_onchain: false,
+        execution_gas_limit: None,
+        io_gas_limit: None,
+        storage_fee_limit: None,
+        conflict_penalty_decay_percentage: None,
+        txn_output_limit: Some(txn_output_limit),
+    };
+
+    let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get().max(2));
+    config.onchain.block_gas_limit_type = block_gas_limit_type.clone();
+    let block_executor = BlockExecutor::<
+        MockTransaction<KeyType<u32>, MockEvent>,
+        MockTask<KeyType<u32>, MockEvent>,
+        S,
+        MockCommitHook,
+        ExecutableTestType,
+    >::new(config, new_executor_thread_pool(), None);
+
+    let par_output = block_executor
+        .execute_transactions_parallel((), &transactions, data_view, None, None)
+        .unwrap();
+    let seq_output = block_executor
+        .execute_transactions_sequential(
+            (),
+            &transactions,
+            data_view,
+            &block_gas_limit_type,
+            false,
+            None,
+        )
+        .unwrap();
+    vec![
+        par_output.into_transaction_outputs_forced(),
+        seq_output.into_transaction_outputs_forced(),
+    ]
+}
+
+#[test]
+fn txn_output_limit() {
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    // The output of the second transaction exceeds the limit, so the third transaction reads
+    // the value written by the first one.
+    let key = KeyType::<u32>(1, false);
+    let write = |len: usize| {
+        MockTransaction::from_behavior(MockIncarnation::new(
+            vec![],
+            vec![(
+                key.clone(),
+                ValueType::with_len_and_metadata(len, StateValueMetadata::none()),
+            )],
+            vec![],
+            vec![],
+            10,
+        ))
+    };
+    let transactions = vec![
+        write(10),
+        write(100),
+        MockTransaction::from_behavior(MockIncarnation::new(
+            vec![key.clone()],
+            vec![],
+            vec![],
+            vec![],
+            10,
+        )),
+    ];
+
+    for outputs in execute_with_txn_output_limit(&transactions, &data_view, 50) {
+        assert_eq!(
+            outputs
+                .iter()
+                .map(|output| output.skipped)
+                .collect::<Vec<_>>(),
+            vec![false, true, false]
+        );
+        assert_eq!(outputs[2].read_results, vec![Some(vec![100; 10])]);
+    }
+}
+
+#[test]
+fn txn_output_limit_materialized_group() {
+    let group_key = KeyType::<u32>(100, false);
+    let data_view = NonEmptyGroupDataView::<KeyType<u32>> {
+        group_keys: HashSet::from([group_key.clone()]),
+    };
+    let group_write = |tag: u32, len: usize| {
+        let mut incarnation = MockIncarnation::new(vec![], vec![], vec![], vec![], 10);
+        incarnation.group_writes = vec![(
+            group_key.clone(),
+            HashMap::from([(
+                tag,
+                ValueType::with_len_and_metadata(len, StateValueMetadata::none()),
+            )]),
+        )];
+        MockTransaction::from_behavior(incarnation)
+    };
+    let mut read_incarnation = MockIncarnation::new(vec![], vec![], vec![], vec![], 10);
+    read_incarnation.group_reads = vec![(group_key.clone(), 1), (group_key.clone(), 2)];
+    // The second transaction only writes 20 bytes, but the group it materializes (with the
+    // member at RESERVED_TAG and the 30 bytes written by the first transaction) is 67 bytes
+    // long, above the limit, while the materialized group of the first one is 42 bytes long.
+    let transactions = vec![
+        group_write(1, 30),
+        group_write(2, 20),
+        MockTransaction::from_behavior(read_incarnation),
+    ];
+
+    for outputs in execute_with_txn_output_limit(&transactions, &data_view, 50) {
+        assert_eq!(
+            outputs
+                .iter()
+                .map(|output| output.skipped)
+                .collect::<Vec<_>>(),
+            vec![false, true, false]
+        );
+        assert_eq!(outputs[2].read_results, vec![Some(vec![100; 30]), None]);
+    }
+}
+
+// Ends the block after a fixed number of transactions, regardless of their gas.
+struct TxnCountLimitPolicy {
+    block_gas_limit_type: BlockGasLimitType,
+    max_txns: u64,
+    num_txns: u64,
+    finished: Arc<Mutex<Vec<(bool, u32)>>>,
+}
+
+impl BlockLimitPolicy<MockTransaction<KeyType<u32>, MockEvent>> for TxnCountLimitPolicy {
+    fn block_gas_limit_type(&self) -> &BlockGasLimitType {
+        &self.block_gas_limit_type
+    }
+
+    fn accumulate(
+        &mut self,
+        _fee_statement: FeeStatement,
+        _txn_read_write_summary: Option<ReadWriteSummary<MockTransaction<KeyType<u32>, MockEvent>>>,
+        _approx_output_size: Option<u64>,
+    ) {
+        self.num_txns += 1;
+    }
+
+    fn should_end(&mut self, _is_parallel: bool) -> Option<SkipReason> {
+        (self.num_txns >= self.max_txns).then_some(SkipReason::BlockGasLimit)
+    }
+
+    fn finish(&mut self, is_parallel: bool, num_committed: u32, _num_total: u32) {
+        self.finished.lock().push((is_parallel, num_committed));
+    }
+
+    fn limit_usage(&self) -> BlockLimitUsage {
+        BlockLimitUsage {
+            effective_block_gas: self.num_txns,
+            ..BlockLimitUsage::default()
+        }
+    }
+
+    fn snapshot(&self) -> BlockLimitSnapshot {
+        BlockLimitSnapshot {
+            num_accumulated_txns: self.num_txns as u32,
+            limit_usage: self.limit_usage(),
+            last_conflict_multiplier: 1,
+        }
+    }
+}
+
+#[test]
+fn custom_block_limit_policy() {
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let transactions: Vec<_> = (0..10)
+        .map(|_| {
+            MockTransaction::from_behavior(MockIncarnation::new(vec![], vec![], vec![], vec![], 10))
+        })
+        .collect();
+
+    let finished = Arc::new(Mutex::new(vec![]));
+    let policy_finished = finished.clone();
+    let block_executor = new_block_executor(BlockExecutorConfig::new_no_block_limit(
+        num_cpus::get().max(2),
+    ))
+    .with_block_limit_policy(Arc::new(
+        move |block_gas_limit_type: BlockGasLimitType,
+              _num_txns: usize|
+              -> Box<dyn BlockLimitPolicy<MockTransaction<KeyType<u32>, MockEvent>>> {
+            Box::new(TxnCountLimitPolicy {
+                block_gas_limit_type,
+                max_txns: 4,
+                num_txns: 0,
+                finished: policy_finished.clone(),
+            })
+        },
+    ));
+    let expected_block_end_info = Some(BlockEndInfo {
+        num_committed_txns: 4,
+        skip_reason: Some(SkipReason::BlockGasLimit),
+        limit_usage: BlockLimitUsage {
+            effective_block_gas: 4,
+            ..BlockLimitUsage::default()
+        },
+    });
+
+    let par_output = block_executor
+        .execute_transactions_parallel((), &transactions, &data_view, None, None)
+        .unwrap();
+    assert_eq!(par_output.block_end_info(), expected_block_end_info);
+
+    let seq_output = block_executor
+        .execute_transactions_sequential(
+            (),
+            &transactions,
+            &data_view,
+            &BlockGasLimitType::NoLimit,
+            false,
+            None,
+        )
+        .unwrap();
+    assert_eq!(seq_output.block_end_info(), expected_block_end_info);
+    assert_eq!(*finished.lock(), vec![(true, 4), (false, 4)]);
+}
+
+// TODO: add unit test for block gas limit!
+fn run_and_assert<K, E>(transactions: Vec<MockTransaction<K, E>>)
+where
+    K: PartialOrd + Ord + Send + Sync + Clone + Hash + Eq + ModulePath + Debug + 'static,
+    E: Send + Sync + Debug + Clone + TransactionEvent + 'static,
+{
+    let data_view = DeltaDataView::<K> {
+        phantom: PhantomData,
+    };
+
+    let executor_thread_pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_cpus::get())
+            .build()
+            .unwrap(),
+    );
+
+    let output = BlockExecutor::<
+        MockTransaction<K, E>,
+        MockTask<K, E>,
+        DeltaDataView<K>,
+        NoOpTransactionCommitHook<MockOutput<K, E>, usize>,
+        ExecutableTestType,
+    >::new(
+        BlockExecutorConfig::new_no_block_limit(num_cpus::get()),
+        executor_thread_pool,
+        None,
+    )
+    .execute_transactions_parallel((), &transactions, &data_view, None, None);
+
+    let baseline = BaselineOutput::generate(&transactions, None);
+    baseline.assert_parallel_output(&output);
+}
+
+fn random_value(delete_value: bool) -> ValueType {
+    ValueType::from_value(
+        (0..32).map(|_| (random::<u8>())).collect::<Vec<u8>>(),
+        !delete_value,
+    )
+}
+
+#[test]
+fn empty_block() {
+    // This test checks that we do not trigger asserts due to an empty block, e.g. in the
+    // scheduler. Instead, parallel execution should gracefully early return empty output.
+    run_and_assert::<KeyType<[u8; 32]>, MockEvent>(vec![]);
+}
+
+#[test]
+fn delta_counters() {
+    let key = KeyType(random::<[u8; 32]>(), false);
+    let mut transactions = vec![MockTransaction::from_behavior(MockIncarnation::<
+        KeyType<[u8; 32]>,
+        MockEvent,
+    >::new(
+        vec![],
+        vec![(key, random_value(false))], // writes
+        vec![],
+        vec![],
+        1, // gas
+    ))];
+
+    for _ in 0..50 {
+        transactions.push(MockTransaction::from_behavior(MockIncarnation::<
+            KeyType<[u8; 32]>,
+            MockEvent,
+        >::new(
+            vec![key], // reads
+            vec![],
+            vec![(key, delta_add(5, u128::MAX))], // deltas
+            vec![],
+            1, // gas
+        )));
+    }
+
+    transactions.push(MockTransaction::from_behavior(MockIncarnation::<
+        KeyType<[u8; 32]>,
+        MockEvent,
+    >::new(
+        vec![],
+        vec![(key, random_value(false))], // writes
+        vec![],
+        vec![],
+        1, // gas
+    )));
+
+    for _ in 0..50 {
+        transactions.push(MockTransaction::from_behavior(MockIncarnation::<
+            KeyType<[u8; 32]>,
+            MockEvent,
+        >::new(
+            vec![key], // reads
+            vec![],
+            vec![(key, delta_sub(2, u128::MAX))], // deltas
+            vec![],
+            1, // gas
+        )));
+    }
+
+    run_and_assert(transactions)
+}
+
+#[test]
+fn delta_chains() {
+    let mut transactions = vec![];
+    // Generate a series of transactions add and subtract from an aggregator.
+
+    let keys: Vec<KeyType<[u8; 32]>> = (0..10)
+        .map(|_| KeyType(random::<[u8; 32]>(), false))
+        .collect();
+
+    for i in 0..500 {
+        transactions.push(
+            MockTransaction::<KeyType<[u8; 32]>, MockEvent>::from_behavior(MockIncarnation::new(
+                keys.clone(), // reads
+                vec![],
+                keys.iter()
+                    .enumerate()
+                    .filter_map(|(j, k)| match (i + j) % 2 == 0 {
+                        true => Some((
+                            *k,
+                            // Deterministic pattern for adds/subtracts.
+                            DeltaOp::new(
+                                if (i % 2 == 0) == (j < 5) {
+                                    SignedU128::Positive(10)
+                                } else {
+                                    SignedU128::Negative(1)
+                                },
+                                // below params irrelevant for this test.
+                                u128::MAX,
+                                DeltaHistory::new(),
+                            ),
+                        )),
+                        false => None,
+                    })
+                    .collect(), // deltas
+                vec![],
+                1, // gas
+            )),
+        );
+    }
+
+    run_and_assert(transactions)
+}
+
+const TOTAL_KEY_NUM: u64 = 50;
+const WRITES_PER_KEY: u64 = 100;
+
+#[test]
+fn cycle_transactions() {
+    let mut transactions = vec![];
+    // For every key in `TOTAL_KEY_NUM`, generate a series of transactions that will assign a
+    // value to this key.
+    for _ in 0..TOTAL_KEY_NUM {
+        let key = random::<[u8; 32]>();
+        for _ in 0..WRITES_PER_KEY {
+            transactions.push(MockTransaction::from_behavior(MockIncarnation::<
+                KeyType<[u8; 32]>,
+                MockEvent,
+            >::new(
+                vec![KeyType(key, false)],                        // reads
+                vec![(KeyType(key, false), random_value(false))], // writes
+                vec![],
+                vec![],
+                1, // gas
+            )));
+        }
+    }
+    run_and_assert(transactions)
+}
+
+const NUM_BLOCKS: u64 = 10;
+const TXN_PER_BLOCK: u64 = 100;
+
+#[test]
+fn one_reads_all_barrier() {
+    let mut transactions = vec![];
+    let keys: Vec<KeyType<_>> = (0..TXN_PER_BLOCK)
+        .map(|_| KeyType(random::<[u8; 32]>(), false))
+        .collect();
+    for _ in 0..NUM_BLOCKS {
+        for key in &keys {
+            transactions.push(MockTransaction::from_behavior(MockIncarnation::<
+                KeyType<[u8; 32]>,
+                MockEvent,
+            >::new(
+                vec![*key],                        // reads
+                vec![(*key, random_value(false))], // writes
+                vec![],
+                vec![],
+                1, // gas
+            )));
+        }
+        // One transaction reading the write results of every prior transactions in the block.
+        transactions.push(MockTransaction::from_behavior(MockIncarnation::<
+            KeyType<[u8; 32]>,
+            MockEvent,
+        >::new(
+            keys.clone(), //reads
+            vec![],
+            vec![],
+            vec![],
+            1, //gas
+        )));
+    }
+    run_and_assert(transactions)
+}
+
+#[test]
+fn one_writes_all_barrier() {
+    let mut transactions = vec![];
+    let keys: Vec<KeyType<_>> = (0..TXN_PER_BLOCK)
+        .map(|_| KeyType(random::<[u8; 32]>(), false))
+        .collect();
+    for _ in 0..NUM_BLOCKS {
+        for key in &keys {
+            transactions.push(MockTransaction::from_behavior(MockIncarnation::new(
+                vec![*key],                        //reads
+                vec![(*key, random_value(false))], //writes
+                vec![],
+                vec![],
+                1, //gas
+            )));
+        }
+        // One transaction writing to the write results of every prior transactions in the block.
+        transactions.push(MockTransaction::from_behavior(MockIncarnation::<
+            KeyType<[u8; 32]>,
+            MockEvent,
+        >::new(
+            keys.clone(), // reads
+            keys.iter()
+                .map(|key| (*key, random_value(false)))
+                .collect::<Vec<_>>(), //writes
+            vec![],
+            vec![],
+            1, // gas
+        )));
+    }
+    run_and_assert(transactions)
+}
+
+#[test]
+fn early_aborts() {
+    let mut transactions = vec![];
+    let keys: Vec<_> = (0..TXN_PER_BLOCK)
+        .map(|_| KeyType(random::<[u8; 32]>(), false))
+        .collect();
+
+    for _ in 0..NUM_BLOCKS {
+        for key in &keys {
+            transactions.push(MockTransaction::from_behavior(MockIncarnation::<
+                KeyType<[u8; 32]>,
+                MockEvent,
+            >::new(
+                vec![*key],                        // reads
+                vec![(*key, random_value(false))], // writes
+                vec![],
+                vec![],
+                1, // gas
+            )));
+        }
+        // One transaction that triggers an abort
+        transactions.push(MockTransaction::Abort)
+    }
+    run_and_assert(transactions)
+}
+
+#[test]
+fn early_skips() {
+    let mut transactions = vec![];
+    let keys: Vec<_> = (0..TXN_PER_BLOCK)
+        .map(|_| KeyType(random::<[u8; 32]>(), false))
+        .collect();
+
+    for _ in 0..NUM_BLOCKS {
+        for key in &keys {
+            transactions.push(MockTransaction::from_behavior(MockIncarnation::<
+                KeyType<[u8; 32]>,
+                MockEvent,
+            >::new(
+                vec![*key],                        // reads
+                vec![(*key, random_value(false))], //writes
+                vec![],
+                vec![],
+                1, // gas
+            )));
+        }
+        // One transaction that triggers an abort
+        transactions.push(MockTransaction::SkipRest(0))
+    }
+    run_and_assert(transactions)
+}
+
+#[test]
+fn scheduler_tasks() {
+    let s = Scheduler::new(5);
+
+    for i in 0..5 {
+        // No validation tasks.
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if i == j
+        );
+    }
+
+    for i in 0..5 {
+        // Validation index is at 0, so transactions will be validated and no
+        // need to return a validation task to the caller.
+        assert_matches!(s.finish_execution(i, 0, false), Ok(SchedulerTask::NoTask));
+    }
+
+    for i in 0..5 {
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ValidationTask(j, 0, 0) if i == j
+        );
+    }
+
+    // successful aborts.
+    assert!(s.try_abort(3, 0));
+    s.finish_validation(4, 0);
+    assert!(s.try_abort(4, 0)); // can abort even after successful validation
+    assert!(s.try_abort(1, 0));
+
+    // unsuccessful aborts
+    assert!(!s.try_abort(1, 0));
+    assert!(!s.try_abort(3, 0));
+
+    assert_matches!(
+        s.finish_abort(4, 0),
+        Ok(SchedulerTask::ExecutionTask(
+            4,
+            1,
+            ExecutionTaskType::Execution
+        ))
+    );
+    assert_matches!(
+        s.finish_abort(1, 0),
+        Ok(SchedulerTask::ExecutionTask(
+            1,
+            1,
+            ExecutionTaskType::Execution
+        ))
+    );
+    // Validation index = 2, wave = 1.
+    assert_matches!(
+        s.finish_abort(3, 0),
+        Ok(SchedulerTask::ExecutionTask(
+            3,
+            1,
+            ExecutionTaskType::Execution
+        ))
+    );
+
+    assert_matches!(s.finish_execution(4, 1, true), Ok(SchedulerTask::NoTask));
+    assert_matches!(
+        s.finish_execution(1, 1, false),
+        Ok(SchedulerTask::ValidationTask(1, 1, 1))
+    );
+
+    // Another validation task for (2, 0).
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(2, 0, 1));
+    // Now skip over txn 3 (status is Executing), and validate 4.
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(4, 1, 1));
+
+    assert_matches!(
+        s.finish_execution(3, 1, false),
+        Ok(SchedulerTask::ValidationTask(3, 1, 1))
+    );
+
+    s.finish_validation(0, 0);
+    s.finish_validation(1, 2);
+    for i in 2..5 {
+        s.finish_validation(i, 2)
+    }
+
+    // Make sure everything can be committed.
+    for i in 0..5 {
+        assert_matches!(s.try_commit(), Some((v, _)) if v == i);
+    }
+
+    assert_matches!(s.next_task(), SchedulerTask::Done);
+}
+
+#[test]
+fn scheduler_first_wave() {
+    let s = Scheduler::new(6);
+
+    for i in 0..5 {
+        // Nothing to validate.
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if j == i
+        );
+    }
+
+    // validation index will not increase for the first execution wave
+    // until the status becomes executed.
+    assert_matches!(s.finish_execution(0, 0, false), Ok(SchedulerTask::NoTask));
+
+    // Now we can validate version (0, 0).
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(0, 0, 0));
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(5, 0, ExecutionTaskType::Execution)
+    );
+    // Since (1, 0) is not EXECUTED, no validation tasks, and execution index
+    // is already at the limit, so no tasks immediately available.
+    assert_matches!(s.next_task(), SchedulerTask::NoTask);
+
+    assert_matches!(s.finish_execution(2, 0, false), Ok(SchedulerTask::NoTask));
+    // There should be no tasks, but finishing (1,0) should enable validating
+    // (1, 0) then (2,0).
+    assert_matches!(s.next_task(), SchedulerTask::NoTask);
+
+    assert_matches!(s.finish_execution(1, 0, false), Ok(SchedulerTask::NoTask));
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(1, 0, 0));
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(2, 0, 0));
+    assert_matches!(s.next_task(), SchedulerTask::NoTask);
+}
+
+#[test]
+fn scheduler_dependency() {
+    let s = Scheduler::new(10);
+
+    for i in 0..5 {
+        // Nothing to validate.
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if j == i
+        );
+    }
+
+    // validation index will not increase for the first execution wave
+    // until the status becomes executed.
+    assert_matches!(s.finish_execution(0, 0, false), Ok(SchedulerTask::NoTask));
+    // Now we can validate version (0, 0).
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(0, 0, 0));
+    // Current status of 0 is executed - hence, no dependency added.
+    assert_matches!(s.wait_for_dependency(3, 0), Ok(DependencyResult::Resolved));
+    // Dependency added for transaction 4 on transaction 2.
+    assert_matches!(
+        s.wait_for_dependency(4, 2),
+        Ok(DependencyResult::Dependency(_))
+    );
+
+    assert_matches!(s.finish_execution(2, 0, false), Ok(SchedulerTask::NoTask));
+
+    // resumed task doesn't bump incarnation
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(4, 0, ExecutionTaskType::Wakeup(_))
+    );
+}
+
+#[test]
+fn scheduler_dependency_requeue() {
+    let s = Scheduler::new(5).with_dependency_wait(DependencyWaitConfig {
+        requeue: true,
+        wait_budget: Some(1),
+        speculate_past_estimates: false,
+    });
+
+    for i in 0..5 {
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if j == i
+        );
+    }
+    assert!(!s.dependency_wait_budget_exhausted(4));
+
+    // Transaction 4 is requeued on transaction 2, no worker waits.
+    assert_matches!(s.wait_for_dependency(4, 2), Ok(DependencyResult::Requeued));
+    assert_eq!(s.num_waiting_workers(), 0);
+    assert!(s.dependency_wait_budget_exhausted(4));
+    assert!(!s.dependency_wait_budget_exhausted(3));
+
+    assert_matches!(s.finish_execution(2, 0, false), Ok(SchedulerTask::NoTask));
+
+    // The same incarnation is executed again, from the start.
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(4, 0, ExecutionTaskType::Execution)
+    );
+}
+
+#[test]
+fn scheduler_read_only() {
+    let s = Scheduler::new(2);
+
+    for i in 0..2 {
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if j == i
+        );
+    }
+    assert_matches!(
+        s.finish_read_only_execution(1, 0),
+        Ok(SchedulerTask::NoTask)
+    );
+    assert_matches!(s.finish_execution(0, 0, false), Ok(SchedulerTask::NoTask));
+
+    // Only transaction 0 is validated.
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(0, 0, 0));
+    assert_matches!(s.next_task(), SchedulerTask::NoTask);
+    s.finish_validation(0, 0);
+
+    // Transaction 1 is committed without a validation, to be validated by the caller.
+    assert_matches!(s.try_commit(), Some((0, 0)));
+    assert!(!s.is_validated_at_commit(0));
+    assert_matches!(s.try_commit(), Some((1, 0)));
+    assert!(s.is_validated_at_commit(1));
+    assert_matches!(s.next_task(), SchedulerTask::Done);
+}
+
+#[test]
+fn scheduler_max_incarnations() {
+    let s = Scheduler::new(3).with_max_incarnations(Some(1));
+
+    for i in 0..2 {
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if j == i
+        );
+    }
+    for i in 0..2 {
+        assert_matches!(s.finish_execution(i, 0, false), Ok(SchedulerTask::NoTask));
+    }
+    for i in 0..2 {
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ValidationTask(j, 0, 0) if j == i
+        );
+    }
+    s.finish_validation(0, 0);
+
+    // Transaction 1 reached the maximum incarnations, so it is not re-executed speculatively.
+    assert!(s.try_abort(1, 0));
+    assert_matches!(s.finish_abort(1, 0), Ok(SchedulerTask::NoTask));
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(2, 0, ExecutionTaskType::Execution)
+    );
+    // No worker waits on the deferred transaction.
+    assert_matches!(s.wait_for_dependency(2, 1), Ok(DependencyResult::Requeued));
+    assert_eq!(s.num_waiting_workers(), 0);
+    assert_matches!(s.next_task(), SchedulerTask::NoTask);
+
+    // Transaction 1 is committed once transaction 0 is, then executed during commit.
+    assert_matches!(s.try_commit_deferred(), None);
+    assert_matches!(s.try_commit(), Some((0, 0)));
+    assert_matches!(s.try_commit(), None);
+    assert_matches!(s.try_commit_deferred(), Some((1, 1, false)));
+    assert_matches!(s.finish_execution_during_commit(1), Ok(()));
+
+    // The requeued transaction is executed again, from the start.
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(2, 0, ExecutionTaskType::Execution)
+    );
+}
+
+#[test]
+fn scheduler_soft_failure() {
+    let s = Scheduler::new(3);
+
+    for i in 0..2 {
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if j == i
+        );
+    }
+    for i in 0..2 {
+        assert_matches!(s.finish_execution(i, 0, false), Ok(SchedulerTask::NoTask));
+    }
+    for i in 0..2 {
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ValidationTask(j, 0, 0) if j == i
+        );
+    }
+    s.finish_validation(0, 0);
+
+    // Transaction 1 is not re-executed speculatively, and soft-fails once.
+    assert!(s.try_soft_fail(1, 0));
+    assert!(!s.try_soft_fail(1, 0));
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(2, 0, ExecutionTaskType::Execution)
+    );
+    // The writes of the soft-failed incarnation are kept, so no worker waits on it.
+    assert_matches!(s.wait_for_dependency(2, 1), Ok(DependencyResult::Resolved));
+
+    // Transaction 1 is committed once transaction 0 is, then executed during commit.
+    assert_matches!(s.try_commit_deferred(), None);
+    assert_matches!(s.try_commit(), Some((0, 0)));
+    assert_matches!(s.try_commit(), None);
+    assert_matches!(s.try_commit_deferred(), Some((1, 1, true)));
+    assert_matches!(s.finish_execution_during_commit(1), Ok(()));
+    assert_eq!(s.commit_state(), (2, 0));
+}
+
+#[test]
+fn scheduler_soft_failure_abort() {
+    let s = Scheduler::new(2);
+
+    for i in 0..2 {
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if j == i
+        );
+    }
+    for i in 0..2 {
+        assert_matches!(s.finish_execution(i, 0, false), Ok(SchedulerTask::NoTask));
+    }
+    for i in 0..2 {
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ValidationTask(j, 0, 0) if j == i
+        );
+    }
+    assert!(s.try_soft_fail(1, 0));
+
+    // Re-executing transaction 0 leads to the validation of the soft-failed incarnation.
+    assert!(s.try_abort(0, 0));
+    assert_matches!(
+        s.finish_abort(0, 0),
+        Ok(SchedulerTask::ExecutionTask(
+            0,
+            1,
+            ExecutionTaskType::Execution
+        ))
+    );
+    assert_matches!(
+        s.finish_execution(0, 1, true),
+        Ok(SchedulerTask::ValidationTask(0, 1, _))
+    );
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(1, 0, _));
+
+    // Once its other reads are invalid, the soft-failed transaction is re-executed right away.
+    assert!(s.try_abort(1, 0));
+    assert_matches!(
+        s.finish_abort(1, 0),
+        Ok(SchedulerTask::ExecutionTask(
+            1,
+            1,
+            ExecutionTaskType::Execution
+        ))
+    );
+    assert_matches!(s.try_commit_deferred(), None);
+}
+
+#[test]
+fn hinted_dependencies_from_access_hints() {
+    let hints = vec![
+        AccessHint::new(vec![1], vec![1]),
+        AccessHint::new(vec![2], vec![2]),
+        AccessHint::new(vec![1, 2], vec![3]),
+        AccessHint::new(vec![4], vec![]),
+        AccessHint::new(vec![1, 3], vec![1]),
+    ];
+    assert_eq!(hinted_dependencies(&hints), vec![
+        None,
+        None,
+        Some(1),
+        None,
+        Some(2)
+    ]);
+}
+
+#[test]
+fn conflict_free_prefix_from_access_hints() {
+    let hints = vec![
+        AccessHint::new(vec![1], vec![1]),
+        AccessHint::new(vec![2, 4], vec![2]),
+        AccessHint::new(vec![4], vec![3]),
+        AccessHint::new(vec![3], vec![]),
+        AccessHint::new(vec![], vec![5]),
+    ];
+    // Transaction 3 reads the key written by transaction 2.
+    assert_eq!(conflict_free_prefix(&hints), 3);
+    assert_eq!(conflict_free_prefix(&hints[..3]), 3);
+    assert_eq!(conflict_free_prefix(&hints[3..]), 2);
+    // Transaction 1 writes the key read by transaction 0.
+    assert_eq!(
+        conflict_free_prefix(&[
+            AccessHint::new(vec![1], vec![]),
+            AccessHint::new(vec![], vec![1]),
+        ]),
+        1
+    );
+}
+
+#[test]
+fn scheduler_conflict_free_prefix() {
+    let s = Scheduler::new(3).with_conflict_free_prefix(2);
+
+    for i in 0..3 {
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if j == i
+        );
+    }
+    assert_matches!(s.finish_execution(2, 0, false), Ok(SchedulerTask::NoTask));
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(2, 0, 0));
+
+    // The transactions of the prefix are not validated, but only the transactions after it
+    // are validated again after their writes.
+    assert_matches!(s.finish_execution(0, 0, true), Ok(SchedulerTask::NoTask));
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(2, 0, 1));
+    assert_matches!(s.finish_execution(1, 0, true), Ok(SchedulerTask::NoTask));
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(2, 0, 2));
+    assert_matches!(s.next_task(), SchedulerTask::NoTask);
+
+    assert_matches!(s.try_commit(), Some((0, 0)));
+    assert!(s.is_validated_at_commit(0));
+    assert_matches!(s.try_commit(), Some((1, 0)));
+    assert!(s.is_validated_at_commit(1));
+    assert_matches!(s.try_commit(), None);
+    s.finish_validation(2, 2);
+    assert_matches!(s.try_commit(), Some((2, 0)));
+    assert!(!s.is_validated_at_commit(2));
+}
+
+#[test]
+fn scheduler_hinted_dependency() {
+    let s = Scheduler::new_with_hinted_dependencies(4, vec![None, None, Some(0), None]);
+
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(0, 0, ExecutionTaskType::Execution)
+    );
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(1, 0, ExecutionTaskType::Execution)
+    );
+    // Transaction 2 is deferred until transaction 0 is executed.
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(3, 0, ExecutionTaskType::Execution)
+    );
+    assert_matches!(s.next_task(), SchedulerTask::NoTask);
+
+    // Executing transaction 0 makes transaction 2 schedulable again.
+    assert_matches!(s.finish_execution(0, 0, false), Ok(SchedulerTask::NoTask));
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(0, 0, 0));
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(2, 0, ExecutionTaskType::Execution)
+    );
+}
+
+#[test]
+fn gas_price_priority_order() {
+    // Transactions 0 and 5 are not user transactions.
+    assert_eq!(gas_price_priority(&[0, 100, 300, 100, 200, 0, 300]), vec![
+        2, 6, 4
+    ]);
+    assert_eq!(gas_price_priority(&[0, 100, 100]), Vec::<TxnIndex>::new());
+    assert_eq!(gas_price_priority(&[]), Vec::<TxnIndex>::new());
+}
+
+#[test]
+fn scheduler_execution_priority() {
+    let s = Scheduler::new_with_hinted_dependencies(6, vec![None, None, None, None, None, Some(0)])
+        .with_execution_priority(vec![3, 5, 1]);
+
+    // The prioritized transactions are started first, except for the one with a hinted
+    // dependency.
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(3, 0, ExecutionTaskType::Execution)
+    );
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(1, 0, ExecutionTaskType::Execution)
+    );
+    // The remaining transactions are started in order.
+    for txn_idx in [0, 2, 4] {
+        assert_matches!(
+            s.next_task(),
+            SchedulerTask::ExecutionTask(idx, 0, ExecutionTaskType::Execution) if idx == txn_idx
+        );
+    }
+    assert_matches!(s.next_task(), SchedulerTask::NoTask);
+
+    // Transaction 5 is started once its hinted dependency is executed.
+    assert_matches!(s.finish_execution(0, 0, false), Ok(SchedulerTask::NoTask));
+    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(0, 0, 0));
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(5, 0, ExecutionTaskType::Execution)
+    );
+}
+
+#[test]
+fn scheduler_commit_reexecution_handoff() {
+    let s = Scheduler::new(2);
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(0, 0, ExecutionTaskType::Execution)
+    );
+
+    // A handed off re-execution is taken (once) before any other task.
+    s.hand_off_commit_reexecution(0, 1);
+    assert_matches!(s.next_task(), SchedulerTask::CommitReExecutionTask(0, 1));
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(1, 0, ExecutionTaskType::Execution)
+    );
+    assert_matches!(s.next_task(), SchedulerTask::NoTask);
+}
+
+#[test]
+fn scheduler_commit_queue_batches() {
     let s = Scheduler::new(5);
+    assert_eq!(s.pop_from_commit_queue(2), 0..0);
 
-    for i in 0..5 {
-        // No validation tasks.
-        assert_matches!(
-            s.next_task(),
-            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if i == j
-        );
+    for txn_idx in 0..3 {
+        s.add_to_commit_queue(txn_idx);
     }
+    assert_eq!(s.pop_from_commit_queue(2), 0..2);
+    s.add_to_commit_queue(3);
+    assert_eq!(s.pop_from_commit_queue(10), 2..4);
+    assert_eq!(s.pop_from_commit_queue(10), 4..4);
 
-    for i in 0..5 {
-        // Validation index is at 0, so transactions will be validated and no
-        // need to return a validation task to the caller.
-        assert_matches!(s.finish_execution(i, 0, false), Ok(SchedulerTask::NoTask));
+    // Concurrent pops get disjoint batches, together covering all the pushed transactions.
+    let num_txns = 1000;
+    let s = Scheduler::new(num_txns);
+    for txn_idx in 0..num_txns {
+        s.add_to_commit_queue(txn_idx);
     }
+    let mut popped: Vec<TxnIndex> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut popped = vec![];
+                    loop {
+                        let batch = s.pop_from_commit_queue(7);
+                        if batch.is_empty() {
+                            return popped;
+                        }
+                        popped.extend(batch);
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+    popped.sort();
+    assert_eq!(popped, (0..num_txns).collect::<Vec<_>>());
+}
 
-    for i in 0..5 {
-        assert_matches!(
-            s.next_task(),
-            SchedulerTask::ValidationTask(j, 0, 0) if i == j
+#[test]
+fn access_hints_parallel_execution() {
+    let num_txns = 100;
+    let keys: Vec<KeyType<u32>> = (0..5).map(|key| KeyType(key, false)).collect();
+    let mut transactions = vec![];
+    let mut hints = vec![];
+    for i in 0..num_txns {
+        let key = keys[i % keys.len()].clone();
+        let mock_incarnation = MockIncarnation::new(
+            vec![key.clone()],
+            vec![(key.clone(), random_value(false))],
+            vec![],
+            vec![],
+            10,
         );
+        transactions.push(MockTransaction::from_behavior(mock_incarnation));
+        hints.push(AccessHint::new(vec![key.clone()], vec![key]));
     }
 
-    // successful aborts.
-    assert!(s.try_abort(3, 0));
-    s.finish_validation(4, 0);
-    assert!(s.try_abort(4, 0)); // can abort even after successful validation
-    assert!(s.try_abort(1, 0));
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let output = new_block_executor(BlockExecutorConfig::new_no_block_limit(
+        num_cpus::get().max(2),
+    ))
+    .execute_transactions_parallel(
+        (),
+        &transactions,
+        &data_view,
+        Some(BlockHints::Access(&hints)),
+        None,
+    );
 
-    // unsuccessful aborts
-    assert!(!s.try_abort(1, 0));
-    assert!(!s.try_abort(3, 0));
+    BaselineOutput::generate(&transactions, None).assert_parallel_output(&output);
+}
 
-    assert_matches!(
-        s.finish_abort(4, 0),
-        Ok(SchedulerTask::ExecutionTask(
-            4,
-            1,
-            ExecutionTaskType::Execution
-        ))
-    );
-    assert_matches!(
-        s.finish_abort(1, 0),
-        Ok(SchedulerTask::ExecutionTask(
-            1,
-            1,
-            ExecutionTaskType::Execution
-        ))
-    );
-    // Validation index = 2, wave = 1.
-    assert_matches!(
-        s.finish_abort(3, 0),
-        Ok(SchedulerTask::ExecutionTask(
-            3,
-            1,
-            ExecutionTaskType::Execution
-        ))
-    );
+#[test]
+fn block_pipeline_execution() {
+    let read_keys: Vec<KeyType<u32>> = (0..5).map(|key| KeyType(key, false)).collect();
+    let block_transactions = |first_write_key: u32| -> Vec<_> {
+        (0..50)
+            .map(|i| {
+                let write_key = KeyType(first_write_key + i % 5, false);
+                let mock_incarnation = MockIncarnation::new(
+                    vec![
+                        read_keys[i as usize % read_keys.len()].clone(),
+                        write_key.clone(),
+                    ],
+                    vec![(write_key, random_value(false))],
+                    vec![],
+                    vec![],
+                    10,
+                );
+                MockTransaction::from_behavior(mock_incarnation)
+            })
+            .collect()
+    };
+    // The blocks write disjoint keys, so the same base view is valid for both.
+    let first_block = block_transactions(100);
+    let second_block = block_transactions(200);
 
-    assert_matches!(s.finish_execution(4, 1, true), Ok(SchedulerTask::NoTask));
-    assert_matches!(
-        s.finish_execution(1, 1, false),
-        Ok(SchedulerTask::ValidationTask(1, 1, 1))
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let results = new_block_executor(BlockExecutorConfig::new_no_block_limit(
+        num_cpus::get().max(2),
+    ))
+    .execute_block_pipeline(
+        (),
+        vec![
+            (first_block.as_slice(), &data_view),
+            (second_block.as_slice(), &data_view),
+        ],
+        None,
     );
 
-    // Another validation task for (2, 0).
-    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(2, 0, 1));
-    // Now skip over txn 3 (status is Executing), and validate 4.
-    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(4, 1, 1));
+    assert_eq!(results.len(), 2);
+    BaselineOutput::generate(&first_block, None).assert_output(&results[0]);
+    BaselineOutput::generate(&second_block, None).assert_output(&results[1]);
+}
 
-    assert_matches!(
-        s.finish_execution(3, 1, false),
-        Ok(SchedulerTask::ValidationTask(3, 1, 1))
+#[test]
+fn prefetch_base_values_parallel_execution() {
+    let num_txns = 100;
+    let keys: Vec<KeyType<u32>> = (0..10).map(|key| KeyType(key, false)).collect();
+    let mut transactions = vec![];
+    let mut hints = vec![];
+    for i in 0..num_txns {
+        let read_key = keys[i % keys.len()].clone();
+        let write_key = keys[(i + 1) % keys.len()].clone();
+        let mock_incarnation = MockIncarnation::new(
+            vec![read_key.clone()],
+            vec![(write_key.clone(), random_value(false))],
+            vec![],
+            vec![],
+            10,
+        );
+        transactions.push(MockTransaction::from_behavior(mock_incarnation));
+        hints.push(AccessHint::new(vec![read_key], vec![write_key]));
+    }
+
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get().max(2));
+    config.local.prefetch_base_values = true;
+    let output = new_block_executor(config).execute_transactions_parallel(
+        (),
+        &transactions,
+        &data_view,
+        Some(BlockHints::Access(&hints)),
+        None,
     );
 
-    s.finish_validation(0, 0);
-    s.finish_validation(1, 2);
-    for i in 2..5 {
-        s.finish_validation(i, 2)
+    BaselineOutput::generate(&transactions, None).assert_parallel_output(&output);
+}
+
+#[test]
+fn dependency_wait_parallel_execution() {
+    // All transactions read and write among a few keys, for frequent dependencies.
+    let num_txns = 200;
+    let keys: Vec<KeyType<u32>> = (0..4).map(|key| KeyType(key, false)).collect();
+    let transactions: Vec<_> = (0..num_txns)
+        .map(|i| {
+            MockTransaction::from_behavior(MockIncarnation::new(
+                vec![
+                    keys[i % keys.len()].clone(),
+                    keys[(i + 2) % keys.len()].clone(),
+                ],
+                vec![(keys[(i + 1) % keys.len()].clone(), random_value(false))],
+                vec![],
+                vec![],
+                10,
+            ))
+        })
+        .collect();
+
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let baseline = BaselineOutput::generate(&transactions, None);
+    for (requeue, wait_budget, speculate_past_estimates) in [
+        (true, None, false),
+        (false, Some(1), false),
+        (true, Some(2), false),
+        (false, None, true),
+    ] {
+        let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get().max(2));
+        config.local.dependency_wait = DependencyWaitConfig {
+            requeue,
+            wait_budget,
+            speculate_past_estimates,
+        };
+        let output = new_block_executor(config).execute_transactions_parallel(
+            (),
+            &transactions,
+            &data_view,
+            None,
+            None,
+        );
+
+        baseline.assert_parallel_output(&output);
     }
 
-    // Make sure everything can be committed.
-    for i in 0..5 {
-        assert_matches!(s.try_commit(), Some((v, _)) if v == i);
+    for (max_incarnations, requeue) in [(1, false), (2, false), (1, true)] {
+        let mut config = BlockExecutorConfig::new_no_block_limit(num_cpus::get().max(2));
+        config.local.max_incarnations = Some(max_incarnations);
+        config.local.dependency_wait.requeue = requeue;
+        let output = new_block_executor(config).execute_transactions_parallel(
+            (),
+            &transactions,
+            &data_view,
+            None,
+            None,
+        );
+
+        baseline.assert_parallel_output(&output);
     }
+}
 
-    assert_matches!(s.next_task(), SchedulerTask::Done);
+#[test]
+fn read_only_parallel_execution() {
+    // Every other transaction only reads the keys written by the others.
+    let num_txns = 200;
+    let keys: Vec<KeyType<u32>> = (0..4).map(|key| KeyType(key, false)).collect();
+    let transactions: Vec<_> = (0..num_txns)
+        .map(|i| {
+            let writes = if i % 2 == 0 {
+                vec![(keys[i % keys.len()].clone(), random_value(false))]
+            } else {
+                vec![]
+            };
+            MockTransaction::from_behavior(MockIncarnation::new(
+                vec![
+                    keys[i % keys.len()].clone(),
+                    keys[(i + 1) % keys.len()].clone(),
+                ],
+                writes,
+                vec![],
+                vec![],
+                10,
+            ))
+        })
+        .collect();
+
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let output = new_block_executor(BlockExecutorConfig::new_no_block_limit(
+        num_cpus::get().max(2),
+    ))
+    .execute_transactions_parallel((), &transactions, &data_view, None, None);
+
+    BaselineOutput::generate(&transactions, None).assert_parallel_output(&output);
 }
 
 #[test]
-fn scheduler_first_wave() {
-    let s = Scheduler::new(6);
+fn conflict_graph_recording() {
+    // Transaction i reads key i % 10 and writes key (i + 1) % 10, i.e. it reads the write of
+    // transaction i - 1.
+    let num_txns = 50;
+    let keys: Vec<KeyType<u32>> = (0..10).map(|key| KeyType(key, false)).collect();
+    let transactions: Vec<_> = (0..num_txns)
+        .map(|i| {
+            let mock_incarnation = MockIncarnation::new(
+                vec![keys[i % keys.len()].clone()],
+                vec![(keys[(i + 1) % keys.len()].clone(), random_value(false))],
+                vec![],
+                vec![],
+                10,
+            );
+            MockTransaction::from_behavior(mock_incarnation)
+        })
+        .collect();
 
-    for i in 0..5 {
-        // Nothing to validate.
-        assert_matches!(
-            s.next_task(),
-            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if j == i
-        );
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+
+    // Both sequential and parallel execution record the conflict graph.
+    for concurrency_level in [1, num_cpus::get().max(2)] {
+        let mut config = BlockExecutorConfig::new_no_block_limit(concurrency_level);
+        config.local.record_conflict_graph = true;
+        let block_executor = new_block_executor(config);
+        let output = block_executor.execute_block((), &transactions, &data_view, None, None, None);
+        BaselineOutput::generate(&transactions, None).assert_output(&output);
+
+        let conflict_graph = block_executor.take_conflict_graph().unwrap();
+        assert_eq!(conflict_graph.num_txns(), num_txns);
+        assert!(conflict_graph.dependencies(0).is_empty());
+        for txn_idx in 1..num_txns as TxnIndex {
+            assert_eq!(conflict_graph.dependencies(txn_idx), &[txn_idx - 1]);
+        }
+        assert_eq!(conflict_graph.num_edges(), num_txns - 1);
+        assert!(block_executor.take_conflict_graph().is_none());
     }
+}
 
-    // validation index will not increase for the first execution wave
-    // until the status becomes executed.
-    assert_matches!(s.finish_execution(0, 0, false), Ok(SchedulerTask::NoTask));
+#[test]
+fn schedule_record_and_replay() {
+    // Independent transactions, so that the replay does not depend on the timing of the reads.
+    let num_txns = 30;
+    let transactions: Vec<_> = (0..num_txns)
+        .map(|i| {
+            let key = KeyType(i as u32, false);
+            let mock_incarnation = MockIncarnation::new(
+                vec![key.clone()],
+                vec![(key, random_value(false))],
+                vec![],
+                vec![],
+                10,
+            );
+            MockTransaction::from_behavior(mock_incarnation)
+        })
+        .collect();
+
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let concurrency_level = num_cpus::get().max(2);
+    let mut config = BlockExecutorConfig::new_no_block_limit(concurrency_level);
+    config.local.record_schedule = true;
+    let block_executor = new_block_executor(config);
+
+    let baseline = BaselineOutput::generate(&transactions, None);
+    let output = block_executor.execute_block((), &transactions, &data_view, None, None, None);
+    baseline.assert_output(&output);
+
+    let trace = block_executor.take_schedule_trace().unwrap();
+    assert_eq!(trace.concurrency_level(), concurrency_level);
+    assert_eq!(trace.num_txns(), num_txns as TxnIndex);
+    let commits: Vec<_> = trace
+        .events()
+        .iter()
+        .filter_map(|event| match event {
+            ScheduleEvent::Commit(txn_idx) => Some(*txn_idx),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(commits, (0..num_txns as TxnIndex).collect::<Vec<_>>());
+    for txn_idx in 0..num_txns as TxnIndex {
+        assert!(trace.events().contains(&ScheduleEvent::Execute(txn_idx, 0)));
+    }
+    assert_eq!(ScheduleTrace::from_bytes(&trace.to_bytes()).unwrap(), trace);
+
+    let replay_output = block_executor
+        .replay_schedule((), &transactions, &data_view, None, trace.clone())
+        .unwrap();
+    baseline.assert_output(&Ok(replay_output));
+    assert_eq!(block_executor.take_schedule_trace().unwrap(), trace);
+
+    // A schedule that is not replayed in full diverges.
+    let mut events = trace.events().to_vec();
+    events.push(ScheduleEvent::Commit(0));
+    let extended_trace = ScheduleTrace::new(concurrency_level, num_txns as TxnIndex, events);
+    assert_matches!(
+        block_executor.replay_schedule((), &transactions, &data_view, None, extended_trace),
+        Err(ScheduleReplayError::ExecutionFailure(
+            ParallelExecutionFailure::CodeInvariantError(_)
+        ))
+    );
+
+    // The concurrency level must match the recorded one.
+    let trace = ScheduleTrace::new(concurrency_level + 1, num_txns as TxnIndex, vec![]);
+    assert_matches!(
+        block_executor.replay_schedule((), &transactions, &data_view, None, trace),
+        Err(ScheduleReplayError::TraceMismatch { .. })
+    );
+}
+
+#[test]
+fn scheduler_partitioned_block() {
+    let partitioning = BlockPartitioning::new(vec![Some(5), Some(7), Some(5), None]);
+    assert_eq!(partitioning.num_partitions(), 2);
+    let s = Scheduler::new_with_partitioning(4, &partitioning);
 
-    // Now we can validate version (0, 0).
-    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(0, 0, 0));
     assert_matches!(
         s.next_task(),
-        SchedulerTask::ExecutionTask(5, 0, ExecutionTaskType::Execution)
+        SchedulerTask::ExecutionTask(0, 0, ExecutionTaskType::Execution)
+    );
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(1, 0, ExecutionTaskType::Execution)
+    );
+    // Transaction 2 is deferred until the previous transaction of its partition is executed.
+    assert_matches!(
+        s.next_task(),
+        SchedulerTask::ExecutionTask(3, 0, ExecutionTaskType::Execution)
     );
-    // Since (1, 0) is not EXECUTED, no validation tasks, and execution index
-    // is already at the limit, so no tasks immediately available.
     assert_matches!(s.next_task(), SchedulerTask::NoTask);
 
-    assert_matches!(s.finish_execution(2, 0, false), Ok(SchedulerTask::NoTask));
-    // There should be no tasks, but finishing (1,0) should enable validating
-    // (1, 0) then (2,0).
-    assert_matches!(s.next_task(), SchedulerTask::NoTask);
+    // The cross-partition transaction 3 can not affect earlier transactions.
+    assert_matches!(s.finish_execution(3, 0, true), Ok(SchedulerTask::NoTask));
+    assert!(s.is_validation_redundant(1, 0));
+    assert!(!s.is_validation_redundant(3, 0));
 
-    assert_matches!(s.finish_execution(1, 0, false), Ok(SchedulerTask::NoTask));
-    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(1, 0, 0));
-    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(2, 0, 0));
-    assert_matches!(s.next_task(), SchedulerTask::NoTask);
+    // The worker continues with the next transaction of the partition.
+    assert_matches!(
+        s.finish_execution(0, 0, false),
+        Ok(SchedulerTask::ExecutionTask(
+            2,
+            0,
+            ExecutionTaskType::Execution
+        ))
+    );
+    assert!(s.is_validation_redundant(0, 0));
+    assert!(s.is_validation_redundant(2, 0));
+
+    // Aborting transaction 0 may affect transaction 2, but not transaction 1.
+    assert!(s.try_abort(0, 0));
+    assert_matches!(
+        s.finish_abort(0, 0),
+        Ok(SchedulerTask::ExecutionTask(
+            0,
+            1,
+            ExecutionTaskType::Execution
+        ))
+    );
+    assert!(!s.is_validation_redundant(2, 0));
+    assert!(s.is_validation_redundant(1, 0));
 }
 
 #[test]
-fn scheduler_dependency() {
-    let s = Scheduler::new(10);
-
-    for i in 0..5 {
-        // Nothing to validate.
-        assert_matches!(
-            s.next_task(),
-            SchedulerTask::ExecutionTask(j, 0, ExecutionTaskType::Execution) if j == i
+fn partitioned_parallel_execution() {
+    let num_txns = 100;
+    let keys: Vec<KeyType<u32>> = (0..5).map(|key| KeyType(key, false)).collect();
+    let mut transactions = vec![];
+    let mut partitions = vec![];
+    for i in 0..num_txns {
+        let key = keys[i % keys.len()].clone();
+        // Every 10th transaction also accesses the key of the next partition.
+        let (accessed_keys, partition) = if i % 10 == 9 {
+            (vec![key, keys[(i + 1) % keys.len()].clone()], None)
+        } else {
+            (vec![key], Some(i % keys.len()))
+        };
+        let mock_incarnation = MockIncarnation::new(
+            accessed_keys.clone(),
+            accessed_keys
+                .into_iter()
+                .map(|key| (key, random_value(false)))
+                .collect(),
+            vec![],
+            vec![],
+            10,
         );
+        transactions.push(MockTransaction::from_behavior(mock_incarnation));
+        partitions.push(partition);
     }
 
-    // validation index will not increase for the first execution wave
-    // until the status becomes executed.
-    assert_matches!(s.finish_execution(0, 0, false), Ok(SchedulerTask::NoTask));
-    // Now we can validate version (0, 0).
-    assert_matches!(s.next_task(), SchedulerTask::ValidationTask(0, 0, 0));
-    // Current status of 0 is executed - hence, no dependency added.
-    assert_matches!(s.wait_for_dependency(3, 0), Ok(DependencyResult::Resolved));
-    // Dependency added for transaction 4 on transaction 2.
-    assert_matches!(
-        s.wait_for_dependency(4, 2),
-        Ok(DependencyResult::Dependency(_))
+    let data_view = DeltaDataView::<KeyType<u32>> {
+        phantom: PhantomData,
+    };
+    let output = new_block_executor(BlockExecutorConfig::new_no_block_limit(
+        num_cpus::get().max(2),
+    ))
+    .execute_transactions_parallel(
+        (),
+        &transactions,
+        &data_view,
+        Some(BlockHints::Partitioning(&BlockPartitioning::new(
+            partitions,
+        ))),
+        None,
     );
 
-    assert_matches!(s.finish_execution(2, 0, false), Ok(SchedulerTask::NoTask));
-
-    // resumed task doesn't bump incarnation
-    assert_matches!(
-        s.next_task(),
-        SchedulerTask::ExecutionTask(4, 0, ExecutionTaskType::Wakeup(_))
-    );
+    BaselineOutput::generate(&transactions, None).assert_parallel_output(&output);
 }
 
 // Will return a scheduler in a state where all transactions are scheduled for
@@ -969,7 +3427,9 @@ fn no_conflict_task_count() {
                     },
                     SchedulerTask::NoTask => break,
                     // Unreachable because we never call try_commit.
-                    SchedulerTask::Done => unreachable!(),
+                    SchedulerTask::CommitReExecutionTask(_, _) | SchedulerTask::Done => {
+                        unreachable!()
+                    },
                 }
             }
 
@@ -1014,3 +3474,128 @@ fn no_conflict_task_count() {
         assert_matches!(s.next_task(), SchedulerTask::Done);
     }
 }
+
+#[test]
+fn scripted_stale_validation_races_abort() {
+    // A validation of txn 2 is dispatched in wave 0, then txn 1 fails validation and is
+    // re-executed. The stale (wave 0) validation of txn 2 finishes successfully after the
+    // abort, but must not allow txn 2 to commit until it is re-validated in wave 1.
+    use ExpectedTask::*;
+    use Step::*;
+
+    let mut s = ScriptedSchedulerDriver::new(3);
+    s.run(&[
+        NextTask(Execution(0, 0)),
+        NextTask(Execution(1, 0)),
+        NextTask(Execution(2, 0)),
+    ]);
+    for txn_idx in 0..3 {
+        s.step(FinishExecution {
+            txn_idx,
+            incarnation: 0,
+            revalidate_suffix: false,
+            expected: NoTask,
+        });
+    }
+    s.run(&[
+        NextTask(Validation(0, 0, 0)),
+        NextTask(Validation(1, 0, 0)),
+        NextTask(Validation(2, 0, 0)),
+        FinishValidation {
+            txn_idx: 0,
+            wave: 0,
+        },
+        TryAbort {
+            txn_idx: 1,
+            incarnation: 0,
+            expected: true,
+        },
+        FinishAbort {
+            txn_idx: 1,
+            incarnation: 0,
+            expected: Execution(1, 1),
+        },
+        // The stale validation of txn 2 completes after the abort of txn 1.
+        FinishValidation {
+            txn_idx: 2,
+            wave: 0,
+        },
+        TryCommit(Some(0)),
+        // Txn 1 is being re-executed.
+        TryCommit(None),
+        FinishExecution {
+            txn_idx: 1,
+            incarnation: 1,
+            revalidate_suffix: false,
+            expected: Validation(1, 1, 1),
+        },
+        FinishValidation {
+            txn_idx: 1,
+            wave: 1,
+        },
+        TryCommit(Some(1)),
+        // Validation in wave 0 is not sufficient for txn 2.
+        TryCommit(None),
+    ]);
+    assert_eq!(s.scheduler().commit_state(), (2, 1));
+
+    s.run(&[
+        NextTask(Validation(2, 0, 1)),
+        FinishValidation {
+            txn_idx: 2,
+            wave: 1,
+        },
+        TryCommit(Some(2)),
+        NextTask(Done),
+    ]);
+}
+
+#[test]
+fn scripted_dependency_resolution_and_halt() {
+    use ExpectedTask::*;
+    use Step::*;
+
+    let mut s = ScriptedSchedulerDriver::new(3);
+    s.run(&[
+        NextTask(Execution(0, 0)),
+        NextTask(Execution(1, 0)),
+        NextTask(Execution(2, 0)),
+        FinishExecution {
+            txn_idx: 0,
+            incarnation: 0,
+            revalidate_suffix: false,
+            expected: NoTask,
+        },
+        // Txn 0 is already executed, so the dependency is immediately resolved.
+        WaitForDependency {
+            txn_idx: 2,
+            dep_txn_idx: 0,
+            expected: ExpectedDependency::Resolved,
+        },
+        // Txn 1 is still executing, so txn 2 gets suspended.
+        WaitForDependency {
+            txn_idx: 2,
+            dep_txn_idx: 1,
+            expected: ExpectedDependency::Dependency,
+        },
+        // Finishing txn 1 resumes txn 2 and decreases the execution index.
+        FinishExecution {
+            txn_idx: 1,
+            incarnation: 0,
+            revalidate_suffix: false,
+            expected: NoTask,
+        },
+        NextTask(Validation(0, 0, 0)),
+        NextTask(Validation(1, 0, 0)),
+        // Resumed task doesn't bump the incarnation.
+        NextTask(Wakeup(2, 0)),
+        Halt(true),
+        Halt(false),
+        WaitForDependency {
+            txn_idx: 2,
+            dep_txn_idx: 1,
+            expected: ExpectedDependency::ExecutionHalted,
+        },
+        NextTask(Done),
+    ]);
+}
@@ -0,0 +1,199 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A single-threaded driver that applies a scripted interleaving of execute / validate /
+//! abort events to the Block-STM Scheduler. Instead of relying on worker threads to
+//! interleave the events, a test provides the exact interleaving as a script of steps, each
+//! with the expected outcome. This allows reproducing specific races (e.g. a stale validation
+//! racing with an abort and re-execution) that are otherwise only hit probabilistically.
+//!
+//! The driver does not re-implement the scheduling logic: every step is applied to the same
+//! Scheduler used by parallel execution, so the scripts exercise the production state
+//! machine. Only the order of the calls is fixed by the test.
+
+use crate::scheduler::{
+    DependencyResult, ExecutionTaskType, Scheduler, SchedulerTask, TWaitForDependency, Wave,
+};
+use aptos_mvhashmap::types::{Incarnation, TxnIndex};
+
+/// Expected task returned by the scheduler, in a form that can be compared for equality
+/// (condition variables in Wakeup tasks are not comparable).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ExpectedTask {
+    Execution(TxnIndex, Incarnation),
+    Wakeup(TxnIndex, Incarnation),
+    Validation(TxnIndex, Incarnation, Wave),
+    CommitReExecution(TxnIndex, Incarnation),
+    NoTask,
+    Done,
+}
+
+impl From<&SchedulerTask> for ExpectedTask {
+    fn from(task: &SchedulerTask) -> Self {
+        match task {
+            SchedulerTask::ExecutionTask(txn_idx, incarnation, ExecutionTaskType::Execution) => {
+                ExpectedTask::Execution(*txn_idx, *incarnation)
+            },
+            SchedulerTask::ExecutionTask(txn_idx, incarnation, ExecutionTaskType::Wakeup(_)) => {
+                ExpectedTask::Wakeup(*txn_idx, *incarnation)
+            },
+            SchedulerTask::ValidationTask(txn_idx, incarnation, wave) => {
+                ExpectedTask::Validation(*txn_idx, *incarnation, *wave)
+            },
+            SchedulerTask::CommitReExecutionTask(txn_idx, incarnation) => {
+                ExpectedTask::CommitReExecution(*txn_idx, *incarnation)
+            },
+            SchedulerTask::NoTask => ExpectedTask::NoTask,
+            SchedulerTask::Done => ExpectedTask::Done,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ExpectedDependency {
+    Dependency,
+    Resolved,
+    ExecutionHalted,
+    Requeued,
+}
+
+/// A single event applied to the scheduler, together with the expected outcome.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Step {
+    NextTask(ExpectedTask),
+    FinishExecution {
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        revalidate_suffix: bool,
+        expected: ExpectedTask,
+    },
+    FinishValidation {
+        txn_idx: TxnIndex,
+        wave: Wave,
+    },
+    TryAbort {
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        expected: bool,
+    },
+    FinishAbort {
+        txn_idx: TxnIndex,
+        incarnation: Incarnation,
+        expected: ExpectedTask,
+    },
+    WaitForDependency {
+        txn_idx: TxnIndex,
+        dep_txn_idx: TxnIndex,
+        expected: ExpectedDependency,
+    },
+    TryCommit(Option<TxnIndex>),
+    Halt(bool),
+}
+
+pub(crate) struct ScriptedSchedulerDriver {
+    scheduler: Scheduler,
+    // Steps that were applied so far, used to provide context on a mismatch.
+    applied: Vec<Step>,
+}
+
+impl ScriptedSchedulerDriver {
+    pub(crate) fn new(num_txns: TxnIndex) -> Self {
+        Self {
+            scheduler: Scheduler::new(num_txns),
+            applied: Vec::new(),
+        }
+    }
+
+    pub(crate) fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+
+    /// Applies all steps in order, panicking at the first step whose outcome does not
+    /// match the expectation.
+    pub(crate) fn run(&mut self, script: &[Step]) {
+        for step in script {
+            self.step(*step);
+        }
+    }
+
+    pub(crate) fn step(&mut self, step: Step) {
+        let s = &self.scheduler;
+        match step {
+            Step::NextTask(expected) => {
+                let task = s.next_task();
+                self.check(step, expected, ExpectedTask::from(&task));
+            },
+            Step::FinishExecution {
+                txn_idx,
+                incarnation,
+                revalidate_suffix,
+                expected,
+            } => {
+                let task = s
+                    .finish_execution(txn_idx, incarnation, revalidate_suffix)
+                    .unwrap_or_else(|e| self.fail(step, format!("{:?}", e)));
+                self.check(step, expected, ExpectedTask::from(&task));
+            },
+            Step::FinishValidation { txn_idx, wave } => s.finish_validation(txn_idx, wave),
+            Step::TryAbort {
+                txn_idx,
+                incarnation,
+                expected,
+            } => {
+                let aborted = s.try_abort(txn_idx, incarnation);
+                self.check(step, expected, aborted);
+            },
+            Step::FinishAbort {
+                txn_idx,
+                incarnation,
+                expected,
+            } => {
+                let task = s
+                    .finish_abort(txn_idx, incarnation)
+                    .unwrap_or_else(|e| self.fail(step, format!("{:?}", e)));
+                self.check(step, expected, ExpectedTask::from(&task));
+            },
+            Step::WaitForDependency {
+                txn_idx,
+                dep_txn_idx,
+                expected,
+            } => {
+                let result = match s
+                    .wait_for_dependency(txn_idx, dep_txn_idx)
+                    .unwrap_or_else(|e| self.fail(step, format!("{:?}", e)))
+                {
+                    DependencyResult::Dependency(_) => ExpectedDependency::Dependency,
+                    DependencyResult::Resolved => ExpectedDependency::Resolved,
+                    DependencyResult::ExecutionHalted => ExpectedDependency::ExecutionHalted,
+                    DependencyResult::Requeued => ExpectedDependency::Requeued,
+                };
+                self.check(step, expected, result);
+            },
+            Step::TryCommit(expected) => {
+                let committed = s.try_commit().map(|(txn_idx, _)| txn_idx);
+                self.check(step, expected, committed);
+            },
+            Step::Halt(expected) => {
+                let halted = s.halt();
+                self.check(step, expected, halted);
+            },
+        }
+        self.applied.push(step);
+    }
+
+    fn check<V: PartialEq + std::fmt::Debug>(&self, step: Step, expected: V, actual: V) {
+        if expected != actual {
+            self.fail(step, format!("expected {:?}, got {:?}", expected, actual));
+        }
+    }
+
+    fn fail(&self, step: Step, msg: String) -> ! {
+        panic!(
+            "Step #{} {:?} failed: {}. Previously applied steps: {:?}",
+            self.applied.len(),
+            step,
+            msg,
+            self.applied
+        );
+    }
+}
@@ -5,10 +5,12 @@
 use crate::types::InputOutputKey;
 use crate::{
     captured_reads::{
-        CapturedReads, DataRead, DelayedFieldRead, DelayedFieldReadKind, GroupRead, ReadKind,
-        UnsyncReadSet,
+        CapturedReads, DataRead, DelayedFieldRead, DelayedFieldReadKind, GroupRead, ModuleRead,
+        ReadKind, UnsyncReadSet,
     },
+    chrome_trace::{self, TraceArgs},
     counters,
+    hot_state_cache::HotStateCache,
     scheduler::{DependencyResult, DependencyStatus, Scheduler, TWaitForDependency},
     value_exchange::{
         does_value_need_exchange, filter_value_for_exchange, TemporaryValueToIdentifierMapping,
@@ -28,7 +30,7 @@ use aptos_logger::error;
 use aptos_mvhashmap::{
     types::{
         GroupReadResult, MVDataError, MVDataOutput, MVDelayedFieldsError, MVGroupError,
-        MVModulesError, MVModulesOutput, StorageVersion, TxnIndex, UnknownOrLayout,
+        MVModulesError, MVModulesOutput, MVRangeError, StorageVersion, TxnIndex, UnknownOrLayout,
         UnsyncGroupError, ValueWithLayout,
     },
     unsync_map::UnsyncMap,
@@ -37,9 +39,10 @@ use aptos_mvhashmap::{
 };
 use aptos_types::{
     delayed_fields::PanicError,
-    executable::{Executable, ModulePath},
+    executable::{Executable, KeyPrefix, ModulePath},
     state_store::{
         errors::StateviewError,
+        state_read_stats::{StateReadStats, StateReadStatsRecorder},
         state_storage_usage::StateStorageUsage,
         state_value::{StateValue, StateValueMetadata},
         StateViewId, TStateView,
@@ -49,7 +52,8 @@ use aptos_types::{
 };
 use aptos_vm_logging::{log_schema::AdapterLogSchema, prelude::*};
 use aptos_vm_types::resolver::{
-    ResourceGroupSize, StateStorageView, TModuleView, TResourceGroupView, TResourceView,
+    ResourceGroupSize, StateStorageView, TModuleView, TResourceGroupView, TResourceRangeView,
+    TResourceView,
 };
 use bytes::Bytes;
 use claims::assert_ok;
@@ -63,13 +67,15 @@ use move_vm_types::{
     },
 };
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::{BTreeMap, HashMap, HashSet},
     fmt::Debug,
+    ops::Bound,
     sync::{
         atomic::{AtomicU32, Ordering},
         Arc,
     },
+    time::Instant,
 };
 
 /// A struct which describes the result of the read from the proxy. The client
@@ -161,6 +167,9 @@ pub(crate) struct ParallelState<'a, T: Transaction, X: Executable> {
     start_counter: u32,
     counter: &'a AtomicU32,
     captured_reads: RefCell<CapturedReads<T>>,
+    /// Set when the transaction was requeued on a dependency, after which the execution is
+    /// stopped and its output dropped.
+    requeued: Cell<bool>,
 }
 
 fn get_delayed_field_value_impl<T: Transaction>(
@@ -410,6 +419,10 @@ fn wait_for_dependency(
     match wait_for.wait_for_dependency(txn_idx, dep_idx)? {
         DependencyResult::Dependency(dep_condition) => {
             let _timer = counters::DEPENDENCY_WAIT_SECONDS.start_timer();
+            let _span = chrome_trace::span(
+                "dependency_wait",
+                TraceArgs::txn(txn_idx).dependency(dep_idx),
+            );
             // Wait on a condition variable corresponding to the encountered
             // read dependency. Once the dep_idx finishes re-execution, scheduler
             // will mark the dependency as resolved, and then the txn_idx will be
@@ -424,19 +437,40 @@ fn wait_for_dependency(
             // thread that aborted dep_idx was alive, and again, since lower txns
             // than txn_idx are not blocked, so the execution of dep_idx will
             // eventually finish and lead to unblocking txn_idx, contradiction.
+            let wait_start = Instant::now();
             let (lock, cvar) = &*dep_condition;
             let mut dep_resolved = lock.lock();
             while let DependencyStatus::Unresolved = *dep_resolved {
                 dep_resolved = cvar.wait(dep_resolved).unwrap();
             }
+            wait_for.record_dependency_wait(txn_idx, wait_start.elapsed());
             // dep resolved status is either resolved or execution halted.
             Ok(matches!(*dep_resolved, DependencyStatus::Resolved))
         },
-        DependencyResult::ExecutionHalted => Ok(false),
+        // The execution stops, as the block execution was halted or the transaction requeued.
+        DependencyResult::ExecutionHalted | DependencyResult::Requeued => Ok(false),
         DependencyResult::Resolved => Ok(true),
     }
 }
 
+impl<'a, T: Transaction, X: Executable> TWaitForDependency for ParallelState<'a, T, X> {
+    fn wait_for_dependency(
+        &self,
+        txn_idx: TxnIndex,
+        dep_txn_idx: TxnIndex,
+    ) -> Result<DependencyResult, PanicError> {
+        if self.requeued.get() {
+            // The transaction is no longer executing, the scheduler may not be waited on again.
+            return Ok(DependencyResult::Requeued);
+        }
+        let result = self.scheduler.wait_for_dependency(txn_idx, dep_txn_idx)?;
+        if let DependencyResult::Requeued = result {
+            self.requeued.set(true);
+        }
+        Ok(result)
+    }
+}
+
 impl<'a, T: Transaction, X: Executable> ParallelState<'a, T, X> {
     pub(crate) fn new(
         shared_map: &'a MVHashMap<T::Key, T::Tag, T::Value, X, T::Identifier>,
@@ -449,7 +483,10 @@ impl<'a, T: Transaction, X: Executable> ParallelState<'a, T, X> {
             scheduler: shared_scheduler,
             start_counter: start_shared_counter,
             counter: shared_counter,
-            captured_reads: RefCell::new(CapturedReads::new()),
+            captured_reads: RefCell::new(CapturedReads::with_change_clock(
+                shared_map.data().change_clock(),
+            )),
+            requeued: Cell::new(false),
         }
     }
 
@@ -460,18 +497,43 @@ impl<'a, T: Transaction, X: Executable> ParallelState<'a, T, X> {
     }
 
     // TODO: Actually fill in the logic to record fetched executables, etc.
+    /// Returns the module published at the key by the latest earlier transaction of the block,
+    /// or None if the module is not published by the block (i.e. it is read from storage). The
+    /// version of the read is recorded, to be validated (or for the R/W path intersection
+    /// fallback for modules).
     fn fetch_module(
         &self,
         key: &T::Key,
         txn_idx: TxnIndex,
-    ) -> anyhow::Result<MVModulesOutput<T::Value, X>, MVModulesError> {
-        // Record for the R/W path intersection fallback for modules.
-        self.captured_reads
-            .borrow_mut()
-            .module_reads
-            .push(key.clone());
+    ) -> PartialVMResult<Option<Arc<T::Value>>> {
+        use MVModulesError::*;
+        use MVModulesOutput::*;
 
-        self.versioned_map.modules().fetch_module(key, txn_idx)
+        loop {
+            match self.versioned_map.modules().fetch_module(key, txn_idx) {
+                Ok(Executable(_)) => unreachable!("Versioned executable not implemented"),
+                Ok(Module((v, hash))) => {
+                    self.captured_reads
+                        .borrow_mut()
+                        .capture_module_read(key.clone(), ModuleRead::Published(hash));
+                    return Ok(Some(v));
+                },
+                Err(NotFound) => {
+                    self.captured_reads
+                        .borrow_mut()
+                        .capture_module_read(key.clone(), ModuleRead::Storage);
+                    return Ok(None);
+                },
+                Err(Dependency(dep_idx)) => {
+                    if !wait_for_dependency(self.scheduler, txn_idx, dep_idx)? {
+                        return Err(PartialVMError::new(
+                            StatusCode::SPECULATIVE_EXECUTION_ABORT_ERROR,
+                        )
+                        .with_message("Interrupted as block execution was halted".to_string()));
+                    }
+                },
+            }
+        }
     }
 
     fn read_group_size(
@@ -508,7 +570,7 @@ impl<'a, T: Transaction, X: Executable> ParallelState<'a, T, X> {
                     unreachable!("Reading group size does not require a specific tag look-up");
                 },
                 Err(Dependency(dep_idx)) => {
-                    if !wait_for_dependency(self.scheduler, txn_idx, dep_idx)? {
+                    if !wait_for_dependency(self, txn_idx, dep_idx)? {
                         return Err(PartialVMError::new(
                             StatusCode::SPECULATIVE_EXECUTION_ABORT_ERROR,
                         )
@@ -521,6 +583,42 @@ impl<'a, T: Transaction, X: Executable> ParallelState<'a, T, X> {
             }
         }
     }
+
+    fn read_range(
+        &self,
+        prefix: &<T::Key as KeyPrefix>::Prefix,
+        range: &(Bound<T::Key>, Bound<T::Key>),
+        txn_idx: TxnIndex,
+    ) -> PartialVMResult<Vec<(T::Key, bool)>> {
+        if let Some(keys) = self.captured_reads.borrow().get_range_read(prefix, range) {
+            return Ok(keys);
+        }
+
+        loop {
+            match self
+                .versioned_map
+                .data()
+                .fetch_range(prefix, range, txn_idx)
+            {
+                Ok(keys) => {
+                    self.captured_reads.borrow_mut().capture_range_read(
+                        prefix.clone(),
+                        range.clone(),
+                        keys.clone(),
+                    );
+                    return Ok(keys);
+                },
+                Err(MVRangeError::Dependency(dep_idx)) => {
+                    if !wait_for_dependency(self, txn_idx, dep_idx)? {
+                        return Err(PartialVMError::new(
+                            StatusCode::SPECULATIVE_EXECUTION_ABORT_ERROR,
+                        )
+                        .with_message("Interrupted as block execution was halted".to_string()));
+                    }
+                },
+            }
+        }
+    }
 }
 
 impl<'a, T: Transaction, X: Executable> ResourceState<T> for ParallelState<'a, T, X> {
@@ -549,8 +647,16 @@ impl<'a, T: Transaction, X: Executable> ResourceState<T> for ParallelState<'a, T
             return ReadResult::from_data_read(data);
         }
 
+        let mut past_estimates = false;
         loop {
-            match self.versioned_map.data().fetch_data(key, txn_idx) {
+            let fetched = if past_estimates {
+                self.versioned_map
+                    .data()
+                    .fetch_data_past_estimates(key, txn_idx)
+            } else {
+                self.versioned_map.data().fetch_data(key, txn_idx)
+            };
+            match fetched {
                 Ok(Versioned(version, value)) => {
                     // If we have a known layout, upgrade RawFromStorage value to Exchanged.
                     if let UnknownOrLayout::Known(layout) = layout {
@@ -632,8 +738,17 @@ impl<'a, T: Transaction, X: Executable> ResourceState<T> for ParallelState<'a, T
                     // will make the needed recordings.
                     return ReadResult::Uninitialized;
                 },
+                Err(Dependency(_)) if self.scheduler.reads_past_estimates(txn_idx) => {
+                    // Speculate on the value of the aborted incarnation instead of waiting,
+                    // the read is validated by value.
+                    counters::PAST_ESTIMATE_READ_COUNT.inc();
+                    self.captured_reads
+                        .borrow_mut()
+                        .mark_past_estimate_read(key.clone());
+                    past_estimates = true;
+                },
                 Err(Dependency(dep_idx)) => {
-                    match wait_for_dependency(self.scheduler, txn_idx, dep_idx) {
+                    match wait_for_dependency(self, txn_idx, dep_idx) {
                         Err(e) => {
                             error!("Error {:?} in wait for dependency", e);
                             return ReadResult::HaltSpeculativeExecution(format!(
@@ -749,7 +864,7 @@ impl<'a, T: Transaction, X: Executable> ResourceGroupState<T> for ParallelState<
                     return Ok(GroupReadResult::Value(None, None));
                 },
                 Err(Dependency(dep_idx)) => {
-                    if !wait_for_dependency(self.scheduler, txn_idx, dep_idx)? {
+                    if !wait_for_dependency(self, txn_idx, dep_idx)? {
                         // TODO[agg_v2](cleanup): consider changing from PartialVMResult<GroupReadResult> to GroupReadResult
                         // like in ReadResult for resources.
                         return Err(PartialVMError::new(
@@ -960,8 +1075,10 @@ impl<'a, T: Transaction, X: Executable> ViewState<'a, T, X> {
 /// must be set according to the latest transaction that the worker was / is executing.
 pub(crate) struct LatestView<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> {
     base_view: &'a S,
+    hot_state_cache: Option<&'a HotStateCache<T::Key>>,
     pub(crate) latest_view: ViewState<'a, T, X>,
     txn_idx: TxnIndex,
+    read_stats: Option<StateReadStatsRecorder<T::Key>>,
 }
 
 impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> LatestView<'a, T, S, X> {
@@ -972,11 +1089,39 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> LatestView<
     ) -> Self {
         Self {
             base_view,
+            hot_state_cache: None,
             latest_view,
             txn_idx,
+            read_stats: None,
+        }
+    }
+
+    /// Records the state reads of the transaction, if record_read_stats is set.
+    pub(crate) fn with_read_stats(mut self, record_read_stats: bool) -> Self {
+        self.read_stats = record_read_stats.then(StateReadStatsRecorder::new);
+        self
+    }
+
+    /// Returns the state reads of the transaction, if they are recorded.
+    pub(crate) fn read_stats(&self) -> Option<StateReadStats> {
+        self.read_stats.as_ref().map(StateReadStatsRecorder::stats)
+    }
+
+    fn record_read(&self, state_key: &T::Key, bytes_read: usize) {
+        if let Some(read_stats) = &self.read_stats {
+            read_stats.record_read(state_key, bytes_read);
         }
     }
 
+    /// Reads the base values through the hot-state cache shared across blocks, if any.
+    pub(crate) fn with_hot_state_cache(
+        mut self,
+        hot_state_cache: Option<&'a HotStateCache<T::Key>>,
+    ) -> Self {
+        self.hot_state_cache = hot_state_cache;
+        self
+    }
+
     #[cfg(test)]
     fn get_read_summary(&self) -> HashSet<InputOutputKey<T::Key, T::Tag, T::Identifier>> {
         match &self.latest_view {
@@ -985,6 +1130,14 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> LatestView<
         }
     }
 
+    /// Returns true if the parallel execution was requeued on a dependency.
+    pub(crate) fn is_requeued(&self) -> bool {
+        match &self.latest_view {
+            ViewState::Sync(state) => state.requeued.get(),
+            ViewState::Unsync(_) => false,
+        }
+    }
+
     /// Drains the parallel captured reads.
     pub(crate) fn take_parallel_reads(&self) -> CapturedReads<T> {
         match &self.latest_view {
@@ -1020,7 +1173,22 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> LatestView<
     }
 
     fn get_raw_base_value(&self, state_key: &T::Key) -> PartialVMResult<Option<StateValue>> {
-        let ret = self.base_view.get_state_value(state_key).map_err(|e| {
+        let ret = match self.hot_state_cache {
+            Some(hot_state_cache) => {
+                hot_state_cache.get_state_value_with_hit(self.base_view, state_key)
+            },
+            None => self
+                .base_view
+                .get_state_value(state_key)
+                .map(|value| (value, false)),
+        }
+        .map(|(value, cache_hit)| {
+            if let Some(read_stats) = &self.read_stats {
+                read_stats.record_storage_read(cache_hit);
+            }
+            value
+        })
+        .map_err(|e| {
             PartialVMError::new(StatusCode::STORAGE_ERROR).with_message(format!(
                 "Unexpected storage error for {:?}: {:?}",
                 state_key, e
@@ -1355,7 +1523,14 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> LatestView<
             ReadResult::Uninitialized => {
                 unreachable!("base value must already be recorded in the MV data structure")
             },
-            _ => Ok(ret),
+            _ => {
+                let bytes_read = match &ret {
+                    ReadResult::Value(Some(value), _) => value.size(),
+                    _ => 0,
+                };
+                self.record_read(state_key, bytes_read);
+                Ok(ret)
+            },
         }
     }
 
@@ -1440,6 +1615,31 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TResourceVi
     }
 }
 
+impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TResourceRangeView
+    for LatestView<'a, T, S, X>
+{
+    type Key = T::Key;
+
+    fn get_written_keys_in_range(
+        &self,
+        prefix: &<T::Key as KeyPrefix>::Prefix,
+        range: &(Bound<T::Key>, Bound<T::Key>),
+    ) -> PartialVMResult<Vec<(T::Key, bool)>> {
+        match &self.latest_view {
+            ViewState::Sync(state) => state.read_range(prefix, range, self.txn_idx),
+            ViewState::Unsync(state) => {
+                let keys = state.unsync_map.fetch_range(prefix, range);
+                state
+                    .read_set
+                    .borrow_mut()
+                    .resource_reads
+                    .extend(keys.iter().map(|(key, _)| key.clone()));
+                Ok(keys)
+            },
+        }
+    }
+}
+
 impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TResourceGroupView
     for LatestView<'a, T, S, X>
 {
@@ -1465,6 +1665,7 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TResourceGr
             }
         };
 
+        self.record_read(group_key, 0);
         Ok(group_read.into_size())
     }
 
@@ -1502,7 +1703,9 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TResourceGr
                 )?;
         };
 
-        Ok(group_read.into_value().0)
+        let value = group_read.into_value().0;
+        self.record_read(group_key, value.as_ref().map_or(0, Bytes::len));
+        Ok(value)
     }
 
     fn resource_size_in_group(
@@ -1547,21 +1750,10 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TModuleView
             state_key,
         );
 
-        match &self.latest_view {
-            ViewState::Sync(state) => {
-                use MVModulesError::*;
-                use MVModulesOutput::*;
-
-                match state.fetch_module(state_key, self.txn_idx) {
-                    Ok(Executable(_)) => unreachable!("Versioned executable not implemented"),
-                    Ok(Module((v, _))) => Ok(v.as_state_value()),
-                    Err(Dependency(_)) => {
-                        // Return anything (e.g. module does not exist) to avoid waiting,
-                        // because parallel execution will fall back to sequential anyway.
-                        Ok(None)
-                    },
-                    Err(NotFound) => self.get_raw_base_value(state_key),
-                }
+        let value = match &self.latest_view {
+            ViewState::Sync(state) => match state.fetch_module(state_key, self.txn_idx)? {
+                Some(v) => v.as_state_value(),
+                None => self.get_raw_base_value(state_key)?,
             },
             ViewState::Unsync(state) => {
                 state
@@ -1572,9 +1764,11 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TModuleView
                 state.unsync_map.fetch_module_data(state_key).map_or_else(
                     || self.get_raw_base_value(state_key),
                     |v| Ok(v.as_state_value()),
-                )
+                )?
             },
-        }
+        };
+        self.record_read(state_key, value.as_ref().map_or(0, StateValue::size));
+        Ok(value)
     }
 }
 
@@ -1630,7 +1824,7 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TDelayedFie
             ViewState::Sync(state) => get_delayed_field_value_impl(
                 &state.captured_reads,
                 state.versioned_map.delayed_fields(),
-                state.scheduler,
+                state,
                 id,
                 self.txn_idx,
             ),
@@ -1654,7 +1848,7 @@ impl<'a, T: Transaction, S: TStateView<Key = T::Key>, X: Executable> TDelayedFie
             ViewState::Sync(state) => delayed_field_try_add_delta_outcome_impl(
                 &state.captured_reads,
                 state.versioned_map.delayed_fields(),
-                state.scheduler,
+                state,
                 id,
                 base_delta,
                 delta,
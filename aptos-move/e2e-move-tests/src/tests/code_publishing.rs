@@ -7,10 +7,15 @@ use crate::{
 use aptos_framework::natives::code::{PackageRegistry, UpgradePolicy};
 use aptos_package_builder::PackageBuilder;
 use aptos_types::{
+    access_path::AccessPath,
     account_address::{create_resource_address, AccountAddress},
+    account_config::{StagedCodeActivation, STAGED_CODE_ACTIVATION_MOVE_TYPE_TAG},
     on_chain_config::FeatureFlag,
+    state_store::state_key::StateKey,
+};
+use move_core_types::{
+    ident_str, language_storage::ModuleId, parser::parse_struct_tag, vm_status::StatusCode,
 };
-use move_core_types::{parser::parse_struct_tag, vm_status::StatusCode};
 use rstest::rstest;
 use serde::{Deserialize, Serialize};
 
@@ -289,6 +294,59 @@ fn code_publishing_with_two_attempts_and_verify_loader_is_invalidated() {
     assert_eq!(2, value_resource.important_value);
 }
 
+#[test]
+fn code_publishing_staged_upgrade() {
+    let mut h = MoveHarness::new_with_features(vec![FeatureFlag::STAGED_MODULE_PUBLISHING], vec![]);
+    let acc = h.new_account_at(AccountAddress::from_hex_literal("0xcafe").unwrap());
+    let module_key = StateKey::access_path(AccessPath::code_access_path(ModuleId::new(
+        *acc.address(),
+        ident_str!("test").to_owned(),
+    )));
+
+    // New modules are published right away.
+    assert_success!(h.publish_package_cache_building(
+        &acc,
+        &common::test_dir_path("code_publishing.data/pack_initial"),
+    ));
+    let initial_code = h.read_state_value_bytes(&module_key).unwrap();
+
+    // Upgrades are checked against the code on chain, but only staged until the next epoch.
+    let status = h.publish_package_cache_building(
+        &acc,
+        &common::test_dir_path("code_publishing.data/pack_upgrade_incompat"),
+    );
+    assert_vm_status!(status, StatusCode::BACKWARD_INCOMPATIBLE_MODULE_UPDATE);
+    assert_success!(h.publish_package_cache_building(
+        &acc,
+        &common::test_dir_path("code_publishing.data/pack_upgrade_compat"),
+    ));
+    assert_eq!(h.read_state_value_bytes(&module_key).unwrap(), initial_code);
+
+    // The epoch ends in the prologue of the next block, and the upgrade is published in the
+    // prologue of the first block of the new epoch.
+    h.new_epoch();
+    assert_eq!(h.read_state_value_bytes(&module_key).unwrap(), initial_code);
+    h.executor.new_block();
+    assert_ne!(h.read_state_value_bytes(&module_key).unwrap(), initial_code);
+
+    let activations: Vec<_> = h
+        .get_events()
+        .iter()
+        .filter(|event| event.type_tag() == &*STAGED_CODE_ACTIVATION_MOVE_TYPE_TAG)
+        .map(|event| StagedCodeActivation::try_from_bytes(event.event_data()).unwrap())
+        .collect();
+    assert_eq!(activations.len(), 1);
+    assert_eq!(activations[0].code_address(), *acc.address());
+    assert!(activations[0].activated());
+
+    assert_success!(h.run_entry_function(
+        &acc,
+        str::parse("0xcafe::test::hello2").unwrap(),
+        vec![],
+        vec![bcs::to_bytes::<u64>(&42).unwrap()]
+    ));
+}
+
 #[rstest(enabled, disabled,
          case(vec![], vec![FeatureFlag::CODE_DEPENDENCY_CHECK]),
          case(vec![FeatureFlag::CODE_DEPENDENCY_CHECK], vec![]),
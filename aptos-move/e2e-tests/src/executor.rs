@@ -34,6 +34,7 @@ use aptos_types::{
     },
     block_executor::config::{
         BlockExecutorConfig, BlockExecutorConfigFromOnchain, BlockExecutorLocalConfig,
+        DependencyWaitConfig, FallbackPolicies,
     },
     block_metadata::BlockMetadata,
     chain_id::ChainId,
@@ -497,11 +498,37 @@ impl FakeExecutor {
                 },
                 allow_fallback: self.allow_block_executor_fallback,
                 discard_failed_blocks: false,
+                fallback_policies: FallbackPolicies::default(),
+                adaptive_concurrency: None,
+                early_delayed_field_validation: false,
+                prefetch_base_values: false,
+                record_conflict_graph: false,
+                record_schedule: false,
+                txn_execution_timeout: None,
+                prioritize_by_gas_price: false,
+                dependency_wait: DependencyWaitConfig::default(),
+                early_release: false,
+                materialization_concurrency: 0,
+                core_affinity: None,
+                differential_execution: false,
+                retain_block_state: false,
+                max_incarnations: None,
+                block_time_budget: None,
+                parallel_single_worker: false,
+                profile_transactions: false,
+                validate_module_reads: false,
+                soft_delayed_field_validation_failures: false,
+                mvhashmap_memory_soft_cap: None,
+                record_read_stats: false,
+                block_seed: None,
+                discarded_block_bookkeeping: false,
             },
             onchain: onchain_config,
         };
         BlockAptosVM::execute_block::<_, NoOpTransactionCommitHook<AptosTransactionOutput, VMStatus>>(
             self.executor_thread_pool.clone(),
+            None,
+            None,
             txn_block,
             &self.data_store,
             config,
@@ -375,6 +375,24 @@ fn verify_simple_payment() {
         StatusCode::EXCEEDED_MAX_TRANSACTION_SIZE
     );
 
+    let txn = sender
+        .account()
+        .transaction()
+        .script(Script::new(
+            empty_script.clone(),
+            vec![TypeTag::U8; u64::from(txn_gas_params.max_num_type_args) as usize + 1],
+            vec![],
+        ))
+        .sequence_number(10)
+        .max_gas_amount(100_000)
+        .gas_unit_price(txn_gas_params.min_price_per_gas_unit.into())
+        .sign();
+    assert_prologue_parity!(
+        executor.validate_transaction(txn.clone()).status(),
+        executor.execute_transaction(txn).status(),
+        StatusCode::EXCEEDED_MAX_TYPE_ARGUMENTS
+    );
+
     // Create a new transaction with wrong argument.
 
     let txn = sender
@@ -75,6 +75,29 @@ module aptos_framework::code {
         is_upgrade: bool,
     }
 
+    /// Module upgrades that are staged until the start of an epoch, when staged module publishing is
+    /// enabled. The VM publishes the staged bundles in the first block of their activation epoch, so
+    /// that the code of a module never changes in the middle of a block.
+    struct StagedCode has key {
+        bundles: vector<StagedBundle>,
+    }
+
+    /// A module upgrade that is staged until the start of the activation epoch. The package metadata
+    /// of the upgrade is already updated in the registry of the publisher when the upgrade is staged.
+    struct StagedBundle has store, drop {
+        publisher: address,
+        activation_epoch: u64,
+        code: vector<vector<u8>>,
+    }
+
+    #[event]
+    /// Event emitted when the staged code of an address is published at the start of its activation
+    /// epoch, or dropped, if it is no longer compatible with the code on chain.
+    struct StagedCodeActivation has drop, store {
+        code_address: address,
+        activated: bool,
+    }
+
     /// Package contains duplicate module names with existing modules publised in other packages on this address
     const EMODULE_NAME_CLASH: u64 = 0x1;
 
@@ -220,6 +243,66 @@ module aptos_framework::code {
         });
     }
 
+    // ----------------------------------------------------------------------
+    // Staged Code
+
+    #[view]
+    /// Whether the given address has module upgrades that are staged until the start of an epoch.
+    public fun has_staged_code(code_address: address): bool acquires StagedCode {
+        exists<StagedCode>(@aptos_framework) &&
+            vector::any(&borrow_global<StagedCode>(@aptos_framework).bundles, |bundle| {
+                let bundle: &StagedBundle = bundle;
+                bundle.publisher == code_address
+            })
+    }
+
+    /// Stages a module upgrade of the publisher until the given epoch. Called by the VM, once the
+    /// upgrade was validated against the code on chain.
+    fun stage_code(
+        aptos_framework: &signer,
+        publisher: address,
+        activation_epoch: u64,
+        code: vector<vector<u8>>
+    ) acquires StagedCode {
+        system_addresses::assert_aptos_framework(aptos_framework);
+        if (!exists<StagedCode>(@aptos_framework)) {
+            move_to(aptos_framework, StagedCode { bundles: vector::empty() })
+        };
+        let bundles = &mut borrow_global_mut<StagedCode>(@aptos_framework).bundles;
+        vector::push_back(bundles, StagedBundle { publisher, activation_epoch, code });
+    }
+
+    /// Removes the staged bundles that activate at or before the given epoch, and returns their
+    /// publishers and code, in the order they were staged. Called by the VM in the block prologue.
+    fun take_staged_code(
+        aptos_framework: &signer,
+        epoch: u64
+    ): (vector<address>, vector<vector<vector<u8>>>) acquires StagedCode {
+        system_addresses::assert_aptos_framework(aptos_framework);
+        let publishers = vector::empty();
+        let codes = vector::empty();
+        if (exists<StagedCode>(@aptos_framework)) {
+            let bundles = &mut borrow_global_mut<StagedCode>(@aptos_framework).bundles;
+            let i = 0;
+            while (i < vector::length(bundles)) {
+                if (vector::borrow(bundles, i).activation_epoch <= epoch) {
+                    let StagedBundle { publisher, activation_epoch: _, code } = vector::remove(bundles, i);
+                    vector::push_back(&mut publishers, publisher);
+                    vector::push_back(&mut codes, code);
+                } else {
+                    i = i + 1;
+                }
+            };
+        };
+        (publishers, codes)
+    }
+
+    /// Emits the outcome of the activation of a staged bundle. Called by the VM in the block prologue.
+    fun emit_staged_code_activation(aptos_framework: &signer, code_address: address, activated: bool) {
+        system_addresses::assert_aptos_framework(aptos_framework);
+        event::emit(StagedCodeActivation { code_address, activated });
+    }
+
     /// Same as `publish_package` but as an entry function which can be called as a transaction. Because
     /// of current restrictions for txn parameters, the metadata needs to be passed in serialized form.
     public entry fun publish_package_txn(owner: &signer, metadata_serialized: vector<u8>, code: vector<vector<u8>>)
@@ -88,6 +88,22 @@ spec aptos_framework::code {
         aborts_if pack.upgrade_policy.policy <= upgrade_policy_arbitrary().policy;
     }
 
+    spec has_staged_code {
+        pragma verify = false;
+    }
+
+    spec stage_code {
+        pragma verify = false;
+    }
+
+    spec take_staged_code {
+        pragma verify = false;
+    }
+
+    spec emit_staged_code_activation {
+        pragma verify = false;
+    }
+
     spec publish_package_txn {
         // TODO: Calls `publish_package`.
         pragma verify = false;
@@ -356,6 +356,16 @@ module aptos_framework::randomness {
         randomness.seed = option::some(seed);
     }
 
+    #[test_only]
+    /// Returns the deterministic seed of the block being executed, which is derived from the block id
+    /// and unrelated to the randomness seed. Empty outside of block execution.
+    public native fun block_seed_for_testing(): vector<u8>;
+
+    #[test]
+    fun test_block_seed_outside_of_block_execution() {
+        assert!(vector::is_empty(&block_seed_for_testing()), 0);
+    }
+
     /// Compute `(a + b) % m`, assuming `m >= 1, 0 <= a < m, 0<= b < m`.
     inline fun safe_add_mod(a: u256, b: u256, m: u256): u256 {
         let neg_b = m - b;
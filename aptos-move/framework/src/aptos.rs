@@ -144,6 +144,13 @@ impl ReleaseTarget {
         }
     }
 
+    /// Creates the release bundle for this target in memory, without generating Rust bindings.
+    pub fn create_release_bundle(self, with_srcs: bool) -> anyhow::Result<ReleaseBundle> {
+        let mut options = self.create_release_options(with_srcs, None);
+        options.rust_bindings = vec![String::new(); options.packages.len()];
+        options.create_release_bundle()
+    }
+
     pub fn create_release(self, with_srcs: bool, out: Option<PathBuf>) -> anyhow::Result<()> {
         let options = self.create_release_options(with_srcs, out);
         #[cfg(unix)]
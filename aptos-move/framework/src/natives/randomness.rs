@@ -4,6 +4,8 @@ use crate::natives::transaction_context::NativeTransactionContext;
 use aptos_native_interface::{
     RawSafeNative, SafeNativeBuilder, SafeNativeContext, SafeNativeResult,
 };
+#[cfg(feature = "testing")]
+use aptos_vm_types::block_seed::block_seed;
 use better_any::{Tid, TidAble};
 use move_vm_runtime::native_functions::NativeFunction;
 use move_vm_types::{loaded_data::runtime_types::Type, values::Value};
@@ -59,10 +61,20 @@ pub fn is_safe_call(
     )])
 }
 
+#[cfg(feature = "testing")]
+fn native_block_seed_for_testing(
+    _context: &mut SafeNativeContext,
+    _ty_args: Vec<Type>,
+    _args: VecDeque<Value>,
+) -> SafeNativeResult<SmallVec<[Value; 1]>> {
+    let seed = block_seed().map_or_else(Vec::new, |seed| seed.to_vec());
+    Ok(smallvec![Value::vector_u8(seed)])
+}
+
 pub fn make_all(
     builder: &SafeNativeBuilder,
 ) -> impl Iterator<Item = (String, NativeFunction)> + '_ {
-    let natives = vec![
+    let mut natives = vec![
         (
             "fetch_and_increment_txn_counter",
             fetch_and_increment_txn_counter as RawSafeNative,
@@ -70,5 +82,11 @@ pub fn make_all(
         ("is_safe_call", is_safe_call),
     ];
 
+    #[cfg(feature = "testing")]
+    natives.push((
+        "block_seed_for_testing",
+        native_block_seed_for_testing as RawSafeNative,
+    ));
+
     builder.make_named_natives(natives)
 }
@@ -37,16 +37,28 @@ impl ReleaseOptions {
     /// Creates a release bundle from the specified options and saves it to disk. As a side
     /// effect, also generates rust bindings.
     pub fn create_release(self) -> anyhow::Result<()> {
+        let bundle = self.create_release_bundle()?;
+        let parent = self
+            .output
+            .parent()
+            .expect("Failed to get parent directory");
+        std::fs::create_dir_all(parent).context("Failed to create dirs")?;
+        std::fs::write(&self.output, bcs::to_bytes(&bundle)?).context("Failed to write output")?;
+        Ok(())
+    }
+
+    /// Creates a release bundle from the specified options, without saving it. As a side
+    /// effect, also generates rust bindings.
+    pub fn create_release_bundle(&self) -> anyhow::Result<ReleaseBundle> {
         let ReleaseOptions {
             build_options,
             packages,
             rust_bindings,
-            output,
+            output: _,
         } = self;
         let mut released_packages = vec![];
         let mut source_paths = vec![];
-        for (package_path, rust_binding_path) in packages.into_iter().zip(rust_bindings.into_iter())
-        {
+        for (package_path, rust_binding_path) in packages.iter().zip(rust_bindings.iter()) {
             let built = BuiltPackage::build(package_path.clone(), build_options.clone())
                 .with_context(|| {
                     format!(
@@ -79,11 +91,7 @@ impl ReleaseOptions {
             let relative_path = path_relative_to_crate(package_path.join("sources"));
             source_paths.push(relative_path.display().to_string());
         }
-        let bundle = ReleaseBundle::new(released_packages, source_paths);
-        let parent = output.parent().expect("Failed to get parent directory");
-        std::fs::create_dir_all(parent).context("Failed to create dirs")?;
-        std::fs::write(&output, bcs::to_bytes(&bundle)?).context("Failed to write output")?;
-        Ok(())
+        Ok(ReleaseBundle::new(released_packages, source_paths))
     }
 
     fn generate_rust_bindings(abis: &[EntryABI], path: &Path) -> anyhow::Result<()> {
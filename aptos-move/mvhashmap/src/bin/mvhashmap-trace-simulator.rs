@@ -0,0 +1,46 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use aptos_mvhashmap::trace::{simulate, txn_profiles, BlockTrace, SchedulerPolicy};
+use clap::Parser;
+use std::path::PathBuf;
+
+/// Replays a recorded MVHashMap contention trace against alternative scheduler policies.
+#[derive(Parser)]
+pub struct Args {
+    /// Path of the trace dumped by `BlockTrace::dump`.
+    trace: PathBuf,
+
+    /// Numbers of simulated workers to replay the trace with.
+    #[clap(long, num_args = 1.., default_values_t = vec![1, 8, 32])]
+    num_workers: Vec<usize>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let trace = BlockTrace::load(&args.trace)?;
+    println!(
+        "Loaded {} events of {} transactions",
+        trace.events.len(),
+        txn_profiles(&trace).len()
+    );
+
+    for num_workers in args.num_workers {
+        for policy in [
+            SchedulerPolicy::Optimistic,
+            SchedulerPolicy::WaitForHintedDependency,
+        ] {
+            let result = simulate(&trace, policy, num_workers);
+            println!(
+                "workers: {:>3}, policy: {:?}, makespan: {}us, executions: {}, aborts: {}",
+                num_workers,
+                policy,
+                result.makespan_nanos / 1000,
+                result.num_executions,
+                result.num_aborts,
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,17 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_metrics_core::{register_int_counter_vec, IntCounterVec};
+use once_cell::sync::Lazy;
+
+/// Count of the accesses to the multi-version data-structure that found the shard of the key
+/// locked by another access (and had to wait for it), by map ("data" or "group_data") and kind
+/// of access ("read" or "write").
+pub static SHARD_CONTENTION_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_mvhashmap_shard_contention_count",
+        "Count of accesses to the MVHashMap that waited for the lock of the shard of the key",
+        &["map", "access"]
+    )
+    .unwrap()
+});
@@ -3,16 +3,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    memory_usage::MemoryUsage, state_diff::StateDiff, types::TxnIndex,
     versioned_data::VersionedData, versioned_delayed_fields::VersionedDelayedFields,
     versioned_group_data::VersionedGroupData, versioned_modules::VersionedModules,
 };
 use aptos_types::{
-    executable::{Executable, ModulePath},
+    executable::{Executable, KeyPrefix, ModulePath},
     write_set::TransactionWrite,
 };
 use serde::Serialize;
-use std::{fmt::Debug, hash::Hash};
+use std::{collections::HashSet, fmt::Debug, hash::Hash};
 
+pub mod counters;
+pub mod memory_usage;
+mod sharded_map;
+pub mod state_diff;
+pub mod trace;
 pub mod types;
 pub mod unsync_map;
 mod utils;
@@ -20,6 +26,7 @@ pub mod versioned_data;
 pub mod versioned_delayed_fields;
 pub mod versioned_group_data;
 pub mod versioned_modules;
+mod versioned_ordered_index;
 
 #[cfg(test)]
 mod unit_tests;
@@ -27,13 +34,13 @@ mod unit_tests;
 /// Main multi-version data-structure used by threads to read/write during parallel
 /// execution.
 ///
-/// Concurrency is managed by DashMap, i.e. when a method accesses a BTreeMap at a
-/// given key, it holds exclusive access and doesn't need to explicitly synchronize
-/// with other reader/writers.
+/// Concurrency is managed by DashMap (sharded by the hash of the keys, see ShardedMap for the
+/// data and groups), i.e. when a method accesses a BTreeMap at a given key, it holds exclusive
+/// access and doesn't need to explicitly synchronize with other reader/writers.
 ///
 /// TODO: separate V into different generic types for data and code modules with specialized
 /// traits (currently both WriteOp for executor).
-pub struct MVHashMap<K, T, V: TransactionWrite, X: Executable, I: Clone> {
+pub struct MVHashMap<K: KeyPrefix, T, V: TransactionWrite, X: Executable, I: Clone> {
     data: VersionedData<K, V>,
     group_data: VersionedGroupData<K, T, V>,
     delayed_fields: VersionedDelayedFields<I>,
@@ -41,7 +48,7 @@ pub struct MVHashMap<K, T, V: TransactionWrite, X: Executable, I: Clone> {
 }
 
 impl<
-        K: ModulePath + Hash + Clone + Eq + Debug,
+        K: ModulePath + KeyPrefix + Hash + Clone + Eq + Debug,
         T: Hash + Clone + Eq + Debug + Serialize,
         V: TransactionWrite,
         X: Executable,
@@ -78,10 +85,88 @@ impl<
     pub fn modules(&self) -> &VersionedModules<K, V, X> {
         &self.modules
     }
+
+    /// Returns the approximate number of bytes allocated by the entries of every component of
+    /// the data-structure since it was created or last re-used (see
+    /// [`MVHashMap::retain_unmodified_base_values`]). Used to bound the memory of a block.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            data: self.data.allocated_bytes(),
+            group_data: self.group_data.allocated_bytes(),
+            modules: self.modules.allocated_bytes(),
+            delayed_fields: self.delayed_fields.allocated_bytes(),
+        }
+    }
+
+    /// Returns the final value of every key written by the first num_committed_txns transactions
+    /// of the block, which must all be committed (and their outputs materialized), e.g. for the
+    /// callers that need the aggregate state delta of the block rather than the write set of
+    /// every transaction. Must be called before the data-structure is dropped.
+    pub fn committed_state_diff(&self, num_committed_txns: TxnIndex) -> StateDiff<K, T, V> {
+        let groups = self.group_data.committed_groups(num_committed_txns);
+        // The metadata of the groups is stored with the data.
+        let group_keys: HashSet<&K> = groups.iter().map(|(key, _, _)| key).collect();
+        let resources = self
+            .data
+            .committed_writes(num_committed_txns)
+            .into_iter()
+            .filter(|(key, _, _)| !group_keys.contains(key))
+            .collect();
+        StateDiff {
+            resources,
+            groups,
+            modules: self.modules.committed_writes(num_committed_txns),
+        }
+    }
+
+    /// Returns the version chains of the key in a human-readable form, for debugging (e.g. in
+    /// tests, or in the postmortems of code invariant errors): the entries of every version
+    /// (storage or transaction index) of the data, of every tag of the group and of the module
+    /// at the key, with their incarnations, estimate flags and value layouts.
+    pub fn dump_key(&self, key: &K) -> String {
+        let sections: Vec<String> = [
+            ("data", self.data.dump(key)),
+            ("group", self.group_data.dump(key)),
+            ("module", self.modules.dump(key)),
+        ]
+        .into_iter()
+        .filter_map(|(name, lines)| {
+            lines.map(|lines| {
+                let mut section = format!("  {}:", name);
+                for line in lines {
+                    section.push_str("\n    ");
+                    section.push_str(&line);
+                }
+                section
+            })
+        })
+        .collect();
+
+        if sections.is_empty() {
+            format!("{:?}: not in the multi-version data-structure", key)
+        } else {
+            format!("{:?}:\n{}", key, sections.join("\n"))
+        }
+    }
+
+    /// Prepares the data-structure to be re-used for the execution of the next block, after
+    /// all transactions of the current block have been committed. Only the storage base values
+    /// of the keys and groups not modified by the block are retained, as they remain valid
+    /// base values for the next block. Delayed fields and modules are cleared.
+    ///
+    /// Returns the keys that were read from storage during the block but whose base values
+    /// were removed, as they are likely to be read by the next block as well.
+    pub fn retain_unmodified_base_values(&mut self) -> Vec<K> {
+        let removed_base_keys = self.data.retain_unmodified_base_values();
+        self.group_data.retain_unmodified_base_values();
+        self.delayed_fields = VersionedDelayedFields::new();
+        self.modules = VersionedModules::new();
+        removed_base_keys
+    }
 }
 
 impl<
-        K: ModulePath + Hash + Clone + Debug + Eq,
+        K: ModulePath + KeyPrefix + Hash + Clone + Debug + Eq,
         T: Hash + Clone + Debug + Eq + Serialize,
         V: TransactionWrite,
         X: Executable,
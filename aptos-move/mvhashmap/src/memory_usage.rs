@@ -0,0 +1,48 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crossbeam::utils::CachePadded;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The approximate bookkeeping cost of an entry of the data-structure (the version map node, the
+/// entry and its flags), on top of the bytes of its value.
+pub(crate) const ENTRY_OVERHEAD_BYTES: u64 = 64;
+
+/// The approximate number of bytes allocated by the entries of each component of the
+/// multi-version data-structure during the block. Entries replaced by later incarnations or
+/// removed are not subtracted (the outputs of the transactions may still hold on to their
+/// values), so this is an upper bound of the memory held by the data-structure.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub data: u64,
+    pub group_data: u64,
+    pub modules: u64,
+    pub delayed_fields: u64,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> u64 {
+        self.data + self.group_data + self.modules + self.delayed_fields
+    }
+}
+
+/// Counts the bytes allocated by the entries of a component of the data-structure.
+#[derive(Debug, Default)]
+pub(crate) struct AllocationCounter {
+    bytes: CachePadded<AtomicU64>,
+}
+
+impl AllocationCounter {
+    pub(crate) fn record_entry(&self, value_bytes: usize) {
+        self.bytes
+            .fetch_add(ENTRY_OVERHEAD_BYTES + value_bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn get(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn reset(&self) {
+        self.bytes.store(0, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::counters::SHARD_CONTENTION_COUNT;
+use dashmap::{
+    mapref::{
+        multiple::RefMulti,
+        one::{Ref, RefMut},
+    },
+    try_result::TryResult,
+    DashMap,
+};
+use std::{hash::Hash, num::NonZeroUsize, thread::available_parallelism};
+
+// DashMap defaults to 4 shards per CPU. With a shard per hash bucket, the keys accessed by the
+// workers concurrently share a shard less often with more shards, e.g. on 64 core machines.
+const SHARDS_PER_CPU: usize = 16;
+
+fn num_shards() -> usize {
+    (available_parallelism().map_or(1, NonZeroUsize::get) * SHARDS_PER_CPU).next_power_of_two()
+}
+
+/// A concurrent map from keys to the versioned values of the keys, split in shards by the hash
+/// of the keys, each with its own lock. The accesses that find the shard of the key locked by
+/// another access are counted (see [`SHARD_CONTENTION_COUNT`]), so that the contention on the
+/// multi-version data-structure is measurable.
+pub(crate) struct ShardedMap<K, V> {
+    shards: DashMap<K, V>,
+    // The name of the map in the counters.
+    name: &'static str,
+}
+
+impl<K: Hash + Eq, V> ShardedMap<K, V> {
+    pub(crate) fn new(name: &'static str) -> Self {
+        Self {
+            shards: DashMap::with_shard_amount(num_shards()),
+            name,
+        }
+    }
+
+    fn record_contention(&self, access: &str) {
+        SHARD_CONTENTION_COUNT
+            .with_label_values(&[self.name, access])
+            .inc();
+    }
+
+    pub(crate) fn get(&self, key: &K) -> Option<Ref<'_, K, V>> {
+        match self.shards.try_get(key) {
+            TryResult::Present(v) => Some(v),
+            TryResult::Absent => None,
+            TryResult::Locked => {
+                self.record_contention("read");
+                self.shards.get(key)
+            },
+        }
+    }
+
+    pub(crate) fn get_mut(&self, key: &K) -> Option<RefMut<'_, K, V>> {
+        match self.shards.try_get_mut(key) {
+            TryResult::Present(v) => Some(v),
+            TryResult::Absent => None,
+            TryResult::Locked => {
+                self.record_contention("write");
+                self.shards.get_mut(key)
+            },
+        }
+    }
+
+    /// Returns the value at the key, inserting the default value if the key is absent.
+    pub(crate) fn get_mut_or_default(&self, key: K) -> RefMut<'_, K, V>
+    where
+        V: Default,
+    {
+        match self.shards.try_get_mut(&key) {
+            TryResult::Present(v) => v,
+            TryResult::Absent => self.shards.entry(key).or_default(),
+            TryResult::Locked => {
+                self.record_contention("write");
+                self.shards.entry(key).or_default()
+            },
+        }
+    }
+
+    pub(crate) fn retain(&self, f: impl FnMut(&K, &mut V) -> bool) {
+        self.shards.retain(f);
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = RefMulti<'_, K, V>> {
+        self.shards.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use claims::assert_none;
+    use std::{sync::Arc, thread};
+
+    #[test]
+    fn contention_is_counted() {
+        let map = Arc::new(ShardedMap::<u32, u32>::new("test"));
+        *map.get_mut_or_default(1) = 10;
+        let num_contended = || {
+            SHARD_CONTENTION_COUNT
+                .with_label_values(&["test", "read"])
+                .get()
+        };
+        let initial_num_contended = num_contended();
+
+        let guard = map.get_mut(&1).unwrap();
+        let reader = {
+            let map = map.clone();
+            thread::spawn(move || *map.get(&1).unwrap())
+        };
+        // The read waits for the lock held by the guard.
+        while num_contended() == initial_num_contended {
+            thread::yield_now();
+        }
+        drop(guard);
+        assert_eq!(reader.join().unwrap(), 10);
+        assert_none!(map.get(&2));
+    }
+}
@@ -0,0 +1,28 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::types::{TxnIndex, ValueWithLayout};
+use std::sync::Arc;
+
+/// The final value of a resource written by the committed transactions of a block.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FinalValue<V> {
+    /// The value of the last write. The value holds the identifiers of its delayed fields (if
+    /// the layout is set), which are exchanged for their values in the transaction outputs.
+    Write(ValueWithLayout<V>),
+    /// The materialized value of the last aggregator v1 delta.
+    AggregatorV1(u128),
+}
+
+/// The writes of the committed transactions of a block deduplicated per key, i.e. the final value
+/// of every key written by the block, taken from the multi-version data-structure (see
+/// [`crate::MVHashMap::committed_state_diff`]). Every value comes with the index of the last
+/// transaction that wrote the key.
+#[derive(Debug)]
+pub struct StateDiff<K, T, V> {
+    /// The resources, excluding the resource groups.
+    pub resources: Vec<(K, TxnIndex, FinalValue<V>)>,
+    /// The committed members of the resource groups, with the deletions applied.
+    pub groups: Vec<(K, TxnIndex, Vec<(T, ValueWithLayout<V>)>)>,
+    pub modules: Vec<(K, TxnIndex, Arc<V>)>,
+}
@@ -0,0 +1,282 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Contention traces of the multi-version data-structures, for offline scheduler simulation.
+//!
+//! While recording is enabled (e.g. by a benchmark harness around the execution of a single
+//! block), every read and write to the versioned data, group and module maps is recorded with
+//! a timestamp and the id of the worker thread. Keys are recorded as 64-bit hashes to keep the
+//! trace compact. The recorded [`BlockTrace`] can be dumped to a file and replayed by
+//! [`simulate`] against alternative scheduler policies, without re-executing the block.
+//!
+//! When recording is disabled, the overhead on the hot path is a single relaxed atomic load.
+
+use crate::types::{Incarnation, TxnIndex};
+use anyhow::Result;
+use aptos_infallible::Mutex;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::DefaultHasher, BTreeSet, BinaryHeap, HashMap},
+    hash::{Hash, Hasher},
+    path::Path,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    time::Instant,
+};
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static RECORDER: Lazy<Mutex<Option<Recorder>>> = Lazy::new(|| Mutex::new(None));
+static NEXT_THREAD_ID: AtomicU32 = AtomicU32::new(0);
+
+thread_local! {
+    static THREAD_ID: u32 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+struct Recorder {
+    start: Instant,
+    events: Vec<TraceEvent>,
+}
+
+/// The multi-version map an access was made to. Part of the key hash, so that keys of the
+/// same value in different maps don't conflict in the simulation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum KeySpace {
+    Data,
+    Group,
+    Module,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum AccessKind {
+    Read,
+    /// A write or a delta, with the incarnation of the writer if known.
+    Write(Option<Incarnation>),
+    /// The writes of an aborted incarnation were marked as estimates.
+    MarkEstimate,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TraceEvent {
+    /// Nanoseconds since the recording started.
+    pub timestamp_nanos: u64,
+    pub thread_id: u32,
+    pub txn_idx: TxnIndex,
+    pub kind: AccessKind,
+    pub key_id: u64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BlockTrace {
+    pub events: Vec<TraceEvent>,
+}
+
+impl BlockTrace {
+    pub fn dump(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, bcs::to_bytes(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(bcs::from_bytes(&std::fs::read(path)?)?)
+    }
+}
+
+/// Starts recording the accesses to all multi-version maps, discarding any previous trace.
+pub fn start_recording() {
+    *RECORDER.lock() = Some(Recorder {
+        start: Instant::now(),
+        events: Vec::new(),
+    });
+    RECORDING.store(true, Ordering::Release);
+}
+
+/// Stops recording and returns the recorded trace, sorted by timestamp.
+pub fn stop_recording() -> BlockTrace {
+    RECORDING.store(false, Ordering::Release);
+    let mut events = RECORDER
+        .lock()
+        .take()
+        .map(|recorder| recorder.events)
+        .unwrap_or_default();
+    events.sort_by_key(|event| event.timestamp_nanos);
+    BlockTrace { events }
+}
+
+#[inline]
+pub(crate) fn record_access<K: Hash + ?Sized>(
+    space: KeySpace,
+    key: &K,
+    txn_idx: TxnIndex,
+    kind: AccessKind,
+) {
+    if RECORDING.load(Ordering::Relaxed) {
+        record_access_slow(space, key, txn_idx, kind);
+    }
+}
+
+#[cold]
+fn record_access_slow<K: Hash + ?Sized>(
+    space: KeySpace,
+    key: &K,
+    txn_idx: TxnIndex,
+    kind: AccessKind,
+) {
+    let mut hasher = DefaultHasher::new();
+    space.hash(&mut hasher);
+    key.hash(&mut hasher);
+    let key_id = hasher.finish();
+    let thread_id = THREAD_ID.with(|id| *id);
+
+    if let Some(recorder) = RECORDER.lock().as_mut() {
+        recorder.events.push(TraceEvent {
+            timestamp_nanos: recorder.start.elapsed().as_nanos() as u64,
+            thread_id,
+            txn_idx,
+            kind,
+            key_id,
+        });
+    }
+}
+
+/// The read and write sets and the estimated execution time of a transaction, as derived
+/// from a trace.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TxnProfile {
+    pub reads: BTreeSet<u64>,
+    pub writes: BTreeSet<u64>,
+    pub duration_nanos: u64,
+}
+
+/// Derives the profile of every transaction in the trace. The read and write sets are the
+/// union over all incarnations. The execution time is estimated as the span between the first
+/// and last access of the transaction, divided by its number of incarnations.
+pub fn txn_profiles(trace: &BlockTrace) -> Vec<TxnProfile> {
+    let num_txns = trace
+        .events
+        .iter()
+        .map(|event| event.txn_idx as usize + 1)
+        .max()
+        .unwrap_or(0);
+    let mut profiles = vec![TxnProfile::default(); num_txns];
+    let mut spans = vec![(u64::MAX, 0u64, 1u64); num_txns];
+
+    for event in &trace.events {
+        let idx = event.txn_idx as usize;
+        let (first, last, incarnations) = &mut spans[idx];
+        *first = (*first).min(event.timestamp_nanos);
+        *last = (*last).max(event.timestamp_nanos);
+        match event.kind {
+            AccessKind::Read => {
+                profiles[idx].reads.insert(event.key_id);
+            },
+            AccessKind::Write(incarnation) => {
+                profiles[idx].writes.insert(event.key_id);
+                *incarnations = (*incarnations).max(incarnation.map_or(0, |i| i as u64) + 1);
+            },
+            AccessKind::MarkEstimate => (),
+        }
+    }
+
+    for (profile, (first, last, incarnations)) in profiles.iter_mut().zip(spans) {
+        profile.duration_nanos = (last.saturating_sub(first) / incarnations).max(1);
+    }
+    profiles
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SchedulerPolicy {
+    /// Executes the lowest pending transaction optimistically, as BlockSTM does.
+    Optimistic,
+    /// Delays a transaction until the last earlier writer of a key it reads has executed
+    /// successfully, as with pre-declared access hints.
+    WaitForHintedDependency,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SimulationResult {
+    pub makespan_nanos: u64,
+    pub num_executions: usize,
+    pub num_aborts: usize,
+}
+
+/// Replays the transactions of a trace on `num_workers` simulated workers under the given
+/// policy.
+///
+/// The model is deliberately simple: a finished execution is valid iff, for every key it
+/// read, the last earlier writer of that key had already finished a valid execution when the
+/// transaction started. Otherwise the execution is aborted and the transaction is scheduled
+/// for re-execution. Scheduling and validation themselves are free.
+pub fn simulate(
+    trace: &BlockTrace,
+    policy: SchedulerPolicy,
+    num_workers: usize,
+) -> SimulationResult {
+    assert!(num_workers > 0, "At least one worker is required");
+    let profiles = txn_profiles(trace);
+
+    // For every transaction, the last earlier writer of each key it reads.
+    let mut last_writer: HashMap<u64, usize> = HashMap::new();
+    let dependencies: Vec<BTreeSet<usize>> = profiles
+        .iter()
+        .enumerate()
+        .map(|(idx, profile)| {
+            let dependencies = profile
+                .reads
+                .iter()
+                .filter_map(|key| last_writer.get(key).copied())
+                .collect();
+            for key in &profile.writes {
+                last_writer.insert(*key, idx);
+            }
+            dependencies
+        })
+        .collect();
+
+    let mut result = SimulationResult::default();
+    let mut pending: BTreeSet<usize> = (0..profiles.len()).collect();
+    let mut valid_at: Vec<Option<u64>> = vec![None; profiles.len()];
+    // Running executions, as (finish time, txn index, start time).
+    let mut running = BinaryHeap::new();
+    let mut idle_workers = num_workers;
+    let mut now = 0;
+
+    loop {
+        while idle_workers > 0 {
+            let next = pending.iter().copied().find(|idx| match policy {
+                SchedulerPolicy::Optimistic => true,
+                SchedulerPolicy::WaitForHintedDependency => dependencies[*idx]
+                    .iter()
+                    .next_back()
+                    .map_or(true, |dep| valid_at[*dep].is_some()),
+            });
+            let idx = match next {
+                Some(idx) => idx,
+                None => break,
+            };
+            pending.remove(&idx);
+            running.push(Reverse((now + profiles[idx].duration_nanos, idx, now)));
+            idle_workers -= 1;
+            result.num_executions += 1;
+        }
+
+        let Reverse((finish, idx, start)) = match running.pop() {
+            Some(execution) => execution,
+            None => break,
+        };
+        now = finish;
+        idle_workers += 1;
+        if dependencies[idx]
+            .iter()
+            .all(|dep| valid_at[*dep].map_or(false, |valid_at| valid_at <= start))
+        {
+            valid_at[idx] = Some(finish);
+        } else {
+            result.num_aborts += 1;
+            pending.insert(idx);
+        }
+    }
+
+    result.makespan_nanos = now;
+    result
+}
@@ -15,7 +15,10 @@ use bytes::Bytes;
 use derivative::Derivative;
 use move_binary_format::errors::PartialVMError;
 use move_core_types::value::MoveTypeLayout;
-use std::sync::{atomic::AtomicU32, Arc};
+use std::{
+    fmt,
+    sync::{atomic::AtomicU32, Arc},
+};
 
 pub type AtomicTxnIndex = AtomicU32;
 pub type TxnIndex = u32;
@@ -62,6 +65,13 @@ pub enum MVDataError {
     DeltaApplicationFailure,
 }
 
+/// Returned as Err(..) when failed to read a range of keys from the multi-version data-structure.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MVRangeError {
+    /// A dependency on other transaction has been found during the read.
+    Dependency(TxnIndex),
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum MVModulesError {
     /// No prior entry is found.
@@ -184,6 +194,15 @@ impl ShiftedTxnIndex {
     }
 }
 
+impl fmt::Display for ShiftedTxnIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.idx() {
+            Ok(txn_idx) => write!(f, "txn {}", txn_idx),
+            Err(StorageVersion) => write!(f, "storage"),
+        }
+    }
+}
+
 // TODO[agg_v2](cleanup): consider adding `DoesntExist` variant.
 // Currently, "not existing value" is represented as Deletion.
 #[derive(Debug, PartialEq, Eq)]
@@ -226,6 +245,30 @@ impl<V: TransactionWrite> ValueWithLayout<V> {
         }
     }
 
+    /// The kind, the size and the layout of the value, for the dumps of the version chains (see
+    /// [`crate::MVHashMap::dump_key`]).
+    pub(crate) fn describe(&self) -> String {
+        let bytes_len = self
+            .bytes_len()
+            .map_or("no bytes".to_string(), |len| format!("{} bytes", len));
+        match self {
+            ValueWithLayout::RawFromStorage(_) => {
+                format!("raw {:?}, {}", self.write_op_kind(), bytes_len)
+            },
+            ValueWithLayout::Exchanged(_, None) => {
+                format!("{:?}, {}, no layout", self.write_op_kind(), bytes_len)
+            },
+            ValueWithLayout::Exchanged(_, Some(layout)) => {
+                format!(
+                    "{:?}, {}, layout {}",
+                    self.write_op_kind(),
+                    bytes_len,
+                    layout
+                )
+            },
+        }
+    }
+
     pub fn extract_value_no_layout(&self) -> &V {
         match self {
             ValueWithLayout::RawFromStorage(value) => value.as_ref(),
@@ -247,7 +290,7 @@ pub(crate) mod test {
     use aptos_aggregator::delta_change_set::serialize;
     use aptos_types::{
         access_path::AccessPath,
-        executable::ModulePath,
+        executable::{KeyPrefix, ModulePath},
         state_store::state_value::StateValue,
         write_set::{TransactionWrite, WriteOpKind},
     };
@@ -255,7 +298,7 @@ pub(crate) mod test {
     use claims::{assert_err, assert_ok_eq};
     use std::{fmt::Debug, hash::Hash, sync::Arc};
 
-    #[derive(Clone, Eq, Hash, PartialEq, Debug)]
+    #[derive(Clone, Eq, Hash, PartialEq, PartialOrd, Ord, Debug)]
     pub(crate) struct KeyType<K: Hash + Clone + Debug + Eq>(
         /// Wrapping the types used for testing to add ModulePath trait implementation.
         pub K,
@@ -267,6 +310,15 @@ pub(crate) mod test {
         }
     }
 
+    // All the keys share a prefix, so that the proptests also exercise the ordered index.
+    impl<K: Hash + Clone + Eq + Ord + Debug> KeyPrefix for KeyType<K> {
+        type Prefix = ();
+
+        fn key_prefix(&self) -> Option<()> {
+            Some(())
+        }
+    }
+
     #[test]
     fn test_shifted_idx() {
         let zero = ShiftedTxnIndex::zero_idx();
@@ -5,12 +5,12 @@
 use super::{
     types::{
         test::{arc_value_for, u128_for, KeyType, TestValue},
-        MVDataError, MVDataOutput,
+        MVDataError, MVDataOutput, MVRangeError, StorageVersion,
     },
     unsync_map::UnsyncMap,
     *,
 };
-use crate::types::ValueWithLayout;
+use crate::{state_diff::FinalValue, types::ValueWithLayout};
 use aptos_aggregator::{
     bounded_math::SignedU128,
     delta_change_set::{delta_add, delta_sub, DeltaOp},
@@ -18,7 +18,10 @@ use aptos_aggregator::{
 };
 use aptos_types::executable::ExecutableTestType;
 use claims::{assert_err_eq, assert_none, assert_ok_eq, assert_some_eq};
-use std::sync::Arc;
+use std::{
+    ops::Bound::{Excluded, Included, Unbounded},
+    sync::Arc,
+};
 mod proptest_types;
 
 fn match_unresolved(
@@ -147,6 +150,18 @@ fn create_write_read_placeholder_struct() {
     let r_11 = mvtbl.data().fetch_data(&ap1, 12);
     assert_eq!(Err(Dependency(10)), r_11);
 
+    // Unless the reads go past the estimates.
+    let r_10 = mvtbl.data().fetch_data_past_estimates(&ap1, 11);
+    assert_eq!(
+        Ok(Versioned(
+            Ok((10, 1)),
+            ValueWithLayout::Exchanged(arc_value_for(10, 1), None)
+        )),
+        r_10
+    );
+    let r_11 = mvtbl.data().fetch_data_past_estimates(&ap1, 12);
+    assert_eq!(Ok(Resolved(u128_for(10, 1) + 11)), r_11);
+
     // Delete the entry written by 10, write to a different ap.
     mvtbl.data().remove(&ap1, 10);
     mvtbl
@@ -227,6 +242,38 @@ fn create_write_read_placeholder_struct() {
     assert_eq!(Err(DeltaApplicationFailure), r_31);
 }
 
+#[test]
+fn materialize_deltas_in_one_pass() {
+    let vd: VersionedData<KeyType<Vec<u8>>, TestValue> = VersionedData::new();
+    let ap = KeyType(b"/foo/b".to_vec());
+    let limit = 10000;
+
+    vd.add_delta(ap.clone(), 5, delta_add(10, limit));
+    vd.add_delta(ap.clone(), 8, delta_add(20, limit));
+    vd.add_delta(ap.clone(), 11, delta_add(30, limit));
+
+    // Without a base value, nothing is materialized.
+    assert_err_eq!(
+        vd.materialize_deltas(&ap, &[5, 8, 11]),
+        DeltaOp::new(SignedU128::Positive(10), limit, DeltaHistory {
+            max_achieved_positive_delta: 10,
+            min_achieved_negative_delta: 0,
+            min_overflow_positive_delta: None,
+            max_underflow_negative_delta: None,
+        })
+    );
+    vd.set_base_value(
+        ap.clone(),
+        ValueWithLayout::RawFromStorage(Arc::new(TestValue::from_u128(5))),
+    );
+    assert_ok_eq!(vd.materialize_deltas(&ap, &[5, 8, 11]), vec![15, 35, 65]);
+
+    // The shortcuts are recorded as with materialize_delta.
+    vd.add_delta(ap.clone(), 6, delta_add(15, limit));
+    assert_eq!(vd.fetch_data(&ap, 12), Ok(MVDataOutput::Resolved(65)));
+    assert_ok_eq!(vd.materialize_delta(&ap, 11), 65);
+}
+
 #[test]
 fn materialize_delta_shortcut() {
     use MVDataOutput::*;
@@ -273,6 +320,103 @@ fn materialize_delta_shortcut() {
     assert_eq!(vd.fetch_data(&ap, 10), Ok(Resolved(50)));
 }
 
+#[test]
+fn retain_unmodified_base_values() {
+    use MVDataError::*;
+    use MVDataOutput::*;
+
+    let ap1 = KeyType(b"/foo/b".to_vec());
+    let ap2 = KeyType(b"/foo/c".to_vec());
+    let ap3 = KeyType(b"/foo/d".to_vec());
+    let ap4 = KeyType(b"/foo/e".to_vec());
+
+    let mut mvtbl: MVHashMap<KeyType<Vec<u8>>, usize, TestValue, ExecutableTestType, ()> =
+        MVHashMap::new();
+
+    // Only read by the block.
+    mvtbl.data().set_base_value(
+        ap1.clone(),
+        ValueWithLayout::RawFromStorage(arc_value_for(1, 0)),
+    );
+    // Read and then written by the block.
+    mvtbl.data().set_base_value(
+        ap2.clone(),
+        ValueWithLayout::RawFromStorage(arc_value_for(2, 0)),
+    );
+    mvtbl
+        .data()
+        .write(ap2.clone(), 3, 1, arc_value_for(2, 3), None);
+    // Written by the block without a base value.
+    mvtbl.data().add_delta(ap3.clone(), 5, delta_add(10, 1000));
+    // Base value with delayed fields.
+    mvtbl.data().set_base_value(
+        ap4.clone(),
+        ValueWithLayout::Exchanged(
+            arc_value_for(4, 0),
+            Some(Arc::new(move_core_types::value::MoveTypeLayout::U64)),
+        ),
+    );
+
+    // The keys that had a base value, but were modified or contain delayed fields.
+    let removed_base_keys = mvtbl.retain_unmodified_base_values();
+    assert_eq!(removed_base_keys.len(), 2);
+    assert!(removed_base_keys.contains(&ap2) && removed_base_keys.contains(&ap4));
+
+    assert_eq!(
+        mvtbl.data().fetch_data(&ap1, 0),
+        Ok(Versioned(
+            Err(StorageVersion),
+            ValueWithLayout::RawFromStorage(arc_value_for(1, 0))
+        ))
+    );
+    assert_eq!(mvtbl.data().fetch_data(&ap2, 10), Err(Uninitialized));
+    assert_eq!(mvtbl.data().fetch_data(&ap3, 10), Err(Uninitialized));
+    assert_eq!(mvtbl.data().fetch_data(&ap4, 10), Err(Uninitialized));
+}
+
+#[test]
+fn memory_usage() {
+    use crate::memory_usage::ENTRY_OVERHEAD_BYTES;
+
+    let ap1 = KeyType(b"/foo/b".to_vec());
+    let ap2 = KeyType(b"/foo/c".to_vec());
+
+    let mut mvtbl: MVHashMap<KeyType<Vec<u8>>, usize, TestValue, ExecutableTestType, ()> =
+        MVHashMap::new();
+    assert_eq!(mvtbl.memory_usage().total(), 0);
+
+    let base_value = arc_value_for(1, 0);
+    let value_size = base_value.bytes().unwrap().len() as u64;
+    mvtbl.data().set_base_value(
+        ap1.clone(),
+        ValueWithLayout::RawFromStorage(base_value.clone()),
+    );
+    // Setting the same base value again does not allocate a new entry.
+    mvtbl
+        .data()
+        .set_base_value(ap1.clone(), ValueWithLayout::RawFromStorage(base_value));
+    assert_eq!(mvtbl.memory_usage().data, ENTRY_OVERHEAD_BYTES + value_size);
+
+    // Re-executions are counted again, the replaced entries are not subtracted.
+    mvtbl
+        .data()
+        .write(ap1.clone(), 3, 0, arc_value_for(1, 0), None);
+    mvtbl
+        .data()
+        .write(ap1.clone(), 3, 1, arc_value_for(1, 0), None);
+    mvtbl.data().add_delta(ap2.clone(), 5, delta_add(10, 1000));
+    assert_eq!(mvtbl.memory_usage(), MemoryUsage {
+        data: 4 * ENTRY_OVERHEAD_BYTES + 3 * value_size,
+        group_data: 0,
+        modules: 0,
+        delayed_fields: 0,
+    });
+
+    // Re-used for the next block.
+    mvtbl.retain_unmodified_base_values();
+    assert_eq!(mvtbl.memory_usage().total(), 0);
+}
+
 #[test]
 #[should_panic]
 fn aggregator_base_mismatch() {
@@ -316,3 +460,304 @@ fn commit_without_entry() {
     // Must panic as there is no delta at provided index.
     let _ = vd.materialize_delta(&ap, 9);
 }
+
+#[test]
+fn committed_state_diff() {
+    let mvtbl: MVHashMap<KeyType<Vec<u8>>, usize, TestValue, ExecutableTestType, ()> =
+        MVHashMap::new();
+    let num_committed_txns = 4;
+
+    // The last committed write of a resource, not the writes of uncommitted transactions.
+    let ap1 = KeyType(b"/foo/a".to_vec());
+    for txn_idx in [0, 2, 5] {
+        mvtbl
+            .data()
+            .write(ap1.clone(), txn_idx, 0, arc_value_for(txn_idx, 0), None);
+    }
+    // The materialized value of an aggregator v1 delta.
+    let ap2 = KeyType(b"/foo/b".to_vec());
+    mvtbl.data().set_base_value(
+        ap2.clone(),
+        ValueWithLayout::RawFromStorage(Arc::new(TestValue::from_u128(10))),
+    );
+    mvtbl.data().add_delta(ap2.clone(), 1, delta_add(5, 1000));
+    assert_ok_eq!(mvtbl.data().materialize_delta(&ap2, 1), 15);
+    // Only written by an uncommitted transaction.
+    mvtbl
+        .data()
+        .write(KeyType(b"/foo/c".to_vec()), 6, 0, arc_value_for(6, 0), None);
+
+    // A group, whose metadata is written with the data.
+    let group_key = KeyType(b"/foo/g".to_vec());
+    mvtbl
+        .group_data()
+        .set_raw_base_values(group_key.clone(), vec![(
+            0,
+            TestValue::creation_with_len(1),
+        )]);
+    mvtbl.group_data().write(group_key.clone(), 3, 0, vec![(
+        1,
+        (TestValue::creation_with_len(2), None),
+    )]);
+    mvtbl
+        .data()
+        .write(group_key.clone(), 3, 0, arc_value_for(3, 0), None);
+    assert!(mvtbl.group_data().finalize_group(&group_key, 3).is_ok());
+
+    let module_key = KeyType(b"/foo/m".to_vec());
+    mvtbl
+        .modules()
+        .write(module_key.clone(), 0, TestValue::new(vec![1]));
+    mvtbl
+        .modules()
+        .write(module_key.clone(), 3, TestValue::new(vec![2]));
+
+    let mut diff = mvtbl.committed_state_diff(num_committed_txns);
+    diff.resources
+        .sort_by(|(k1, _, _), (k2, _, _)| k1.0.cmp(&k2.0));
+    assert_eq!(diff.resources, vec![
+        (
+            ap1,
+            2,
+            FinalValue::Write(ValueWithLayout::Exchanged(arc_value_for(2, 0), None))
+        ),
+        (ap2, 1, FinalValue::AggregatorV1(15)),
+    ]);
+
+    assert_eq!(diff.groups.len(), 1);
+    let (key, txn_idx, mut members) = diff.groups.pop().unwrap();
+    assert_eq!((key, txn_idx), (group_key, 3));
+    members.sort_by_key(|(tag, _)| *tag);
+    assert_eq!(members, vec![
+        (
+            0,
+            ValueWithLayout::RawFromStorage(Arc::new(TestValue::creation_with_len(1)))
+        ),
+        (
+            1,
+            ValueWithLayout::Exchanged(Arc::new(TestValue::creation_with_len(2)), None)
+        ),
+    ]);
+
+    assert_eq!(diff.modules, vec![(
+        module_key,
+        3,
+        Arc::new(TestValue::new(vec![2]))
+    )]);
+}
+
+#[test]
+fn fetch_range() {
+    let vd: VersionedData<KeyType<Vec<u8>>, TestValue> = VersionedData::new();
+    let [ap1, ap2, ap3, ap4] =
+        [b"/foo/a", b"/foo/b", b"/foo/c", b"/foo/d"].map(|path| KeyType(path.to_vec()));
+    let all = (Unbounded, Unbounded);
+
+    // Base values are not in the index (they are scanned from storage by the callers).
+    vd.set_base_value(
+        ap1.clone(),
+        ValueWithLayout::RawFromStorage(Arc::new(TestValue::from_u128(10))),
+    );
+    assert!(vd.fetch_range(&(), &all, 10).unwrap().is_empty());
+
+    vd.write(ap3.clone(), 2, 0, arc_value_for(2, 0), None);
+    vd.write(ap2.clone(), 5, 0, arc_value_for(5, 0), None);
+    vd.write(ap2.clone(), 7, 0, Arc::new(TestValue::deletion()), None);
+    vd.add_delta(ap4.clone(), 8, delta_add(5, 1000));
+
+    // Only the writes of the lower transactions are visible, with the latest existence.
+    assert!(vd.fetch_range(&(), &all, 2).unwrap().is_empty());
+    assert_ok_eq!(vd.fetch_range(&(), &all, 6), vec![
+        (ap2.clone(), true),
+        (ap3.clone(), true)
+    ]);
+    assert_ok_eq!(vd.fetch_range(&(), &all, 10), vec![
+        (ap2.clone(), false),
+        (ap3.clone(), true),
+        (ap4.clone(), true)
+    ]);
+    assert_ok_eq!(
+        vd.fetch_range(&(), &(Excluded(ap2.clone()), Included(ap3.clone())), 10),
+        vec![(ap3.clone(), true)]
+    );
+    // Empty (and inverted) ranges.
+    assert!(vd
+        .fetch_range(&(), &(Excluded(ap3.clone()), Excluded(ap3.clone())), 10)
+        .unwrap()
+        .is_empty());
+    assert!(vd
+        .fetch_range(&(), &(Included(ap4.clone()), Excluded(ap1.clone())), 10)
+        .unwrap()
+        .is_empty());
+
+    // An estimate in the range is a dependency, unless overwritten below txn_idx.
+    vd.mark_estimate(&ap2, 5);
+    assert_err_eq!(vd.fetch_range(&(), &all, 6), MVRangeError::Dependency(5));
+    assert_ok_eq!(
+        vd.fetch_range(&(), &(Included(ap3.clone()), Unbounded), 6),
+        vec![(ap3.clone(), true)]
+    );
+    assert_ok_eq!(vd.fetch_range(&(), &all, 8), vec![
+        (ap2.clone(), false),
+        (ap3.clone(), true)
+    ]);
+
+    // Removed keys are no longer in the range.
+    vd.remove(&ap2, 5);
+    vd.remove(&ap2, 7);
+    assert_ok_eq!(vd.fetch_range(&(), &all, 10), vec![
+        (ap3.clone(), true),
+        (ap4.clone(), true)
+    ]);
+
+    vd.retain_unmodified_base_values();
+    assert!(vd.fetch_range(&(), &all, 10).unwrap().is_empty());
+}
+
+#[test]
+fn dump_key() {
+    let mvtbl: MVHashMap<KeyType<Vec<u8>>, usize, TestValue, ExecutableTestType, ()> =
+        MVHashMap::new();
+
+    let ap = KeyType(b"/foo/a".to_vec());
+    assert!(mvtbl
+        .dump_key(&ap)
+        .ends_with(": not in the multi-version data-structure"));
+
+    mvtbl.data().set_base_value(
+        ap.clone(),
+        ValueWithLayout::RawFromStorage(Arc::new(TestValue::from_u128(10))),
+    );
+    mvtbl.data().write(
+        ap.clone(),
+        2,
+        1,
+        arc_value_for(2, 1),
+        Some(Arc::new(move_core_types::value::MoveTypeLayout::U64)),
+    );
+    mvtbl.data().mark_estimate(&ap, 2);
+    mvtbl.data().add_delta(ap.clone(), 4, delta_add(5, 1000));
+    let dump = mvtbl.dump_key(&ap);
+    let lines: Vec<_> = dump.lines().skip(1).collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[..3], [
+        "  data:",
+        "    storage: incarnation 0, raw Creation, 16 bytes",
+        "    txn 2: incarnation 1, Creation, 16 bytes, layout u64, estimate",
+    ]);
+    assert!(lines[3].starts_with("    txn 4: delta +5 ensures 0 <= result <= 1000"));
+    assert!(lines[3].ends_with(", materialized None"));
+
+    let group_key = KeyType(b"/foo/g".to_vec());
+    mvtbl
+        .group_data()
+        .set_raw_base_values(group_key.clone(), vec![(
+            0,
+            TestValue::creation_with_len(1),
+        )]);
+    mvtbl.group_data().write(group_key.clone(), 3, 0, vec![(
+        1,
+        (TestValue::creation_with_len(2), None),
+    )]);
+    mvtbl.group_data().mark_estimate(&group_key, 3);
+    let dump = mvtbl.dump_key(&group_key);
+    assert_eq!(dump.lines().skip(1).collect::<Vec<_>>(), [
+        "  group:",
+        "    tag 0:",
+        "      storage: incarnation 0, raw Creation, 1 bytes",
+        "    tag 1:",
+        "      txn 3: incarnation 0, Creation, 2 bytes, no layout, estimate",
+        "    committed tags: [0]",
+    ]);
+
+    let module_key = KeyType(b"/foo/m".to_vec());
+    mvtbl
+        .modules()
+        .write(module_key.clone(), 1, TestValue::new(vec![1]));
+    let dump = mvtbl.dump_key(&module_key);
+    let lines: Vec<_> = dump.lines().skip(1).collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "  module:");
+    assert!(lines[1].starts_with("    txn 1: Creation, hash "));
+}
+
+#[test]
+fn record_trace() {
+    let vd: VersionedData<KeyType<Vec<u8>>, TestValue> = VersionedData::new();
+    let ap = KeyType(b"/trace/a".to_vec());
+
+    trace::start_recording();
+    vd.write(ap.clone(), 3003, 1, arc_value_for(3003, 1), None);
+    let _ = vd.fetch_data(&ap, 3005);
+    let block_trace = trace::stop_recording();
+    let _ = vd.fetch_data(&ap, 3006);
+
+    // Other tests may access their maps concurrently, only check the accesses to our key
+    // (and use transaction indices unlikely to be used by them).
+    let write = block_trace
+        .events
+        .iter()
+        .find(|event| event.txn_idx == 3003 && event.kind == trace::AccessKind::Write(Some(1)))
+        .expect("Write must be recorded");
+    let reads: Vec<_> = block_trace
+        .events
+        .iter()
+        .filter(|event| event.key_id == write.key_id && event.kind == trace::AccessKind::Read)
+        .collect();
+    assert_eq!(reads.len(), 1);
+    assert_eq!(reads[0].txn_idx, 3005);
+    assert!(reads[0].timestamp_nanos >= write.timestamp_nanos);
+
+    let profiles = trace::txn_profiles(&block_trace);
+    assert!(profiles[3005].reads.contains(&write.key_id));
+    assert!(profiles[3003].writes.contains(&write.key_id));
+}
+
+#[test]
+fn simulate_trace() {
+    use trace::{AccessKind::*, BlockTrace, SchedulerPolicy::*, SimulationResult, TraceEvent};
+
+    let event = |timestamp_nanos, txn_idx, kind, key_id| TraceEvent {
+        timestamp_nanos,
+        thread_id: 0,
+        txn_idx,
+        kind,
+        key_id,
+    };
+    // A chain: txn 1 reads the write of txn 0, txn 2 reads the write of txn 1.
+    let block_trace = BlockTrace {
+        events: vec![
+            event(0, 0, Write(Some(0)), 1),
+            event(100, 0, Read, 0),
+            event(0, 1, Read, 1),
+            event(100, 1, Write(Some(0)), 2),
+            event(0, 2, Read, 2),
+            event(100, 2, Write(Some(0)), 3),
+        ],
+    };
+
+    assert_eq!(
+        trace::simulate(&block_trace, Optimistic, 1),
+        SimulationResult {
+            makespan_nanos: 300,
+            num_executions: 3,
+            num_aborts: 0,
+        }
+    );
+    assert_eq!(
+        trace::simulate(&block_trace, Optimistic, 3),
+        SimulationResult {
+            makespan_nanos: 300,
+            num_executions: 6,
+            num_aborts: 3,
+        }
+    );
+    assert_eq!(
+        trace::simulate(&block_trace, WaitForHintedDependency, 3),
+        SimulationResult {
+            makespan_nanos: 300,
+            num_executions: 3,
+            num_aborts: 0,
+        }
+    );
+}
@@ -3,27 +3,34 @@
 
 use crate::{
     types::{GroupReadResult, MVModulesOutput, UnsyncGroupError, ValueWithLayout},
-    utils::module_hash,
+    utils::{is_empty_range, module_hash},
 };
 use aptos_aggregator::types::{code_invariant_error, DelayedFieldValue};
 use aptos_crypto::hash::HashValue;
 use aptos_types::{
     delayed_fields::PanicError,
-    executable::{Executable, ExecutableDescriptor, ModulePath},
+    executable::{Executable, ExecutableDescriptor, KeyPrefix, ModulePath},
     write_set::TransactionWrite,
 };
 use aptos_vm_types::resource_group_adapter::group_size_as_sum;
 use move_binary_format::errors::PartialVMResult;
 use move_core_types::value::MoveTypeLayout;
 use serde::Serialize;
-use std::{cell::RefCell, collections::HashMap, fmt::Debug, hash::Hash, sync::Arc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    hash::Hash,
+    ops::Bound,
+    sync::Arc,
+};
 
 /// UnsyncMap is designed to mimic the functionality of MVHashMap for sequential execution.
 /// In this case only the latest recorded version is relevant, simplifying the implementation.
 /// The functionality also includes Executable caching based on the hash of ExecutableDescriptor
 /// (i.e. module hash for modules published during the latest block - not at storage version).
 pub struct UnsyncMap<
-    K: ModulePath,
+    K: ModulePath + KeyPrefix,
     T: Hash + Clone + Debug + Eq + Serialize,
     V: TransactionWrite,
     X: Executable,
@@ -38,10 +45,13 @@ pub struct UnsyncMap<
     executable_cache: RefCell<HashMap<HashValue, Arc<X>>>,
     executable_bytes: RefCell<usize>,
     delayed_field_map: RefCell<HashMap<I, DelayedFieldValue>>,
+    // The written keys with a prefix, ordered within the prefix, and whether they exist (i.e.
+    // were not deleted), for the range reads.
+    ordered_index: RefCell<HashMap<K::Prefix, BTreeMap<K, bool>>>,
 }
 
 impl<
-        K: ModulePath + Hash + Clone + Eq,
+        K: ModulePath + KeyPrefix + Hash + Clone + Eq,
         T: Hash + Clone + Debug + Eq + Serialize,
         V: TransactionWrite,
         X: Executable,
@@ -56,12 +66,13 @@ impl<
             executable_cache: RefCell::new(HashMap::new()),
             executable_bytes: RefCell::new(0),
             delayed_field_map: RefCell::new(HashMap::new()),
+            ordered_index: RefCell::new(HashMap::new()),
         }
     }
 }
 
 impl<
-        K: ModulePath + Hash + Clone + Eq + Debug,
+        K: ModulePath + KeyPrefix + Hash + Clone + Eq + Debug,
         T: Hash + Clone + Debug + Eq + Serialize,
         V: TransactionWrite,
         X: Executable,
@@ -232,7 +243,32 @@ impl<
         self.delayed_field_map.borrow().get(id).cloned()
     }
 
+    /// Returns the keys of the prefix in the range that were written (or deleted) in the block,
+    /// in order, each with whether it exists (see [`VersionedData::fetch_range`]).
+    ///
+    /// [`VersionedData::fetch_range`]: crate::versioned_data::VersionedData::fetch_range
+    pub fn fetch_range(&self, prefix: &K::Prefix, range: &(Bound<K>, Bound<K>)) -> Vec<(K, bool)> {
+        if is_empty_range(range) {
+            return vec![];
+        }
+        self.ordered_index
+            .borrow()
+            .get(prefix)
+            .map_or(vec![], |keys| {
+                keys.range::<K, _>((range.0.as_ref(), range.1.as_ref()))
+                    .map(|(key, exists)| (key.clone(), *exists))
+                    .collect()
+            })
+    }
+
     pub fn write(&self, key: K, value: Arc<V>, layout: Option<Arc<MoveTypeLayout>>) {
+        if let Some(prefix) = key.key_prefix() {
+            self.ordered_index
+                .borrow_mut()
+                .entry(prefix)
+                .or_default()
+                .insert(key.clone(), !value.is_deletion());
+        }
         self.resource_map
             .borrow_mut()
             .insert(key, ValueWithLayout::Exchanged(value, layout));
@@ -3,6 +3,7 @@
 
 use aptos_crypto::hash::{DefaultHasher, HashValue};
 use aptos_types::write_set::TransactionWrite;
+use std::ops::Bound::{self, Excluded, Included};
 
 pub(crate) fn module_hash<V: TransactionWrite>(module: &V) -> HashValue {
     module
@@ -14,3 +15,13 @@ pub(crate) fn module_hash<V: TransactionWrite>(module: &V) -> HashValue {
         })
         .expect("Module can't be deleted")
 }
+
+/// BTreeMap::range panics if the start of the range is after its end, or if both bounds are
+/// excluded and equal. Such ranges are empty.
+pub(crate) fn is_empty_range<K: Ord>(range: &(Bound<K>, Bound<K>)) -> bool {
+    match range {
+        (Excluded(start), Excluded(end)) => start >= end,
+        (Included(start) | Excluded(start), Included(end) | Excluded(end)) => start > end,
+        _ => false,
+    }
+}
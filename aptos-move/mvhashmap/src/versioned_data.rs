@@ -2,21 +2,32 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::types::{
-    Flag, Incarnation, MVDataError, MVDataOutput, ShiftedTxnIndex, TxnIndex, ValueWithLayout,
+use crate::{
+    memory_usage::AllocationCounter,
+    sharded_map::ShardedMap,
+    state_diff::FinalValue,
+    trace::{record_access, AccessKind, KeySpace},
+    types::{
+        Flag, Incarnation, MVDataError, MVDataOutput, MVRangeError, ShiftedTxnIndex, TxnIndex,
+        ValueWithLayout,
+    },
+    versioned_ordered_index::VersionedOrderedIndex,
 };
 use anyhow::Result;
 use aptos_aggregator::delta_change_set::DeltaOp;
-use aptos_types::write_set::TransactionWrite;
+use aptos_types::{executable::KeyPrefix, write_set::TransactionWrite};
 use claims::assert_some;
 use crossbeam::utils::CachePadded;
-use dashmap::DashMap;
 use move_core_types::value::MoveTypeLayout;
 use std::{
     collections::btree_map::{self, BTreeMap},
     fmt::Debug,
     hash::Hash,
-    sync::Arc,
+    ops::Bound,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
 
 /// Every entry in shared multi-version data-structure has an "estimate" flag
@@ -47,11 +58,23 @@ enum EntryCell<V> {
 /// transactions that update the given access path & the corresponding entries.
 struct VersionedValue<V> {
     versioned_map: BTreeMap<ShiftedTxnIndex, CachePadded<Entry<V>>>,
+    /// The change clock at the last change of the entries that may affect reads, i.e. writes,
+    /// deltas, removals and estimates (but not base values or delta shortcuts).
+    last_change: u64,
 }
 
 /// Maps each key (access path) to an internal versioned value representation.
-pub struct VersionedData<K, V> {
-    values: DashMap<K, VersionedValue<V>>,
+pub struct VersionedData<K: KeyPrefix, V> {
+    values: ShardedMap<K, VersionedValue<V>>,
+    /// Stamps the changes to the values, so that the reads of the keys that did not change since
+    /// the last validation are not re-validated (see [`VersionedData::changed_since`]).
+    change_clock: CachePadded<AtomicU64>,
+    /// The keys with a prefix, ordered within the prefix for the range reads (see
+    /// [`VersionedData::fetch_range`]).
+    ordered_index: VersionedOrderedIndex<K::Prefix, K>,
+    /// The bytes allocated by the entries written during the block (see
+    /// [`crate::MVHashMap::memory_usage`]).
+    allocated: AllocationCounter,
 }
 
 impl<V> Entry<V> {
@@ -99,12 +122,19 @@ impl<V: TransactionWrite> Default for VersionedValue<V> {
     fn default() -> Self {
         Self {
             versioned_map: BTreeMap::new(),
+            last_change: 0,
         }
     }
 }
 
 impl<V: TransactionWrite> VersionedValue<V> {
-    fn read(&self, txn_idx: TxnIndex) -> anyhow::Result<MVDataOutput<V>, MVDataError> {
+    // If past_estimates is true, the entries marked as estimates are read as the other entries
+    // instead of reporting a dependency.
+    fn read(
+        &self,
+        txn_idx: TxnIndex,
+        past_estimates: bool,
+    ) -> anyhow::Result<MVDataOutput<V>, MVDataError> {
         use MVDataError::*;
         use MVDataOutput::*;
 
@@ -117,7 +147,7 @@ impl<V: TransactionWrite> VersionedValue<V> {
         // During traversal, all aggregator deltas have to be accumulated together.
         let mut accumulator: Option<Result<DeltaOp, ()>> = None;
         while let Some((idx, entry)) = iter.next_back() {
-            if entry.flag() == Flag::Estimate {
+            if entry.flag() == Flag::Estimate && !past_estimates {
                 // Found a dependency.
                 return Err(Dependency(
                     idx.idx().expect("May not depend on storage version"),
@@ -209,15 +239,49 @@ impl<V: TransactionWrite> VersionedValue<V> {
     }
 }
 
-impl<K: Hash + Clone + Debug + Eq, V: TransactionWrite> VersionedData<K, V> {
+impl<K: KeyPrefix + Hash + Clone + Debug + Eq, V: TransactionWrite> VersionedData<K, V> {
     pub(crate) fn new() -> Self {
         Self {
-            values: DashMap::new(),
+            values: ShardedMap::new("data"),
+            change_clock: CachePadded::new(AtomicU64::new(0)),
+            ordered_index: VersionedOrderedIndex::new(),
+            allocated: AllocationCounter::default(),
         }
     }
 
+    pub(crate) fn allocated_bytes(&self) -> u64 {
+        self.allocated.get()
+    }
+
+    /// Stamps a change of the value, must be called while holding its lock. Hence, once the
+    /// change clock is observed, all the changes with a lower or equal stamp are visible to
+    /// the subsequent accesses of the values.
+    fn stamp_change(&self, v: &mut VersionedValue<V>) {
+        v.last_change = self.change_clock.fetch_add(1, Ordering::SeqCst) + 1;
+    }
+
+    /// Returns the current value of the change clock.
+    pub fn change_clock(&self) -> u64 {
+        self.change_clock.load(Ordering::SeqCst)
+    }
+
+    /// Returns true if the value at the key changed after the change clock had the given value,
+    /// i.e. if the reads of the key may need to be validated again. Also true for a key that
+    /// is not in the data-structure.
+    pub fn changed_since(&self, key: &K, change_clock: u64) -> bool {
+        self.values
+            .get(key)
+            .map_or(true, |v| v.last_change > change_clock)
+    }
+
     pub fn add_delta(&self, key: K, txn_idx: TxnIndex, delta: DeltaOp) {
-        let mut v = self.values.entry(key).or_default();
+        record_access(KeySpace::Data, &key, txn_idx, AccessKind::Write(None));
+        if let Some(prefix) = key.key_prefix() {
+            self.ordered_index.write(prefix, key.clone(), txn_idx, true);
+        }
+        let mut v = self.values.get_mut_or_default(key);
+        self.stamp_change(&mut v);
+        self.allocated.record_entry(0);
         v.versioned_map.insert(
             ShiftedTxnIndex::new(txn_idx),
             CachePadded::new(Entry::new_delta_from(delta)),
@@ -227,11 +291,16 @@ impl<K: Hash + Clone + Debug + Eq, V: TransactionWrite> VersionedData<K, V> {
     /// Mark an entry from transaction 'txn_idx' at access path 'key' as an estimated write
     /// (for future incarnation). Will panic if the entry is not in the data-structure.
     pub fn mark_estimate(&self, key: &K, txn_idx: TxnIndex) {
+        record_access(KeySpace::Data, key, txn_idx, AccessKind::MarkEstimate);
         let mut v = self.values.get_mut(key).expect("Path must exist");
+        self.stamp_change(&mut v);
         v.versioned_map
             .get_mut(&ShiftedTxnIndex::new(txn_idx))
             .expect("Entry by the txn must exist to mark estimate")
             .mark_estimate();
+        if let Some(prefix) = key.key_prefix() {
+            self.ordered_index.mark_estimate(&prefix, key, txn_idx);
+        }
     }
 
     /// Delete an entry from transaction 'txn_idx' at access path 'key'. Will panic
@@ -239,10 +308,14 @@ impl<K: Hash + Clone + Debug + Eq, V: TransactionWrite> VersionedData<K, V> {
     pub fn remove(&self, key: &K, txn_idx: TxnIndex) {
         // TODO: investigate logical deletion.
         let mut v = self.values.get_mut(key).expect("Path must exist");
+        self.stamp_change(&mut v);
         assert_some!(
             v.versioned_map.remove(&ShiftedTxnIndex::new(txn_idx)),
             "Entry for key / idx must exist to be deleted"
         );
+        if let Some(prefix) = key.key_prefix() {
+            self.ordered_index.remove(&prefix, key, txn_idx);
+        }
     }
 
     pub fn fetch_data(
@@ -250,12 +323,41 @@ impl<K: Hash + Clone + Debug + Eq, V: TransactionWrite> VersionedData<K, V> {
         key: &K,
         txn_idx: TxnIndex,
     ) -> anyhow::Result<MVDataOutput<V>, MVDataError> {
+        record_access(KeySpace::Data, key, txn_idx, AccessKind::Read);
         self.values
             .get(key)
-            .map(|v| v.read(txn_idx))
+            .map(|v| v.read(txn_idx, false))
             .unwrap_or(Err(MVDataError::Uninitialized))
     }
 
+    /// Like fetch_data, but reads the entries of the aborted incarnations (marked as estimates)
+    /// instead of reporting a dependency on them. The read is speculative: the version of an
+    /// estimated write no longer validates once the writer is re-executed.
+    pub fn fetch_data_past_estimates(
+        &self,
+        key: &K,
+        txn_idx: TxnIndex,
+    ) -> anyhow::Result<MVDataOutput<V>, MVDataError> {
+        record_access(KeySpace::Data, key, txn_idx, AccessKind::Read);
+        self.values
+            .get(key)
+            .map(|v| v.read(txn_idx, true))
+            .unwrap_or(Err(MVDataError::Uninitialized))
+    }
+
+    /// Returns the keys of the prefix in the range that were written (or deleted) by the
+    /// transactions before txn_idx, in order, each with whether it exists at txn_idx. The other
+    /// keys of the prefix in the range are as in storage, so the range in the state seen by the
+    /// transaction is the range in storage overlaid with the returned keys.
+    pub fn fetch_range(
+        &self,
+        prefix: &K::Prefix,
+        range: &(Bound<K>, Bound<K>),
+        txn_idx: TxnIndex,
+    ) -> Result<Vec<(K, bool)>, MVRangeError> {
+        self.ordered_index.fetch_range(prefix, range, txn_idx)
+    }
+
     pub fn fetch_exchanged_data(
         &self,
         key: &K,
@@ -271,13 +373,14 @@ impl<K: Hash + Clone + Debug + Eq, V: TransactionWrite> VersionedData<K, V> {
     }
 
     pub fn set_base_value(&self, key: K, value: ValueWithLayout<V>) {
-        let mut v = self.values.entry(key).or_default();
+        let mut v = self.values.get_mut_or_default(key);
         // For base value, incarnation is irrelevant, and is always set to 0.
 
         use btree_map::Entry::*;
         use ValueWithLayout::*;
         match v.versioned_map.entry(ShiftedTxnIndex::zero_idx()) {
             Vacant(v) => {
+                self.allocated.record_entry(value.bytes_len().unwrap_or(0));
                 v.insert(CachePadded::new(Entry::new_write_from(0, value)));
             },
             Occupied(mut o) => {
@@ -325,7 +428,20 @@ impl<K: Hash + Clone + Debug + Eq, V: TransactionWrite> VersionedData<K, V> {
         data: Arc<V>,
         maybe_layout: Option<Arc<MoveTypeLayout>>,
     ) {
-        let mut v = self.values.entry(key).or_default();
+        record_access(
+            KeySpace::Data,
+            &key,
+            txn_idx,
+            AccessKind::Write(Some(incarnation)),
+        );
+        if let Some(prefix) = key.key_prefix() {
+            self.ordered_index
+                .write(prefix, key.clone(), txn_idx, !data.is_deletion());
+        }
+        let mut v = self.values.get_mut_or_default(key);
+        self.stamp_change(&mut v);
+        self.allocated
+            .record_entry(data.bytes().map_or(0, |bytes| bytes.len()));
         let prev_entry = v.versioned_map.insert(
             ShiftedTxnIndex::new(txn_idx),
             CachePadded::new(Entry::new_write_from(
@@ -351,11 +467,96 @@ impl<K: Hash + Clone + Debug + Eq, V: TransactionWrite> VersionedData<K, V> {
     /// transaction has indeed produced a delta recorded at the given key.
     ///
     /// If the result is Err(op), it means the base value to apply DeltaOp op hadn't been set.
+    /// Removes all entries written by the transactions of the block, and retains the storage
+    /// base values of the keys that the block did not modify. The base values that contain
+    /// delayed fields are also removed, as the identifiers exchanged into them are only valid
+    /// while the delayed fields data-structure is alive. Returns the removed keys that had a
+    /// base value, i.e. that were read from storage during the block.
+    pub(crate) fn retain_unmodified_base_values(&self) -> Vec<K> {
+        use ValueWithLayout::*;
+
+        let zero_idx = ShiftedTxnIndex::zero_idx();
+        let mut removed_base_keys = Vec::new();
+        self.values.retain(|key, v| {
+            let base_entry = v.versioned_map.get(&zero_idx);
+            let retain = v.versioned_map.len() == 1
+                && base_entry.map_or(false, |entry| {
+                    matches!(
+                        entry.cell,
+                        EntryCell::Write(_, RawFromStorage(_) | Exchanged(_, None))
+                    )
+                });
+            if !retain && base_entry.is_some() {
+                removed_base_keys.push(key.clone());
+            }
+            retain
+        });
+        self.ordered_index.clear();
+        self.allocated.reset();
+        removed_base_keys
+    }
+
+    /// Describes every entry of the key, one per line (see [`crate::MVHashMap::dump_key`]).
+    pub(crate) fn dump(&self, key: &K) -> Option<Vec<String>> {
+        let v = self.values.get(key)?;
+        Some(
+            v.versioned_map
+                .iter()
+                .map(|(idx, entry)| {
+                    let estimate = if entry.flag == Flag::Estimate {
+                        ", estimate"
+                    } else {
+                        ""
+                    };
+                    match &entry.cell {
+                        EntryCell::Write(incarnation, value) => format!(
+                            "{}: incarnation {}, {}{}",
+                            idx,
+                            incarnation,
+                            value.describe(),
+                            estimate
+                        ),
+                        EntryCell::Delta(delta, shortcut) => format!(
+                            "{}: delta {:?}, materialized {:?}{}",
+                            idx, delta, shortcut, estimate
+                        ),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the latest value of every key written by the first num_committed_txns (committed)
+    /// transactions, with the index of the last transaction that wrote the key.
+    pub(crate) fn committed_writes(
+        &self,
+        num_committed_txns: TxnIndex,
+    ) -> Vec<(K, TxnIndex, FinalValue<V>)> {
+        let committed_range = ShiftedTxnIndex::new(0)..ShiftedTxnIndex::new(num_committed_txns);
+        self.values
+            .iter()
+            .filter_map(|v| {
+                let (idx, entry) = v.versioned_map.range(committed_range.clone()).next_back()?;
+                let value = match &entry.cell {
+                    EntryCell::Write(_, value) => FinalValue::Write(value.clone()),
+                    EntryCell::Delta(_, shortcut) => FinalValue::AggregatorV1(
+                        shortcut.expect("Committed delta must be materialized"),
+                    ),
+                };
+                Some((
+                    v.key().clone(),
+                    idx.idx().expect("Range excludes the storage version"),
+                    value,
+                ))
+            })
+            .collect()
+    }
+
     pub fn materialize_delta(&self, key: &K, txn_idx: TxnIndex) -> Result<u128, DeltaOp> {
         let mut v = self.values.get_mut(key).expect("Path must exist");
 
         // +1 makes sure we include the delta from txn_idx.
-        match v.read(txn_idx + 1) {
+        match v.read(txn_idx + 1, false) {
             Ok(MVDataOutput::Resolved(value)) => {
                 v.versioned_map
                     .get_mut(&ShiftedTxnIndex::new(txn_idx))
@@ -371,4 +572,39 @@ impl<K: Hash + Clone + Debug + Eq, V: TransactionWrite> VersionedData<K, V> {
             ),
         }
     }
+
+    /// Materializes the deltas of the (committed) transactions at the key, in one pass under a
+    /// single lock of the key: the transaction indices must be increasing. Each materialized
+    /// delta is resolved against the shortcut recorded for the previous one, so the latest
+    /// committed value is read once. If the delta of the first transaction cannot be resolved,
+    /// nothing is materialized, and the delta op to be applied to the base value is returned
+    /// (see materialize_delta).
+    pub fn materialize_deltas(
+        &self,
+        key: &K,
+        txn_indices: &[TxnIndex],
+    ) -> Result<Vec<u128>, DeltaOp> {
+        debug_assert!(txn_indices.windows(2).all(|w| w[0] < w[1]));
+        let mut v = self.values.get_mut(key).expect("Path must exist");
+
+        let mut values = Vec::with_capacity(txn_indices.len());
+        for txn_idx in txn_indices {
+            match v.read(txn_idx + 1, false) {
+                Ok(MVDataOutput::Resolved(value)) => {
+                    v.versioned_map
+                        .get_mut(&ShiftedTxnIndex::new(*txn_idx))
+                        .expect("Entry by the txn must exist to commit delta")
+                        .record_delta_shortcut(value);
+                    values.push(value);
+                },
+                // Once a delta is resolved, the subsequent deltas resolve against its shortcut.
+                Err(MVDataError::Unresolved(op)) if values.is_empty() => return Err(op),
+                _ => unreachable!(
+                    "Must resolve delta at key = {:?}, txn_idx = {}",
+                    key, txn_idx
+                ),
+            }
+        }
+        Ok(values)
+    }
 }
@@ -1,7 +1,10 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::types::{AtomicTxnIndex, MVDelayedFieldsError, TxnIndex};
+use crate::{
+    memory_usage::AllocationCounter,
+    types::{AtomicTxnIndex, MVDelayedFieldsError, TxnIndex},
+};
 use aptos_aggregator::{
     delayed_change::{ApplyBase, DelayedApplyEntry, DelayedEntry},
     types::{code_invariant_error, DelayedFieldValue, PanicOr, ReadPosition},
@@ -379,6 +382,18 @@ pub struct VersionedDelayedFields<K: Clone> {
     /// No deltas are allowed below next_idx_to_commit version, as all deltas (and snapshots)
     /// must be materialized and converted to Values during commit.
     next_idx_to_commit: AtomicTxnIndex,
+
+    /// The bytes allocated by the entries recorded during the block (see
+    /// [`crate::MVHashMap::memory_usage`]).
+    allocated: AllocationCounter,
+}
+
+/// The number of bytes a delayed field value holds on top of the entry.
+fn value_size(value: &DelayedFieldValue) -> usize {
+    match value {
+        DelayedFieldValue::Derived(bytes) => bytes.len(),
+        DelayedFieldValue::Aggregator(_) | DelayedFieldValue::Snapshot(_) => 0,
+    }
 }
 
 impl<K: Eq + Hash + Clone + Debug + Copy> VersionedDelayedFields<K> {
@@ -389,9 +404,14 @@ impl<K: Eq + Hash + Clone + Debug + Copy> VersionedDelayedFields<K> {
         Self {
             values: DashMap::new(),
             next_idx_to_commit: AtomicTxnIndex::new(0),
+            allocated: AllocationCounter::default(),
         }
     }
 
+    pub(crate) fn allocated_bytes(&self) -> u64 {
+        self.allocated.get()
+    }
+
     /// Must be called when an delayed field from storage is resolved, with ID replacing the
     /// base value. This ensures that VersionedValue exists for the delayed field before any
     /// other uses (adding deltas, etc).
@@ -399,9 +419,10 @@ impl<K: Eq + Hash + Clone + Debug + Copy> VersionedDelayedFields<K> {
     /// Setting base value multiple times, even concurrently, is okay for the same ID,
     /// because the corresponding value prior to the block is fixed.
     pub fn set_base_value(&self, id: K, base_value: DelayedFieldValue) {
-        self.values
-            .entry(id)
-            .or_insert(VersionedValue::new(Some(base_value)));
+        if let dashmap::mapref::entry::Entry::Vacant(entry) = self.values.entry(id) {
+            self.allocated.record_entry(value_size(&base_value));
+            entry.insert(VersionedValue::new(Some(base_value)));
+        }
     }
 
     /// Must be called when an delayed field creation with a given ID and initial value is
@@ -412,6 +433,7 @@ impl<K: Eq + Hash + Clone + Debug + Copy> VersionedDelayedFields<K> {
         txn_idx: TxnIndex,
         value: DelayedFieldValue,
     ) -> Result<(), PanicError> {
+        self.allocated.record_entry(value_size(&value));
         let mut created = VersionedValue::new(None);
         created.insert_speculative_value(txn_idx, VersionEntry::Value(value, None))?;
 
@@ -433,6 +455,7 @@ impl<K: Eq + Hash + Clone + Debug + Copy> VersionedDelayedFields<K> {
         txn_idx: TxnIndex,
         apply: DelayedApplyEntry<K>,
     ) -> Result<(), PanicError> {
+        self.allocated.record_entry(0);
         let mut created = VersionedValue::new(None);
         created.insert_speculative_value(txn_idx, VersionEntry::Apply(apply))?;
 
@@ -452,11 +475,13 @@ impl<K: Eq + Hash + Clone + Debug + Copy> VersionedDelayedFields<K> {
         match change {
             DelayedEntry::Create(value) => self.initialize_delayed_field(id, txn_idx, value)?,
             DelayedEntry::Apply(apply) => match &apply {
-                DelayedApplyEntry::AggregatorDelta { .. } => self
-                    .values
-                    .get_mut(&id)
-                    .ok_or(PanicOr::Or(MVDelayedFieldsError::NotFound))?
-                    .insert_speculative_value(txn_idx, VersionEntry::Apply(apply))?,
+                DelayedApplyEntry::AggregatorDelta { .. } => {
+                    self.allocated.record_entry(0);
+                    self.values
+                        .get_mut(&id)
+                        .ok_or(PanicOr::Or(MVDelayedFieldsError::NotFound))?
+                        .insert_speculative_value(txn_idx, VersionEntry::Apply(apply))?
+                },
                 DelayedApplyEntry::SnapshotDelta { .. }
                 | DelayedApplyEntry::SnapshotDerived { .. } => {
                     self.initialize_dependent_delayed_field(id, txn_idx, apply)?
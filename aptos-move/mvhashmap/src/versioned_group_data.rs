@@ -1,15 +1,17 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::types::{
-    Flag, Incarnation, MVGroupError, ShiftedTxnIndex, TxnIndex, ValueWithLayout, Version,
+use crate::{
+    memory_usage::AllocationCounter,
+    sharded_map::ShardedMap,
+    trace::{record_access, AccessKind, KeySpace},
+    types::{Flag, Incarnation, MVGroupError, ShiftedTxnIndex, TxnIndex, ValueWithLayout, Version},
 };
 use anyhow::bail;
 use aptos_types::write_set::{TransactionWrite, WriteOpKind};
 use aptos_vm_types::{resolver::ResourceGroupSize, resource_group_adapter::group_size_as_sum};
 use claims::{assert_matches, assert_none, assert_some};
 use crossbeam::utils::CachePadded;
-use dashmap::DashMap;
 use move_core_types::value::MoveTypeLayout;
 use serde::Serialize;
 use std::{
@@ -66,7 +68,10 @@ pub(crate) struct VersionedGroupValue<T, V> {
 
 /// Maps each key (access path) to an internal VersionedValue.
 pub struct VersionedGroupData<K, T, V> {
-    group_values: DashMap<K, VersionedGroupValue<T, V>>,
+    group_values: ShardedMap<K, VersionedGroupValue<T, V>>,
+    /// The bytes allocated by the entries written during the block (see
+    /// [`crate::MVHashMap::memory_usage`]).
+    allocated: AllocationCounter,
 }
 
 impl<T: Hash + Clone + Debug + Eq + Serialize, V: TransactionWrite> Default
@@ -373,16 +378,23 @@ impl<
 {
     pub(crate) fn new() -> Self {
         Self {
-            group_values: DashMap::new(),
+            group_values: ShardedMap::new("group_data"),
+            allocated: AllocationCounter::default(),
         }
     }
 
+    pub(crate) fn allocated_bytes(&self) -> u64 {
+        self.allocated.get()
+    }
+
     pub fn set_raw_base_values(&self, key: K, base_values: impl IntoIterator<Item = (T, V)>) {
         // Incarnation is irrelevant for storage version, set to 0.
         self.group_values
-            .entry(key)
-            .or_default()
-            .set_raw_base_values(base_values.into_iter());
+            .get_mut_or_default(key)
+            .set_raw_base_values(base_values.into_iter().inspect(|(_, v)| {
+                self.allocated
+                    .record_entry(v.bytes().map_or(0, |bytes| bytes.len()))
+            }));
     }
 
     pub fn update_tagged_base_value_with_layout(
@@ -394,8 +406,7 @@ impl<
     ) {
         // Incarnation is irrelevant for storage version, set to 0.
         self.group_values
-            .entry(key)
-            .or_default()
+            .get_mut_or_default(key)
             .update_tagged_base_value_with_layout(tag, value, layout);
     }
 
@@ -406,18 +417,29 @@ impl<
         incarnation: Incarnation,
         values: impl IntoIterator<Item = (T, (V, Option<Arc<MoveTypeLayout>>))>,
     ) -> bool {
-        self.group_values.entry(key).or_default().write(
+        record_access(
+            KeySpace::Group,
+            &key,
+            txn_idx,
+            AccessKind::Write(Some(incarnation)),
+        );
+        self.group_values.get_mut_or_default(key).write(
             ShiftedTxnIndex::new(txn_idx),
             incarnation,
-            values
-                .into_iter()
-                .map(|(k, (v, l))| (k, ValueWithLayout::Exchanged(Arc::new(v), l))),
+            values.into_iter().map(|(k, (v, l))| {
+                self.allocated
+                    .record_entry(v.bytes().map_or(0, |bytes| bytes.len()));
+                (k, ValueWithLayout::Exchanged(Arc::new(v), l))
+            }),
         )
     }
 
     /// Mark all entry from transaction 'txn_idx' at access path 'key' as an estimated write
     /// (for future incarnation). Will panic if the entry is not in the data-structure.
+    /// Estimates are per tag: only the tags written by the transaction are marked, so reads of
+    /// the other tags of the group do not depend on the transaction (but group size reads do).
     pub fn mark_estimate(&self, key: &K, txn_idx: TxnIndex) {
+        record_access(KeySpace::Group, key, txn_idx, AccessKind::MarkEstimate);
         self.group_values
             .get_mut(key)
             .expect("Path must exist")
@@ -443,6 +465,7 @@ impl<
         tag: &T,
         txn_idx: TxnIndex,
     ) -> Result<(Version, ValueWithLayout<V>), MVGroupError> {
+        record_access(KeySpace::Group, key, txn_idx, AccessKind::Read);
         match self.group_values.get(key) {
             Some(g) => g.get_latest_tagged_value(tag, txn_idx),
             None => Err(MVGroupError::Uninitialized),
@@ -489,6 +512,76 @@ impl<
         Ok(v.get_committed_group())
     }
 
+    /// Removes all groups that were written by the transactions of the block, and retains the
+    /// storage base values of the other groups. Similar to data, groups with a base value that
+    /// contains delayed fields are also removed.
+    pub(crate) fn retain_unmodified_base_values(&self) {
+        let zero_idx = ShiftedTxnIndex::zero_idx();
+        self.group_values.retain(|_, g| {
+            g.idx_to_update.len() == 1
+                && g.idx_to_update.get(&zero_idx).map_or(false, |base_values| {
+                    base_values
+                        .values()
+                        .all(|v| !matches!(v, ValueWithLayout::Exchanged(_, Some(_))))
+                })
+        });
+        self.allocated.reset();
+    }
+
+    /// Describes the entries of every tag of the group, and the committed tags, one per line (see
+    /// [`crate::MVHashMap::dump_key`]).
+    pub(crate) fn dump(&self, key: &K) -> Option<Vec<String>> {
+        let g = self.group_values.get(key)?;
+        // Sorted by tag, for readability.
+        let mut tags: Vec<_> = g.versioned_map.iter().collect();
+        tags.sort_by_cached_key(|(tag, _)| format!("{:?}", tag));
+        let mut lines = Vec::new();
+        for (tag, tree) in tags {
+            lines.push(format!("tag {:?}:", tag));
+            lines.extend(tree.iter().map(|(idx, entry)| {
+                format!(
+                    "  {}: incarnation {}, {}{}",
+                    idx,
+                    entry.incarnation,
+                    entry.value.describe(),
+                    if entry.flag == Flag::Estimate {
+                        ", estimate"
+                    } else {
+                        ""
+                    }
+                )
+            }));
+        }
+        let mut committed_tags: Vec<_> = g
+            .committed_group
+            .keys()
+            .map(|tag| format!("{:?}", tag))
+            .collect();
+        committed_tags.sort();
+        lines.push(format!("committed tags: [{}]", committed_tags.join(", ")));
+        Some(lines)
+    }
+
+    /// Returns the committed contents of every group written by the first num_committed_txns
+    /// (committed) transactions, with the index of the last transaction that wrote the group.
+    pub(crate) fn committed_groups(
+        &self,
+        num_committed_txns: TxnIndex,
+    ) -> Vec<(K, TxnIndex, Vec<(T, ValueWithLayout<V>)>)> {
+        let committed_range = ShiftedTxnIndex::new(0)..ShiftedTxnIndex::new(num_committed_txns);
+        self.group_values
+            .iter()
+            .filter_map(|g| {
+                let (idx, _) = g.idx_to_update.range(committed_range.clone()).next_back()?;
+                Some((
+                    g.key().clone(),
+                    idx.idx().expect("Range excludes the storage version"),
+                    g.get_committed_group(),
+                ))
+            })
+            .collect()
+    }
+
     pub fn get_last_committed_group(
         &self,
         key: &K,
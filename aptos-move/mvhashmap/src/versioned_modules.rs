@@ -1,7 +1,11 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::types::{Flag, MVModulesError, MVModulesOutput, TxnIndex};
+use crate::{
+    memory_usage::AllocationCounter,
+    trace::{record_access, AccessKind, KeySpace},
+    types::{Flag, MVModulesError, MVModulesOutput, TxnIndex},
+};
 use aptos_crypto::hash::{DefaultHasher, HashValue};
 use aptos_types::{
     executable::{Executable, ExecutableDescriptor},
@@ -42,6 +46,9 @@ struct VersionedValue<V: TransactionWrite, X: Executable> {
 /// Maps each key (access path) to an internal VersionedValue.
 pub struct VersionedModules<K, V: TransactionWrite, X: Executable> {
     values: DashMap<K, VersionedValue<V, X>>,
+    /// The bytes allocated by the entries written during the block (see
+    /// [`crate::MVHashMap::memory_usage`]).
+    allocated: AllocationCounter,
 }
 
 impl<V: TransactionWrite> Entry<V> {
@@ -104,12 +111,18 @@ impl<K: Hash + Clone + Eq, V: TransactionWrite, X: Executable> VersionedModules<
     pub(crate) fn new() -> Self {
         Self {
             values: DashMap::new(),
+            allocated: AllocationCounter::default(),
         }
     }
 
+    pub(crate) fn allocated_bytes(&self) -> u64 {
+        self.allocated.get()
+    }
+
     /// Mark an entry from transaction 'txn_idx' at access path 'key' as an estimated write
     /// (for future incarnation). Will panic if the entry is not in the data-structure.
     pub fn mark_estimate(&self, key: &K, txn_idx: TxnIndex) {
+        record_access(KeySpace::Module, key, txn_idx, AccessKind::MarkEstimate);
         let mut v = self.values.get_mut(key).expect("Path must exist");
         v.versioned_map
             .get_mut(&txn_idx)
@@ -117,8 +130,49 @@ impl<K: Hash + Clone + Eq, V: TransactionWrite, X: Executable> VersionedModules<
             .mark_estimate();
     }
 
+    /// Describes every entry of the key, one per line (see [`crate::MVHashMap::dump_key`]).
+    pub(crate) fn dump(&self, key: &K) -> Option<Vec<String>> {
+        let v = self.values.get(key)?;
+        Some(
+            v.versioned_map
+                .iter()
+                .map(|(idx, entry)| {
+                    format!(
+                        "txn {}: {:?}, hash {}{}",
+                        idx,
+                        entry.module.write_op_kind(),
+                        entry.hash,
+                        if entry.flag == Flag::Estimate {
+                            ", estimate"
+                        } else {
+                            ""
+                        }
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the latest module at every key written by the first num_committed_txns (committed)
+    /// transactions, with the index of the last transaction that wrote the module.
+    pub(crate) fn committed_writes(
+        &self,
+        num_committed_txns: TxnIndex,
+    ) -> Vec<(K, TxnIndex, Arc<V>)> {
+        self.values
+            .iter()
+            .filter_map(|v| {
+                let (idx, entry) = v.versioned_map.range(0..num_committed_txns).next_back()?;
+                Some((v.key().clone(), *idx, entry.module.clone()))
+            })
+            .collect()
+    }
+
     /// Versioned write of module at a given key (and version).
     pub fn write(&self, key: K, txn_idx: TxnIndex, data: V) {
+        record_access(KeySpace::Module, &key, txn_idx, AccessKind::Write(None));
+        self.allocated
+            .record_entry(data.bytes().map_or(0, |bytes| bytes.len()));
         let mut v = self.values.entry(key).or_default();
         v.versioned_map
             .insert(txn_idx, CachePadded::new(Entry::new_write_from(data)));
@@ -145,6 +199,7 @@ impl<K: Hash + Clone + Eq, V: TransactionWrite, X: Executable> VersionedModules<
         use MVModulesError::*;
         use MVModulesOutput::*;
 
+        record_access(KeySpace::Module, key, txn_idx, AccessKind::Read);
         match self.values.get(key) {
             Some(v) => v
                 .read(txn_idx)
@@ -0,0 +1,99 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    sharded_map::ShardedMap,
+    types::{Flag, MVRangeError, TxnIndex},
+    utils::is_empty_range,
+};
+use std::{collections::BTreeMap, hash::Hash, ops::Bound};
+
+/// The entry of a key written by a transaction: whether the key exists after the write (i.e.
+/// false for a deletion), and the estimate flag, mirrored from the data entry.
+struct Entry {
+    exists: bool,
+    flag: Flag,
+}
+
+/// Orders the keys written by the block within their prefixes (e.g. the items of a table), with
+/// the versions of the entries of every key, so that the keys of a prefix can be read by range
+/// at a given transaction index. Only the written keys are indexed: the keys in storage are
+/// scanned by the callers and overlaid with the keys read from the index.
+pub(crate) struct VersionedOrderedIndex<P, K> {
+    prefixes: ShardedMap<P, BTreeMap<K, BTreeMap<TxnIndex, Entry>>>,
+}
+
+impl<P: Hash + Eq, K: Ord + Clone> VersionedOrderedIndex<P, K> {
+    pub(crate) fn new() -> Self {
+        Self {
+            prefixes: ShardedMap::new("ordered_index"),
+        }
+    }
+
+    pub(crate) fn write(&self, prefix: P, key: K, txn_idx: TxnIndex, exists: bool) {
+        self.prefixes
+            .get_mut_or_default(prefix)
+            .entry(key)
+            .or_default()
+            .insert(txn_idx, Entry {
+                exists,
+                flag: Flag::Done,
+            });
+    }
+
+    /// Will panic if the entry is not in the index.
+    pub(crate) fn mark_estimate(&self, prefix: &P, key: &K, txn_idx: TxnIndex) {
+        self.prefixes
+            .get_mut(prefix)
+            .expect("Prefix must exist")
+            .get_mut(key)
+            .and_then(|versions| versions.get_mut(&txn_idx))
+            .expect("Entry by the txn must exist to mark estimate")
+            .flag = Flag::Estimate;
+    }
+
+    /// Will panic if the entry is not in the index. The keys without entries are removed, so
+    /// that they are not visited by the range reads.
+    pub(crate) fn remove(&self, prefix: &P, key: &K, txn_idx: TxnIndex) {
+        let mut keys = self.prefixes.get_mut(prefix).expect("Prefix must exist");
+        let versions = keys.get_mut(key).expect("Key must exist");
+        assert!(
+            versions.remove(&txn_idx).is_some(),
+            "Entry for key / idx must exist to be deleted"
+        );
+        if versions.is_empty() {
+            keys.remove(key);
+        }
+    }
+
+    /// Returns the keys of the prefix in the range that were written by the transactions
+    /// before txn_idx, in order, each with whether it exists after the last of these writes.
+    /// Returns a dependency if the last write of a key in the range is an estimate.
+    pub(crate) fn fetch_range(
+        &self,
+        prefix: &P,
+        range: &(Bound<K>, Bound<K>),
+        txn_idx: TxnIndex,
+    ) -> Result<Vec<(K, bool)>, MVRangeError> {
+        let Some(keys) = self.prefixes.get(prefix) else {
+            return Ok(vec![]);
+        };
+        if is_empty_range(range) {
+            return Ok(vec![]);
+        }
+
+        keys.range::<K, _>((range.0.as_ref(), range.1.as_ref()))
+            .filter_map(|(key, versions)| {
+                let (idx, entry) = versions.range(..txn_idx).next_back()?;
+                Some(match entry.flag {
+                    Flag::Done => Ok((key.clone(), entry.exists)),
+                    Flag::Estimate => Err(MVRangeError::Dependency(*idx)),
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn clear(&self) {
+        self.prefixes.retain(|_, _| false);
+    }
+}
@@ -8,6 +8,7 @@ mod indexer;
 mod logger;
 mod network;
 mod services;
+mod standby;
 mod state_sync;
 mod storage;
 pub mod utils;
@@ -15,7 +16,7 @@ pub mod utils;
 #[cfg(test)]
 mod tests;
 
-use crate::network::ApplicationNetworkInterfaces;
+use crate::{network::ApplicationNetworkInterfaces, standby::StandbyComponents};
 use anyhow::anyhow;
 use aptos_admin_service::AdminService;
 use aptos_api::bootstrap as bootstrap_api;
@@ -24,7 +25,9 @@ use aptos_config::config::{
     merge_node_config, InitialSafetyRulesConfig, NodeConfig, PersistableConfig,
 };
 use aptos_dkg_runtime::start_dkg_runtime;
+use aptos_executor::execution_audit_log::EXECUTION_AUDIT_LOG;
 use aptos_framework::ReleaseBundle;
+use aptos_infallible::Mutex;
 use aptos_jwk_consensus::start_jwk_consensus_runtime;
 use aptos_logger::{prelude::*, telemetry_log_writer::TelemetryLog, Level, LoggerFilterUpdater};
 use aptos_safety_rules::safety_rules_manager::load_consensus_key_from_secure_storage;
@@ -200,6 +203,7 @@ pub struct AptosHandle {
     _mempool_runtime: Runtime,
     _network_runtimes: Vec<Runtime>,
     _peer_monitoring_service_runtime: Runtime,
+    _standby_consensus_runtime: Arc<Mutex<Option<Runtime>>>,
     _state_sync_runtimes: StateSyncRuntimes,
     _telemetry_runtime: Option<Runtime>,
 }
@@ -576,6 +580,14 @@ pub fn setup_environment_and_start_node(
     // Set the Aptos VM configurations
     utils::set_aptos_vm_configurations(&node_config);
 
+    // Enable the execution result audit log (if configured)
+    if let Some(audit_log_config) = node_config.execution.audit_log {
+        EXECUTION_AUDIT_LOG.enable(
+            audit_log_config.sampling_prefix_bits as usize,
+            audit_log_config.max_sampled_blocks,
+        );
+    }
+
     // Obtain the chain_id from the DB
     let chain_id = utils::fetch_chain_id(&db_rw)?;
 
@@ -609,6 +621,7 @@ pub fn setup_environment_and_start_node(
         mempool_network_interfaces,
         peer_monitoring_service_network_interfaces,
         storage_service_network_interfaces,
+        standby_validator_network,
     ) = network::setup_networks_and_get_interfaces(
         &node_config,
         chain_id,
@@ -730,26 +743,51 @@ pub fn setup_environment_and_start_node(
         _ => None,
     };
 
-    // Create the consensus runtime (this blocks on state sync first)
-    let consensus_runtime = consensus_network_interfaces.map(|consensus_network_interfaces| {
-        // Wait until state sync has been initialized
-        debug!("Waiting until state sync is initialized!");
-        state_sync_runtimes.block_until_initialized();
-        debug!("State sync initialization complete.");
-
-        // Initialize and start consensus
-        let (runtime, consensus_db, quorum_store_db) = services::start_consensus_runtime(
-            &mut node_config,
-            db_rw,
-            consensus_reconfig_subscription,
-            consensus_network_interfaces,
-            consensus_notifier,
-            consensus_to_mempool_sender,
-            vtxn_pool,
-        );
-        admin_service.set_consensus_dbs(consensus_db, quorum_store_db);
-        runtime
-    });
+    // Create the consensus runtime (this blocks on state sync first). A standby
+    // only starts consensus once it is promoted through the admin service.
+    let standby_consensus_runtime = Arc::new(Mutex::new(None));
+    let consensus_runtime = match consensus_network_interfaces {
+        Some(consensus_network_interfaces) if node_config.node_startup.standby => {
+            info!("Starting the node as a standby! Consensus will start once promoted.");
+            let standby_components = StandbyComponents {
+                node_config: node_config.clone(),
+                db_rw,
+                consensus_reconfig_subscription,
+                consensus_network_interfaces,
+                consensus_notifier,
+                consensus_to_mempool_sender,
+                vtxn_pool,
+                validator_network: standby_validator_network,
+            };
+            admin_service.set_standby_promoter(standby::create_standby_promoter(
+                &state_sync_runtimes,
+                standby_components,
+                standby_consensus_runtime.clone(),
+            ));
+            None
+        },
+        consensus_network_interfaces => {
+            consensus_network_interfaces.map(|consensus_network_interfaces| {
+                // Wait until state sync has been initialized
+                debug!("Waiting until state sync is initialized!");
+                state_sync_runtimes.block_until_initialized();
+                debug!("State sync initialization complete.");
+
+                // Initialize and start consensus
+                let (runtime, consensus_db, quorum_store_db) = services::start_consensus_runtime(
+                    &mut node_config,
+                    db_rw,
+                    consensus_reconfig_subscription,
+                    consensus_network_interfaces,
+                    consensus_notifier,
+                    consensus_to_mempool_sender,
+                    vtxn_pool,
+                );
+                admin_service.set_consensus_dbs(consensus_db, quorum_store_db);
+                runtime
+            })
+        },
+    };
 
     Ok(AptosHandle {
         _admin_service: admin_service,
@@ -764,6 +802,7 @@ pub fn setup_environment_and_start_node(
         _mempool_runtime: mempool_runtime,
         _network_runtimes: network_runtimes,
         _peer_monitoring_service_runtime: peer_monitoring_service_runtime,
+        _standby_consensus_runtime: standby_consensus_runtime,
         _state_sync_runtimes: state_sync_runtimes,
         _telemetry_runtime: telemetry_runtime,
     })
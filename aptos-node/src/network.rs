@@ -216,7 +216,9 @@ pub fn create_peers_and_metadata(node_config: &NodeConfig) -> Arc<PeersAndMetada
     PeersAndMetadata::new(&network_ids)
 }
 
-/// Sets up all networks and returns the appropriate application network interfaces
+/// Sets up all networks and returns the appropriate application network interfaces. If the
+/// node is a standby, the validator network is built but not started, and is returned (so
+/// that it can be started once the node is promoted).
 pub fn setup_networks_and_get_interfaces(
     node_config: &NodeConfig,
     chain_id: ChainId,
@@ -230,6 +232,7 @@ pub fn setup_networks_and_get_interfaces(
     ApplicationNetworkInterfaces<MempoolSyncMsg>,
     ApplicationNetworkInterfaces<PeerMonitoringServiceMessage>,
     ApplicationNetworkInterfaces<StorageServiceMessage>,
+    Option<NetworkBuilder>,
 ) {
     // Gather all network configs
     let network_configs = extract_network_configs(node_config);
@@ -243,6 +246,7 @@ pub fn setup_networks_and_get_interfaces(
     let mut peer_monitoring_service_network_handles = vec![];
     let mut storage_service_network_handles = vec![];
     let mut netbench_handles = Vec::<ApplicationNetworkHandle<NetbenchMessage>>::new();
+    let mut standby_validator_network = None;
     for network_config in network_configs.into_iter() {
         // Create a network runtime for the config
         let runtime = create_network_runtime(&network_config);
@@ -336,14 +340,20 @@ pub fn setup_networks_and_get_interfaces(
             netbench_handles.push(netbench_handle);
         }
 
-        // Build and start the network on the runtime
+        // Build and start the network on the runtime. A standby only starts the
+        // validator network once it is promoted (the validator identity is still
+        // in use by the active validator).
         network_builder.build(runtime.handle().clone());
-        network_builder.start();
-        network_runtimes.push(runtime);
         debug!(
             "Network built for the network context: {}",
             network_builder.network_context()
         );
+        if network_id.is_validator_network() && node_config.node_startup.standby {
+            standby_validator_network = Some(network_builder);
+        } else {
+            network_builder.start();
+        }
+        network_runtimes.push(runtime);
     }
 
     // Transform all network handles into application interfaces
@@ -386,6 +396,7 @@ pub fn setup_networks_and_get_interfaces(
         mempool_interfaces,
         peer_monitoring_service_interfaces,
         storage_service_interfaces,
+        standby_validator_network,
     )
 }
 
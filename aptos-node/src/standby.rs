@@ -0,0 +1,110 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A hot standby is a validator node that executes and verifies all blocks (through state
+//! sync) but doesn't participate in consensus: its validator network and consensus are only
+//! started once it is promoted through the admin service. This allows operators to fail over
+//! to a node that already holds the latest state.
+//!
+//! Note: the standby shares the identity of the active validator, so the operator must ensure
+//! that the active validator has been stopped before promoting the standby. The safety rules
+//! storage is shared by both nodes (this is enforced by the config sanitizer), so that the
+//! promoted standby never votes in conflict with the votes of the previously active validator.
+
+use crate::{network::ApplicationNetworkInterfaces, services};
+use anyhow::anyhow;
+use aptos_admin_service::StandbyPromoter;
+use aptos_config::config::NodeConfig;
+use aptos_consensus::{
+    network_interface::ConsensusMsg, persistent_liveness_storage::StorageWriteProxy,
+    quorum_store::quorum_store_db::QuorumStoreDB,
+};
+use aptos_consensus_notifications::ConsensusNotifier;
+use aptos_event_notifications::{DbBackedOnChainConfig, ReconfigNotificationListener};
+use aptos_infallible::Mutex;
+use aptos_logger::info;
+use aptos_mempool::QuorumStoreRequest;
+use aptos_network_builder::builder::NetworkBuilder;
+use aptos_state_sync_driver::driver_factory::StateSyncRuntimes;
+use aptos_storage_interface::DbReaderWriter;
+use aptos_validator_transaction_pool::VTxnPoolState;
+use futures::{channel::mpsc::Sender, FutureExt};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+/// The components required to start consensus once the standby is promoted
+pub struct StandbyComponents {
+    pub node_config: NodeConfig,
+    pub db_rw: DbReaderWriter,
+    pub consensus_reconfig_subscription:
+        Option<ReconfigNotificationListener<DbBackedOnChainConfig>>,
+    pub consensus_network_interfaces: ApplicationNetworkInterfaces<ConsensusMsg>,
+    pub consensus_notifier: ConsensusNotifier,
+    pub consensus_to_mempool_sender: Sender<QuorumStoreRequest>,
+    pub vtxn_pool: VTxnPoolState,
+    pub validator_network: Option<NetworkBuilder>,
+}
+
+impl StandbyComponents {
+    /// Starts the validator network and consensus, and returns the consensus runtime
+    fn start(mut self) -> (Runtime, Arc<StorageWriteProxy>, Arc<QuorumStoreDB>) {
+        if let Some(mut validator_network) = self.validator_network {
+            validator_network.start();
+            info!(
+                "Started the validator network for the network context: {}",
+                validator_network.network_context()
+            );
+        }
+
+        services::start_consensus_runtime(
+            &mut self.node_config,
+            self.db_rw,
+            self.consensus_reconfig_subscription,
+            self.consensus_network_interfaces,
+            self.consensus_notifier,
+            self.consensus_to_mempool_sender,
+            self.vtxn_pool,
+        )
+    }
+}
+
+/// Creates the promoter of the standby. On promotion, state sync stops syncing (once all
+/// synced data has been committed) and the consensus components are started. The consensus
+/// runtime of the promoted node is stored in the given `consensus_runtime`.
+pub fn create_standby_promoter(
+    state_sync_runtimes: &StateSyncRuntimes,
+    standby_components: StandbyComponents,
+    consensus_runtime: Arc<Mutex<Option<Runtime>>>,
+) -> StandbyPromoter {
+    let state_sync_client = Arc::new(state_sync_runtimes.create_driver_client());
+    let standby_components = Arc::new(Mutex::new(Some(standby_components)));
+
+    Arc::new(move || {
+        let state_sync_client = state_sync_client.clone();
+        let standby_components = standby_components.clone();
+        let consensus_runtime = consensus_runtime.clone();
+        async move {
+            // Take the components, so that the standby is only promoted once
+            let components = standby_components
+                .lock()
+                .take()
+                .ok_or_else(|| anyhow!("The standby has already been promoted!"))?;
+
+            // Hand control of storage over from state sync to consensus
+            if let Err(error) = state_sync_client.promote_standby().await {
+                *standby_components.lock() = Some(components);
+                return Err(anyhow!(
+                    "State sync failed to promote the standby: {}",
+                    error
+                ));
+            }
+
+            // Start the validator network and consensus
+            let (runtime, consensus_db, quorum_store_db) =
+                tokio::task::spawn_blocking(move || components.start()).await?;
+            *consensus_runtime.lock() = Some(runtime);
+            Ok((consensus_db, quorum_store_db))
+        }
+        .boxed()
+    })
+}
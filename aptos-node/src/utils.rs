@@ -9,7 +9,7 @@ use aptos_types::{
     state_store::account_with_state_view::AsAccountWithStateView,
 };
 use aptos_vm::AptosVM;
-use std::cmp::min;
+use std::{cmp::min, time::Duration};
 
 /// Error message to display when non-production features are enabled
 pub const ERROR_MSG_BAD_FEATURE_FLAGS: &str = r#"
@@ -57,6 +57,36 @@ pub fn set_aptos_vm_configurations(node_config: &NodeConfig) {
     };
     AptosVM::set_concurrency_level_once(effective_concurrency_level as usize);
     AptosVM::set_discard_failed_blocks(node_config.execution.discard_failed_blocks);
+    AptosVM::set_fallback_policies_once(node_config.execution.fallback_policies);
+    AptosVM::set_adaptive_concurrency_once(node_config.execution.adaptive_concurrency);
+    AptosVM::set_dependency_wait_once(node_config.execution.dependency_wait);
+    AptosVM::set_early_delayed_field_validation(
+        node_config.execution.early_delayed_field_validation,
+    );
+    AptosVM::set_prefetch_base_values(node_config.execution.prefetch_base_values);
+    AptosVM::set_prioritize_by_gas_price(node_config.execution.prioritize_by_gas_price);
+    AptosVM::set_materialization_concurrency_once(
+        node_config.execution.materialization_concurrency as usize,
+    );
+    AptosVM::set_core_affinity_once(node_config.execution.core_affinity.clone());
+    AptosVM::set_differential_execution(node_config.execution.differential_execution);
+    AptosVM::set_hot_state_cache_capacity_once(node_config.execution.hot_state_cache_capacity);
+    AptosVM::set_txn_execution_timeout_once(
+        node_config
+            .execution
+            .txn_execution_timeout_ms
+            .map(Duration::from_millis),
+    );
+    AptosVM::set_max_incarnations_once(node_config.execution.max_txn_incarnations);
+    AptosVM::set_block_time_budget_once(
+        node_config
+            .execution
+            .block_time_budget_ms
+            .map(Duration::from_millis),
+    );
+    AptosVM::set_mvhashmap_memory_soft_cap_once(
+        node_config.execution.mvhashmap_memory_soft_cap_bytes,
+    );
     AptosVM::set_num_proof_reading_threads_once(
         node_config.execution.num_proof_reading_threads as usize,
     );
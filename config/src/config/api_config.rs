@@ -81,6 +81,64 @@ pub struct ApiConfig {
     pub view_filter: ViewFilter,
     /// Periodically log stats for view function and simulate transaction usage
     pub periodic_function_stats_sec: Option<u64>,
+    /// Enables the v2 namespace of the API (/v2). Until the v2 endpoints diverge
+    /// from v1, v2 requests are served by the v1 endpoints.
+    #[serde(default = "default_enabled")]
+    pub v2_enabled: bool,
+    /// If set, the v1 API is announced as deprecated in the headers of its responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub v1_deprecation: Option<ApiDeprecationConfig>,
+    /// Configs for shedding the expensive requests while the node is overloaded
+    pub load_shedding: LoadSheddingConfig,
+}
+
+/// The deprecation of an API version, announced in the headers of all its responses
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiDeprecationConfig {
+    /// The date after which the version may be removed, sent in the Sunset header.
+    /// This must be an HTTP-date, e.g., "Wed, 01 Jan 2025 00:00:00 GMT".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sunset: Option<String>,
+    /// A warning for the clients (e.g., pointing to the migration guide), sent in the Warning header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// While the node is overloaded, the expensive requests (i.e., simulations, view functions
+/// and large pages) are rejected with a Retry-After header, so that the cheap requests (e.g.,
+/// health checks and transaction submissions) are still served promptly. The node is
+/// overloaded if reading the latest ledger info from storage is slow, or if the latest
+/// ledger info is too old (i.e., the execution and commit of the blocks falls behind).
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoadSheddingConfig {
+    /// Enables the load shedding
+    pub enabled: bool,
+    /// How often the load of the node is checked
+    pub check_interval_ms: u64,
+    /// The node is overloaded if reading the latest ledger info takes longer
+    pub storage_latency_threshold_ms: u64,
+    /// The node is overloaded if the timestamp of the latest ledger info is older.
+    /// Note: this includes nodes that are catching up with the network.
+    pub ledger_lag_threshold_ms: u64,
+    /// While the node is overloaded, the paginated requests with a larger limit are rejected
+    pub max_page_size_under_load: u16,
+    /// The time after which the rejected requests should be retried, in the Retry-After header
+    pub retry_after_secs: u64,
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            check_interval_ms: 1_000,
+            storage_latency_threshold_ms: 500,
+            ledger_lag_threshold_ms: 30_000,
+            max_page_size_under_load: DEFAULT_MAX_PAGE_SIZE,
+            retry_after_secs: 5,
+        }
+    }
 }
 
 const DEFAULT_ADDRESS: &str = "127.0.0.1";
@@ -129,6 +187,9 @@ impl Default for ApiConfig {
             simulation_filter: Filter::default(),
             view_filter: ViewFilter::default(),
             periodic_function_stats_sec: Some(60),
+            v2_enabled: default_enabled(),
+            v1_deprecation: None,
+            load_shedding: LoadSheddingConfig::default(),
         }
     }
 }
@@ -188,6 +249,34 @@ impl ConfigSanitizer for ApiConfig {
             }
         }
 
+        // Verify that the v1 deprecation can be announced
+        if let Some(v1_deprecation) = &api_config.v1_deprecation {
+            if !api_config.v2_enabled {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "The v1 API cannot be deprecated with the v2 API disabled!".into(),
+                ));
+            }
+            let is_header_value =
+                |value: &String| value.chars().all(|c| c.is_ascii() && !c.is_ascii_control());
+            if !v1_deprecation.sunset.iter().all(is_header_value)
+                || !v1_deprecation.message.iter().all(is_header_value)
+            {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "The v1 deprecation sunset and message must be printable ASCII!".into(),
+                ));
+            }
+        }
+
+        // Verify that the load of the node can be checked
+        if api_config.load_shedding.enabled && api_config.load_shedding.check_interval_ms == 0 {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "The load shedding check interval must be greater than 0!".into(),
+            ));
+        }
+
         // Sanitize the gas estimation config
         GasEstimationConfig::sanitize(node_config, node_type, chain_id)?;
 
@@ -303,4 +392,63 @@ mod tests {
                 .unwrap_err();
         assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
     }
+
+    #[test]
+    fn test_sanitize_v1_deprecation() {
+        // Create a node config that deprecates the v1 API
+        let mut node_config = NodeConfig {
+            api: ApiConfig {
+                v1_deprecation: Some(ApiDeprecationConfig {
+                    sunset: Some("Wed, 01 Jan 2025 00:00:00 GMT".into()),
+                    message: Some("Migrate to /v2".into()),
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it succeeds
+        ApiConfig::sanitize(&node_config, NodeType::Validator, Some(ChainId::mainnet())).unwrap();
+
+        // Verify that the deprecation message must be a valid header value
+        node_config.api.v1_deprecation.as_mut().unwrap().message = Some("Migrate\nnow".into());
+        let error =
+            ApiConfig::sanitize(&node_config, NodeType::Validator, Some(ChainId::mainnet()))
+                .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Verify that v1 cannot be deprecated without v2
+        node_config.api.v1_deprecation.as_mut().unwrap().message = None;
+        node_config.api.v2_enabled = false;
+        let error =
+            ApiConfig::sanitize(&node_config, NodeType::Validator, Some(ChainId::mainnet()))
+                .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
+
+    #[test]
+    fn test_sanitize_load_shedding() {
+        // Create a node config with load shedding enabled, but no check interval
+        let mut node_config = NodeConfig {
+            api: ApiConfig {
+                load_shedding: LoadSheddingConfig {
+                    enabled: true,
+                    check_interval_ms: 0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Verify that the config sanitizer fails
+        let error =
+            ApiConfig::sanitize(&node_config, NodeType::Validator, Some(ChainId::mainnet()))
+                .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Verify that the config sanitizer passes with a check interval
+        node_config.api.load_shedding.check_interval_ms = 1_000;
+        ApiConfig::sanitize(&node_config, NodeType::Validator, Some(ChainId::mainnet())).unwrap();
+    }
 }
@@ -4,9 +4,10 @@
 use crate::config::{
     node_config_loader::NodeType,
     utils::{are_failpoints_enabled, get_config_name},
-    AdminServiceConfig, ApiConfig, BaseConfig, ConsensusConfig, DagConsensusConfig, Error,
-    ExecutionConfig, IndexerGrpcConfig, InspectionServiceConfig, LoggerConfig, MempoolConfig,
-    NetbenchConfig, NodeConfig, PeerMonitoringServiceConfig, StateSyncConfig, StorageConfig,
+    AdminServiceConfig, ApiConfig, BaseConfig, ConsensusConfig, ContinuousSyncingMode,
+    DagConsensusConfig, Error, ExecutionConfig, IndexerGrpcConfig, InspectionServiceConfig,
+    LoggerConfig, MempoolConfig, NetbenchConfig, NodeConfig, PeerMonitoringServiceConfig,
+    SecureBackend, StateSyncConfig, StorageConfig,
 };
 use aptos_types::chain_id::ChainId;
 use std::collections::HashSet;
@@ -15,6 +16,7 @@ use std::collections::HashSet;
 const FAILPOINTS_SANITIZER_NAME: &str = "FailpointsConfigSanitizer";
 const FULLNODE_NETWORKS_SANITIZER_NAME: &str = "FullnodeNetworksConfigSanitizer";
 const SANITIZER_STRING: &str = "Sanitizer";
+const STANDBY_SANITIZER_NAME: &str = "StandbyConfigSanitizer";
 const VALIDATOR_NETWORK_SANITIZER_NAME: &str = "ValidatorNetworkConfigSanitizer";
 
 /// A trait for validating and sanitizing node configs (and their sub-configs)
@@ -61,6 +63,7 @@ impl ConfigSanitizer for NodeConfig {
         MempoolConfig::sanitize(node_config, node_type, chain_id)?;
         NetbenchConfig::sanitize(node_config, node_type, chain_id)?;
         PeerMonitoringServiceConfig::sanitize(node_config, node_type, chain_id)?;
+        sanitize_standby_config(node_config, node_type, chain_id)?;
         StateSyncConfig::sanitize(node_config, node_type, chain_id)?;
         StorageConfig::sanitize(node_config, node_type, chain_id)?;
         sanitize_validator_network_config(node_config, node_type, chain_id)?;
@@ -152,6 +155,75 @@ fn sanitize_fullnode_network_configs(
     Ok(())
 }
 
+/// Sanitize the hot standby config according to the node role and chain ID. A standby
+/// executes all blocks (received through the fullnode networks) and only joins the
+/// validator network and consensus once promoted, so it must be able to sync without
+/// the validator network, and it must share the safety rules storage with the active
+/// validator (otherwise, the promoted standby could equivocate).
+fn sanitize_standby_config(
+    node_config: &NodeConfig,
+    node_type: NodeType,
+    _chain_id: Option<ChainId>,
+) -> Result<(), Error> {
+    let sanitizer_name = STANDBY_SANITIZER_NAME.to_string();
+    if !node_config.node_startup.standby {
+        return Ok(()); // The node is not a standby
+    }
+
+    // Verify that the node is a validator
+    if !node_type.is_validator() {
+        return Err(Error::ConfigSanitizerFailed(
+            sanitizer_name,
+            "Only validators can be started as a standby!".into(),
+        ));
+    }
+
+    // Verify that the standby can sync through the fullnode networks
+    if node_config.full_node_networks.is_empty() {
+        return Err(Error::ConfigSanitizerFailed(
+            sanitizer_name,
+            "A standby requires a fullnode network to sync from!".into(),
+        ));
+    }
+
+    // Verify that the standby executes (and verifies) all transactions
+    let continuous_syncing_mode = node_config
+        .state_sync
+        .state_sync_driver
+        .continuous_syncing_mode;
+    if continuous_syncing_mode != ContinuousSyncingMode::ExecuteTransactions {
+        return Err(Error::ConfigSanitizerFailed(
+            sanitizer_name,
+            format!(
+                "A standby must execute all transactions! Continuous syncing mode: {:?}",
+                continuous_syncing_mode
+            ),
+        ));
+    }
+
+    // Verify that the standby doesn't serve the API
+    if node_config.api.enabled {
+        return Err(Error::ConfigSanitizerFailed(
+            sanitizer_name,
+            "A standby cannot serve the API!".into(),
+        ));
+    }
+
+    // Verify that the safety rules storage can be shared with the active validator
+    if !matches!(
+        node_config.consensus.safety_rules.backend,
+        SecureBackend::Vault(_)
+    ) {
+        return Err(Error::ConfigSanitizerFailed(
+            sanitizer_name,
+            "A standby must use a vault safety rules backend (shared with the active validator)!"
+                .into(),
+        ));
+    }
+
+    Ok(())
+}
+
 /// Sanitize the validator network config according to the node role and chain ID
 fn sanitize_validator_network_config(
     node_config: &NodeConfig,
@@ -203,7 +275,10 @@ fn sanitize_validator_network_config(
 mod tests {
     use super::*;
     use crate::{
-        config::{node_startup_config::NodeStartupConfig, NetworkConfig},
+        config::{
+            node_startup_config::NodeStartupConfig, ApiConfig, NetworkConfig, SafetyRulesConfig,
+            StateSyncDriverConfig, Token, VaultConfig,
+        },
         network_id::NetworkId,
     };
 
@@ -321,6 +396,61 @@ mod tests {
         assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
     }
 
+    #[test]
+    fn test_sanitize_standby_config() {
+        // Create a valid standby node config
+        let mut node_config = create_standby_node_config();
+
+        // Sanitize the config and verify that it passes
+        sanitize_standby_config(&node_config, NodeType::Validator, Some(ChainId::testnet()))
+            .unwrap();
+
+        // Verify that the sanitizer fails for fullnodes
+        let error = sanitize_standby_config(
+            &node_config,
+            NodeType::PublicFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+
+        // Verify that the sanitizer fails if the API is enabled
+        node_config.api.enabled = true;
+        let error =
+            sanitize_standby_config(&node_config, NodeType::Validator, Some(ChainId::testnet()))
+                .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
+
+    #[test]
+    fn test_sanitize_standby_config_local_safety_rules() {
+        // Create a standby node config with in-memory safety rules storage
+        let mut node_config = create_standby_node_config();
+        node_config.consensus.safety_rules.backend = SecureBackend::InMemoryStorage;
+
+        // Sanitize the config and verify that it fails
+        let error =
+            sanitize_standby_config(&node_config, NodeType::Validator, Some(ChainId::testnet()))
+                .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
+
+    #[test]
+    fn test_sanitize_standby_config_output_syncing() {
+        // Create a standby node config that applies transaction outputs
+        let mut node_config = create_standby_node_config();
+        node_config
+            .state_sync
+            .state_sync_driver
+            .continuous_syncing_mode = ContinuousSyncingMode::ApplyTransactionOutputs;
+
+        // Sanitize the config and verify that it fails
+        let error =
+            sanitize_standby_config(&node_config, NodeType::Validator, Some(ChainId::testnet()))
+                .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
+
     #[test]
     fn test_sanitize_missing_validator_network_config() {
         // Create a node config with an empty validator network config
@@ -403,4 +533,38 @@ mod tests {
         .unwrap_err();
         assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
     }
+
+    /// Creates a node config for a standby that passes the standby sanitizer
+    fn create_standby_node_config() -> NodeConfig {
+        let mut node_config = NodeConfig {
+            node_startup: NodeStartupConfig {
+                standby: true,
+                ..Default::default()
+            },
+            api: ApiConfig {
+                enabled: false,
+                ..Default::default()
+            },
+            full_node_networks: vec![NetworkConfig::network_with_id(NetworkId::Vfn)],
+            ..Default::default()
+        };
+        node_config.state_sync.state_sync_driver = StateSyncDriverConfig {
+            continuous_syncing_mode: ContinuousSyncingMode::ExecuteTransactions,
+            ..Default::default()
+        };
+        node_config.consensus.safety_rules = SafetyRulesConfig {
+            backend: SecureBackend::Vault(VaultConfig {
+                ca_certificate: None,
+                namespace: None,
+                renew_ttl_secs: None,
+                server: "127.0.0.1:8200".into(),
+                token: Token::FromConfig("test".into()),
+                disable_cas: None,
+                connection_timeout_ms: None,
+                response_timeout_ms: None,
+            }),
+            ..Default::default()
+        };
+        node_config
+    }
 }
@@ -57,6 +57,10 @@ pub struct ConsensusConfig {
     pub quorum_store: QuorumStoreConfig,
     pub vote_back_pressure_limit: u64,
     pub pipeline_backpressure: Vec<PipelineBackpressureValues>,
+    // Backpressure based on the number of blocks pending in the execution pipeline. When set, it
+    // replaces the (commit latency based) pipeline_backpressure, as it reacts as soon as blocks
+    // start queueing up for execution, rather than once they are already late to commit.
+    pub execution_backpressure: Option<ExecutionBackpressureValues>,
     // Used to decide if backoff is needed.
     // must match one of the CHAIN_HEALTH_WINDOW_SIZES values.
     pub window_for_chain_health: usize,
@@ -127,6 +131,18 @@ pub struct PipelineBackpressureValues {
     pub max_txns_from_block_to_execute: Option<usize>,
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ExecutionBackpressureValues {
+    // Backpressure is engaged once the number of blocks pending execution or ledger update
+    // reaches enter_pipeline_depth, and only released once it drops to exit_pipeline_depth,
+    // so that proposal sizes don't flap around a single threshold.
+    pub enter_pipeline_depth: usize,
+    pub exit_pipeline_depth: usize,
+    pub max_sending_block_txns_override: u64,
+    pub max_sending_block_bytes_override: u64,
+    pub backpressure_proposal_delay_ms: u64,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 pub struct ChainHealthBackoffValues {
     pub backoff_if_below_participating_voting_power_percentage: usize,
@@ -233,6 +249,7 @@ impl Default for ConsensusConfig {
                     max_txns_from_block_to_execute: None,
                 },
             ],
+            execution_backpressure: None,
             window_for_chain_health: 100,
             chain_health_backoff: vec![
                 ChainHealthBackoffValues {
@@ -366,6 +383,24 @@ impl ConsensusConfig {
                 ),
             ));
         }
+        if let Some(backpressure_values) = &config.execution_backpressure {
+            recv_batch_send_block_pairs.push((
+                config.quorum_store.receiver_max_batch_txns as u64,
+                backpressure_values.max_sending_block_txns_override,
+                format!(
+                    "execution backpressure {} blocks: txns",
+                    backpressure_values.enter_pipeline_depth,
+                ),
+            ));
+            recv_batch_send_block_pairs.push((
+                config.quorum_store.receiver_max_batch_bytes as u64,
+                backpressure_values.max_sending_block_bytes_override,
+                format!(
+                    "execution backpressure {} blocks: bytes",
+                    backpressure_values.enter_pipeline_depth,
+                ),
+            ));
+        }
         for backoff_values in &config.chain_health_backoff {
             recv_batch_send_block_pairs.push((
                 config.quorum_store.receiver_max_batch_txns as u64,
@@ -395,6 +430,25 @@ impl ConsensusConfig {
         }
         Ok(())
     }
+
+    fn sanitize_execution_backpressure(
+        sanitizer_name: &str,
+        config: &ConsensusConfig,
+    ) -> Result<(), Error> {
+        if let Some(backpressure_values) = &config.execution_backpressure {
+            if backpressure_values.exit_pipeline_depth >= backpressure_values.enter_pipeline_depth {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name.to_owned(),
+                    format!(
+                        "Execution backpressure exit depth must be below the enter depth: {} >= {}",
+                        backpressure_values.exit_pipeline_depth,
+                        backpressure_values.enter_pipeline_depth,
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl ConfigSanitizer for ConsensusConfig {
@@ -425,6 +479,9 @@ impl ConfigSanitizer for ConsensusConfig {
         // Quorum store batches must be <= consensus blocks
         Self::sanitize_batch_block_limits(&sanitizer_name, &node_config.consensus)?;
 
+        // Execution backpressure must have a hysteresis window
+        Self::sanitize_execution_backpressure(&sanitizer_name, &node_config.consensus)?;
+
         Ok(())
     }
 }
@@ -640,6 +697,33 @@ mod test {
         assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
     }
 
+    #[test]
+    fn test_invalid_execution_backpressure_depths() {
+        // Create a node config with an execution backpressure exit depth above the enter depth
+        let node_config = NodeConfig {
+            consensus: ConsensusConfig {
+                execution_backpressure: Some(ExecutionBackpressureValues {
+                    enter_pipeline_depth: 4,
+                    exit_pipeline_depth: 4,
+                    max_sending_block_txns_override: MAX_SENDING_BLOCK_TXNS,
+                    max_sending_block_bytes_override: 3 * 1024 * 1024,
+                    backpressure_proposal_delay_ms: 0,
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails
+        let error = ConsensusConfig::sanitize(
+            &node_config,
+            NodeType::ValidatorFullnode,
+            Some(ChainId::testnet()),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
+
     #[test]
     fn test_invalid_chain_health_backoff_txn_limits() {
         // Create a node config with invalid chain health backoff txn limits
@@ -7,7 +7,13 @@ use crate::config::{
     config_sanitizer::ConfigSanitizer, node_config_loader::NodeType,
     transaction_filter_type::Filter, utils::RootPath, Error, NodeConfig,
 };
-use aptos_types::{chain_id::ChainId, transaction::Transaction};
+use aptos_types::{
+    block_executor::config::{
+        AdaptiveConcurrencyConfig, CoreAffinityConfig, DependencyWaitConfig, FallbackPolicies,
+    },
+    chain_id::ChainId,
+    transaction::Transaction,
+};
 use serde::{Deserialize, Serialize};
 use std::{
     fs::File,
@@ -35,6 +41,57 @@ pub struct ExecutionConfig {
     pub paranoid_type_verification: bool,
     /// Enabled discarding blocks that fail execution due to BlockSTM/VM issue.
     pub discard_failed_blocks: bool,
+    /// What to do with a block when its parallel execution fails, depending on the failure.
+    /// Discarding the block is not allowed for validators
+    pub fallback_policies: FallbackPolicies,
+    /// If set, parallel execution parks workers while the abort rate within a block is high
+    pub adaptive_concurrency: Option<AdaptiveConcurrencyConfig>,
+    /// How parallel execution waits on the dependencies of transactions (see
+    /// DependencyWaitConfig)
+    pub dependency_wait: DependencyWaitConfig,
+    /// Enables checking delayed field reads during the regular validation of parallel
+    /// execution, and not only at commit time
+    pub early_delayed_field_validation: bool,
+    /// Enables reading the base values of the keys likely to be read by a block from storage
+    /// before its parallel execution starts
+    pub prefetch_base_values: bool,
+    /// If set, a transaction whose execution takes longer (in milliseconds) is discarded with
+    /// EXECUTION_LIMIT_REACHED. The outcome depends on the wall-clock time, hence may differ
+    /// across nodes: not allowed for validators, only meant as a safety valve for other nodes
+    pub txn_execution_timeout_ms: Option<u64>,
+    /// If set, a transaction executed that many times during parallel execution is no longer
+    /// executed speculatively: it is executed once more when it is the next to commit
+    pub max_txn_incarnations: Option<u32>,
+    /// If set, a block is ended early once committing its transactions took longer (in
+    /// milliseconds), and the rest of the block is retried in a later block. The outcome depends
+    /// on the wall-clock time, hence may differ across nodes: not allowed for validators
+    pub block_time_budget_ms: Option<u64>,
+    /// If set, a block executed in parallel is ended early once the multi-version
+    /// data-structure takes more memory (approximately, in bytes), and the rest of the block is
+    /// retried in a later block. The outcome depends on the speculative executions, hence may
+    /// differ across nodes: not allowed for validators
+    pub mvhashmap_memory_soft_cap_bytes: Option<u64>,
+    /// Enables starting the execution of the transactions with a higher gas unit price first
+    /// during parallel execution (the commit order of the block is unchanged)
+    pub prioritize_by_gas_price: bool,
+    /// If non-zero, the number of threads dedicated to materializing the committed transactions
+    /// of parallel execution (e.g. serializing their outputs), in a separate thread pool
+    pub materialization_concurrency: u16,
+    /// If set, the CPU cores (e.g. of a NUMA node) that the worker threads of parallel execution
+    /// are pinned to (see CoreAffinityConfig)
+    pub core_affinity: Option<CoreAffinityConfig>,
+    /// Enables executing every block also sequentially, and comparing the outputs of parallel
+    /// and sequential execution (the first divergence is logged). Doubles the execution time:
+    /// only meant for testing and canary nodes
+    pub differential_execution: bool,
+    /// The number of base values (e.g. the resources of 0x1 or hot account balances) cached
+    /// across the executed blocks, which are invalidated by the writes of the blocks (0 disables
+    /// the cache). The cache is cleared when a block is not executed on top of the previously
+    /// executed one. Not allowed for validators
+    pub hot_state_cache_capacity: usize,
+    /// If set, the execution results of a sample of the committed blocks are recorded for
+    /// comparison across validators (see ExecutionAuditLogConfig)
+    pub audit_log: Option<ExecutionAuditLogConfig>,
     /// Enables paranoid mode for hot potatoes, which adds extra runtime VM checks
     pub paranoid_hot_potato_verification: bool,
     /// Enables enhanced metrics around processed transactions
@@ -72,6 +129,21 @@ impl Default for ExecutionConfig {
             paranoid_type_verification: true,
             paranoid_hot_potato_verification: true,
             discard_failed_blocks: false,
+            fallback_policies: FallbackPolicies::default(),
+            adaptive_concurrency: None,
+            dependency_wait: DependencyWaitConfig::default(),
+            early_delayed_field_validation: false,
+            prefetch_base_values: false,
+            txn_execution_timeout_ms: None,
+            max_txn_incarnations: None,
+            block_time_budget_ms: None,
+            mvhashmap_memory_soft_cap_bytes: None,
+            prioritize_by_gas_price: false,
+            materialization_concurrency: 0,
+            core_affinity: None,
+            differential_execution: false,
+            hot_state_cache_capacity: 0,
+            audit_log: None,
             processed_transactions_detailed_counters: false,
             transaction_filter: Filter::empty(),
             genesis_waypoint: None,
@@ -79,6 +151,27 @@ impl Default for ExecutionConfig {
     }
 }
 
+/// Configuration of the execution result audit log. Committed blocks are sampled by the prefix
+/// of their id (so that all validators sample the same blocks), and a digest of the outputs of
+/// their transactions is exposed through the admin service.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecutionAuditLogConfig {
+    /// A block is sampled if this many leading bits of its id are zero (i.e., 1 in 2^bits)
+    pub sampling_prefix_bits: u8,
+    /// The maximum number of sampled blocks kept in memory
+    pub max_sampled_blocks: usize,
+}
+
+impl Default for ExecutionAuditLogConfig {
+    fn default() -> Self {
+        Self {
+            sampling_prefix_bits: 6,
+            max_sampled_blocks: 1000,
+        }
+    }
+}
+
 impl ExecutionConfig {
     pub fn load_from_path(&mut self, root_dir: &RootPath) -> Result<(), Error> {
         if !self.genesis_file_location.as_os_str().is_empty() {
@@ -140,12 +233,51 @@ impl ExecutionConfig {
 impl ConfigSanitizer for ExecutionConfig {
     fn sanitize(
         node_config: &NodeConfig,
-        _node_type: NodeType,
+        node_type: NodeType,
         chain_id: Option<ChainId>,
     ) -> Result<(), Error> {
         let sanitizer_name = Self::get_sanitizer_name();
         let execution_config = &node_config.execution;
 
+        // Validators must end blocks deterministically, i.e. not depending on the wall-clock time
+        // or on the speculative executions of the block
+        if node_type.is_validator() && execution_config.block_time_budget_ms.is_some() {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "block_time_budget_ms must not be set for validators!".into(),
+            ));
+        }
+        if node_type.is_validator() && execution_config.mvhashmap_memory_soft_cap_bytes.is_some() {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "mvhashmap_memory_soft_cap_bytes must not be set for validators!".into(),
+            ));
+        }
+
+        // Validators must read the base values of blocks from storage
+        if node_type.is_validator() && execution_config.hot_state_cache_capacity > 0 {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "hot_state_cache_capacity must be 0 for validators!".into(),
+            ));
+        }
+
+        // Validators must not discard a block depending on the schedule of its parallel execution
+        if node_type.is_validator() && execution_config.fallback_policies.may_discard() {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "fallback_policies must execute failed blocks sequentially for validators!".into(),
+            ));
+        }
+
+        // Validators must not discard transactions depending on the wall-clock time
+        if node_type.is_validator() && execution_config.txn_execution_timeout_ms.is_some() {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "txn_execution_timeout_ms must not be set for validators!".into(),
+            ));
+        }
+
         // If this is a mainnet node, ensure that additional verifiers are enabled
         if let Some(chain_id) = chain_id {
             if chain_id.is_mainnet() {
@@ -174,6 +306,7 @@ mod test {
     use super::*;
     use aptos_temppath::TempPath;
     use aptos_types::{
+        block_executor::config::FallbackPolicy,
         transaction::{ChangeSet, Transaction, WriteSetPayload},
         write_set::WriteSetMut,
     };
@@ -233,6 +366,108 @@ mod test {
         assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
     }
 
+    #[test]
+    fn test_sanitize_block_time_budget_validator() {
+        // Create a node config with a block time budget
+        let node_config = NodeConfig {
+            execution: ExecutionConfig {
+                block_time_budget_ms: Some(100),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails for validators only
+        let error = ExecutionConfig::sanitize(&node_config, NodeType::Validator, None).unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+        ExecutionConfig::sanitize(&node_config, NodeType::PublicFullnode, None).unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_mvhashmap_memory_soft_cap_validator() {
+        // Create a node config with a memory soft cap for the multi-version data-structure
+        let node_config = NodeConfig {
+            execution: ExecutionConfig {
+                mvhashmap_memory_soft_cap_bytes: Some(1 << 30),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails for validators only
+        let error = ExecutionConfig::sanitize(&node_config, NodeType::Validator, None).unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+        ExecutionConfig::sanitize(&node_config, NodeType::PublicFullnode, None).unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_fallback_policies_validator() {
+        // Create a node config that discards the blocks failing with a fatal VM error
+        let node_config = NodeConfig {
+            execution: ExecutionConfig {
+                fallback_policies: FallbackPolicies {
+                    fatal_vm_error: FallbackPolicy::Discard,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails for validators only
+        let error = ExecutionConfig::sanitize(&node_config, NodeType::Validator, None).unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+        ExecutionConfig::sanitize(&node_config, NodeType::PublicFullnode, None).unwrap();
+
+        // Retrying parallel execution eventually falls back to sequential execution
+        let node_config = NodeConfig {
+            execution: ExecutionConfig {
+                fallback_policies: FallbackPolicies {
+                    code_invariant_error: FallbackPolicy::RetryParallelOnce,
+                    module_read_write_conflict: FallbackPolicy::ParallelWithReducedConcurrency,
+                    fatal_vm_error: FallbackPolicy::Sequential,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        ExecutionConfig::sanitize(&node_config, NodeType::Validator, None).unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_hot_state_cache_validator() {
+        // Create a node config with a hot-state cache
+        let node_config = NodeConfig {
+            execution: ExecutionConfig {
+                hot_state_cache_capacity: 1_000,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails for validators only
+        let error = ExecutionConfig::sanitize(&node_config, NodeType::Validator, None).unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+        ExecutionConfig::sanitize(&node_config, NodeType::PublicFullnode, None).unwrap();
+    }
+
+    #[test]
+    fn test_sanitize_txn_execution_timeout_validator() {
+        // Create a node config with a transaction execution timeout
+        let node_config = NodeConfig {
+            execution: ExecutionConfig {
+                txn_execution_timeout_ms: Some(1_000),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails for validators only
+        let error = ExecutionConfig::sanitize(&node_config, NodeType::Validator, None).unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+        ExecutionConfig::sanitize(&node_config, NodeType::PublicFullnode, None).unwrap();
+    }
+
     #[test]
     fn test_no_genesis() {
         let (mut config, path) = generate_config();
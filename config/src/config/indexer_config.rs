@@ -87,6 +87,22 @@ pub struct IndexerConfig {
     /// Custom NFT points contract
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub nft_points_contract: Option<String>,
+
+    /// Where the processor checkpoints are stored (default: Postgres)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint_backend: Option<IndexerCheckpointBackend>,
+}
+
+/// The backend storing the processor checkpoints, i.e. the last version processed by each processor
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum IndexerCheckpointBackend {
+    /// The `processor_status` table of the Postgres database
+    #[default]
+    Postgres,
+    /// An embedded SQLite database at the given path. Requires the indexer to be built with the
+    /// `sqlite` feature.
+    Sqlite { path: String },
 }
 
 impl Debug for IndexerConfig {
@@ -112,6 +128,7 @@ impl Debug for IndexerConfig {
             .field("gap_lookback_versions", &self.gap_lookback_versions)
             .field("ans_contract_address", &self.ans_contract_address)
             .field("nft_points_contract", &self.nft_points_contract)
+            .field("checkpoint_backend", &self.checkpoint_backend)
             .finish()
     }
 }
@@ -3,13 +3,17 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::config::{
-    config_optimizer::ConfigOptimizer, config_sanitizer::ConfigSanitizer,
-    node_config_loader::NodeType, Error, NodeConfig, MAX_APPLICATION_MESSAGE_SIZE,
+    config_optimizer::ConfigOptimizer,
+    config_sanitizer::ConfigSanitizer,
+    node_config_loader::NodeType,
+    transaction_filter_type::{Filter, Matcher},
+    Error, NodeConfig, MAX_APPLICATION_MESSAGE_SIZE,
 };
 use aptos_global_constants::DEFAULT_BUCKETS;
 use aptos_types::chain_id::ChainId;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
+use std::path::PathBuf;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -53,6 +57,15 @@ pub struct MempoolConfig {
     pub broadcast_buckets: Vec<u64>,
     pub eager_expire_threshold_ms: Option<u64>,
     pub eager_expire_time_ms: u64,
+    /// Filter applied to transactions on admission into the Mempool, and when pulling
+    /// transactions for consensus proposals (it is never applied at execution time). Can be
+    /// used to deny-list senders, modules or entry functions during incident response.
+    pub transaction_filter: Filter,
+    /// If set, the transaction filter is loaded from this (YAML) file instead, and reloaded
+    /// whenever the file changes, without having to restart the node.
+    pub transaction_filter_file: Option<PathBuf>,
+    /// Interval to check the transaction filter file for changes.
+    pub transaction_filter_reload_interval_ms: u64,
 }
 
 impl Default for MempoolConfig {
@@ -77,17 +90,43 @@ impl Default for MempoolConfig {
             broadcast_buckets: DEFAULT_BUCKETS.to_vec(),
             eager_expire_threshold_ms: Some(10_000),
             eager_expire_time_ms: 3_000,
+            transaction_filter: Filter::empty(),
+            transaction_filter_file: None,
+            transaction_filter_reload_interval_ms: 10_000,
         }
     }
 }
 
 impl ConfigSanitizer for MempoolConfig {
     fn sanitize(
-        _node_config: &NodeConfig,
+        node_config: &NodeConfig,
         _node_type: NodeType,
         _chain_id: Option<ChainId>,
     ) -> Result<(), Error> {
-        Ok(()) // TODO: add reasonable verifications
+        let sanitizer_name = Self::get_sanitizer_name();
+        let mempool_config = &node_config.mempool;
+
+        // Transactions are filtered before they are part of a block, so we don't support
+        // Block ID based filters.
+        for rule in mempool_config.transaction_filter.rules() {
+            if let Matcher::BlockId(_) = rule.matcher() {
+                return Err(Error::ConfigSanitizerFailed(
+                    sanitizer_name,
+                    "Block ID based mempool transaction filters are not supported!".into(),
+                ));
+            }
+        }
+
+        if mempool_config.transaction_filter_file.is_some()
+            && mempool_config.transaction_filter_reload_interval_ms == 0
+        {
+            return Err(Error::ConfigSanitizerFailed(
+                sanitizer_name,
+                "transaction_filter_reload_interval_ms must be greater than 0!".into(),
+            ));
+        }
+
+        Ok(())
     }
 }
 
@@ -253,4 +292,23 @@ mod tests {
             default_mempool_config.shared_mempool_tick_interval_ms
         );
     }
+
+    #[test]
+    fn test_sanitize_block_id_transaction_filter() {
+        // Create a node config with a block ID based transaction filter
+        let node_config = NodeConfig {
+            mempool: MempoolConfig {
+                transaction_filter: Filter::empty()
+                    .add_deny_block_id(aptos_crypto::HashValue::random()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Sanitize the config and verify that it fails
+        let error =
+            MempoolConfig::sanitize(&node_config, NodeType::Validator, Some(ChainId::testnet()))
+                .unwrap_err();
+        assert!(matches!(error, Error::ConfigSanitizerFailed(_, _)));
+    }
 }
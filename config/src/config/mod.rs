@@ -26,6 +26,7 @@ mod netbench_config;
 mod network_config;
 mod node_config;
 mod node_config_loader;
+mod node_config_preset;
 mod node_startup_config;
 mod override_node_config;
 mod peer_monitoring_config;
@@ -57,7 +58,8 @@ pub use mempool_config::*;
 pub use netbench_config::*;
 pub use network_config::*;
 pub use node_config::*;
-pub use node_config_loader::sanitize_node_config;
+pub use node_config_loader::{sanitize_node_config, NodeType};
+pub use node_config_preset::*;
 pub use override_node_config::*;
 pub use peer_monitoring_config::*;
 pub use persistable_config::*;
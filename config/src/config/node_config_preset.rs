@@ -0,0 +1,308 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::{
+    config_sanitizer::ConfigSanitizer, node_config_loader::NodeType, BootstrappingMode, Error,
+    NodeConfig, OnDiskStorageConfig, SafetyRulesService, SecureBackend,
+    NO_OP_STORAGE_PRUNER_CONFIG,
+};
+use aptos_types::chain_id::{ChainId, NamedChain};
+
+// Useful preset constants
+const PRESET_SANITIZER_NAME: &str = "NodeConfigPresetSanitizer";
+
+/// The networks that node config presets are provided for
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NetworkPreset {
+    Mainnet,
+    Testnet,
+    Devnet,
+    /// A local test network (e.g., started with the CLI or a local swarm)
+    Localnet,
+}
+
+impl NetworkPreset {
+    /// Returns the network preset for the given chain ID. Chain IDs that
+    /// do not belong to a long living network are treated as local networks.
+    pub fn from_chain_id(chain_id: ChainId) -> Self {
+        match NamedChain::from_chain_id(&chain_id) {
+            Ok(NamedChain::MAINNET) => NetworkPreset::Mainnet,
+            Ok(NamedChain::TESTNET) => NetworkPreset::Testnet,
+            Ok(NamedChain::DEVNET) => NetworkPreset::Devnet,
+            _ => NetworkPreset::Localnet,
+        }
+    }
+
+    /// Returns the chain ID of the network, if it is fixed
+    /// (the devnet chain ID changes with every reset).
+    pub fn chain_id(self) -> Option<ChainId> {
+        match self {
+            NetworkPreset::Mainnet => Some(ChainId::mainnet()),
+            NetworkPreset::Testnet => Some(ChainId::testnet()),
+            NetworkPreset::Devnet => None,
+            NetworkPreset::Localnet => Some(ChainId::test()),
+        }
+    }
+
+    fn is_long_living(self) -> bool {
+        matches!(self, NetworkPreset::Mainnet | NetworkPreset::Testnet)
+    }
+}
+
+/// A typed node config preset for a node type on a network. The preset
+/// starts from the default config of the node type, applies the settings
+/// of the network (and of archive nodes, if requested), and then applies
+/// the programmatic overrides (in the order they were added). The built
+/// config is validated against the sanitizers of the network, as well as
+/// against combinations of settings that are dangerous for the preset.
+pub struct NodeConfigPreset {
+    network: NetworkPreset,
+    node_type: NodeType,
+    archive: bool,
+    overrides: Vec<Box<dyn Fn(&mut NodeConfig)>>,
+}
+
+impl NodeConfigPreset {
+    pub fn new(network: NetworkPreset, node_type: NodeType) -> Self {
+        Self {
+            network,
+            node_type,
+            archive: false,
+            overrides: vec![],
+        }
+    }
+
+    /// Makes the node an archive node, i.e., a node that
+    /// keeps (and syncs) the entire history of the chain.
+    pub fn archive(mut self) -> Self {
+        self.archive = true;
+        self
+    }
+
+    /// Adds an override that is applied to the config after the preset settings
+    pub fn with_override(mut self, config_override: impl Fn(&mut NodeConfig) + 'static) -> Self {
+        self.overrides.push(Box::new(config_override));
+        self
+    }
+
+    /// Builds and validates the node config of the preset
+    pub fn build(&self) -> Result<NodeConfig, Error> {
+        let mut node_config = match self.node_type {
+            NodeType::Validator => NodeConfig::get_default_validator_config(),
+            NodeType::ValidatorFullnode => NodeConfig::get_default_vfn_config(),
+            NodeType::PublicFullnode => NodeConfig::get_default_pfn_config(),
+        };
+        self.apply_network_settings(&mut node_config);
+        if self.archive {
+            node_config.storage.storage_pruner_config = NO_OP_STORAGE_PRUNER_CONFIG;
+            node_config.state_sync.state_sync_driver.bootstrapping_mode =
+                BootstrappingMode::ExecuteOrApplyFromGenesis;
+        }
+        for config_override in &self.overrides {
+            config_override(&mut node_config);
+        }
+
+        self.validate(&node_config)?;
+        Ok(node_config)
+    }
+
+    /// Applies the settings of the network to the given node config
+    fn apply_network_settings(&self, node_config: &mut NodeConfig) {
+        let is_mainnet = self.network == NetworkPreset::Mainnet;
+
+        // The admin service is only enabled for non-mainnet networks
+        // (as mainnet requires authentication to be configured).
+        node_config.admin_service.enabled = Some(!is_mainnet);
+
+        // Expose the node configuration for non-mainnet networks (to aid debugging)
+        node_config.inspection_service.expose_configuration = !is_mainnet;
+
+        // Mainnet validators must keep their safety data on disk and run
+        // safety rules locally (for optimal performance).
+        if is_mainnet && self.node_type.is_validator() {
+            let safety_rules_config = &mut node_config.consensus.safety_rules;
+            safety_rules_config.service = SafetyRulesService::Local;
+            if safety_rules_config.backend.is_in_memory() {
+                safety_rules_config.backend =
+                    SecureBackend::OnDiskStorage(OnDiskStorageConfig::default());
+            }
+        }
+
+        // Long living networks have pruned their history, so the nodes fast sync
+        if self.network.is_long_living() {
+            node_config.state_sync.state_sync_driver.bootstrapping_mode =
+                BootstrappingMode::DownloadLatestStates;
+        }
+    }
+
+    /// Validates the given node config for the preset
+    fn validate(&self, node_config: &NodeConfig) -> Result<(), Error> {
+        if self.archive {
+            // Verify that the archive node does not prune its history
+            let pruner_config = &node_config.storage.storage_pruner_config;
+            if pruner_config.ledger_pruner_config.enable
+                || pruner_config.state_merkle_pruner_config.enable
+                || pruner_config.epoch_snapshot_pruner_config.enable
+            {
+                return Err(Error::ConfigSanitizerFailed(
+                    PRESET_SANITIZER_NAME.to_string(),
+                    "Pruning cannot be enabled on archive nodes!".into(),
+                ));
+            }
+
+            // Verify that the archive node syncs the entire history
+            let bootstrapping_mode = node_config.state_sync.state_sync_driver.bootstrapping_mode;
+            if bootstrapping_mode.is_fast_sync() {
+                return Err(Error::ConfigSanitizerFailed(
+                    PRESET_SANITIZER_NAME.to_string(),
+                    "Archive nodes cannot fast sync, as they must sync the entire history!".into(),
+                ));
+            }
+        }
+
+        // Verify that the node type of the config matches the preset
+        let node_type = NodeType::extract_from_config(node_config);
+        if node_type != self.node_type {
+            return Err(Error::ConfigSanitizerFailed(
+                PRESET_SANITIZER_NAME.to_string(),
+                format!(
+                    "The node type of the config ({:?}) does not match the preset ({:?})!",
+                    node_type, self.node_type
+                ),
+            ));
+        }
+
+        // Sanitize the config for the network
+        NodeConfig::sanitize(node_config, self.node_type, self.network.chain_id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AuthenticationConfig;
+
+    #[test]
+    fn test_build_all_presets() {
+        for network in [
+            NetworkPreset::Mainnet,
+            NetworkPreset::Testnet,
+            NetworkPreset::Devnet,
+            NetworkPreset::Localnet,
+        ] {
+            for node_type in [
+                NodeType::Validator,
+                NodeType::ValidatorFullnode,
+                NodeType::PublicFullnode,
+            ] {
+                let node_config = NodeConfigPreset::new(network, node_type).build().unwrap();
+                assert_eq!(NodeType::extract_from_config(&node_config), node_type);
+                assert_eq!(
+                    node_config.admin_service.enabled,
+                    Some(network != NetworkPreset::Mainnet)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_network_from_chain_id() {
+        for network in [
+            NetworkPreset::Mainnet,
+            NetworkPreset::Testnet,
+            NetworkPreset::Localnet,
+        ] {
+            assert_eq!(
+                NetworkPreset::from_chain_id(network.chain_id().unwrap()),
+                network
+            );
+        }
+        assert_eq!(
+            NetworkPreset::from_chain_id(ChainId::new(100)),
+            NetworkPreset::Localnet
+        );
+    }
+
+    #[test]
+    fn test_archive_preset() {
+        // Archive nodes neither prune nor fast sync (even on mainnet)
+        let node_config = NodeConfigPreset::new(NetworkPreset::Mainnet, NodeType::PublicFullnode)
+            .archive()
+            .build()
+            .unwrap();
+        assert_eq!(
+            node_config.storage.storage_pruner_config,
+            NO_OP_STORAGE_PRUNER_CONFIG
+        );
+        assert!(!node_config
+            .state_sync
+            .state_sync_driver
+            .bootstrapping_mode
+            .is_fast_sync());
+
+        // Enabling pruning on an archive node is flagged
+        let error = NodeConfigPreset::new(NetworkPreset::Mainnet, NodeType::PublicFullnode)
+            .archive()
+            .with_override(|node_config| {
+                node_config
+                    .storage
+                    .storage_pruner_config
+                    .ledger_pruner_config
+                    .enable = true;
+            })
+            .build()
+            .unwrap_err();
+        assert!(
+            matches!(error, Error::ConfigSanitizerFailed(name, _) if name == PRESET_SANITIZER_NAME)
+        );
+
+        // And so is fast syncing an archive node
+        let error = NodeConfigPreset::new(NetworkPreset::Testnet, NodeType::ValidatorFullnode)
+            .archive()
+            .with_override(|node_config| {
+                node_config.state_sync.state_sync_driver.bootstrapping_mode =
+                    BootstrappingMode::DownloadLatestStates;
+            })
+            .build()
+            .unwrap_err();
+        assert!(
+            matches!(error, Error::ConfigSanitizerFailed(name, _) if name == PRESET_SANITIZER_NAME)
+        );
+    }
+
+    #[test]
+    fn test_preset_overrides() {
+        // The overrides are applied after the network settings
+        let node_config = NodeConfigPreset::new(NetworkPreset::Mainnet, NodeType::Validator)
+            .with_override(|node_config| node_config.api.enabled = false)
+            .build()
+            .unwrap();
+        assert!(!node_config.api.enabled);
+
+        // The overrides are sanitized for the network
+        let preset = NodeConfigPreset::new(NetworkPreset::Mainnet, NodeType::Validator)
+            .with_override(|node_config| node_config.admin_service.enabled = Some(true));
+        assert!(preset.build().is_err());
+        let node_config = preset
+            .with_override(|node_config| {
+                node_config.admin_service.authentication_configs =
+                    vec![AuthenticationConfig::PasscodeSha256("passcode".into())];
+            })
+            .build()
+            .unwrap();
+        assert_eq!(node_config.admin_service.enabled, Some(true));
+
+        // The node type cannot be changed by the overrides
+        let error = NodeConfigPreset::new(NetworkPreset::Devnet, NodeType::ValidatorFullnode)
+            .with_override(|node_config| {
+                node_config
+                    .full_node_networks
+                    .retain(|network| !network.network_id.is_vfn_network());
+            })
+            .build()
+            .unwrap_err();
+        assert!(
+            matches!(error, Error::ConfigSanitizerFailed(name, _) if name == PRESET_SANITIZER_NAME)
+        );
+    }
+}
@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 pub struct NodeStartupConfig {
     pub skip_config_optimizer: bool, // Whether or not to skip the config optimizer at startup
     pub skip_config_sanitizer: bool, // Whether or not to skip the config sanitizer at startup
+    pub standby: bool,               // Whether or not to start the node as a hot standby
 }
 
 #[allow(clippy::derivable_impls)] // Derive default manually (this is safer than guessing defaults)
@@ -16,6 +17,7 @@ impl Default for NodeStartupConfig {
         Self {
             skip_config_optimizer: false,
             skip_config_sanitizer: false,
+            standby: false,
         }
     }
 }
@@ -29,8 +31,9 @@ mod test {
         // Create the default config
         let config = NodeStartupConfig::default();
 
-        // Verify both fields are set to false
+        // Verify all fields are set to false
         assert!(!config.skip_config_optimizer);
         assert!(!config.skip_config_sanitizer);
+        assert!(!config.standby);
     }
 }
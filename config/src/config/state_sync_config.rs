@@ -147,8 +147,12 @@ impl Default for StateSyncDriverConfig {
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct StorageServiceConfig {
+    /// The number of bytes each peer is credited per round of fair queuing
+    pub fair_queuing_quantum_bytes: u64,
     /// Maximum number of concurrent storage server tasks
     pub max_concurrent_requests: u64,
+    /// Maximum number of concurrent storage server tasks (per peer)
+    pub max_concurrent_requests_per_peer: u64,
     /// Maximum number of epoch ending ledger infos per chunk
     pub max_epoch_chunk_size: u64,
     /// Maximum number of invalid requests per peer
@@ -163,6 +167,10 @@ pub struct StorageServiceConfig {
     pub max_num_active_subscriptions: u64,
     /// Maximum period (ms) of pending optimistic fetch requests
     pub max_optimistic_fetch_period_ms: u64,
+    /// Maximum number of response bytes to send per second (per peer)
+    pub max_peer_bandwidth_bytes_per_sec: u64,
+    /// Maximum number of requests waiting to be processed (per peer)
+    pub max_pending_requests_per_peer: u64,
     /// Maximum number of state keys and values per chunk
     pub max_state_chunk_size: u64,
     /// Maximum period (ms) of pending subscription requests
@@ -182,7 +190,9 @@ pub struct StorageServiceConfig {
 impl Default for StorageServiceConfig {
     fn default() -> Self {
         Self {
+            fair_queuing_quantum_bytes: 1024 * 1024, // 1 MiB
             max_concurrent_requests: 4000,
+            max_concurrent_requests_per_peer: 100,
             max_epoch_chunk_size: MAX_EPOCH_CHUNK_SIZE,
             max_invalid_requests_per_peer: 500,
             max_lru_cache_size: 500, // At ~0.6MiB per chunk, this should take no more than 0.5GiB
@@ -190,6 +200,8 @@ impl Default for StorageServiceConfig {
             max_network_chunk_bytes: MAX_MESSAGE_SIZE as u64,
             max_num_active_subscriptions: 30,
             max_optimistic_fetch_period_ms: 5000, // 5 seconds
+            max_peer_bandwidth_bytes_per_sec: 100 * 1024 * 1024, // 100 MiB/s
+            max_pending_requests_per_peer: 500,
             max_state_chunk_size: MAX_STATE_CHUNK_SIZE,
             max_subscription_period_ms: 30_000, // 30 seconds
             max_transaction_chunk_size: MAX_TRANSACTION_CHUNK_SIZE,
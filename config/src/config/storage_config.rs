@@ -152,6 +152,8 @@ pub struct StorageConfig {
     pub dir: PathBuf,
     /// Storage pruning configuration
     pub storage_pruner_config: PrunerConfig,
+    /// Background storage format migration configuration
+    pub storage_migration_config: StorageMigrationConfig,
     /// Subdirectory for storage in tests only
     #[serde(skip)]
     data_dir: PathBuf,
@@ -250,6 +252,28 @@ impl From<EpochSnapshotPrunerConfig> for StateMerklePrunerConfig {
     }
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageMigrationConfig {
+    /// Boolean to enable/disable background storage migrations. When disabled, a DB whose
+    /// storage schema version is behind keeps being served in the old format.
+    pub enable: bool,
+    /// Number of items a migration processes a time. Progress is persisted after each batch.
+    pub batch_size: usize,
+    /// Time to sleep between batches, to throttle the migration against foreground traffic.
+    pub sleep_between_batches_ms: u64,
+}
+
+impl Default for StorageMigrationConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            batch_size: 10_000,
+            sleep_between_batches_ms: 10,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, Default)]
 #[serde(default, deny_unknown_fields)]
 pub struct PrunerConfig {
@@ -318,6 +342,7 @@ impl Default for StorageConfig {
             // conservatively safe minimal prune window. It'll take a few Gigabytes of disk space
             // depending on the size of an average account blob.
             storage_pruner_config: PrunerConfig::default(),
+            storage_migration_config: StorageMigrationConfig::default(),
             data_dir: PathBuf::from("/opt/aptos/data"),
             rocksdb_configs: RocksdbConfigs::default(),
             enable_indexer: false,
@@ -17,6 +17,7 @@ pub enum Matcher {
     TransactionId(HashValue),
     Sender(AccountAddress),
     ModuleAddress(AccountAddress),
+    Module(AccountAddress, String),
     EntryFunction(AccountAddress, String, String),
 }
 
@@ -35,6 +36,13 @@ impl Matcher {
                 },
                 _ => false,
             },
+            Matcher::Module(address, module_name) => match txn.payload() {
+                TransactionPayload::EntryFunction(entry_function) => {
+                    *entry_function.module().address() == *address
+                        && entry_function.module().name().to_string() == *module_name
+                },
+                _ => false,
+            },
             Matcher::EntryFunction(address, module_name, function) => match txn.payload() {
                 TransactionPayload::EntryFunction(entry_function) => {
                     *entry_function.module().address() == *address
@@ -169,6 +177,18 @@ impl Filter {
         self
     }
 
+    pub fn add_allow_module(mut self, address: AccountAddress, module_name: String) -> Self {
+        self.rules
+            .push(Rule::Allow(Matcher::Module(address, module_name)));
+        self
+    }
+
+    pub fn add_deny_module(mut self, address: AccountAddress, module_name: String) -> Self {
+        self.rules
+            .push(Rule::Deny(Matcher::Module(address, module_name)));
+        self
+    }
+
     pub fn add_deny_entry_function(
         mut self,
         address: AccountAddress,
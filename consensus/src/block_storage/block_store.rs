@@ -587,6 +587,10 @@ impl BlockReader for BlockStore {
     fn pipeline_pending_latency(&self, proposal_timestamp: Duration) -> Duration {
         self.pipeline_pending_latency(proposal_timestamp)
     }
+
+    fn execution_pipeline_depth(&self) -> usize {
+        self.execution_client.execution_pipeline_depth()
+    }
 }
 
 #[cfg(any(test, feature = "fuzzing"))]
@@ -63,4 +63,7 @@ pub trait BlockReader: Send + Sync {
 
     // Return time difference between last committed block and new proposal
     fn pipeline_pending_latency(&self, proposal_timestamp: Duration) -> Duration;
+
+    // Return the number of blocks pending execution or ledger update
+    fn execution_pipeline_depth(&self) -> usize;
 }
@@ -264,6 +264,24 @@ pub static PIPELINE_BACKPRESSURE_ON_PROPOSAL_TRIGGERED: Lazy<Histogram> = Lazy::
     )
 });
 
+/// Counts when execution backpressure is triggered
+pub static EXECUTION_BACKPRESSURE_ON_PROPOSAL_TRIGGERED: Lazy<Histogram> = Lazy::new(|| {
+    register_avg_counter(
+        "aptos_execution_backpressure_on_proposal_triggered",
+        "Counts when execution backpressure is triggered",
+    )
+});
+
+/// Number of blocks in the execution pipeline, by stage (pending execution or ledger update)
+pub static EXECUTION_PIPELINE_DEPTH: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "aptos_consensus_execution_pipeline_depth",
+        "Number of blocks in the execution pipeline, by stage",
+        &["stage"]
+    )
+    .unwrap()
+});
+
 /// number of rounds pending when creating proposal
 pub static CONSENSUS_PROPOSAL_PENDING_ROUNDS: Lazy<Histogram> = Lazy::new(|| {
     register_avg_counter(
@@ -967,3 +985,13 @@ pub static RAND_QUEUE_SIZE: Lazy<IntGauge> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+/// Count of signature verification cache lookups, by result (hit / miss)
+pub static TXN_SIG_VERIFY_CACHE: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_consensus_txn_sig_verify_cache",
+        "Count of transaction signature verification cache lookups, by result",
+        &["result"]
+    )
+    .unwrap()
+});
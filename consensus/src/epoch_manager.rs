@@ -17,7 +17,8 @@ use crate::{
             ProposerAndVoterHeuristic, ReputationHeuristic,
         },
         proposal_generator::{
-            ChainHealthBackoffConfig, PipelineBackpressureConfig, ProposalGenerator,
+            ChainHealthBackoffConfig, ExecutionBackpressureConfig, PipelineBackpressureConfig,
+            ProposalGenerator,
         },
         proposer_election::ProposerElection,
         rotating_proposer_election::{choose_leader, RotatingProposer},
@@ -765,6 +766,8 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             ChainHealthBackoffConfig::new(self.config.chain_health_backoff.clone());
         let pipeline_backpressure_config =
             PipelineBackpressureConfig::new(self.config.pipeline_backpressure.clone());
+        let execution_backpressure_config =
+            ExecutionBackpressureConfig::new(self.config.execution_backpressure.clone());
 
         let safety_rules_container = Arc::new(Mutex::new(safety_rules));
 
@@ -807,6 +810,7 @@ impl<P: OnChainConfigProvider> EpochManager<P> {
             self.config.max_sending_block_bytes,
             onchain_consensus_config.max_failed_authors_to_store(),
             pipeline_backpressure_config,
+            execution_backpressure_config,
             chain_health_backoff_config,
             self.quorum_store_enabled,
             onchain_consensus_config.effective_validator_txn_config(),
@@ -5,7 +5,8 @@
 
 use crate::{
     block_preparer::BlockPreparer,
-    monitor,
+    counters, monitor,
+    signature_verification_cache::{SignatureVerificationCache, DEFAULT_SIG_VERIFY_CACHE_CAPACITY},
     state_computer::{PipelineExecutionResult, StateComputeResultFut},
 };
 use aptos_consensus_types::block::Block;
@@ -14,7 +15,6 @@ use aptos_executor_types::{
     state_checkpoint_output::StateCheckpointOutput, BlockExecutorTrait, ExecutorError,
     ExecutorResult,
 };
-use aptos_experimental_runtimes::thread_manager::optimal_min_len;
 use aptos_logger::{debug, error};
 use aptos_types::{
     block_executor::{config::BlockExecutorConfigFromOnchain, partitioner::ExecutableBlock},
@@ -25,8 +25,10 @@ use aptos_types::{
 };
 use fail::fail_point;
 use once_cell::sync::Lazy;
-use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 use tokio::sync::{mpsc, oneshot};
 
 pub static SIG_VERIFY_POOL: Lazy<Arc<rayon::ThreadPool>> = Lazy::new(|| {
@@ -39,8 +41,53 @@ pub static SIG_VERIFY_POOL: Lazy<Arc<rayon::ThreadPool>> = Lazy::new(|| {
     )
 });
 
+/// Tracks the number of blocks queued in the execution pipeline, which is the backpressure
+/// signal consensus uses to size its proposals.
+#[derive(Default)]
+pub struct ExecutionPipelineDepth {
+    // Blocks queued, being prepared or being executed (i.e. not materialized yet).
+    pending_execution: AtomicUsize,
+    // Blocks executed and waiting for or going through ledger update.
+    pending_ledger_update: AtomicUsize,
+}
+
+impl ExecutionPipelineDepth {
+    pub fn get(&self) -> usize {
+        self.pending_execution.load(Ordering::Relaxed)
+            + self.pending_ledger_update.load(Ordering::Relaxed)
+    }
+
+    fn execution_queued(&self) {
+        self.pending_execution.fetch_add(1, Ordering::Relaxed);
+        self.update_counters();
+    }
+
+    fn execution_done(&self, to_ledger_update: bool) {
+        if to_ledger_update {
+            self.pending_ledger_update.fetch_add(1, Ordering::Relaxed);
+        }
+        self.pending_execution.fetch_sub(1, Ordering::Relaxed);
+        self.update_counters();
+    }
+
+    fn ledger_update_done(&self) {
+        self.pending_ledger_update.fetch_sub(1, Ordering::Relaxed);
+        self.update_counters();
+    }
+
+    fn update_counters(&self) {
+        counters::EXECUTION_PIPELINE_DEPTH
+            .with_label_values(&["execution"])
+            .set(self.pending_execution.load(Ordering::Relaxed) as i64);
+        counters::EXECUTION_PIPELINE_DEPTH
+            .with_label_values(&["ledger_update"])
+            .set(self.pending_ledger_update.load(Ordering::Relaxed) as i64);
+    }
+}
+
 pub struct ExecutionPipeline {
     prepare_block_tx: mpsc::UnboundedSender<PrepareBlockCommand>,
+    depth: Arc<ExecutionPipelineDepth>,
 }
 
 impl ExecutionPipeline {
@@ -48,17 +95,37 @@ impl ExecutionPipeline {
         let (prepare_block_tx, prepare_block_rx) = mpsc::unbounded_channel();
         let (execute_block_tx, execute_block_rx) = mpsc::unbounded_channel();
         let (ledger_apply_tx, ledger_apply_rx) = mpsc::unbounded_channel();
+        let sig_verify_cache = Arc::new(SignatureVerificationCache::new(
+            DEFAULT_SIG_VERIFY_CACHE_CAPACITY,
+        ));
+        let depth = Arc::new(ExecutionPipelineDepth::default());
         runtime.spawn(Self::prepare_block_stage(
             prepare_block_rx,
             execute_block_tx,
+            sig_verify_cache,
+            depth.clone(),
         ));
         runtime.spawn(Self::execute_stage(
             execute_block_rx,
             ledger_apply_tx,
             executor.clone(),
+            depth.clone(),
+        ));
+        runtime.spawn(Self::ledger_apply_stage(
+            ledger_apply_rx,
+            executor,
+            depth.clone(),
         ));
-        runtime.spawn(Self::ledger_apply_stage(ledger_apply_rx, executor));
-        Self { prepare_block_tx }
+        Self {
+            prepare_block_tx,
+            depth,
+        }
+    }
+
+    /// Returns the number of blocks queued in the pipeline that haven't finished execution and
+    /// ledger update yet.
+    pub fn depth(&self) -> usize {
+        self.depth.get()
     }
 
     pub async fn queue(
@@ -71,6 +138,7 @@ impl ExecutionPipeline {
     ) -> StateComputeResultFut {
         let (result_tx, result_rx) = oneshot::channel();
         let block_id = block.id();
+        self.depth.execution_queued();
         self.prepare_block_tx
             .send(PrepareBlockCommand {
                 block,
@@ -97,6 +165,8 @@ impl ExecutionPipeline {
     async fn prepare_block(
         execute_block_tx: mpsc::UnboundedSender<ExecuteBlockCommand>,
         command: PrepareBlockCommand,
+        sig_verify_cache: Arc<SignatureVerificationCache>,
+        depth: Arc<ExecutionPipelineDepth>,
     ) {
         let PrepareBlockCommand {
             block,
@@ -110,6 +180,7 @@ impl ExecutionPipeline {
         debug!("prepare_block received block {}.", block.id());
         let input_txns = block_preparer.prepare_block(&block).await;
         if let Err(e) = input_txns {
+            depth.execution_done(false);
             result_tx.send(Err(e)).unwrap_or_else(|err| {
                 error!(
                     block_id = block.id(),
@@ -122,18 +193,14 @@ impl ExecutionPipeline {
         }
         let validator_txns = block.validator_txns().cloned().unwrap_or_default();
         let input_txns = input_txns.unwrap();
+        let epoch = block.epoch();
         tokio::task::spawn_blocking(move || {
             let txns_to_execute =
                 Block::combine_to_input_transactions(validator_txns, input_txns.clone(), metadata);
+            // Signature verification results are cached, so that a block re-proposed within
+            // the same epoch does not pay the full verification cost again.
             let sig_verified_txns: Vec<SignatureVerifiedTransaction> =
-                SIG_VERIFY_POOL.install(|| {
-                    let num_txns = txns_to_execute.len();
-                    txns_to_execute
-                        .into_par_iter()
-                        .with_min_len(optimal_min_len(num_txns, 32))
-                        .map(|t| t.into())
-                        .collect::<Vec<_>>()
-                });
+                sig_verify_cache.verify_block(epoch, txns_to_execute, &SIG_VERIFY_POOL);
             execute_block_tx
                 .send(ExecuteBlockCommand {
                     input_txns,
@@ -151,11 +218,19 @@ impl ExecutionPipeline {
     async fn prepare_block_stage(
         mut prepare_block_rx: mpsc::UnboundedReceiver<PrepareBlockCommand>,
         execute_block_tx: mpsc::UnboundedSender<ExecuteBlockCommand>,
+        sig_verify_cache: Arc<SignatureVerificationCache>,
+        depth: Arc<ExecutionPipelineDepth>,
     ) {
         while let Some(command) = prepare_block_rx.recv().await {
             monitor!(
                 "prepare_block",
-                Self::prepare_block(execute_block_tx.clone(), command).await
+                Self::prepare_block(
+                    execute_block_tx.clone(),
+                    command,
+                    sig_verify_cache.clone(),
+                    depth.clone()
+                )
+                .await
             );
         }
         debug!("prepare_block_stage quitting.");
@@ -165,6 +240,7 @@ impl ExecutionPipeline {
         mut block_rx: mpsc::UnboundedReceiver<ExecuteBlockCommand>,
         ledger_apply_tx: mpsc::UnboundedSender<LedgerApplyCommand>,
         executor: Arc<dyn BlockExecutorTrait>,
+        depth: Arc<ExecutionPipelineDepth>,
     ) {
         while let Some(ExecuteBlockCommand {
             input_txns,
@@ -194,6 +270,7 @@ impl ExecutionPipeline {
                 .await
             )
             .expect("Failed to spawn_blocking.");
+            depth.execution_done(true);
 
             ledger_apply_tx
                 .send(LedgerApplyCommand {
@@ -211,6 +288,7 @@ impl ExecutionPipeline {
     async fn ledger_apply_stage(
         mut block_rx: mpsc::UnboundedReceiver<LedgerApplyCommand>,
         executor: Arc<dyn BlockExecutorTrait>,
+        depth: Arc<ExecutionPipelineDepth>,
     ) {
         while let Some(LedgerApplyCommand {
             input_txns,
@@ -233,6 +311,7 @@ impl ExecutionPipeline {
                 .expect("Failed to spawn_blocking().")
             }
             .await;
+            depth.ledger_update_done();
             let pipe_line_res = res.map(|output| PipelineExecutionResult::new(input_txns, output));
             result_tx.send(pipe_line_res).unwrap_or_else(|err| {
                 error!(
@@ -57,6 +57,7 @@ mod execution_pipeline;
 pub mod network_interface;
 mod payload_manager;
 mod qc_aggregator;
+mod signature_verification_cache;
 mod transaction_deduper;
 mod transaction_filter;
 mod transaction_shuffler;
@@ -8,15 +8,17 @@ use super::{
 use crate::{
     block_storage::BlockReader,
     counters::{
-        CHAIN_HEALTH_BACKOFF_TRIGGERED, PIPELINE_BACKPRESSURE_ON_PROPOSAL_TRIGGERED,
-        PROPOSER_DELAY_PROPOSAL, PROPOSER_PENDING_BLOCKS_COUNT,
-        PROPOSER_PENDING_BLOCKS_FILL_FRACTION,
+        CHAIN_HEALTH_BACKOFF_TRIGGERED, EXECUTION_BACKPRESSURE_ON_PROPOSAL_TRIGGERED,
+        PIPELINE_BACKPRESSURE_ON_PROPOSAL_TRIGGERED, PROPOSER_DELAY_PROPOSAL,
+        PROPOSER_PENDING_BLOCKS_COUNT, PROPOSER_PENDING_BLOCKS_FILL_FRACTION,
     },
     payload_client::PayloadClient,
     util::time_service::TimeService,
 };
 use anyhow::{bail, ensure, format_err, Context};
-use aptos_config::config::{ChainHealthBackoffValues, PipelineBackpressureValues};
+use aptos_config::config::{
+    ChainHealthBackoffValues, ExecutionBackpressureValues, PipelineBackpressureValues,
+};
 use aptos_consensus_types::{
     block::Block,
     block_data::BlockData,
@@ -140,6 +142,55 @@ impl PipelineBackpressureConfig {
     }
 }
 
+/// Backpressure based on the number of blocks pending in the execution pipeline, with hysteresis:
+/// it's engaged once the depth reaches the enter depth, and released once it drops back to the
+/// exit depth.
+#[derive(Clone)]
+pub struct ExecutionBackpressureConfig {
+    values: Option<ExecutionBackpressureValues>,
+    engaged: bool,
+}
+
+impl ExecutionBackpressureConfig {
+    pub fn new(values: Option<ExecutionBackpressureValues>) -> Self {
+        if let Some(values) = &values {
+            assert!(values.exit_pipeline_depth < values.enter_pipeline_depth);
+        }
+        Self {
+            values,
+            engaged: false,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.values.is_some()
+    }
+
+    pub fn get_backoff(
+        &mut self,
+        execution_pipeline_depth: usize,
+    ) -> Option<&ExecutionBackpressureValues> {
+        let values = self.values.as_ref()?;
+
+        if self.engaged {
+            self.engaged = execution_pipeline_depth > values.exit_pipeline_depth;
+        } else {
+            self.engaged = execution_pipeline_depth >= values.enter_pipeline_depth;
+        }
+        if !self.engaged {
+            return None;
+        }
+        sample!(
+            SampleRate::Duration(Duration::from_secs(10)),
+            warn!(
+                "Using execution backpressure config for {} pending blocks: {:?}",
+                execution_pipeline_depth, values
+            )
+        );
+        Some(values)
+    }
+}
+
 /// ProposalGenerator is responsible for generating the proposed block on demand: it's typically
 /// used by a validator that believes it's a valid candidate for serving as a proposer at a given
 /// round.
@@ -170,6 +221,7 @@ pub struct ProposalGenerator {
     max_failed_authors_to_store: usize,
 
     pipeline_backpressure_config: PipelineBackpressureConfig,
+    execution_backpressure_config: ExecutionBackpressureConfig,
     chain_health_backoff_config: ChainHealthBackoffConfig,
 
     // Last round that a proposal was generated
@@ -189,6 +241,7 @@ impl ProposalGenerator {
         max_block_bytes: u64,
         max_failed_authors_to_store: usize,
         pipeline_backpressure_config: PipelineBackpressureConfig,
+        execution_backpressure_config: ExecutionBackpressureConfig,
         chain_health_backoff_config: ChainHealthBackoffConfig,
         quorum_store_enabled: bool,
         vtxn_config: ValidatorTxnConfig,
@@ -203,6 +256,7 @@ impl ProposalGenerator {
             max_block_bytes,
             max_failed_authors_to_store,
             pipeline_backpressure_config,
+            execution_backpressure_config,
             chain_health_backoff_config,
             last_round_generated: 0,
             quorum_store_enabled,
@@ -409,9 +463,26 @@ impl ProposalGenerator {
             CHAIN_HEALTH_BACKOFF_TRIGGERED.observe(0.0);
         }
 
-        let pipeline_backpressure = self
-            .pipeline_backpressure_config
-            .get_backoff(self.block_store.pipeline_pending_latency(timestamp));
+        // When enabled, execution backpressure replaces the latency based pipeline backpressure.
+        let execution_backpressure = self
+            .execution_backpressure_config
+            .get_backoff(self.block_store.execution_pipeline_depth())
+            .cloned();
+        if let Some(value) = &execution_backpressure {
+            values_max_block_txns.push(value.max_sending_block_txns_override);
+            values_max_block_bytes.push(value.max_sending_block_bytes_override);
+            values_proposal_delay.push(Duration::from_millis(value.backpressure_proposal_delay_ms));
+            EXECUTION_BACKPRESSURE_ON_PROPOSAL_TRIGGERED.observe(1.0);
+        } else {
+            EXECUTION_BACKPRESSURE_ON_PROPOSAL_TRIGGERED.observe(0.0);
+        }
+
+        let pipeline_backpressure = if self.execution_backpressure_config.is_enabled() {
+            None
+        } else {
+            self.pipeline_backpressure_config
+                .get_backoff(self.block_store.pipeline_pending_latency(timestamp))
+        };
         if let Some(value) = pipeline_backpressure {
             values_max_block_txns.push(value.max_sending_block_txns_override);
             values_max_block_bytes.push(value.max_sending_block_bytes_override);
@@ -426,12 +497,16 @@ impl ProposalGenerator {
         let max_block_bytes = values_max_block_bytes.into_iter().min().unwrap();
         let proposal_delay = values_proposal_delay.into_iter().max().unwrap();
 
-        if pipeline_backpressure.is_some() || chain_health_backoff.is_some() {
+        if pipeline_backpressure.is_some()
+            || execution_backpressure.is_some()
+            || chain_health_backoff.is_some()
+        {
             warn!(
-                "Generating proposal: reducing limits to {} txns and {} bytes, due to pipeline_backpressure: {}, chain health backoff: {}. Delaying sending proposal by {}ms. Round: {}",
+                "Generating proposal: reducing limits to {} txns and {} bytes, due to pipeline_backpressure: {}, execution_backpressure: {}, chain health backoff: {}. Delaying sending proposal by {}ms. Round: {}",
                 max_block_txns,
                 max_block_bytes,
                 pipeline_backpressure.is_some(),
+                execution_backpressure.is_some(),
                 chain_health_backoff.is_some(),
                 proposal_delay.as_millis(),
                 round,
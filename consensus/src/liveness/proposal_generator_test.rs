@@ -6,7 +6,8 @@ use crate::{
     block_storage::BlockReader,
     liveness::{
         proposal_generator::{
-            ChainHealthBackoffConfig, PipelineBackpressureConfig, ProposalGenerator,
+            ChainHealthBackoffConfig, ExecutionBackpressureConfig, PipelineBackpressureConfig,
+            ProposalGenerator,
         },
         rotating_proposer_election::RotatingProposer,
         unequivocal_proposer_election::UnequivocalProposerElection,
@@ -14,6 +15,7 @@ use crate::{
     test_utils::{build_empty_tree, MockPayloadManager, TreeInserter},
     util::mock_time_service::SimulatedTimeService,
 };
+use aptos_config::config::ExecutionBackpressureValues;
 use aptos_consensus_types::{
     block::{block_test_utils::certificate_for_genesis, Block},
     common::Author,
@@ -40,6 +42,7 @@ async fn test_proposal_generation_empty_tree() {
         10,
         10,
         PipelineBackpressureConfig::new_no_backoff(),
+        ExecutionBackpressureConfig::new(None),
         ChainHealthBackoffConfig::new_no_backoff(),
         false,
         ValidatorTxnConfig::default_disabled(),
@@ -81,6 +84,7 @@ async fn test_proposal_generation_parent() {
         1000,
         10,
         PipelineBackpressureConfig::new_no_backoff(),
+        ExecutionBackpressureConfig::new(None),
         ChainHealthBackoffConfig::new_no_backoff(),
         false,
         ValidatorTxnConfig::default_disabled(),
@@ -154,6 +158,7 @@ async fn test_old_proposal_generation() {
         1000,
         10,
         PipelineBackpressureConfig::new_no_backoff(),
+        ExecutionBackpressureConfig::new(None),
         ChainHealthBackoffConfig::new_no_backoff(),
         false,
         ValidatorTxnConfig::default_disabled(),
@@ -192,6 +197,7 @@ async fn test_correct_failed_authors() {
         1000,
         10,
         PipelineBackpressureConfig::new_no_backoff(),
+        ExecutionBackpressureConfig::new(None),
         ChainHealthBackoffConfig::new_no_backoff(),
         false,
         ValidatorTxnConfig::default_disabled(),
@@ -217,3 +223,27 @@ async fn test_correct_failed_authors() {
     assert_eq!(result.failed_authors().unwrap()[3], (4, peer1));
     assert_eq!(result.failed_authors().unwrap()[4], (5, peer2));
 }
+
+#[test]
+fn test_execution_backpressure_hysteresis() {
+    let mut config = ExecutionBackpressureConfig::new(Some(ExecutionBackpressureValues {
+        enter_pipeline_depth: 4,
+        exit_pipeline_depth: 1,
+        max_sending_block_txns_override: 100,
+        max_sending_block_bytes_override: 1024 * 1024,
+        backpressure_proposal_delay_ms: 100,
+    }));
+
+    assert!(config.get_backoff(3).is_none());
+    assert!(config.get_backoff(4).is_some());
+    // Stays engaged until the depth drops to the exit depth
+    assert!(config.get_backoff(3).is_some());
+    assert!(config.get_backoff(2).is_some());
+    assert!(config.get_backoff(1).is_none());
+    assert!(config.get_backoff(3).is_none());
+    assert!(config.get_backoff(5).is_some());
+
+    assert!(ExecutionBackpressureConfig::new(None)
+        .get_backoff(100)
+        .is_none());
+}
@@ -67,6 +67,10 @@ pub trait TExecutionClient: Send + Sync {
     /// This is needed for some DAG tests. Clean this up as a TODO.
     fn get_execution_channel(&self) -> Option<UnboundedSender<OrderedBlocks>>;
 
+    /// Returns the number of blocks pending execution or ledger update, used as backpressure
+    /// signal when generating proposals.
+    fn execution_pipeline_depth(&self) -> usize;
+
     /// Send ordered blocks to the real execution phase through the channel.
     async fn finalize_order(
         &self,
@@ -306,6 +310,10 @@ impl TExecutionClient for ExecutionProxyClient {
         self.handle.read().execute_tx.clone()
     }
 
+    fn execution_pipeline_depth(&self) -> usize {
+        self.execution_proxy.execution_pipeline_depth()
+    }
+
     async fn finalize_order(
         &self,
         blocks: &[Arc<PipelinedBlock>],
@@ -458,6 +466,10 @@ impl TExecutionClient for DummyExecutionClient {
         None
     }
 
+    fn execution_pipeline_depth(&self) -> usize {
+        0
+    }
+
     async fn finalize_order(
         &self,
         _: &[Arc<PipelinedBlock>],
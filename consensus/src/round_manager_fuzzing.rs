@@ -6,7 +6,8 @@ use crate::{
     block_storage::BlockStore,
     liveness::{
         proposal_generator::{
-            ChainHealthBackoffConfig, PipelineBackpressureConfig, ProposalGenerator,
+            ChainHealthBackoffConfig, ExecutionBackpressureConfig, PipelineBackpressureConfig,
+            ProposalGenerator,
         },
         rotating_proposer_election::RotatingProposer,
         round_state::{ExponentialTimeInterval, NewRoundEvent, NewRoundReason, RoundState},
@@ -181,6 +182,7 @@ fn create_node_for_fuzzing() -> RoundManager {
         1024,
         10,
         PipelineBackpressureConfig::new_no_backoff(),
+        ExecutionBackpressureConfig::new(None),
         ChainHealthBackoffConfig::new_no_backoff(),
         false,
         ValidatorTxnConfig::default_disabled(),
@@ -6,7 +6,8 @@ use crate::{
     block_storage::{BlockReader, BlockStore},
     liveness::{
         proposal_generator::{
-            ChainHealthBackoffConfig, PipelineBackpressureConfig, ProposalGenerator,
+            ChainHealthBackoffConfig, ExecutionBackpressureConfig, PipelineBackpressureConfig,
+            ProposalGenerator,
         },
         proposer_election::ProposerElection,
         rotating_proposer_election::RotatingProposer,
@@ -284,6 +285,7 @@ impl NodeSetup {
             1000,
             10,
             PipelineBackpressureConfig::new_no_backoff(),
+            ExecutionBackpressureConfig::new(None),
             ChainHealthBackoffConfig::new_no_backoff(),
             false,
             onchain_consensus_config.effective_validator_txn_config(),
@@ -0,0 +1,163 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::counters::TXN_SIG_VERIFY_CACHE;
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_experimental_runtimes::thread_manager::optimal_min_len;
+use aptos_infallible::Mutex;
+use aptos_types::transaction::{
+    signature_verified_transaction::SignatureVerifiedTransaction, Transaction,
+};
+use lru::LruCache;
+use rayon::prelude::*;
+
+/// Default number of transaction signature verification results kept in the cache.
+pub const DEFAULT_SIG_VERIFY_CACHE_CAPACITY: usize = 100_000;
+
+/// Caches the results of signature verification of user transactions, keyed by the
+/// transaction hash (which covers the authenticator). When a block is re-proposed (e.g. after
+/// a timeout) within the same epoch, its transactions don't need to be verified again.
+///
+/// The cache is cleared on epoch change, as the set of transactions (and the validity of
+/// e.g. keyless signatures) is not expected to carry over between epochs.
+pub struct SignatureVerificationCache {
+    inner: Mutex<CacheInner>,
+}
+
+struct CacheInner {
+    epoch: u64,
+    verified: LruCache<HashValue, bool>,
+}
+
+impl SignatureVerificationCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(CacheInner {
+                epoch: 0,
+                verified: LruCache::new(capacity),
+            }),
+        }
+    }
+
+    /// Verifies signatures of all transactions in the block (in parallel on the provided
+    /// pool), re-using and recording results in the cache.
+    pub fn verify_block(
+        &self,
+        epoch: u64,
+        txns: Vec<Transaction>,
+        pool: &rayon::ThreadPool,
+    ) -> Vec<SignatureVerifiedTransaction> {
+        self.maybe_reset(epoch);
+
+        pool.install(|| {
+            let num_txns = txns.len();
+            txns.into_par_iter()
+                .with_min_len(optimal_min_len(num_txns, 32))
+                .map(|txn| self.verify(txn))
+                .collect::<Vec<_>>()
+        })
+    }
+
+    fn maybe_reset(&self, epoch: u64) {
+        let mut inner = self.inner.lock();
+        if inner.epoch != epoch {
+            inner.verified.clear();
+            inner.epoch = epoch;
+        }
+    }
+
+    fn verify(&self, txn: Transaction) -> SignatureVerifiedTransaction {
+        let signed_txn = match &txn {
+            Transaction::UserTransaction(signed_txn) => signed_txn,
+            // Only user transactions carry signatures.
+            _ => return SignatureVerifiedTransaction::Valid(txn),
+        };
+
+        let hash = txn.hash();
+        let cached = self.inner.lock().verified.get(&hash).copied();
+        let is_valid = match cached {
+            Some(is_valid) => {
+                TXN_SIG_VERIFY_CACHE.with_label_values(&["hit"]).inc();
+                is_valid
+            },
+            None => {
+                TXN_SIG_VERIFY_CACHE.with_label_values(&["miss"]).inc();
+                let is_valid = signed_txn.verify_signature().is_ok();
+                self.inner.lock().verified.put(hash, is_valid);
+                is_valid
+            },
+        };
+
+        if is_valid {
+            SignatureVerifiedTransaction::Valid(txn)
+        } else {
+            SignatureVerifiedTransaction::Invalid(txn)
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.inner.lock().verified.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, SigningKey, Uniform};
+    use aptos_types::{
+        account_address::AccountAddress,
+        chain_id::ChainId,
+        transaction::{RawTransaction, Script, SignedTransaction, TransactionPayload},
+    };
+
+    fn user_txn(seq_num: u64, valid: bool) -> Transaction {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let raw_txn = RawTransaction::new(
+            AccountAddress::random(),
+            seq_num,
+            TransactionPayload::Script(Script::new(vec![], vec![], vec![])),
+            0,
+            0,
+            0,
+            ChainId::test(),
+        );
+        let signature = if valid {
+            private_key.sign(&raw_txn).unwrap()
+        } else {
+            Ed25519PrivateKey::generate_for_testing()
+                .sign(&raw_txn)
+                .unwrap()
+        };
+        Transaction::UserTransaction(SignedTransaction::new(
+            raw_txn,
+            private_key.public_key(),
+            signature,
+        ))
+    }
+
+    #[test]
+    fn test_cache_reuse_and_epoch_reset() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        let cache = SignatureVerificationCache::new(10);
+        let txns = vec![user_txn(0, true), user_txn(1, false)];
+
+        let verified = cache.verify_block(1, txns.clone(), &pool);
+        assert!(verified[0].is_valid());
+        assert!(!verified[1].is_valid());
+        assert_eq!(cache.len(), 2);
+
+        // Re-proposal in the same epoch uses cached results.
+        let verified = cache.verify_block(1, txns.clone(), &pool);
+        assert!(verified[0].is_valid());
+        assert!(!verified[1].is_valid());
+        assert_eq!(cache.len(), 2);
+
+        // New epoch clears the cache.
+        cache.verify_block(2, vec![txns[0].clone()], &pool);
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -128,6 +128,11 @@ impl ExecutionProxy {
         }
     }
 
+    /// Returns the number of blocks pending execution or ledger update.
+    pub fn execution_pipeline_depth(&self) -> usize {
+        self.execution_pipeline.depth()
+    }
+
     fn transactions_to_commit(
         &self,
         executed_block: &PipelinedBlock,
@@ -398,6 +403,7 @@ async fn test_commit_sync_race() {
     use aptos_consensus_notifications::Error;
     use aptos_executor_types::state_checkpoint_output::StateCheckpointOutput;
     use aptos_infallible::Mutex;
+    use aptos_storage_interface::cached_state_view::CachedStateView;
     use aptos_types::{
         aggregate_signature::AggregateSignature,
         block_executor::partitioner::ExecutableBlock,
@@ -447,6 +453,10 @@ async fn test_commit_sync_race() {
             todo!()
         }
 
+        fn state_view(&self, _block_id: HashValue) -> ExecutorResult<CachedStateView> {
+            todo!()
+        }
+
         fn commit_blocks_ext(
             &self,
             _block_ids: Vec<HashValue>,
@@ -15,6 +15,7 @@ use aptos_executor_types::{
     StateComputeResult,
 };
 use aptos_infallible::Mutex;
+use aptos_storage_interface::cached_state_view::CachedStateView;
 use aptos_types::{
     aggregate_signature::AggregateSignature,
     block_executor::{config::BlockExecutorConfigFromOnchain, partitioner::ExecutableBlock},
@@ -120,6 +121,10 @@ impl BlockExecutorTrait for DummyBlockExecutor {
         Ok(StateComputeResult::new_dummy())
     }
 
+    fn state_view(&self, _block_id: HashValue) -> ExecutorResult<CachedStateView> {
+        todo!()
+    }
+
     fn commit_blocks_ext(
         &self,
         _block_ids: Vec<HashValue>,
@@ -106,6 +106,10 @@ impl TExecutionClient for MockExecutionClient {
         Some(self.executor_channel.clone())
     }
 
+    fn execution_pipeline_depth(&self) -> usize {
+        0
+    }
+
     async fn finalize_order(
         &self,
         blocks: &[Arc<PipelinedBlock>],
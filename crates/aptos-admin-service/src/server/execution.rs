@@ -0,0 +1,66 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::server::utils::{reply_with, reply_with_status};
+use aptos_crypto::HashValue;
+use aptos_executor::{
+    execution_audit_log::EXECUTION_AUDIT_LOG, module_gas_stats::MODULE_GAS_STATS,
+};
+use aptos_logger::info;
+use http::header::{HeaderValue, CONTENT_LENGTH};
+use hyper::{Body, Request, Response, StatusCode};
+use std::collections::HashMap;
+
+const DEFAULT_TOP_MODULES: usize = 20;
+
+/// Returns the top modules by gas used, for the last executed block and cumulatively since the
+/// node started. The number of modules returned can be set with the `top` query parameter.
+pub async fn handle_module_gas_stats_request(req: Request<Body>) -> hyper::Result<Response<Body>> {
+    let query = req.uri().query().unwrap_or("");
+    let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+
+    let top: usize = match query_pairs.get("top") {
+        Some(val) => match val.parse() {
+            Ok(val) => val,
+            Err(err) => return Ok(reply_with_status(StatusCode::BAD_REQUEST, err.to_string())),
+        },
+        None => DEFAULT_TOP_MODULES,
+    };
+
+    info!("Dumping module gas stats.");
+
+    let result = MODULE_GAS_STATS.report(top);
+    let headers: Vec<(_, HeaderValue)> = vec![(CONTENT_LENGTH, HeaderValue::from(result.len()))];
+    Ok(reply_with(headers, result))
+}
+
+/// Returns the digests of the execution results of the sampled committed blocks, so that they
+/// can be compared across validators. The digests of the individual transactions of a block are
+/// included if its id is given with the `block_id` query parameter.
+pub async fn handle_execution_audit_log_request(
+    req: Request<Body>,
+) -> hyper::Result<Response<Body>> {
+    if !EXECUTION_AUDIT_LOG.is_enabled() {
+        return Ok(reply_with_status(
+            StatusCode::NOT_FOUND,
+            "Execution audit log is not enabled.",
+        ));
+    }
+
+    let query = req.uri().query().unwrap_or("");
+    let query_pairs: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes()).collect();
+
+    let block_id = match query_pairs.get("block_id") {
+        Some(val) => match HashValue::from_hex(val.as_bytes()) {
+            Ok(val) => Some(val),
+            Err(err) => return Ok(reply_with_status(StatusCode::BAD_REQUEST, err.to_string())),
+        },
+        None => None,
+    };
+
+    info!("Dumping execution audit log.");
+
+    let result = EXECUTION_AUDIT_LOG.report(block_id);
+    let headers: Vec<(_, HeaderValue)> = vec![(CONTENT_LENGTH, HeaderValue::from(result.len()))];
+    Ok(reply_with(headers, result))
+}
@@ -22,12 +22,16 @@ use std::{
 use tokio::runtime::Runtime;
 
 mod consensus;
+mod execution;
 #[cfg(target_os = "linux")]
 pub mod profiling;
+mod standby;
 #[cfg(target_os = "linux")]
 mod thread_dump;
 mod utils;
 
+pub use standby::StandbyPromoter;
+
 #[derive(Default)]
 pub struct Context {
     authentication_configs: Vec<AuthenticationConfig>,
@@ -35,6 +39,7 @@ pub struct Context {
     aptos_db: RwLock<Option<Arc<DbReaderWriter>>>,
     consensus_db: RwLock<Option<Arc<StorageWriteProxy>>>,
     quorum_store_db: RwLock<Option<Arc<QuorumStoreDB>>>,
+    standby_promoter: RwLock<Option<StandbyPromoter>>,
 }
 
 impl Context {
@@ -50,6 +55,10 @@ impl Context {
         *self.consensus_db.write() = Some(consensus_db);
         *self.quorum_store_db.write() = Some(quorum_store_db);
     }
+
+    fn set_standby_promoter(&self, standby_promoter: StandbyPromoter) {
+        *self.standby_promoter.write() = Some(standby_promoter);
+    }
 }
 
 pub struct AdminService {
@@ -108,6 +117,10 @@ impl AdminService {
             .set_consensus_dbs(consensus_db, quorum_store_db)
     }
 
+    pub fn set_standby_promoter(&self, standby_promoter: StandbyPromoter) {
+        self.context.set_standby_promoter(standby_promoter)
+    }
+
     fn start(&self, address: SocketAddr, enabled: bool) {
         let context = self.context.clone();
         self.runtime.spawn(async move {
@@ -211,6 +224,24 @@ impl AdminService {
                     ))
                 }
             },
+            (hyper::Method::GET, "/debug/execution/module_gas_stats") => {
+                execution::handle_module_gas_stats_request(req).await
+            },
+            (hyper::Method::GET, "/debug/execution/audit_log") => {
+                execution::handle_execution_audit_log_request(req).await
+            },
+            (hyper::Method::POST, "/standby/promote") => {
+                let standby_promoter = context.standby_promoter.read().clone();
+                if let Some(standby_promoter) = standby_promoter {
+                    standby::handle_promote_standby_request(req, context.clone(), standby_promoter)
+                        .await
+                } else {
+                    Ok(reply_with_status(
+                        StatusCode::NOT_FOUND,
+                        "The node is not a standby.",
+                    ))
+                }
+            },
             _ => Ok(reply_with_status(StatusCode::NOT_FOUND, "Not found.")),
         }
     }
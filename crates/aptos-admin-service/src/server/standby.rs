@@ -0,0 +1,47 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::server::{utils::reply_with_status, Context};
+use aptos_consensus::{
+    persistent_liveness_storage::StorageWriteProxy, quorum_store::quorum_store_db::QuorumStoreDB,
+};
+use aptos_logger::{info, warn};
+use futures::future::BoxFuture;
+use hyper::{Body, Request, Response, StatusCode};
+use std::sync::Arc;
+
+/// Promotes a hot standby node to an active validator (i.e., starts the validator network and
+/// consensus), and returns the consensus databases of the promoted node.
+pub type StandbyPromoter = Arc<
+    dyn Fn() -> BoxFuture<'static, anyhow::Result<(Arc<StorageWriteProxy>, Arc<QuorumStoreDB>)>>
+        + Send
+        + Sync,
+>;
+
+/// Promotes the standby node to an active validator. Note: the operator must ensure that the
+/// previously active validator (with the same identity) has been stopped before the promotion.
+pub async fn handle_promote_standby_request(
+    _req: Request<Body>,
+    context: Arc<Context>,
+    standby_promoter: StandbyPromoter,
+) -> hyper::Result<Response<Body>> {
+    info!("Promoting the standby node.");
+
+    match standby_promoter().await {
+        Ok((consensus_db, quorum_store_db)) => {
+            context.set_consensus_dbs(consensus_db, quorum_store_db);
+            info!("The standby node has been promoted.");
+            Ok(reply_with_status(
+                StatusCode::OK,
+                "The standby node has been promoted.",
+            ))
+        },
+        Err(error) => {
+            warn!("Failed to promote the standby node: {:?}", error);
+            Ok(reply_with_status(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to promote the standby node: {:?}", error),
+            ))
+        },
+    }
+}
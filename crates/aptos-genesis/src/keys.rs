@@ -43,6 +43,63 @@ pub fn generate_key_objects(
 
     let account_address = AuthenticationKey::ed25519(&account_key.public_key()).account_address();
 
+    Ok(build_key_objects(
+        account_address,
+        account_key,
+        consensus_key,
+        validator_network_key,
+        full_node_network_key,
+    ))
+}
+
+/// The keys of a validator to rotate with [`rotate_key_objects`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct KeyRotation {
+    pub consensus_key: bool,
+    /// Both the validator and the full node network keys
+    pub network_keys: bool,
+}
+
+/// Generates the objects of a validator from its current private keys, with new keys for the
+/// rotated ones.  The account key is never rotated, nor is the account address.
+pub fn rotate_key_objects(
+    keygen: &mut KeyGen,
+    current: PrivateIdentity,
+    rotation: KeyRotation,
+) -> anyhow::Result<(IdentityBlob, IdentityBlob, PrivateIdentity, PublicIdentity)> {
+    let consensus_key = if rotation.consensus_key {
+        keygen.generate_bls12381_private_key()
+    } else {
+        current.consensus_private_key
+    };
+    let (validator_network_key, full_node_network_key) = if rotation.network_keys {
+        (
+            keygen.generate_x25519_private_key()?,
+            keygen.generate_x25519_private_key()?,
+        )
+    } else {
+        (
+            current.validator_network_private_key,
+            current.full_node_network_private_key,
+        )
+    };
+
+    Ok(build_key_objects(
+        current.account_address,
+        ConfigKey::new(current.account_private_key),
+        ConfigKey::new(consensus_key),
+        ConfigKey::new(validator_network_key),
+        ConfigKey::new(full_node_network_key),
+    ))
+}
+
+fn build_key_objects(
+    account_address: AccountAddress,
+    account_key: ConfigKey<Ed25519PrivateKey>,
+    consensus_key: ConfigKey<bls12381::PrivateKey>,
+    validator_network_key: ConfigKey<x25519::PrivateKey>,
+    full_node_network_key: ConfigKey<x25519::PrivateKey>,
+) -> (IdentityBlob, IdentityBlob, PrivateIdentity, PublicIdentity) {
     // Build these for use later as node identity
     let validator_blob = IdentityBlob {
         account_address: Some(account_address),
@@ -76,5 +133,5 @@ pub fn generate_key_objects(
         validator_network_public_key: Some(validator_network_key.public_key()),
     };
 
-    Ok((validator_blob, vfn_blob, private_identity, public_identity))
+    (validator_blob, vfn_blob, private_identity, public_identity)
 }
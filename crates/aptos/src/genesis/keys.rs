@@ -25,10 +25,10 @@ use async_trait::async_trait;
 use clap::Parser;
 use std::path::{Path, PathBuf};
 
-const PRIVATE_KEYS_FILE: &str = "private-keys.yaml";
+pub const PRIVATE_KEYS_FILE: &str = "private-keys.yaml";
 pub const PUBLIC_KEYS_FILE: &str = "public-keys.yaml";
-const VALIDATOR_FILE: &str = "validator-identity.yaml";
-const VFN_FILE: &str = "validator-full-node-identity.yaml";
+pub const VALIDATOR_FILE: &str = "validator-identity.yaml";
+pub const VFN_FILE: &str = "validator-full-node-identity.yaml";
 
 /// Generate keys for a new validator
 ///
@@ -0,0 +1,542 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{OperatorArgs, ValidatorConfig};
+use crate::{
+    common::{
+        types::{
+            CliCommand, CliError, CliTypedResult, RngArgs, TransactionOptions, TransactionSummary,
+        },
+        utils::{
+            append_file_extension, create_dir_if_not_exist, dir_default_to_current, read_from_file,
+            write_to_user_only_file,
+        },
+    },
+    genesis::{
+        git::{from_yaml, to_yaml},
+        keys::{PRIVATE_KEYS_FILE, PUBLIC_KEYS_FILE, VALIDATOR_FILE, VFN_FILE},
+    },
+};
+use aptos_cached_packages::aptos_stdlib;
+use aptos_config::config::IdentityBlob;
+use aptos_crypto::{bls12381, x25519, PrivateKey};
+use aptos_genesis::keys::{rotate_key_objects, KeyRotation, PrivateIdentity};
+use aptos_keygen::KeyGen;
+use aptos_rest_client::{Client, State};
+use aptos_types::{
+    account_address::AccountAddress, account_config::CORE_CODE_ADDRESS,
+    network_address::NetworkAddress, on_chain_config::ValidatorSet, validator_info::ValidatorInfo,
+    validator_performances::ValidatorPerformances,
+};
+use async_trait::async_trait;
+use clap::Parser;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// The directory (in the identity directory) where the keys of a rotation are staged, until
+/// the rotation is activated on chain
+const PENDING_ROTATION_DIR: &str = "pending-key-rotation";
+/// The extension of the backups of the identity files replaced by a rotation
+const BACKUP_EXTENSION: &str = "bak";
+/// The identity files replaced by a rotation, in order.  The private keys file is replaced
+/// last, as an interrupted rotation is resumed from it.
+const IDENTITY_FILES: [&str; 4] = [
+    VALIDATOR_FILE,
+    VFN_FILE,
+    PUBLIC_KEYS_FILE,
+    PRIVATE_KEYS_FILE,
+];
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Rotate the consensus and / or network keys of a running validator
+///
+/// Rotates the keys of the identity files created by `aptos genesis generate-keys`:
+/// 1. Generates the new keys, staged next to the identity files
+/// 2. Submits the rotation transactions, which take effect in the next epoch
+/// 3. Waits for the next epoch, with the new keys in the validator set
+/// 4. Replaces the identity files, keeping backups of the replaced files
+/// 5. Waits for the validator, once restarted with the new identity, to propose a block
+///
+/// If the rotation is interrupted, running the command again resumes it with the staged keys.
+#[derive(Parser)]
+pub struct RotateValidatorKeys {
+    /// Directory of the identity files of the validator
+    ///
+    /// Defaults to the current directory
+    #[clap(long, value_parser)]
+    pub(crate) identity_dir: Option<PathBuf>,
+
+    /// Rotate the consensus key
+    #[clap(long)]
+    pub(crate) rotate_consensus_key: bool,
+
+    /// Rotate the validator and full node network keys
+    ///
+    /// The network addresses on chain keep their hosts, only their keys are replaced
+    #[clap(long)]
+    pub(crate) rotate_network_keys: bool,
+
+    /// Maximum time to wait for the next epoch, in seconds
+    #[clap(long, default_value_t = 3 * 3600)]
+    pub(crate) activation_timeout_secs: u64,
+
+    /// Maximum time to wait for the validator to propose with the new keys, in seconds
+    ///
+    /// The validator must be restarted with the new identity files in the meantime
+    #[clap(long, default_value_t = 1800)]
+    pub(crate) participation_timeout_secs: u64,
+
+    #[clap(flatten)]
+    pub(crate) txn_options: TransactionOptions,
+    #[clap(flatten)]
+    pub(crate) operator_args: OperatorArgs,
+    #[clap(flatten)]
+    pub(crate) rng_args: RngArgs,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyRotationSummary {
+    pub pool_address: AccountAddress,
+    pub activation_epoch: u64,
+    /// The rotation transactions submitted (none if they were submitted by an interrupted run)
+    pub transactions: Vec<TransactionSummary>,
+    pub backup_files: Vec<PathBuf>,
+    /// The proposals of the validator in its first epoch with a successful proposal
+    pub successful_proposals: u64,
+}
+
+#[async_trait]
+impl CliCommand<KeyRotationSummary> for RotateValidatorKeys {
+    fn command_name(&self) -> &'static str {
+        "RotateValidatorKeys"
+    }
+
+    async fn execute(self) -> CliTypedResult<KeyRotationSummary> {
+        let rotation = KeyRotation {
+            consensus_key: self.rotate_consensus_key,
+            network_keys: self.rotate_network_keys,
+        };
+        if rotation == KeyRotation::default() {
+            return Err(CliError::CommandArgumentError(
+                "Must provide --rotate-consensus-key and / or --rotate-network-keys".to_string(),
+            ));
+        }
+        let pool_address = self
+            .operator_args
+            .address_fallback_to_txn(&self.txn_options)?;
+        let client = self
+            .txn_options
+            .rest_options
+            .client(&self.txn_options.profile_options)?;
+        let identity_dir = dir_default_to_current(self.identity_dir.clone())?;
+
+        // The keys of a validator out of the validator set are updated right away, without
+        // waiting for an epoch.
+        let (validator_set, _) = get_validator_set(&client).await?;
+        if find_validator(&validator_set, pool_address).is_none() {
+            return Err(CliError::CommandArgumentError(format!(
+                "Stake pool {} is not in the validator set, use update-consensus-key or \
+                 update-validator-network-addresses instead",
+                pool_address
+            )));
+        }
+
+        let mut keygen = self.rng_args.key_generator()?;
+        let staged = stage_rotation(&identity_dir, &mut keygen, rotation)?;
+
+        // The validator config of the stake pool is updated right away, so the transactions
+        // already submitted by an interrupted run are skipped.
+        let validator_config = client
+            .get_account_resource_bcs::<ValidatorConfig>(
+                pool_address,
+                "0x1::stake::ValidatorConfig",
+            )
+            .await?
+            .into_inner();
+        let expected = staged.expected_validator_config(&validator_config)?;
+        let mut transactions = vec![];
+        if validator_config.consensus_public_key != expected.consensus_public_key {
+            let consensus_private_key = &staged.rotated.consensus_private_key;
+            transactions.push(
+                self.txn_options
+                    .submit_transaction(aptos_stdlib::stake_rotate_consensus_key(
+                        pool_address,
+                        expected.consensus_public_key.clone(),
+                        bls12381::ProofOfPossession::create(consensus_private_key)
+                            .to_bytes()
+                            .to_vec(),
+                    ))
+                    .await?
+                    .into(),
+            );
+        }
+        if validator_config.validator_network_addresses != expected.validator_network_addresses
+            || validator_config.fullnode_network_addresses != expected.fullnode_network_addresses
+        {
+            transactions.push(
+                self.txn_options
+                    .submit_transaction(aptos_stdlib::stake_update_network_and_fullnode_addresses(
+                        pool_address,
+                        expected.validator_network_addresses.clone(),
+                        expected.fullnode_network_addresses.clone(),
+                    ))
+                    .await?
+                    .into(),
+            );
+        }
+
+        eprintln!("Waiting for the rotation to be activated at the next epoch...");
+        let activation_epoch = wait_for_activation(
+            &client,
+            pool_address,
+            &expected,
+            Duration::from_secs(self.activation_timeout_secs),
+        )
+        .await?;
+
+        let backup_files = apply_staged_rotation(&identity_dir)?;
+        eprintln!(
+            "The rotation is active since epoch {}, and the identity files in {} are updated. \
+             Restart the validator to use the new keys, waiting for it to propose...",
+            activation_epoch,
+            identity_dir.display()
+        );
+        let successful_proposals = wait_for_participation(
+            &client,
+            pool_address,
+            activation_epoch,
+            Duration::from_secs(self.participation_timeout_secs),
+        )
+        .await?;
+
+        Ok(KeyRotationSummary {
+            pool_address,
+            activation_epoch,
+            transactions,
+            backup_files,
+            successful_proposals,
+        })
+    }
+}
+
+/// The current and rotated keys of a staged rotation
+struct StagedRotation {
+    current: PrivateIdentity,
+    rotated: PrivateIdentity,
+}
+
+impl StagedRotation {
+    /// Returns the validator config with the rotated keys, from the current one on chain
+    fn expected_validator_config(
+        &self,
+        validator_config: &ValidatorConfig,
+    ) -> CliTypedResult<ValidatorConfig> {
+        let rotate_addresses = |addresses: &[u8],
+                                current_key: &x25519::PrivateKey,
+                                rotated_key: &x25519::PrivateKey|
+         -> CliTypedResult<Vec<u8>> {
+            let mut addresses: Vec<NetworkAddress> = bcs::from_bytes(addresses)?;
+            for address in addresses.iter_mut() {
+                address
+                    .rotate_noise_public_key(&current_key.public_key(), &rotated_key.public_key());
+            }
+            // BCS encode, so that we can hide the original type
+            Ok(bcs::to_bytes(&addresses)?)
+        };
+
+        Ok(ValidatorConfig::new(
+            self.rotated
+                .consensus_private_key
+                .public_key()
+                .to_bytes()
+                .to_vec(),
+            rotate_addresses(
+                &validator_config.validator_network_addresses,
+                &self.current.validator_network_private_key,
+                &self.rotated.validator_network_private_key,
+            )?,
+            rotate_addresses(
+                &validator_config.fullnode_network_addresses,
+                &self.current.full_node_network_private_key,
+                &self.rotated.full_node_network_private_key,
+            )?,
+            validator_config.validator_index,
+        ))
+    }
+}
+
+/// Stages the rotated keys in the identity directory, unless a rotation is already staged, in
+/// which case it is resumed with the staged keys
+fn stage_rotation(
+    identity_dir: &Path,
+    keygen: &mut KeyGen,
+    rotation: KeyRotation,
+) -> CliTypedResult<StagedRotation> {
+    let pending_dir = identity_dir.join(PENDING_ROTATION_DIR);
+    if pending_dir.exists() {
+        eprintln!(
+            "Resuming the key rotation staged in {}",
+            pending_dir.display()
+        );
+    } else {
+        let validator_blob: IdentityBlob = read_yaml(&identity_dir.join(VALIDATOR_FILE))?;
+        let vfn_blob: IdentityBlob = read_yaml(&identity_dir.join(VFN_FILE))?;
+        let (mut rotated_validator_blob, mut rotated_vfn_blob, private_identity, public_identity) =
+            rotate_key_objects(
+                keygen,
+                read_yaml(&identity_dir.join(PRIVATE_KEYS_FILE))?,
+                rotation,
+            )
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        // The owner may be different than the operator
+        rotated_validator_blob.account_address = validator_blob.account_address;
+        rotated_vfn_blob.account_address = vfn_blob.account_address;
+
+        // Written to a temporary directory first, so that the rotation is staged all at once
+        let staging_dir = identity_dir.join(format!("{}.tmp", PENDING_ROTATION_DIR));
+        if staging_dir.exists() {
+            std::fs::remove_dir_all(&staging_dir)
+                .map_err(|err| CliError::IO(staging_dir.display().to_string(), err))?;
+        }
+        create_dir_if_not_exist(&staging_dir)?;
+        write_to_user_only_file(
+            &staging_dir.join(VALIDATOR_FILE),
+            VALIDATOR_FILE,
+            to_yaml(&rotated_validator_blob)?.as_bytes(),
+        )?;
+        write_to_user_only_file(
+            &staging_dir.join(VFN_FILE),
+            VFN_FILE,
+            to_yaml(&rotated_vfn_blob)?.as_bytes(),
+        )?;
+        write_to_user_only_file(
+            &staging_dir.join(PUBLIC_KEYS_FILE),
+            PUBLIC_KEYS_FILE,
+            to_yaml(&public_identity)?.as_bytes(),
+        )?;
+        write_to_user_only_file(
+            &staging_dir.join(PRIVATE_KEYS_FILE),
+            PRIVATE_KEYS_FILE,
+            to_yaml(&private_identity)?.as_bytes(),
+        )?;
+        std::fs::rename(&staging_dir, &pending_dir)
+            .map_err(|err| CliError::IO(pending_dir.display().to_string(), err))?;
+    }
+
+    Ok(StagedRotation {
+        current: read_yaml(&identity_dir.join(PRIVATE_KEYS_FILE))?,
+        rotated: read_yaml(&pending_dir.join(PRIVATE_KEYS_FILE))?,
+    })
+}
+
+/// Replaces the identity files with the staged ones, and returns the backups of the replaced
+/// files.  Each file is replaced atomically (by a rename), and the files already replaced by
+/// an interrupted run are skipped.
+fn apply_staged_rotation(identity_dir: &Path) -> CliTypedResult<Vec<PathBuf>> {
+    let pending_dir = identity_dir.join(PENDING_ROTATION_DIR);
+    let mut backup_files = vec![];
+    for file in IDENTITY_FILES {
+        let staged_file = pending_dir.join(file);
+        if !staged_file.exists() {
+            continue;
+        }
+        let identity_file = identity_dir.join(file);
+        if identity_file.exists() {
+            let backup_file = append_file_extension(&identity_file, BACKUP_EXTENSION)?;
+            write_to_user_only_file(&backup_file, file, &read_from_file(&identity_file)?)?;
+            backup_files.push(backup_file);
+        }
+        std::fs::rename(&staged_file, &identity_file)
+            .map_err(|err| CliError::IO(file.to_string(), err))?;
+    }
+    std::fs::remove_dir(&pending_dir)
+        .map_err(|err| CliError::IO(pending_dir.display().to_string(), err))?;
+    Ok(backup_files)
+}
+
+/// Waits for the validator set to have the expected config of the validator, and returns the
+/// epoch since which it has
+async fn wait_for_activation(
+    client: &Client,
+    pool_address: AccountAddress,
+    expected: &ValidatorConfig,
+    timeout: Duration,
+) -> CliTypedResult<u64> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let (validator_set, state) = get_validator_set(client).await?;
+        let validator_info = find_validator(&validator_set, pool_address).ok_or_else(|| {
+            CliError::UnexpectedError(format!(
+                "Stake pool {} left the validator set during the rotation",
+                pool_address
+            ))
+        })?;
+        let config = validator_info.config();
+        if config.consensus_public_key.to_bytes().as_slice() == expected.consensus_public_key
+            && config.validator_network_addresses == expected.validator_network_addresses
+            && config.fullnode_network_addresses == expected.fullnode_network_addresses
+        {
+            return Ok(state.epoch);
+        }
+
+        if Instant::now() >= deadline {
+            return Err(CliError::UnexpectedError(format!(
+                "The rotation was not activated within {:?} (epoch {}), run the command again \
+                 to keep waiting",
+                timeout, state.epoch
+            )));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Waits for the validator to successfully propose in the activation epoch or later, and
+/// returns its number of successful proposals in that epoch
+async fn wait_for_participation(
+    client: &Client,
+    pool_address: AccountAddress,
+    activation_epoch: u64,
+    timeout: Duration,
+) -> CliTypedResult<u64> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let (validator_set, state) = get_validator_set(client).await?;
+        if state.epoch >= activation_epoch {
+            if let Some(validator_info) = find_validator(&validator_set, pool_address) {
+                let validator_performances: ValidatorPerformances = client
+                    .get_account_resource_at_version_bcs(
+                        CORE_CODE_ADDRESS,
+                        "0x1::stake::ValidatorPerformance",
+                        state.version,
+                    )
+                    .await?
+                    .into_inner();
+                let successful_proposals = validator_performances
+                    .validators
+                    .get(validator_info.config().validator_index as usize)
+                    .map_or(0, |performance| performance.successful_proposals);
+                if successful_proposals > 0 {
+                    return Ok(successful_proposals);
+                }
+            }
+        }
+
+        if Instant::now() >= deadline {
+            return Err(CliError::UnexpectedError(format!(
+                "The validator did not propose within {:?} of the identity files update, check \
+                 that it was restarted with them (backups of the previous files are kept)",
+                timeout
+            )));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn get_validator_set(client: &Client) -> CliTypedResult<(ValidatorSet, State)> {
+    Ok(client
+        .get_account_resource_bcs::<ValidatorSet>(CORE_CODE_ADDRESS, "0x1::stake::ValidatorSet")
+        .await?
+        .into_parts())
+}
+
+fn find_validator(
+    validator_set: &ValidatorSet,
+    pool_address: AccountAddress,
+) -> Option<&ValidatorInfo> {
+    validator_set
+        .payload()
+        .find(|validator_info| *validator_info.account_address() == pool_address)
+}
+
+fn read_yaml<T: DeserializeOwned>(path: &Path) -> CliTypedResult<T> {
+    from_yaml(&String::from_utf8(read_from_file(path)?).map_err(CliError::from)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_genesis::keys::generate_key_objects;
+    use aptos_temppath::TempPath;
+
+    #[test]
+    fn test_stage_and_apply_rotation() {
+        let identity_dir = TempPath::new();
+        identity_dir.create_as_dir().unwrap();
+        let identity_dir = identity_dir.path();
+        let mut keygen = KeyGen::from_seed([0; 32]);
+
+        let (validator_blob, vfn_blob, private_identity, public_identity) =
+            generate_key_objects(&mut keygen).unwrap();
+        let current_consensus_key = private_identity.consensus_private_key.public_key();
+        let current_network_key = private_identity.validator_network_private_key.public_key();
+        for (file, yaml) in [
+            (VALIDATOR_FILE, to_yaml(&validator_blob).unwrap()),
+            (VFN_FILE, to_yaml(&vfn_blob).unwrap()),
+            (PUBLIC_KEYS_FILE, to_yaml(&public_identity).unwrap()),
+            (PRIVATE_KEYS_FILE, to_yaml(&private_identity).unwrap()),
+        ] {
+            write_to_user_only_file(&identity_dir.join(file), file, yaml.as_bytes()).unwrap();
+        }
+
+        let rotation = KeyRotation {
+            consensus_key: true,
+            network_keys: false,
+        };
+        let staged = stage_rotation(identity_dir, &mut keygen, rotation).unwrap();
+        assert_eq!(
+            staged.current.consensus_private_key.public_key(),
+            current_consensus_key
+        );
+        assert_ne!(
+            staged.rotated.consensus_private_key.public_key(),
+            current_consensus_key
+        );
+        assert_eq!(
+            staged.rotated.validator_network_private_key.public_key(),
+            current_network_key
+        );
+        assert_eq!(
+            staged.rotated.account_address,
+            private_identity.account_address
+        );
+
+        // An interrupted rotation is resumed with the staged keys
+        let rotated_consensus_key = staged.rotated.consensus_private_key.public_key();
+        let staged = stage_rotation(identity_dir, &mut keygen, rotation).unwrap();
+        assert_eq!(
+            staged.rotated.consensus_private_key.public_key(),
+            rotated_consensus_key
+        );
+
+        let backup_files = apply_staged_rotation(identity_dir).unwrap();
+        assert_eq!(backup_files.len(), IDENTITY_FILES.len());
+        assert!(!identity_dir.join(PENDING_ROTATION_DIR).exists());
+        let rotated_identity: PrivateIdentity =
+            read_yaml(&identity_dir.join(PRIVATE_KEYS_FILE)).unwrap();
+        assert_eq!(
+            rotated_identity.consensus_private_key.public_key(),
+            rotated_consensus_key
+        );
+        let validator_identity: IdentityBlob =
+            read_yaml(&identity_dir.join(VALIDATOR_FILE)).unwrap();
+        assert_eq!(
+            validator_identity
+                .consensus_private_key
+                .unwrap()
+                .public_key(),
+            rotated_consensus_key
+        );
+        let backup_identity: PrivateIdentity = read_yaml(
+            &append_file_extension(&identity_dir.join(PRIVATE_KEYS_FILE), BACKUP_EXTENSION)
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            backup_identity.consensus_private_key.public_key(),
+            current_consensus_key
+        );
+    }
+}
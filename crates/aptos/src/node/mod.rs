@@ -2,9 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod analyze;
+pub mod key_rotation;
 pub mod local_testnet;
 
-use self::local_testnet::RunLocalTestnet;
+use self::{key_rotation::RotateValidatorKeys, local_testnet::RunLocalTestnet};
 use crate::{
     common::{
         types::{
@@ -77,6 +78,7 @@ pub enum NodeTool {
     ShowValidatorSet(ShowValidatorSet),
     ShowValidatorStake(ShowValidatorStake),
     RunLocalTestnet(RunLocalTestnet),
+    RotateValidatorKeys(RotateValidatorKeys),
     UpdateConsensusKey(UpdateConsensusKey),
     UpdateValidatorNetworkAddresses(UpdateValidatorNetworkAddresses),
 }
@@ -104,6 +106,7 @@ impl NodeTool {
                 .execute_serialized_without_logger()
                 .await
                 .map(|_| "".to_string()),
+            RotateValidatorKeys(tool) => tool.execute_serialized().await,
             UpdateConsensusKey(tool) => tool.execute_serialized().await,
             UpdateValidatorNetworkAddresses(tool) => tool.execute_serialized().await,
         }
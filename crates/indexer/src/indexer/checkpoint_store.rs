@@ -0,0 +1,184 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Storage for processor checkpoints, i.e. the last version successfully processed by each
+//! processor. Checkpoints can be kept either in Postgres alongside the indexed data, or in an
+//! embedded SQLite database for lightweight local deployments (with the `sqlite` feature).
+
+use crate::{
+    database::{execute_with_better_error, PgDbPool},
+    models::processor_status::{ProcessorStatusV2, ProcessorStatusV2Query},
+    schema::processor_status,
+};
+use anyhow::Result;
+use diesel::{pg::upsert::excluded, ExpressionMethods};
+#[cfg(feature = "sqlite")]
+use diesel::{
+    r2d2::{ConnectionManager, Pool},
+    sql_query,
+    sql_types::{BigInt, Text},
+    OptionalExtension, RunQueryDsl, SqliteConnection,
+};
+use std::fmt::Debug;
+
+#[cfg(feature = "sqlite")]
+pub type SqlitePool = Pool<ConnectionManager<SqliteConnection>>;
+
+/// Keeps track of the last version successfully processed by each processor, so that
+/// processing can resume from there after a restart.
+pub trait CheckpointStore: Send + Sync + Debug {
+    /// Stores the last version successfully processed. A checkpoint never moves backwards.
+    fn update_last_processed_version(&self, processor_name: &str, version: u64) -> Result<()>;
+
+    /// Returns the last version successfully processed, if any.
+    fn get_last_processed_version(&self, processor_name: &str) -> Result<Option<u64>>;
+}
+
+/// Stores checkpoints in the `processor_status` table of the indexer's Postgres database.
+#[derive(Debug)]
+pub struct PostgresCheckpointStore {
+    connection_pool: PgDbPool,
+}
+
+impl PostgresCheckpointStore {
+    pub fn new(connection_pool: PgDbPool) -> Self {
+        Self { connection_pool }
+    }
+}
+
+impl CheckpointStore for PostgresCheckpointStore {
+    fn update_last_processed_version(&self, processor_name: &str, version: u64) -> Result<()> {
+        let mut conn = self.connection_pool.get()?;
+
+        let status = ProcessorStatusV2 {
+            processor: processor_name.to_owned(),
+            last_success_version: version as i64,
+        };
+        execute_with_better_error(
+            &mut conn,
+            diesel::insert_into(processor_status::table)
+                .values(&status)
+                .on_conflict(processor_status::processor)
+                .do_update()
+                .set((
+                    processor_status::last_success_version
+                        .eq(excluded(processor_status::last_success_version)),
+                    processor_status::last_updated.eq(excluded(processor_status::last_updated)),
+                )),
+            Some(" WHERE processor_status.last_success_version <= EXCLUDED.last_success_version "),
+        )?;
+        Ok(())
+    }
+
+    fn get_last_processed_version(&self, processor_name: &str) -> Result<Option<u64>> {
+        let mut conn = self.connection_pool.get()?;
+
+        Ok(
+            ProcessorStatusV2Query::get_by_processor(&processor_name.to_owned(), &mut conn)?
+                .map(|status| status.last_success_version as u64),
+        )
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[derive(Debug, QueryableByName)]
+struct Checkpoint {
+    #[diesel(sql_type = BigInt)]
+    last_success_version: i64,
+}
+
+/// Stores checkpoints in an embedded SQLite database, so that no Postgres instance is needed
+/// just to track processing progress.
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct SqliteCheckpointStore {
+    connection_pool: SqlitePool,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteCheckpointStore {
+    /// Opens (or creates) the SQLite database at the given path.
+    pub fn new(path: &str) -> Result<Self> {
+        let manager = ConnectionManager::<SqliteConnection>::new(path);
+        // SQLite allows a single writer at a time, so there is no point in more connections.
+        let connection_pool = Pool::builder().max_size(1).build(manager)?;
+
+        sql_query(
+            "CREATE TABLE IF NOT EXISTS processor_status (
+                processor TEXT PRIMARY KEY NOT NULL,
+                last_success_version BIGINT NOT NULL,
+                last_updated TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+        )
+        .execute(&mut connection_pool.get()?)?;
+
+        Ok(Self { connection_pool })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl CheckpointStore for SqliteCheckpointStore {
+    fn update_last_processed_version(&self, processor_name: &str, version: u64) -> Result<()> {
+        let mut conn = self.connection_pool.get()?;
+
+        sql_query(
+            "INSERT INTO processor_status (processor, last_success_version, last_updated)
+            VALUES (?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT (processor) DO UPDATE SET
+                last_success_version = excluded.last_success_version,
+                last_updated = excluded.last_updated
+            WHERE processor_status.last_success_version <= excluded.last_success_version",
+        )
+        .bind::<Text, _>(processor_name)
+        .bind::<BigInt, _>(version as i64)
+        .execute(&mut conn)?;
+        Ok(())
+    }
+
+    fn get_last_processed_version(&self, processor_name: &str) -> Result<Option<u64>> {
+        let mut conn = self.connection_pool.get()?;
+
+        Ok(
+            sql_query("SELECT last_success_version FROM processor_status WHERE processor = ?")
+                .bind::<Text, _>(processor_name)
+                .get_result::<Checkpoint>(&mut conn)
+                .optional()?
+                .map(|checkpoint| checkpoint.last_success_version as u64),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_checkpoint_store() {
+        let store = SqliteCheckpointStore::new(":memory:").unwrap();
+        assert_eq!(store.get_last_processed_version("processor").unwrap(), None);
+
+        store
+            .update_last_processed_version("processor", 10)
+            .unwrap();
+        assert_eq!(
+            store.get_last_processed_version("processor").unwrap(),
+            Some(10)
+        );
+
+        // Checkpoints never move backwards.
+        store.update_last_processed_version("processor", 5).unwrap();
+        assert_eq!(
+            store.get_last_processed_version("processor").unwrap(),
+            Some(10)
+        );
+
+        store
+            .update_last_processed_version("processor", 20)
+            .unwrap();
+        assert_eq!(
+            store.get_last_processed_version("processor").unwrap(),
+            Some(20)
+        );
+        assert_eq!(store.get_last_processed_version("other").unwrap(), None);
+    }
+}
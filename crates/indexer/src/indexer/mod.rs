@@ -1,6 +1,7 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod checkpoint_store;
 pub mod errors;
 pub mod fetcher;
 pub mod processing_result;
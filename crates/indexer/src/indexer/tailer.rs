@@ -3,26 +3,23 @@
 use crate::{
     database::{execute_with_better_error, PgDbPool},
     indexer::{
+        checkpoint_store::{CheckpointStore, PostgresCheckpointStore},
         errors::TransactionProcessingError,
         fetcher::{TransactionFetcher, TransactionFetcherOptions, TransactionFetcherTrait},
         processing_result::ProcessingResult,
         transaction_processor::TransactionProcessor,
     },
-    models::{
-        ledger_info::LedgerInfo,
-        processor_status::{ProcessorStatusV2, ProcessorStatusV2Query},
-    },
-    schema::{ledger_infos, processor_status},
+    models::ledger_info::LedgerInfo,
+    schema::ledger_infos,
 };
 use anyhow::{ensure, Context, Result};
 use aptos_api::context::Context as ApiContext;
 use aptos_logger::{debug, info};
 use chrono::ParseError;
 use diesel::{
-    pg::upsert::excluded,
     sql_query,
     sql_types::{BigInt, Text},
-    ExpressionMethods, RunQueryDsl,
+    RunQueryDsl,
 };
 use diesel_migrations::{EmbeddedMigrations, MigrationHarness};
 use std::{fmt::Debug, sync::Arc};
@@ -35,6 +32,7 @@ pub struct Tailer {
     pub transaction_fetcher: Arc<Mutex<dyn TransactionFetcherTrait>>,
     processor: Arc<dyn TransactionProcessor>,
     connection_pool: PgDbPool,
+    checkpoint_store: Arc<dyn CheckpointStore>,
 }
 
 impl Tailer {
@@ -48,11 +46,18 @@ impl Tailer {
 
         Ok(Self {
             transaction_fetcher: Arc::new(Mutex::new(transaction_fetcher)),
+            checkpoint_store: Arc::new(PostgresCheckpointStore::new(connection_pool.clone())),
             connection_pool,
             processor,
         })
     }
 
+    /// Overrides where the processor checkpoints are stored (by default, in Postgres).
+    pub fn with_checkpoint_store(mut self, checkpoint_store: Arc<dyn CheckpointStore>) -> Self {
+        self.checkpoint_store = checkpoint_store;
+        self
+    }
+
     pub fn run_migrations(&self) {
         let _ = &self
             .connection_pool
@@ -165,39 +170,19 @@ impl Tailer {
         (num_txns, Some(results))
     }
 
-    /// Store last processed version from database. We can assume that all previously processed
-    /// versions are successful because any gap would cause the processor to panic
+    /// Store last processed version in the checkpoint store. We can assume that all previously
+    /// processed versions are successful because any gap would cause the processor to panic
     pub fn update_last_processed_version(&self, processor_name: &str, version: u64) -> Result<()> {
-        let mut conn = self.connection_pool.get()?;
-
-        let status = ProcessorStatusV2 {
-            processor: processor_name.to_owned(),
-            last_success_version: version as i64,
-        };
-        execute_with_better_error(
-            &mut conn,
-            diesel::insert_into(processor_status::table)
-                .values(&status)
-                .on_conflict(processor_status::processor)
-                .do_update()
-                .set((
-                    processor_status::last_success_version
-                        .eq(excluded(processor_status::last_success_version)),
-                    processor_status::last_updated.eq(excluded(processor_status::last_updated)),
-                )),
-            Some(" WHERE processor_status.last_success_version <= EXCLUDED.last_success_version "),
-        )?;
-        Ok(())
+        self.checkpoint_store
+            .update_last_processed_version(processor_name, version)
     }
 
-    /// Get last version processed successfully from databse
+    /// Get the version following the last version processed successfully, from the checkpoint store
     pub fn get_start_version(&self, processor_name: &String) -> Result<Option<i64>> {
-        let mut conn = self.connection_pool.get()?;
-
-        match ProcessorStatusV2Query::get_by_processor(processor_name, &mut conn)? {
-            Some(status) => Ok(Some(status.last_success_version + 1)),
-            None => Ok(None),
-        }
+        Ok(self
+            .checkpoint_store
+            .get_last_processed_version(processor_name)?
+            .map(|version| version as i64 + 1))
     }
 
     /// Get starting version from database. Starting version is defined as the first version that's either
@@ -4,7 +4,8 @@
 use crate::{
     database::new_db_pool,
     indexer::{
-        fetcher::TransactionFetcherOptions, processing_result::ProcessingResult, tailer::Tailer,
+        checkpoint_store::CheckpointStore, fetcher::TransactionFetcherOptions,
+        processing_result::ProcessingResult, tailer::Tailer,
         transaction_processor::TransactionProcessor,
     },
     processors::{
@@ -14,7 +15,7 @@ use crate::{
     },
 };
 use aptos_api::context::Context;
-use aptos_config::config::{IndexerConfig, NodeConfig};
+use aptos_config::config::{IndexerCheckpointBackend, IndexerConfig, NodeConfig};
 use aptos_logger::{error, info};
 use aptos_mempool::MempoolClientSender;
 use aptos_storage_interface::DbReader;
@@ -103,6 +104,19 @@ pub fn bootstrap(
     Some(Ok(runtime))
 }
 
+#[cfg(feature = "sqlite")]
+fn new_sqlite_checkpoint_store(path: &str) -> Arc<dyn CheckpointStore> {
+    Arc::new(
+        crate::indexer::checkpoint_store::SqliteCheckpointStore::new(path)
+            .expect("Failed to open SQLite checkpoint store"),
+    )
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn new_sqlite_checkpoint_store(_path: &str) -> Arc<dyn CheckpointStore> {
+    panic!("Storing processor checkpoints in SQLite requires the `sqlite` feature of the indexer");
+}
+
 pub async fn run_forever(config: IndexerConfig, context: Arc<Context>) {
     // All of these options should be filled already with defaults
     let processor_name = config.processor.clone().unwrap();
@@ -146,8 +160,19 @@ pub async fn run_forever(config: IndexerConfig, context: Arc<Context>) {
     let options =
         TransactionFetcherOptions::new(None, None, Some(batch_size), None, fetch_tasks as usize);
 
-    let tailer = Tailer::new(context, conn_pool.clone(), processor, options)
+    let mut tailer = Tailer::new(context, conn_pool.clone(), processor, options)
         .expect("Failed to instantiate tailer");
+    match config.checkpoint_backend.unwrap_or_default() {
+        IndexerCheckpointBackend::Postgres => {},
+        IndexerCheckpointBackend::Sqlite { path } => {
+            info!(
+                processor_name = processor_name,
+                checkpoint_sqlite_path = path,
+                "Storing processor checkpoints in SQLite..."
+            );
+            tailer = tailer.with_checkpoint_store(new_sqlite_checkpoint_store(&path));
+        },
+    }
 
     if !skip_migrations {
         info!(processor_name = processor_name, "Running migrations...");
@@ -213,6 +213,11 @@ pub struct EmitArgs {
     #[clap(long)]
     pub account_minter_seed: Option<String>,
 
+    /// Number of accounts (generated from the account minter seed) to skip, so that
+    /// emitters sharing the seed use disjoint ranges of accounts.
+    #[clap(long, requires = "account_minter_seed")]
+    pub account_offset: Option<usize>,
+
     #[clap(long)]
     pub coins_per_account_override: Option<u64>,
 }
@@ -0,0 +1,215 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runs the emitter from multiple machines, as a single emitter cannot saturate the network.
+//! The coordinator assigns every worker (an emitter machine) its share of the load and its own
+//! range of accounts, and aggregates the stats the workers report at the end of the run.
+
+use crate::{
+    args::{ClusterArgs, EmitArgs},
+    emitter::stats::TxnStats,
+    wrappers::emit_transactions,
+};
+use anyhow::{bail, ensure, format_err, Context, Result};
+use aptos_logger::{error, info};
+use futures::future::try_join_all;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::net::SocketAddr;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+/// The assignment sent by the coordinator to a worker, which replies with the stats of the run
+/// (or the error it failed with). Messages are sent as lines of JSON.
+#[derive(Debug, Deserialize, Serialize)]
+struct WorkerAssignment {
+    worker_index: usize,
+    emit_args: EmitArgs,
+}
+
+type WorkerResult = std::result::Result<TxnStats, String>;
+
+/// Splits the load of the emit args evenly across the workers: the target TPS (or mempool
+/// backlog) and the accounts. All workers use the same account minter seed (generated if
+/// not given), with consecutive account offsets, so that their accounts do not overlap.
+pub fn split_emit_args(emit_args: &EmitArgs, num_workers: usize) -> Result<Vec<EmitArgs>> {
+    ensure!(num_workers > 0, "At least one worker is required");
+    let num_accounts = emit_args.num_accounts.ok_or_else(|| {
+        format_err!("num_accounts must be set, to assign account ranges to the workers")
+    })?;
+    ensure!(
+        num_accounts >= num_workers,
+        "num_accounts ({}) must not be lower than the number of workers ({})",
+        num_accounts,
+        num_workers
+    );
+    let account_minter_seed = emit_args.account_minter_seed.clone().unwrap_or_else(|| {
+        let seed: [u8; 32] = StdRng::from_entropy().gen();
+        format!("{:?}", seed)
+    });
+    info!(
+        "AccountMinter Seed of the workers (reuse accounts by passing into --account-minter-seed): {}",
+        account_minter_seed
+    );
+
+    let mut account_offset = emit_args.account_offset.unwrap_or(0);
+    let mut worker_args = vec![];
+    for worker_index in 0..num_workers {
+        let mut args = emit_args.clone();
+        args.mempool_backlog = emit_args
+            .mempool_backlog
+            .map(|mempool_backlog| split_evenly(mempool_backlog, num_workers, worker_index));
+        args.target_tps = emit_args
+            .target_tps
+            .map(|target_tps| split_evenly(target_tps, num_workers, worker_index));
+        if args.mempool_backlog == Some(0) || args.target_tps == Some(0) {
+            bail!(
+                "The load is too low to be split across {} workers",
+                num_workers
+            );
+        }
+
+        let worker_num_accounts = split_evenly(num_accounts, num_workers, worker_index);
+        args.num_accounts = Some(worker_num_accounts);
+        args.account_minter_seed = Some(account_minter_seed.clone());
+        args.account_offset = Some(account_offset);
+        account_offset += worker_num_accounts;
+
+        worker_args.push(args);
+    }
+    Ok(worker_args)
+}
+
+fn split_evenly(total: usize, num_parts: usize, index: usize) -> usize {
+    total / num_parts + usize::from(index < total % num_parts)
+}
+
+/// Runs the emit args on the workers (listening at the given addresses, see
+/// [`run_emit_worker`]), and returns the combined stats of the workers.
+pub async fn coordinate_emit_workers(workers: &[String], emit_args: &EmitArgs) -> Result<TxnStats> {
+    let worker_args = split_emit_args(emit_args, workers.len())?;
+    let worker_stats = try_join_all(workers.iter().zip(worker_args).enumerate().map(
+        |(worker_index, (worker, emit_args))| async move {
+            let assignment = WorkerAssignment {
+                worker_index,
+                emit_args,
+            };
+            let stats = run_on_worker(worker, &assignment)
+                .await
+                .with_context(|| format!("Worker {} ({}) failed", worker_index, worker))?;
+            info!(
+                "Worker {} ({}) stats: {}, rate: {}",
+                worker_index,
+                worker,
+                stats,
+                stats.rate()
+            );
+            Ok::<_, anyhow::Error>(stats)
+        },
+    ))
+    .await?;
+
+    Ok(worker_stats
+        .iter()
+        .fold(TxnStats::default(), |combined, stats| {
+            combined.combine_concurrent(stats)
+        }))
+}
+
+async fn run_on_worker(worker: &str, assignment: &WorkerAssignment) -> Result<TxnStats> {
+    let mut stream = BufReader::new(TcpStream::connect(worker).await?);
+    write_message(stream.get_mut(), assignment).await?;
+    let result: WorkerResult = read_message(&mut stream).await?;
+    result.map_err(|error| format_err!(error))
+}
+
+/// Runs the assignments of the coordinator (one at a time) on the cluster, reporting the stats
+/// of every run back to the coordinator.
+pub async fn run_emit_worker(cluster_args: &ClusterArgs, listen_address: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(listen_address)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen_address))?;
+    info!("Emit worker listening on {}", listen_address);
+    loop {
+        let (stream, coordinator) = listener.accept().await?;
+        if let Err(error) = serve_assignment(cluster_args, stream).await {
+            error!(
+                "Failed to serve the assignment of coordinator {}: {:?}",
+                coordinator, error
+            );
+        }
+    }
+}
+
+async fn serve_assignment(cluster_args: &ClusterArgs, stream: TcpStream) -> Result<()> {
+    let mut stream = BufReader::new(stream);
+    let assignment: WorkerAssignment = read_message(&mut stream).await?;
+    info!(
+        "Running the assignment of worker {}: {:?}",
+        assignment.worker_index, assignment.emit_args
+    );
+    let result: WorkerResult = emit_transactions(cluster_args, &assignment.emit_args)
+        .await
+        .map_err(|error| format!("{:?}", error));
+    write_message(stream.get_mut(), &result).await
+}
+
+async fn write_message<T: Serialize>(stream: &mut TcpStream, message: &T) -> Result<()> {
+    let mut bytes = serde_json::to_vec(message)?;
+    bytes.push(b'\n');
+    stream.write_all(&bytes).await?;
+    Ok(())
+}
+
+async fn read_message<T: DeserializeOwned>(stream: &mut BufReader<TcpStream>) -> Result<T> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await? == 0 {
+        bail!("Connection closed before the message was received");
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_emit_args() {
+        let emit_args = EmitArgs {
+            target_tps: Some(1000),
+            num_accounts: Some(100),
+            account_offset: Some(10),
+            ..EmitArgs::default()
+        };
+        let worker_args = split_emit_args(&emit_args, 3).unwrap();
+
+        let target_tps: Vec<_> = worker_args.iter().map(|args| args.target_tps).collect();
+        assert_eq!(target_tps, vec![Some(334), Some(333), Some(333)]);
+        let account_ranges: Vec<_> = worker_args
+            .iter()
+            .map(|args| (args.account_offset.unwrap(), args.num_accounts.unwrap()))
+            .collect();
+        assert_eq!(account_ranges, vec![(10, 34), (44, 33), (77, 33)]);
+        for args in &worker_args {
+            assert_eq!(args.mempool_backlog, None);
+            assert_eq!(args.account_minter_seed, worker_args[0].account_minter_seed);
+        }
+        assert!(worker_args[0].account_minter_seed.is_some());
+
+        // The load and the accounts must be enough for all the workers
+        assert!(split_emit_args(&emit_args, 101).is_err());
+        let emit_args = EmitArgs {
+            mempool_backlog: Some(2),
+            num_accounts: Some(100),
+            ..EmitArgs::default()
+        };
+        assert!(split_emit_args(&emit_args, 3).is_err());
+        let emit_args = EmitArgs {
+            target_tps: Some(1000),
+            ..EmitArgs::default()
+        };
+        assert!(split_emit_args(&emit_args, 3).is_err());
+    }
+}
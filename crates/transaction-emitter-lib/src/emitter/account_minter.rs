@@ -583,6 +583,17 @@ where
     Ok(accounts)
 }
 
+/// Advances the rng past the keys of the next num_accounts reusable accounts, so that the
+/// accounts generated afterwards are the ones following them.
+pub fn skip_reusable_accounts<R>(num_accounts: usize, rng: &mut R)
+where
+    R: rand_core::RngCore + ::rand_core::CryptoRng,
+{
+    for _ in 0..num_accounts {
+        AccountKey::generate(rng);
+    }
+}
+
 pub fn create_and_fund_account_request(
     creation_account: &LocalAccount,
     amount: u64,
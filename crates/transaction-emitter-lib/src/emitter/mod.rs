@@ -7,7 +7,9 @@ pub mod submission_worker;
 pub mod transaction_executor;
 
 use crate::emitter::{
-    account_minter::{gen_reusable_accounts, AccountMinter, SourceAccountManager},
+    account_minter::{
+        gen_reusable_accounts, skip_reusable_accounts, AccountMinter, SourceAccountManager,
+    },
     stats::{DynamicStatsTracking, TxnStats},
     submission_worker::SubmissionWorker,
     transaction_executor::RestApiReliableTransactionSubmitter,
@@ -171,6 +173,7 @@ pub struct EmitJobRequest {
     latency_polling_interval: Duration,
 
     account_minter_seed: Option<[u8; 32]>,
+    account_offset: usize,
 }
 
 impl Default for EmitJobRequest {
@@ -199,6 +202,7 @@ impl Default for EmitJobRequest {
             coordination_delay_between_instances: Duration::from_secs(0),
             latency_polling_interval: Duration::from_millis(300),
             account_minter_seed: None,
+            account_offset: 0,
             coins_per_account_override: None,
         }
     }
@@ -314,6 +318,13 @@ impl EmitJobRequest {
         self
     }
 
+    /// Skips the given number of accounts generated from the account minter seed, so that
+    /// emitters sharing the seed (e.g. on different machines) use disjoint ranges of accounts.
+    pub fn account_offset(mut self, account_offset: usize) -> Self {
+        self.account_offset = account_offset;
+        self
+    }
+
     pub fn coins_per_account_override(mut self, coins: u64) -> Self {
         self.coins_per_account_override = Some(coins);
         self
@@ -1150,6 +1161,9 @@ pub async fn create_accounts(
     };
 
     let mut rng = StdRng::from_seed(seed);
+    skip_reusable_accounts(req.account_offset, &mut rng);
+    // The minter must not create the same seed accounts as the emitters of other ranges
+    let minter_rng = rng.clone();
 
     let accounts = gen_reusable_accounts(&txn_executor, num_accounts, &mut rng).await?;
     info!("Generated re-usable accounts for seed {:?}", seed);
@@ -1164,7 +1178,7 @@ pub async fn create_accounts(
     let mut account_minter = AccountMinter::new(
         &source_account_manager,
         txn_factory.clone().with_max_gas_amount(send_money_gas),
-        StdRng::from_seed(seed),
+        minter_rng,
     );
 
     if !skip_minting_accounts {
@@ -1,7 +1,9 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use serde::{Deserialize, Serialize};
 use std::{
+    cmp::max,
     fmt,
     ops::{Add, Sub},
     sync::{
@@ -11,7 +13,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct TxnStats {
     pub submitted: u64,
     pub committed: u64,
@@ -72,6 +74,15 @@ impl TxnStats {
             p99_latency: self.latency_buckets.percentile(99, 100),
         }
     }
+
+    /// Combines the stats of emitters that ran concurrently (e.g. on different machines),
+    /// i.e. over the same window rather than one after the other (as with addition).
+    pub fn combine_concurrent(&self, other: &TxnStats) -> TxnStats {
+        TxnStats {
+            lasted: max(self.lasted, other.lasted),
+            ..self + other
+        }
+    }
 }
 
 impl fmt::Display for TxnStats {
@@ -201,7 +212,7 @@ impl AtomicHistogramAccumulator {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AtomicHistogramSnapshot {
     capacity: usize,
     step_width: u64,
@@ -410,4 +421,40 @@ mod test {
         let res = stat.latency_buckets.percentile(9, 10);
         assert_eq!(res, 900);
     }
+
+    #[test]
+    pub fn test_combine_concurrent() {
+        let histogram = AtomicHistogramAccumulator::default();
+        histogram.record_data_point(100, 10);
+        let stat = TxnStats {
+            submitted: 20,
+            committed: 10,
+            expired: 0,
+            failed_submission: 0,
+            latency: 1000,
+            latency_samples: 10,
+            latency_buckets: histogram.snapshot(),
+            lasted: Duration::from_secs(10),
+        };
+        histogram.record_data_point(300, 20);
+        let other = TxnStats {
+            submitted: 30,
+            committed: 20,
+            expired: 1,
+            failed_submission: 0,
+            latency: 4000,
+            latency_samples: 20,
+            latency_buckets: &histogram.snapshot() - &stat.latency_buckets,
+            lasted: Duration::from_secs(12),
+        };
+
+        // The emitters ran over the same window, so the rates add up
+        let combined = stat.combine_concurrent(&other);
+        assert_eq!(combined.lasted, Duration::from_secs(12));
+        let rate = combined.rate();
+        assert_eq!(rate.submitted, 4);
+        assert_eq!(rate.committed, 2);
+        assert_eq!(rate.latency, 5000 / 30);
+        assert_eq!(rate.p50_latency, 300);
+    }
 }
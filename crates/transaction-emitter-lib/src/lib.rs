@@ -5,6 +5,7 @@
 
 mod args;
 mod cluster;
+mod coordinator;
 pub mod emitter;
 mod instance;
 mod wrappers;
@@ -13,6 +14,7 @@ mod wrappers;
 pub use args::{ClusterArgs, CoinSourceArgs, CreateAccountsArgs, EmitArgs};
 // We export these if you want finer grained control.
 pub use cluster::Cluster;
+pub use coordinator::{coordinate_emit_workers, run_emit_worker, split_emit_args};
 pub use emitter::{
     query_sequence_number, query_sequence_numbers,
     stats::{TxnStats, TxnStatsRate},
@@ -144,6 +144,10 @@ pub async fn emit_transactions_with_cluster(
         emit_job_request = emit_job_request.account_minter_seed(seed);
     }
 
+    if let Some(account_offset) = args.account_offset {
+        emit_job_request = emit_job_request.account_offset(account_offset);
+    }
+
     if let Some(coins) = args.coins_per_account_override {
         emit_job_request = emit_job_request.coins_per_account_override(coins);
     }
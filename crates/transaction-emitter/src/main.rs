@@ -7,10 +7,12 @@ mod diag;
 use anyhow::{Context, Result};
 use aptos_logger::{Level, Logger};
 use aptos_transaction_emitter_lib::{
-    create_accounts_command, emit_transactions, Cluster, ClusterArgs, CreateAccountsArgs, EmitArgs,
+    coordinate_emit_workers, create_accounts_command, emit_transactions, run_emit_worker, Cluster,
+    ClusterArgs, CreateAccountsArgs, EmitArgs,
 };
 use clap::{Parser, Subcommand};
 use diag::diag;
+use std::net::SocketAddr;
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -25,6 +27,13 @@ enum TxnEmitterCommand {
     /// recording stats as we go.
     EmitTx(EmitTx),
 
+    /// Coordinates emitting transactions from multiple machines (running EmitTxWorker),
+    /// splitting the load and the accounts across them, and aggregating their stats.
+    EmitTxCoordinator(EmitTxCoordinator),
+
+    /// Runs the transactions emitted by a coordinator (see EmitTxCoordinator) on this machine.
+    EmitTxWorker(EmitTxWorker),
+
     /// Create test accounts, for use with EmitTx
     CreateAccounts(CreateAccounts),
 
@@ -46,6 +55,26 @@ struct EmitTx {
     emit_args: EmitArgs,
 }
 
+#[derive(Parser, Debug)]
+struct EmitTxCoordinator {
+    /// Addresses of the workers, e.g. `emitter-1.mysite.com:9105`
+    #[clap(long, required = true, num_args = 1..)]
+    workers: Vec<String>,
+
+    #[clap(flatten)]
+    emit_args: EmitArgs,
+}
+
+#[derive(Parser, Debug)]
+struct EmitTxWorker {
+    #[clap(flatten)]
+    cluster_args: ClusterArgs,
+
+    /// Address to listen on for the coordinator
+    #[clap(long, default_value = "0.0.0.0:9105")]
+    listen_address: SocketAddr,
+}
+
 #[derive(Parser, Debug)]
 struct CreateAccounts {
     #[clap(flatten)]
@@ -84,6 +113,20 @@ pub async fn main() -> Result<()> {
             println!("Average rate: {}", stats.rate());
             Ok(())
         },
+        TxnEmitterCommand::EmitTxCoordinator(args) => {
+            let stats = coordinate_emit_workers(&args.workers, &args.emit_args)
+                .await
+                .map_err(|e| panic!("Emit transactions failed {:?}", e))
+                .unwrap();
+            println!("Total stats: {}", stats);
+            println!("Average rate: {}", stats.rate());
+            Ok(())
+        },
+        TxnEmitterCommand::EmitTxWorker(args) => {
+            run_emit_worker(&args.cluster_args, args.listen_address)
+                .await
+                .context("Emit worker failed")
+        },
         TxnEmitterCommand::CreateAccounts(args) => {
             create_accounts_command(&args.cluster_args, &args.create_accounts_args)
                 .await
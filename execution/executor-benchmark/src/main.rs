@@ -254,6 +254,14 @@ struct Opt {
     #[clap(long)]
     verify_sequence_numbers: bool,
 
+    /// Check delayed field reads during the regular validation of parallel execution
+    #[clap(long)]
+    early_delayed_field_validation: bool,
+
+    /// Read the base values of the keys likely to be read by a block before parallel execution
+    #[clap(long)]
+    prefetch_base_values: bool,
+
     #[clap(flatten)]
     vm_selection_opt: VmSelectionOpt,
 
@@ -491,6 +499,8 @@ fn main() {
     AptosVM::set_concurrency_level_once(execution_threads_per_shard);
     NativeExecutor::set_concurrency_level_once(execution_threads_per_shard);
     AptosVM::set_processed_transactions_detailed_counters();
+    AptosVM::set_early_delayed_field_validation(opt.early_delayed_field_validation);
+    AptosVM::set_prefetch_base_values(opt.prefetch_base_values);
 
     let config = ProfilerConfig::new_with_defaults();
     let handler = ProfilerHandler::new(config);
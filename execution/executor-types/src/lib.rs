@@ -10,6 +10,7 @@ use aptos_crypto::{
     HashValue,
 };
 use aptos_scratchpad::{ProofRead, SparseMerkleTree};
+use aptos_storage_interface::cached_state_view::CachedStateView;
 use aptos_types::{
     account_config::NEW_EPOCH_EVENT_MOVE_TYPE_TAG,
     block_executor::{config::BlockExecutorConfigFromOnchain, partitioner::ExecutableBlock},
@@ -165,6 +166,12 @@ pub trait BlockExecutorTrait: Send + Sync {
         state_checkpoint_output: StateCheckpointOutput,
     ) -> ExecutorResult<StateComputeResult>;
 
+    /// Returns a view of the state after the given block, which may be executed but not committed
+    /// yet (e.g. a proposed block), so that other components can read the speculative state, e.g.
+    /// to validate the transactions that follow the block against it rather than against the
+    /// last committed state.
+    fn state_view(&self, block_id: HashValue) -> ExecutorResult<CachedStateView>;
+
     /// Saves eligible blocks to persistent storage.
     /// If we have multiple blocks and not all of them have signatures, we may send them to storage
     /// in a few batches. For example, if we have
@@ -8,6 +8,7 @@ use crate::{
     components::{
         apply_chunk_output::ApplyChunkOutput, block_tree::BlockTree, chunk_output::ChunkOutput,
     },
+    execution_audit_log::EXECUTION_AUDIT_LOG,
     logging::{LogEntry, LogSchema},
     metrics::{
         APTOS_EXECUTOR_COMMIT_BLOCKS_SECONDS, APTOS_EXECUTOR_EXECUTE_BLOCK_SECONDS,
@@ -137,6 +138,15 @@ where
             .ledger_update(block_id, parent_block_id, state_checkpoint_output)
     }
 
+    fn state_view(&self, block_id: HashValue) -> ExecutorResult<CachedStateView> {
+        self.maybe_initialize()?;
+        self.inner
+            .read()
+            .as_ref()
+            .expect("BlockExecutor is not reset")
+            .state_view(block_id)
+    }
+
     fn commit_blocks_ext(
         &self,
         block_ids: Vec<HashValue>,
@@ -229,7 +239,10 @@ where
                         .start_timer();
                     info!("next_version: {}", parent_output.next_version());
                     CachedStateView::new(
-                        StateViewId::BlockExecution { block_id },
+                        StateViewId::BlockExecution {
+                            block_id,
+                            parent_block_id,
+                        },
                         Arc::clone(&self.db.reader),
                         parent_output.next_version(),
                         parent_output.state().current.clone(),
@@ -264,6 +277,27 @@ where
         Ok(state_checkpoint_output)
     }
 
+    fn state_view(&self, block_id: HashValue) -> ExecutorResult<CachedStateView> {
+        let block = self
+            .block_tree
+            .get_blocks_opt(&[block_id])?
+            .pop()
+            .expect("Must exist.")
+            .ok_or(ExecutorError::BlockNotFound(block_id))?;
+        let output = &block.output;
+        // A view of the state after the block, e.g. to execute transactions on top of it.
+        Ok(CachedStateView::new(
+            StateViewId::BlockExecution {
+                block_id,
+                parent_block_id: block_id,
+            },
+            Arc::clone(&self.db.reader),
+            output.next_version(),
+            output.state().current.clone(),
+            Arc::new(AsyncProofFetcher::new(self.db.reader.clone())),
+        )?)
+    }
+
     fn ledger_update(
         &self,
         block_id: HashValue,
@@ -415,6 +449,11 @@ where
                     .clone(),
                 Some(&block.output.get_ledger_update().sharded_state_cache),
             )?;
+            EXECUTION_AUDIT_LOG.record_block(
+                block.id,
+                first_version,
+                txns_to_commit.iter().map(|txn| txn.transaction_info()),
+            );
             first_version += txns_to_commit.len() as u64;
             committed_block = block.clone();
         }
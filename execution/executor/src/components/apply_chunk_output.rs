@@ -10,6 +10,7 @@ use crate::{
         in_memory_state_calculator_v2::InMemoryStateCalculatorV2,
     },
     metrics::{APTOS_EXECUTOR_ERRORS, APTOS_EXECUTOR_OTHER_TIMERS_SECONDS},
+    module_gas_stats::MODULE_GAS_STATS,
 };
 use anyhow::{ensure, Result};
 use aptos_crypto::{hash::CryptoHash, HashValue};
@@ -134,6 +135,7 @@ impl ApplyChunkOutput {
             to_retry.parsed_outputs(),
             "execution",
         );
+        MODULE_GAS_STATS.record_block(to_commit.txns(), to_commit.parsed_outputs());
 
         // Calculate TransactionData and TransactionInfo, i.e. the ledger history diff.
         let _timer = APTOS_EXECUTOR_OTHER_TIMERS_SECONDS
@@ -304,15 +306,6 @@ impl ApplyChunkOutput {
         let mut txn_info_hashes = Vec::with_capacity(num_txns);
         let hashes_vec =
             Self::calculate_events_and_writeset_hashes(to_commit_from_execution.parsed_outputs());
-        let hashes_vec: Vec<(HashValue, HashValue)> = hashes_vec
-            .into_par_iter()
-            .map(|(event_hashes, write_set_hash)| {
-                (
-                    InMemoryEventAccumulator::from_leaves(&event_hashes).root_hash(),
-                    write_set_hash,
-                )
-            })
-            .collect();
 
         let mut all_subscribable_events = Vec::new();
         let (to_commit_txns, to_commit_outputs) = to_commit_from_execution.into_inner();
@@ -365,27 +358,43 @@ impl ApplyChunkOutput {
         (to_commit, txn_info_hashes, all_subscribable_events)
     }
 
+    /// Computes the event root hash and the write set hash of each transaction, in parallel, as
+    /// they are independent per transaction. The result is in the same order as the input.
     fn calculate_events_and_writeset_hashes(
         to_commit_from_execution: &[ParsedTransactionOutput],
-    ) -> Vec<(Vec<HashValue>, HashValue)> {
+    ) -> Vec<(HashValue, HashValue)> {
         let _timer = APTOS_EXECUTOR_OTHER_TIMERS_SECONDS
             .with_label_values(&["calculate_events_and_writeset_hashes"])
             .start_timer();
         let num_txns = to_commit_from_execution.len();
-        to_commit_from_execution
+        let hashes: Vec<_> = to_commit_from_execution
             .par_iter()
             .with_min_len(optimal_min_len(num_txns, 64))
-            .map(|txn_output| {
+            .enumerate()
+            .map(|(idx, txn_output)| {
+                let event_hashes: Vec<_> =
+                    txn_output.events().iter().map(CryptoHash::hash).collect();
                 (
-                    txn_output
-                        .events()
-                        .iter()
-                        .map(CryptoHash::hash)
-                        .collect::<Vec<_>>(),
+                    idx,
+                    InMemoryEventAccumulator::from_leaves(&event_hashes).root_hash(),
                     CryptoHash::hash(txn_output.write_set()),
                 )
             })
-            .collect::<Vec<_>>()
+            .collect();
+
+        // Collecting an indexed parallel iterator preserves the order, and the hashes must
+        // line up with the transactions they end up in the transaction infos of.
+        assert!(
+            hashes
+                .iter()
+                .enumerate()
+                .all(|(expected_idx, (idx, _, _))| *idx == expected_idx),
+            "Event and write set hashes are out of order.",
+        );
+        hashes
+            .into_iter()
+            .map(|(_idx, event_root_hash, write_set_hash)| (event_root_hash, write_set_hash))
+            .collect()
     }
 }
 
@@ -441,3 +450,41 @@ fn assemble_ledger_diff_should_filter_subscribable_events() {
     );
     assert_eq!(vec![event_0, event_2], subscribable_events);
 }
+
+#[test]
+fn calculate_events_and_writeset_hashes_should_preserve_order() {
+    let outputs: Vec<_> = (0..1000u64)
+        .map(|i| {
+            let events = (0..i % 5)
+                .map(|j| {
+                    ContractEvent::new_v2_with_type_tag_str(
+                        "0x1::test::TestEvent",
+                        bcs::to_bytes(&(i, j)).unwrap(),
+                    )
+                })
+                .collect();
+            ParsedTransactionOutput::from(TransactionOutput::new(
+                WriteSet::default(),
+                events,
+                i,
+                TransactionStatus::Keep(ExecutionStatus::Success),
+                TransactionAuxiliaryData::default(),
+            ))
+        })
+        .collect();
+
+    let expected: Vec<_> = outputs
+        .iter()
+        .map(|output| {
+            let event_hashes: Vec<_> = output.events().iter().map(CryptoHash::hash).collect();
+            (
+                InMemoryEventAccumulator::from_leaves(&event_hashes).root_hash(),
+                CryptoHash::hash(output.write_set()),
+            )
+        })
+        .collect();
+    assert_eq!(
+        ApplyChunkOutput::calculate_events_and_writeset_hashes(&outputs),
+        expected
+    );
+}
@@ -371,6 +371,20 @@ pub fn update_counters_for_processed_chunk<T, O>(
                             state,
                         ])
                         .inc();
+                    metrics::APTOS_PROCESSED_USER_TRANSACTIONS_GAS_BY_MODULE
+                        .with_label_values(&[
+                            detailed_counters_label,
+                            process_type,
+                            if is_core { "core" } else { "user" },
+                            if detailed_counters {
+                                function.module().name().as_str()
+                            } else if is_core {
+                                "core_module"
+                            } else {
+                                "user_module"
+                            },
+                        ])
+                        .inc_by(output.get_transaction_output().gas_used());
                     if is_core && detailed_counters {
                         metrics::APTOS_PROCESSED_USER_TRANSACTIONS_ENTRY_FUNCTION_CORE_METHOD
                             .with_label_values(&[
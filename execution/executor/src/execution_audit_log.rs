@@ -0,0 +1,238 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! An opt-in audit log of the execution results of a sample of the committed blocks. For every
+//! sampled block, a compact digest of the output of each transaction (status, gas used and write
+//! set hash) is kept in memory and exposed through the admin service.
+//!
+//! Blocks are sampled by the prefix of their id, so that all validators sample the same blocks.
+//! Operators can then compare the digests across validators to detect non-determinism early,
+//! i.e., before it results in a fork of the state root.
+
+use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
+use aptos_types::transaction::{ExecutionStatus, TransactionInfo, Version};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::{collections::VecDeque, fmt::Write};
+
+pub static EXECUTION_AUDIT_LOG: Lazy<ExecutionAuditLog> = Lazy::new(ExecutionAuditLog::default);
+
+/// The digest of the output of a single committed transaction
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct TxnOutputDigest {
+    pub version: Version,
+    pub status: ExecutionStatus,
+    pub gas_used: u64,
+    pub write_set_hash: HashValue,
+}
+
+/// The digests of the transaction outputs of a sampled block
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlockOutputDigest {
+    pub block_id: HashValue,
+    /// A hash over all transaction output digests, to compare blocks at a glance
+    pub digest: HashValue,
+    pub txn_digests: Vec<TxnOutputDigest>,
+}
+
+#[derive(Default)]
+pub struct ExecutionAuditLog {
+    inner: Mutex<Option<ExecutionAuditLogInner>>,
+}
+
+struct ExecutionAuditLogInner {
+    sampling_prefix_bits: usize,
+    max_sampled_blocks: usize,
+    sampled_blocks: VecDeque<BlockOutputDigest>,
+}
+
+impl ExecutionAuditLog {
+    /// Enables the audit log. A committed block is sampled if the first `sampling_prefix_bits`
+    /// bits of its id are zero (i.e., one block in 2^sampling_prefix_bits), and only the last
+    /// `max_sampled_blocks` sampled blocks are kept.
+    pub fn enable(&self, sampling_prefix_bits: usize, max_sampled_blocks: usize) {
+        *self.inner.lock() = Some(ExecutionAuditLogInner {
+            sampling_prefix_bits,
+            max_sampled_blocks,
+            sampled_blocks: VecDeque::new(),
+        });
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.lock().is_some()
+    }
+
+    /// Records the transaction outputs of a committed block, if the block is sampled.
+    pub fn record_block<'a>(
+        &self,
+        block_id: HashValue,
+        first_version: Version,
+        txn_infos: impl IntoIterator<Item = &'a TransactionInfo>,
+    ) {
+        let mut inner = self.inner.lock();
+        let inner = match inner.as_mut() {
+            Some(inner) => inner,
+            None => return,
+        };
+        if block_id.common_prefix_bits_len(HashValue::zero()) < inner.sampling_prefix_bits
+            || inner.max_sampled_blocks == 0
+        {
+            return;
+        }
+
+        let txn_digests: Vec<_> = txn_infos
+            .into_iter()
+            .zip(first_version..)
+            .map(|(txn_info, version)| TxnOutputDigest {
+                version,
+                status: txn_info.status().clone(),
+                gas_used: txn_info.gas_used(),
+                write_set_hash: txn_info.state_change_hash(),
+            })
+            .collect();
+        let digest = HashValue::sha3_256_of(
+            &bcs::to_bytes(&txn_digests).expect("Transaction output digests must serialize"),
+        );
+
+        if inner.sampled_blocks.len() >= inner.max_sampled_blocks {
+            inner.sampled_blocks.pop_front();
+        }
+        inner.sampled_blocks.push_back(BlockOutputDigest {
+            block_id,
+            digest,
+            txn_digests,
+        });
+    }
+
+    /// Returns the sampled blocks, from the oldest to the most recently committed.
+    pub fn sampled_blocks(&self) -> Vec<BlockOutputDigest> {
+        self.inner
+            .lock()
+            .as_ref()
+            .map(|inner| inner.sampled_blocks.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Formats the digests of the sampled blocks. If a block id is given, the digests of the
+    /// individual transactions of that block are included.
+    pub fn report(&self, block_id: Option<HashValue>) -> String {
+        let mut report = String::new();
+        writeln!(
+            report,
+            "Sampled blocks (block_id, first_version, num_txns, digest):"
+        )
+        .unwrap();
+        for block in self.sampled_blocks() {
+            let first_version = block.txn_digests.first().map(|txn| txn.version);
+            writeln!(
+                report,
+                "{}, {:?}, {}, {}",
+                block.block_id.to_hex(),
+                first_version,
+                block.txn_digests.len(),
+                block.digest.to_hex()
+            )
+            .unwrap();
+
+            if Some(block.block_id) == block_id {
+                writeln!(report).unwrap();
+                writeln!(
+                    report,
+                    "Transactions (version, status, gas_used, write_set_hash):"
+                )
+                .unwrap();
+                for txn in &block.txn_digests {
+                    writeln!(
+                        report,
+                        "{}, {:?}, {}, {}",
+                        txn.version,
+                        txn.status,
+                        txn.gas_used,
+                        txn.write_set_hash.to_hex()
+                    )
+                    .unwrap();
+                }
+                writeln!(report).unwrap();
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn txn_info(gas_used: u64) -> TransactionInfo {
+        TransactionInfo::new(
+            HashValue::random(),
+            HashValue::sha3_256_of(b"write set"),
+            HashValue::random(),
+            None,
+            gas_used,
+            ExecutionStatus::Success,
+        )
+    }
+
+    fn sampled_block_id(byte: u8) -> HashValue {
+        let mut bytes = [byte; HashValue::LENGTH];
+        bytes[0] = 0;
+        HashValue::new(bytes)
+    }
+
+    #[test]
+    fn test_disabled() {
+        let audit_log = ExecutionAuditLog::default();
+        audit_log.record_block(sampled_block_id(1), 0, &[txn_info(1)]);
+        assert!(!audit_log.is_enabled());
+        assert!(audit_log.sampled_blocks().is_empty());
+    }
+
+    #[test]
+    fn test_sampling_by_block_id_prefix() {
+        let audit_log = ExecutionAuditLog::default();
+        audit_log.enable(8, 10);
+
+        audit_log.record_block(HashValue::new([0xFF; HashValue::LENGTH]), 0, &[txn_info(1)]);
+        assert!(audit_log.sampled_blocks().is_empty());
+
+        audit_log.record_block(sampled_block_id(1), 10, &[txn_info(1), txn_info(2)]);
+        let sampled_blocks = audit_log.sampled_blocks();
+        assert_eq!(sampled_blocks.len(), 1);
+        assert_eq!(sampled_blocks[0].block_id, sampled_block_id(1));
+        let versions: Vec<_> = sampled_blocks[0]
+            .txn_digests
+            .iter()
+            .map(|txn| txn.version)
+            .collect();
+        assert_eq!(versions, vec![10, 11]);
+    }
+
+    #[test]
+    fn test_digests() {
+        let audit_log = ExecutionAuditLog::default();
+        audit_log.enable(0, 2);
+
+        // The digest only depends on the outputs, e.g., not on the transaction hashes.
+        audit_log.record_block(sampled_block_id(1), 0, &[txn_info(1)]);
+        audit_log.record_block(sampled_block_id(2), 0, &[txn_info(1)]);
+        let sampled_blocks = audit_log.sampled_blocks();
+        assert_eq!(sampled_blocks[0].digest, sampled_blocks[1].digest);
+
+        // Only the last blocks are kept.
+        audit_log.record_block(sampled_block_id(3), 0, &[txn_info(2)]);
+        let sampled_blocks = audit_log.sampled_blocks();
+        assert_eq!(sampled_blocks.len(), 2);
+        assert_eq!(sampled_blocks[0].block_id, sampled_block_id(2));
+        assert_eq!(sampled_blocks[1].block_id, sampled_block_id(3));
+        assert_ne!(sampled_blocks[0].digest, sampled_blocks[1].digest);
+
+        let report = audit_log.report(Some(sampled_block_id(3)));
+        assert!(report.contains(&sampled_blocks[1].digest.to_hex()));
+        assert!(report.contains(&format!(
+            "0, Success, 2, {}",
+            HashValue::sha3_256_of(b"write set").to_hex()
+        )));
+    }
+}
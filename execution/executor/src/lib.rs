@@ -17,3 +17,5 @@ pub mod block_executor;
 pub mod chunk_executor;
 pub mod components;
 pub mod db_bootstrapper;
+pub mod execution_audit_log;
+pub mod module_gas_stats;
@@ -190,6 +190,16 @@ pub static APTOS_PROCESSED_USER_TRANSACTIONS_ENTRY_FUNCTION_MODULE: Lazy<IntCoun
         .unwrap()
     });
 
+/// Counter of gas used by processed EntryFunction user transactions by module
+pub static APTOS_PROCESSED_USER_TRANSACTIONS_GAS_BY_MODULE: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_processed_user_transactions_gas_by_module",
+        "Counter of gas used by processed EntryFunction user transactions by module",
+        &["is_detailed", "process", "account", "name"]
+    )
+    .unwrap()
+});
+
 /// Counter of executed EntryFunction user transaction for core address by method
 pub static APTOS_PROCESSED_USER_TRANSACTIONS_ENTRY_FUNCTION_CORE_METHOD: Lazy<IntCounterVec> =
     Lazy::new(|| {
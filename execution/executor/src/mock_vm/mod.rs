@@ -260,7 +260,7 @@ fn decode_bytes(bytes: &[u8]) -> u64 {
     u64::from_le_bytes(buf)
 }
 
-fn balance_ap(account: AccountAddress) -> AccessPath {
+pub(crate) fn balance_ap(account: AccountAddress) -> AccessPath {
     AccessPath::new(account, b"balance".to_vec())
 }
 
@@ -0,0 +1,226 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Aggregates the gas used by user transactions per Move module, attributing the gas of each
+//! transaction to the module of the entry function it calls. Exposed through metrics and the
+//! admin service, so that operators can see which contracts consume the network's compute.
+
+use aptos_infallible::Mutex;
+use aptos_types::transaction::{
+    Multisig, MultisigTransactionPayload, Transaction, TransactionOutputProvider,
+    TransactionPayload, TransactionProvider,
+};
+use once_cell::sync::Lazy;
+use std::{collections::HashMap, fmt::Write};
+
+/// Maximum number of modules tracked cumulatively. Gas used by modules seen after the limit
+/// was reached is accounted under [`OTHER_MODULES`].
+const MAX_TRACKED_MODULES: usize = 10_000;
+
+/// Label for transactions not calling an entry function, i.e. scripts.
+pub const SCRIPTS: &str = "<scripts>";
+/// Label for multisig transactions whose payload is stored on chain.
+pub const MULTISIG: &str = "<multisig>";
+/// Label for modules that are not tracked individually.
+pub const OTHER_MODULES: &str = "<other>";
+
+pub static MODULE_GAS_STATS: Lazy<ModuleGasStats> = Lazy::new(ModuleGasStats::default);
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ModuleGasUsage {
+    pub num_txns: u64,
+    pub gas_used: u64,
+}
+
+impl ModuleGasUsage {
+    fn add(&mut self, gas_used: u64) {
+        self.num_txns += 1;
+        self.gas_used += gas_used;
+    }
+}
+
+#[derive(Default)]
+pub struct ModuleGasStats {
+    inner: Mutex<ModuleGasStatsInner>,
+}
+
+#[derive(Default)]
+struct ModuleGasStatsInner {
+    last_block: HashMap<String, ModuleGasUsage>,
+    cumulative: HashMap<String, ModuleGasUsage>,
+}
+
+impl ModuleGasStats {
+    /// Records the gas used by the user transactions of an executed block.
+    pub fn record_block<T, O>(&self, transactions: &[T], transaction_outputs: &[O])
+    where
+        T: TransactionProvider,
+        O: TransactionOutputProvider,
+    {
+        let mut last_block: HashMap<String, ModuleGasUsage> = HashMap::new();
+        for (txn, output) in transactions.iter().zip(transaction_outputs.iter()) {
+            if let Some(module) = txn.get_transaction().and_then(module_label) {
+                last_block
+                    .entry(module)
+                    .or_default()
+                    .add(output.get_transaction_output().gas_used());
+            }
+        }
+
+        let mut inner = self.inner.lock();
+        for (module, usage) in &last_block {
+            let module = if inner.cumulative.len() < MAX_TRACKED_MODULES
+                || inner.cumulative.contains_key(module)
+            {
+                module.as_str()
+            } else {
+                OTHER_MODULES
+            };
+            let cumulative = inner.cumulative.entry(module.to_string()).or_default();
+            cumulative.num_txns += usage.num_txns;
+            cumulative.gas_used += usage.gas_used;
+        }
+        inner.last_block = last_block;
+    }
+
+    /// Returns the gas usage per module of the last executed block, by decreasing gas used.
+    pub fn last_block(&self) -> Vec<(String, ModuleGasUsage)> {
+        sorted_by_gas_used(&self.inner.lock().last_block)
+    }
+
+    /// Returns the cumulative gas usage per module since the node started, by decreasing gas
+    /// used.
+    pub fn cumulative(&self) -> Vec<(String, ModuleGasUsage)> {
+        sorted_by_gas_used(&self.inner.lock().cumulative)
+    }
+
+    /// Formats the top modules by gas used, for the last block and cumulatively.
+    pub fn report(&self, top: usize) -> String {
+        let mut report = String::new();
+        for (title, stats) in [
+            ("Last block", self.last_block()),
+            ("Cumulative", self.cumulative()),
+        ] {
+            writeln!(report, "{} (module, num_txns, gas_used):", title).unwrap();
+            for (module, usage) in stats.into_iter().take(top) {
+                writeln!(report, "{}, {}, {}", module, usage.num_txns, usage.gas_used).unwrap();
+            }
+            writeln!(report).unwrap();
+        }
+        report
+    }
+}
+
+/// Returns the module the gas of a user transaction is attributed to.
+pub fn module_label(txn: &Transaction) -> Option<String> {
+    let user_txn = match txn {
+        Transaction::UserTransaction(user_txn) => user_txn,
+        _ => return None,
+    };
+    Some(match user_txn.payload() {
+        TransactionPayload::EntryFunction(entry_function) => {
+            entry_function.module().short_str_lossless()
+        },
+        TransactionPayload::Multisig(Multisig {
+            transaction_payload: Some(MultisigTransactionPayload::EntryFunction(entry_function)),
+            ..
+        }) => entry_function.module().short_str_lossless(),
+        // The payload of the multisig transaction is stored on chain.
+        TransactionPayload::Multisig(_) => MULTISIG.to_string(),
+        TransactionPayload::Script(_) | TransactionPayload::ModuleBundle(_) => SCRIPTS.to_string(),
+    })
+}
+
+fn sorted_by_gas_used(stats: &HashMap<String, ModuleGasUsage>) -> Vec<(String, ModuleGasUsage)> {
+    let mut stats: Vec<_> = stats
+        .iter()
+        .map(|(module, usage)| (module.clone(), *usage))
+        .collect();
+    stats.sort_by(|(_, a), (_, b)| b.gas_used.cmp(&a.gas_used));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+    use aptos_types::{
+        account_address::AccountAddress,
+        test_helpers::transaction_test_helpers::get_test_signed_txn,
+        transaction::{
+            EntryFunction, ExecutionStatus, TransactionAuxiliaryData, TransactionOutput,
+            TransactionStatus,
+        },
+        write_set::WriteSet,
+    };
+    use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+
+    fn entry_function_txn(module: &str) -> Transaction {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let payload = TransactionPayload::EntryFunction(EntryFunction::new(
+            ModuleId::new(AccountAddress::ONE, Identifier::new(module).unwrap()),
+            Identifier::new("f").unwrap(),
+            vec![],
+            vec![],
+        ));
+        Transaction::UserTransaction(get_test_signed_txn(
+            AccountAddress::random(),
+            0,
+            &private_key,
+            private_key.public_key(),
+            Some(payload),
+        ))
+    }
+
+    fn output(gas_used: u64) -> TransactionOutput {
+        TransactionOutput::new(
+            WriteSet::default(),
+            vec![],
+            gas_used,
+            TransactionStatus::Keep(ExecutionStatus::Success),
+            TransactionAuxiliaryData::default(),
+        )
+    }
+
+    #[test]
+    fn test_record_block() {
+        let stats = ModuleGasStats::default();
+        stats.record_block(
+            &[
+                entry_function_txn("coin"),
+                entry_function_txn("dex"),
+                entry_function_txn("dex"),
+            ],
+            &[output(10), output(20), output(30)],
+        );
+        assert_eq!(stats.last_block(), vec![
+            ("0x1::dex".to_string(), ModuleGasUsage {
+                num_txns: 2,
+                gas_used: 50
+            }),
+            ("0x1::coin".to_string(), ModuleGasUsage {
+                num_txns: 1,
+                gas_used: 10
+            }),
+        ]);
+
+        stats.record_block(&[entry_function_txn("coin")], &[output(100)]);
+        assert_eq!(stats.last_block(), vec![(
+            "0x1::coin".to_string(),
+            ModuleGasUsage {
+                num_txns: 1,
+                gas_used: 100
+            }
+        )]);
+        assert_eq!(stats.cumulative(), vec![
+            ("0x1::coin".to_string(), ModuleGasUsage {
+                num_txns: 2,
+                gas_used: 110
+            }),
+            ("0x1::dex".to_string(), ModuleGasUsage {
+                num_txns: 2,
+                gas_used: 50
+            }),
+        ]);
+    }
+}
@@ -7,8 +7,8 @@ use crate::{
     components::chunk_output::ChunkOutput,
     db_bootstrapper::{generate_waypoint, maybe_bootstrap},
     mock_vm::{
-        encode_mint_transaction, encode_reconfiguration_transaction, encode_transfer_transaction,
-        MockVM, DISCARD_STATUS, KEEP_STATUS,
+        balance_ap, encode_mint_transaction, encode_reconfiguration_transaction,
+        encode_transfer_transaction, MockVM, DISCARD_STATUS, KEEP_STATUS,
     },
 };
 use aptos_crypto::{ed25519::Ed25519PrivateKey, HashValue, PrivateKey, SigningKey, Uniform};
@@ -28,7 +28,7 @@ use aptos_types::{
     chain_id::ChainId,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     proof::definition::LeafCount,
-    state_store::{state_key::StateKey, state_value::StateValue, StateViewId},
+    state_store::{state_key::StateKey, state_value::StateValue, StateViewId, TStateView},
     test_helpers::transaction_test_helpers::{block, TEST_BLOCK_EXECUTOR_ONCHAIN_CONFIG},
     transaction::{
         signature_verified_transaction::SignatureVerifiedTransaction, ExecutionStatus,
@@ -223,6 +223,35 @@ fn test_executor_one_block() {
     executor.commit_blocks(vec![block_id], ledger_info).unwrap();
 }
 
+#[test]
+fn test_executor_state_view_of_uncommitted_block() {
+    let executor = TestExecutor::new();
+    let parent_block_id = executor.committed_block_id();
+    let block_id = gen_block_id(1);
+
+    let txn = encode_mint_transaction(gen_address(0), 100);
+    executor
+        .execute_block(
+            (block_id, block(vec![txn])).into(),
+            parent_block_id,
+            TEST_BLOCK_EXECUTOR_ONCHAIN_CONFIG,
+        )
+        .unwrap();
+
+    // The mint is only visible in the state after the block, which is not committed.
+    let balance_key = StateKey::access_path(balance_ap(gen_address(0)));
+    let read_balance = |block_id| {
+        executor
+            .state_view(block_id)
+            .unwrap()
+            .get_state_value_bytes(&balance_key)
+            .unwrap()
+    };
+    assert_eq!(read_balance(parent_block_id), None);
+    assert_eq!(read_balance(block_id), Some(100u64.le_bytes()));
+    assert!(executor.state_view(gen_block_id(2)).is_err());
+}
+
 #[test]
 fn test_executor_multiple_blocks() {
     let executor = TestExecutor::new();
@@ -119,6 +119,18 @@ impl Mempool {
             .reject_transaction(sender, sequence_number, hash);
     }
 
+    /// Removes a transaction that is not allowed by the transaction filter (anymore).
+    pub(crate) fn reject_filtered_transaction(
+        &mut self,
+        sender: &AccountAddress,
+        sequence_number: u64,
+        hash: &HashValue,
+    ) {
+        self.log_reject_transaction(sender, sequence_number, counters::REJECTED_BY_FILTER_LABEL);
+        self.transactions
+            .reject_transaction(sender, sequence_number, hash);
+    }
+
     pub(crate) fn log_txn_latency(
         insertion_info: &InsertionInfo,
         bucket: &str,
@@ -28,6 +28,7 @@ pub const COMMIT_ACCEPTED_BLOCK_LABEL: &str = "commit_accepted_block";
 pub const COMMIT_REJECTED_LABEL: &str = "commit_rejected";
 pub const COMMIT_REJECTED_DUPLICATE_LABEL: &str = "commit_rejected_duplicate";
 pub const COMMIT_IGNORED_LABEL: &str = "commit_ignored";
+pub const REJECTED_BY_FILTER_LABEL: &str = "rejected_by_filter";
 pub const CONSENSUS_READY_LABEL: &str = "consensus_ready";
 pub const CONSENSUS_PULLED_LABEL: &str = "consensus_pulled";
 pub const BROADCAST_READY_LABEL: &str = "broadcast_ready";
@@ -49,6 +50,10 @@ pub const GET_BLOCK_GET_BATCH_LABEL: &str = "get_block_get_batch";
 pub const COMMIT_STATE_SYNC_LABEL: &str = "commit_accepted";
 pub const COMMIT_CONSENSUS_LABEL: &str = "commit_rejected";
 
+// Transaction filter stage labels
+pub const TXN_FILTER_ADMISSION_LABEL: &str = "admission";
+pub const TXN_FILTER_CONSENSUS_PULL_LABEL: &str = "consensus_pull";
+
 // Mempool service request result labels
 pub const REQUEST_FAIL_LABEL: &str = "fail";
 pub const REQUEST_SUCCESS_LABEL: &str = "success";
@@ -516,6 +521,16 @@ pub static CORE_MEMPOOL_INVARIANT_VIOLATION_COUNT: Lazy<IntCounter> = Lazy::new(
     .unwrap()
 });
 
+/// Counter for transactions rejected by the transaction filter, by stage
+pub static TRANSACTION_FILTER_REJECTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_mempool_transaction_filter_rejected_count",
+        "Number of transactions rejected by the transaction filter",
+        &["stage"]
+    )
+    .unwrap()
+});
+
 pub static VM_RECONFIG_UPDATE_FAIL_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
         "aptos_mempool_vm_reconfig_update_fail_count",
@@ -173,6 +173,7 @@ pub enum LogEntry {
     DBError,
     UnexpectedNetworkMsg,
     MempoolSnapshot,
+    TransactionFilter,
 }
 
 #[derive(Clone, Copy, Serialize)]
@@ -195,5 +196,10 @@ pub enum LogEvent {
     SystemTTLExpiration,
     ClientExpiration,
 
+    // transaction filter events
+    Rejected,
+    Reloaded,
+    ReloadFail,
+
     Success,
 }
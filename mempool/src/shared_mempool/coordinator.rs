@@ -12,6 +12,7 @@ use crate::{
     shared_mempool::{
         tasks,
         tasks::process_committed_transactions,
+        transaction_filter::TransactionFilter,
         types::{notify_subscribers, ScheduledBroadcast, SharedMempool, SharedMempoolNotification},
     },
     MempoolEventsReceiver, QuorumStoreRequest,
@@ -397,6 +398,17 @@ pub(crate) async fn gc_coordinator(mempool: Arc<Mutex<CoreMempool>>, gc_interval
     ));
 }
 
+/// Periodically reloads the transaction filter from its file, if it was modified.
+pub(crate) async fn transaction_filter_reloader(
+    transaction_filter: Arc<TransactionFilter>,
+    reload_interval_ms: u64,
+) {
+    let mut interval = IntervalStream::new(interval(Duration::from_millis(reload_interval_ms)));
+    while let Some(_interval) = interval.next().await {
+        transaction_filter.maybe_reload();
+    }
+}
+
 /// Periodically logs a snapshot of transactions in core mempool.
 /// In the future we may want an interactive way to directly query mempool's internal state.
 /// For now, we will rely on this periodic snapshot to observe the internal state.
@@ -10,3 +10,4 @@ pub use runtime::bootstrap;
 pub(crate) use runtime::start_shared_mempool;
 mod coordinator;
 pub(crate) mod tasks;
+mod transaction_filter;
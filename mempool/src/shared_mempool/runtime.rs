@@ -6,7 +6,7 @@ use crate::{
     core_mempool::CoreMempool,
     network::MempoolSyncMsg,
     shared_mempool::{
-        coordinator::{coordinator, gc_coordinator, snapshot_job},
+        coordinator::{coordinator, gc_coordinator, snapshot_job, transaction_filter_reloader},
         types::{MempoolEventsReceiver, SharedMempool, SharedMempoolNotification},
     },
     QuorumStoreRequest,
@@ -32,6 +32,7 @@ use tokio::runtime::{Handle, Runtime};
 ///   - outbound_sync_task (task that periodically broadcasts transactions to peers).
 ///   - inbound_network_task (task that handles inbound mempool messages and network events).
 ///   - gc_task (task that performs GC of all expired transactions by SystemTTL).
+///   - transaction_filter_reloader (task that reloads the transaction filter file, if any).
 pub(crate) fn start_shared_mempool<TransactionValidator, ConfigProvider>(
     executor: &Handle,
     config: &NodeConfig,
@@ -61,6 +62,13 @@ pub(crate) fn start_shared_mempool<TransactionValidator, ConfigProvider>(
             config.base.role,
         );
 
+    if smp.transaction_filter.has_filter_file() {
+        executor.spawn(transaction_filter_reloader(
+            smp.transaction_filter.clone(),
+            config.mempool.transaction_filter_reload_interval_ms,
+        ));
+    }
+
     executor.spawn(coordinator(
         smp,
         executor.clone(),
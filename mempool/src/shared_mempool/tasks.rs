@@ -263,6 +263,33 @@ where
 {
     let mut statuses = vec![];
 
+    // Reject transactions that are not allowed by the transaction filter
+    let transactions: Vec<_> = transactions
+        .into_iter()
+        .filter_map(|t| {
+            if smp
+                .transaction_filter
+                .allows(&t, counters::TXN_FILTER_ADMISSION_LABEL)
+            {
+                Some(t)
+            } else {
+                statuses.push((
+                    t,
+                    (
+                        MempoolStatus::new(MempoolStatusCode::RejectedByFilter).with_message(
+                            "Transaction was rejected by the transaction filter".to_string(),
+                        ),
+                        None,
+                    ),
+                ));
+                None
+            }
+        })
+        .collect();
+    if transactions.is_empty() {
+        return statuses;
+    }
+
     let start_storage_read = Instant::now();
     let state_view = smp
         .db
@@ -503,13 +530,31 @@ pub(crate) fn process_quorum_store_request<NetworkClient, TransactionValidator>(
                     counters::GET_BLOCK_GET_BATCH_LABEL,
                     counters::REQUEST_SUCCESS_LABEL,
                 );
-                txns = mempool.get_batch(
+                let batch = mempool.get_batch(
                     max_txns,
                     max_bytes,
                     return_non_full,
                     include_gas_upgraded,
                     exclude_transactions,
                 );
+
+                // The transaction filter may have been updated since the transactions were
+                // admitted, so remove the ones that are not allowed (anymore) from the proposal
+                // and from mempool.
+                let (allowed_txns, filtered_txns): (Vec<_>, Vec<_>) =
+                    batch.into_iter().partition(|txn| {
+                        smp.transaction_filter
+                            .allows(txn, counters::TXN_FILTER_CONSENSUS_PULL_LABEL)
+                    });
+                for txn in filtered_txns {
+                    let (sender, sequence_number) = (txn.sender(), txn.sequence_number());
+                    mempool.reject_filtered_transaction(
+                        &sender,
+                        sequence_number,
+                        &txn.committed_hash(),
+                    );
+                }
+                txns = allowed_txns;
             }
 
             // mempool_service_transactions is logged inside get_batch
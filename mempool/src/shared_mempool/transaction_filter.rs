@@ -0,0 +1,173 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    logging::{LogEntry, LogEvent, LogSchema},
+};
+use aptos_config::config::{transaction_filter_type::Filter, MempoolConfig, PersistableConfig};
+use aptos_crypto::HashValue;
+use aptos_infallible::{Mutex, RwLock};
+use aptos_logger::prelude::*;
+use aptos_types::transaction::SignedTransaction;
+use std::{fs, path::PathBuf, time::SystemTime};
+
+/// The transaction filter applied on admission into mempool, and when pulling transactions
+/// for consensus proposals. When a filter file is configured, the filter is reloaded from it
+/// whenever the file changes, so that e.g. senders can be deny-listed during incident response
+/// without restarting the node.
+pub(crate) struct TransactionFilter {
+    filter: RwLock<Filter>,
+    filter_file: Option<PathBuf>,
+    last_modified: Mutex<Option<SystemTime>>,
+}
+
+impl TransactionFilter {
+    pub(crate) fn new(config: &MempoolConfig) -> Self {
+        let transaction_filter = Self {
+            filter: RwLock::new(config.transaction_filter.clone()),
+            filter_file: config.transaction_filter_file.clone(),
+            last_modified: Mutex::new(None),
+        };
+        transaction_filter.maybe_reload();
+        transaction_filter
+    }
+
+    /// Returns true iff the transaction is allowed by the filter. Rejections are counted under
+    /// the given stage label.
+    pub(crate) fn allows(&self, txn: &SignedTransaction, stage: &'static str) -> bool {
+        let filter = self.filter.read();
+        if filter.is_empty() {
+            return true;
+        }
+
+        // Block based rules are not supported, as transactions are not part of a block yet.
+        let timestamp_usecs = aptos_infallible::duration_since_epoch().as_micros() as u64;
+        let allowed = filter.allows(HashValue::zero(), timestamp_usecs, txn);
+        if !allowed {
+            counters::TRANSACTION_FILTER_REJECTED
+                .with_label_values(&[stage])
+                .inc();
+            sample!(
+                SampleRate::Duration(std::time::Duration::from_secs(1)),
+                info!(
+                    LogSchema::event_log(LogEntry::TransactionFilter, LogEvent::Rejected)
+                        .account(txn.sender()),
+                    sequence_number = txn.sequence_number(),
+                    stage = stage,
+                )
+            );
+        }
+        allowed
+    }
+
+    /// Reloads the filter from the filter file (if any) if the file was modified since it was
+    /// last loaded. If the file can't be read or parsed, the current filter is kept.
+    pub(crate) fn maybe_reload(&self) {
+        let filter_file = match &self.filter_file {
+            Some(filter_file) => filter_file,
+            None => return,
+        };
+
+        let modified = match fs::metadata(filter_file).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => modified,
+            Err(error) => {
+                error!(
+                    LogSchema::event_log(LogEntry::TransactionFilter, LogEvent::ReloadFail),
+                    "Failed to read the transaction filter file {:?}: {:?}", filter_file, error
+                );
+                return;
+            },
+        };
+        let mut last_modified = self.last_modified.lock();
+        if *last_modified == Some(modified) {
+            return;
+        }
+
+        match Filter::load_config(filter_file) {
+            Ok(filter) => {
+                info!(
+                    LogSchema::event_log(LogEntry::TransactionFilter, LogEvent::Reloaded),
+                    "Reloaded the transaction filter from {:?}: {:?}", filter_file, filter
+                );
+                *self.filter.write() = filter;
+                *last_modified = Some(modified);
+            },
+            Err(error) => {
+                error!(
+                    LogSchema::event_log(LogEntry::TransactionFilter, LogEvent::ReloadFail),
+                    "Failed to parse the transaction filter file {:?}: {:?}", filter_file, error
+                );
+            },
+        }
+    }
+
+    pub(crate) fn has_filter_file(&self) -> bool {
+        self.filter_file.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, SigningKey, Uniform};
+    use aptos_temppath::TempPath;
+    use aptos_types::{
+        account_address::AccountAddress,
+        chain_id::ChainId,
+        transaction::{RawTransaction, Script, TransactionPayload},
+    };
+
+    fn create_signed_transaction(sender: AccountAddress) -> SignedTransaction {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let raw_transaction = RawTransaction::new(
+            sender,
+            0,
+            TransactionPayload::Script(Script::new(vec![], vec![], vec![])),
+            0,
+            0,
+            0,
+            ChainId::test(),
+        );
+        SignedTransaction::new(
+            raw_transaction.clone(),
+            private_key.public_key(),
+            private_key.sign(&raw_transaction).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_reload_filter_file() {
+        let denied_sender = AccountAddress::random();
+        let txn = create_signed_transaction(denied_sender);
+
+        let filter_file = TempPath::new();
+        filter_file.create_as_file().unwrap();
+        Filter::empty().save_config(filter_file.path()).unwrap();
+
+        let config = MempoolConfig {
+            transaction_filter_file: Some(filter_file.path().to_path_buf()),
+            ..Default::default()
+        };
+        let transaction_filter = TransactionFilter::new(&config);
+        assert!(transaction_filter.allows(&txn, "test"));
+
+        // Update the filter file and verify the filter is reloaded
+        Filter::empty()
+            .add_deny_sender(denied_sender)
+            .save_config(filter_file.path())
+            .unwrap();
+        *transaction_filter.last_modified.lock() = None;
+        transaction_filter.maybe_reload();
+        assert!(!transaction_filter.allows(&txn, "test"));
+        assert!(
+            transaction_filter.allows(&create_signed_transaction(AccountAddress::random()), "test")
+        );
+
+        // An unparsable filter file keeps the current filter
+        fs::write(filter_file.path(), "not a filter").unwrap();
+        *transaction_filter.last_modified.lock() = None;
+        transaction_filter.maybe_reload();
+        assert!(!transaction_filter.allows(&txn, "test"));
+    }
+}
@@ -6,6 +6,7 @@
 use crate::{
     core_mempool::CoreMempool,
     network::{MempoolNetworkInterface, MempoolSyncMsg},
+    shared_mempool::transaction_filter::TransactionFilter,
 };
 use anyhow::Result;
 use aptos_config::{
@@ -52,6 +53,7 @@ pub(crate) struct SharedMempool<NetworkClient, TransactionValidator> {
     pub validator: Arc<RwLock<TransactionValidator>>,
     pub subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
     pub broadcast_within_validator_network: Arc<RwLock<bool>>,
+    pub transaction_filter: Arc<TransactionFilter>,
 }
 
 impl<
@@ -69,6 +71,7 @@ impl<
         role: RoleType,
     ) -> Self {
         let network_interface = MempoolNetworkInterface::new(network_client, role, config.clone());
+        let transaction_filter = Arc::new(TransactionFilter::new(&config));
         SharedMempool {
             mempool,
             config,
@@ -77,6 +80,7 @@ impl<
             validator,
             subscribers,
             broadcast_within_validator_network: Arc::new(RwLock::new(true)),
+            transaction_filter,
         }
     }
 
@@ -58,14 +58,23 @@ pub struct DriverConfiguration {
 
     // The trusted waypoint for the node
     pub waypoint: Waypoint,
+
+    // Whether the node starts as a hot standby (i.e., without consensus)
+    pub standby: bool,
 }
 
 impl DriverConfiguration {
-    pub fn new(config: StateSyncDriverConfig, role: RoleType, waypoint: Waypoint) -> Self {
+    pub fn new(
+        config: StateSyncDriverConfig,
+        role: RoleType,
+        waypoint: Waypoint,
+        standby: bool,
+    ) -> Self {
         Self {
             config,
             role,
             waypoint,
+            standby,
         }
     }
 }
@@ -109,6 +118,9 @@ pub struct StateSyncDriver<
     // The handler for notifications to mempool
     mempool_notification_handler: MempoolNotificationHandler<MempoolNotifier>,
 
+    // Whether the node is a standby (i.e., consensus is not running until promotion)
+    standby: bool,
+
     // The timestamp at which the driver started executing
     start_time: Option<Instant>,
 
@@ -178,6 +190,7 @@ impl<
             storage_synchronizer.clone(),
         );
 
+        let standby = driver_configuration.standby;
         Self {
             bootstrapper,
             client_notification_listener,
@@ -189,6 +202,7 @@ impl<
             error_notification_listener,
             event_subscription_service,
             mempool_notification_handler,
+            standby,
             start_time: None,
             storage,
             storage_service_notification_handler,
@@ -392,27 +406,82 @@ impl<
 
     /// Handles a client notification sent by the driver client
     async fn handle_client_notification(&mut self, notification: DriverNotification) {
-        debug!(LogSchema::new(LogEntry::ClientNotification)
-            .message("Received a notify bootstrap notification from the client!"));
         metrics::increment_counter(
             &metrics::DRIVER_COUNTERS,
             metrics::DRIVER_CLIENT_NOTIFICATION,
         );
 
-        // TODO(joshlind): refactor this if the client only supports one notification type!
-        // Extract the bootstrap notifier channel
-        let DriverNotification::NotifyOnceBootstrapped(notifier_channel) = notification;
+        match notification {
+            DriverNotification::NotifyOnceBootstrapped(notifier_channel) => {
+                debug!(LogSchema::new(LogEntry::ClientNotification)
+                    .message("Received a notify bootstrap notification from the client!"));
 
-        // Subscribe the bootstrap notifier channel
-        if let Err(error) = self
-            .bootstrapper
-            .subscribe_to_bootstrap_notifications(notifier_channel)
-            .await
-        {
-            warn!(LogSchema::new(LogEntry::ClientNotification)
-                .error(&error)
-                .message("Failed to subscribe to bootstrap notifications!"));
+                // Subscribe the bootstrap notifier channel
+                if let Err(error) = self
+                    .bootstrapper
+                    .subscribe_to_bootstrap_notifications(notifier_channel)
+                    .await
+                {
+                    warn!(LogSchema::new(LogEntry::ClientNotification)
+                        .error(&error)
+                        .message("Failed to subscribe to bootstrap notifications!"));
+                }
+            },
+            DriverNotification::PromoteStandby(notifier_channel) => {
+                info!(LogSchema::new(LogEntry::ClientNotification)
+                    .message("Received a promote standby notification from the client!"));
+
+                // Promote the standby and respond to the client
+                let result = self.promote_standby().await;
+                if let Err(error) = &result {
+                    warn!(LogSchema::new(LogEntry::ClientNotification)
+                        .error(error)
+                        .message("Failed to promote the standby!"));
+                }
+                if notifier_channel.send(result).is_err() {
+                    warn!(LogSchema::new(LogEntry::ClientNotification)
+                        .message("Failed to respond to the promote standby notification!"));
+                }
+            },
+        }
+    }
+
+    /// Promotes the standby, i.e., stops continuously syncing and hands control
+    /// of storage over to consensus. Consensus must only start once all synced
+    /// data has been committed, so we wait for the storage synchronizer to drain.
+    async fn promote_standby(&mut self) -> Result<(), Error> {
+        if !self.standby {
+            return Err(Error::NotStandby(
+                "The node has already been promoted (or was not started as a standby)!".into(),
+            ));
         }
+        if !self.bootstrapper.is_bootstrapped() {
+            return Err(Error::BootstrapNotComplete(
+                "The standby cannot be promoted before it has bootstrapped!".into(),
+            ));
+        }
+
+        // Terminate the active stream and wait for the storage synchronizer to drain
+        self.continuous_syncer.reset_active_stream(None).await?;
+        while self.storage_synchronizer.pending_storage_data() {
+            sample!(
+                SampleRate::Duration(Duration::from_secs(PENDING_DATA_LOG_FREQ_SECS)),
+                info!("Waiting for the storage synchronizer to handle pending data!")
+            );
+
+            // Yield to avoid starving the storage synchronizer threads.
+            yield_now().await;
+        }
+        self.storage_synchronizer.finish_chunk_executor(); // Consensus is now in control
+
+        // Consensus can now take over
+        self.standby = false;
+        let latest_synced_version = utils::fetch_latest_synced_version(self.storage.clone())?;
+        info!(LogSchema::new(LogEntry::Driver).message(&format!(
+            "The standby has been promoted! Latest synced version: {:?}",
+            latest_synced_version
+        )));
+        Ok(())
     }
 
     /// Handles a commit notification sent by the storage synchronizer for a
@@ -540,9 +609,13 @@ impl<
         self.driver_configuration.role == RoleType::Validator
     }
 
-    /// Returns true iff consensus is currently executing
+    /// Returns true iff consensus is currently executing (a standby
+    /// continuously syncs until it is promoted)
     fn check_if_consensus_executing(&self) -> bool {
-        self.is_validator() && self.bootstrapper.is_bootstrapped() && !self.active_sync_request()
+        self.is_validator()
+            && !self.standby
+            && self.bootstrapper.is_bootstrapped()
+            && !self.active_sync_request()
     }
 
     /// Checks if the connection deadline has passed. If so, validators with
@@ -17,6 +17,7 @@ use std::{
 /// Notifications that can be sent to the state sync driver
 pub enum DriverNotification {
     NotifyOnceBootstrapped(oneshot::Sender<Result<(), Error>>),
+    PromoteStandby(oneshot::Sender<Result<(), Error>>),
 }
 
 /// A client for sending notifications to the state sync driver
@@ -43,6 +44,21 @@ impl DriverClient {
             callback_receiver.await?
         }
     }
+
+    /// Notifies the driver that the standby node is being promoted, i.e., that
+    /// consensus is about to start. The driver stops syncing and responds once
+    /// all synced data has been committed to storage.
+    pub fn promote_standby(&self) -> impl Future<Output = Result<(), Error>> {
+        let mut notification_sender = self.notification_sender.clone();
+        let (callback_sender, callback_receiver) = oneshot::channel();
+
+        async move {
+            notification_sender
+                .send(DriverNotification::PromoteStandby(callback_sender))
+                .await?;
+            callback_receiver.await?
+        }
+    }
 }
 
 /// A simple listener for client notifications
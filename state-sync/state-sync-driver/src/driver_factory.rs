@@ -157,6 +157,7 @@ impl DriverFactory {
             node_config.state_sync.state_sync_driver,
             node_config.base.role,
             waypoint,
+            node_config.node_startup.standby,
         );
 
         // Create the state sync driver
@@ -229,4 +230,9 @@ impl StateSyncRuntimes {
         block_on(state_sync_client.notify_once_bootstrapped())
             .expect("State sync v2 initialization failure");
     }
+
+    /// Returns a new client that can be used to communicate with the driver
+    pub fn create_driver_client(&self) -> DriverClient {
+        self.state_sync.create_driver_client()
+    }
 }
@@ -29,6 +29,8 @@ pub enum Error {
     IntegerOverflow(String),
     #[error("An invalid payload was received: {0}")]
     InvalidPayload(String),
+    #[error("The node is not a standby: {0}")]
+    NotStandby(String),
     #[error("Failed to notify mempool of the new commit: {0}")]
     NotifyMempoolError(String),
     #[error("Failed to notify the storage service of the new commit: {0}")]
@@ -63,6 +65,7 @@ impl Error {
             Error::FullNodeConsensusNotification(_) => "full_node_consensus_notification",
             Error::IntegerOverflow(_) => "integer_overflow",
             Error::InvalidPayload(_) => "invalid_payload",
+            Error::NotStandby(_) => "not_standby",
             Error::NotifyMempoolError(_) => "notify_mempool_error",
             Error::NotifyStorageServiceError(_) => "notify_storage_service_error",
             Error::OldSyncRequest(_, _) => "old_sync_request",
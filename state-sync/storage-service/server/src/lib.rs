@@ -6,7 +6,8 @@
 
 use crate::{
     logging::{LogEntry, LogSchema},
-    network::StorageServiceNetworkEvents,
+    network::{NetworkRequest, StorageServiceNetworkEvents},
+    request_scheduler::{RequestPermit, RequestScheduler},
     subscription::SubscriptionStreamRequests,
 };
 use aptos_bounded_executor::BoundedExecutor;
@@ -26,7 +27,7 @@ use aptos_time_service::{TimeService, TimeServiceTrait};
 use arc_swap::ArcSwap;
 use dashmap::DashMap;
 use error::Error;
-use futures::stream::StreamExt;
+use futures::{future::FutureExt, stream::StreamExt};
 use handler::Handler;
 use mini_moka::sync::Cache;
 use moderator::RequestModerator;
@@ -43,6 +44,7 @@ pub mod metrics;
 mod moderator;
 pub mod network;
 mod optimistic_fetch;
+mod request_scheduler;
 pub mod storage;
 mod subscription;
 mod utils;
@@ -56,6 +58,9 @@ mod tests;
 // the next X-1 will execute with an unchanged version (thus, becoming a no-op and wasting the CPU).
 const CACHED_SUMMARY_UPDATE_CHANNEL_SIZE: usize = 1;
 
+// The interval (ms) at which to re-check the bandwidth quotas of peers with pending requests
+const PEER_QUOTA_REFRESH_INTERVAL_MS: u64 = 100;
+
 /// The server-side actor for the storage service. Handles inbound storage
 /// service requests from clients.
 pub struct StorageServiceServer<T> {
@@ -83,6 +88,9 @@ pub struct StorageServiceServer<T> {
     // A moderator for incoming peer requests
     request_moderator: Arc<RequestModerator>,
 
+    // A scheduler that enforces the per-peer quotas on incoming requests
+    request_scheduler: Arc<RequestScheduler>,
+
     // The listener for notifications from state sync
     storage_service_listener: Option<StorageServiceNotificationListener>,
 }
@@ -118,6 +126,10 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
             storage_service_config,
             time_service.clone(),
         ));
+        let request_scheduler = Arc::new(RequestScheduler::new(
+            storage_service_config,
+            time_service.clone(),
+        ));
         let storage_service_listener = Some(storage_service_listener);
 
         Self {
@@ -131,6 +143,7 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
             optimistic_fetches,
             subscriptions,
             request_moderator,
+            request_scheduler,
             storage_service_listener,
         }
     }
@@ -395,42 +408,80 @@ impl<T: StorageReaderInterface + Send + Sync> StorageServiceServer<T> {
         // Spawn the continuously running tasks
         self.spawn_continuous_storage_summary_tasks().await;
 
-        // Handle the storage requests as they arrive
-        while let Some(network_request) = self.network_requests.next().await {
-            // All handler methods are currently CPU-bound and synchronous
-            // I/O-bound, so we want to spawn on the blocking thread pool to
-            // avoid starving other async tasks on the same runtime.
-            let storage = self.storage.clone();
-            let config = self.storage_service_config;
-            let cached_storage_server_summary = self.cached_storage_server_summary.clone();
-            let optimistic_fetches = self.optimistic_fetches.clone();
-            let subscriptions = self.subscriptions.clone();
-            let lru_response_cache = self.lru_response_cache.clone();
-            let request_moderator = self.request_moderator.clone();
-            let time_service = self.time_service.clone();
-            self.bounded_executor
-                .spawn_blocking(move || {
-                    Handler::new(
-                        cached_storage_server_summary,
-                        optimistic_fetches,
-                        lru_response_cache,
-                        request_moderator,
-                        storage,
-                        subscriptions,
-                        time_service,
-                    )
-                    .process_request_and_respond(
-                        config,
-                        network_request.peer_network_id,
-                        network_request.protocol_id,
-                        network_request.storage_service_request,
-                        network_request.response_sender,
-                    );
-                })
-                .await;
+        // Create a ticker to re-check the bandwidth quotas of the peers
+        let duration = Duration::from_millis(PEER_QUOTA_REFRESH_INTERVAL_MS);
+        let ticker = self.time_service.interval(duration);
+        futures::pin_mut!(ticker);
+
+        // Handle the storage requests as they arrive (and the peer quotas allow)
+        loop {
+            // Process the pending requests of the peers that are within their quotas
+            while let Some((network_request, request_permit)) =
+                self.request_scheduler.next_request()
+            {
+                self.process_request(network_request, request_permit).await;
+            }
+
+            // Wait for a new request, or for a peer to be within its quotas again
+            let capacity_available = self.request_scheduler.wait_for_capacity().fuse();
+            futures::pin_mut!(capacity_available);
+            futures::select! {
+                network_request = self.network_requests.next().fuse() => {
+                    match network_request {
+                        Some(network_request) => {
+                            self.request_scheduler.enqueue_request(network_request)
+                        },
+                        None => break, // The network stream has terminated
+                    }
+                },
+                _ = capacity_available => {},
+                _ = ticker.select_next_some() => {},
+            }
         }
     }
 
+    /// Processes the given request on the blocking thread pool
+    async fn process_request(
+        &self,
+        network_request: NetworkRequest,
+        request_permit: RequestPermit,
+    ) {
+        // All handler methods are currently CPU-bound and synchronous
+        // I/O-bound, so we want to spawn on the blocking thread pool to
+        // avoid starving other async tasks on the same runtime.
+        let storage = self.storage.clone();
+        let config = self.storage_service_config;
+        let cached_storage_server_summary = self.cached_storage_server_summary.clone();
+        let optimistic_fetches = self.optimistic_fetches.clone();
+        let subscriptions = self.subscriptions.clone();
+        let lru_response_cache = self.lru_response_cache.clone();
+        let request_moderator = self.request_moderator.clone();
+        let time_service = self.time_service.clone();
+        self.bounded_executor
+            .spawn_blocking(move || {
+                Handler::new(
+                    cached_storage_server_summary,
+                    optimistic_fetches,
+                    lru_response_cache,
+                    request_moderator,
+                    storage,
+                    subscriptions,
+                    time_service,
+                )
+                .process_request_and_respond(
+                    config,
+                    network_request.peer_network_id,
+                    network_request.protocol_id,
+                    network_request.storage_service_request,
+                    network_request.response_sender,
+                );
+
+                // Release the concurrency slot of the peer
+                drop(request_permit);
+            })
+            .await;
+    }
+
     #[cfg(test)]
     /// Returns a copy of the request moderator for test purposes
     pub(crate) fn get_request_moderator(&self) -> Arc<RequestModerator> {
@@ -44,6 +44,7 @@ pub enum LogEntry {
     ReceivedStorageRequest,
     RequestModeratorIgnoredPeer,
     RequestModeratorRefresh,
+    RequestScheduler,
     SentStorageResponse,
     StorageServiceError,
     StorageSummaryRefresh,
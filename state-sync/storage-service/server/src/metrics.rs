@@ -15,6 +15,7 @@ pub const LRU_CACHE_HIT: &str = "lru_cache_hit";
 pub const LRU_CACHE_PROBE: &str = "lru_cache_probe";
 pub const OPTIMISTIC_FETCH_ADD: &str = "optimistic_fetch_add";
 pub const OPTIMISTIC_FETCH_EXPIRE: &str = "optimistic_fetch_expire";
+pub const REQUEST_DROPPED: &str = "request_dropped";
 pub const RESULT_SUCCESS: &str = "success";
 pub const RESULT_FAILURE: &str = "failure";
 pub const SUBSCRIPTION_ADD: &str = "subscription_add";
@@ -100,6 +101,16 @@ pub static PENDING_STORAGE_SERVER_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::ne
     .unwrap()
 });
 
+/// Counter for request scheduler events (e.g., requests dropped due to peer quotas)
+pub static REQUEST_SCHEDULER_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_storage_service_server_request_scheduler_event",
+        "Counters related to the request scheduler events",
+        &["network_id", "event"]
+    )
+    .unwrap()
+});
+
 /// Counter for storage service errors encountered
 pub static STORAGE_ERRORS_ENCOUNTERED: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
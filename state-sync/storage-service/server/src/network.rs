@@ -2,6 +2,7 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::request_scheduler::ResponseCharger;
 use aptos_config::network_id::{NetworkId, PeerNetworkId};
 use aptos_network::{
     application::interface::NetworkServiceEvents,
@@ -97,11 +98,20 @@ impl Stream for StorageServiceNetworkEvents {
 /// Provides a more strongly typed interface around the raw RPC response channel.
 pub struct ResponseSender {
     response_tx: oneshot::Sender<Result<Bytes, RpcError>>,
+    response_charger: Option<ResponseCharger>, // Charges the response to the peer quotas
 }
 
 impl ResponseSender {
     pub fn new(response_tx: oneshot::Sender<Result<Bytes, RpcError>>) -> Self {
-        Self { response_tx }
+        Self {
+            response_tx,
+            response_charger: None,
+        }
+    }
+
+    /// Sets the charger for the size of the response
+    pub(crate) fn set_response_charger(&mut self, response_charger: ResponseCharger) {
+        self.response_charger = Some(response_charger);
     }
 
     pub fn send(self, response: Result<StorageServiceResponse>) {
@@ -109,6 +119,9 @@ impl ResponseSender {
         let result = bcs::to_bytes(&msg)
             .map(Bytes::from)
             .map_err(RpcError::BcsError);
+        if let (Some(response_charger), Ok(bytes)) = (self.response_charger, &result) {
+            response_charger.charge_response(bytes.len() as u64);
+        }
         let _ = self.response_tx.send(result);
     }
 }
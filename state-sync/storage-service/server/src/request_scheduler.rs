@@ -0,0 +1,280 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    logging::{LogEntry, LogSchema},
+    metrics,
+    network::NetworkRequest,
+};
+use aptos_config::{config::StorageServiceConfig, network_id::PeerNetworkId};
+use aptos_infallible::Mutex;
+use aptos_logger::{sample, sample::SampleRate, warn};
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use std::{
+    cmp::min,
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::Notify;
+
+// The frequency to log dropped requests (secs)
+const DROPPED_REQUEST_LOG_FREQUENCY_SECS: u64 = 5;
+
+/// The quota and fair queuing state of a single peer
+struct PeerState {
+    pending_requests: VecDeque<NetworkRequest>, // The requests waiting to be processed
+    num_in_flight_requests: u64,                // The number of requests being processed
+    // The deficit counter (in bytes) for deficit round robin. Responses are only charged
+    // once they are sent, so the deficit becomes negative after large responses.
+    deficit_bytes: i64,
+    // The bytes the peer may still be sent before exceeding its bandwidth quota (i.e.,
+    // a token bucket). This becomes negative after a response larger than the budget.
+    available_bandwidth_bytes: i64,
+    last_refill_time: Instant, // The time the bandwidth budget was last refilled
+}
+
+impl PeerState {
+    fn new(max_bandwidth_bytes_per_sec: u64, time_service: &TimeService) -> Self {
+        Self {
+            pending_requests: VecDeque::new(),
+            num_in_flight_requests: 0,
+            deficit_bytes: 0,
+            available_bandwidth_bytes: max_bandwidth_bytes_per_sec as i64,
+            last_refill_time: time_service.now(),
+        }
+    }
+
+    /// Refills the bandwidth budget for the time elapsed since the last refill
+    fn refill_bandwidth(&mut self, now: Instant, max_bandwidth_bytes_per_sec: u64) {
+        let elapsed_secs = now
+            .saturating_duration_since(self.last_refill_time)
+            .as_secs_f64();
+        let refill_bytes = (elapsed_secs * max_bandwidth_bytes_per_sec as f64) as i64;
+        if refill_bytes > 0 {
+            self.available_bandwidth_bytes = min(
+                self.available_bandwidth_bytes.saturating_add(refill_bytes),
+                max_bandwidth_bytes_per_sec as i64,
+            );
+            self.last_refill_time = now;
+        }
+    }
+
+    /// Returns true iff the peer has not exhausted its concurrency or bandwidth quota
+    fn is_within_quotas(&self, max_concurrent_requests: u64) -> bool {
+        self.num_in_flight_requests < max_concurrent_requests && self.available_bandwidth_bytes > 0
+    }
+
+    /// Returns true iff the peer has no pending or in-flight requests, and a full bandwidth
+    /// budget (i.e., its state can be dropped without affecting its quotas).
+    fn is_idle(&self, max_bandwidth_bytes_per_sec: u64) -> bool {
+        self.pending_requests.is_empty()
+            && self.num_in_flight_requests == 0
+            && self.available_bandwidth_bytes >= max_bandwidth_bytes_per_sec as i64
+    }
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    peer_states: HashMap<PeerNetworkId, PeerState>,
+    active_peers: VecDeque<PeerNetworkId>, // The peers with pending requests (in round robin order)
+}
+
+/// The request scheduler enforces per-peer concurrency and bandwidth quotas on the
+/// inbound storage requests, and shares the server fairly across peers using deficit
+/// round robin (weighted by the size of the responses). This prevents a single (e.g.,
+/// aggressively syncing) peer from monopolizing the disk bandwidth of the node.
+pub struct RequestScheduler {
+    capacity_notifier: Notify,
+    state: Mutex<SchedulerState>,
+    storage_service_config: StorageServiceConfig,
+    time_service: TimeService,
+}
+
+impl RequestScheduler {
+    pub fn new(storage_service_config: StorageServiceConfig, time_service: TimeService) -> Self {
+        Self {
+            capacity_notifier: Notify::new(),
+            state: Mutex::new(SchedulerState::default()),
+            storage_service_config,
+            time_service,
+        }
+    }
+
+    /// Queues the given request until the peer that sent it may be served. If the
+    /// peer already has too many pending requests, the request is dropped.
+    pub fn enqueue_request(&self, network_request: NetworkRequest) {
+        let peer_network_id = network_request.peer_network_id;
+        let mut state = self.state.lock();
+        let SchedulerState {
+            peer_states,
+            active_peers,
+        } = &mut *state;
+
+        // Get (or create) the state for the peer
+        let peer_state = peer_states.entry(peer_network_id).or_insert_with(|| {
+            PeerState::new(
+                self.storage_service_config.max_peer_bandwidth_bytes_per_sec,
+                &self.time_service,
+            )
+        });
+
+        // Drop the request if the peer has too many pending requests
+        if peer_state.pending_requests.len() as u64
+            >= self.storage_service_config.max_pending_requests_per_peer
+        {
+            metrics::increment_counter(
+                &metrics::REQUEST_SCHEDULER_EVENTS,
+                peer_network_id.network_id(),
+                metrics::REQUEST_DROPPED.into(),
+            );
+            sample!(
+                SampleRate::Duration(Duration::from_secs(DROPPED_REQUEST_LOG_FREQUENCY_SECS)),
+                warn!(LogSchema::new(LogEntry::RequestScheduler)
+                    .peer_network_id(&peer_network_id)
+                    .message("Dropping request! The peer has too many pending requests."));
+            );
+            return;
+        }
+
+        // Queue the request and mark the peer as active
+        if peer_state.pending_requests.is_empty() {
+            active_peers.push_back(peer_network_id);
+        }
+        peer_state.pending_requests.push_back(network_request);
+    }
+
+    /// Returns the next request to process (together with the permit that holds the
+    /// concurrency slot of the peer), or None if no peer with pending requests is within
+    /// its quotas. Peers are served in deficit round robin order.
+    pub fn next_request(self: &Arc<Self>) -> Option<(NetworkRequest, RequestPermit)> {
+        let config = self.storage_service_config;
+        let now = self.time_service.now();
+        let mut state = self.state.lock();
+        let SchedulerState {
+            peer_states,
+            active_peers,
+        } = &mut *state;
+
+        // Refill the bandwidth budgets and drop the state of idle peers
+        peer_states.retain(|_, peer_state| {
+            peer_state.refill_bandwidth(now, config.max_peer_bandwidth_bytes_per_sec);
+            !peer_state.is_idle(config.max_peer_bandwidth_bytes_per_sec)
+        });
+
+        // Identify the active peers that are within their quotas
+        let eligible_peers: Vec<usize> = active_peers
+            .iter()
+            .enumerate()
+            .filter(|(_, peer_network_id)| {
+                peer_states[*peer_network_id]
+                    .is_within_quotas(config.max_concurrent_requests_per_peer)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        let max_deficit_bytes = eligible_peers
+            .iter()
+            .map(|index| peer_states[&active_peers[*index]].deficit_bytes)
+            .max()?;
+
+        // If no eligible peer has a positive deficit, run as many (empty) rounds
+        // as required for one of them to have it. Each round adds a quantum.
+        if max_deficit_bytes <= 0 {
+            let quantum_bytes = config.fair_queuing_quantum_bytes.max(1) as i64;
+            let num_rounds = (1 - max_deficit_bytes + quantum_bytes - 1) / quantum_bytes;
+            for index in &eligible_peers {
+                let peer_state = peer_states.get_mut(&active_peers[*index]).unwrap();
+                peer_state.deficit_bytes += num_rounds * quantum_bytes;
+            }
+        }
+
+        // Serve the first eligible peer (in round robin order) with a positive deficit
+        let index = eligible_peers
+            .into_iter()
+            .find(|index| peer_states[&active_peers[*index]].deficit_bytes > 0)?;
+        let peer_network_id = active_peers.remove(index)?;
+        let peer_state = peer_states.get_mut(&peer_network_id)?;
+        let mut network_request = peer_state.pending_requests.pop_front()?;
+        peer_state.num_in_flight_requests += 1;
+
+        // Move the peer to the back of the round, or reset any
+        // unused deficit if the peer has no more pending requests.
+        if peer_state.pending_requests.is_empty() {
+            peer_state.deficit_bytes = min(peer_state.deficit_bytes, 0);
+        } else {
+            active_peers.push_back(peer_network_id);
+        }
+
+        // Charge the response of the request to the peer
+        network_request
+            .response_sender
+            .set_response_charger(ResponseCharger {
+                peer_network_id,
+                request_scheduler: self.clone(),
+            });
+        let request_permit = RequestPermit {
+            peer_network_id,
+            request_scheduler: self.clone(),
+        };
+        Some((network_request, request_permit))
+    }
+
+    /// Waits until a peer frees up a concurrency slot
+    pub async fn wait_for_capacity(&self) {
+        self.capacity_notifier.notified().await
+    }
+
+    /// Charges the size of a response sent to the peer to its quotas
+    pub(crate) fn charge_response(&self, peer_network_id: &PeerNetworkId, num_bytes: u64) {
+        let mut state = self.state.lock();
+        let peer_state = state
+            .peer_states
+            .entry(*peer_network_id)
+            .or_insert_with(|| {
+                PeerState::new(
+                    self.storage_service_config.max_peer_bandwidth_bytes_per_sec,
+                    &self.time_service,
+                )
+            });
+        peer_state.deficit_bytes = peer_state.deficit_bytes.saturating_sub(num_bytes as i64);
+        peer_state.available_bandwidth_bytes = peer_state
+            .available_bandwidth_bytes
+            .saturating_sub(num_bytes as i64);
+    }
+
+    /// Releases the concurrency slot of a processed request
+    fn release_request(&self, peer_network_id: &PeerNetworkId) {
+        if let Some(peer_state) = self.state.lock().peer_states.get_mut(peer_network_id) {
+            peer_state.num_in_flight_requests = peer_state.num_in_flight_requests.saturating_sub(1);
+        }
+        self.capacity_notifier.notify_one();
+    }
+}
+
+/// Holds a concurrency slot of the peer until the request has been processed
+pub struct RequestPermit {
+    peer_network_id: PeerNetworkId,
+    request_scheduler: Arc<RequestScheduler>,
+}
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        self.request_scheduler
+            .release_request(&self.peer_network_id);
+    }
+}
+
+/// Charges the size of the response to a request to the quotas of the peer.
+/// Note: responses may be sent after the request permit has been dropped
+/// (e.g., for optimistic fetches and subscriptions).
+pub struct ResponseCharger {
+    peer_network_id: PeerNetworkId,
+    request_scheduler: Arc<RequestScheduler>,
+}
+
+impl ResponseCharger {
+    pub fn charge_response(self, num_bytes: u64) {
+        self.request_scheduler
+            .charge_response(&self.peer_network_id, num_bytes);
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    network::{NetworkRequest, ResponseSender},
+    request_scheduler::RequestScheduler,
+};
+use aptos_config::{config::StorageServiceConfig, network_id::PeerNetworkId};
+use aptos_network::ProtocolId;
+use aptos_storage_service_types::{
+    requests::{DataRequest, StorageServiceRequest},
+    StorageServiceError,
+};
+use aptos_time_service::TimeService;
+use futures::channel::oneshot;
+use std::sync::Arc;
+
+#[test]
+fn test_request_scheduler_concurrency_quota() {
+    // Create a request scheduler that allows a single in-flight request per peer
+    let storage_service_config = StorageServiceConfig {
+        max_concurrent_requests_per_peer: 1,
+        ..Default::default()
+    };
+    let request_scheduler = create_request_scheduler(storage_service_config, TimeService::mock());
+
+    // Enqueue several requests for the peer
+    let peer_network_id = PeerNetworkId::random();
+    for _ in 0..2 {
+        request_scheduler.enqueue_request(create_network_request(peer_network_id));
+    }
+
+    // Verify that only a single request is scheduled at a time
+    let (network_request, request_permit) = request_scheduler.next_request().unwrap();
+    assert_eq!(network_request.peer_network_id, peer_network_id);
+    assert!(request_scheduler.next_request().is_none());
+
+    // Release the permit and verify the next request is scheduled
+    drop(request_permit);
+    let (network_request, _) = request_scheduler.next_request().unwrap();
+    assert_eq!(network_request.peer_network_id, peer_network_id);
+    assert!(request_scheduler.next_request().is_none());
+}
+
+#[test]
+fn test_request_scheduler_pending_quota() {
+    // Create a request scheduler that allows few pending requests per peer
+    let max_pending_requests_per_peer = 3;
+    let storage_service_config = StorageServiceConfig {
+        max_pending_requests_per_peer,
+        ..Default::default()
+    };
+    let request_scheduler = create_request_scheduler(storage_service_config, TimeService::mock());
+
+    // Enqueue more requests than the peer is allowed to have pending
+    let peer_network_id = PeerNetworkId::random();
+    for _ in 0..max_pending_requests_per_peer * 2 {
+        request_scheduler.enqueue_request(create_network_request(peer_network_id));
+    }
+
+    // Verify that the excess requests were dropped
+    let mut request_permits = vec![];
+    while let Some((_, request_permit)) = request_scheduler.next_request() {
+        request_permits.push(request_permit);
+    }
+    assert_eq!(request_permits.len() as u64, max_pending_requests_per_peer);
+}
+
+#[test]
+fn test_request_scheduler_round_robin() {
+    // Create a request scheduler
+    let request_scheduler =
+        create_request_scheduler(StorageServiceConfig::default(), TimeService::mock());
+
+    // Enqueue several requests for each peer (one peer after the other)
+    let peer_network_ids = [PeerNetworkId::random(), PeerNetworkId::random()];
+    for peer_network_id in peer_network_ids {
+        for _ in 0..3 {
+            request_scheduler.enqueue_request(create_network_request(peer_network_id));
+        }
+    }
+
+    // Verify that the peers are served in round robin order
+    for _ in 0..3 {
+        for peer_network_id in peer_network_ids {
+            let (network_request, _) = request_scheduler.next_request().unwrap();
+            assert_eq!(network_request.peer_network_id, peer_network_id);
+        }
+    }
+    assert!(request_scheduler.next_request().is_none());
+}
+
+#[test]
+fn test_request_scheduler_response_deficit() {
+    // Create a request scheduler with a tiny quantum
+    let storage_service_config = StorageServiceConfig {
+        fair_queuing_quantum_bytes: 1,
+        ..Default::default()
+    };
+    let request_scheduler = create_request_scheduler(storage_service_config, TimeService::mock());
+
+    // Enqueue several requests for each peer
+    let heavy_peer = PeerNetworkId::random();
+    let light_peer = PeerNetworkId::random();
+    for peer_network_id in [heavy_peer, light_peer] {
+        for _ in 0..3 {
+            request_scheduler.enqueue_request(create_network_request(peer_network_id));
+        }
+    }
+
+    // Serve a large response to the heavy peer
+    let (network_request, _) = request_scheduler.next_request().unwrap();
+    assert_eq!(network_request.peer_network_id, heavy_peer);
+    send_response(network_request, 1000);
+
+    // Verify that the light peer is served until it catches up with the heavy peer
+    for _ in 0..3 {
+        let (network_request, _) = request_scheduler.next_request().unwrap();
+        assert_eq!(network_request.peer_network_id, light_peer);
+    }
+
+    // Verify the heavy peer is served again once the light peer is done
+    let (network_request, _) = request_scheduler.next_request().unwrap();
+    assert_eq!(network_request.peer_network_id, heavy_peer);
+}
+
+#[test]
+fn test_request_scheduler_bandwidth_quota() {
+    // Create a request scheduler with a low bandwidth quota
+    let storage_service_config = StorageServiceConfig {
+        max_peer_bandwidth_bytes_per_sec: 1000,
+        ..Default::default()
+    };
+    let time_service = TimeService::mock();
+    let request_scheduler = create_request_scheduler(storage_service_config, time_service.clone());
+
+    // Enqueue several requests for the peer
+    let peer_network_id = PeerNetworkId::random();
+    for _ in 0..2 {
+        request_scheduler.enqueue_request(create_network_request(peer_network_id));
+    }
+
+    // Serve a response that exceeds the bandwidth quota of the peer by ~1 second
+    let (network_request, _) = request_scheduler.next_request().unwrap();
+    send_response(network_request, 2000);
+
+    // Verify the peer is not served until its bandwidth budget is refilled
+    assert!(request_scheduler.next_request().is_none());
+    let time_service = time_service.into_mock();
+    time_service.advance_secs(1);
+    assert!(request_scheduler.next_request().is_none());
+    time_service.advance_secs(1);
+    let (network_request, _) = request_scheduler.next_request().unwrap();
+    assert_eq!(network_request.peer_network_id, peer_network_id);
+}
+
+/// Creates a request scheduler with the given config and time service
+fn create_request_scheduler(
+    storage_service_config: StorageServiceConfig,
+    time_service: TimeService,
+) -> Arc<RequestScheduler> {
+    Arc::new(RequestScheduler::new(storage_service_config, time_service))
+}
+
+/// Creates a network request for the given peer
+fn create_network_request(peer_network_id: PeerNetworkId) -> NetworkRequest {
+    let (response_tx, _) = oneshot::channel();
+    NetworkRequest {
+        peer_network_id,
+        protocol_id: ProtocolId::StorageServiceRpc,
+        storage_service_request: StorageServiceRequest::new(
+            DataRequest::GetServerProtocolVersion,
+            false,
+        ),
+        response_sender: ResponseSender::new(response_tx),
+    }
+}
+
+/// Sends a response (of at least the given size) for the network request
+fn send_response(network_request: NetworkRequest, num_bytes: usize) {
+    let error = StorageServiceError::InternalError("x".repeat(num_bytes));
+    network_request.response_sender.send(Err(error));
+}
@@ -56,6 +56,7 @@ impl AptosDB {
             ledger_commit_lock: std::sync::Mutex::new(()),
             indexer: None,
             skip_index_and_usage,
+            _migration_worker: None,
         }
     }
 
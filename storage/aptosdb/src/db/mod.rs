@@ -15,6 +15,10 @@ use crate::{
         API_LATENCY_SECONDS, COMMITTED_TXNS, LATEST_TXN_VERSION, LEDGER_VERSION, NEXT_BLOCK_EPOCH,
         OTHER_TIMERS_SECONDS,
     },
+    migration::{
+        get_storage_schema_version, put_storage_schema_version, registered_migrations,
+        MigrationWorker, LATEST_STORAGE_SCHEMA_VERSION,
+    },
     pruner::{LedgerPrunerManager, PrunerManager, StateKvPrunerManager, StateMerklePrunerManager},
     rocksdb_property_reporter::RocksdbPropertyReporter,
     schema::{
@@ -28,7 +32,8 @@ use crate::{
     utils::new_sharded_kv_schema_batch,
 };
 use aptos_config::config::{
-    PrunerConfig, RocksdbConfig, RocksdbConfigs, StorageDirPaths, NO_OP_STORAGE_PRUNER_CONFIG,
+    PrunerConfig, RocksdbConfig, RocksdbConfigs, StorageDirPaths, StorageMigrationConfig,
+    NO_OP_STORAGE_PRUNER_CONFIG,
 };
 use aptos_crypto::HashValue;
 use aptos_db_indexer::Indexer;
@@ -101,6 +106,7 @@ pub struct AptosDB {
     ledger_commit_lock: std::sync::Mutex<()>,
     indexer: Option<Indexer>,
     skip_index_and_usage: bool,
+    _migration_worker: Option<MigrationWorker>,
 }
 
 // DbReader implementations and private functions used by them.
@@ -179,6 +185,42 @@ impl AptosDB {
         Ok((ledger_db, state_merkle_db, state_kv_db))
     }
 
+    /// Starts migrating the storage format in the background if the DB is at an older storage
+    /// schema version. A newly created DB is stamped with the latest version instead.
+    pub fn start_storage_migrations(&mut self, config: StorageMigrationConfig) -> Result<()> {
+        let metadata_db = self.ledger_db.metadata_db_arc();
+        let schema_version = if metadata_db
+            .get::<DbMetadataSchema>(&DbMetadataKey::OverallCommitProgress)?
+            .is_none()
+            && metadata_db
+                .get::<DbMetadataSchema>(&DbMetadataKey::StorageSchemaVersion)?
+                .is_none()
+        {
+            put_storage_schema_version(&metadata_db, LATEST_STORAGE_SCHEMA_VERSION)?;
+            LATEST_STORAGE_SCHEMA_VERSION
+        } else {
+            get_storage_schema_version(&metadata_db)?
+        };
+        info!(
+            schema_version = schema_version,
+            latest_schema_version = LATEST_STORAGE_SCHEMA_VERSION,
+            "Opened AptosDB storage schema."
+        );
+
+        let pending_migrations: Vec<_> = registered_migrations()
+            .into_iter()
+            .filter(|migration| migration.target_schema_version() > schema_version)
+            .collect();
+        if config.enable && !pending_migrations.is_empty() {
+            self._migration_worker = Some(MigrationWorker::new(
+                metadata_db,
+                pending_migrations,
+                config,
+            ));
+        }
+        Ok(())
+    }
+
     /// Gets an instance of `BackupHandler` for data backup purpose.
     pub fn get_backup_handler(&self) -> BackupHandler {
         BackupHandler::new(Arc::clone(&self.state_store), Arc::clone(&self.ledger_db))
@@ -41,7 +41,7 @@ impl FastSyncStorageWrapper {
     /// If the db is empty and configured to do fast sync, we return a FastSyncStorageWrapper
     /// Otherwise, we returns AptosDB directly and the FastSyncStorageWrapper is None
     pub fn initialize_dbs(config: &NodeConfig) -> Result<Either<AptosDB, Self>> {
-        let db_main = AptosDB::open(
+        let mut db_main = AptosDB::open(
             config.storage.get_dir_paths(),
             /*readonly=*/ false,
             config.storage.storage_pruner_config,
@@ -51,6 +51,7 @@ impl FastSyncStorageWrapper {
             config.storage.max_num_nodes_per_lru_cache_shard,
         )
         .map_err(|err| anyhow!("fast sync DB failed to open {}", err))?;
+        db_main.start_storage_migrations(config.storage.storage_migration_config)?;
 
         let mut db_dir = config.storage.dir();
         // when the db is empty and configured to do fast sync, we will create a second DB
@@ -34,6 +34,7 @@ mod db_options;
 mod event_store;
 mod ledger_db;
 mod lru_node_cache;
+mod migration;
 mod pruner;
 mod state_kv_db;
 mod state_merkle_db;
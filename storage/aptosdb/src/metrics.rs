@@ -103,6 +103,19 @@ pub static PRUNER_BATCH_SIZE: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of items migrated so far by each ongoing storage migration.
+pub static STORAGE_MIGRATION_PROGRESS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        // metric name
+        "aptos_storage_migration_progress",
+        // metric description
+        "Number of items migrated by ongoing storage migrations",
+        // metric labels (dimensions)
+        &["migration_name"]
+    )
+    .unwrap()
+});
+
 pub static API_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         // metric name
@@ -0,0 +1,183 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    metrics::STORAGE_MIGRATION_PROGRESS,
+    migration::{get_storage_schema_version, MigrationProgress, StorageMigration},
+    schema::db_metadata::{DbMetadataKey, DbMetadataSchema, DbMetadataValue},
+};
+use aptos_config::config::StorageMigrationConfig;
+use aptos_logger::{
+    error, info,
+    prelude::{sample, SampleRate},
+};
+use aptos_schemadb::{SchemaBatch, DB};
+use aptos_storage_interface::{db_ensure as ensure, AptosDbError, Result};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{sleep, JoinHandle},
+    time::Duration,
+};
+
+const RETRY_INTERVAL_MS: u64 = 1000;
+
+/// Runs the pending storage migrations on a background thread, in order of their target schema
+/// versions. Stops early (and resumes on the next start) if dropped before they are done.
+pub(crate) struct MigrationWorker {
+    /// The thread to run migrations.
+    worker_thread: Option<JoinHandle<()>>,
+
+    inner: Arc<MigrationWorkerInner>,
+}
+
+struct MigrationWorkerInner {
+    /// The DB the storage schema version and migration progress are persisted in.
+    db: Arc<DB>,
+    migrations: Vec<Arc<dyn StorageMigration>>,
+    config: StorageMigrationConfig,
+    /// Indicates whether the worker should quit. Will only be set to true on worker destruction.
+    quit_worker: AtomicBool,
+}
+
+impl MigrationWorkerInner {
+    fn work(&self) {
+        for migration in &self.migrations {
+            while let Err(err) = self.run_migration(migration.as_ref()) {
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(1)),
+                    error!(
+                        migration = migration.name(),
+                        error = ?err,
+                        "Storage migration has error."
+                    )
+                );
+                if self.quit_worker.load(Ordering::SeqCst) {
+                    return;
+                }
+                sleep(Duration::from_millis(RETRY_INTERVAL_MS));
+            }
+            if self.quit_worker.load(Ordering::SeqCst) {
+                return;
+            }
+        }
+        info!("Storage migrations done.");
+    }
+
+    /// Runs the migration to completion, unless the worker is asked to quit.
+    fn run_migration(&self, migration: &dyn StorageMigration) -> Result<()> {
+        let target_version = migration.target_schema_version();
+        let current_version = get_storage_schema_version(&self.db)?;
+        if target_version <= current_version {
+            return Ok(());
+        }
+        ensure!(
+            target_version == current_version + 1,
+            "Storage migration {} targets schema version {}, but the DB is at {}.",
+            migration.name(),
+            target_version,
+            current_version,
+        );
+
+        let progress_key = DbMetadataKey::StorageMigrationProgress(target_version);
+        let mut progress = self.db.get::<DbMetadataSchema>(&progress_key)?.map_or_else(
+            MigrationProgress::default,
+            DbMetadataValue::expect_migration_progress,
+        );
+        info!(
+            migration = migration.name(),
+            num_migrated_items = progress.num_migrated_items,
+            "Running storage migration."
+        );
+
+        while !progress.data_migrated {
+            if self.quit_worker.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            let batch =
+                migration.migrate_batch(progress.cursor.as_deref(), self.config.batch_size)?;
+            progress.num_migrated_items += batch.num_items as u64;
+            progress.data_migrated = batch.next_cursor.is_none();
+            progress.cursor = batch.next_cursor;
+            self.db.put::<DbMetadataSchema>(
+                &progress_key,
+                &DbMetadataValue::MigrationProgress(progress.clone()),
+            )?;
+            STORAGE_MIGRATION_PROGRESS
+                .with_label_values(&[migration.name()])
+                .set(progress.num_migrated_items as i64);
+            sleep(Duration::from_millis(self.config.sleep_between_batches_ms));
+        }
+
+        migration.cutover()?;
+        let batch = SchemaBatch::new();
+        batch.put::<DbMetadataSchema>(
+            &DbMetadataKey::StorageSchemaVersion,
+            &DbMetadataValue::Version(target_version),
+        )?;
+        batch.delete::<DbMetadataSchema>(&progress_key)?;
+        self.db.write_schemas(batch)?;
+        info!(
+            migration = migration.name(),
+            schema_version = target_version,
+            num_migrated_items = progress.num_migrated_items,
+            "Storage migration cut over."
+        );
+        Ok(())
+    }
+
+    fn stop(&self) {
+        self.quit_worker.store(true, Ordering::SeqCst);
+    }
+}
+
+impl MigrationWorker {
+    pub(crate) fn new(
+        db: Arc<DB>,
+        mut migrations: Vec<Arc<dyn StorageMigration>>,
+        config: StorageMigrationConfig,
+    ) -> Self {
+        migrations.sort_by_key(|migration| migration.target_schema_version());
+        let inner = Arc::new(MigrationWorkerInner {
+            db,
+            migrations,
+            config,
+            quit_worker: AtomicBool::new(false),
+        });
+        let inner_cloned = Arc::clone(&inner);
+
+        let worker_thread = std::thread::Builder::new()
+            .name("storage_migration".into())
+            .spawn(move || inner_cloned.work())
+            .expect("Creating storage migration thread should succeed.");
+
+        Self {
+            worker_thread: Some(worker_thread),
+            inner,
+        }
+    }
+
+    /// Waits for all migrations to finish. Only meant for tests, as migrations that keep failing
+    /// never finish.
+    #[cfg(test)]
+    pub(crate) fn join(mut self) {
+        self.worker_thread
+            .take()
+            .expect("Storage migration thread must exist.")
+            .join()
+            .expect("Storage migration thread should join peacefully.");
+    }
+}
+
+impl Drop for MigrationWorker {
+    fn drop(&mut self) {
+        self.inner.stop();
+        if let Some(worker_thread) = self.worker_thread.take() {
+            worker_thread.join().unwrap_or_else(|e| {
+                panic!("Storage migration thread should join peacefully: {e:?}")
+            });
+        }
+    }
+}
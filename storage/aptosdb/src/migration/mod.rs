@@ -0,0 +1,82 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Background storage format migrations.
+//!
+//! Every [`StorageMigration`] brings the DB from the previous storage schema version to its
+//! target version while the node keeps running, instead of requiring an offline restore. Pending
+//! migrations run one at a time in the order of their target versions, on a background thread:
+//! the data is migrated in throttled batches and the resume cursor is persisted after each batch,
+//! so a restarted node picks up where it left off. Once all the data is migrated, the migration
+//! cuts over to the new format and the storage schema version is bumped.
+
+mod migration_worker;
+#[cfg(test)]
+mod test;
+
+use crate::schema::db_metadata::{DbMetadataKey, DbMetadataSchema, DbMetadataValue};
+use aptos_schemadb::DB;
+use aptos_storage_interface::Result;
+pub(crate) use migration_worker::MigrationWorker;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// The storage schema version a freshly created DB starts at, i.e. the highest target version of
+/// all registered migrations.
+pub(crate) const LATEST_STORAGE_SCHEMA_VERSION: u64 = 0;
+
+/// A migration of the storage format to `target_schema_version()`.
+pub trait StorageMigration: Send + Sync {
+    /// Name of the migration, used in logs and metrics.
+    fn name(&self) -> &'static str;
+
+    /// The storage schema version of the DB once this migration has cut over.
+    fn target_schema_version(&self) -> u64;
+
+    /// Migrates up to `batch_size` items starting at `cursor` (or at the beginning if `None`) and
+    /// returns where to resume from. A batch can be re-run after a crash, so it must be
+    /// idempotent. Data written in the old format while the migration is running is the
+    /// migration's responsibility, e.g. by writing both formats until cutover.
+    fn migrate_batch(&self, cursor: Option<&[u8]>, batch_size: usize) -> Result<MigrationBatch>;
+
+    /// Switches reads and writes over to the new format once all data is migrated. Can be re-run
+    /// after a crash, so it must be idempotent.
+    fn cutover(&self) -> Result<()>;
+}
+
+/// Result of migrating a batch.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MigrationBatch {
+    /// Where to resume from, or `None` if there is nothing left to migrate.
+    pub next_cursor: Option<Vec<u8>>,
+    /// Number of items migrated in this batch.
+    pub num_items: usize,
+}
+
+/// Progress of an ongoing migration, persisted after each batch.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(proptest_derive::Arbitrary))]
+pub struct MigrationProgress {
+    pub cursor: Option<Vec<u8>>,
+    pub num_migrated_items: u64,
+    /// All data is migrated, only the cutover is pending.
+    pub data_migrated: bool,
+}
+
+/// Migrations to run on AptosDB, ordered by target schema version.
+pub(crate) fn registered_migrations() -> Vec<Arc<dyn StorageMigration>> {
+    vec![]
+}
+
+pub(crate) fn get_storage_schema_version(db: &DB) -> Result<u64> {
+    Ok(db
+        .get::<DbMetadataSchema>(&DbMetadataKey::StorageSchemaVersion)?
+        .map_or(0, DbMetadataValue::expect_version))
+}
+
+pub(crate) fn put_storage_schema_version(db: &DB, version: u64) -> Result<()> {
+    Ok(db.put::<DbMetadataSchema>(
+        &DbMetadataKey::StorageSchemaVersion,
+        &DbMetadataValue::Version(version),
+    )?)
+}
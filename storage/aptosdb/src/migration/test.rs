@@ -0,0 +1,156 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::AptosDB;
+use aptos_config::config::StorageMigrationConfig;
+use aptos_temppath::TempPath;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+const CONFIG: StorageMigrationConfig = StorageMigrationConfig {
+    enable: true,
+    batch_size: 10,
+    sleep_between_batches_ms: 0,
+};
+
+/// Migrates the items `0..num_items`, using the next item as the cursor.
+struct TestMigration {
+    target_schema_version: u64,
+    num_items: u64,
+    batch_starts: Mutex<Vec<u64>>,
+    cut_over: AtomicBool,
+}
+
+impl TestMigration {
+    fn new(target_schema_version: u64, num_items: u64) -> Arc<Self> {
+        Arc::new(Self {
+            target_schema_version,
+            num_items,
+            batch_starts: Mutex::new(vec![]),
+            cut_over: AtomicBool::new(false),
+        })
+    }
+
+    fn batch_starts(&self) -> Vec<u64> {
+        self.batch_starts.lock().unwrap().clone()
+    }
+}
+
+impl StorageMigration for TestMigration {
+    fn name(&self) -> &'static str {
+        "test_migration"
+    }
+
+    fn target_schema_version(&self) -> u64 {
+        self.target_schema_version
+    }
+
+    fn migrate_batch(&self, cursor: Option<&[u8]>, batch_size: usize) -> Result<MigrationBatch> {
+        assert!(!self.cut_over.load(Ordering::SeqCst));
+        let start = cursor.map_or(0, |cursor| u64::from_be_bytes(cursor.try_into().unwrap()));
+        let end = std::cmp::min(start + batch_size as u64, self.num_items);
+        self.batch_starts.lock().unwrap().push(start);
+        Ok(MigrationBatch {
+            next_cursor: (end < self.num_items).then(|| end.to_be_bytes().to_vec()),
+            num_items: (end - start) as usize,
+        })
+    }
+
+    fn cutover(&self) -> Result<()> {
+        self.cut_over.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+fn get_progress(db: &DB, target_schema_version: u64) -> Option<MigrationProgress> {
+    db.get::<DbMetadataSchema>(&DbMetadataKey::StorageMigrationProgress(
+        target_schema_version,
+    ))
+    .unwrap()
+    .map(DbMetadataValue::expect_migration_progress)
+}
+
+#[test]
+fn test_migrations_run_in_order() {
+    let tmp_dir = TempPath::new();
+    let aptos_db = AptosDB::new_for_test(&tmp_dir);
+    let db = aptos_db.ledger_db.metadata_db_arc();
+    put_storage_schema_version(&db, 0).unwrap();
+
+    let first = TestMigration::new(1, 25);
+    let second = TestMigration::new(2, 5);
+    MigrationWorker::new(db.clone(), vec![second.clone(), first.clone()], CONFIG).join();
+
+    assert_eq!(first.batch_starts(), vec![0, 10, 20]);
+    assert!(first.cut_over.load(Ordering::SeqCst));
+    assert_eq!(second.batch_starts(), vec![0]);
+    assert!(second.cut_over.load(Ordering::SeqCst));
+    assert_eq!(get_storage_schema_version(&db).unwrap(), 2);
+    assert_eq!(get_progress(&db, 1), None);
+    assert_eq!(get_progress(&db, 2), None);
+}
+
+#[test]
+fn test_migration_resumes_from_progress() {
+    let tmp_dir = TempPath::new();
+    let aptos_db = AptosDB::new_for_test(&tmp_dir);
+    let db = aptos_db.ledger_db.metadata_db_arc();
+    put_storage_schema_version(&db, 0).unwrap();
+    db.put::<DbMetadataSchema>(
+        &DbMetadataKey::StorageMigrationProgress(1),
+        &DbMetadataValue::MigrationProgress(MigrationProgress {
+            cursor: Some(20u64.to_be_bytes().to_vec()),
+            num_migrated_items: 20,
+            data_migrated: false,
+        }),
+    )
+    .unwrap();
+
+    let migration = TestMigration::new(1, 25);
+    MigrationWorker::new(db.clone(), vec![migration.clone()], CONFIG).join();
+
+    assert_eq!(migration.batch_starts(), vec![20]);
+    assert!(migration.cut_over.load(Ordering::SeqCst));
+    assert_eq!(get_storage_schema_version(&db).unwrap(), 1);
+}
+
+#[test]
+fn test_cutover_resumes_after_data_migrated() {
+    let tmp_dir = TempPath::new();
+    let aptos_db = AptosDB::new_for_test(&tmp_dir);
+    let db = aptos_db.ledger_db.metadata_db_arc();
+    put_storage_schema_version(&db, 0).unwrap();
+    db.put::<DbMetadataSchema>(
+        &DbMetadataKey::StorageMigrationProgress(1),
+        &DbMetadataValue::MigrationProgress(MigrationProgress {
+            cursor: None,
+            num_migrated_items: 25,
+            data_migrated: true,
+        }),
+    )
+    .unwrap();
+
+    let migration = TestMigration::new(1, 25);
+    MigrationWorker::new(db.clone(), vec![migration.clone()], CONFIG).join();
+
+    assert!(migration.batch_starts().is_empty());
+    assert!(migration.cut_over.load(Ordering::SeqCst));
+    assert_eq!(get_storage_schema_version(&db).unwrap(), 1);
+}
+
+#[test]
+fn test_applied_migration_is_skipped() {
+    let tmp_dir = TempPath::new();
+    let aptos_db = AptosDB::new_for_test(&tmp_dir);
+    let db = aptos_db.ledger_db.metadata_db_arc();
+    put_storage_schema_version(&db, 1).unwrap();
+
+    let migration = TestMigration::new(1, 25);
+    MigrationWorker::new(db.clone(), vec![migration.clone()], CONFIG).join();
+
+    assert!(migration.batch_starts().is_empty());
+    assert!(!migration.cut_over.load(Ordering::SeqCst));
+}
@@ -9,7 +9,9 @@
 //! ```
 //!
 
-use crate::{schema::DB_METADATA_CF_NAME, state_restore::StateSnapshotProgress};
+use crate::{
+    migration::MigrationProgress, schema::DB_METADATA_CF_NAME, state_restore::StateSnapshotProgress,
+};
 use anyhow::Result;
 use aptos_schemadb::{
     define_schema,
@@ -25,6 +27,7 @@ type ShardId = usize;
 pub(crate) enum DbMetadataValue {
     Version(Version),
     StateSnapshotProgress(StateSnapshotProgress),
+    MigrationProgress(MigrationProgress),
 }
 
 impl DbMetadataValue {
@@ -41,6 +44,13 @@ impl DbMetadataValue {
             _ => unreachable!("expected KeyHashAndUsage, got {:?}", self),
         }
     }
+
+    pub fn expect_migration_progress(self) -> MigrationProgress {
+        match self {
+            Self::MigrationProgress(progress) => progress,
+            _ => unreachable!("expected MigrationProgress, got {:?}", self),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -67,6 +77,8 @@ pub enum DbMetadataKey {
     StateKvShardPrunerProgress(ShardId),
     StateMerkleShardRestoreProgress(ShardId, Version),
     TransactionAuxiliaryDataPrunerProgress,
+    StorageSchemaVersion,
+    StorageMigrationProgress(u64),
 }
 
 define_schema!(
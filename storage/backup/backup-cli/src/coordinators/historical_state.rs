@@ -0,0 +1,242 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reconstructs state at an arbitrary version from backups, by combining the nearest state
+//! snapshot at or before that version with the write sets of the transactions in between.
+//!
+//! This is a slow path: every query downloads (part of) a state snapshot and the transaction
+//! chunks since, so it's meant for occasional deep-history queries that a pruned node can no
+//! longer answer from its own DB.
+
+use crate::{
+    backup_types::{
+        state_snapshot::manifest::{StateSnapshotBackup, StateSnapshotChunk},
+        transaction::{manifest::TransactionBackup, restore::LoadedChunk},
+    },
+    metadata,
+    metadata::{cache::MetadataCacheOpt, view::MetadataView},
+    storage::{BackupStorage, FileHandle},
+    utils::{read_record_bytes::ReadRecordBytes, storage_ext::BackupStorageExt, stream::StreamX},
+};
+use anyhow::{anyhow, ensure, Result};
+use aptos_crypto::hash::CryptoHash;
+use aptos_logger::prelude::*;
+use aptos_types::{
+    account_address::AccountAddress,
+    state_store::{
+        state_key::{StateKey, StateKeyInner},
+        state_value::StateValue,
+    },
+    transaction::Version,
+    write_set::TransactionWrite,
+};
+use futures::{stream, TryStreamExt};
+use itertools::multizip;
+use std::{collections::BTreeMap, sync::Arc};
+use tokio::io::BufReader;
+
+pub struct HistoricalStateReader {
+    storage: Arc<dyn BackupStorage>,
+    metadata_view: MetadataView,
+    concurrent_downloads: usize,
+}
+
+impl HistoricalStateReader {
+    pub async fn new(
+        storage: Arc<dyn BackupStorage>,
+        metadata_cache_opt: &MetadataCacheOpt,
+        concurrent_downloads: usize,
+    ) -> Result<Self> {
+        let metadata_view = metadata::cache::sync_and_load(
+            metadata_cache_opt,
+            Arc::clone(&storage),
+            concurrent_downloads,
+        )
+        .await?;
+
+        Ok(Self {
+            storage,
+            metadata_view,
+            concurrent_downloads,
+        })
+    }
+
+    /// Returns the value of `state_key` at `version`, or `None` if it didn't exist then.
+    pub async fn get_state_value(
+        &self,
+        state_key: &StateKey,
+        version: Version,
+    ) -> Result<Option<StateValue>> {
+        let key_hash = state_key.hash();
+        let mut state = self
+            .reconstruct(
+                version,
+                |chunk| chunk.first_key <= key_hash && key_hash <= chunk.last_key,
+                |key| key == state_key,
+            )
+            .await?;
+
+        Ok(state.remove(state_key))
+    }
+
+    /// Returns all the resources, resource groups and modules under `address` at `version`.
+    ///
+    /// Snapshot chunks are ordered by key hash rather than by account, so this reads the whole
+    /// state snapshot.
+    pub async fn get_account_state(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<BTreeMap<StateKey, StateValue>> {
+        self.reconstruct(
+            version,
+            |_chunk| true,
+            |key| matches!(key.inner(), StateKeyInner::AccessPath(path) if path.address == address),
+        )
+        .await
+    }
+
+    async fn reconstruct(
+        &self,
+        version: Version,
+        chunk_filter: impl Fn(&StateSnapshotChunk) -> bool,
+        key_filter: impl Fn(&StateKey) -> bool,
+    ) -> Result<BTreeMap<StateKey, StateValue>> {
+        let max_txn_version = self
+            .metadata_view
+            .max_transaction_version()?
+            .ok_or_else(|| anyhow!("No transaction backup found."))?;
+        ensure!(
+            version <= max_txn_version,
+            "Version {} is newer than the latest transaction backed up: {}.",
+            version,
+            max_txn_version,
+        );
+        let snapshot = self
+            .metadata_view
+            .select_state_snapshot(version)?
+            .ok_or_else(|| anyhow!("No state snapshot found at or before version {}.", version))?;
+        info!(
+            version = version,
+            snapshot_version = snapshot.version,
+            "Reconstructing historical state."
+        );
+
+        let mut state = self
+            .read_state_snapshot(&snapshot.manifest, chunk_filter, &key_filter)
+            .await?;
+        if version > snapshot.version {
+            self.replay_write_sets(&mut state, snapshot.version + 1, version, &key_filter)
+                .await?;
+        }
+
+        Ok(state)
+    }
+
+    async fn read_state_snapshot(
+        &self,
+        manifest_handle: &FileHandle,
+        chunk_filter: impl Fn(&StateSnapshotChunk) -> bool,
+        key_filter: &impl Fn(&StateKey) -> bool,
+    ) -> Result<BTreeMap<StateKey, StateValue>> {
+        let manifest: StateSnapshotBackup = self.storage.load_json_file(manifest_handle).await?;
+
+        let futs_iter = manifest
+            .chunks
+            .into_iter()
+            .filter(|chunk| chunk_filter(chunk))
+            .map(|chunk| {
+                let storage = self.storage.clone();
+                async move {
+                    tokio::spawn(async move {
+                        let mut file = BufReader::new(storage.open_for_read(&chunk.blobs).await?);
+                        let mut blobs = Vec::new();
+                        while let Some(record_bytes) = file.read_record_bytes().await? {
+                            blobs.push(bcs::from_bytes::<(StateKey, StateValue)>(&record_bytes)?);
+                        }
+                        Result::<_>::Ok(blobs)
+                    })
+                    .await?
+                }
+            });
+        let con = self.concurrent_downloads;
+        let mut futs_stream = stream::iter(futs_iter).buffered_x(con * 2, con);
+
+        let mut state = BTreeMap::new();
+        while let Some(blobs) = futs_stream.try_next().await? {
+            state.extend(blobs.into_iter().filter(|(key, _value)| key_filter(key)));
+        }
+        Ok(state)
+    }
+
+    async fn replay_write_sets(
+        &self,
+        state: &mut BTreeMap<StateKey, StateValue>,
+        first_version: Version,
+        last_version: Version,
+        key_filter: &impl Fn(&StateKey) -> bool,
+    ) -> Result<()> {
+        let mut chunks = Vec::new();
+        for backup in self
+            .metadata_view
+            .select_transaction_backups(first_version, last_version)?
+        {
+            let manifest: TransactionBackup = self.storage.load_json_file(&backup.manifest).await?;
+            manifest.verify()?;
+            chunks.extend(manifest.chunks.into_iter().filter(|chunk| {
+                chunk.last_version >= first_version && chunk.first_version <= last_version
+            }));
+        }
+
+        let futs_iter = chunks.into_iter().map(|chunk| {
+            let storage = self.storage.clone();
+            // The transactions are verified against the ledger info in the chunk, but the ledger
+            // info itself is not verified against the epoch history.
+            async move {
+                tokio::spawn(async move { LoadedChunk::load(chunk, &storage, None).await }).await?
+            }
+        });
+        let con = self.concurrent_downloads;
+        let mut futs_stream = stream::iter(futs_iter).buffered_x(con * 2, con);
+
+        let mut next_version = first_version;
+        while let Some(chunk) = futs_stream.try_next().await? {
+            let mut version = chunk.manifest.first_version;
+            let (_txns, txn_infos, _event_vecs, write_sets) = chunk.unpack();
+            for (txn_info, write_set) in multizip((txn_infos, write_sets)) {
+                if version >= first_version && version <= last_version {
+                    ensure!(
+                        version == next_version,
+                        "Transactions not continuous, expecting version {}, got {}.",
+                        next_version,
+                        version,
+                    );
+                    ensure!(
+                        CryptoHash::hash(&write_set) == txn_info.state_change_hash(),
+                        "Write set hash mismatch at version {}.",
+                        version,
+                    );
+                    for (key, write_op) in write_set.iter() {
+                        if !key_filter(key) {
+                            continue;
+                        }
+                        match write_op.as_state_value() {
+                            Some(value) => state.insert(key.clone(), value),
+                            None => state.remove(key),
+                        };
+                    }
+                    next_version += 1;
+                }
+                version += 1;
+            }
+        }
+        ensure!(
+            next_version == last_version + 1,
+            "Transactions missing from backups, replayed up to version {}, requested {}.",
+            next_version.saturating_sub(1),
+            last_version,
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use aptos_backup_cli::{
+    coordinators::historical_state::HistoricalStateReader, metadata::cache::MetadataCacheOpt,
+    storage::DBToolStorageOpt, utils::ConcurrentDownloadsOpt,
+};
+use aptos_types::{account_address::AccountAddress, transaction::Version};
+use clap::Parser;
+
+/// Reconstruct the state of an account at an old version from backups, by replaying the write
+/// sets since the nearest state snapshot. Slow, but works for versions pruned from the DB.
+#[derive(Parser)]
+pub struct Opt {
+    #[clap(flatten)]
+    metadata_cache_opt: MetadataCacheOpt,
+    #[clap(flatten)]
+    storage: DBToolStorageOpt,
+    #[clap(flatten)]
+    concurrent_downloads: ConcurrentDownloadsOpt,
+    #[clap(long)]
+    address: AccountAddress,
+    #[clap(long)]
+    version: Version,
+}
+
+impl Opt {
+    pub async fn run(self) -> Result<()> {
+        let reader = HistoricalStateReader::new(
+            self.storage.init_storage().await?,
+            &self.metadata_cache_opt,
+            self.concurrent_downloads.get(),
+        )
+        .await?;
+
+        let state = reader.get_account_state(self.address, self.version).await?;
+        for (key, value) in state {
+            println!("{:?}: {:?}", key, value);
+        }
+        Ok(())
+    }
+}
@@ -47,6 +47,16 @@ fn test_various_cmd_parsing() {
     ]);
 
     run_cmd(&["aptos-db-tool", "backup", "verify", "--local-fs-dir", "."]);
+    run_cmd(&[
+        "aptos-db-tool",
+        "historical-state",
+        "--address",
+        "0x1",
+        "--version",
+        "100",
+        "--local-fs-dir",
+        ".",
+    ]);
     run_cmd(&[
         "aptos-db-tool",
         "replay-verify",
@@ -74,7 +84,7 @@ fn run_cmd(args: &[&str]) {
 mod dbtool_tests {
     use crate::DBTool;
     use aptos_backup_cli::{
-        coordinators::backup::BackupCompactor,
+        coordinators::{backup::BackupCompactor, historical_state::HistoricalStateReader},
         metadata,
         metadata::{cache::MetadataCacheOpt, view::MetadataView},
         storage::{local_fs::LocalFs, BackupStorage},
@@ -88,6 +98,7 @@ mod dbtool_tests {
     use aptos_storage_interface::DbReader;
     use aptos_temppath::TempPath;
     use aptos_types::{
+        account_config::aptos_test_root_address,
         state_store::{state_key::StateKeyTag::AccessPath, state_key_prefix::StateKeyPrefix},
         transaction::Version,
     };
@@ -279,6 +290,87 @@ mod dbtool_tests {
         rt.shutdown_timeout(Duration::from_secs(1));
     }
 
+    #[test]
+    fn test_historical_state_reconstruction() {
+        let db = test_execution_with_storage_impl();
+        let backup_dir = TempPath::new();
+        backup_dir.create_as_dir().unwrap();
+        let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+        let (rt, port) = start_local_backup_service(Arc::clone(&db));
+        let server_addr = format!(" http://localhost:{}", port);
+
+        rt.block_on(
+            DBTool::try_parse_from([
+                "aptos-db-tool",
+                "backup",
+                "oneoff",
+                "--backup-service-address",
+                server_addr.as_str(),
+                "state-snapshot",
+                "--state-snapshot-epoch",
+                "1",
+                "--local-fs-dir",
+                backup_dir.path().to_str().unwrap(),
+            ])
+            .unwrap()
+            .run(),
+        )
+        .unwrap();
+        rt.block_on(
+            DBTool::try_parse_from([
+                "aptos-db-tool",
+                "backup",
+                "oneoff",
+                "--backup-service-address",
+                server_addr.as_str(),
+                "transaction",
+                "--start-version",
+                "0",
+                "--num_transactions",
+                "30",
+                "--local-fs-dir",
+                backup_dir.path().to_str().unwrap(),
+            ])
+            .unwrap()
+            .run(),
+        )
+        .unwrap();
+
+        let metadata_cache_dir = TempPath::new();
+        let metadata_opt = MetadataCacheOpt::new(Some(metadata_cache_dir.path().to_path_buf()));
+        let reader = rt
+            .block_on(HistoricalStateReader::new(store, &metadata_opt, 1))
+            .unwrap();
+
+        // The state at the version is the snapshot with the write sets since replayed on top.
+        let address = aptos_test_root_address();
+        let version = 29;
+        let state = rt
+            .block_on(reader.get_account_state(address, version))
+            .unwrap();
+        let expected: Vec<_> = db
+            .get_prefixed_state_value_iterator(&StateKeyPrefix::from(address), None, version)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(!expected.is_empty());
+        assert_eq!(state.into_iter().collect::<Vec<_>>(), expected);
+
+        let (state_key, value) = expected.first().unwrap();
+        assert_eq!(
+            rt.block_on(reader.get_state_value(state_key, version))
+                .unwrap()
+                .as_ref(),
+            Some(value),
+        );
+
+        // Versions not covered by transaction backups can't be reconstructed.
+        assert!(rt
+            .block_on(reader.get_account_state(address, version + 1))
+            .is_err());
+        rt.shutdown_timeout(Duration::from_secs(1));
+    }
+
     #[cfg(test)]
     fn db_restore_test_setup(
         start: Version,
@@ -41,6 +41,7 @@ use aptos_testcases::{
     modifiers::{CpuChaosTest, ExecutionDelayConfig, ExecutionDelayTest},
     multi_region_network_test::{
         MultiRegionNetworkEmulationConfig, MultiRegionNetworkEmulationTest,
+        MultiRegionPerformanceTest,
     },
     network_bandwidth_test::NetworkBandwidthTest,
     network_loss_test::NetworkLossTest,
@@ -711,6 +712,12 @@ fn get_multi_region_test(test_name: &str) -> Option<ForgeConfig> {
         "three_region_simulation_with_different_node_speed" => {
             three_region_simulation_with_different_node_speed()
         },
+        "multi_region_performance_two_region" => {
+            multi_region_performance_test(MultiRegionPerformanceTest::two_region())
+        },
+        "multi_region_performance_four_region" => {
+            multi_region_performance_test(MultiRegionPerformanceTest::four_region())
+        },
         _ => return None, // The test name does not match a multi-region test
     };
     Some(test)
@@ -2380,6 +2387,34 @@ fn multiregion_benchmark_test() -> ForgeConfig {
         )
 }
 
+/// Runs the standardized max load performance suite with validators assigned to the synthetic
+/// regions of the given topology, reporting consensus round times and committed throughput.
+fn multi_region_performance_test(test: MultiRegionPerformanceTest) -> ForgeConfig {
+    ForgeConfig::default()
+        .with_initial_validator_count(NonZeroUsize::new(20).unwrap())
+        .with_initial_fullnode_count(10)
+        .add_network_test(test)
+        .with_emit_job(EmitJobRequest::default().mode(EmitJobMode::MaxLoad {
+            mempool_backlog: 40000,
+        }))
+        .with_genesis_helm_config_fn(Arc::new(|helm_values| {
+            // Have single epoch change in land blocking
+            helm_values["chain"]["epoch_duration_secs"] = 300.into();
+        }))
+        .with_success_criteria(
+            SuccessCriteria::new(4500)
+                .add_no_restarts()
+                .add_wait_for_catchup_s(
+                    // Give at least 60s for catchup, give 10% of the run for longer durations.
+                    180,
+                )
+                .add_chain_progress(StateProgressThreshold {
+                    max_no_progress_secs: 10.0,
+                    max_round_gap: 4,
+                }),
+        )
+}
+
 /// This test runs a constant-TPS benchmark where the network includes
 /// PFNs, and the transactions are submitted to the PFNs. This is useful
 /// for measuring latencies when the system is not saturated.
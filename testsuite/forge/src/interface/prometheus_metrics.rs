@@ -190,3 +190,47 @@ pub async fn fetch_latency_breakdown(
 
     Ok(LatencyBreakdown::new(samples))
 }
+
+#[derive(Clone, Debug)]
+pub struct ConsensusPerformanceMetrics {
+    /// Time between consecutive committed rounds, in seconds.
+    pub round_time_secs: MetricSamples,
+    /// Committed transactions per second, as observed by the validators.
+    pub committed_tps: MetricSamples,
+}
+
+pub async fn fetch_consensus_performance_metrics(
+    swarm: &dyn Swarm,
+    start_time: u64,
+    end_time: u64,
+) -> anyhow::Result<ConsensusPerformanceMetrics> {
+    // Averaging over 1m, and skipping data points at the start that would take averages outside of the interval.
+    let start_time_adjusted = start_time + 60;
+    let round_time_query =
+        r#"quantile(0.67, 1 / rate(aptos_consensus_last_committed_round{role=~"validator"}[1m]))"#;
+    let committed_tps_query =
+        r#"quantile(0.67, rate(aptos_consensus_last_committed_version{role=~"validator"}[1m]))"#;
+
+    let round_time_samples = swarm
+        .query_range_metrics(
+            round_time_query,
+            start_time_adjusted as i64,
+            end_time as i64,
+            None,
+        )
+        .await?;
+
+    let committed_tps_samples = swarm
+        .query_range_metrics(
+            committed_tps_query,
+            start_time_adjusted as i64,
+            end_time as i64,
+            None,
+        )
+        .await?;
+
+    Ok(ConsensusPerformanceMetrics {
+        round_time_secs: MetricSamples::new(round_time_samples),
+        committed_tps: MetricSamples::new(committed_tps_samples),
+    })
+}
@@ -4,9 +4,14 @@
 use crate::{
     smoke_test_environment::SwarmBuilder,
     test_utils::{MAX_CONNECTIVITY_WAIT_SECS, MAX_HEALTHY_WAIT_SECS},
+    utils::{
+        flip_on_chain_config, get_current_features, rollback_on_chain_config_change,
+        OnChainConfigChange,
+    },
 };
 use aptos_config::config::{NodeConfig, OverrideNodeConfig};
 use aptos_forge::{NodeExt, Swarm};
+use aptos_types::on_chain_config::FeatureFlag;
 use std::{
     sync::Arc,
     time::{Duration, Instant},
@@ -51,3 +56,39 @@ async fn test_aptos_node_after_get_bin() {
             .unwrap();
     }
 }
+
+/// Flip a feature flag via governance, check it took effect, then roll it back.
+#[tokio::test]
+async fn test_on_chain_config_flip_and_rollback() {
+    let (mut swarm, mut cli, _faucet) = SwarmBuilder::new_local(1)
+        .with_aptos()
+        .build_with_cli(0)
+        .await;
+    let root_addr = swarm.chain_info().root_account().address();
+    let root_cli_index = cli.add_account_with_address_to_cli(swarm.root_key(), root_addr);
+    let rest_client = swarm.validators().next().unwrap().rest_client();
+    let epoch_timeout = Duration::from_secs(60);
+    assert!(get_current_features(&rest_client)
+        .await
+        .is_enabled(FeatureFlag::WEBAUTHN_SIGNATURE));
+
+    let rollback = flip_on_chain_config(
+        &swarm,
+        &cli,
+        root_cli_index,
+        OnChainConfigChange::FeatureFlags {
+            enable: vec![],
+            disable: vec![FeatureFlag::WEBAUTHN_SIGNATURE],
+        },
+        epoch_timeout,
+    )
+    .await;
+    assert!(!get_current_features(&rest_client)
+        .await
+        .is_enabled(FeatureFlag::WEBAUTHN_SIGNATURE));
+
+    rollback_on_chain_config_change(&swarm, &cli, root_cli_index, rollback, epoch_timeout).await;
+    assert!(get_current_features(&rest_client)
+        .await
+        .is_enabled(FeatureFlag::WEBAUTHN_SIGNATURE));
+}
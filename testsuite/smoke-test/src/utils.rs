@@ -1,8 +1,14 @@
 // Copyright © Aptos Foundation
 
+use aptos::test::CliTestFramework;
+use aptos_forge::{LocalSwarm, NodeExt, Swarm, SwarmExt};
+use aptos_logger::info;
 use aptos_rest_client::Client;
-use aptos_types::on_chain_config::OnChainConsensusConfig;
+use aptos_types::on_chain_config::{
+    FeatureFlag, Features, OnChainConfig, OnChainConsensusConfig, OnChainExecutionConfig,
+};
 use move_core_types::language_storage::CORE_CODE_ADDRESS;
+use std::time::Duration;
 
 pub(crate) async fn get_current_version(rest_client: &Client) -> u64 {
     rest_client
@@ -26,3 +32,175 @@ pub(crate) async fn get_current_consensus_config(rest_client: &Client) -> OnChai
     )
     .unwrap()
 }
+
+pub(crate) async fn get_current_execution_config(rest_client: &Client) -> OnChainExecutionConfig {
+    bcs::from_bytes(
+        &rest_client
+            .get_account_resource_bcs::<Vec<u8>>(
+                CORE_CODE_ADDRESS,
+                "0x1::execution_config::ExecutionConfig",
+            )
+            .await
+            .unwrap()
+            .into_inner(),
+    )
+    .unwrap()
+}
+
+pub(crate) async fn get_current_features(rest_client: &Client) -> Features {
+    rest_client
+        .get_account_resource_bcs::<Features>(
+            CORE_CODE_ADDRESS,
+            Features::struct_tag().to_string().as_str(),
+        )
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+/// A change to on-chain configs, applied via governance at the next epoch.
+#[derive(Clone, Debug)]
+pub(crate) enum OnChainConfigChange {
+    FeatureFlags {
+        enable: Vec<FeatureFlag>,
+        disable: Vec<FeatureFlag>,
+    },
+    Consensus(OnChainConsensusConfig),
+    Execution(OnChainExecutionConfig),
+}
+
+impl OnChainConfigChange {
+    /// Returns the change that restores the current on-chain state, once this one was applied.
+    async fn rollback(&self, rest_client: &Client) -> Self {
+        match self {
+            Self::FeatureFlags { enable, disable } => {
+                let features = get_current_features(rest_client).await;
+                // Only revert the flags that are actually changed.
+                Self::FeatureFlags {
+                    enable: disable
+                        .iter()
+                        .filter(|flag| features.is_enabled(**flag))
+                        .copied()
+                        .collect(),
+                    disable: enable
+                        .iter()
+                        .filter(|flag| !features.is_enabled(**flag))
+                        .copied()
+                        .collect(),
+                }
+            },
+            Self::Consensus(_) => Self::Consensus(get_current_consensus_config(rest_client).await),
+            Self::Execution(_) => Self::Execution(get_current_execution_config(rest_client).await),
+        }
+    }
+
+    fn script(&self) -> String {
+        let update = match self {
+            Self::FeatureFlags { enable, disable } => format!(
+                "features::change_feature_flags_for_next_epoch(&framework_signer, vector{:?}, vector{:?});",
+                enable.iter().map(|flag| *flag as u64).collect::<Vec<_>>(),
+                disable.iter().map(|flag| *flag as u64).collect::<Vec<_>>(),
+            ),
+            Self::Consensus(config) => format!(
+                "consensus_config::set_for_next_epoch(&framework_signer, vector{:?});",
+                bcs::to_bytes(config).unwrap()
+            ),
+            Self::Execution(config) => format!(
+                "execution_config::set_for_next_epoch(&framework_signer, vector{:?});",
+                bcs::to_bytes(config).unwrap()
+            ),
+        };
+        format!(
+            r#"
+script {{
+    use aptos_framework::aptos_governance;
+    use aptos_framework::consensus_config;
+    use aptos_framework::execution_config;
+    use std::features;
+    fun main(core_resources: &signer) {{
+        let framework_signer = aptos_governance::get_signer_testnet_only(core_resources, @0x1);
+        {}
+        aptos_governance::reconfigure(&framework_signer);
+    }}
+}}
+"#,
+            update
+        )
+    }
+
+    async fn verify_applied(&self, rest_client: &Client) {
+        match self {
+            Self::FeatureFlags { enable, disable } => {
+                let features = get_current_features(rest_client).await;
+                for flag in enable {
+                    assert!(features.is_enabled(*flag), "{:?} was not enabled", flag);
+                }
+                for flag in disable {
+                    assert!(!features.is_enabled(*flag), "{:?} was not disabled", flag);
+                }
+            },
+            Self::Consensus(config) => {
+                assert_eq!(&get_current_consensus_config(rest_client).await, config)
+            },
+            Self::Execution(config) => {
+                assert_eq!(&get_current_execution_config(rest_client).await, config)
+            },
+        }
+    }
+}
+
+/// Applies the given on-chain config change via governance (using the root account at
+/// `root_cli_index`), and waits until it is active, i.e. until the next epoch starts.
+/// Returns the change that rolls back to the previous on-chain state, to be passed to
+/// [`rollback_on_chain_config_change`] once the test is done with its assertions.
+pub(crate) async fn flip_on_chain_config(
+    swarm: &LocalSwarm,
+    cli: &CliTestFramework,
+    root_cli_index: usize,
+    change: OnChainConfigChange,
+    epoch_timeout: Duration,
+) -> OnChainConfigChange {
+    let rest_client = swarm.validators().next().unwrap().rest_client();
+    let rollback = change.rollback(&rest_client).await;
+    apply_on_chain_config_change(swarm, cli, root_cli_index, &change, epoch_timeout).await;
+    rollback
+}
+
+/// Reverts a change previously applied with [`flip_on_chain_config`], and waits until the
+/// previous on-chain state is active again.
+pub(crate) async fn rollback_on_chain_config_change(
+    swarm: &LocalSwarm,
+    cli: &CliTestFramework,
+    root_cli_index: usize,
+    rollback: OnChainConfigChange,
+    epoch_timeout: Duration,
+) {
+    apply_on_chain_config_change(swarm, cli, root_cli_index, &rollback, epoch_timeout).await;
+}
+
+async fn apply_on_chain_config_change(
+    swarm: &LocalSwarm,
+    cli: &CliTestFramework,
+    root_cli_index: usize,
+    change: &OnChainConfigChange,
+    epoch_timeout: Duration,
+) {
+    let rest_client = swarm.validators().next().unwrap().rest_client();
+    let epoch = rest_client
+        .get_ledger_information()
+        .await
+        .unwrap()
+        .inner()
+        .epoch;
+
+    info!("Applying on-chain config change {:?}", change);
+    cli.run_script(root_cli_index, &change.script())
+        .await
+        .expect("Failed to apply on-chain config change");
+
+    swarm
+        .wait_for_all_nodes_to_catchup_to_epoch(epoch + 1, epoch_timeout)
+        .await
+        .expect("Waited too long for the on-chain config change to take effect");
+    change.verify_applied(&rest_client).await;
+}
@@ -2,11 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{LoadDestination, NetworkLoadTest};
-use aptos_forge::{GroupNetEm, NetworkContext, NetworkTest, Swarm, SwarmChaos, SwarmNetEm, Test};
+use aptos_forge::{
+    prometheus_metrics::fetch_consensus_performance_metrics, GroupNetEm, NetworkContext,
+    NetworkTest, Swarm, SwarmChaos, SwarmNetEm, Test, TestReport,
+};
 use aptos_logger::info;
 use aptos_types::PeerId;
 use itertools::{self, EitherOrBoth, Itertools};
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::runtime::Runtime;
 
 /// The link stats are obtained from https://github.com/doitintl/intercloud-throughput/blob/master/results_202202/results.csv
 /// The four regions were hand-picked from the dataset to simulate a multi-region setup
@@ -219,6 +226,10 @@ impl MultiRegionNetworkEmulationConfig {
             ..Default::default()
         }
     }
+
+    pub fn four_region() -> Self {
+        Self::default()
+    }
 }
 
 /// A test to emulate network conditions for a multi-region setup.
@@ -318,6 +329,109 @@ impl NetworkTest for MultiRegionNetworkEmulationTest {
     }
 }
 
+/// A standardized performance suite for a given multi-region topology. Validators are assigned
+/// to the synthetic regions of the topology (with the corresponding inter-region latencies), and
+/// consensus round times and committed throughput are reported per topology, so that
+/// geo-distribution regressions can be tracked across runs.
+pub struct MultiRegionPerformanceTest {
+    topology: &'static str,
+    network_emulation_test: MultiRegionNetworkEmulationTest,
+}
+
+impl MultiRegionPerformanceTest {
+    pub fn new(topology: &'static str, config: MultiRegionNetworkEmulationConfig) -> Self {
+        Self {
+            topology,
+            network_emulation_test: MultiRegionNetworkEmulationTest::new_with_config(config),
+        }
+    }
+
+    pub fn two_region() -> Self {
+        Self::new(
+            "two_region",
+            MultiRegionNetworkEmulationConfig::two_region(),
+        )
+    }
+
+    pub fn four_region() -> Self {
+        Self::new(
+            "four_region",
+            MultiRegionNetworkEmulationConfig::four_region(),
+        )
+    }
+}
+
+impl Test for MultiRegionPerformanceTest {
+    fn name(&self) -> &'static str {
+        "network:multi-region-performance"
+    }
+}
+
+impl NetworkLoadTest for MultiRegionPerformanceTest {
+    fn setup(&self, ctx: &mut NetworkContext) -> anyhow::Result<LoadDestination> {
+        self.network_emulation_test.setup(ctx)
+    }
+
+    fn test(
+        &self,
+        swarm: &mut dyn Swarm,
+        report: &mut TestReport,
+        duration: Duration,
+    ) -> anyhow::Result<()> {
+        let start_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+        std::thread::sleep(duration);
+        let end_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+
+        let runtime = Runtime::new()?;
+        let metrics = runtime.block_on(fetch_consensus_performance_metrics(
+            swarm,
+            start_timestamp,
+            end_timestamp,
+        ))?;
+
+        let test_name = format!("multi_region_performance_{}", self.topology);
+        report.report_metric(
+            &test_name,
+            "avg_round_time_secs",
+            metrics.round_time_secs.avg_sample(),
+        );
+        report.report_metric(
+            &test_name,
+            "max_round_time_secs",
+            metrics.round_time_secs.max_sample(),
+        );
+        report.report_metric(
+            &test_name,
+            "avg_committed_tps",
+            metrics.committed_tps.avg_sample(),
+        );
+        report.report_text(format!(
+            "{} : round time avg {:.3}s / max {:.3}s, committed tps avg {:.1}",
+            test_name,
+            metrics.round_time_secs.avg_sample(),
+            metrics.round_time_secs.max_sample(),
+            metrics.committed_tps.avg_sample(),
+        ));
+        Ok(())
+    }
+
+    fn finish(&self, ctx: &mut NetworkContext) -> anyhow::Result<()> {
+        self.network_emulation_test.finish(ctx)
+    }
+}
+
+impl NetworkTest for MultiRegionPerformanceTest {
+    fn run(&self, ctx: &mut NetworkContext<'_>) -> anyhow::Result<()> {
+        <dyn NetworkLoadTest>::run(self, ctx)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
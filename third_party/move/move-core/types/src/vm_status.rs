@@ -581,11 +581,14 @@ pub enum StatusCode {
     MULTISIG_TRANSACTION_INSUFFICIENT_APPROVALS = 34,
     MULTISIG_TRANSACTION_PAYLOAD_DOES_NOT_MATCH_HASH = 35,
     GAS_PAYER_ACCOUNT_MISSING = 36,
-    // Reserved error code for future use
-    RESERVED_VALIDATION_ERROR_2 = 37,
-    RESERVED_VALIDATION_ERROR_3 = 38,
-    RESERVED_VALIDATION_ERROR_4 = 39,
-    RESERVED_VALIDATION_ERROR_5 = 40,
+    // The payload passes more type arguments than allowed
+    EXCEEDED_MAX_TYPE_ARGUMENTS = 37,
+    // A type argument of the payload is nested deeper than allowed
+    EXCEEDED_MAX_TYPE_ARGUMENT_DEPTH = 38,
+    // The payload passes more arguments than allowed
+    EXCEEDED_MAX_ARGUMENTS = 39,
+    // An argument of the payload is larger than allowed
+    EXCEEDED_MAX_ARGUMENT_SIZE = 40,
 
     // When a code module/script is published it is verified. These are the
     // possible errors that can arise from the verification process.
@@ -0,0 +1,58 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_crypto::test_utils::TestAptosCrypto;
+use aptos_types::{
+    aggregate_signature::PartialSignatures,
+    validator_verifier::{random_validator_verifier, ValidatorVerifier},
+};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+fn verify_multi_signatures(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify_multi_signatures");
+    let message = TestAptosCrypto("Hello, World".to_string());
+
+    for num_validators in [150, 200] {
+        let (signers, verifier) = random_validator_verifier(num_validators, None, false);
+        let mut partial_signatures = PartialSignatures::empty();
+        for signer in signers.iter().take(num_validators * 2 / 3 + 1) {
+            partial_signatures.add_signature(signer.author(), signer.sign(&message).unwrap());
+        }
+        let multi_signature = verifier.aggregate_signatures(&partial_signatures).unwrap();
+
+        // A verifier of a new epoch, which aggregates the public keys of the signers.
+        let serialized_verifier = bcs::to_bytes(&verifier).unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("uncached", num_validators),
+            &multi_signature,
+            |b, multi_signature| {
+                b.iter_batched(
+                    || bcs::from_bytes::<ValidatorVerifier>(&serialized_verifier).unwrap(),
+                    |verifier| verifier.verify_multi_signatures(&message, multi_signature),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+
+        // The signer set was verified before in the epoch.
+        verifier
+            .verify_multi_signatures(&message, &multi_signature)
+            .unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("cached", num_validators),
+            &multi_signature,
+            |b, multi_signature| {
+                b.iter(|| verifier.verify_multi_signatures(&message, multi_signature))
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    name = validator_verifier_benches;
+    config = Criterion::default();
+    targets = verify_multi_signatures
+);
+
+criterion_main!(validator_verifier_benches);
@@ -0,0 +1,41 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::account_address::AccountAddress;
+use anyhow::Result;
+use move_core_types::{
+    ident_str, identifier::IdentStr, language_storage::TypeTag, move_resource::MoveStructType,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// Struct that represents a StagedCodeActivation event, emitted when a module upgrade that was
+/// staged until the start of an epoch is published (or dropped).
+/// Should be kept in-sync with StagedCodeActivation move struct in code.move.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StagedCodeActivation {
+    code_address: AccountAddress,
+    activated: bool,
+}
+
+impl StagedCodeActivation {
+    pub fn code_address(&self) -> AccountAddress {
+        self.code_address
+    }
+
+    pub fn activated(&self) -> bool {
+        self.activated
+    }
+
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self> {
+        bcs::from_bytes(bytes).map_err(Into::into)
+    }
+}
+
+impl MoveStructType for StagedCodeActivation {
+    const MODULE_NAME: &'static IdentStr = ident_str!("code");
+    const STRUCT_NAME: &'static IdentStr = ident_str!("StagedCodeActivation");
+}
+
+pub static STAGED_CODE_ACTIVATION_MOVE_TYPE_TAG: Lazy<TypeTag> =
+    Lazy::new(|| TypeTag::Struct(Box::new(StagedCodeActivation::struct_tag())));
@@ -2,6 +2,7 @@
 
 use crate::on_chain_config::BlockGasLimitType;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// Local, per-node configuration.
 #[derive(Clone, Debug)]
@@ -13,6 +14,232 @@ pub struct BlockExecutorLocalConfig {
     // If true, we will discard the failed blocks and continue with the next block.
     // (allow_fallback needs to be set)
     pub discard_failed_blocks: bool,
+    // What to do when parallel execution fails, depending on the failure
+    // (allow_fallback needs to be set).
+    pub fallback_policies: FallbackPolicies,
+    // If specified, parallel execution parks workers while the abort rate is high.
+    pub adaptive_concurrency: Option<AdaptiveConcurrencyConfig>,
+    // If true, delayed field reads are also checked during the regular validation of
+    // parallel execution (not only at commit time), to abort stale incarnations sooner.
+    pub early_delayed_field_validation: bool,
+    // If true, the base values of the keys likely to be read by the block (based on the access
+    // hints, or the previous block of a pipeline) are read before parallel execution starts.
+    pub prefetch_base_values: bool,
+    // If true, the read/write conflict graph of the committed transactions is recorded, to be
+    // taken from the block executor after the block is executed.
+    pub record_conflict_graph: bool,
+    // If true, the schedule of parallel execution (the order in which the tasks start, and the
+    // transactions abort and commit) is recorded, to be taken from the block executor after the
+    // block is executed, and replayed when debugging.
+    pub record_schedule: bool,
+    // If specified, an incarnation whose execution takes longer is aborted, and a transaction
+    // whose committed incarnation timed out is discarded (with EXECUTION_LIMIT_REACHED). The
+    // outcome depends on the wall-clock time, hence may differ across nodes.
+    pub txn_execution_timeout: Option<Duration>,
+    // If true, parallel execution starts the transactions with a higher gas unit price than
+    // the cheapest transactions of the block first (the commit order is unchanged), so that
+    // their outputs are ready when the block gas limit would otherwise cut them off.
+    pub prioritize_by_gas_price: bool,
+    // How parallel execution handles the reads of values that are estimated to be written by
+    // a transaction being re-executed.
+    pub dependency_wait: DependencyWaitConfig,
+    // If true, parallel execution releases the outputs of the transactions that are independent
+    // of all the earlier transactions not committed yet to the commit hook, ahead of their
+    // commit (see TransactionCommitHook::on_transaction_released in the block executor).
+    pub early_release: bool,
+    // If non-zero, the committed transactions are materialized (and passed to the commit hook)
+    // by that many dedicated workers, instead of by the execution workers. The workers run on
+    // the materialization thread pool of the block executor if it has one, and otherwise take
+    // the place of execution workers (at least one execution worker remains).
+    pub materialization_concurrency: usize,
+    // If specified, the worker threads of parallel execution are pinned to CPU cores.
+    pub core_affinity: Option<CoreAffinityConfig>,
+    // If true, the blocks executed in parallel are also executed sequentially beforehand, and
+    // the outputs of both executions are compared per transaction (the parallel outputs are
+    // returned). The first divergence is logged, and can be taken from the block executor after
+    // the block is executed. Only meant for testing and canary nodes, as it roughly doubles
+    // the execution time.
+    pub differential_execution: bool,
+    // If true, the multi-version data-structure of a block executed in parallel is retained, to
+    // be taken from the block executor after the block is executed, and used to execute single
+    // transactions against the state of a prefix of the block, e.g. when debugging.
+    pub retain_block_state: bool,
+    // If specified, parallel execution stops speculating on a transaction that was executed
+    // that many times: its next incarnation is executed once, when the transaction is the next
+    // to commit, which bounds the work wasted on a transaction that keeps being aborted.
+    pub max_incarnations: Option<u32>,
+    // If specified, a block is ended early (the rest of the block is skipped) once committing
+    // its transactions took longer, to protect the block latency when the gas calibration
+    // underestimates the actual costs. The outcome depends on the wall-clock time, hence may
+    // differ across nodes.
+    pub block_time_budget: Option<Duration>,
+    // If true, blocks are executed in parallel even with a concurrency level of 1 (instead of
+    // sequentially): the single worker coordinates its own commits. Exercises the parallel
+    // execution code path deterministically, e.g. when debugging or for differential testing.
+    pub parallel_single_worker: bool,
+    // If true, the timings of every incarnation of every transaction (execution, validation,
+    // materialization and dependency waits) are recorded during parallel execution, to be
+    // taken from the block executor after the block is executed, e.g. to diagnose a single
+    // hot transaction.
+    pub profile_transactions: bool,
+    // If true, the module reads of parallel execution are validated against the versions of the
+    // modules published by the block, instead of falling back to sequential execution when a
+    // transaction reads a module published by the block. Requires the executor tasks not to
+    // cache modules across versions. The loader cache of the AptosVM caches modules by id only,
+    // so the VM always executes with it unset (and blocks publishing modules that they also
+    // read still fall back to sequential execution) until the cache is made version-aware.
+    pub validate_module_reads: bool,
+    // If true (and early_delayed_field_validation is set), an incarnation that only fails the
+    // early check of its delayed field reads is not re-executed right away: it is re-executed
+    // once it is the next to commit, or once the validation of its other reads fails, which
+    // avoids incarnations wasted on delayed fields that are still being updated by the
+    // preceding transactions.
+    pub soft_delayed_field_validation_failures: bool,
+    // If specified, a block executed in parallel is ended early (the rest of the block is
+    // skipped) once the entries of its multi-version data-structure take more memory (in bytes,
+    // approximately). The outcome depends on the speculative executions, hence may differ
+    // across nodes.
+    pub mvhashmap_memory_soft_cap: Option<u64>,
+    // If true, the state reads of every transaction (distinct keys, bytes, storage reads and
+    // storage cache hits) are recorded, and returned in the block output.
+    pub record_read_stats: bool,
+    // The deterministic seed of the block (derived from its id), which the randomized internal
+    // checks of the VM sample with while the block is executed, so that they are the same when
+    // the block is replayed. If not specified, they sample randomly.
+    pub block_seed: Option<[u8; 32]>,
+    // If true, a discarded block still executes its block metadata and validator transactions
+    // (alone, sequentially), and only discards its user transactions. It changes the outputs of
+    // the discarded blocks, hence the VM sets it from an on-chain feature flag.
+    pub discarded_block_bookkeeping: bool,
+}
+
+/// Overrides of the config of the block executor for the execution of a single block, e.g. to
+/// replay or backfill blocks at a different concurrency level than the live execution.
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionOverrides {
+    /// Takes precedence over the concurrency level of the local config (capped at the number
+    /// of CPUs).
+    pub concurrency_level: Option<usize>,
+    /// Takes precedence over the block gas limit type of the on-chain config.
+    pub gas_limit_override: Option<BlockGasLimitType>,
+}
+
+/// What to do with a block when its parallel execution fails.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FallbackPolicy {
+    /// Retry parallel execution once, e.g. for failures that depend on the interleaving of the
+    /// workers. If the retry fails as well, the block is executed sequentially.
+    RetryParallelOnce,
+    /// Retry parallel execution once with half of the concurrency level (but at least 2, or 1
+    /// for a single worker). If the retry fails as well, the block is executed sequentially.
+    ParallelWithReducedConcurrency,
+    /// Execute the block sequentially.
+    Sequential,
+    /// Discard all transactions of the block, without executing it sequentially (which may
+    /// stall the node for a large block). Parallel execution failures depend on the schedule,
+    /// so nodes may disagree on whether the block is discarded: not allowed for validators.
+    Discard,
+}
+
+/// The fallback policy for each kind of parallel execution failure. By default, all failed
+/// blocks are executed sequentially.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FallbackPolicies {
+    /// A code invariant error in the block executor.
+    pub code_invariant_error: FallbackPolicy,
+    /// The same module was both read and written during speculative executions.
+    pub module_read_write_conflict: FallbackPolicy,
+    /// An unrecoverable VM error.
+    pub fatal_vm_error: FallbackPolicy,
+}
+
+impl FallbackPolicies {
+    /// Returns whether a block may be discarded after a parallel execution failure, without
+    /// being executed sequentially first.
+    pub fn may_discard(&self) -> bool {
+        [
+            self.code_invariant_error,
+            self.module_read_write_conflict,
+            self.fatal_vm_error,
+        ]
+        .contains(&FallbackPolicy::Discard)
+    }
+}
+
+impl Default for FallbackPolicies {
+    fn default() -> Self {
+        Self {
+            code_invariant_error: FallbackPolicy::Sequential,
+            module_read_write_conflict: FallbackPolicy::Sequential,
+            fatal_vm_error: FallbackPolicy::Sequential,
+        }
+    }
+}
+
+/// Adapts the number of active workers during parallel execution of a block to the observed
+/// abort rate, i.e. the number of speculative aborts per committed transaction so far in the
+/// block. Execution starts at the configured concurrency level. Above park_abort_percentage,
+/// one worker is parked on every commit (down to min_concurrency_level), and below
+/// unpark_abort_percentage, one parked worker is resumed on every commit.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdaptiveConcurrencyConfig {
+    pub min_concurrency_level: usize,
+    /// Aborts per 100 committed transactions above which workers are parked.
+    pub park_abort_percentage: u64,
+    /// Aborts per 100 committed transactions below which parked workers are resumed.
+    pub unpark_abort_percentage: u64,
+    /// The concurrency is not adapted until that many transactions are committed.
+    pub min_committed_txns: u32,
+}
+
+impl Default for AdaptiveConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            min_concurrency_level: 2,
+            park_abort_percentage: 50,
+            unpark_abort_percentage: 20,
+            min_committed_txns: 20,
+        }
+    }
+}
+
+/// How a transaction that reads a value estimated to be written by an earlier transaction (whose
+/// incarnation was aborted and is being re-executed) waits for that transaction. By default, the
+/// worker executing the transaction blocks until the dependency is resolved.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DependencyWaitConfig {
+    /// If true, the execution is stopped instead, and the transaction is executed again from
+    /// the start once the dependency is resolved, so that the worker can perform other tasks.
+    pub requeue: bool,
+    /// If set, once a transaction waited on that many dependencies (over all its incarnations),
+    /// its reads of resources go past the estimates instead of waiting, i.e. it is executed
+    /// against the values of the aborted incarnations, which validation later checks. The reads
+    /// of resource groups and delayed fields still wait.
+    pub wait_budget: Option<u32>,
+    /// If true, the reads of resources never wait: they go past the estimates from the start,
+    /// as if the wait budget was zero. As aborted incarnations often write the same values
+    /// again, the reads past an estimate are validated against the re-executed write by value
+    /// (rather than by version), which also avoids re-executing the reader in this case.
+    pub speculate_past_estimates: bool,
+}
+
+/// The CPU cores that the worker threads of parallel execution are pinned to, e.g. to keep them
+/// on a single NUMA node (socket) of a multi-socket machine. The multi-version data structure of
+/// a block is then allocated on that node as well, as its entries are allocated by the workers
+/// (with the default first-touch memory policy of Linux).
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CoreAffinityConfig {
+    /// If set, the workers are only pinned to the cores of that NUMA node (only supported on
+    /// Linux, otherwise the workers are not pinned).
+    pub numa_node: Option<usize>,
+    /// The ids of the cores that the workers are pinned to. If empty, all the cores available
+    /// to the process (of the NUMA node, if set).
+    pub cores: Vec<usize>,
 }
 
 /// Configuration from on-chain configuration, that is
@@ -72,6 +299,30 @@ impl BlockExecutorConfig {
                 concurrency_level,
                 allow_fallback: true,
                 discard_failed_blocks: false,
+                fallback_policies: FallbackPolicies::default(),
+                adaptive_concurrency: None,
+                early_delayed_field_validation: false,
+                prefetch_base_values: false,
+                record_conflict_graph: false,
+                record_schedule: false,
+                txn_execution_timeout: None,
+                prioritize_by_gas_price: false,
+                dependency_wait: DependencyWaitConfig::default(),
+                early_release: false,
+                materialization_concurrency: 0,
+                core_affinity: None,
+                differential_execution: false,
+                retain_block_state: false,
+                max_incarnations: None,
+                block_time_budget: None,
+                parallel_single_worker: false,
+                profile_transactions: false,
+                validate_module_reads: false,
+                soft_delayed_field_validation_failures: false,
+                mvhashmap_memory_soft_cap: None,
+                record_read_stats: false,
+                block_seed: None,
+                discarded_block_bookkeeping: false,
             },
             onchain: BlockExecutorConfigFromOnchain::new_no_block_limit(),
         }
@@ -86,6 +337,30 @@ impl BlockExecutorConfig {
                 concurrency_level,
                 allow_fallback: true,
                 discard_failed_blocks: false,
+                fallback_policies: FallbackPolicies::default(),
+                adaptive_concurrency: None,
+                early_delayed_field_validation: false,
+                prefetch_base_values: false,
+                record_conflict_graph: false,
+                record_schedule: false,
+                txn_execution_timeout: None,
+                prioritize_by_gas_price: false,
+                dependency_wait: DependencyWaitConfig::default(),
+                early_release: false,
+                materialization_concurrency: 0,
+                core_affinity: None,
+                differential_execution: false,
+                retain_block_state: false,
+                max_incarnations: None,
+                block_time_budget: None,
+                parallel_single_worker: false,
+                profile_transactions: false,
+                validate_module_reads: false,
+                soft_delayed_field_validation_failures: false,
+                mvhashmap_memory_soft_cap: None,
+                record_read_stats: false,
+                block_seed: None,
+                discarded_block_bookkeeping: false,
             },
             onchain: BlockExecutorConfigFromOnchain::new_maybe_block_limit(maybe_block_gas_limit),
         }
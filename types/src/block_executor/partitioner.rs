@@ -510,6 +510,31 @@ impl PartitionedTransactions {
         self.num_sharded_txns() + self.global_txns.len()
     }
 
+    /// Returns the shard of each transaction, in the order of [`PartitionedTransactions::flatten`].
+    /// Only the transactions without cross-shard dependencies are known not to conflict with the
+    /// transactions of other shards, so the shard is None for the others (and the global ones).
+    pub fn txn_shards(&self) -> Vec<Option<ShardId>> {
+        let num_rounds = self
+            .sharded_txns
+            .first()
+            .map_or(0, |sub_blocks| sub_blocks.num_sub_blocks());
+        let mut shards = Vec::with_capacity(self.num_txns());
+        for round in 0..num_rounds {
+            for sub_blocks in &self.sharded_txns {
+                if let Some(sub_block) = sub_blocks.get_sub_block(round) {
+                    shards.extend(sub_block.iter().map(|txn| {
+                        let dependencies = txn.cross_shard_dependencies();
+                        (dependencies.required_edges().is_empty()
+                            && dependencies.dependent_edges().is_empty())
+                        .then_some(sub_blocks.shard_id)
+                    }));
+                }
+            }
+        }
+        shards.extend(self.global_txns.iter().map(|_| None));
+        shards
+    }
+
     pub fn add_checkpoint_txn(&mut self, last_txn: SignatureVerifiedTransaction) {
         assert!(matches!(
             last_txn.expect_valid(),
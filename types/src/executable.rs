@@ -3,10 +3,13 @@
 
 use crate::{
     access_path::AccessPath,
-    state_store::state_key::{StateKey, StateKeyInner},
+    state_store::{
+        state_key::{StateKey, StateKeyInner},
+        table::TableHandle,
+    },
 };
 use aptos_crypto::HashValue;
-use std::sync::Arc;
+use std::{fmt::Debug, hash::Hash, sync::Arc};
 
 #[derive(PartialEq, Eq, Debug)]
 pub enum ExecutableDescriptor {
@@ -32,6 +35,26 @@ impl ModulePath for StateKey {
     }
 }
 
+/// Keys that are ordered within a prefix, so that the keys of a prefix can be read by range (e.g.
+/// to iterate over the items of a table, whose prefix is the table handle).
+pub trait KeyPrefix: Ord {
+    type Prefix: Clone + Debug + Eq + Hash + Send + Sync;
+
+    /// The prefix of the key, or None if the key cannot be read by range.
+    fn key_prefix(&self) -> Option<Self::Prefix>;
+}
+
+impl KeyPrefix for StateKey {
+    type Prefix = TableHandle;
+
+    fn key_prefix(&self) -> Option<TableHandle> {
+        match self.inner() {
+            StateKeyInner::TableItem { handle, .. } => Some(*handle),
+            StateKeyInner::AccessPath(_) | StateKeyInner::Raw(_) => None,
+        }
+    }
+}
+
 /// For now we will handle the VM code cache / arena memory consumption on the
 /// executor side, likely naively in the beginning (e.g. flushing after a threshold).
 /// For the executor to manage memory consumption, executables should provide size.
@@ -67,6 +67,9 @@ pub enum FeatureFlag {
     MAX_OBJECT_NESTING_CHECK = 53,
     KEYLESS_ACCOUNTS_WITH_PASSKEYS = 54,
     TRANSACTION_CONTEXT_EXTENSION = 55,
+    RESOURCE_GROUP_PER_TAG_GAS = 56,
+    STAGED_MODULE_PUBLISHING = 57,
+    DISCARDED_BLOCK_BOOKKEEPING = 58,
 }
 
 impl FeatureFlag {
@@ -261,6 +264,24 @@ impl Features {
     pub fn is_refundable_bytes_enabled(&self) -> bool {
         self.is_enabled(FeatureFlag::REFUNDABLE_BYTES)
     }
+
+    /// Whether reads and writes of resource group members are charged for the size of the
+    /// accessed members (and their tags), rather than for the size of the whole group.
+    pub fn is_resource_group_per_tag_gas_enabled(&self) -> bool {
+        self.is_enabled(FeatureFlag::RESOURCE_GROUP_PER_TAG_GAS)
+    }
+
+    /// Whether module upgrades are staged until the next epoch, instead of being published by
+    /// the transaction that requested them.
+    pub fn is_staged_module_publishing_enabled(&self) -> bool {
+        self.is_enabled(FeatureFlag::STAGED_MODULE_PUBLISHING)
+    }
+
+    /// Whether a block discarded after an execution failure still executes its block metadata
+    /// and validator transactions, only discarding its user transactions.
+    pub fn is_discarded_block_bookkeeping_enabled(&self) -> bool {
+        self.is_enabled(FeatureFlag::DISCARDED_BLOCK_BOOKKEEPING)
+    }
 }
 
 pub fn aptos_test_feature_flags_genesis() -> ChangeSet {
@@ -201,6 +201,42 @@ pub enum BlockGasLimitType {
         /// NOTE: Currently not supported.
         add_block_limit_outcome_onchain: bool,
     },
+    /// The limits of ComplexLimitV1, with independent limits on the execution gas, the IO gas
+    /// and the storage fee of the block in addition. The block ends once any limit is reached.
+    ComplexLimitV2 {
+        effective_block_gas_limit: u64,
+        execution_gas_effective_multiplier: u64,
+        io_gas_effective_multiplier: u64,
+        conflict_penalty_window: u32,
+        use_granular_resource_group_conflicts: bool,
+        use_module_publishing_block_conflict: bool,
+        block_output_limit: Option<u64>,
+        include_user_txn_size_in_block_output: bool,
+        add_block_limit_outcome_onchain: bool,
+
+        /// Block limit on the total execution gas of the committed txns (not scaled by the
+        /// effective multiplier or by conflicts).
+        execution_gas_limit: Option<u64>,
+        /// Block limit on the total IO gas of the committed txns (not scaled by the effective
+        /// multiplier or by conflicts).
+        io_gas_limit: Option<u64>,
+        /// Block limit on the total storage fee (in octas) of the committed txns.
+        storage_fee_limit: Option<u64>,
+
+        /// If set (with a conflict_penalty_window above 1), the conflicts are penalized based on
+        /// an exponentially decaying conflict score per key instead: every write of a key adds 1
+        /// to its score, which then decays to the given percentage (at most 99) with every
+        /// committed txn. A txn is penalized by the highest score among the keys it reads
+        /// (rounded down, and capped by conflict_penalty_window), so that sporadic conflicts
+        /// are not penalized, while sustained contention on a hot key is.
+        conflict_penalty_decay_percentage: Option<u8>,
+
+        /// Limit on the materialized output size in bytes of each txn, i.e. the sizes of its
+        /// written values (with whole resource groups) and events. A txn whose output exceeds it
+        /// is discarded at commit (with STORAGE_WRITE_LIMIT_REACHED), so that a single txn cannot
+        /// dominate the block output.
+        txn_output_limit: Option<u64>,
+    },
 }
 
 impl BlockGasLimitType {
@@ -211,6 +247,10 @@ impl BlockGasLimitType {
             BlockGasLimitType::ComplexLimitV1 {
                 effective_block_gas_limit,
                 ..
+            }
+            | BlockGasLimitType::ComplexLimitV2 {
+                effective_block_gas_limit,
+                ..
             } => Some(*effective_block_gas_limit),
         }
     }
@@ -222,6 +262,10 @@ impl BlockGasLimitType {
             BlockGasLimitType::ComplexLimitV1 {
                 execution_gas_effective_multiplier,
                 ..
+            }
+            | BlockGasLimitType::ComplexLimitV2 {
+                execution_gas_effective_multiplier,
+                ..
             } => *execution_gas_effective_multiplier,
         }
     }
@@ -233,6 +277,10 @@ impl BlockGasLimitType {
             BlockGasLimitType::ComplexLimitV1 {
                 io_gas_effective_multiplier,
                 ..
+            }
+            | BlockGasLimitType::ComplexLimitV2 {
+                io_gas_effective_multiplier,
+                ..
             } => *io_gas_effective_multiplier,
         }
     }
@@ -243,6 +291,9 @@ impl BlockGasLimitType {
             BlockGasLimitType::Limit(_) => None,
             BlockGasLimitType::ComplexLimitV1 {
                 block_output_limit, ..
+            }
+            | BlockGasLimitType::ComplexLimitV2 {
+                block_output_limit, ..
             } => *block_output_limit,
         }
     }
@@ -254,6 +305,10 @@ impl BlockGasLimitType {
             BlockGasLimitType::ComplexLimitV1 {
                 conflict_penalty_window,
                 ..
+            }
+            | BlockGasLimitType::ComplexLimitV2 {
+                conflict_penalty_window,
+                ..
             } => {
                 if *conflict_penalty_window > 1 {
                     Some(*conflict_penalty_window)
@@ -271,6 +326,10 @@ impl BlockGasLimitType {
             BlockGasLimitType::ComplexLimitV1 {
                 use_module_publishing_block_conflict,
                 ..
+            }
+            | BlockGasLimitType::ComplexLimitV2 {
+                use_module_publishing_block_conflict,
+                ..
             } => *use_module_publishing_block_conflict,
         }
     }
@@ -282,6 +341,10 @@ impl BlockGasLimitType {
             BlockGasLimitType::ComplexLimitV1 {
                 include_user_txn_size_in_block_output,
                 ..
+            }
+            | BlockGasLimitType::ComplexLimitV2 {
+                include_user_txn_size_in_block_output,
+                ..
             } => *include_user_txn_size_in_block_output,
         }
     }
@@ -293,6 +356,10 @@ impl BlockGasLimitType {
             BlockGasLimitType::ComplexLimitV1 {
                 add_block_limit_outcome_onchain,
                 ..
+            }
+            | BlockGasLimitType::ComplexLimitV2 {
+                add_block_limit_outcome_onchain,
+                ..
             } => *add_block_limit_outcome_onchain,
         }
     }
@@ -304,9 +371,74 @@ impl BlockGasLimitType {
             BlockGasLimitType::ComplexLimitV1 {
                 use_granular_resource_group_conflicts,
                 ..
+            }
+            | BlockGasLimitType::ComplexLimitV2 {
+                use_granular_resource_group_conflicts,
+                ..
             } => *use_granular_resource_group_conflicts,
         }
     }
+
+    /// The limit on the execution gas of the block, independent of the effective block gas.
+    pub fn execution_gas_limit(&self) -> Option<u64> {
+        match self {
+            BlockGasLimitType::NoLimit
+            | BlockGasLimitType::Limit(_)
+            | BlockGasLimitType::ComplexLimitV1 { .. } => None,
+            BlockGasLimitType::ComplexLimitV2 {
+                execution_gas_limit,
+                ..
+            } => *execution_gas_limit,
+        }
+    }
+
+    /// The limit on the IO gas of the block, independent of the effective block gas.
+    pub fn io_gas_limit(&self) -> Option<u64> {
+        match self {
+            BlockGasLimitType::NoLimit
+            | BlockGasLimitType::Limit(_)
+            | BlockGasLimitType::ComplexLimitV1 { .. } => None,
+            BlockGasLimitType::ComplexLimitV2 { io_gas_limit, .. } => *io_gas_limit,
+        }
+    }
+
+    /// The limit on the storage fee of the block.
+    pub fn storage_fee_limit(&self) -> Option<u64> {
+        match self {
+            BlockGasLimitType::NoLimit
+            | BlockGasLimitType::Limit(_)
+            | BlockGasLimitType::ComplexLimitV1 { .. } => None,
+            BlockGasLimitType::ComplexLimitV2 {
+                storage_fee_limit, ..
+            } => *storage_fee_limit,
+        }
+    }
+
+    /// The percentage of the conflict score of a key retained after each committed txn, if the
+    /// conflicts are penalized based on decaying conflict scores (see ComplexLimitV2).
+    pub fn conflict_penalty_decay_percentage(&self) -> Option<u8> {
+        match self {
+            BlockGasLimitType::NoLimit
+            | BlockGasLimitType::Limit(_)
+            | BlockGasLimitType::ComplexLimitV1 { .. } => None,
+            BlockGasLimitType::ComplexLimitV2 {
+                conflict_penalty_decay_percentage,
+                ..
+            } => conflict_penalty_decay_percentage.map(|percentage| percentage.min(99)),
+        }
+    }
+
+    /// The limit on the materialized output size of each txn, above which it is discarded.
+    pub fn txn_output_limit(&self) -> Option<u64> {
+        match self {
+            BlockGasLimitType::NoLimit
+            | BlockGasLimitType::Limit(_)
+            | BlockGasLimitType::ComplexLimitV1 { .. } => None,
+            BlockGasLimitType::ComplexLimitV2 {
+                txn_output_limit, ..
+            } => *txn_output_limit,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -24,6 +24,7 @@ pub mod errors;
 pub mod in_memory_state_view;
 pub mod state_key;
 pub mod state_key_prefix;
+pub mod state_read_stats;
 pub mod state_storage_usage;
 pub mod state_value;
 pub mod table;
@@ -66,8 +67,11 @@ impl<T: TStateView<Key = StateKey>> StateView for T {}
 pub enum StateViewId {
     /// State-sync applying a chunk of transactions.
     ChunkExecution { first_version: Version },
-    /// LEC applying a block.
-    BlockExecution { block_id: HashValue },
+    /// LEC applying a block (on the state after its parent block).
+    BlockExecution {
+        block_id: HashValue,
+        parent_block_id: HashValue,
+    },
     /// VmValidator verifying incoming transaction.
     TransactionValidation { base_version: Version },
     /// For test, db-bootstrapper, etc. Usually not aimed to pass to VM.
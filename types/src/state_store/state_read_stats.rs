@@ -0,0 +1,159 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::state_store::{
+    state_storage_usage::StateStorageUsage, state_value::StateValue, Result, StateViewId,
+    TStateView,
+};
+use aptos_infallible::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, hash::Hash};
+
+/// The state reads of a single transaction, e.g. for developers to optimize the storage access
+/// patterns of their contracts.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StateReadStats {
+    /// The number of distinct state keys (resources, resource groups and modules) read.
+    pub num_distinct_reads: u64,
+    /// The total size of the values returned by the reads, in bytes.
+    pub bytes_read: u64,
+    /// The number of values read from the storage layer, i.e. that were not already read (or
+    /// written) by the preceding transactions of the block.
+    pub num_storage_reads: u64,
+    /// The number of storage reads served by a cache of the storage layer.
+    pub num_storage_cache_hits: u64,
+}
+
+/// Records the state reads of a single transaction (see [`StateReadStats`]).
+pub struct StateReadStatsRecorder<K> {
+    inner: Mutex<(HashSet<K>, StateReadStats)>,
+}
+
+impl<K: Hash + Eq + Clone> StateReadStatsRecorder<K> {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new((HashSet::new(), StateReadStats::default())),
+        }
+    }
+
+    /// Records a read of the key that returned bytes_read bytes.
+    pub fn record_read(&self, key: &K, bytes_read: usize) {
+        let mut inner = self.inner.lock();
+        let (keys, stats) = &mut *inner;
+        if !keys.contains(key) {
+            keys.insert(key.clone());
+            stats.num_distinct_reads += 1;
+        }
+        stats.bytes_read += bytes_read as u64;
+    }
+
+    pub fn record_storage_read(&self, cache_hit: bool) {
+        let mut inner = self.inner.lock();
+        inner.1.num_storage_reads += 1;
+        if cache_hit {
+            inner.1.num_storage_cache_hits += 1;
+        }
+    }
+
+    pub fn stats(&self) -> StateReadStats {
+        self.inner.lock().1
+    }
+
+    pub fn reset(&self) {
+        *self.inner.lock() = (HashSet::new(), StateReadStats::default());
+    }
+}
+
+impl<K: Hash + Eq + Clone> Default for StateReadStatsRecorder<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A state view that records the reads of a single transaction executed on top of it, e.g. for
+/// simulation. Every read is a storage read, served by the underlying view.
+pub struct StateReadStatsView<'a, S: TStateView> {
+    base_view: &'a S,
+    recorder: StateReadStatsRecorder<S::Key>,
+}
+
+impl<'a, S: TStateView> StateReadStatsView<'a, S>
+where
+    S::Key: Hash + Eq + Clone,
+{
+    pub fn new(base_view: &'a S) -> Self {
+        Self {
+            base_view,
+            recorder: StateReadStatsRecorder::new(),
+        }
+    }
+
+    pub fn stats(&self) -> StateReadStats {
+        self.recorder.stats()
+    }
+
+    /// Discards the reads recorded so far, e.g. the reads to set up the execution.
+    pub fn reset_stats(&self) {
+        self.recorder.reset()
+    }
+}
+
+impl<'a, S: TStateView> TStateView for StateReadStatsView<'a, S>
+where
+    S::Key: Hash + Eq + Clone,
+{
+    type Key = S::Key;
+
+    fn id(&self) -> StateViewId {
+        self.base_view.id()
+    }
+
+    fn get_state_value(&self, state_key: &Self::Key) -> Result<Option<StateValue>> {
+        let value = self.base_view.get_state_value(state_key)?;
+        self.recorder.record_storage_read(false);
+        self.recorder
+            .record_read(state_key, value.as_ref().map_or(0, StateValue::size));
+        Ok(value)
+    }
+
+    fn get_usage(&self) -> Result<StateStorageUsage> {
+        self.base_view.get_usage()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_store::{in_memory_state_view::InMemoryStateView, state_key::StateKey};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_state_read_stats_view() {
+        let key = StateKey::raw(b"key".to_vec());
+        let missing_key = StateKey::raw(b"missing".to_vec());
+        let base_view = InMemoryStateView::new(HashMap::from([(
+            key.clone(),
+            StateValue::new_legacy(vec![0; 10].into()),
+        )]));
+        let view = StateReadStatsView::new(&base_view);
+
+        view.get_state_value(&key).unwrap();
+        view.get_state_value(&key).unwrap();
+        view.get_state_value(&missing_key).unwrap();
+        assert_eq!(view.stats(), StateReadStats {
+            num_distinct_reads: 2,
+            bytes_read: 20,
+            num_storage_reads: 3,
+            num_storage_cache_hits: 0,
+        });
+
+        view.reset_stats();
+        view.get_state_value(&key).unwrap();
+        assert_eq!(view.stats(), StateReadStats {
+            num_distinct_reads: 1,
+            bytes_read: 10,
+            num_storage_reads: 1,
+            num_storage_cache_hits: 0,
+        });
+    }
+}
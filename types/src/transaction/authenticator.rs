@@ -6,7 +6,8 @@ use crate::{
     account_address::AccountAddress,
     keyless::{EphemeralCertificate, KeylessPublicKey, KeylessSignature, TransactionAndProof},
     transaction::{
-        webauthn::PartialAuthenticatorAssertionResponse, RawTransaction, RawTransactionWithData,
+        authenticator_registry::SignatureScheme, webauthn::PartialAuthenticatorAssertionResponse,
+        RawTransaction, RawTransactionWithData,
     },
 };
 use anyhow::{bail, ensure, Error, Result};
@@ -991,6 +992,16 @@ impl AnySignature {
         Self::Keyless { signature }
     }
 
+    /// The identifier of the signature scheme, used to look it up in the authenticator registry
+    pub fn scheme(&self) -> SignatureScheme {
+        match self {
+            Self::Ed25519 { .. } => SignatureScheme::Ed25519,
+            Self::Secp256k1Ecdsa { .. } => SignatureScheme::Secp256k1Ecdsa,
+            Self::WebAuthn { .. } => SignatureScheme::WebAuthn,
+            Self::Keyless { .. } => SignatureScheme::Keyless,
+        }
+    }
+
     pub fn verify<T: Serialize + CryptoHash>(
         &self,
         public_key: &AnyPublicKey,
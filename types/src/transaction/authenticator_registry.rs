@@ -0,0 +1,192 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! A registry of the signature schemes that may authenticate transactions, keyed by their
+//! scheme identifier.
+//!
+//! Every signature of a transaction authenticator is mapped to a [`SignatureScheme`], and the
+//! registry decides whether the scheme is accepted (i.e., registered, and its feature gate, if
+//! any, is enabled). A new scheme (e.g., an experimental one on devnet) is thus added with a new
+//! identifier and its registration behind a feature flag, instead of a dedicated check at every
+//! site that validates transactions.
+
+use crate::{
+    on_chain_config::{FeatureFlag, Features},
+    transaction::{
+        authenticator::TransactionAuthenticator, SignatureCheckedTransaction, SignedTransaction,
+    },
+};
+use once_cell::sync::Lazy;
+use std::{collections::BTreeMap, fmt};
+use thiserror::Error;
+
+/// The identifier of a signature scheme that may sign transactions
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[repr(u8)]
+pub enum SignatureScheme {
+    Ed25519 = 0,
+    Secp256k1Ecdsa = 1,
+    WebAuthn = 2,
+    Keyless = 3,
+    // ... add more schemes here
+}
+
+impl fmt::Display for SignatureScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let display = match self {
+            SignatureScheme::Ed25519 => "Ed25519",
+            SignatureScheme::Secp256k1Ecdsa => "Secp256k1Ecdsa",
+            SignatureScheme::WebAuthn => "WebAuthn",
+            SignatureScheme::Keyless => "Keyless",
+        };
+        write!(f, "SignatureScheme::{}", display)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum AuthenticatorSchemeError {
+    #[error("The authenticator is malformed: {0}")]
+    Malformed(String),
+    #[error("{0} is not registered")]
+    Unregistered(SignatureScheme),
+    #[error("{0} is gated by the disabled feature {1:?}")]
+    Disabled(SignatureScheme, FeatureFlag),
+    #[error("The signature is invalid: {0}")]
+    InvalidSignature(String),
+}
+
+/// The schemes that are accepted by the validators, together with their feature gates
+pub static DEFAULT_AUTHENTICATOR_REGISTRY: Lazy<AuthenticatorRegistry> = Lazy::new(|| {
+    AuthenticatorRegistry::empty()
+        .register(SignatureScheme::Ed25519, None)
+        .register(SignatureScheme::Secp256k1Ecdsa, None)
+        .register(
+            SignatureScheme::WebAuthn,
+            Some(FeatureFlag::WEBAUTHN_SIGNATURE),
+        )
+        // Keyless accounts are gated in the prologue, as the check needs the on-chain configs.
+        .register(SignatureScheme::Keyless, None)
+});
+
+#[derive(Clone, Debug, Default)]
+pub struct AuthenticatorRegistry {
+    // The feature gate of each registered scheme (if any)
+    schemes: BTreeMap<SignatureScheme, Option<FeatureFlag>>,
+}
+
+impl AuthenticatorRegistry {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Registers the scheme, which is only accepted if the feature gate (if any) is enabled.
+    pub fn register(mut self, scheme: SignatureScheme, feature_gate: Option<FeatureFlag>) -> Self {
+        self.schemes.insert(scheme, feature_gate);
+        self
+    }
+
+    /// Returns Ok if the scheme is registered and its feature gate (if any) is enabled
+    pub fn check_scheme(
+        &self,
+        scheme: SignatureScheme,
+        features: &Features,
+    ) -> Result<(), AuthenticatorSchemeError> {
+        match self.schemes.get(&scheme) {
+            None => Err(AuthenticatorSchemeError::Unregistered(scheme)),
+            Some(Some(feature_gate)) if !features.is_enabled(*feature_gate) => {
+                Err(AuthenticatorSchemeError::Disabled(scheme, *feature_gate))
+            },
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Returns Ok if all the signature schemes used by the authenticator are accepted
+    pub fn check_authenticator(
+        &self,
+        authenticator: &TransactionAuthenticator,
+        features: &Features,
+    ) -> Result<(), AuthenticatorSchemeError> {
+        let single_key_authenticators = authenticator
+            .to_single_key_authenticators()
+            .map_err(|err| AuthenticatorSchemeError::Malformed(err.to_string()))?;
+        single_key_authenticators
+            .iter()
+            .try_for_each(|authenticator| {
+                self.check_scheme(authenticator.signature().scheme(), features)
+            })
+    }
+
+    /// Checks the signature schemes used by the transaction are accepted, and then verifies
+    /// the signatures of the transaction.
+    pub fn verify(
+        &self,
+        transaction: SignedTransaction,
+        features: &Features,
+    ) -> Result<SignatureCheckedTransaction, AuthenticatorSchemeError> {
+        self.check_authenticator(transaction.authenticator_ref(), features)?;
+        transaction
+            .check_signature()
+            .map_err(|err| AuthenticatorSchemeError::InvalidSignature(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::authenticator::{
+        AccountAuthenticator, AnyPublicKey, AnySignature, SingleKeyAuthenticator,
+    };
+    use aptos_crypto::{
+        ed25519::{Ed25519PrivateKey, Ed25519Signature},
+        PrivateKey, Uniform,
+    };
+
+    fn ed25519_single_sender() -> TransactionAuthenticator {
+        let public_key = Ed25519PrivateKey::generate_for_testing().public_key();
+        TransactionAuthenticator::single_sender(AccountAuthenticator::single_key(
+            SingleKeyAuthenticator::new(
+                AnyPublicKey::ed25519(public_key),
+                AnySignature::ed25519(Ed25519Signature::dummy_signature()),
+            ),
+        ))
+    }
+
+    #[test]
+    fn test_check_authenticator() {
+        let authenticator = ed25519_single_sender();
+        let mut features = Features::default();
+
+        // Default registry
+        assert_eq!(
+            DEFAULT_AUTHENTICATOR_REGISTRY.check_authenticator(&authenticator, &features),
+            Ok(())
+        );
+
+        // Unregistered scheme
+        assert_eq!(
+            AuthenticatorRegistry::empty().check_authenticator(&authenticator, &features),
+            Err(AuthenticatorSchemeError::Unregistered(
+                SignatureScheme::Ed25519
+            ))
+        );
+
+        // Feature gated scheme
+        let registry = AuthenticatorRegistry::empty().register(
+            SignatureScheme::Ed25519,
+            Some(FeatureFlag::WEBAUTHN_SIGNATURE),
+        );
+        features.enable(FeatureFlag::WEBAUTHN_SIGNATURE);
+        assert_eq!(
+            registry.check_authenticator(&authenticator, &features),
+            Ok(())
+        );
+        features.disable(FeatureFlag::WEBAUTHN_SIGNATURE);
+        assert_eq!(
+            registry.check_authenticator(&authenticator, &features),
+            Err(AuthenticatorSchemeError::Disabled(
+                SignatureScheme::Ed25519,
+                FeatureFlag::WEBAUTHN_SIGNATURE
+            ))
+        );
+    }
+}
@@ -1,21 +1,109 @@
 // Copyright © Aptos Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::state_store::state_read_stats::StateReadStats;
 use std::fmt::Debug;
 
+/// Why the transactions following a committed transaction of a block were skipped, i.e. not
+/// committed in the block (and retried in a later block).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The transaction itself asked to skip the rest of the block (e.g. a reconfiguration).
+    TransactionSkipRest,
+    /// The accumulated (effective) gas of the committed transactions reached the block gas limit.
+    BlockGasLimit,
+    /// The accumulated approximate output size of the committed transactions reached the block
+    /// output limit.
+    BlockOutputLimit,
+    /// The accumulated execution gas of the committed transactions reached its block limit.
+    BlockExecutionGasLimit,
+    /// The accumulated IO gas of the committed transactions reached its block limit.
+    BlockIoGasLimit,
+    /// The accumulated storage fee of the committed transactions reached its block limit.
+    BlockStorageFeeLimit,
+    /// Committing the transactions of the block took longer than the block time budget of the
+    /// local config.
+    BlockTimeBudget,
+    /// The entries of the multi-version data-structure of the block took more memory than the
+    /// soft cap of the local config.
+    MVHashMapMemorySoftCap,
+    /// The transaction commit hook halted the block after the transaction.
+    CommitHook,
+}
+
+/// The amounts accumulated by the committed transactions of a block, for each of the block
+/// limits (see BlockGasLimitType).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockLimitUsage {
+    /// The execution and IO gas, scaled by their multipliers and by the conflicts.
+    pub effective_block_gas: u64,
+    pub execution_gas: u64,
+    pub io_gas: u64,
+    pub storage_fee: u64,
+    /// Only accumulated if the block output limit is set.
+    pub approx_output_size: u64,
+}
+
+/// How a block ended: which of its transactions were committed, and why the rest were skipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockEndInfo {
+    /// The number of committed transactions, a prefix of the transactions of the block.
+    pub num_committed_txns: u32,
+    /// Set if the block ended early, i.e. if the transactions after the committed ones were
+    /// skipped.
+    pub skip_reason: Option<SkipReason>,
+    pub limit_usage: BlockLimitUsage,
+}
+
 #[derive(Debug)]
 pub struct BlockOutput<Output: Debug> {
     transaction_outputs: Vec<Output>,
-    // TODO add block_limit_info
+    // Set by the block executor for every block it executes.
+    block_end_info: Option<BlockEndInfo>,
+    // The state reads of every transaction (skipped transactions read nothing), if recorded.
+    read_stats: Option<Vec<StateReadStats>>,
 }
 
 impl<Output: Debug> BlockOutput<Output> {
     pub fn new(transaction_outputs: Vec<Output>) -> Self {
         Self {
             transaction_outputs,
+            block_end_info: None,
+            read_stats: None,
         }
     }
 
+    pub fn with_block_end_info(mut self, block_end_info: Option<BlockEndInfo>) -> Self {
+        self.block_end_info = block_end_info;
+        self
+    }
+
+    pub fn with_read_stats(mut self, read_stats: Option<Vec<StateReadStats>>) -> Self {
+        self.read_stats = read_stats;
+        self
+    }
+
+    /// Returns the state reads of every transaction of the block, if they were recorded with
+    /// record_read_stats set in the local config of the block executor.
+    pub fn read_stats(&self) -> Option<&[StateReadStats]> {
+        self.read_stats.as_deref()
+    }
+
+    /// Returns how the block ended, or None if the outputs were not produced by the block
+    /// executor.
+    pub fn block_end_info(&self) -> Option<BlockEndInfo> {
+        self.block_end_info
+    }
+
+    /// The number of committed transactions of the block, i.e. of all its transactions unless
+    /// it ended early. The outputs of the skipped transactions follow.
+    pub fn num_committed_txns(&self) -> usize {
+        self.block_end_info
+            .map_or(self.transaction_outputs.len(), |info| {
+                info.num_committed_txns as usize
+            })
+    }
+
     /// If block limit is not set (i.e. in tests), we can safely unwrap here
     pub fn into_transaction_outputs_forced(self) -> Vec<Output> {
         // TODO assert there is no block limit info?
@@ -23,7 +111,6 @@ impl<Output: Debug> BlockOutput<Output> {
         self.transaction_outputs
     }
 
-    // TODO add block_limit_info
     pub fn into_inner(self) -> Vec<Output> {
         self.transaction_outputs
     }
@@ -42,10 +42,12 @@ use std::{
 
 pub mod analyzed_transaction;
 pub mod authenticator;
+pub mod authenticator_registry;
 mod block_output;
 mod change_set;
 mod module;
 mod multisig;
+mod payload_limits;
 mod script;
 pub mod signature_verified_transaction;
 pub mod webauthn;
@@ -53,11 +55,15 @@ pub mod webauthn;
 #[cfg(any(test, feature = "fuzzing"))]
 use crate::state_store::create_empty_sharded_state_updates;
 use crate::{
-    block_metadata_ext::BlockMetadataExt, contract_event::TransactionEvent, executable::ModulePath,
-    fee_statement::FeeStatement, proof::accumulator::InMemoryEventAccumulator,
-    validator_txn::ValidatorTransaction, write_set::TransactionWrite,
+    block_metadata_ext::BlockMetadataExt,
+    contract_event::TransactionEvent,
+    executable::{KeyPrefix, ModulePath},
+    fee_statement::FeeStatement,
+    proof::accumulator::InMemoryEventAccumulator,
+    validator_txn::ValidatorTransaction,
+    write_set::TransactionWrite,
 };
-pub use block_output::BlockOutput;
+pub use block_output::{BlockEndInfo, BlockLimitUsage, BlockOutput, SkipReason};
 pub use change_set::ChangeSet;
 pub use module::{Module, ModuleBundle};
 pub use move_core_types::transaction_argument::TransactionArgument;
@@ -67,6 +73,7 @@ use move_vm_types::delayed_values::delayed_field_id::{
 };
 pub use multisig::{ExecutionError, Multisig, MultisigTransactionPayload};
 use once_cell::sync::OnceCell;
+pub use payload_limits::{PayloadLimitViolation, PayloadLimits};
 pub use script::{
     ArgumentABI, EntryABI, EntryFunction, EntryFunctionABI, Script, TransactionScriptABI,
     TypeArgumentABI,
@@ -2027,7 +2034,7 @@ impl TryFrom<Transaction> for SignedTransaction {
 /// Trait that defines a transaction type that can be executed by the block executor. A transaction
 /// transaction will write to a key value storage as their side effect.
 pub trait BlockExecutableTransaction: Sync + Send + Clone + 'static {
-    type Key: PartialOrd + Ord + Send + Sync + Clone + Hash + Eq + ModulePath + Debug;
+    type Key: PartialOrd + Ord + Send + Sync + Clone + Hash + Eq + ModulePath + KeyPrefix + Debug;
     /// Some keys contain multiple "resources" distinguished by a tag. Reading these keys requires
     /// specifying a tag, and output requires merging all resources together (Note: this may change
     /// in the future if write-set format changes to be per-resource, could be more performant).
@@ -2063,6 +2070,29 @@ pub trait BlockExecutableTransaction: Sync + Send + Clone + 'static {
 
     /// Size of the user transaction in bytes, 0 otherwise
     fn user_txn_bytes_len(&self) -> usize;
+
+    /// Gas unit price of the user transaction, 0 otherwise
+    fn gas_unit_price(&self) -> u64 {
+        0
+    }
+
+    /// Whether the transaction is a block metadata or validator transaction. When a block cannot
+    /// be executed and is discarded, these transactions are still executed on their own, so that
+    /// e.g. the block timestamp and epoch changes are not lost.
+    fn is_block_metadata_or_validator_txn(&self) -> bool {
+        false
+    }
+
+    /// Expiration time (in seconds) of the user transaction, None otherwise. A user transaction
+    /// is discarded if the block timestamp is not before its expiration time.
+    fn expiration_timestamp_secs(&self) -> Option<u64> {
+        None
+    }
+
+    /// Block timestamp (in microseconds) set by the block metadata transaction, None otherwise.
+    fn block_timestamp_usecs(&self) -> Option<u64> {
+        None
+    }
 }
 
 pub struct ViewFunctionOutput {
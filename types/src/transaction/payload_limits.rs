@@ -0,0 +1,303 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structural limits on transaction payloads.
+//!
+//! The same checks are run by the API on submission, by mempool when validating incoming
+//! transactions and by the VM prologue, so a payload rejected by one of them is rejected by all
+//! of them with the same status code.
+
+use crate::transaction::{
+    EntryFunction, Multisig, MultisigTransactionPayload, Script, TransactionArgument,
+    TransactionPayload,
+};
+use move_core_types::{
+    account_address::AccountAddress, language_storage::TypeTag, vm_status::StatusCode,
+};
+use std::fmt;
+
+/// Limits on the shape of a transaction payload. All limits are inclusive.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PayloadLimits {
+    /// Maximum number of type arguments passed to a script or entry function.
+    pub max_num_type_args: u64,
+    /// Maximum nesting depth of a single type argument, e.g. `vector<vector<u8>>` has depth 3.
+    pub max_type_arg_depth: u64,
+    /// Maximum number of arguments passed to a script or entry function.
+    pub max_num_args: u64,
+    /// Maximum size of a single serialized argument in bytes. This bounds the length of vector
+    /// and string arguments, which are opaque BCS blobs at this point.
+    pub max_arg_size_in_bytes: u64,
+}
+
+/// The limit a payload violated, together with the observed value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PayloadLimitViolation {
+    TooManyTypeArgs { actual: u64, max: u64 },
+    TypeArgTooDeep { actual: u64, max: u64 },
+    TooManyArgs { actual: u64, max: u64 },
+    ArgTooLarge { actual: u64, max: u64 },
+}
+
+impl PayloadLimitViolation {
+    /// The status code the transaction is discarded with.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::TooManyTypeArgs { .. } => StatusCode::EXCEEDED_MAX_TYPE_ARGUMENTS,
+            Self::TypeArgTooDeep { .. } => StatusCode::EXCEEDED_MAX_TYPE_ARGUMENT_DEPTH,
+            Self::TooManyArgs { .. } => StatusCode::EXCEEDED_MAX_ARGUMENTS,
+            Self::ArgTooLarge { .. } => StatusCode::EXCEEDED_MAX_ARGUMENT_SIZE,
+        }
+    }
+}
+
+impl fmt::Display for PayloadLimitViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyTypeArgs { actual, max } => {
+                write!(f, "Too many type arguments {} (max {})", actual, max)
+            },
+            Self::TypeArgTooDeep { actual, max } => {
+                write!(f, "Type argument too deep {} (max {})", actual, max)
+            },
+            Self::TooManyArgs { actual, max } => {
+                write!(f, "Too many arguments {} (max {})", actual, max)
+            },
+            Self::ArgTooLarge { actual, max } => {
+                write!(f, "Argument too large {} bytes (max {})", actual, max)
+            },
+        }
+    }
+}
+
+impl PayloadLimits {
+    /// Checks the payload against the limits. Multisig payloads are checked against the inner
+    /// payload if it is provided; deprecated payloads are left to the VM to reject.
+    pub fn check(&self, payload: &TransactionPayload) -> Result<(), PayloadLimitViolation> {
+        match payload {
+            TransactionPayload::Script(script) => self.check_script(script),
+            TransactionPayload::EntryFunction(entry_function) => {
+                self.check_entry_function(entry_function)
+            },
+            TransactionPayload::Multisig(multisig) => self.check_multisig(multisig),
+            TransactionPayload::ModuleBundle(_) => Ok(()),
+        }
+    }
+
+    pub fn check_script(&self, script: &Script) -> Result<(), PayloadLimitViolation> {
+        self.check_type_args(script.ty_args())?;
+        self.check_args(script.args().iter().map(transaction_argument_size))
+    }
+
+    pub fn check_entry_function(
+        &self,
+        entry_function: &EntryFunction,
+    ) -> Result<(), PayloadLimitViolation> {
+        self.check_type_args(entry_function.ty_args())?;
+        self.check_args(entry_function.args().iter().map(|arg| arg.len() as u64))
+    }
+
+    pub fn check_multisig(&self, multisig: &Multisig) -> Result<(), PayloadLimitViolation> {
+        match &multisig.transaction_payload {
+            Some(MultisigTransactionPayload::EntryFunction(entry_function)) => {
+                self.check_entry_function(entry_function)
+            },
+            None => Ok(()),
+        }
+    }
+
+    fn check_type_args(&self, ty_args: &[TypeTag]) -> Result<(), PayloadLimitViolation> {
+        let num_type_args = ty_args.len() as u64;
+        if num_type_args > self.max_num_type_args {
+            return Err(PayloadLimitViolation::TooManyTypeArgs {
+                actual: num_type_args,
+                max: self.max_num_type_args,
+            });
+        }
+        for ty_arg in ty_args {
+            let depth = type_tag_depth(ty_arg);
+            if depth > self.max_type_arg_depth {
+                return Err(PayloadLimitViolation::TypeArgTooDeep {
+                    actual: depth,
+                    max: self.max_type_arg_depth,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_args(
+        &self,
+        arg_sizes: impl ExactSizeIterator<Item = u64>,
+    ) -> Result<(), PayloadLimitViolation> {
+        let num_args = arg_sizes.len() as u64;
+        if num_args > self.max_num_args {
+            return Err(PayloadLimitViolation::TooManyArgs {
+                actual: num_args,
+                max: self.max_num_args,
+            });
+        }
+        for size in arg_sizes {
+            if size > self.max_arg_size_in_bytes {
+                return Err(PayloadLimitViolation::ArgTooLarge {
+                    actual: size,
+                    max: self.max_arg_size_in_bytes,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn transaction_argument_size(arg: &TransactionArgument) -> u64 {
+    match arg {
+        TransactionArgument::U8Vector(bytes) => bytes.len() as u64,
+        TransactionArgument::Bool(_) | TransactionArgument::U8(_) => 1,
+        TransactionArgument::U16(_) => 2,
+        TransactionArgument::U32(_) => 4,
+        TransactionArgument::U64(_) => 8,
+        TransactionArgument::U128(_) => 16,
+        TransactionArgument::U256(_) => 32,
+        TransactionArgument::Address(_) => AccountAddress::LENGTH as u64,
+    }
+}
+
+/// Depth of a type tag, counting every vector and struct layer.
+fn type_tag_depth(ty: &TypeTag) -> u64 {
+    let mut max_depth = 0;
+    let mut stack = vec![(ty, 1)];
+    while let Some((ty, depth)) = stack.pop() {
+        max_depth = max_depth.max(depth);
+        match ty {
+            TypeTag::Vector(elem) => stack.push((elem, depth + 1)),
+            TypeTag::Struct(struct_tag) => stack.extend(
+                struct_tag
+                    .type_params
+                    .iter()
+                    .map(|type_arg| (type_arg, depth + 1)),
+            ),
+            TypeTag::Bool
+            | TypeTag::U8
+            | TypeTag::U16
+            | TypeTag::U32
+            | TypeTag::U64
+            | TypeTag::U128
+            | TypeTag::U256
+            | TypeTag::Address
+            | TypeTag::Signer => (),
+        }
+    }
+    max_depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_core_types::{
+        identifier::Identifier,
+        language_storage::{ModuleId, StructTag},
+    };
+
+    const LIMITS: PayloadLimits = PayloadLimits {
+        max_num_type_args: 2,
+        max_type_arg_depth: 3,
+        max_num_args: 2,
+        max_arg_size_in_bytes: 4,
+    };
+
+    fn entry_function(ty_args: Vec<TypeTag>, args: Vec<Vec<u8>>) -> TransactionPayload {
+        TransactionPayload::EntryFunction(EntryFunction::new(
+            ModuleId::new(AccountAddress::ONE, Identifier::new("m").unwrap()),
+            Identifier::new("f").unwrap(),
+            ty_args,
+            args,
+        ))
+    }
+
+    fn vector_of(ty: TypeTag, depth: usize) -> TypeTag {
+        (0..depth).fold(ty, |ty, _| TypeTag::Vector(Box::new(ty)))
+    }
+
+    #[test]
+    fn test_payload_within_limits() {
+        let payload = entry_function(vec![vector_of(TypeTag::U8, 2), TypeTag::Address], vec![
+            vec![0; 4],
+            vec![],
+        ]);
+        assert_eq!(LIMITS.check(&payload), Ok(()));
+    }
+
+    #[test]
+    fn test_payload_limit_violations() {
+        let payload = entry_function(vec![TypeTag::U8; 3], vec![]);
+        let err = LIMITS.check(&payload).unwrap_err();
+        assert_eq!(err, PayloadLimitViolation::TooManyTypeArgs {
+            actual: 3,
+            max: 2
+        });
+        assert_eq!(err.status_code(), StatusCode::EXCEEDED_MAX_TYPE_ARGUMENTS);
+
+        let payload = entry_function(vec![vector_of(TypeTag::U8, 3)], vec![]);
+        let err = LIMITS.check(&payload).unwrap_err();
+        assert_eq!(err, PayloadLimitViolation::TypeArgTooDeep {
+            actual: 4,
+            max: 3
+        });
+        assert_eq!(
+            err.status_code(),
+            StatusCode::EXCEEDED_MAX_TYPE_ARGUMENT_DEPTH
+        );
+
+        let payload = entry_function(vec![], vec![vec![]; 3]);
+        assert_eq!(
+            LIMITS.check(&payload),
+            Err(PayloadLimitViolation::TooManyArgs { actual: 3, max: 2 })
+        );
+
+        let payload = entry_function(vec![], vec![vec![0; 5]]);
+        let err = LIMITS.check(&payload).unwrap_err();
+        assert_eq!(err, PayloadLimitViolation::ArgTooLarge {
+            actual: 5,
+            max: 4
+        });
+        assert_eq!(err.status_code(), StatusCode::EXCEEDED_MAX_ARGUMENT_SIZE);
+    }
+
+    #[test]
+    fn test_multisig_inner_payload_is_checked() {
+        let inner = entry_function(vec![TypeTag::U8; 3], vec![]).into_entry_function();
+        let payload = TransactionPayload::Multisig(Multisig {
+            multisig_address: AccountAddress::ONE,
+            transaction_payload: Some(MultisigTransactionPayload::EntryFunction(inner)),
+        });
+        assert!(matches!(
+            LIMITS.check(&payload),
+            Err(PayloadLimitViolation::TooManyTypeArgs { .. })
+        ));
+    }
+
+    #[test]
+    fn test_script_args() {
+        let payload = TransactionPayload::Script(Script::new(vec![], vec![], vec![
+            TransactionArgument::U64(0),
+        ]));
+        assert_eq!(
+            LIMITS.check(&payload),
+            Err(PayloadLimitViolation::ArgTooLarge { actual: 8, max: 4 })
+        );
+    }
+
+    #[test]
+    fn test_deep_struct_type_arg() {
+        let mut ty = TypeTag::U8;
+        for _ in 0..100 {
+            ty = TypeTag::Struct(Box::new(StructTag {
+                address: AccountAddress::ONE,
+                module: Identifier::new("m").unwrap(),
+                name: Identifier::new("S").unwrap(),
+                type_params: vec![ty],
+            }));
+        }
+        assert_eq!(type_tag_depth(&ty), 101);
+    }
+}
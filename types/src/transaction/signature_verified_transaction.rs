@@ -74,6 +74,48 @@ impl BlockExecutableTransaction for SignatureVerifiedTransaction {
             _ => 0,
         }
     }
+
+    fn gas_unit_price(&self) -> u64 {
+        match self {
+            SignatureVerifiedTransaction::Valid(Transaction::UserTransaction(txn)) => {
+                txn.gas_unit_price()
+            },
+            _ => 0,
+        }
+    }
+
+    fn is_block_metadata_or_validator_txn(&self) -> bool {
+        matches!(
+            self,
+            SignatureVerifiedTransaction::Valid(
+                Transaction::BlockMetadata(_)
+                    | Transaction::BlockMetadataExt(_)
+                    | Transaction::ValidatorTransaction(_)
+            )
+        )
+    }
+
+    fn expiration_timestamp_secs(&self) -> Option<u64> {
+        // Transactions with invalid signatures are discarded for that reason instead.
+        match self {
+            SignatureVerifiedTransaction::Valid(Transaction::UserTransaction(txn)) => {
+                Some(txn.expiration_timestamp_secs())
+            },
+            _ => None,
+        }
+    }
+
+    fn block_timestamp_usecs(&self) -> Option<u64> {
+        match self {
+            SignatureVerifiedTransaction::Valid(Transaction::BlockMetadata(block_metadata)) => {
+                Some(block_metadata.timestamp_usecs())
+            },
+            SignatureVerifiedTransaction::Valid(Transaction::BlockMetadataExt(block_metadata)) => {
+                Some(block_metadata.timestamp_usecs())
+            },
+            _ => None,
+        }
+    }
 }
 
 impl From<Transaction> for SignatureVerifiedTransaction {
@@ -17,6 +17,7 @@ use aptos_crypto::{
     hash::CryptoHash,
     Signature, VerifyingKey,
 };
+use aptos_infallible::Mutex;
 use itertools::Itertools;
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
@@ -24,7 +25,12 @@ use serde::{Deserialize, Deserializer, Serialize};
 use std::{
     collections::{BTreeMap, HashMap},
     fmt,
+    sync::Arc,
 };
+
+/// The maximum number of signer sets whose aggregated public keys are cached by a verifier. The
+/// cache is cleared once full, which only happens if the signer sets keep changing.
+const MAX_CACHED_AGGREGATED_KEYS: usize = 1024;
 use thiserror::Error;
 
 /// Errors possible during signature verification.
@@ -141,8 +147,58 @@ pub struct ValidatorVerifier {
     /// In-memory index of account address to its index in the vector, does not go through serde.
     #[serde(skip)]
     address_to_validator_index: HashMap<AccountAddress, usize>,
+    /// The aggregated public keys of the signer sets of the verified multi-signatures, shared by
+    /// the clones of the verifier (e.g. of the epoch state), as most quorum certificates of an
+    /// epoch are signed by the same few sets of validators.
+    #[serde(skip)]
+    aggregated_key_cache: Arc<AggregatedKeyCache>,
+}
+
+/// The aggregated public keys of validator subsets, keyed by the signers bitvec.
+#[derive(Default)]
+struct AggregatedKeyCache {
+    keys: Mutex<HashMap<BitVec, PublicKey>>,
+}
+
+impl AggregatedKeyCache {
+    fn get_or_aggregate(
+        &self,
+        signers: &BitVec,
+        aggregate: impl FnOnce() -> std::result::Result<PublicKey, VerifyError>,
+    ) -> std::result::Result<PublicKey, VerifyError> {
+        if let Some(aggregated_key) = self.keys.lock().get(signers) {
+            return Ok(aggregated_key.clone());
+        }
+
+        let aggregated_key = aggregate()?;
+        let mut keys = self.keys.lock();
+        if keys.len() >= MAX_CACHED_AGGREGATED_KEYS {
+            keys.clear();
+        }
+        keys.insert(signers.clone(), aggregated_key.clone());
+        Ok(aggregated_key)
+    }
+
+    fn len(&self) -> usize {
+        self.keys.lock().len()
+    }
+}
+
+impl fmt::Debug for AggregatedKeyCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> std::fmt::Result {
+        write!(f, "AggregatedKeyCache {{ len: {} }}", self.len())
+    }
 }
 
+/// The cache is derived from the validator infos, so it does not affect the equality of verifiers.
+impl PartialEq for AggregatedKeyCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for AggregatedKeyCache {}
+
 /// Reconstruct fields from the raw data upon deserialization.
 impl<'de> Deserialize<'de> for ValidatorVerifier {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -179,6 +235,7 @@ impl ValidatorVerifier {
             quorum_voting_power,
             total_voting_power,
             address_to_validator_index,
+            aggregated_key_cache: Arc::new(AggregatedKeyCache::default()),
         }
     }
 
@@ -273,19 +330,23 @@ impl ValidatorVerifier {
         multi_signature: &AggregateSignature,
     ) -> std::result::Result<(), VerifyError> {
         // Verify the number of signature is not greater than expected.
-        Self::check_num_of_voters(self.len() as u16, multi_signature.get_signers_bitvec())?;
-        let mut pub_keys = vec![];
-        let mut authors = vec![];
-        for index in multi_signature.get_signers_bitvec().iter_ones() {
+        let signers = multi_signature.get_signers_bitvec();
+        Self::check_num_of_voters(self.len() as u16, signers)?;
+        let mut validators = vec![];
+        for index in signers.iter_ones() {
             let validator = self
                 .validator_infos
                 .get(index)
                 .ok_or(VerifyError::UnknownAuthor)?;
-            authors.push(validator.address);
-            pub_keys.push(validator.public_key());
+            validators.push(validator);
         }
-        // Verify the quorum voting power of the authors
-        self.check_voting_power(authors.iter(), true)?;
+        // Verify the quorum voting power of the authors, indexed by the bitvec (rather than looked
+        // up by address).
+        let aggregated_voting_power: u128 = validators
+            .iter()
+            .map(|validator| validator.voting_power as u128)
+            .sum();
+        self.check_aggregated_voting_power(aggregated_voting_power, true)?;
         #[cfg(any(test, feature = "fuzzing"))]
         {
             if self.quorum_voting_power == 0 {
@@ -301,8 +362,15 @@ impl ValidatorVerifier {
             .as_ref()
             .ok_or(VerifyError::EmptySignature)?;
         // Verify the optimistically aggregated signature.
-        let aggregated_key =
-            PublicKey::aggregate(pub_keys).map_err(|_| VerifyError::FailedToAggregatePubKey)?;
+        let aggregated_key = self.aggregated_key_cache.get_or_aggregate(signers, || {
+            PublicKey::aggregate(
+                validators
+                    .iter()
+                    .map(|validator| validator.public_key())
+                    .collect(),
+            )
+            .map_err(|_| VerifyError::FailedToAggregatePubKey)
+        })?;
 
         multi_sig
             .verify(message, &aggregated_key)
@@ -845,6 +913,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_aggregated_key_cache() {
+        let (validator_signers, validator_verifier) = random_validator_verifier(7, Some(5), false);
+        let dummy_struct = TestAptosCrypto("Hello, World".to_string());
+        let aggregated_signature = |num_signers| {
+            let mut partial_signature = PartialSignatures::empty();
+            for validator in validator_signers.iter().take(num_signers) {
+                partial_signature
+                    .add_signature(validator.author(), validator.sign(&dummy_struct).unwrap());
+            }
+            validator_verifier
+                .aggregate_signatures(&partial_signature)
+                .unwrap()
+        };
+
+        // The key of a signer set is aggregated once, and shared with the clones of the verifier.
+        let cloned_verifier = validator_verifier.clone();
+        for (verifier, num_signers, num_cached_keys) in [
+            (&validator_verifier, 5, 1),
+            (&cloned_verifier, 5, 1),
+            (&validator_verifier, 6, 2),
+        ] {
+            assert_eq!(
+                verifier.verify_multi_signatures(&dummy_struct, &aggregated_signature(num_signers)),
+                Ok(())
+            );
+            assert_eq!(verifier.aggregated_key_cache.len(), num_cached_keys);
+        }
+
+        // The signature is still verified against the cached key of the signer set.
+        let invalid_signature = AggregateSignature::new(
+            aggregated_signature(5).get_signers_bitvec().clone(),
+            aggregated_signature(6).sig().clone(),
+        );
+        assert_eq!(
+            validator_verifier.verify_multi_signatures(&dummy_struct, &invalid_signature),
+            Err(VerifyError::InvalidMultiSignature)
+        );
+    }
+
     #[test]
     fn test_unequal_vote_quorum_validators() {
         const NUM_SIGNERS: u8 = 4;